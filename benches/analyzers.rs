@@ -0,0 +1,46 @@
+//! `cargo bench`: slow, fast and parallel on hot-word-only, prose-like,
+//! mixed-case, Unicode and never-repeating text of 10,000 to 1,000,000
+//! words, as a median table with significance marks. Built on `td5::bench` rather than criterion, which
+//! would be the crate's first dev-dependency; `td5 bench` runs the same
+//! suite on every analyzer and shape, with JSON, CSV and baseline output.
+//!
+//! `cargo bench -- unicode` keeps the inputs whose name contains `unicode`.
+
+use td5::bench::{BenchInput, BenchSuite};
+use td5::generator::{Cyclic, TextSource};
+use td5::shapes::{Shape, TextGenerator};
+
+const SIZES: [usize; 3] = [10_000, 100_000, 1_000_000];
+
+fn main() {
+    // Cargo passes `--bench`; anything else is a filter.
+    let filter: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .collect();
+    let sources: [(&str, Box<dyn TextSource>); 5] = [
+        ("hot", Box::new(Cyclic::hot_words())),
+        ("prose", Box::new(TextGenerator::new())),
+        ("mixed", Box::new(Shape::MixedAscii)),
+        ("unicode", Box::new(Shape::Unicode)),
+        ("distinct", Box::new(Shape::Distinct)),
+    ];
+    let mut inputs = Vec::new();
+    for words in SIZES {
+        for (shape, source) in &sources {
+            let name = format!("{shape}/{words}");
+            if filter.is_empty() || filter.iter().any(|f| name.contains(f.as_str())) {
+                inputs.push(BenchInput::new(name, source.text(words)));
+            }
+        }
+    }
+    if inputs.is_empty() {
+        return;
+    }
+    let suite = BenchSuite::with_builtins_named(&["slow", "fast", "parallel"])
+        .expect("built-in analyzers")
+        .warmup(2)
+        .iterations(20);
+    let report = suite.run(&inputs).expect("benchmark inputs");
+    print!("{}", report.grid());
+}
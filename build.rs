@@ -0,0 +1,305 @@
+//! Records how the crate was compiled so benchmark reports can say so, and
+//! generates the fast path's hot-word matcher from the vocabulary file and
+//! the Unicode normalization tables from their data file.
+
+use std::env;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn main() {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".into());
+    let version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|out| String::from_utf8(out.stdout).ok())
+        .map(|v| v.trim().to_string())
+        .unwrap_or_else(|| "unknown".into());
+    println!("cargo:rustc-env=TD5_RUSTC_VERSION={version}");
+    for (var, name) in [
+        ("OPT_LEVEL", "TD5_OPT_LEVEL"),
+        ("PROFILE", "TD5_PROFILE"),
+        ("TARGET", "TD5_TARGET"),
+        ("CARGO_CFG_TARGET_FEATURE", "TD5_TARGET_FEATURES"),
+    ] {
+        let value = env::var(var).unwrap_or_default();
+        println!("cargo:rustc-env={name}={value}");
+    }
+    println!("cargo:rerun-if-env-changed=RUSTC");
+    hot_words();
+    normalization();
+}
+
+/// Longest hot word: the simple path never produces longer tokens. Kept in
+/// step with `td5::hotwords`.
+const HOT_WORD_MAX: usize = 32;
+const HOT_WORDS_MAX: usize = 256;
+
+/// Writes `$OUT_DIR/hot.rs`: the `HOT` vocabulary, its alphabetical and
+/// longest-first orders, and `match_hot_lower`, which dispatches on length and
+/// first byte before comparing whole words.
+fn hot_words() {
+    println!("cargo:rerun-if-env-changed=TD5_HOT_WORDS");
+    let path = env::var_os("TD5_HOT_WORDS")
+        .filter(|p| !p.is_empty())
+        .map_or_else(|| PathBuf::from("hot-words.txt"), PathBuf::from);
+    println!("cargo:rerun-if-changed={}", path.display());
+    let words = read_hot_words(&path).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+
+    let mut alphabetical: Vec<usize> = (0..words.len()).collect();
+    alphabetical.sort_by_key(|&i| &words[i]);
+    let mut longest = alphabetical.clone();
+    longest.sort_by_key(|&i| std::cmp::Reverse(words[i].len()));
+
+    let mut groups: Vec<((usize, u8), Vec<usize>)> = Vec::new();
+    for (i, word) in words.iter().enumerate() {
+        let key = (word.len(), word.as_bytes()[0]);
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, members)) => members.push(i),
+            None => groups.push((key, vec![i])),
+        }
+    }
+
+    let mut out = String::from("// Generated by build.rs from the hot-word list.\n");
+    let list = |indices: &[usize]| {
+        let items: Vec<String> = indices.iter().map(usize::to_string).collect();
+        items.join(", ")
+    };
+    let quoted: Vec<String> = words.iter().map(|w| format!("{w:?}")).collect();
+    writeln!(
+        out,
+        "const HOT: [&str; {}] = [{}];",
+        words.len(),
+        quoted.join(", ")
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "/// Every hot word, alphabetically.\nconst HOT_ORDER: [usize; {}] = [{}];",
+        alphabetical.len(),
+        list(&alphabetical)
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "/// Every hot word, longest first, alphabetically among equal lengths.\nconst LONGEST_ORDER: [usize; {}] = [{}];",
+        longest.len(),
+        list(&longest)
+    )
+    .unwrap();
+    out.push_str("#[inline]\nfn match_hot_lower(token: &[u8]) -> Option<usize> {\n");
+    out.push_str("    match (token.len(), *token.first()?) {\n");
+    for ((len, first), members) in &groups {
+        let byte = format!("b'{}'", *first as char);
+        if let [i] = members[..] {
+            writeln!(
+                out,
+                "        ({len}, {byte}) if token == b{:?} => Some({i}),",
+                words[i]
+            )
+            .unwrap();
+        } else {
+            writeln!(out, "        ({len}, {byte}) => match token {{").unwrap();
+            for &i in members {
+                writeln!(out, "            b{:?} => Some({i}),", words[i]).unwrap();
+            }
+            out.push_str("            _ => None,\n        },\n");
+        }
+    }
+    out.push_str("        _ => None,\n    }\n}\n");
+
+    let dest = Path::new(&env::var_os("OUT_DIR").expect("cargo sets OUT_DIR")).join("hot.rs");
+    std::fs::write(&dest, out).unwrap_or_else(|e| panic!("{}: {e}", dest.display()));
+}
+
+fn read_hot_words(path: &Path) -> Result<Vec<String>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut words: Vec<String> = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let word = line.split('#').next().unwrap_or("").trim();
+        if word.is_empty() {
+            continue;
+        }
+        if !word.bytes().all(|b| b.is_ascii_lowercase()) || word.len() > HOT_WORD_MAX {
+            return Err(format!(
+                "line {}: `{word}` is not a lowercase ASCII word of at most {HOT_WORD_MAX} letters",
+                n + 1
+            ));
+        }
+        if words.iter().any(|w| w == word) {
+            return Err(format!("line {}: `{word}` is listed twice", n + 1));
+        }
+        words.push(word.to_string());
+    }
+    if words.len() > HOT_WORDS_MAX {
+        return Err(format!("more than {HOT_WORDS_MAX} hot words"));
+    }
+    Ok(words)
+}
+
+/// A code point's line of `unicode-normalization.txt`.
+struct Entry {
+    class: u8,
+    /// Decomposition kind (`=`, `!` or `~`) and one level of it.
+    decomposition: Option<(char, Vec<char>)>,
+}
+
+/// Writes `$OUT_DIR/normalization.rs` for `td5::normalize`: the combining
+/// classes, the full canonical and compatibility decompositions, and the
+/// pairs that compose, each sorted for binary search.
+fn normalization() {
+    let path = Path::new("unicode-normalization.txt");
+    println!("cargo:rerun-if-changed={}", path.display());
+    let entries = read_normalization(path).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+
+    fn expand(
+        ch: char,
+        compat: bool,
+        entries: &std::collections::BTreeMap<char, Entry>,
+        out: &mut Vec<char>,
+    ) {
+        match entries.get(&ch).and_then(|e| e.decomposition.as_ref()) {
+            Some((kind, parts)) if compat || *kind != '~' => {
+                for &part in parts {
+                    expand(part, compat, entries, out);
+                }
+            }
+            _ => out.push(ch),
+        }
+    }
+
+    let chars = |chars: &[char]| {
+        let items: Vec<String> = chars
+            .iter()
+            .map(|c| format!("'\\u{{{:x}}}'", *c as u32))
+            .collect();
+        items.join(", ")
+    };
+    let mut classes = String::new();
+    let mut canonical = String::new();
+    let mut compatibility = String::new();
+    let mut compositions = Vec::new();
+    let (mut n_classes, mut n_canonical, mut n_compatibility) = (0, 0, 0);
+    for (&ch, entry) in &entries {
+        if entry.class != 0 {
+            writeln!(classes, "    ('\\u{{{:x}}}', {}),", ch as u32, entry.class).unwrap();
+            n_classes += 1;
+        }
+        let Some((kind, parts)) = &entry.decomposition else {
+            continue;
+        };
+        let (mut full, mut full_compat) = (Vec::new(), Vec::new());
+        expand(ch, false, &entries, &mut full);
+        expand(ch, true, &entries, &mut full_compat);
+        if full != [ch] {
+            writeln!(
+                canonical,
+                "    ('\\u{{{:x}}}', &[{}]),",
+                ch as u32,
+                chars(&full)
+            )
+            .unwrap();
+            n_canonical += 1;
+        }
+        if full_compat != full {
+            writeln!(
+                compatibility,
+                "    ('\\u{{{:x}}}', &[{}]),",
+                ch as u32,
+                chars(&full_compat)
+            )
+            .unwrap();
+            n_compatibility += 1;
+        }
+        if *kind == '=' {
+            compositions.push((parts[0], parts[1], ch));
+        }
+    }
+    compositions.sort();
+
+    let mut out = String::from("// Generated by build.rs from unicode-normalization.txt.\n");
+    writeln!(
+        out,
+        "/// Canonical combining classes other than 0.\nstatic COMBINING_CLASSES: [(char, u8); {n_classes}] = [\n{classes}];"
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "/// Full canonical decompositions, Hangul syllables aside.\nstatic CANONICAL: [(char, &[char]); {n_canonical}] = [\n{canonical}];"
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "/// Full compatibility decompositions, where they differ from the canonical one.\nstatic COMPATIBILITY: [(char, &[char]); {n_compatibility}] = [\n{compatibility}];"
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "/// The primary composites, by the pair they compose from, Hangul aside.\nstatic COMPOSITIONS: [(char, char, char); {}] = [",
+        compositions.len()
+    )
+    .unwrap();
+    for (a, b, composite) in &compositions {
+        writeln!(
+            out,
+            "    ('\\u{{{:x}}}', '\\u{{{:x}}}', '\\u{{{:x}}}'),",
+            *a as u32, *b as u32, *composite as u32
+        )
+        .unwrap();
+    }
+    out.push_str("];\n");
+
+    let dest =
+        Path::new(&env::var_os("OUT_DIR").expect("cargo sets OUT_DIR")).join("normalization.rs");
+    std::fs::write(&dest, out).unwrap_or_else(|e| panic!("{}: {e}", dest.display()));
+}
+
+fn read_normalization(path: &Path) -> Result<std::collections::BTreeMap<char, Entry>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let code = |field: &str, n: usize| {
+        u32::from_str_radix(field, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| format!("line {n}: `{field}` is not a code point"))
+    };
+    let mut entries = std::collections::BTreeMap::new();
+    for (n, line) in text.lines().enumerate() {
+        let n = n + 1;
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut fields = line.split_whitespace();
+        let ch = code(fields.next().unwrap_or(""), n)?;
+        let class = fields
+            .next()
+            .and_then(|f| f.parse().ok())
+            .ok_or_else(|| format!("line {n}: missing combining class"))?;
+        let decomposition = match fields.next() {
+            None => None,
+            Some(kind @ ("=" | "!" | "~")) => {
+                let parts = fields.map(|f| code(f, n)).collect::<Result<Vec<_>, _>>()?;
+                let kind = kind.chars().next().unwrap();
+                if parts.is_empty() || (kind == '=' && parts.len() != 2) {
+                    return Err(format!("line {n}: bad decomposition"));
+                }
+                Some((kind, parts))
+            }
+            Some(kind) => return Err(format!("line {n}: unknown decomposition kind `{kind}`")),
+        };
+        if entries
+            .insert(
+                ch,
+                Entry {
+                    class,
+                    decomposition,
+                },
+            )
+            .is_some()
+        {
+            return Err(format!("line {n}: U+{:04X} is listed twice", ch as u32));
+        }
+    }
+    Ok(entries)
+}
@@ -0,0 +1,251 @@
+"use strict";
+
+(function () {
+  const data = JSON.parse(document.getElementById("data").textContent);
+  const stats = data.stats;
+  const total = stats.total_words;
+  const SVG = "http://www.w3.org/2000/svg";
+
+  const number = (n) => n.toLocaleString();
+  const percent = (share) => (share * 100).toFixed(1) + "%";
+
+  function node(name, attributes, parent) {
+    const element = document.createElementNS(SVG, name);
+    for (const [key, value] of Object.entries(attributes || {})) {
+      element.setAttribute(key, value);
+    }
+    if (parent) {
+      parent.appendChild(element);
+    }
+    return element;
+  }
+
+  function text(parent, x, y, content, attributes) {
+    const element = node("text", Object.assign({ x, y }, attributes), parent);
+    element.textContent = content;
+    return element;
+  }
+
+  function tooltip(element, content) {
+    node("title", {}, element).textContent = content;
+  }
+
+  function chart(id, width, height) {
+    const holder = document.getElementById(id);
+    holder.textContent = "";
+    return node("svg", { viewBox: `0 0 ${width} ${height}`, role: "img" }, holder);
+  }
+
+  function empty(id) {
+    const holder = document.getElementById(id);
+    holder.innerHTML = '<p class="empty">No words in this text.</p>';
+  }
+
+  // Round axis maximum and tick step for values up to `max`.
+  function ticks(max, count) {
+    const raw = Math.max(max, 1) / count;
+    const magnitude = Math.pow(10, Math.floor(Math.log10(raw)));
+    const step = [1, 2, 5, 10].map((m) => m * magnitude).find((s) => s >= raw);
+    return { step, max: Math.ceil(Math.max(max, 1) / step) * step };
+  }
+
+  function summary() {
+    const cards = [
+      ["Words", number(total)],
+      ["Distinct words", number(stats.word_count)],
+      ["Letters", number(stats.char_count)],
+      ["Longest word", stats.longest_words[0] || "–"],
+    ];
+    const holder = document.getElementById("summary");
+    for (const [label, value] of cards) {
+      const card = document.createElement("div");
+      card.className = "card";
+      const v = document.createElement("div");
+      v.className = "value";
+      v.textContent = value;
+      const l = document.createElement("div");
+      l.className = "label";
+      l.textContent = label;
+      card.append(v, l);
+      holder.appendChild(card);
+    }
+  }
+
+  function topWords() {
+    const words = data.top_words;
+    if (words.length === 0) {
+      return empty("top-words");
+    }
+    const row = 22;
+    const left = 140;
+    const width = 900;
+    const svg = chart("top-words", width, words.length * row + 24);
+    const axis = ticks(words[0][1], 5);
+    const scale = (width - left - 60) / axis.max;
+    const g = node("g", { class: "axis" }, svg);
+    for (let v = 0; v <= axis.max; v += axis.step) {
+      const x = left + v * scale;
+      node("line", { x1: x, x2: x, y1: 0, y2: words.length * row, class: "grid" }, g);
+      text(g, x, words.length * row + 16, number(v), { "text-anchor": "middle" });
+    }
+    words.forEach(([word, count], i) => {
+      const y = i * row;
+      text(svg, left - 8, y + row / 2 + 4, word, { "text-anchor": "end" });
+      const bar = node("rect", {
+        x: left,
+        y: y + 3,
+        width: Math.max(count * scale, 1),
+        height: row - 6,
+        class: "bar",
+      }, svg);
+      tooltip(bar, `${word}: ${number(count)} (${percent(count / Math.max(total, 1))})`);
+      text(svg, left + count * scale + 6, y + row / 2 + 4, number(count));
+    });
+  }
+
+  function lengths(key) {
+    const bins = data.lengths;
+    if (bins.length === 0) {
+      return empty("lengths");
+    }
+    const width = 900;
+    const height = 260;
+    const left = 60;
+    const bottom = 30;
+    const svg = chart("lengths", width, height);
+    const longest = bins[bins.length - 1].length;
+    const slot = (width - left) / longest;
+    const axis = ticks(Math.max(...bins.map((b) => b[key])), 5);
+    const scale = (height - bottom - 10) / axis.max;
+    const g = node("g", { class: "axis" }, svg);
+    for (let v = 0; v <= axis.max; v += axis.step) {
+      const y = height - bottom - v * scale;
+      node("line", { x1: left, x2: width, y1: y, y2: y, class: "grid" }, g);
+      text(g, left - 8, y + 4, number(v), { "text-anchor": "end" });
+    }
+    const every = Math.ceil(longest / 30);
+    for (let length = 1; length <= longest; length += every) {
+      text(g, left + (length - 0.5) * slot, height - bottom + 16, length, {
+        "text-anchor": "middle",
+      });
+    }
+    const unit = key === "words" ? "distinct words" : "occurrences";
+    const sum = bins.reduce((s, b) => s + b[key], 0);
+    for (const bin of bins) {
+      const h = bin[key] * scale;
+      const bar = node("rect", {
+        x: left + (bin.length - 1) * slot + 1,
+        y: height - bottom - h,
+        width: Math.max(slot - 2, 1),
+        height: h,
+        class: "bar",
+      }, svg);
+      tooltip(bar, `${bin.length} letters: ${number(bin[key])} ${unit} (${percent(bin[key] / sum)})`);
+    }
+  }
+
+  function coverage() {
+    const points = data.coverage;
+    if (points.length === 0) {
+      return empty("coverage");
+    }
+    const width = 900;
+    const height = 280;
+    const left = 50;
+    const bottom = 30;
+    const svg = chart("coverage", width, height);
+    const ranks = points[points.length - 1][0];
+    // Log scale on ranks: the head of the curve is where it moves.
+    const span = Math.log10(Math.max(ranks, 10));
+    const x = (rank) => left + (Math.log10(rank) / span) * (width - left - 10);
+    const y = (share) => height - bottom - share * (height - bottom - 10);
+    const g = node("g", { class: "axis" }, svg);
+    for (let share = 0; share <= 1.0001; share += 0.25) {
+      node("line", { x1: left, x2: width - 10, y1: y(share), y2: y(share), class: "grid" }, g);
+      text(g, left - 8, y(share) + 4, percent(share), { "text-anchor": "end" });
+    }
+    for (let rank = 1; rank <= ranks; rank *= 10) {
+      node("line", { x1: x(rank), x2: x(rank), y1: 10, y2: height - bottom, class: "grid" }, g);
+      text(g, x(rank), height - bottom + 16, number(rank), { "text-anchor": "middle" });
+    }
+    const path = points.map(([rank, share], i) => `${i ? "L" : "M"}${x(rank)},${y(share)}`);
+    node("path", { d: path.join(""), class: "curve" }, svg);
+
+    const cursor = node("line", { y1: 10, y2: height - bottom, class: "cursor", visibility: "hidden" }, svg);
+    const dot = node("circle", { r: 4, class: "dot", visibility: "hidden" }, svg);
+    const label = text(svg, 0, 24, "", { visibility: "hidden" });
+    svg.addEventListener("mousemove", (event) => {
+      const box = svg.getBoundingClientRect();
+      const px = ((event.clientX - box.left) / box.width) * width;
+      let best = points[0];
+      for (const point of points) {
+        if (Math.abs(x(point[0]) - px) < Math.abs(x(best[0]) - px)) {
+          best = point;
+        }
+      }
+      const [rank, share] = best;
+      for (const element of [cursor, dot, label]) {
+        element.setAttribute("visibility", "visible");
+      }
+      cursor.setAttribute("x1", x(rank));
+      cursor.setAttribute("x2", x(rank));
+      dot.setAttribute("cx", x(rank));
+      dot.setAttribute("cy", y(share));
+      const right = x(rank) > width * 0.6;
+      label.setAttribute("x", x(rank) + (right ? -8 : 8));
+      label.setAttribute("text-anchor", right ? "end" : "start");
+      const words = rank === 1 ? "the most frequent word" : `the ${number(rank)} most frequent words`;
+      label.textContent = `${words}: ${percent(share)} of the text`;
+    });
+    svg.addEventListener("mouseleave", () => {
+      for (const element of [cursor, dot, label]) {
+        element.setAttribute("visibility", "hidden");
+      }
+    });
+  }
+
+  function timings() {
+    const runs = data.timings || [];
+    if (runs.length === 0) {
+      return;
+    }
+    document.getElementById("timings-section").hidden = false;
+    const row = 28;
+    const left = 140;
+    const width = 900;
+    const svg = chart("timings", width, runs.length * row + 24);
+    const ms = (ns) => ns / 1e6;
+    const axis = ticks(Math.max(...runs.map(([, ns]) => ms(ns))), 5);
+    const scale = (width - left - 120) / axis.max;
+    const g = node("g", { class: "axis" }, svg);
+    for (let v = 0; v <= axis.max; v += axis.step) {
+      const x = left + v * scale;
+      node("line", { x1: x, x2: x, y1: 0, y2: runs.length * row, class: "grid" }, g);
+      text(g, x, runs.length * row + 16, `${number(v)} ms`, { "text-anchor": "middle" });
+    }
+    const base = runs[0];
+    runs.forEach(([name, ns], i) => {
+      const y = i * row;
+      text(svg, left - 8, y + row / 2 + 4, name, { "text-anchor": "end" });
+      const bar = node("rect", {
+        x: left,
+        y: y + 4,
+        width: Math.max(ms(ns) * scale, 1),
+        height: row - 8,
+        class: "bar",
+      }, svg);
+      const speedup = base[1] / Math.max(ns, 1);
+      tooltip(bar, `${name}: ${speedup.toFixed(2)}× the speed of ${base[0]}`);
+      text(svg, left + ms(ns) * scale + 6, y + row / 2 + 4, `${ms(ns).toFixed(3)} ms`);
+    });
+  }
+
+  summary();
+  topWords();
+  lengths("words");
+  coverage();
+  timings();
+  for (const input of document.querySelectorAll('input[name="lengths"]')) {
+    input.addEventListener("change", () => lengths(input.value));
+  }
+})();
@@ -0,0 +1,60 @@
+//! Acronyms and all-caps terms, taken as written before words are lowercased,
+//! so a technical document's abbreviations can be audited.
+//!
+//! A term is a whitespace-separated token, surrounding punctuation trimmed,
+//! with at least two capital letters and no lowercase letter except a plural
+//! `s` at the end: `API`, `APIs`, `MP3`, `R&D`, `U.S.`, `TCP/IP`, `NOTE`.
+
+use crate::json::Json;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Acronyms {
+    /// Every term with its count, most frequent first, ties alphabetically.
+    pub terms: Vec<(String, usize)>,
+    /// Occurrences of all of them.
+    pub total: usize,
+}
+
+/// The acronym in `token`, if it is one.
+fn acronym(token: &str) -> Option<&str> {
+    let term = token.trim_matches(|c: char| !c.is_alphanumeric() && c != '.');
+    let term = term.trim_start_matches('.');
+    // A sentence-final period is not part of `NASA.` but is of `U.S.`.
+    let term = match term.strip_suffix('.') {
+        Some(stripped) if !stripped.contains('.') => stripped,
+        _ => term,
+    };
+    let body = term.strip_suffix('s').unwrap_or(term);
+    let capitals = body.chars().filter(|c| c.is_uppercase()).count();
+    let allowed = |c: char| c.is_uppercase() || c.is_ascii_digit() || "&./-".contains(c);
+    (capitals >= 2 && body.chars().all(allowed)).then_some(term)
+}
+
+impl Acronyms {
+    pub fn extract(text: &str) -> Acronyms {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for term in text.split_whitespace().filter_map(acronym) {
+            *counts.entry(term).or_insert(0) += 1;
+        }
+        let total = counts.values().sum();
+        let mut terms: Vec<(String, usize)> = counts
+            .into_iter()
+            .map(|(term, count)| (term.to_string(), count))
+            .collect();
+        terms.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        Acronyms { terms, total }
+    }
+
+    pub fn to_json_value(&self) -> Json {
+        let terms = self
+            .terms
+            .iter()
+            .map(|(t, c)| Json::Array(vec![t.as_str().into(), (*c).into()]))
+            .collect();
+        Json::Object(vec![
+            ("terms".into(), Json::Array(terms)),
+            ("total".into(), self.total.into()),
+        ])
+    }
+}
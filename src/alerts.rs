@@ -0,0 +1,184 @@
+//! Alerts for a stream under analysis (`td5 consume --alert`): a threshold
+//! on the running stats that starts or stops holding, or a deny-list entry
+//! showing up in a message. Each becomes an `Event`, rendered to a payload
+//! that an `OutputSink` (usually a webhook) delivers.
+//!
+//! Thresholds use the `check` syntax but mean the opposite of an assertion:
+//! `unique_words>5000` fires when the vocabulary grows past 5000. They are
+//! edge-triggered, so a rule fires once when it starts holding and once,
+//! as `resolved`, when it stops, not on every snapshot in between.
+//!
+//! Payloads default to the event's JSON object. A `template::Template` can
+//! shape them instead; it sees the same fields, with strings JSON-escaped so
+//! they can be placed inside quotes:
+//!
+//! ```text
+//! {"text": "td5: {{alert}} ({{value}}) on {{subject}}"}
+//! ```
+
+use crate::TextStats;
+use crate::check::Assertion;
+use crate::deny::DenyList;
+use crate::json::Json;
+use crate::template::Template;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// A threshold started holding.
+    Crossed { rule: Assertion, value: f64 },
+    /// A threshold that held stopped holding.
+    Resolved { rule: Assertion, value: f64 },
+    /// Deny-list entries matched in one message: the first one, and how
+    /// many matches there were in all.
+    Denied {
+        entry: String,
+        text: String,
+        matches: usize,
+    },
+}
+
+impl Event {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Event::Crossed { .. } => "threshold",
+            Event::Resolved { .. } => "resolved",
+            Event::Denied { .. } => "denied",
+        }
+    }
+
+    /// The event as a JSON object: `event` plus `alert`, `metric`, `value`
+    /// and `threshold` for thresholds, `entry`, `text` and `matches` for
+    /// deny-list matches.
+    pub fn to_json_value(&self) -> Json {
+        let mut fields = vec![("event".to_string(), self.kind().into())];
+        match self {
+            Event::Crossed { rule, value } | Event::Resolved { rule, value } => {
+                fields.push(("alert".into(), rule.to_string().into()));
+                fields.push(("metric".into(), rule.metric.name().into()));
+                fields.push(("value".into(), (*value).into()));
+                fields.push(("threshold".into(), rule.threshold.into()));
+            }
+            Event::Denied {
+                entry,
+                text,
+                matches,
+            } => {
+                fields.push(("entry".into(), entry.as_str().into()));
+                fields.push(("text".into(), text.as_str().into()));
+                fields.push(("matches".into(), (*matches).into()));
+            }
+        }
+        Json::Object(fields)
+    }
+}
+
+/// The rules watched over one stream, and which thresholds currently hold.
+///
+/// ```
+/// use td5::alerts::{Alerts, Event};
+/// use td5::check::Assertion;
+///
+/// let mut alerts = Alerts::new(vec![Assertion::parse("unique_words>2").unwrap()]);
+/// assert!(alerts.check(&td5::analyze("a b")).is_empty());
+/// let crossed = alerts.check(&td5::analyze("a b c"));
+/// assert!(matches!(crossed[..], [Event::Crossed { value: 3.0, .. }]));
+/// // Still over: nothing new.
+/// assert!(alerts.check(&td5::analyze("a b c d")).is_empty());
+/// assert_eq!(alerts.check(&td5::analyze("a"))[0].kind(), "resolved");
+/// ```
+pub struct Alerts {
+    rules: Vec<(Assertion, bool)>,
+    deny: Option<DenyList>,
+    template: Option<Template>,
+}
+
+impl Alerts {
+    pub fn new(rules: Vec<Assertion>) -> Alerts {
+        Alerts {
+            rules: rules.into_iter().map(|rule| (rule, false)).collect(),
+            deny: None,
+            template: None,
+        }
+    }
+
+    /// Also report messages matching `list`.
+    pub fn deny(mut self, list: DenyList) -> Alerts {
+        self.deny = Some(list);
+        self
+    }
+
+    /// Render payloads with `template` instead of as the event's JSON.
+    pub fn template(mut self, template: Template) -> Alerts {
+        self.template = Some(template);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty() && self.deny.is_none()
+    }
+
+    /// The thresholds that started or stopped holding since the last call.
+    pub fn check(&mut self, stats: &TextStats) -> Vec<Event> {
+        let mut events = Vec::new();
+        for (rule, active) in &mut self.rules {
+            // `check` answers "does it hold": `Ok` means the alert is on.
+            let (holds, value) = match rule.check(stats) {
+                Ok(()) => (true, rule.metric.value(stats)),
+                Err(value) => (false, value),
+            };
+            if holds != *active {
+                *active = holds;
+                let rule = *rule;
+                events.push(if holds {
+                    Event::Crossed { rule, value }
+                } else {
+                    Event::Resolved { rule, value }
+                });
+            }
+        }
+        events
+    }
+
+    /// The deny-list event for one message, if anything in it matches.
+    pub fn scan(&self, message: &str) -> Option<Event> {
+        let matches = self.deny.as_ref()?.scan(message);
+        let first = matches.first()?;
+        Some(Event::Denied {
+            entry: first.entry.clone(),
+            text: first.text.clone(),
+            matches: matches.len(),
+        })
+    }
+
+    /// The payload of `event`, with `context` (the stream's name, a time)
+    /// added to its fields.
+    pub fn payload(&self, event: &Event, context: &[(&str, Json)]) -> String {
+        let mut value = event.to_json_value();
+        for (key, extra) in context {
+            value.set(key, extra.clone());
+        }
+        match &self.template {
+            None => value.to_string(),
+            Some(template) => template.render(&escaped(value)).trim_end().to_string(),
+        }
+    }
+}
+
+/// `value` with every string replaced by its JSON-escaped content, so a
+/// template can put it between quotes.
+fn escaped(value: Json) -> Json {
+    match value {
+        Json::String(s) => {
+            let quoted = Json::String(s).to_string();
+            Json::String(quoted[1..quoted.len() - 1].to_string())
+        }
+        Json::Array(items) => Json::Array(items.into_iter().map(escaped).collect()),
+        Json::Object(fields) => Json::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key, escaped(value)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
@@ -0,0 +1,97 @@
+use crate::parallel::Parallel;
+use crate::sharded::Sharded;
+use crate::{
+    AnalyzerConfig, TextStats, analyze_text_fast, analyze_text_fast_with, analyze_text_slow,
+    analyze_text_slow_with,
+};
+
+/// Anything that turns a text into `TextStats`.
+///
+/// Implemented for every `Fn(&str) -> TextStats`, so the built-in
+/// `analyze_text_slow` / `analyze_text_fast` (or a closure) can be passed
+/// wherever an analyzer is expected.
+pub trait Analyzer {
+    fn analyze(&self, text: &str) -> TextStats;
+}
+
+impl<F> Analyzer for F
+where
+    F: Fn(&str) -> TextStats,
+{
+    fn analyze(&self, text: &str) -> TextStats {
+        self(text)
+    }
+}
+
+/// A built-in analyzer as listed by `registry()`.
+pub struct Registered {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub analyzer: Box<dyn Analyzer + Send + Sync>,
+}
+
+/// Every built-in implementation, reference (`slow`) first.
+pub fn registry() -> Vec<Registered> {
+    vec![
+        Registered {
+            name: "slow",
+            description: "reference implementation, several passes and allocations per word",
+            analyzer: Box::new(analyze_text_slow),
+        },
+        Registered {
+            name: "fast",
+            description: "single pass, ASCII specializations and hot-word table",
+            analyzer: Box::new(analyze_text_fast),
+        },
+        Registered {
+            name: "parallel",
+            description: "fast path on every core, per-thread maps merged in a tree",
+            analyzer: Box::new(Parallel::default()),
+        },
+        Registered {
+            name: "sharded",
+            description: "fast path on every core, one shared map behind sharded locks",
+            analyzer: Box::new(Sharded::default()),
+        },
+    ]
+}
+
+pub fn find(name: &str) -> Option<Registered> {
+    registry().into_iter().find(|r| r.name == name)
+}
+
+/// Like `find`, with a multi-threaded backend limited to `threads` workers
+/// (0 for every core). `None` for a single-threaded one.
+pub fn find_threaded(name: &str, threads: usize) -> Option<Registered> {
+    let mut registered = find(name)?;
+    registered.analyzer = match name {
+        "parallel" => Box::new(Parallel {
+            threads,
+            ..Parallel::default()
+        }),
+        "sharded" => Box::new(Sharded {
+            threads,
+            ..Sharded::default()
+        }),
+        _ => return None,
+    };
+    Some(registered)
+}
+
+/// Like `find_threaded`, with `config` instead of the default options.
+/// `threads` is ignored by single-threaded backends.
+pub fn configured(name: &str, threads: usize, config: AnalyzerConfig) -> Option<Registered> {
+    let mut registered = find(name)?;
+    registered.analyzer = match name {
+        "slow" => Box::new(move |text: &str| analyze_text_slow_with(text, &config)),
+        "fast" => Box::new(move |text: &str| analyze_text_fast_with(text, &config)),
+        "parallel" => Box::new(Parallel { threads, config }),
+        "sharded" => Box::new(Sharded {
+            threads,
+            config,
+            ..Sharded::default()
+        }),
+        _ => return None,
+    };
+    Some(registered)
+}
@@ -0,0 +1,351 @@
+//! Top words of a stream of any length in fixed memory (`td5 approx`).
+//!
+//! The analyzers keep an exact count of every distinct word, so their
+//! memory grows with the vocabulary. `ApproxCounter` instead keeps two
+//! structures whose size only depends on the error allowed:
+//!
+//! - a Count-Min Sketch, `depth` rows of `width` counters, each word adding
+//!   one to a counter per row. A word's estimate is the smallest of its
+//!   counters: never below its true count and, with `width = ⌈e/ε⌉` and
+//!   `depth = ⌈ln(1/δ)⌉`, over it by at most ε·N with probability 1 − δ,
+//!   N being the words counted.
+//! - a Space-Saving table of the `capacity` words most likely to be frequent.
+//!   A new word replaces the least counted one and inherits its count, kept
+//!   as that entry's error. Every word more frequent than N / `capacity` is
+//!   in the table.
+//!
+//! A listed word's count is the smaller of its two overestimates, and its
+//! table count minus its error is a lower bound that always holds.
+
+use crate::config::AnalyzerConfig;
+use crate::error::{Result, Td5Error};
+use crate::fast::FastMap;
+use crate::freq::select_top;
+use crate::json::Json;
+use crate::tokens::tokenize;
+use std::hash::{BuildHasher, BuildHasherDefault};
+
+/// Most counters a sketch may have: 256 MiB of them, enough for ε = 10⁻⁶ at
+/// δ = 10⁻⁵.
+pub const MAX_COUNTERS: usize = 1 << 25;
+
+/// Counts words approximately in memory fixed by ε, δ and the capacity.
+///
+/// ```
+/// use td5::approx::ApproxCounter;
+///
+/// let mut counter = ApproxCounter::new(0.01, 0.01).unwrap().capacity(20);
+/// let text = td5::generate_test_text(20_000);
+/// counter.add_text(&text);
+///
+/// let exact = td5::WordFrequency::from_text(&text);
+/// for word in counter.top(5) {
+///     let truth = exact.count(&word.word) as u64;
+///     assert!(word.min_count <= truth && truth <= word.count);
+///     assert!(word.count - truth <= counter.error_bound());
+/// }
+/// assert_eq!(counter.total(), 20_000);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ApproxCounter {
+    config: AnalyzerConfig,
+    epsilon: f64,
+    delta: f64,
+    sketch: Sketch,
+    table: SpaceSaving,
+    total: u64,
+}
+
+/// A word of `ApproxCounter::top`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApproxWord {
+    pub word: String,
+    /// Estimated occurrences, never below the true count.
+    pub count: u64,
+    /// Occurrences the word certainly has.
+    pub min_count: u64,
+}
+
+impl ApproxCounter {
+    /// A counter whose estimates exceed the true counts by at most
+    /// `epsilon` times the words counted, with probability `1 - delta`.
+    /// Both must be between 0 and 1. The table holds `⌈1/epsilon⌉` words.
+    /// The sketch is allocated up front, so values that would need more
+    /// than `MAX_COUNTERS` of its counters are refused.
+    ///
+    /// ```
+    /// use td5::approx::ApproxCounter;
+    ///
+    /// let error = ApproxCounter::new(1e-15, 0.01).unwrap_err();
+    /// assert!(error.to_string().contains("raise epsilon or delta"));
+    /// ```
+    pub fn new(epsilon: f64, delta: f64) -> Result<Self> {
+        if !(epsilon > 0.0 && epsilon < 1.0) {
+            return Err(Td5Error::config(format!(
+                "epsilon must be between 0 and 1, not {epsilon}"
+            )));
+        }
+        if !(delta > 0.0 && delta < 1.0) {
+            return Err(Td5Error::config(format!(
+                "delta must be between 0 and 1, not {delta}"
+            )));
+        }
+        let width = (std::f64::consts::E / epsilon).ceil();
+        let depth = (1.0 / delta).ln().ceil().max(1.0);
+        if width * depth > MAX_COUNTERS as f64 {
+            return Err(Td5Error::config(format!(
+                "epsilon {epsilon} and delta {delta} need {} sketch counters, \
+                 over the limit of {MAX_COUNTERS}: raise epsilon or delta",
+                width * depth
+            )));
+        }
+        let (width, depth) = (width as usize, depth as usize);
+        Ok(ApproxCounter {
+            config: AnalyzerConfig::default(),
+            epsilon,
+            delta,
+            sketch: Sketch {
+                width,
+                depth,
+                counters: vec![0; width * depth],
+            },
+            table: SpaceSaving::new((1.0 / epsilon).ceil() as usize),
+            total: 0,
+        })
+    }
+
+    /// Words kept in the Space-Saving table (at least 1): more find rarer
+    /// frequent words and tighten their lower bounds.
+    pub fn capacity(mut self, words: usize) -> Self {
+        self.table = SpaceSaving::new(words.max(1));
+        self
+    }
+
+    /// How words are cut and normalized, and the stopwords left out of
+    /// `top`. The ranking options do not apply.
+    pub fn config(mut self, config: AnalyzerConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Counts the words of `text`. A word cut between two calls counts as
+    /// two, so feed whole lines.
+    pub fn add_text(&mut self, text: &str) {
+        let (sketch, table, total) = (&mut self.sketch, &mut self.table, &mut self.total);
+        tokenize(text, &self.config, |word, _| {
+            *total += 1;
+            sketch.add(word);
+            table.add(word);
+        });
+    }
+
+    /// Words counted so far.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// The estimate of any word, listed or not.
+    pub fn estimate(&self, word: &str) -> u64 {
+        self.sketch.estimate(word)
+    }
+
+    /// ε·N: how far over its true count an estimate may be, with
+    /// probability 1 − δ.
+    pub fn error_bound(&self) -> u64 {
+        (self.epsilon * self.total as f64).ceil() as u64
+    }
+
+    pub fn epsilon(&self) -> f64 {
+        self.epsilon
+    }
+
+    pub fn delta(&self) -> f64 {
+        self.delta
+    }
+
+    /// Bytes taken by the sketch and the table, which stop growing once the
+    /// table is full.
+    pub fn memory_bytes(&self) -> usize {
+        self.sketch.counters.len() * size_of::<u64>() + self.table.memory_bytes()
+    }
+
+    /// The `k` words with the highest estimates, ties alphabetically,
+    /// stopwords left out.
+    pub fn top(&self, k: usize) -> Vec<ApproxWord> {
+        let mut words: Vec<ApproxWord> = self
+            .table
+            .entries
+            .iter()
+            .filter(|entry| !self.config.is_stopword(&entry.word))
+            .map(|entry| ApproxWord {
+                word: entry.word.to_string(),
+                count: entry.count.min(self.sketch.estimate(&entry.word)),
+                min_count: entry.count - entry.error,
+            })
+            .collect();
+        let top = select_top(&mut words, k, |a, b| {
+            b.count.cmp(&a.count).then_with(|| a.word.cmp(&b.word))
+        })
+        .len();
+        words.truncate(top);
+        words
+    }
+
+    /// The totals, the bounds and the `k` top words.
+    pub fn to_json_value(&self, k: usize) -> Json {
+        Json::Object(vec![
+            ("total_words".into(), self.total.into()),
+            ("epsilon".into(), self.epsilon.into()),
+            ("delta".into(), self.delta.into()),
+            ("error_bound".into(), self.error_bound().into()),
+            ("capacity".into(), self.table.capacity.into()),
+            ("memory_bytes".into(), self.memory_bytes().into()),
+            (
+                "top_words".into(),
+                Json::Array(
+                    self.top(k)
+                        .into_iter()
+                        .map(|w| {
+                            Json::Array(vec![w.word.into(), w.count.into(), w.min_count.into()])
+                        })
+                        .collect(),
+                ),
+            ),
+        ])
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Sketch {
+    width: usize,
+    depth: usize,
+    /// Row after row.
+    counters: Vec<u64>,
+}
+
+impl Sketch {
+    /// The counter of `word` in each row, from two hashes (Kirsch and
+    /// Mitzenmacher), which is as good as `depth` independent ones here.
+    fn cells(&self, word: &str) -> impl Iterator<Item = usize> + use<> {
+        let hash = BuildHasherDefault::<rustc_hash::FxHasher>::default().hash_one(word);
+        // FxHash leaves the low bits weak; mix them (SplitMix64's finalizer).
+        let mut h = hash ^ (hash >> 30);
+        h = h.wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        h ^= h >> 27;
+        h = h.wrapping_mul(0x94D0_49BB_1331_11EB);
+        h ^= h >> 31;
+        let (a, b) = (h & 0xFFFF_FFFF, (h >> 32) | 1);
+        let width = self.width as u64;
+        (0..self.depth as u64)
+            .map(move |row| (row * width + a.wrapping_add(row * b) % width) as usize)
+    }
+
+    fn add(&mut self, word: &str) {
+        for cell in self.cells(word) {
+            self.counters[cell] += 1;
+        }
+    }
+
+    fn estimate(&self, word: &str) -> u64 {
+        self.cells(word)
+            .map(|cell| self.counters[cell])
+            .min()
+            .unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    word: Box<str>,
+    count: u64,
+    /// The count inherited from the word this one replaced.
+    error: u64,
+}
+
+/// The Space-Saving table, kept as a binary min-heap on the counts so the
+/// word to replace is always at the root.
+#[derive(Debug, Clone)]
+struct SpaceSaving {
+    capacity: usize,
+    entries: Vec<Entry>,
+    /// Where each word is in `entries`.
+    slots: FastMap<Box<str>, usize>,
+}
+
+impl SpaceSaving {
+    fn new(capacity: usize) -> Self {
+        SpaceSaving {
+            capacity,
+            entries: Vec::new(),
+            slots: FastMap::default(),
+        }
+    }
+
+    fn add(&mut self, word: &str) {
+        if let Some(&slot) = self.slots.get(word) {
+            self.entries[slot].count += 1;
+            self.sift_down(slot);
+        } else if self.entries.len() < self.capacity {
+            let word: Box<str> = word.into();
+            self.slots.insert(word.clone(), self.entries.len());
+            self.entries.push(Entry {
+                word,
+                count: 1,
+                error: 0,
+            });
+            self.sift_up(self.entries.len() - 1);
+        } else {
+            let least = &mut self.entries[0];
+            self.slots.remove(&least.word);
+            least.word = word.into();
+            least.error = least.count;
+            least.count += 1;
+            self.slots.insert(least.word.clone(), 0);
+            self.sift_down(0);
+        }
+    }
+
+    fn sift_up(&mut self, mut i: usize) {
+        while i > 0 {
+            let parent = (i - 1) / 2;
+            if self.entries[parent].count <= self.entries[i].count {
+                break;
+            }
+            self.swap(i, parent);
+            i = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut i: usize) {
+        loop {
+            let (left, right) = (2 * i + 1, 2 * i + 2);
+            let mut least = i;
+            for child in [left, right] {
+                if child < self.entries.len()
+                    && self.entries[child].count < self.entries[least].count
+                {
+                    least = child;
+                }
+            }
+            if least == i {
+                break;
+            }
+            self.swap(i, least);
+            i = least;
+        }
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        self.entries.swap(i, j);
+        for k in [i, j] {
+            if let Some(slot) = self.slots.get_mut(&self.entries[k].word) {
+                *slot = k;
+            }
+        }
+    }
+
+    fn memory_bytes(&self) -> usize {
+        let words: usize = self.entries.iter().map(|e| e.word.len()).sum();
+        self.capacity * (size_of::<Entry>() + size_of::<(Box<str>, usize)>()) + 2 * words
+    }
+}
@@ -0,0 +1,858 @@
+use crate::Analyzer;
+use crate::analyzer::{find, registry};
+use crate::clock::Stopwatch;
+use crate::environment::Environment;
+use crate::error::{Result, Td5Error};
+use crate::json::{self, Json};
+#[cfg(all(feature = "rapl", target_os = "linux"))]
+use crate::rapl::Rapl;
+use crate::schema::DecodeError;
+use crate::seed;
+use std::fmt::{self, Write as _};
+use std::hint::black_box;
+
+/// A named text fed to every registered analyzer.
+pub struct BenchInput {
+    pub name: String,
+    pub text: String,
+}
+
+impl BenchInput {
+    pub fn new(name: impl Into<String>, text: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            text: text.into(),
+        }
+    }
+}
+
+/// Runs each registered analyzer over each input and collects timings.
+///
+/// The first registered analyzer is the baseline for speedups.
+pub struct BenchSuite {
+    analyzers: Vec<(String, Box<dyn Analyzer>)>,
+    warmup: usize,
+    iterations: usize,
+    #[cfg(all(feature = "rapl", target_os = "linux"))]
+    rapl: Option<Rapl>,
+}
+
+impl Default for BenchSuite {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BenchSuite {
+    pub fn new() -> Self {
+        Self {
+            analyzers: Vec::new(),
+            warmup: 1,
+            iterations: 10,
+            #[cfg(all(feature = "rapl", target_os = "linux"))]
+            rapl: None,
+        }
+    }
+
+    /// Suite pre-loaded with every analyzer from `analyzer::registry()`.
+    pub fn with_builtins() -> Self {
+        registry().into_iter().fold(Self::new(), |suite, r| {
+            suite.register_boxed(r.name, r.analyzer)
+        })
+    }
+
+    /// Suite with only the named built-ins, in the given order.
+    pub fn with_builtins_named(names: &[&str]) -> Result<Self> {
+        names.iter().try_fold(Self::new(), |suite, name| {
+            let r =
+                find(name).ok_or_else(|| Td5Error::config(format!("unknown analyzer `{name}`")))?;
+            Ok(suite.register_boxed(r.name, r.analyzer))
+        })
+    }
+
+    pub fn register(mut self, name: impl Into<String>, analyzer: impl Analyzer + 'static) -> Self {
+        self.analyzers.push((name.into(), Box::new(analyzer)));
+        self
+    }
+
+    fn register_boxed(mut self, name: &str, analyzer: Box<dyn Analyzer + Send + Sync>) -> Self {
+        self.analyzers.push((name.to_string(), analyzer));
+        self
+    }
+
+    pub fn warmup(mut self, warmup: usize) -> Self {
+        self.warmup = warmup;
+        self
+    }
+
+    pub fn iterations(mut self, iterations: usize) -> Self {
+        self.iterations = iterations.max(1);
+        self
+    }
+
+    /// Also reads `rapl` around every timed run, into
+    /// `BenchResult::energy_uj`. The readings stay outside the timing.
+    #[cfg(all(feature = "rapl", target_os = "linux"))]
+    pub fn energy(mut self, rapl: Rapl) -> Self {
+        self.rapl = Some(rapl);
+        self
+    }
+
+    pub fn run(&self, inputs: &[BenchInput]) -> Result<BenchReport> {
+        if self.analyzers.is_empty() {
+            return Err(Td5Error::config("no analyzers registered"));
+        }
+        if inputs.is_empty() {
+            return Err(Td5Error::config("no benchmark inputs"));
+        }
+        let mut results = Vec::with_capacity(inputs.len() * self.analyzers.len());
+        for input in inputs {
+            for (name, analyzer) in &self.analyzers {
+                for _ in 0..self.warmup {
+                    black_box(analyzer.analyze(black_box(&input.text)));
+                }
+                let mut samples = Vec::with_capacity(self.iterations);
+                #[cfg_attr(not(all(feature = "rapl", target_os = "linux")), allow(unused_mut))]
+                let mut energy_uj = Vec::new();
+                for _ in 0..self.iterations {
+                    #[cfg(all(feature = "rapl", target_os = "linux"))]
+                    let before = self.rapl.as_ref().map(Rapl::read).transpose()?;
+                    let start = Stopwatch::start();
+                    black_box(analyzer.analyze(black_box(&input.text)));
+                    samples.push(start.elapsed_ns());
+                    #[cfg(all(feature = "rapl", target_os = "linux"))]
+                    if let (Some(rapl), Some(before)) = (&self.rapl, before) {
+                        energy_uj.push(rapl.used(&before, &rapl.read()?));
+                    }
+                }
+                results.push(BenchResult {
+                    analyzer: name.clone(),
+                    input: input.name.clone(),
+                    summary: Summary::from_samples(&samples),
+                    samples,
+                    energy_uj,
+                });
+            }
+        }
+        Ok(BenchReport {
+            environment: Environment::capture(),
+            results,
+            seed: None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Summary {
+    pub min_ns: u128,
+    pub max_ns: u128,
+    pub median_ns: u128,
+    pub mean_ns: f64,
+    pub stddev_ns: f64,
+}
+
+impl Summary {
+    pub fn from_samples(samples: &[u128]) -> Self {
+        if samples.is_empty() {
+            return Self {
+                min_ns: 0,
+                max_ns: 0,
+                median_ns: 0,
+                mean_ns: 0.0,
+                stddev_ns: 0.0,
+            };
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+        let n = sorted.len() as f64;
+        let mean = sorted.iter().map(|&s| s as f64).sum::<f64>() / n;
+        let var = sorted
+            .iter()
+            .map(|&s| (s as f64 - mean).powi(2))
+            .sum::<f64>()
+            / n;
+        Self {
+            min_ns: sorted[0],
+            max_ns: sorted[sorted.len() - 1],
+            median_ns: sorted[sorted.len() / 2],
+            mean_ns: mean,
+            stddev_ns: var.sqrt(),
+        }
+    }
+
+    pub fn to_json_value(&self) -> Json {
+        Json::Object(vec![
+            ("min_ns".into(), self.min_ns.into()),
+            ("max_ns".into(), self.max_ns.into()),
+            ("median_ns".into(), self.median_ns.into()),
+            ("mean_ns".into(), self.mean_ns.into()),
+            ("stddev_ns".into(), self.stddev_ns.into()),
+        ])
+    }
+}
+
+/// Two-sided Mann-Whitney U test between two sets of timing samples.
+///
+/// Rank-based, so a few slow outliers (page faults, preemption) do not
+/// dominate the way they would in a t-test. Up to `EXACT_MAX` samples a side
+/// without ties, the p-value comes from the exact distribution of U: with 3
+/// against 3 it is never below 0.1. Otherwise it uses the normal
+/// approximation with tie and continuity corrections.
+///
+/// ```
+/// use td5::bench::MannWhitney;
+///
+/// let test = MannWhitney::test(&[1, 2, 3], &[4, 5, 6]).unwrap();
+/// assert!((test.p_value - 0.1).abs() < 1e-12);
+/// assert!(!test.significant(0.05));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MannWhitney {
+    pub u: f64,
+    pub z: f64,
+    pub p_value: f64,
+}
+
+impl MannWhitney {
+    pub fn test(a: &[u128], b: &[u128]) -> Option<Self> {
+        if a.is_empty() || b.is_empty() {
+            return None;
+        }
+        let (n1, n2) = (a.len() as f64, b.len() as f64);
+        let mut all: Vec<(u128, bool)> = a
+            .iter()
+            .map(|&s| (s, true))
+            .chain(b.iter().map(|&s| (s, false)))
+            .collect();
+        all.sort_unstable_by_key(|&(s, _)| s);
+
+        // Average ranks over ties, accumulating the tie correction term.
+        let mut rank_sum_a = 0.0;
+        let mut ties = 0.0;
+        let mut i = 0;
+        while i < all.len() {
+            let mut j = i;
+            while j < all.len() && all[j].0 == all[i].0 {
+                j += 1;
+            }
+            let rank = (i + j + 1) as f64 / 2.0;
+            rank_sum_a += rank * all[i..j].iter().filter(|(_, in_a)| *in_a).count() as f64;
+            let t = (j - i) as f64;
+            ties += t * t * t - t;
+            i = j;
+        }
+
+        let u = rank_sum_a - n1 * (n1 + 1.0) / 2.0;
+        let n = n1 + n2;
+        let variance = n1 * n2 / 12.0 * ((n + 1.0) - ties / (n * (n - 1.0)).max(1.0));
+        if variance <= 0.0 {
+            // Every sample identical: no evidence of a difference.
+            return Some(Self {
+                u,
+                z: 0.0,
+                p_value: 1.0,
+            });
+        }
+        // Half a step towards the mean, for a discrete U read off a
+        // continuous curve.
+        let shift = (u - n1 * n2 / 2.0).abs() - 0.5;
+        let z = (u - n1 * n2 / 2.0).signum() * shift.max(0.0) / variance.sqrt();
+        let p_value = if ties == 0.0 && a.len().max(b.len()) <= EXACT_MAX {
+            exact_p(a.len(), b.len(), u as usize)
+        } else {
+            erfc(z.abs() / std::f64::consts::SQRT_2).min(1.0)
+        };
+        Some(Self { u, z, p_value })
+    }
+
+    pub fn significant(&self, alpha: f64) -> bool {
+        self.p_value < alpha
+    }
+}
+
+/// Largest sample count a side for which `MannWhitney` computes the exact
+/// p-value.
+pub const EXACT_MAX: usize = 20;
+
+/// Two-sided p-value of `u` from the exact distribution of U for `n1` and
+/// `n2` samples without ties: twice the smaller tail, at most 1.
+fn exact_p(n1: usize, n2: usize, u: usize) -> f64 {
+    // `ways[m][k]`, built up one sample of the second set at a time: the
+    // orderings of `m` samples of the first set and those of the second
+    // where U is `k`. A new largest sample of the second set adds nothing
+    // to U; a new largest of the first adds one per sample of the second.
+    let max = n1 * n2;
+    let mut ways = vec![vec![0.0f64; max + 1]; n1 + 1];
+    for row in &mut ways {
+        row[0] = 1.0;
+    }
+    for n in 1..=n2 {
+        for m in 1..=n1 {
+            for k in 0..=m * n {
+                ways[m][k] += if k >= n { ways[m - 1][k - n] } else { 0.0 };
+            }
+        }
+    }
+    let all: f64 = ways[n1].iter().sum();
+    let below: f64 = ways[n1][..=u].iter().sum();
+    let above: f64 = ways[n1][u..].iter().sum();
+    (2.0 * below.min(above) / all).min(1.0)
+}
+
+/// Complementary error function (Numerical Recipes `erfcc`, |error| < 1.2e-7).
+fn erfc(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.5 * x.abs());
+    let poly = -x * x - 1.265_512_23
+        + t * (1.000_023_68
+            + t * (0.374_091_96
+                + t * (0.096_784_18
+                    + t * (-0.186_288_06
+                        + t * (0.278_868_07
+                            + t * (-1.135_203_98
+                                + t * (1.488_515_87 + t * (-0.822_152_23 + t * 0.170_872_77))))))));
+    let r = t * poly.exp();
+    if x >= 0.0 { r } else { 2.0 - r }
+}
+
+/// Significance level used when the report marks a speedup as noise.
+pub const ALPHA: f64 = 0.05;
+
+#[derive(Debug)]
+pub struct BenchResult {
+    pub analyzer: String,
+    pub input: String,
+    pub samples: Vec<u128>,
+    pub summary: Summary,
+    /// Microjoules the CPU packages used during each sample, with
+    /// `BenchSuite::energy`; empty otherwise.
+    pub energy_uj: Vec<u64>,
+}
+
+impl BenchResult {
+    /// Median of `energy_uj`, if energy was measured. Saved reports keep
+    /// the samples, and the grid and CSV show them:
+    ///
+    /// ```
+    /// use td5::bench::BenchReport;
+    ///
+    /// let report = BenchReport::from_json(
+    ///     r#"{"results": [
+    ///         {"analyzer": "slow", "input": "zipf", "samples_ns": [9000000, 8000000, 9500000],
+    ///          "energy_uj": [410000, 390000, 420000]},
+    ///         {"analyzer": "fast", "input": "zipf", "samples_ns": [2000000, 2100000, 1900000],
+    ///          "energy_uj": [98000, 97000, 99000]}
+    ///     ]}"#,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(report.results[0].median_energy_uj(), Some(410_000));
+    /// let grid = report.grid().to_string();
+    /// assert!(grid.contains("median mJ"));
+    /// assert!(grid.contains("98.000 (  4.2x)"));
+    /// assert!(report.to_csv().lines().next().unwrap().ends_with(",energy_uj"));
+    /// ```
+    pub fn median_energy_uj(&self) -> Option<u64> {
+        let mut energy = self.energy_uj.clone();
+        energy.sort_unstable();
+        energy.get(energy.len() / 2).copied()
+    }
+}
+
+#[derive(Debug)]
+pub struct BenchReport {
+    /// Machine and build the samples were taken on.
+    pub environment: Environment,
+    pub results: Vec<BenchResult>,
+    /// The seed of the generated inputs, if there were any.
+    pub seed: Option<u64>,
+}
+
+impl BenchReport {
+    /// Saved form: environment plus raw samples and their summaries, which
+    /// are recomputed from the samples on load.
+    pub fn to_json_value(&self) -> Json {
+        let results = self
+            .results
+            .iter()
+            .map(|r| {
+                let mut doc = Json::Object(vec![
+                    ("analyzer".into(), r.analyzer.as_str().into()),
+                    ("input".into(), r.input.as_str().into()),
+                    (
+                        "samples_ns".into(),
+                        Json::Array(r.samples.iter().map(|&s| s.into()).collect()),
+                    ),
+                    ("summary".into(), r.summary.to_json_value()),
+                ]);
+                if !r.energy_uj.is_empty() {
+                    doc.set(
+                        "energy_uj",
+                        Json::Array(r.energy_uj.iter().map(|&e| e.into()).collect()),
+                    );
+                }
+                doc
+            })
+            .collect();
+        let mut doc = Json::Object(vec![
+            ("environment".into(), self.environment.to_json_value()),
+            ("results".into(), Json::Array(results)),
+        ]);
+        if let Some(seed) = self.seed {
+            doc.set("seed", seed::to_json_value(seed));
+        }
+        doc
+    }
+
+    pub fn to_json(&self) -> String {
+        self.to_json_value().to_string()
+    }
+
+    /// One row per sample: `input,analyzer,iteration,ns`, then the cell's
+    /// summary (`min_ns,median_ns,mean_ns,max_ns,stddev_ns`) repeated on
+    /// each of its rows, ready for a dataframe. When energy was measured, an
+    /// `energy_uj` column follows, empty for cells without it.
+    pub fn to_csv(&self) -> String {
+        let field = |s: &str| {
+            if s.contains([',', '"', '\n']) {
+                format!("\"{}\"", s.replace('"', "\"\""))
+            } else {
+                s.to_string()
+            }
+        };
+        let energy = self.results.iter().any(|r| !r.energy_uj.is_empty());
+        let mut out =
+            String::from("input,analyzer,iteration,ns,min_ns,median_ns,mean_ns,max_ns,stddev_ns");
+        out.push_str(if energy { ",energy_uj\n" } else { "\n" });
+        for r in &self.results {
+            let s = &r.summary;
+            for (i, ns) in r.samples.iter().enumerate() {
+                let _ = write!(
+                    out,
+                    "{},{},{},{ns},{},{},{:.1},{},{:.1}",
+                    field(&r.input),
+                    field(&r.analyzer),
+                    i + 1,
+                    s.min_ns,
+                    s.median_ns,
+                    s.mean_ns,
+                    s.max_ns,
+                    s.stddev_ns
+                );
+                if energy {
+                    out.push(',');
+                    if let Some(uj) = r.energy_uj.get(i) {
+                        let _ = write!(out, "{uj}");
+                    }
+                }
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    pub fn from_json(input: &str) -> std::result::Result<Self, DecodeError> {
+        Self::from_json_value(&json::parse(input)?)
+    }
+
+    pub fn from_json_value(doc: &Json) -> std::result::Result<Self, DecodeError> {
+        if !matches!(doc, Json::Object(_)) {
+            return Err(DecodeError::NotAnObject);
+        }
+        let environment = doc.get("environment").map_or_else(
+            || Environment::from_json_value(&Json::Null),
+            Environment::from_json_value,
+        );
+        let results = doc
+            .get("results")
+            .and_then(Json::as_array)
+            .ok_or(DecodeError::InvalidField("results"))?
+            .iter()
+            .map(|r| {
+                let text = |name: &'static str| {
+                    r.get(name)
+                        .and_then(Json::as_str)
+                        .map(str::to_string)
+                        .ok_or(DecodeError::InvalidField(name))
+                };
+                let samples = r
+                    .get("samples_ns")
+                    .and_then(Json::as_array)
+                    .ok_or(DecodeError::InvalidField("samples_ns"))?
+                    .iter()
+                    .map(|s| {
+                        s.as_u64()
+                            .map(u128::from)
+                            .ok_or(DecodeError::InvalidField("samples_ns"))
+                    })
+                    .collect::<std::result::Result<Vec<u128>, _>>()?;
+                let energy_uj = r
+                    .get("energy_uj")
+                    .and_then(Json::as_array)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|e| e.as_u64().ok_or(DecodeError::InvalidField("energy_uj")))
+                    .collect::<std::result::Result<Vec<u64>, _>>()?;
+                Ok(BenchResult {
+                    analyzer: text("analyzer")?,
+                    input: text("input")?,
+                    summary: Summary::from_samples(&samples),
+                    samples,
+                    energy_uj,
+                })
+            })
+            .collect::<std::result::Result<_, DecodeError>>()?;
+        let seed = doc
+            .get("seed")
+            .map(|seed| {
+                seed.get("value")
+                    .and_then(Json::as_u64)
+                    .ok_or(DecodeError::InvalidField("seed"))
+            })
+            .transpose()?;
+        Ok(Self {
+            environment,
+            results,
+            seed,
+        })
+    }
+
+    /// Per-cell comparison of this run against a saved `baseline`.
+    /// Rendering context for `template::Template`: the environment and one
+    /// entry per result with its timings in ms, and speedup and p-value
+    /// against the first analyzer on the same input.
+    pub fn template_context(&self) -> Json {
+        let round = |x: f64, places: i32| {
+            let scale = 10f64.powi(places);
+            Json::from((x * scale).round() / scale)
+        };
+        let analyzers = self.names(|r| &r.analyzer);
+        let results = self
+            .results
+            .iter()
+            .map(|r| {
+                let s = &r.summary;
+                let mut doc = Json::Object(vec![
+                    ("input".into(), r.input.as_str().into()),
+                    ("analyzer".into(), r.analyzer.as_str().into()),
+                    ("iterations".into(), r.samples.len().into()),
+                    ("median_ms".into(), round(ms(s.median_ns as f64), 3)),
+                    ("mean_ms".into(), round(ms(s.mean_ns), 3)),
+                    ("min_ms".into(), round(ms(s.min_ns as f64), 3)),
+                    ("max_ms".into(), round(ms(s.max_ns as f64), 3)),
+                    ("stddev_ms".into(), round(ms(s.stddev_ns), 3)),
+                ]);
+                if let Some(uj) = r.median_energy_uj() {
+                    doc.set("median_mj", round(uj as f64 / 1_000.0, 3));
+                }
+                if let Some(&baseline) = analyzers.first() {
+                    if let Some(speedup) = self.speedup(&r.input, baseline, &r.analyzer) {
+                        doc.set("speedup", round(speedup, 2));
+                    }
+                    if baseline != r.analyzer
+                        && let Some(test) = self.significance(&r.input, baseline, &r.analyzer)
+                    {
+                        doc.set("p_value", round(test.p_value, 4));
+                        doc.set("significant", test.significant(ALPHA).into());
+                    }
+                }
+                doc
+            })
+            .collect();
+        let mut context = Json::Object(vec![
+            ("environment".into(), self.environment.to_json_value()),
+            ("results".into(), Json::Array(results)),
+        ]);
+        if let Some(seed) = self.seed {
+            context.set("seed", seed.into());
+        }
+        context
+    }
+
+    pub fn against<'a>(&'a self, baseline: &'a BenchReport) -> BaselineDiff<'a> {
+        BaselineDiff {
+            current: self,
+            baseline,
+        }
+    }
+
+    /// Adds `result` after the last one on the same input, so rows stay
+    /// grouped by input.
+    pub fn insert(&mut self, result: BenchResult) {
+        let at = self
+            .results
+            .iter()
+            .rposition(|r| r.input == result.input)
+            .map_or(self.results.len(), |i| i + 1);
+        self.results.insert(at, result);
+    }
+
+    pub fn get(&self, input: &str, analyzer: &str) -> Option<&BenchResult> {
+        self.results
+            .iter()
+            .find(|r| r.input == input && r.analyzer == analyzer)
+    }
+
+    /// Median-based speedup of `candidate` over `baseline` on `input`.
+    pub fn speedup(&self, input: &str, baseline: &str, candidate: &str) -> Option<f64> {
+        let base = self.get(input, baseline)?.summary.median_ns as f64;
+        let cand = self.get(input, candidate)?.summary.median_ns as f64;
+        Some(base / cand.max(1.0))
+    }
+
+    /// Mann-Whitney test of `candidate` against `baseline` on `input`.
+    pub fn significance(
+        &self,
+        input: &str,
+        baseline: &str,
+        candidate: &str,
+    ) -> Option<MannWhitney> {
+        let base = self.get(input, baseline)?;
+        let cand = self.get(input, candidate)?;
+        MannWhitney::test(&base.samples, &cand.samples)
+    }
+
+    /// Input × analyzer table of median times, in run order.
+    pub fn grid(&self) -> Grid<'_> {
+        Grid(self)
+    }
+
+    fn names(&self, key: impl Fn(&BenchResult) -> &str) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for r in &self.results {
+            if !names.contains(&key(r)) {
+                names.push(key(r));
+            }
+        }
+        names
+    }
+}
+
+/// Compact view of a `BenchReport`: one row per input, one column per
+/// analyzer, each cell the median in ms and the speedup over the first column.
+/// Speedups that are not significant at `ALPHA` are marked with `~`.
+pub struct Grid<'a>(&'a BenchReport);
+
+impl fmt::Display for Grid<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let report = self.0;
+        let inputs = report.names(|r| &r.input);
+        let analyzers = report.names(|r| &r.analyzer);
+        let width = inputs.iter().map(|i| i.len()).max().unwrap_or(0).max(16);
+        write!(f, "{:<width$}", "median ms")?;
+        for analyzer in &analyzers {
+            write!(f, " {analyzer:>19}")?;
+        }
+        writeln!(f)?;
+        for input in inputs {
+            write!(f, "{input:<width$}")?;
+            let base = analyzers.first().and_then(|a| report.get(input, a));
+            for analyzer in &analyzers {
+                match report.get(input, analyzer) {
+                    Some(r) => {
+                        let median = r.summary.median_ns as f64;
+                        let (speedup, noise) = match base {
+                            Some(b) if b.analyzer != r.analyzer => (
+                                b.summary.median_ns as f64 / median.max(1.0),
+                                MannWhitney::test(&b.samples, &r.samples)
+                                    .is_some_and(|t| !t.significant(ALPHA)),
+                            ),
+                            _ => (1.0, false),
+                        };
+                        let mark = if noise { '~' } else { ' ' };
+                        write!(f, " {:>9.3} ({:>5.1}x){mark}", ms(median), speedup)?;
+                    }
+                    None => write!(f, " {:>19}", "-")?,
+                }
+            }
+            writeln!(f)?;
+        }
+        writeln!(f, "~ not significant (Mann-Whitney p >= {ALPHA})")?;
+        if report.results.iter().all(|r| r.energy_uj.is_empty()) {
+            return Ok(());
+        }
+        // Energy cells compare like time: how many times less than the first column.
+        write!(f, "\n{:<width$}", "median mJ")?;
+        for analyzer in &analyzers {
+            write!(f, " {analyzer:>19}")?;
+        }
+        writeln!(f)?;
+        for input in report.names(|r| &r.input) {
+            write!(f, "{input:<width$}")?;
+            let base = analyzers
+                .first()
+                .and_then(|a| report.get(input, a)?.median_energy_uj());
+            for analyzer in &analyzers {
+                match report
+                    .get(input, analyzer)
+                    .and_then(BenchResult::median_energy_uj)
+                {
+                    Some(uj) => {
+                        let ratio = base.map_or(1.0, |b| b as f64 / (uj as f64).max(1.0));
+                        write!(f, " {:>9.3} ({:>5.1}x) ", uj as f64 / 1_000.0, ratio)?;
+                    }
+                    None => write!(f, " {:>19}", "-")?,
+                }
+            }
+            writeln!(f)?;
+        }
+        writeln!(
+            f,
+            "energy of the whole CPU packages during each run (RAPL), an estimate"
+        )
+    }
+}
+
+/// `BenchReport::against`: every cell present in both runs with its median
+/// change and significance. Check `environment_differences` before trusting it.
+pub struct BaselineDiff<'a> {
+    current: &'a BenchReport,
+    baseline: &'a BenchReport,
+}
+
+/// One cell of a `BaselineDiff`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellChange<'a> {
+    pub input: &'a str,
+    pub analyzer: &'a str,
+    pub baseline_ns: u128,
+    pub now_ns: u128,
+    /// Relative change of the median: 0.1 for 10% slower, -0.5 for twice
+    /// as fast.
+    pub change: f64,
+    /// Mann-Whitney p-value of the two runs' samples, 1 when there are too
+    /// few to test.
+    pub p_value: f64,
+}
+
+impl CellChange<'_> {
+    pub fn significant(&self) -> bool {
+        self.p_value < ALPHA
+    }
+}
+
+impl<'a> BaselineDiff<'a> {
+    pub fn environment_differences(&self) -> Vec<String> {
+        self.current
+            .environment
+            .differences(&self.baseline.environment)
+    }
+
+    /// Every cell in both runs, in the current run's order.
+    pub fn cells(&self) -> Vec<CellChange<'a>> {
+        self.current
+            .results
+            .iter()
+            .filter_map(|r| {
+                let b = self.baseline.get(&r.input, &r.analyzer)?;
+                let (before, now) = (b.summary.median_ns, r.summary.median_ns);
+                Some(CellChange {
+                    input: &r.input,
+                    analyzer: &r.analyzer,
+                    baseline_ns: before,
+                    now_ns: now,
+                    change: now as f64 / (before as f64).max(1.0) - 1.0,
+                    p_value: MannWhitney::test(&b.samples, &r.samples).map_or(1.0, |t| t.p_value),
+                })
+            })
+            .collect()
+    }
+
+    /// Cells whose median grew by more than `max_change` (0.1 for 10%),
+    /// significantly at `ALPHA`, so that noise alone does not fail a gate.
+    ///
+    /// ```
+    /// use td5::bench::BenchReport;
+    ///
+    /// let run = |fast: &str| {
+    ///     BenchReport::from_json(&format!(
+    ///         r#"{{"results": [
+    ///             {{"analyzer": "slow", "input": "zipf", "samples_ns": [900, 910, 905, 895, 902]}},
+    ///             {{"analyzer": "fast", "input": "zipf", "samples_ns": {fast}}}
+    ///         ]}}"#
+    ///     ))
+    ///     .unwrap()
+    /// };
+    /// let baseline = run("[100, 101, 99, 100, 102]");
+    /// let slower = run("[130, 128, 131, 129, 132]");
+    /// let regressions = slower.against(&baseline).regressions(0.10);
+    /// assert_eq!(regressions.len(), 1);
+    /// assert_eq!(regressions[0].analyzer, "fast");
+    /// assert!((regressions[0].change - 0.30).abs() < 0.01);
+    /// assert!(slower.against(&baseline).regressions(0.50).is_empty());
+    /// ```
+    pub fn regressions(&self, max_change: f64) -> Vec<CellChange<'a>> {
+        self.cells()
+            .into_iter()
+            .filter(|cell| cell.change > max_change && cell.significant())
+            .collect()
+    }
+}
+
+impl fmt::Display for BaselineDiff<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<16} {:<12} {:>12} {:>10} {:>8} {:>8}",
+            "input", "analyzer", "baseline ms", "now ms", "change", "p-value"
+        )?;
+        for cell in self.cells() {
+            writeln!(
+                f,
+                "{:<16} {:<12} {:>12.3} {:>10.3} {:>+7.1}% {:>8.4}",
+                cell.input,
+                cell.analyzer,
+                ms(cell.baseline_ns as f64),
+                ms(cell.now_ns as f64),
+                cell.change * 100.0,
+                cell.p_value,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn ms(ns: f64) -> f64 {
+    ns / 1_000_000.0
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<16} {:<12} {:>10} {:>10} {:>10} {:>10} {:>9} {:>8}",
+            "input",
+            "analyzer",
+            "min ms",
+            "median ms",
+            "mean ms",
+            "stddev ms",
+            "speedup",
+            "p-value"
+        )?;
+        let mut baseline: Option<&BenchResult> = None;
+        for r in &self.results {
+            if baseline.is_none_or(|b| b.input != r.input) {
+                baseline = Some(r);
+            }
+            let base = baseline.map_or(1.0, |b| b.summary.median_ns as f64);
+            let p_value = match baseline {
+                Some(b) if b.analyzer != r.analyzer => MannWhitney::test(&b.samples, &r.samples)
+                    .map_or(String::from("-"), |t| format!("{:.4}", t.p_value)),
+                _ => String::from("-"),
+            };
+            let s = &r.summary;
+            writeln!(
+                f,
+                "{:<16} {:<12} {:>10.3} {:>10.3} {:>10.3} {:>10.3} {:>8.2}x {:>8}",
+                r.input,
+                r.analyzer,
+                ms(s.min_ns as f64),
+                ms(s.median_ns as f64),
+                ms(s.mean_ns),
+                ms(s.stddev_ns),
+                base / (s.median_ns as f64).max(1.0),
+                p_value,
+            )?;
+        }
+        Ok(())
+    }
+}
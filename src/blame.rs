@@ -0,0 +1,177 @@
+//! Per-author stats of the documentation in a git repository (`td5 authors`,
+//! the `git` feature).
+//!
+//! The directory walker lists the repository's files; those with a
+//! documentation extension are put through `git blame`, which attributes
+//! each line to the author of the commit that last changed it. The lines of
+//! each author, from every file, are then analyzed as one text. Authors are
+//! told apart by name, as `git shortlog` does. Like `td5::fetch`, this runs
+//! the system `git` rather than linking a git library; files git does not
+//! track are skipped.
+
+use crate::TextStats;
+use crate::error::{Result, Td5Error};
+use crate::json::Json;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Extensions read when none are given.
+pub const DOC_EXTENSIONS: &[&str] = &["md", "markdown", "txt", "rst", "adoc"];
+
+/// One line of a file with who last changed it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlameLine<'a> {
+    pub author: &'a str,
+    pub email: &'a str,
+    pub text: &'a str,
+}
+
+/// The lines of `git blame --line-porcelain` output, in file order.
+///
+/// ```
+/// use td5::blame::parse_porcelain;
+///
+/// let output = "\
+/// 1f2e3d 1 1 1\nauthor Ada\nauthor-mail <ada@example.org>\nsummary First\n\tHello there\n\
+/// 4c5b6a 2 2 1\nauthor Grace\nauthor-mail <grace@example.org>\nsummary Second\n\tGeneral Kenobi\n";
+/// let lines = parse_porcelain(output);
+/// assert_eq!(lines.len(), 2);
+/// assert_eq!((lines[0].author, lines[0].email, lines[0].text), ("Ada", "ada@example.org", "Hello there"));
+/// assert_eq!(lines[1].author, "Grace");
+/// ```
+pub fn parse_porcelain(output: &str) -> Vec<BlameLine<'_>> {
+    let (mut author, mut email) = ("", "");
+    let mut lines = Vec::new();
+    for line in output.lines() {
+        if let Some(text) = line.strip_prefix('\t') {
+            lines.push(BlameLine {
+                author,
+                email,
+                text,
+            });
+        } else if let Some(name) = line.strip_prefix("author ") {
+            author = name;
+        } else if let Some(mail) = line.strip_prefix("author-mail ") {
+            email = mail.trim_start_matches('<').trim_end_matches('>');
+        }
+    }
+    lines
+}
+
+/// `git blame --line-porcelain` of `file`, relative to `repo`.
+pub fn blame(repo: &Path, file: &Path) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["blame", "--line-porcelain", "--"])
+        .arg(file)
+        .output()
+        .map_err(|e| Td5Error::config(format!("cannot run git: {e}")))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Td5Error::config(format!(
+            "git blame {}: {}",
+            file.display(),
+            stderr.trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[derive(Debug, Clone)]
+pub struct AuthorStats {
+    pub author: String,
+    /// The address of the author's first line seen.
+    pub email: String,
+    pub lines: usize,
+    /// Files with at least one of the author's lines.
+    pub files: usize,
+    pub stats: TextStats,
+}
+
+impl AuthorStats {
+    /// The stats' JSON document plus `author`, `email`, `lines` and `files`.
+    pub fn to_json_value(&self) -> Json {
+        let mut doc = self.stats.to_json_value();
+        doc.set("author", self.author.as_str().into());
+        doc.set("email", self.email.as_str().into());
+        doc.set("lines", self.lines.into());
+        doc.set("files", self.files.into());
+        doc
+    }
+}
+
+/// What `authors` read: the stats, most words first, and the files skipped
+/// with the reason, usually that git does not track them.
+#[derive(Debug, Default)]
+pub struct Authorship {
+    pub authors: Vec<AuthorStats>,
+    pub files: usize,
+    pub skipped: Vec<(PathBuf, Td5Error)>,
+}
+
+#[derive(Default)]
+struct Lines {
+    email: String,
+    text: String,
+    lines: usize,
+    files: usize,
+}
+
+/// Per-author stats of the files under `repo` whose extension is in
+/// `extensions` (without the dot, compared case-insensitively).
+pub fn authors(repo: &Path, extensions: &[&str]) -> Result<Authorship> {
+    let documented = |path: &Path| {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| extensions.iter().any(|x| x.eq_ignore_ascii_case(e)))
+    };
+    let mut authorship = Authorship::default();
+    let mut by_author: HashMap<String, Lines> = HashMap::new();
+    for path in crate::input::files(repo)? {
+        if !documented(&path) {
+            continue;
+        }
+        let relative = path.strip_prefix(repo).unwrap_or(&path);
+        let output = match blame(repo, relative) {
+            Ok(output) => output,
+            Err(e) => {
+                authorship.skipped.push((path, e));
+                continue;
+            }
+        };
+        authorship.files += 1;
+        let mut seen: Vec<&str> = Vec::new();
+        for line in parse_porcelain(&output) {
+            let entry = by_author.entry(line.author.to_string()).or_default();
+            if entry.lines == 0 {
+                entry.email = line.email.to_string();
+            }
+            if !seen.contains(&line.author) {
+                seen.push(line.author);
+                entry.files += 1;
+            }
+            entry.text.push_str(line.text);
+            entry.text.push('\n');
+            entry.lines += 1;
+        }
+    }
+    authorship.authors = by_author
+        .into_iter()
+        .map(|(author, lines)| AuthorStats {
+            author,
+            email: lines.email,
+            lines: lines.lines,
+            files: lines.files,
+            stats: crate::analyze(&lines.text),
+        })
+        .collect();
+    authorship.authors.sort_by(|a, b| {
+        b.stats
+            .total_words
+            .cmp(&a.stats.total_words)
+            .then_with(|| a.author.cmp(&b.author))
+    });
+    Ok(authorship)
+}
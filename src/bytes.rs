@@ -0,0 +1,38 @@
+//! Entry points for raw bytes that may not be valid UTF-8 (logs, mixed dumps).
+
+use crate::{AnalyzerConfig, TextStats, analyze_text_fast_with};
+use std::borrow::Cow;
+
+/// Decodes `bytes`, replacing each invalid sequence with U+FFFD.
+///
+/// Borrows when the input is already valid. Returns the text and the number
+/// of sequences replaced. U+FFFD is not alphabetic, so a replaced sequence
+/// never becomes part of a word.
+pub fn decode_lossy(bytes: &[u8]) -> (Cow<'_, str>, usize) {
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return (Cow::Borrowed(text), 0);
+    }
+    let mut text = String::with_capacity(bytes.len());
+    let mut invalid = 0;
+    for chunk in bytes.utf8_chunks() {
+        text.push_str(chunk.valid());
+        if !chunk.invalid().is_empty() {
+            text.push(char::REPLACEMENT_CHARACTER);
+            invalid += 1;
+        }
+    }
+    (Cow::Owned(text), invalid)
+}
+
+/// Analyzes bytes without a separate validation pass; invalid sequences are
+/// reported in `Diagnostics::invalid_utf8`.
+pub fn analyze_bytes(bytes: &[u8]) -> TextStats {
+    analyze_bytes_with(bytes, &AnalyzerConfig::default())
+}
+
+pub fn analyze_bytes_with(bytes: &[u8], config: &AnalyzerConfig) -> TextStats {
+    let (text, invalid) = decode_lossy(bytes);
+    let mut stats = analyze_text_fast_with(&text, config);
+    stats.diagnostics.invalid_utf8 = invalid;
+    stats
+}
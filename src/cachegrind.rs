@@ -0,0 +1,189 @@
+//! Instruction counts and simulated cache misses per analyzer
+//! (`td5 bench --cachegrind`), in the spirit of iai-callgrind: numbers that
+//! do not move with the load on the machine, so a CI runner can compare them
+//! run to run where wall-clock timings are noise.
+//!
+//! Each cell runs `td5 probe` under `valgrind --tool=cachegrind` twice: once
+//! analyzing the input and once doing everything but the analysis (start-up,
+//! reading the file, building the registry). The second run's costs are
+//! subtracted from the first, leaving the analyzer's own.
+
+use crate::bench::BenchInput;
+use crate::error::{Result, Td5Error};
+use crate::input::TempFile;
+use crate::json::Json;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Cachegrind's counters for one run, summed over all threads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Costs {
+    /// Instructions executed (`Ir`).
+    pub instructions: u64,
+    /// Data reads and writes (`Dr` + `Dw`).
+    pub data_accesses: u64,
+    /// First-level misses, instruction and data (`I1mr` + `D1mr` + `D1mw`).
+    pub l1_misses: u64,
+    /// Last-level misses, instruction and data (`ILmr` + `DLmr` + `DLmw`).
+    pub ll_misses: u64,
+}
+
+impl Costs {
+    /// The `events:` and `summary:` lines of a cachegrind output file.
+    pub fn parse(output: &str) -> Result<Costs> {
+        let line = |prefix: &str| {
+            output
+                .lines()
+                .find_map(|l| l.strip_prefix(prefix))
+                .map(|rest| rest.split_whitespace().collect::<Vec<_>>())
+        };
+        let (Some(events), Some(summary)) = (line("events:"), line("summary:")) else {
+            return Err(Td5Error::config(
+                "cachegrind output without events or summary line",
+            ));
+        };
+        let count = |names: &[&str]| -> Result<u64> {
+            let mut total = 0;
+            for name in names {
+                let Some(i) = events.iter().position(|e| e == name) else {
+                    continue;
+                };
+                let value = summary.get(i).and_then(|v| v.parse::<u64>().ok());
+                total += value.ok_or_else(|| {
+                    Td5Error::config(format!("cachegrind summary has no number for {name}"))
+                })?;
+            }
+            Ok(total)
+        };
+        if !events.contains(&"Ir") {
+            return Err(Td5Error::config("cachegrind output without Ir events"));
+        }
+        Ok(Costs {
+            instructions: count(&["Ir"])?,
+            data_accesses: count(&["Dr", "Dw"])?,
+            l1_misses: count(&["I1mr", "D1mr", "D1mw"])?,
+            ll_misses: count(&["ILmr", "DLmr", "DLmw"])?,
+        })
+    }
+
+    /// What `self` costs beyond `baseline`.
+    pub fn minus(self, baseline: Costs) -> Costs {
+        Costs {
+            instructions: self.instructions.saturating_sub(baseline.instructions),
+            data_accesses: self.data_accesses.saturating_sub(baseline.data_accesses),
+            l1_misses: self.l1_misses.saturating_sub(baseline.l1_misses),
+            ll_misses: self.ll_misses.saturating_sub(baseline.ll_misses),
+        }
+    }
+
+    pub fn to_json_value(&self) -> Json {
+        Json::Object(vec![
+            ("instructions".into(), self.instructions.into()),
+            ("data_accesses".into(), self.data_accesses.into()),
+            ("l1_misses".into(), self.l1_misses.into()),
+            ("ll_misses".into(), self.ll_misses.into()),
+        ])
+    }
+}
+
+/// One analyzer on one input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheResult {
+    pub analyzer: String,
+    pub input: String,
+    pub costs: Costs,
+}
+
+/// Runs `exe probe` under cachegrind for every analyzer on every input.
+pub fn run(exe: &Path, analyzers: &[String], inputs: &[BenchInput]) -> Result<Vec<CacheResult>> {
+    let file = TempFile::new("cachegrind");
+    let out = TempFile::new("cachegrind-out");
+    let mut results = Vec::with_capacity(analyzers.len() * inputs.len());
+    for input in inputs {
+        std::fs::write(file.path(), &input.text).map_err(|source| Td5Error::Io {
+            path: Some(file.path().to_path_buf()),
+            source,
+        })?;
+        for analyzer in analyzers {
+            let baseline = measure(exe, analyzer, file.path(), out.path(), true)?;
+            let total = measure(exe, analyzer, file.path(), out.path(), false)?;
+            results.push(CacheResult {
+                analyzer: analyzer.clone(),
+                input: input.name.clone(),
+                costs: total.minus(baseline),
+            });
+        }
+    }
+    Ok(results)
+}
+
+fn measure(exe: &Path, analyzer: &str, input: &Path, out: &Path, skip: bool) -> Result<Costs> {
+    let mut command = Command::new("valgrind");
+    command
+        .args(["--tool=cachegrind", "--cache-sim=yes", "--quiet"])
+        .arg(format!("--cachegrind-out-file={}", out.display()))
+        .arg(exe)
+        .args(["probe", "--analyzer", analyzer]);
+    if skip {
+        command.arg("--skip-analysis");
+    }
+    let output = command
+        .arg(input)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| Td5Error::config(format!("cannot run valgrind: {e}")))?;
+    if !output.status.success() {
+        return Err(Td5Error::config(format!(
+            "valgrind failed ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    let text = std::fs::read_to_string(out).map_err(|source| Td5Error::Io {
+        path: Some(out.to_path_buf()),
+        source,
+    })?;
+    Costs::parse(&text)
+}
+
+pub fn to_csv(results: &[CacheResult]) -> String {
+    let field = |s: &str| {
+        if s.contains([',', '"', '\n']) {
+            format!("\"{}\"", s.replace('"', "\"\""))
+        } else {
+            s.to_string()
+        }
+    };
+    let mut out = String::from("input,analyzer,instructions,data_accesses,l1_misses,ll_misses\n");
+    for r in results {
+        out.push_str(&format!(
+            "{},{},{},{},{},{}\n",
+            field(&r.input),
+            field(&r.analyzer),
+            r.costs.instructions,
+            r.costs.data_accesses,
+            r.costs.l1_misses,
+            r.costs.ll_misses
+        ));
+    }
+    out
+}
+
+pub fn to_json_value(results: &[CacheResult]) -> Json {
+    Json::Array(
+        results
+            .iter()
+            .map(|r| {
+                let mut fields = vec![
+                    ("input".into(), r.input.as_str().into()),
+                    ("analyzer".into(), r.analyzer.as_str().into()),
+                ];
+                if let Json::Object(costs) = r.costs.to_json_value() {
+                    fields.extend(costs);
+                }
+                Json::Object(fields)
+            })
+            .collect(),
+    )
+}
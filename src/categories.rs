@@ -0,0 +1,178 @@
+//! Probable nouns, verbs and adjectives, guessed from English suffixes with
+//! no dictionary or model, for a summary of a text's content words.
+//!
+//! A word is a noun if it ends like `information`, `movement`, `darkness`,
+//! `community` or `friendship` (or their plurals), a verb like `optimize`, `simplify`,
+//! `running` or `walked`, an adjective like `readable`, `careful`,
+//! `careless`, `famous` or `active`. The rest, function words included, is
+//! left out. The suffix must follow at least three letters, so `thing`,
+//! `red` and `five` are not taken. The guesses are rough: `speed` looks
+//! like a verb and `hostess` is not seen as a noun.
+
+use crate::WordFrequency;
+use crate::json::Json;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    Noun,
+    Verb,
+    Adjective,
+}
+
+impl Category {
+    pub const ALL: [Category; 3] = [Category::Noun, Category::Verb, Category::Adjective];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Category::Noun => "nouns",
+            Category::Verb => "verbs",
+            Category::Adjective => "adjectives",
+        }
+    }
+}
+
+/// Suffixes in the order they are tried.
+const SUFFIXES: [(&str, Category); 22] = [
+    ("tion", Category::Noun),
+    ("sion", Category::Noun),
+    ("ment", Category::Noun),
+    ("ness", Category::Noun),
+    ("ity", Category::Noun),
+    ("ship", Category::Noun),
+    ("hood", Category::Noun),
+    ("ism", Category::Noun),
+    ("ance", Category::Noun),
+    ("ence", Category::Noun),
+    ("ize", Category::Verb),
+    ("ise", Category::Verb),
+    ("ify", Category::Verb),
+    ("ing", Category::Verb),
+    ("ed", Category::Verb),
+    ("able", Category::Adjective),
+    ("ible", Category::Adjective),
+    ("ful", Category::Adjective),
+    ("less", Category::Adjective),
+    ("ous", Category::Adjective),
+    ("ive", Category::Adjective),
+    ("ical", Category::Adjective),
+];
+
+/// Common words the suffixes get wrong.
+const EXCEPTIONS: [&str; 13] = [
+    "during",
+    "nothing",
+    "something",
+    "anything",
+    "everything",
+    "morning",
+    "evening",
+    "ceiling",
+    "hundred",
+    "indeed",
+    "exceed",
+    "succeed",
+    "otherwise",
+];
+
+/// The category `word`, lowercase, probably belongs to.
+///
+/// ```
+/// use td5::categories::{Category, category};
+///
+/// assert_eq!(category("optimization"), Some(Category::Noun));
+/// assert_eq!(category("optimizations"), Some(Category::Noun));
+/// assert_eq!(category("optimize"), Some(Category::Verb));
+/// assert_eq!(category("optimized"), Some(Category::Verb));
+/// assert_eq!(category("readable"), Some(Category::Adjective));
+/// assert_eq!(category("thing"), None);
+/// assert_eq!(category("the"), None);
+/// ```
+pub fn category(word: &str) -> Option<Category> {
+    if EXCEPTIONS.contains(&word) {
+        return None;
+    }
+    let ends = |word: &str| {
+        SUFFIXES.iter().find_map(|&(suffix, category)| {
+            let stem = word.strip_suffix(suffix)?;
+            (stem.chars().count() >= 3 && stem.chars().all(char::is_alphabetic)).then_some(category)
+        })
+    };
+    ends(word).or_else(|| {
+        // Plural nouns: `movements`, `optimizations`.
+        let singular = word.strip_suffix('s')?;
+        ends(singular).filter(|&category| category == Category::Noun)
+    })
+}
+
+/// The most frequent words of each category.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Categories {
+    pub nouns: Vec<(String, usize)>,
+    pub verbs: Vec<(String, usize)>,
+    pub adjectives: Vec<(String, usize)>,
+}
+
+impl Categories {
+    /// The `top` most frequent words of `freq` in each category, ties
+    /// broken alphabetically.
+    ///
+    /// ```
+    /// use td5::WordFrequency;
+    /// use td5::categories::Categories;
+    ///
+    /// let freq = WordFrequency::from_text(
+    ///     "The careful optimization of the loop kept running; optimization is \
+    ///      a careful process, and running it walked us through its movement.",
+    /// );
+    /// let top = Categories::of(&freq, 2);
+    /// assert_eq!(top.nouns, [("optimization".to_string(), 2), ("movement".to_string(), 1)]);
+    /// assert_eq!(top.verbs, [("running".to_string(), 2), ("walked".to_string(), 1)]);
+    /// assert_eq!(top.adjectives, [("careful".to_string(), 2)]);
+    /// ```
+    pub fn of(freq: &WordFrequency, top: usize) -> Categories {
+        let mut categories = Categories::default();
+        for (word, count) in freq.by_count() {
+            let Some(category) = category(word) else {
+                continue;
+            };
+            let list = categories.list_mut(category);
+            if list.len() < top {
+                list.push((word.to_string(), count));
+            }
+        }
+        categories
+    }
+
+    pub fn list(&self, category: Category) -> &[(String, usize)] {
+        match category {
+            Category::Noun => &self.nouns,
+            Category::Verb => &self.verbs,
+            Category::Adjective => &self.adjectives,
+        }
+    }
+
+    fn list_mut(&mut self, category: Category) -> &mut Vec<(String, usize)> {
+        match category {
+            Category::Noun => &mut self.nouns,
+            Category::Verb => &mut self.verbs,
+            Category::Adjective => &mut self.adjectives,
+        }
+    }
+
+    /// `{"nouns": [[word, count], ...], "verbs": ..., "adjectives": ...}`.
+    pub fn to_json_value(&self) -> Json {
+        Json::Object(
+            Category::ALL
+                .iter()
+                .map(|&category| {
+                    let words = self
+                        .list(category)
+                        .iter()
+                        .map(|(w, c)| Json::Array(vec![w.as_str().into(), (*c).into()]))
+                        .collect();
+                    (category.name().to_string(), Json::Array(words))
+                })
+                .collect(),
+        )
+    }
+}
@@ -0,0 +1,94 @@
+//! CBOR (RFC 8949) encoding of `TextStats` and frequency tables (`cbor`
+//! feature), for pipelines where JSON's size and parsing cost matter.
+//!
+//! The documents are the JSON ones (`TextStats::to_json_value`,
+//! `WordFrequency::to_json_value`) in CBOR's types: whole numbers become
+//! integers in the shortest head, others float 64, and objects maps with
+//! text keys in the same order. Items are self-delimiting, so several can be
+//! written back to back (a CBOR sequence, RFC 8742).
+
+use crate::json::Json;
+use crate::{TextStats, WordFrequency};
+
+const UNSIGNED: u8 = 0;
+const NEGATIVE: u8 = 1;
+const TEXT: u8 = 3;
+const ARRAY: u8 = 4;
+const MAP: u8 = 5;
+
+/// The initial byte of major type `major` with argument `n`, and the
+/// argument's following bytes if it needs any.
+fn head(out: &mut Vec<u8>, major: u8, n: u64) {
+    let major = major << 5;
+    match n {
+        0..=23 => out.push(major | n as u8),
+        24..=0xff => out.extend([major | 24, n as u8]),
+        0x100..=0xffff => {
+            out.push(major | 25);
+            out.extend((n as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(major | 26);
+            out.extend((n as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(major | 27);
+            out.extend(n.to_be_bytes());
+        }
+    }
+}
+
+fn text(out: &mut Vec<u8>, s: &str) {
+    head(out, TEXT, s.len() as u64);
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn write(out: &mut Vec<u8>, value: &Json) {
+    match value {
+        Json::Bool(false) => out.push(0xf4),
+        Json::Bool(true) => out.push(0xf5),
+        Json::Null => out.push(0xf6),
+        Json::Number(n) => match value.as_i64() {
+            Some(i) if i >= 0 => head(out, UNSIGNED, i as u64),
+            // -1 - k is encoded as k.
+            Some(i) => head(out, NEGATIVE, !(i as u64)),
+            None => {
+                out.push(0xfb);
+                out.extend(n.to_be_bytes());
+            }
+        },
+        Json::String(s) => text(out, s),
+        Json::Array(items) => {
+            head(out, ARRAY, items.len() as u64);
+            for item in items {
+                write(out, item);
+            }
+        }
+        Json::Object(fields) => {
+            head(out, MAP, fields.len() as u64);
+            for (key, value) in fields {
+                text(out, key);
+                write(out, value);
+            }
+        }
+    }
+}
+
+pub fn encode(value: &Json) -> Vec<u8> {
+    let mut out = Vec::new();
+    write(&mut out, value);
+    out
+}
+
+impl TextStats {
+    pub fn to_cbor(&self) -> Vec<u8> {
+        encode(&self.to_json_value())
+    }
+}
+
+impl WordFrequency {
+    /// The words seen at least `min_count` times as a word → count map.
+    pub fn to_cbor(&self, min_count: usize) -> Vec<u8> {
+        encode(&self.to_json_value(min_count))
+    }
+}
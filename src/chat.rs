@@ -0,0 +1,286 @@
+//! Chat logs (`td5 analyze --chat`): the messages without their senders for
+//! the global report, and word counts, top words and message lengths per
+//! speaker.
+//!
+//! Two kinds of input are read. Text logs have one `name: message` line per
+//! message, optionally after a timestamp as WhatsApp exports write them
+//! (`12/31/20, 9:41 PM - Ada: ...` or `[31/12/2020, 21:41:05] Ada: ...`);
+//! a line without a speaker continues the message before it, and a
+//! timestamped line without one (`Messages are end-to-end encrypted`) is
+//! left out. JSON exports are an array of message objects, or an object with
+//! one under `messages`: Slack's (`user_profile.real_name` or `user`, then
+//! `text`), Telegram's (`from`, `text`) and those of most WhatsApp export
+//! tools (`sender`, `author` or `name`, then `message`, `content` or `body`).
+//! Slack's join and leave notices are left out.
+
+use crate::TextStats;
+use crate::error::{Result, Td5Error};
+use crate::json::{self, Json};
+use std::collections::HashMap;
+
+/// Longest name taken as a speaker, in characters: `Note: ...` starts a
+/// message, a sentence with a colon in it does not.
+pub const MAX_NAME: usize = 40;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub speaker: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Chat {
+    /// In log order.
+    pub messages: Vec<Message>,
+}
+
+/// Words per message of one speaker.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageLengths {
+    pub mean: f64,
+    pub median: usize,
+    pub max: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct SpeakerStats {
+    pub speaker: String,
+    pub messages: usize,
+    pub lengths: MessageLengths,
+    /// Of the speaker's messages put together.
+    pub stats: TextStats,
+}
+
+impl Chat {
+    /// A JSON export when `source` starts with an object or an array of
+    /// them, a text log otherwise (`[` can start a WhatsApp timestamp).
+    pub fn parse(source: &str) -> Result<Chat> {
+        let trimmed = source.trim_start_matches('\u{feff}').trim_start();
+        let array = trimmed
+            .strip_prefix('[')
+            .is_some_and(|rest| rest.trim_start().starts_with(['{', ']']));
+        if array || trimmed.starts_with('{') {
+            Chat::parse_json(trimmed)
+        } else {
+            Ok(Chat::parse_text(source))
+        }
+    }
+
+    /// A `name: message` log.
+    ///
+    /// ```
+    /// use td5::chat::Chat;
+    ///
+    /// let log = "12/31/20, 9:40 PM - Messages are end-to-end encrypted.\n\
+    ///            12/31/20, 9:41 PM - Ada: Happy new year\n\
+    ///            and good luck\n\
+    ///            Grace: see https://example.org: fireworks";
+    /// let chat = Chat::parse_text(log);
+    /// assert_eq!(chat.messages.len(), 2);
+    /// assert_eq!(chat.messages[0].text, "Happy new year\nand good luck");
+    /// assert_eq!(chat.messages[1].speaker, "Grace");
+    /// ```
+    pub fn parse_text(source: &str) -> Chat {
+        let mut messages: Vec<Message> = Vec::new();
+        for line in source.lines() {
+            let line = line.trim_end_matches('\r');
+            let (stamped, rest) = match timestamp(line) {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+            match speaker(rest) {
+                Some((speaker, text)) => messages.push(Message {
+                    speaker: speaker.to_string(),
+                    text: text.to_string(),
+                }),
+                None if stamped => {}
+                None => {
+                    if let Some(last) = messages.last_mut() {
+                        last.text.push('\n');
+                        last.text.push_str(line);
+                    }
+                }
+            }
+        }
+        Chat { messages }
+    }
+
+    /// A JSON export.
+    ///
+    /// ```
+    /// use td5::chat::Chat;
+    ///
+    /// let slack = r#"[
+    ///     {"type": "message", "subtype": "channel_join", "user": "U1", "text": "<@U1> has joined the channel"},
+    ///     {"type": "message", "user": "U1", "user_profile": {"real_name": "Ada"}, "text": "Hello"},
+    ///     {"type": "message", "user": "U2", "text": "Hi Ada"}
+    /// ]"#;
+    /// let chat = Chat::parse_json(slack).unwrap();
+    /// let speakers: Vec<&str> = chat.messages.iter().map(|m| m.speaker.as_str()).collect();
+    /// assert_eq!(speakers, ["Ada", "U2"]);
+    /// ```
+    pub fn parse_json(source: &str) -> Result<Chat> {
+        let doc = json::parse(source).map_err(|e| Td5Error::config(e.to_string()))?;
+        let items = match doc.get("messages").unwrap_or(&doc).as_array() {
+            Some(items) => items,
+            None => {
+                return Err(Td5Error::config(
+                    "expected an array of messages, or one under `messages`",
+                ));
+            }
+        };
+        let messages = items
+            .iter()
+            .filter(|item| {
+                let subtype = item.get("subtype").and_then(Json::as_str);
+                !subtype.is_some_and(|s| s.ends_with("_join") || s.ends_with("_leave"))
+            })
+            .filter_map(|item| {
+                let speaker = json_speaker(item)?;
+                let text = ["text", "message", "content", "body"]
+                    .iter()
+                    .find_map(|key| item.get(key))
+                    .map(json_text)?;
+                Some(Message {
+                    speaker: speaker.to_string(),
+                    text,
+                })
+            })
+            .collect();
+        Ok(Chat { messages })
+    }
+
+    /// Every message, one per line, for the global report.
+    pub fn text(&self) -> String {
+        let texts: Vec<&str> = self.messages.iter().map(|m| m.text.as_str()).collect();
+        texts.join("\n")
+    }
+
+    /// Stats per speaker, most words first.
+    ///
+    /// ```
+    /// let chat = td5::chat::Chat::parse_text("Ada: one two three\nGrace: four\nAda: five");
+    /// let speakers = chat.speakers();
+    /// assert_eq!(speakers[0].speaker, "Ada");
+    /// assert_eq!((speakers[0].messages, speakers[0].stats.total_words), (2, 4));
+    /// assert_eq!((speakers[0].lengths.median, speakers[0].lengths.max), (3, 3));
+    /// assert_eq!(speakers[0].lengths.mean, 2.0);
+    /// ```
+    pub fn speakers(&self) -> Vec<SpeakerStats> {
+        let mut by_speaker: HashMap<&str, Vec<&str>> = HashMap::new();
+        for message in &self.messages {
+            by_speaker
+                .entry(&message.speaker)
+                .or_default()
+                .push(&message.text);
+        }
+        let mut speakers: Vec<SpeakerStats> = by_speaker
+            .into_iter()
+            .map(|(speaker, texts)| {
+                let mut lengths: Vec<usize> = texts.iter().map(|t| words(t)).collect();
+                lengths.sort_unstable();
+                SpeakerStats {
+                    speaker: speaker.to_string(),
+                    messages: texts.len(),
+                    lengths: MessageLengths {
+                        mean: lengths.iter().sum::<usize>() as f64 / lengths.len() as f64,
+                        median: lengths[lengths.len() / 2],
+                        max: lengths[lengths.len() - 1],
+                    },
+                    stats: crate::analyze(&texts.join("\n")),
+                }
+            })
+            .collect();
+        speakers.sort_by(|a, b| {
+            b.stats
+                .total_words
+                .cmp(&a.stats.total_words)
+                .then_with(|| a.speaker.cmp(&b.speaker))
+        });
+        speakers
+    }
+}
+
+impl SpeakerStats {
+    /// The stats' JSON document plus `speaker`, `messages` and
+    /// `words_per_message`.
+    pub fn to_json_value(&self) -> Json {
+        let mut doc = self.stats.to_json_value();
+        doc.set("speaker", self.speaker.as_str().into());
+        doc.set("messages", self.messages.into());
+        doc.set(
+            "words_per_message",
+            Json::Object(vec![
+                ("mean".into(), self.lengths.mean.into()),
+                ("median".into(), self.lengths.median.into()),
+                ("max".into(), self.lengths.max.into()),
+            ]),
+        );
+        doc
+    }
+}
+
+/// Words of a message, counted as the subtitle speech rate counts them.
+fn words(text: &str) -> usize {
+    text.split_whitespace()
+        .filter(|t| t.chars().any(char::is_alphabetic))
+        .count()
+}
+
+/// `line` after a WhatsApp timestamp, if it starts with one.
+fn timestamp(line: &str) -> Option<&str> {
+    if let Some(rest) = line.strip_prefix('[') {
+        let (stamp, rest) = rest.split_once(']')?;
+        return stamp
+            .starts_with(|c: char| c.is_ascii_digit())
+            .then(|| rest.trim_start());
+    }
+    let (stamp, rest) = line.split_once(" - ")?;
+    let clock = |c: char| c.is_ascii_digit() || "/.,: APMapm\u{202f}".contains(c);
+    (stamp.starts_with(|c: char| c.is_ascii_digit()) && stamp.chars().all(clock)).then_some(rest)
+}
+
+/// The speaker and message of a `name: message` line.
+fn speaker(line: &str) -> Option<(&str, &str)> {
+    let (name, text) = match line.split_once(": ") {
+        Some(split) => split,
+        None => (line.strip_suffix(':')?, ""),
+    };
+    let name = name.trim();
+    let plausible = !name.is_empty()
+        && name.chars().count() <= MAX_NAME
+        && !name.contains("://")
+        && !line.starts_with(char::is_whitespace);
+    plausible.then_some((name, text))
+}
+
+fn json_speaker(item: &Json) -> Option<&str> {
+    let real_name = item
+        .get("user_profile")
+        .and_then(|p| p.get("real_name"))
+        .and_then(Json::as_str);
+    real_name
+        .into_iter()
+        .chain(
+            ["user_name", "sender", "author", "from", "name", "user"]
+                .iter()
+                .filter_map(|key| item.get(key).and_then(Json::as_str)),
+        )
+        .find(|name| !name.is_empty())
+}
+
+/// A message's text: a string, or Telegram's array of strings and
+/// `{"type": ..., "text": ...}` entities.
+fn json_text(value: &Json) -> String {
+    match value {
+        Json::String(s) => s.clone(),
+        Json::Array(parts) => parts
+            .iter()
+            .map(|part| match part {
+                Json::String(s) => s.as_str(),
+                _ => part.get("text").and_then(Json::as_str).unwrap_or(""),
+            })
+            .collect(),
+        _ => String::new(),
+    }
+}
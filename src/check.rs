@@ -0,0 +1,187 @@
+//! Threshold checks on `TextStats`, e.g. `unique_words>=500` or
+//! `top1_share<=0.1`, for failing CI jobs on vocabulary constraints.
+
+use crate::TextStats;
+use crate::error::{Result, Td5Error};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    UniqueWords,
+    TotalWords,
+    Chars,
+    /// Share of all tokens taken by the most frequent word.
+    Top1Share,
+    /// Share of all tokens taken by the ten most frequent words.
+    Top10Share,
+    /// Unique words / total words.
+    TypeTokenRatio,
+    /// Length in characters of the longest word.
+    LongestWord,
+    LongTokens,
+    LongTokensSkipped,
+    NonAlphabetic,
+    InvalidUtf8,
+    Lines,
+    Sentences,
+    /// Mean length in characters of the words counted.
+    MeanWordLength,
+}
+
+const METRICS: &[(&str, Metric)] = &[
+    ("unique_words", Metric::UniqueWords),
+    ("total_words", Metric::TotalWords),
+    ("chars", Metric::Chars),
+    ("top1_share", Metric::Top1Share),
+    ("top10_share", Metric::Top10Share),
+    ("type_token_ratio", Metric::TypeTokenRatio),
+    ("longest_word", Metric::LongestWord),
+    ("long_tokens", Metric::LongTokens),
+    ("long_tokens_skipped", Metric::LongTokensSkipped),
+    ("non_alphabetic", Metric::NonAlphabetic),
+    ("invalid_utf8", Metric::InvalidUtf8),
+    ("lines", Metric::Lines),
+    ("sentences", Metric::Sentences),
+    ("mean_word_length", Metric::MeanWordLength),
+];
+
+impl Metric {
+    pub fn name(self) -> &'static str {
+        METRICS
+            .iter()
+            .find(|(_, m)| *m == self)
+            .map_or("?", |(n, _)| n)
+    }
+
+    pub fn names() -> impl Iterator<Item = &'static str> {
+        METRICS.iter().map(|(n, _)| *n)
+    }
+
+    pub fn value(self, stats: &TextStats) -> f64 {
+        let share = |count: usize| count as f64 / stats.total_words.max(1) as f64;
+        match self {
+            Metric::UniqueWords => stats.word_count as f64,
+            Metric::TotalWords => stats.total_words as f64,
+            Metric::Chars => stats.char_count as f64,
+            Metric::Top1Share => share(stats.top_words.first().map_or(0, |(_, c)| *c)),
+            Metric::Top10Share => share(stats.top_words.iter().map(|(_, c)| c).sum()),
+            Metric::TypeTokenRatio => share(stats.word_count),
+            Metric::LongestWord => stats
+                .longest_words
+                .iter()
+                .map(|w| w.chars().count())
+                .max()
+                .unwrap_or(0) as f64,
+            Metric::LongTokens => stats.diagnostics.long_tokens as f64,
+            Metric::LongTokensSkipped => stats.diagnostics.long_tokens_skipped as f64,
+            Metric::NonAlphabetic => stats.diagnostics.non_alphabetic as f64,
+            Metric::InvalidUtf8 => stats.diagnostics.invalid_utf8 as f64,
+            // NaN when unknown, so the assertion fails.
+            Metric::Lines => stats.line_count.map_or(f64::NAN, |n| n as f64),
+            Metric::Sentences => stats.sentence_count.map_or(f64::NAN, |n| n as f64),
+            Metric::MeanWordLength => stats.mean_word_length,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl Op {
+    // Two-character operators first so `<=` is not read as `<`.
+    const ALL: [(&'static str, Op); 6] = [
+        ("<=", Op::Le),
+        (">=", Op::Ge),
+        ("==", Op::Eq),
+        ("!=", Op::Ne),
+        ("<", Op::Lt),
+        (">", Op::Gt),
+    ];
+
+    fn holds(self, actual: f64, expected: f64) -> bool {
+        match self {
+            Op::Lt => actual < expected,
+            Op::Le => actual <= expected,
+            Op::Gt => actual > expected,
+            Op::Ge => actual >= expected,
+            Op::Eq => actual == expected,
+            Op::Ne => actual != expected,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        Op::ALL
+            .iter()
+            .find(|(_, op)| *op == self)
+            .map_or("?", |(s, _)| s)
+    }
+}
+
+/// `<metric><op><number>`, whitespace allowed around each part.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Assertion {
+    pub metric: Metric,
+    pub op: Op,
+    pub threshold: f64,
+}
+
+impl Assertion {
+    pub fn parse(expr: &str) -> Result<Assertion> {
+        let invalid = |why: String| Td5Error::config(format!("assertion `{expr}`: {why}"));
+        let (at, symbol, op) = Op::ALL
+            .iter()
+            .filter_map(|&(symbol, op)| expr.find(symbol).map(|at| (at, symbol, op)))
+            .min_by_key(|&(at, symbol, _)| (at, std::cmp::Reverse(symbol.len())))
+            .ok_or_else(|| invalid("expected one of < <= > >= == !=".into()))?;
+        let name = expr[..at].trim();
+        let metric = METRICS
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, m)| *m)
+            .ok_or_else(|| {
+                let known: Vec<_> = Metric::names().collect();
+                invalid(format!(
+                    "unknown metric `{name}` (known: {})",
+                    known.join(", ")
+                ))
+            })?;
+        let value = expr[at + symbol.len()..].trim();
+        let threshold = value
+            .parse()
+            .map_err(|_| invalid(format!("`{value}` is not a number")))?;
+        Ok(Assertion {
+            metric,
+            op,
+            threshold,
+        })
+    }
+
+    /// `Err` carries the actual value when the assertion does not hold.
+    pub fn check(&self, stats: &TextStats) -> std::result::Result<(), f64> {
+        let actual = self.metric.value(stats);
+        if self.op.holds(actual, self.threshold) {
+            Ok(())
+        } else {
+            Err(actual)
+        }
+    }
+}
+
+impl fmt::Display for Assertion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            self.metric.name(),
+            self.op.symbol(),
+            self.threshold
+        )
+    }
+}
@@ -0,0 +1,269 @@
+//! Checkpoints of a `td5 batch` run (`--resume FILE`), so that a corpus run
+//! of several hours picks up after the last input it finished instead of
+//! starting over.
+//!
+//! A checkpoint is JSON, rewritten after every input: the paths done so far,
+//! in manifest order, the stats or error of each, and the combined word
+//! counts, which are the only part of the total that cannot be rebuilt from
+//! the per-input stats. Stats use the `schema` documents of `--format json`.
+//! It is written to a temporary file and renamed over the previous one, so
+//! an interruption leaves either checkpoint whole.
+
+use crate::TextStats;
+use crate::encoding::{Encoding, Transcoding};
+use crate::error::{Result, Td5Error};
+use crate::freq::WordFrequency;
+use crate::json::{self, Json};
+use crate::schema::DecodeError;
+use std::path::{Path, PathBuf};
+
+/// Bumped when the checkpoint layout changes incompatibly.
+pub const CHECKPOINT_VERSION: u64 = 1;
+
+/// A batch run up to some input.
+///
+/// ```
+/// use td5::checkpoint::Checkpoint;
+/// use td5::encoding;
+/// use td5::freq::WordFrequency;
+///
+/// let mut checkpoint = Checkpoint::default();
+/// checkpoint.done.push("a.txt".into());
+/// checkpoint.items.push(("a".into(), td5::analyze("to be or not to be")));
+/// checkpoint.combined = WordFrequency::from_text("to be or not to be");
+/// let transcoding = encoding::decode(b"to be or not to b\xe9").transcoding();
+/// checkpoint.encodings.push(("a".into(), transcoding));
+/// checkpoint.times.push(("a".into(), 1_500));
+/// let saved = checkpoint.to_json();
+/// let loaded = Checkpoint::from_json(&saved).unwrap();
+/// assert_eq!(loaded.done, checkpoint.done);
+/// assert_eq!(loaded.items[0].1.top_words, checkpoint.items[0].1.top_words);
+/// assert_eq!(loaded.combined.count("be"), 2);
+/// assert_eq!(loaded.encodings, checkpoint.encodings);
+/// assert_eq!(loaded.times, checkpoint.times);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Checkpoint {
+    /// The path of each input done, analyzed or not, in manifest order.
+    pub done: Vec<PathBuf>,
+    /// The label and stats of each input analyzed.
+    pub items: Vec<(String, TextStats)>,
+    /// The label and error of each input that could not be analyzed.
+    pub errors: Vec<(String, String)>,
+    /// The word counts of every input analyzed.
+    pub combined: WordFrequency,
+    /// The label of each input analyzed, with how its file was decoded.
+    pub encodings: Vec<(String, Transcoding)>,
+    /// The label of each input analyzed, with how long its analysis took in
+    /// nanoseconds.
+    pub times: Vec<(String, u128)>,
+}
+
+impl Checkpoint {
+    /// The checkpoint saved at `path`, or `None` if there is none yet.
+    pub fn load(path: &Path) -> Result<Option<Checkpoint>> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(source) => {
+                return Err(Td5Error::Io {
+                    path: Some(path.to_path_buf()),
+                    source,
+                });
+            }
+        };
+        Checkpoint::from_json(&text)
+            .map(Some)
+            .map_err(|e| Td5Error::config(format!("{}: {e}", path.display())))
+    }
+
+    /// Replaces the checkpoint at `path`.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+        let temporary = PathBuf::from(temporary);
+        std::fs::write(&temporary, self.to_json())
+            .and_then(|()| std::fs::rename(&temporary, path))
+            .map_err(|source| Td5Error::Io {
+                path: Some(path.to_path_buf()),
+                source,
+            })
+    }
+
+    pub fn to_json(&self) -> String {
+        let done = self
+            .done
+            .iter()
+            .map(|path| path.to_string_lossy().as_ref().into())
+            .collect();
+        let items = self
+            .items
+            .iter()
+            .map(|(label, stats)| {
+                Json::Object(vec![
+                    ("label".into(), label.as_str().into()),
+                    ("stats".into(), stats.to_json_value()),
+                ])
+            })
+            .collect();
+        let errors = self
+            .errors
+            .iter()
+            .map(|(label, message)| {
+                Json::Object(vec![
+                    ("label".into(), label.as_str().into()),
+                    ("error".into(), message.as_str().into()),
+                ])
+            })
+            .collect();
+        let encodings = self
+            .encodings
+            .iter()
+            .map(|(label, transcoding)| {
+                Json::Object(vec![
+                    ("label".into(), label.as_str().into()),
+                    ("encoding".into(), transcoding.encoding.to_string().into()),
+                    ("had_bom".into(), transcoding.had_bom.into()),
+                    ("transcoded".into(), transcoding.transcoded.into()),
+                ])
+            })
+            .collect();
+        let times = self
+            .times
+            .iter()
+            .map(|(label, ns)| {
+                Json::Object(vec![
+                    ("label".into(), label.as_str().into()),
+                    ("time_ns".into(), (*ns).into()),
+                ])
+            })
+            .collect();
+        Json::Object(vec![
+            ("checkpoint_version".into(), CHECKPOINT_VERSION.into()),
+            ("done".into(), Json::Array(done)),
+            ("items".into(), Json::Array(items)),
+            ("errors".into(), Json::Array(errors)),
+            ("encodings".into(), Json::Array(encodings)),
+            ("times".into(), Json::Array(times)),
+            ("char_count".into(), self.combined.char_count().into()),
+            ("combined".into(), self.combined.to_json_value(1)),
+        ])
+        .to_string()
+    }
+
+    pub fn from_json(input: &str) -> std::result::Result<Self, DecodeError> {
+        let doc = json::parse(input)?;
+        if !matches!(doc, Json::Object(_)) {
+            return Err(DecodeError::NotAnObject);
+        }
+        if doc.get("checkpoint_version").and_then(Json::as_u64) != Some(CHECKPOINT_VERSION) {
+            return Err(DecodeError::InvalidField("checkpoint_version"));
+        }
+        let array = |name: &'static str| {
+            doc.get(name)
+                .and_then(Json::as_array)
+                .ok_or(DecodeError::InvalidField(name))
+        };
+        let string = |doc: &Json, name: &'static str, field: &'static str| {
+            doc.get(name)
+                .and_then(Json::as_str)
+                .map(str::to_string)
+                .ok_or(DecodeError::InvalidField(field))
+        };
+        let done = array("done")?
+            .iter()
+            .map(|path| {
+                path.as_str()
+                    .map(PathBuf::from)
+                    .ok_or(DecodeError::InvalidField("done"))
+            })
+            .collect::<std::result::Result<_, _>>()?;
+        let items = array("items")?
+            .iter()
+            .map(|item| {
+                let stats = item
+                    .get("stats")
+                    .ok_or(DecodeError::InvalidField("items"))?;
+                Ok((
+                    string(item, "label", "items")?,
+                    TextStats::from_json_value(stats.clone())?,
+                ))
+            })
+            .collect::<std::result::Result<_, DecodeError>>()?;
+        let errors = array("errors")?
+            .iter()
+            .map(|error| {
+                Ok((
+                    string(error, "label", "errors")?,
+                    string(error, "error", "errors")?,
+                ))
+            })
+            .collect::<std::result::Result<_, DecodeError>>()?;
+        // Absent from checkpoints saved before encodings were reported.
+        let encodings = doc
+            .get("encodings")
+            .and_then(Json::as_array)
+            .unwrap_or_default()
+            .iter()
+            .map(|item| {
+                let flag = |name| {
+                    item.get(name)
+                        .and_then(Json::as_bool)
+                        .ok_or(DecodeError::InvalidField("encodings"))
+                };
+                Ok((
+                    string(item, "label", "encodings")?,
+                    Transcoding {
+                        encoding: item
+                            .get("encoding")
+                            .and_then(Json::as_str)
+                            .and_then(Encoding::from_name)
+                            .ok_or(DecodeError::InvalidField("encodings"))?,
+                        had_bom: flag("had_bom")?,
+                        transcoded: flag("transcoded")?,
+                    },
+                ))
+            })
+            .collect::<std::result::Result<_, DecodeError>>()?;
+        // Absent from checkpoints saved before times were kept apart from
+        // the stats.
+        let times = doc
+            .get("times")
+            .and_then(Json::as_array)
+            .unwrap_or_default()
+            .iter()
+            .map(|item| {
+                Ok((
+                    string(item, "label", "times")?,
+                    item.get("time_ns")
+                        .and_then(Json::as_u64)
+                        .ok_or(DecodeError::InvalidField("times"))? as u128,
+                ))
+            })
+            .collect::<std::result::Result<_, DecodeError>>()?;
+        let Some(Json::Object(words)) = doc.get("combined") else {
+            return Err(DecodeError::InvalidField("combined"));
+        };
+        let words = words
+            .iter()
+            .map(|(word, count)| {
+                count
+                    .as_u64()
+                    .map(|count| (word.as_str(), count as usize))
+                    .ok_or(DecodeError::InvalidField("combined"))
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let char_count = doc
+            .get("char_count")
+            .and_then(Json::as_u64)
+            .ok_or(DecodeError::InvalidField("char_count"))? as usize;
+        Ok(Checkpoint {
+            done,
+            items,
+            errors,
+            combined: WordFrequency::from_parts(words, char_count),
+            encodings,
+            times,
+        })
+    }
+}
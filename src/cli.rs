@@ -0,0 +1,1873 @@
+//! Command table for the `td5` binary. Help text and shell completions are
+//! both generated from `COMMANDS`, so a new subcommand only needs an entry here.
+//! Help is translated through `i18n`; completion scripts stay in English.
+
+use crate::i18n::tr;
+use std::fmt::Write;
+
+pub struct Flag {
+    pub long: &'static str,
+    /// Placeholder shown in help when the flag takes a value.
+    pub value: Option<&'static str>,
+    pub help: &'static str,
+}
+
+pub enum Positional {
+    None,
+    Files,
+    Choices(&'static [&'static str]),
+    Command,
+}
+
+pub struct Command {
+    pub name: &'static str,
+    pub args: &'static str,
+    pub about: &'static str,
+    pub positional: Positional,
+    pub flags: &'static [Flag],
+    pub examples: &'static [&'static str],
+}
+
+pub const SHELLS: &[&str] = &["bash", "zsh", "fish"];
+
+/// A named bundle of `analyze` flags for a use case. `--preset` expands to
+/// them where it appears, so that flags after it win.
+pub struct Preset {
+    pub name: &'static str,
+    pub about: &'static str,
+    pub flags: &'static [&'static str],
+}
+
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        name: "seo",
+        about: "Web pages: keywords and key phrases without stopwords, links and hashtags apart, how evenly keywords spread",
+        flags: &[
+            "--stopwords",
+            "en",
+            "--ngrams",
+            "3",
+            "--social",
+            "--dispersion",
+            "10",
+        ],
+    },
+    Preset {
+        name: "prose",
+        about: "Books and essays: top words and word pairs without stopwords, dialogue and narration, how often words recur",
+        flags: &[
+            "--stopwords",
+            "en",
+            "--ngrams",
+            "2",
+            "--dialogue",
+            "--distribution",
+        ],
+    },
+    Preset {
+        name: "logs",
+        about: "Log files: numbers with units, IP and email addresses, all-caps levels and codes, one JSON document per file",
+        flags: &["--units", "--pii", "--acronyms", "--format", "json"],
+    },
+    Preset {
+        name: "code",
+        about: "Source code: keywords apart from identifiers, the language going by the file extension, and word pairs",
+        flags: &["--keywords", "auto", "--ngrams", "2"],
+    },
+];
+
+pub fn find_preset(name: &str) -> Result<&'static Preset, String> {
+    PRESETS.iter().find(|p| p.name == name).ok_or_else(|| {
+        let names: Vec<&str> = PRESETS.iter().map(|p| p.name).collect();
+        tr!(
+            "unknown preset `{name}` (expected {names})",
+            name = name,
+            names = names.join(", ")
+        )
+    })
+}
+
+/// Shared by `lint` and `serve`.
+const LINT_FLAGS: &[Flag] = &[
+    Flag {
+        long: "max-sentence",
+        value: Some("N"),
+        help: "Longest sentence allowed, in words (default 40)",
+    },
+    Flag {
+        long: "filler",
+        value: Some("WORD"),
+        help: "Filler word to watch, replacing the built-in list (repeatable)",
+    },
+    Flag {
+        long: "max-filler-share",
+        value: Some("RATIO"),
+        help: "Report a filler above this share of all words (default 0.01)",
+    },
+    Flag {
+        long: "phrases",
+        value: Some("FILE"),
+        help: "Phrase lists ([name] headers, one pattern per line) replacing the built-in ones",
+    },
+];
+
+pub const COMMANDS: &[Command] = &[
+    Command {
+        name: "demo",
+        args: "[FILE|-]",
+        about: "Analyze generated text, a file or stdin with the slow and fast analyzers (default)",
+        positional: Positional::Files,
+        flags: &[
+            Flag {
+                long: "words",
+                value: Some("N"),
+                help: "Words of generated text when no file is given (default 50000)",
+            },
+            Flag {
+                long: "seed",
+                value: Some("N"),
+                help: "Seed of the generated text (default 0)",
+            },
+            Flag {
+                long: "vocabulary",
+                value: Some("N"),
+                help: "Distinct words of the generated text, Zipf-distributed (default 10000)",
+            },
+            Flag {
+                long: "source",
+                value: Some("SPEC"),
+                help: "Generated text: prose (default), hot, a shape such as zipf or unicode, markov:FILE or file:FILE",
+            },
+            Flag {
+                long: "baseline",
+                value: Some("NAME"),
+                help: "Analyzer the others are compared to (default slow)",
+            },
+        ],
+        examples: &[
+            "td5",
+            "td5 demo --baseline fast --words 1000000",
+            "td5 demo --seed 7 --vocabulary 200",
+            "td5 demo --source markov:book.txt --words 1000000",
+            "td5 demo book.txt",
+            "cat book.txt | td5 demo -",
+        ],
+    },
+    Command {
+        name: "analyze",
+        args: "[FILE|DIR...]",
+        about: "Print word statistics for files or directories (or stdin)",
+        positional: Positional::Files,
+        flags: &[
+            Flag {
+                long: "format",
+                value: Some("FORMAT"),
+                help: "Output format: text (default), json (one document per line), yaml (a document stream), csv (one row per input), length-delimited protobuf, msgpack or cbor",
+            },
+            Flag {
+                long: "analyzer",
+                value: Some("NAME"),
+                help: "Analyzer to use (default fast)",
+            },
+            Flag {
+                long: "preset",
+                value: Some("NAME"),
+                help: "Start from the flags of a use case: seo, prose, logs or code (listed below); flags after it override them",
+            },
+            Flag {
+                long: "threads",
+                value: Some("N"),
+                help: "Worker threads of the parallel or sharded analyzer (0 for every core; implies --analyzer parallel)",
+            },
+            Flag {
+                long: "progress",
+                value: None,
+                help: "Show a progress bar on stderr while each input is counted (implies --analyzer parallel; only on a terminal)",
+            },
+            Flag {
+                long: "jobs",
+                value: Some("N"),
+                help: "Analyze N files at a time (0 for every core), still reporting them in order; plain text, gzip and EPUB only",
+            },
+            Flag {
+                long: "aggregate",
+                value: None,
+                help: "After the per-input reports, report all inputs together, their word counts merged",
+            },
+            Flag {
+                long: "ngrams",
+                value: Some("N"),
+                help: "Also list the most frequent word pairs (2) or pairs and triples (3)",
+            },
+            Flag {
+                long: "stopwords",
+                value: Some("LIST"),
+                help: "Leave the words of LIST out of the top words, still counting them: en, fr, or a file with one word per line (repeatable)",
+            },
+            Flag {
+                long: "stem",
+                value: Some("LANG"),
+                help: "Rank the top words by stem, with the most frequent form of each: en (needs the stemming feature)",
+            },
+            Flag {
+                long: "auto",
+                value: None,
+                help: "Detect each input's language and use its stopwords and stemmer (needs the language-detection feature)",
+            },
+            Flag {
+                long: "control",
+                value: Some("POLICY"),
+                help: "What becomes of control characters such as NUL: strip (default) drops them, separate cuts words at them, error fails the input",
+            },
+            Flag {
+                long: "tokens",
+                value: Some("SPEC"),
+                help: "What a word is: ascii (letter runs), unicode (whitespace-separated tokens) or regex:PATTERN, e.g. 'regex:#?\\w+' for hashtags apart",
+            },
+            Flag {
+                long: "normalize",
+                value: Some("FORM"),
+                help: "Put words in a Unicode normalization form before counting them: nfc (café is one word however its accent is written) or nfkc (also ligatures and full-width letters)",
+            },
+            Flag {
+                long: "fold-diacritics",
+                value: None,
+                help: "Drop accents and other diacritics before counting, so café, cafe and CAFÉ are one word",
+            },
+            Flag {
+                long: "time",
+                value: Some("POLICY"),
+                help: "What serialized output keeps of time_ns: exact (default), omit, or a width in milliseconds to round it to, for golden files",
+            },
+            Flag {
+                long: "assert",
+                value: Some("CHECK"),
+                help: "Exit with status 3 unless e.g. 'unique_words>=500' holds for every input (repeatable)",
+            },
+            Flag {
+                long: "template",
+                value: Some("FILE"),
+                help: "Render each input's stats through a Handlebars-style template instead",
+            },
+            Flag {
+                long: "window",
+                value: Some("SIZE"),
+                help: "Only count the last SIZE tokens ('10000 tokens') or, for timestamped lines, the last SIZE of time ('5m')",
+            },
+            Flag {
+                long: "split-on",
+                value: Some("SPLIT"),
+                help: "Also report each section, cut at every heading, chapter or regex:PATTERN line",
+            },
+            Flag {
+                long: "dictionary",
+                value: Some("WORDLIST"),
+                help: "Known words, one per line; logs how many words of each input it lacks",
+            },
+            Flag {
+                long: "only-known",
+                value: None,
+                help: "Count only words in the --dictionary",
+            },
+            Flag {
+                long: "pipeline",
+                value: Some("FILE"),
+                help: "Transform the counted words through the stages in FILE (default: pipeline.conf in the config directory, if any)",
+            },
+            Flag {
+                long: "merge-map",
+                value: Some("FILE"),
+                help: "Merge variant spellings into one word, from a file of variant<TAB>canonical lines",
+            },
+            Flag {
+                long: "on-error",
+                value: Some("POLICY"),
+                help: "When an input cannot be read: abort, continue (default; exit status 4 if any failed) or ignore (exit status 0 unless all failed)",
+            },
+            Flag {
+                long: "min-bytes",
+                value: Some("SIZE"),
+                help: "Skip files smaller than SIZE, such as 200 or 4K",
+            },
+            Flag {
+                long: "max-bytes",
+                value: Some("SIZE"),
+                help: "Skip files larger than SIZE, such as 50M",
+            },
+            Flag {
+                long: "min-words",
+                value: Some("N"),
+                help: "Leave out documents of fewer than N words",
+            },
+            Flag {
+                long: "keep-binary",
+                value: None,
+                help: "Analyze binary inputs too (skipped by default: a NUL byte or over 30% control bytes at the start)",
+            },
+            Flag {
+                long: "sample",
+                value: Some("SHARE"),
+                help: "Read only a deterministic share of each file, such as 10%, and scale the counts up as estimates",
+            },
+            Flag {
+                long: "seed",
+                value: Some("N"),
+                help: "With --sample, the seed picking the blocks read (default 0)",
+            },
+            Flag {
+                long: "range",
+                value: Some("START..END"),
+                help: "Analyze only the lines starting between these byte offsets of each file, such as 1GB..2GB",
+            },
+            Flag {
+                long: "lines",
+                value: Some("FIRST..LAST"),
+                help: "Analyze only these lines of each file, counted from 1, such as 1000..2000",
+            },
+            Flag {
+                long: "first-n-words",
+                value: Some("N"),
+                help: "Analyze only the first N words of each input, without reading the rest",
+            },
+            Flag {
+                long: "last-n-words",
+                value: Some("N"),
+                help: "Analyze only the last N words of each input, reading files backwards from the end",
+            },
+            Flag {
+                long: "links",
+                value: Some("FILE"),
+                help: "Also analyze the files and URLs listed in FILE, one per line",
+            },
+            Flag {
+                long: "max-download",
+                value: Some("SIZE"),
+                help: "Largest body accepted from a URL, in bytes or with a K, M or G suffix (default: 50M)",
+            },
+            Flag {
+                long: "content-types",
+                value: Some("LIST"),
+                help: "Media types accepted from a URL, comma-separated, `text/*` for a family (default: text/*, application/json, application/xml, application/xhtml+xml)",
+            },
+            Flag {
+                long: "max-redirects",
+                value: Some("N"),
+                help: "Redirects followed for a URL (default: 5)",
+            },
+            Flag {
+                long: "retries",
+                value: Some("N"),
+                help: "Retries of a URL after a connection failure, timeout, 429 or 5xx answer (default: 3)",
+            },
+            Flag {
+                long: "retry-backoff",
+                value: Some("MS"),
+                help: "Wait before the first retry, doubled on each one (default: 500)",
+            },
+            Flag {
+                long: "fetch-interval",
+                value: Some("MS"),
+                help: "Shortest time between two downloads (default: 0)",
+            },
+            Flag {
+                long: "fetch-timeout",
+                value: Some("SECONDS"),
+                help: "Longest a single download may take (default: 60)",
+            },
+            Flag {
+                long: "subtitles",
+                value: None,
+                help: "Read SRT/WebVTT subtitles and report the speech rate (automatic for .srt and .vtt files)",
+            },
+            Flag {
+                long: "chat",
+                value: None,
+                help: "Read a chat log (name: message lines, or a WhatsApp, Slack or Telegram JSON export) and also report stats per speaker",
+            },
+            Flag {
+                long: "social",
+                value: None,
+                help: "Count hashtags, @mentions and URLs separately instead of as words",
+            },
+            Flag {
+                long: "distribution",
+                value: None,
+                help: "Also report how many words occur once, 2-5 times, ... and count quantiles",
+            },
+            Flag {
+                long: "report",
+                value: Some("FILE"),
+                help: "Also write a self-contained HTML page with charts of the analysis (one input only)",
+            },
+            Flag {
+                long: "acronyms",
+                value: None,
+                help: "Also list acronyms and all-caps terms as written, with their counts",
+            },
+            Flag {
+                long: "keywords",
+                value: Some("LANG"),
+                help: "Also read the input as source code and count LANG's keywords (rust, python or c, or auto to go by each file's extension) apart from its identifiers",
+            },
+            Flag {
+                long: "units",
+                value: None,
+                help: "Also count numbers with units (15 ms, 3.2 GB, 99%) and summarize them per unit",
+            },
+            Flag {
+                long: "dialogue",
+                value: None,
+                help: "Also report the share of text inside quotation marks and the top words of dialogue and narration",
+            },
+            Flag {
+                long: "quotes",
+                value: Some("PAIRS"),
+                help: "With --dialogue, the quotation marks as opening and closing pairs (default '\"\",“”,«»')",
+            },
+            Flag {
+                long: "dispersion",
+                value: Some("N"),
+                help: "Also report how evenly the N most frequent words spread through the text, most bursty first",
+            },
+            Flag {
+                long: "categories",
+                value: Some("N"),
+                help: "Also list the N most frequent probable nouns, verbs and adjectives, guessed from English suffixes",
+            },
+            Flag {
+                long: "pii",
+                value: None,
+                help: "Also count email addresses, phone numbers and IP addresses",
+            },
+            Flag {
+                long: "pii-samples",
+                value: Some("N"),
+                help: "With --pii, show up to N redacted examples of each",
+            },
+            Flag {
+                long: "plugin",
+                value: Some("FILE"),
+                help: "Load a shared library with the td5 plugin ABI: its word filter drops words from the counts, its metrics join each report (repeatable; plugins feature)",
+            },
+            Flag {
+                long: "instrument",
+                value: None,
+                help: "Also report which specialized paths of the fast analyzer each input takes, how many tokens it counts without hashing, the time of each of its phases, and the read buffer sizes of stdin",
+            },
+        ],
+        examples: &[
+            "td5 analyze notes.txt",
+            "td5 analyze --format json notes.txt draft.txt | jq .word_count",
+            "cat notes.txt | td5 analyze --quiet",
+            "td5 analyze docs/*.md --assert 'unique_words>=500' --assert 'top1_share<=0.1'",
+            "td5 analyze --jobs 0 --aggregate 'corpus/**/*.txt'",
+            "td5 analyze --preset seo https://example.com/",
+            "td5 analyze --preset logs --format yaml service.log",
+            "td5 analyze --window 5m service.log",
+            "td5 analyze --split-on heading book.md",
+            "td5 analyze --dictionary /usr/share/dict/words --only-known scan.txt",
+            "td5 analyze --pipeline stems.conf essay.txt",
+            "td5 analyze --links urls.txt --max-download 5M --fetch-interval 1000 --format json",
+            "td5 analyze --merge-map spellings.tsv report.md",
+            "td5 analyze --pii --pii-samples 3 dataset.jsonl",
+            "td5 analyze --dispersion 20 report.md",
+            "td5 analyze --acronyms spec.md",
+            "td5 analyze --keywords rust src/main.rs",
+            "td5 analyze --units bench.log",
+            "td5 analyze --dialogue --quotes '«»,“”' roman.txt",
+            "td5 analyze --social tweets.txt",
+            "td5 analyze episode.srt",
+            "td5 analyze --chat 'WhatsApp Chat with Ada.txt'",
+            "td5 analyze book.epub",
+            "td5 analyze --split-on 'regex:^=+ .* =+$' notes.txt",
+            "td5 analyze --tokens 'regex:[A-Za-z_]\\w*' --analyzer slow main.rs",
+            "td5 analyze --normalize nfkc --fold-diacritics notes.txt",
+        ],
+    },
+    Command {
+        name: "report",
+        args: "<FILE|->",
+        about: "Write a self-contained HTML or Markdown report of a text: top words, word lengths, coverage and how long each analyzer took",
+        positional: Positional::Files,
+        flags: &[
+            Flag {
+                long: "out",
+                value: Some("FILE"),
+                help: "Write the report to FILE instead of standard output",
+            },
+            Flag {
+                long: "format",
+                value: Some("FORMAT"),
+                help: "html or markdown (default: markdown for a .md --out, else html)",
+            },
+            Flag {
+                long: "analyzers",
+                value: Some("LIST"),
+                help: "Comma-separated analyzers to time, the first one the baseline (default slow,fast)",
+            },
+        ],
+        examples: &[
+            "td5 report --out book.html book.txt",
+            "td5 report --out PERF.md --analyzers slow,fast,parallel book.txt",
+            "cat book.txt | td5 report --format markdown -",
+        ],
+    },
+    Command {
+        name: "lint",
+        args: "[FILE...]",
+        about: "Flag repeated words, long sentences and overused fillers",
+        positional: Positional::Files,
+        flags: LINT_FLAGS,
+        examples: &[
+            "td5 lint README.md",
+            "td5 lint --max-sentence 30 --filler very --filler really docs/*.md",
+            "td5 lint --phrases style-guide.txt docs/*.md",
+        ],
+    },
+    Command {
+        name: "bench",
+        args: "[OPTIONS]",
+        about: "Time every analyzer on a matrix of generated input shapes",
+        positional: Positional::None,
+        flags: &[
+            Flag {
+                long: "words",
+                value: Some("N"),
+                help: "Words per generated input (default 200000)",
+            },
+            Flag {
+                long: "iterations",
+                value: Some("N"),
+                help: "Timed runs per cell (default 10)",
+            },
+            Flag {
+                long: "shapes",
+                value: Some("LIST"),
+                help: "Comma-separated shapes (default: all)",
+            },
+            Flag {
+                long: "analyzers",
+                value: Some("LIST"),
+                help: "Comma-separated analyzers (default: all)",
+            },
+            Flag {
+                long: "seed",
+                value: Some("N"),
+                help: "Seed for the input generators (default 0)",
+            },
+            Flag {
+                long: "file",
+                value: Some("FILE"),
+                help: "Also benchmark FILE (repeatable)",
+            },
+            Flag {
+                long: "corpus",
+                value: Some("SPEC"),
+                help: "Also benchmark a cached/downloaded corpus, e.g. gutenberg:1342 (repeatable)",
+            },
+            Flag {
+                long: "against",
+                value: Some("LIST"),
+                help: "Also time external tools on each input: wc, grep, uniq (comma-separated)",
+            },
+            Flag {
+                long: "input-cache",
+                value: Some("MODE"),
+                help: "Keep generated inputs on disk: auto (1M words or more, the default), always or off",
+            },
+            Flag {
+                long: "save",
+                value: Some("FILE"),
+                help: "Write samples and environment to FILE as JSON",
+            },
+            Flag {
+                long: "baseline",
+                value: Some("FILE"),
+                help: "Compare against a report saved with --save",
+            },
+            Flag {
+                long: "max-regression",
+                value: Some("PERCENT"),
+                help: "With --baseline, exit with status 3 if a cell's median got more than PERCENT slower, significantly",
+            },
+            Flag {
+                long: "record",
+                value: Some("FILE"),
+                help: "Write a replayable bundle (inputs, options, environment, results)",
+            },
+            Flag {
+                long: "template",
+                value: Some("FILE"),
+                help: "Render the results through a Handlebars-style template instead of the grid",
+            },
+            Flag {
+                long: "format",
+                value: Some("FORMAT"),
+                help: "Output format: text (default), json or csv, both with every sample and summary",
+            },
+            Flag {
+                long: "cachegrind",
+                value: None,
+                help: "Instead of timing, count instructions and simulated cache misses under valgrind",
+            },
+            Flag {
+                long: "energy",
+                value: None,
+                help: "Also estimate the energy of each run from the RAPL counters (needs the rapl feature on Linux, usually root)",
+            },
+            Flag {
+                long: "cache-sweep",
+                value: None,
+                help: "Instead, time words per vocabulary size to show the cache cliffs of counting (default analyzer: fast)",
+            },
+            Flag {
+                long: "vocabularies",
+                value: Some("LIST"),
+                help: "Vocabulary sizes for --cache-sweep, e.g. 1K,64K,1M (default 1K to 1M by 4)",
+            },
+        ],
+        examples: &[
+            "td5 bench",
+            "td5 bench --words 50000 --shapes zipf,unicode --analyzers fast,parallel",
+            "td5 bench --corpus gutenberg:1342 --analyzers slow,fast",
+            "td5 bench --shapes zipf --against wc,uniq",
+            "td5 bench --save before.json",
+            "td5 bench --baseline before.json",
+            "td5 bench --shapes zipf --analyzers fast --baseline before.json --max-regression 10",
+            "td5 bench --file slow-case.txt --analyzers fast --record run.td5",
+            "td5 bench --template report.md.hbs > report.md",
+            "td5 bench --format csv > samples.csv",
+            "td5 bench --cachegrind --words 20000 --analyzers slow,fast",
+            "sudo td5 bench --energy --shapes zipf --analyzers slow,fast,parallel",
+            "td5 bench --cache-sweep --analyzers fast,sharded",
+        ],
+    },
+    Command {
+        name: "tune",
+        args: "[OPTIONS]",
+        about: "Measure chunk, map and buffer sizes on this machine and save the fastest",
+        positional: Positional::None,
+        flags: &[
+            Flag {
+                long: "words",
+                value: Some("N"),
+                help: "Words of generated Zipf text to tune on (default 1000000)",
+            },
+            Flag {
+                long: "file",
+                value: Some("FILE"),
+                help: "Tune on FILE instead of generated text",
+            },
+            Flag {
+                long: "seed",
+                value: Some("N"),
+                help: "Seed of the generated text (default 0)",
+            },
+            Flag {
+                long: "iterations",
+                value: Some("N"),
+                help: "Timed runs per candidate value (default 5)",
+            },
+            Flag {
+                long: "out",
+                value: Some("FILE"),
+                help: "Write the config to FILE instead of the default location",
+            },
+            Flag {
+                long: "dry-run",
+                value: None,
+                help: "Only print the measurements",
+            },
+        ],
+        examples: &[
+            "td5 tune",
+            "td5 tune --file typical-input.txt --iterations 10",
+            "td5 tune --dry-run",
+        ],
+    },
+    Command {
+        name: "timeline",
+        args: "<FILE...>",
+        about: "Word statistics and trending terms per minute, hour or day of timestamped lines",
+        positional: Positional::Files,
+        flags: &[
+            Flag {
+                long: "bucket",
+                value: Some("SIZE"),
+                help: "Bucket size: minute, hour (default) or day",
+            },
+            Flag {
+                long: "format",
+                value: Some("FORMAT"),
+                help: "Output format: text (default), csv or json (one document per bucket)",
+            },
+        ],
+        examples: &[
+            "td5 timeline service.log",
+            "td5 timeline --bucket day --format csv app.log.1 app.log > words.csv",
+        ],
+    },
+    Command {
+        name: "deny",
+        args: "--list <FILE> <FILE...>",
+        about: "Count words and phrases from a deny list, failing above a threshold",
+        positional: Positional::Files,
+        flags: &[
+            Flag {
+                long: "list",
+                value: Some("FILE"),
+                help: "Denied words or phrase patterns, one per line",
+            },
+            Flag {
+                long: "locations",
+                value: None,
+                help: "Also print where each match is",
+            },
+            Flag {
+                long: "max",
+                value: Some("N"),
+                help: "Exit with status 3 if there are more than N matches in total (default 0)",
+            },
+        ],
+        examples: &[
+            "td5 deny --list banned.txt docs/*.md",
+            "td5 deny --list profanity.txt --locations --max 5 comments.txt",
+        ],
+    },
+    Command {
+        name: "wordlist",
+        args: "<FILE...>",
+        about: "Export the word frequencies of files for spellcheckers and prediction tools",
+        positional: Positional::Files,
+        flags: &[
+            Flag {
+                long: "format",
+                value: Some("FORMAT"),
+                help: "tsv (count<TAB>word, default), dic (Hunspell word list), msgpack or cbor (word-to-count map)",
+            },
+            Flag {
+                long: "min-count",
+                value: Some("N"),
+                help: "Leave out words seen fewer than N times (default 1)",
+            },
+            Flag {
+                long: "offset",
+                value: Some("N"),
+                help: "Skip the N most frequent words (default 0)",
+            },
+            Flag {
+                long: "limit",
+                value: Some("N"),
+                help: "List at most N words, for one page of a large vocabulary",
+            },
+            Flag {
+                long: "pipeline",
+                value: Some("FILE"),
+                help: "Transform the counted words through the stages in FILE (default: pipeline.conf in the config directory, if any)",
+            },
+            Flag {
+                long: "merge-map",
+                value: Some("FILE"),
+                help: "Merge variant spellings into one word, from a file of variant<TAB>canonical lines",
+            },
+        ],
+        examples: &[
+            "td5 wordlist corpus/*.txt > words.tsv",
+            "td5 wordlist --offset 1000 --limit 1000 corpus/*.txt > page-2.tsv",
+            "td5 wordlist --format dic --min-count 3 corpus/*.txt > custom.dic",
+            "td5 wordlist --format msgpack corpus/*.txt > words.msgpack",
+        ],
+    },
+    Command {
+        name: "share",
+        args: "<FILE...> | --decode PAYLOAD",
+        about: "Pack the top words of files into a URL-safe payload for a share link, or read one back",
+        positional: Positional::Files,
+        flags: &[
+            Flag {
+                long: "top",
+                value: Some("N"),
+                help: "Words to include, most frequent first (default 20)",
+            },
+            Flag {
+                long: "base",
+                value: Some("URL"),
+                help: "Print a link: URL, then `#` and the payload",
+            },
+            Flag {
+                long: "decode",
+                value: Some("PAYLOAD"),
+                help: "Print a payload or share link as JSON instead",
+            },
+        ],
+        examples: &[
+            "td5 share book.txt",
+            "td5 share --top 50 --base https://example.org/viewer corpus/*.txt",
+            "td5 share --decode '1.rs.the_14.cat_3'",
+        ],
+    },
+    Command {
+        name: "prefix-tree",
+        args: "--out <FILE> <FILE...>",
+        about: "Write the vocabulary of files with counts as a prefix automaton for autocomplete",
+        positional: Positional::Files,
+        flags: &[
+            Flag {
+                long: "out",
+                value: Some("FILE"),
+                help: "Where to write the automaton",
+            },
+            Flag {
+                long: "min-count",
+                value: Some("N"),
+                help: "Leave out words seen fewer than N times (default 1)",
+            },
+        ],
+        examples: &["td5 prefix-tree --out vocab.td5p --min-count 2 corpus/*.txt"],
+    },
+    Command {
+        name: "complete",
+        args: "<TREE> <PREFIX>",
+        about: "Most frequent words starting with a prefix, from a `prefix-tree` file",
+        positional: Positional::Files,
+        flags: &[Flag {
+            long: "top",
+            value: Some("N"),
+            help: "Number of completions (default 10)",
+        }],
+        examples: &["td5 complete vocab.td5p opti --top 5"],
+    },
+    Command {
+        name: "learn-hot",
+        args: "<PATH...>",
+        about: "Pick the fast path's hot words from a corpus of files or directories",
+        positional: Positional::Files,
+        flags: &[
+            Flag {
+                long: "top",
+                value: Some("N"),
+                help: "Number of hot words, at most 256 (default 10)",
+            },
+            Flag {
+                long: "out",
+                value: Some("FILE"),
+                help: "Write the list to FILE instead of standard output",
+            },
+        ],
+        examples: &[
+            "td5 learn-hot corpus/ --top 64 --out hot.txt",
+            "TD5_HOT_WORDS=hot.txt cargo build --release",
+        ],
+    },
+    Command {
+        name: "dedup",
+        args: "<PATH...>",
+        about: "Find clusters of near-duplicate documents among files or directories",
+        positional: Positional::Files,
+        flags: &[
+            Flag {
+                long: "threshold",
+                value: Some("SIMILARITY"),
+                help: "Estimated similarity, 0 to 1, above which two documents are duplicates (default 0.8)",
+            },
+            Flag {
+                long: "shingle",
+                value: Some("N"),
+                help: "Words per shingle (default 3)",
+            },
+            Flag {
+                long: "seed",
+                value: Some("N"),
+                help: "Seed of the MinHash functions (default 0)",
+            },
+            Flag {
+                long: "unique",
+                value: None,
+                help: "Print only the files to keep: the first of each cluster and every other file",
+            },
+            Flag {
+                long: "format",
+                value: Some("FORMAT"),
+                help: "Output format: text (default) or json",
+            },
+        ],
+        examples: &[
+            "td5 dedup dump/ --threshold 0.9",
+            "td5 learn-hot $(td5 dedup --unique dump/)",
+        ],
+    },
+    Command {
+        name: "overlap",
+        args: "<A> <B>",
+        about: "Compare the vocabularies of two files: Jaccard similarity, words unique to each, shared top terms",
+        positional: Positional::Files,
+        flags: &[
+            Flag {
+                long: "top",
+                value: Some("N"),
+                help: "Words per list (default 10)",
+            },
+            Flag {
+                long: "format",
+                value: Some("FORMAT"),
+                help: "Output format: text (default) or json",
+            },
+        ],
+        examples: &[
+            "td5 overlap draft-v1.txt draft-v2.txt",
+            "td5 overlap --format json a.txt b.txt | jq .jaccard",
+        ],
+    },
+    Command {
+        name: "compare",
+        args: "<A> <B>",
+        about: "Compare two versions of a text, or two results saved by analyze --format json: metrics and timing side by side, shared vocabulary, words unique to each and the largest frequency shifts",
+        positional: Positional::Files,
+        flags: &[
+            Flag {
+                long: "top",
+                value: Some("N"),
+                help: "Words per list (default 10)",
+            },
+            Flag {
+                long: "format",
+                value: Some("FORMAT"),
+                help: "Output format: text (default) or json",
+            },
+        ],
+        examples: &[
+            "td5 compare draft-v1.md draft-v2.md",
+            "td5 compare --format json old.txt new.txt | jq '.diff.shifts[:5]'",
+            "td5 compare run1.json run2.json",
+        ],
+    },
+    Command {
+        name: "stability",
+        args: "<FILE> [--a SPEC] [--b SPEC]",
+        about: "Run two backends or option sets on FILE and report how well their top words agree (Kendall tau)",
+        positional: Positional::Files,
+        flags: &[
+            Flag {
+                long: "a",
+                value: Some("SPEC"),
+                help: "First side: an analyzer, optionally with :sample=SHARE and seed=N (default slow)",
+            },
+            Flag {
+                long: "b",
+                value: Some("SPEC"),
+                help: "Second side, same syntax (default fast)",
+            },
+            Flag {
+                long: "top",
+                value: Some("N"),
+                help: "Top words compared, with any tied with the last (default 10)",
+            },
+            Flag {
+                long: "format",
+                value: Some("FORMAT"),
+                help: "Output format: text (default) or json",
+            },
+        ],
+        examples: &[
+            "td5 stability book.txt",
+            "td5 stability big.log --a fast --b fast:sample=5%",
+            "td5 stability big.log --a fast:sample=5%,seed=1 --b fast:sample=5%,seed=2",
+            "td5 stability book.txt --a fast --b parallel --format json | jq .kendall_tau",
+        ],
+    },
+    Command {
+        name: "batch",
+        args: "<MANIFEST>",
+        about: "Analyze the inputs listed in a JSON or TOML manifest, each with its own analyzer, language and stopwords, plus a combined total",
+        positional: Positional::Files,
+        flags: &[
+            Flag {
+                long: "format",
+                value: Some("FORMAT"),
+                help: "Output format: text (default), csv (one row per input with its path, size, modification time and language, then the combined total) or json",
+            },
+            Flag {
+                long: "on-error",
+                value: Some("POLICY"),
+                help: "When an input cannot be read: abort, continue (default; exit status 4 if any failed) or ignore (exit status 0 unless all failed)",
+            },
+            Flag {
+                long: "resume",
+                value: Some("FILE"),
+                help: "Save a checkpoint to FILE after each input and, if FILE exists, carry on after the inputs it has done (removed once the run completes)",
+            },
+            Flag {
+                long: "write-normalized",
+                value: Some("DIR"),
+                help: "Also write each input as UTF-8 without a BOM under DIR, at its path from the manifest's directory",
+            },
+        ],
+        examples: &[
+            "td5 batch corpus.toml",
+            "td5 batch --resume corpus.checkpoint corpus.toml",
+            "td5 batch --format json corpus.json | jq .combined.total_words",
+            "td5 batch --format csv corpus.toml > summary.csv",
+            "td5 batch --write-normalized clean/ corpus.toml",
+        ],
+    },
+    Command {
+        name: "trend",
+        args: "<SNAPSHOT...>",
+        about: "Compare corpus snapshots, oldest first, and report the words rising and falling the most",
+        positional: Positional::Files,
+        flags: &[
+            Flag {
+                long: "top",
+                value: Some("N"),
+                help: "Words per list (default 10)",
+            },
+            Flag {
+                long: "min-count",
+                value: Some("N"),
+                help: "Leave out words seen fewer than N times over all snapshots (default 5)",
+            },
+            Flag {
+                long: "format",
+                value: Some("FORMAT"),
+                help: "Output format: text (default), csv (the full trend table) or json",
+            },
+        ],
+        examples: &[
+            "td5 trend 2022/ 2023/ 2024/",
+            "td5 trend --format csv --min-count 20 snapshots/*/ > trend.csv",
+        ],
+    },
+    Command {
+        name: "readability",
+        args: "<FILE>",
+        about: "Chart readability and sentence length through a document to find its hardest passages",
+        positional: Positional::Files,
+        flags: &[
+            Flag {
+                long: "window",
+                value: Some("N"),
+                help: "Words per window (default 200)",
+            },
+            Flag {
+                long: "step",
+                value: Some("N"),
+                help: "Words between the starts of two windows (default 50)",
+            },
+            Flag {
+                long: "hardest",
+                value: Some("N"),
+                help: "Passages to list as hardest to read (default 3)",
+            },
+            Flag {
+                long: "format",
+                value: Some("FORMAT"),
+                help: "Output format: text (default), csv or json",
+            },
+        ],
+        examples: &[
+            "td5 readability chapter.md",
+            "td5 readability --format csv --window 300 book.txt > profile.csv",
+        ],
+    },
+    Command {
+        name: "cooccur",
+        args: "<PATH...>",
+        about: "Export the network of words appearing near one another, for Gephi or Graphviz",
+        positional: Positional::Files,
+        flags: &[
+            Flag {
+                long: "window",
+                value: Some("N"),
+                help: "Link words at most N words apart (default 5)",
+            },
+            Flag {
+                long: "top",
+                value: Some("N"),
+                help: "Most frequent words to use as nodes (default 50)",
+            },
+            Flag {
+                long: "min-weight",
+                value: Some("N"),
+                help: "Drop links seen fewer than N times (default 2)",
+            },
+            Flag {
+                long: "format",
+                value: Some("FORMAT"),
+                help: "Output format: text (default), json, dot or graphml",
+            },
+        ],
+        examples: &[
+            "td5 cooccur --format dot notes.txt | dot -Tsvg -Kneato > notes.svg",
+            "td5 cooccur --format graphml --top 200 corpus/ > network.graphml",
+        ],
+    },
+    Command {
+        name: "concordance",
+        args: "<WORD> <PATH...>",
+        about: "Show every occurrence of a word with the words around it (keyword in context)",
+        positional: Positional::Files,
+        flags: &[
+            Flag {
+                long: "context",
+                value: Some("N"),
+                help: "Words shown on each side (default 5)",
+            },
+            Flag {
+                long: "format",
+                value: Some("FORMAT"),
+                help: "Output format: text (default) or json",
+            },
+        ],
+        examples: &[
+            "td5 concordance whale moby-dick.txt",
+            "td5 concordance --context 3 --format json data notes/",
+        ],
+    },
+    Command {
+        name: "approx",
+        args: "[OPTIONS] [PATH...|-]",
+        about: "Top words of input of any length in fixed memory, with error bounds (Count-Min Sketch and Space-Saving)",
+        positional: Positional::Files,
+        flags: &[
+            Flag {
+                long: "epsilon",
+                value: Some("E"),
+                help: "Counts may be over by at most E times the words read (default 0.0001)",
+            },
+            Flag {
+                long: "delta",
+                value: Some("D"),
+                help: "Probability that a count is over by more (default 0.01)",
+            },
+            Flag {
+                long: "capacity",
+                value: Some("N"),
+                help: "Candidate words kept (default 1/epsilon)",
+            },
+            Flag {
+                long: "top",
+                value: Some("N"),
+                help: "Words to list (default 10)",
+            },
+            Flag {
+                long: "stopwords",
+                value: Some("LIST"),
+                help: "Leave the words of LIST out of the top words, still counting them: en, fr, or a file with one word per line (repeatable)",
+            },
+            Flag {
+                long: "every",
+                value: Some("SECONDS"),
+                help: "Also print the top words this often while reading, for streams that do not end",
+            },
+            Flag {
+                long: "format",
+                value: Some("FORMAT"),
+                help: "Output format: text (default) or json",
+            },
+        ],
+        examples: &[
+            "zcat logs/*.gz | td5 approx --top 20 -",
+            "tail -F app.log | td5 approx --every 60 --format json -",
+            "td5 approx --epsilon 0.00001 --stopwords en dump.txt",
+        ],
+    },
+    Command {
+        name: "watch",
+        args: "[OPTIONS] <PATH>",
+        about: "Analyze a file again each time it is saved and print what changed",
+        positional: Positional::Files,
+        flags: &[
+            Flag {
+                long: "interval",
+                value: Some("MS"),
+                help: "How often to check the file for changes (default 500)",
+            },
+            Flag {
+                long: "top",
+                value: Some("N"),
+                help: "Top words to follow (default 10)",
+            },
+            Flag {
+                long: "format",
+                value: Some("FORMAT"),
+                help: "Output format: text (default) or json",
+            },
+        ],
+        examples: &[
+            "td5 watch chapter-3.md",
+            "td5 watch --top 20 --format json draft.txt",
+        ],
+    },
+    Command {
+        name: "unknown",
+        args: "--dictionary <WORDLIST> <FILE...>",
+        about: "List the words of files that are not in a dictionary, most frequent first",
+        positional: Positional::Files,
+        flags: &[
+            Flag {
+                long: "dictionary",
+                value: Some("WORDLIST"),
+                help: "Known words, one per line (Hunspell .dic files work)",
+            },
+            Flag {
+                long: "min-count",
+                value: Some("N"),
+                help: "Leave out words seen fewer than N times (default 1)",
+            },
+        ],
+        examples: &[
+            "td5 unknown --dictionary /usr/share/dict/words scan.txt",
+            "td5 unknown --dictionary en_US.dic --min-count 2 docs/*.md",
+        ],
+    },
+    Command {
+        name: "consume",
+        args: "--nats <ADDRESS> --subject <SUBJECT> | --kafka <ADDRESS> --topic <TOPIC>",
+        about: "Keep stats over messages from a NATS subject or Kafka topic and print snapshots (nats or kafka feature)",
+        positional: Positional::None,
+        flags: &[
+            Flag {
+                long: "nats",
+                value: Some("ADDRESS"),
+                help: "NATS server, e.g. 127.0.0.1:4222",
+            },
+            Flag {
+                long: "subject",
+                value: Some("SUBJECT"),
+                help: "Subject to subscribe to; `*` and `>` wildcards work",
+            },
+            Flag {
+                long: "kafka",
+                value: Some("ADDRESS"),
+                help: "Kafka broker, e.g. 127.0.0.1:9092",
+            },
+            Flag {
+                long: "topic",
+                value: Some("TOPIC"),
+                help: "Kafka topic to read",
+            },
+            Flag {
+                long: "partition",
+                value: Some("N"),
+                help: "Partition of the topic to read (default 0)",
+            },
+            Flag {
+                long: "from-beginning",
+                value: None,
+                help: "Read the partition from its first record instead of its end",
+            },
+            Flag {
+                long: "every",
+                value: Some("SECONDS"),
+                help: "Print a JSON snapshot this often (default 10)",
+            },
+            Flag {
+                long: "output",
+                value: Some("SPEC"),
+                help: "Send snapshots to -, file:PATH, rotate:PATH:MAX_BYTES[:KEEP] or an http(s) webhook instead of stdout (repeatable)",
+            },
+            Flag {
+                long: "frames",
+                value: Some("PATH"),
+                help: "Also append each snapshot to PATH as a checksummed binary frame, or write frames alone to stdout with - (frames feature)",
+            },
+            Flag {
+                long: "alert",
+                value: Some("EXPR"),
+                help: "Alert when e.g. 'unique_words>5000' starts or stops holding, checked at each snapshot (repeatable)",
+            },
+            Flag {
+                long: "alert-deny",
+                value: Some("FILE"),
+                help: "Alert on every message matching this deny list",
+            },
+            Flag {
+                long: "alert-to",
+                value: Some("SPEC"),
+                help: "Send alerts to an http(s) webhook, file:PATH, rotate:… or - (repeatable, default stdout)",
+            },
+            Flag {
+                long: "alert-template",
+                value: Some("FILE"),
+                help: "Template for alert payloads, e.g. {\"text\": \"{{alert}} on {{subject}}\"}",
+            },
+        ],
+        examples: &[
+            "td5 consume --nats 127.0.0.1:4222 --subject 'logs.>' --every 60",
+            "td5 consume --nats 127.0.0.1:4222 --subject 'logs.>' --output rotate:stats.jsonl:10000000 --output http://127.0.0.1:8080/td5",
+            "td5 consume --nats 127.0.0.1:4222 --subject 'logs.>' --frames - | ./reader",
+            "td5 consume --nats 127.0.0.1:4222 --subject chat --alert 'unique_words>5000' --alert-deny banned.txt --alert-to https://hooks.example.com/td5",
+            "td5 consume --kafka 127.0.0.1:9092 --topic logs --from-beginning --every 60",
+        ],
+    },
+    Command {
+        name: "grpc",
+        args: "[--listen <ADDRESS>]",
+        about: "Serve the Analyzer gRPC service of proto/td5.proto over cleartext HTTP/2 (grpc feature)",
+        positional: Positional::None,
+        flags: &[
+            Flag {
+                long: "listen",
+                value: Some("ADDRESS"),
+                help: "Address to listen on (default 127.0.0.1:50051)",
+            },
+            Flag {
+                long: "max-message",
+                value: Some("SIZE"),
+                help: "Largest request message, e.g. 64K or 16M (default 4M)",
+            },
+            Flag {
+                long: "max-connections",
+                value: Some("N"),
+                help: "Connections served at once (default 256)",
+            },
+            Flag {
+                long: "max-streams",
+                value: Some("N"),
+                help: "Calls open at once per connection (default 100)",
+            },
+            Flag {
+                long: "timeout",
+                value: Some("SECONDS"),
+                help: "Longest a call may take before DEADLINE_EXCEEDED (default 30)",
+            },
+            Flag {
+                long: "idle-timeout",
+                value: Some("SECONDS"),
+                help: "Close connections with no call open for this long (default 60)",
+            },
+        ],
+        examples: &[
+            "td5 grpc",
+            "td5 grpc --listen 0.0.0.0:50051 --max-message 1M --timeout 5",
+        ],
+    },
+    Command {
+        name: "authors",
+        args: "[OPTIONS] <REPO>",
+        about: "Word stats per author of a git repository's documentation, from git blame (git feature)",
+        positional: Positional::Files,
+        flags: &[
+            Flag {
+                long: "ext",
+                value: Some("LIST"),
+                help: "Comma-separated extensions of the files to read (default md,markdown,txt,rst,adoc)",
+            },
+            Flag {
+                long: "top",
+                value: Some("N"),
+                help: "Top words shown per author (default 5)",
+            },
+            Flag {
+                long: "format",
+                value: Some("FORMAT"),
+                help: "Output format: text (default) or json",
+            },
+        ],
+        examples: &[
+            "td5 authors .",
+            "td5 authors --ext md,txt --format json ~/src/project | jq .author",
+        ],
+    },
+    Command {
+        name: "wiki",
+        args: "[OPTIONS] <DUMP>",
+        about: "Stream a MediaWiki XML dump and analyze the article text of its pages (- for stdin)",
+        positional: Positional::Files,
+        flags: &[
+            Flag {
+                long: "threads",
+                value: Some("N"),
+                help: "Worker threads counting pages (0 for every core, the default)",
+            },
+            Flag {
+                long: "ns",
+                value: Some("LIST"),
+                help: "Comma-separated namespaces to read, or `all` (default 0, articles)",
+            },
+            Flag {
+                long: "redirects",
+                value: None,
+                help: "Count redirect pages too",
+            },
+            Flag {
+                long: "limit",
+                value: Some("N"),
+                help: "Stop after N pages",
+            },
+            Flag {
+                long: "pages",
+                value: None,
+                help: "Also print the words of each page as it is counted",
+            },
+            Flag {
+                long: "ngrams",
+                value: Some("N"),
+                help: "Also list the most frequent word pairs (2) or pairs and triples (3)",
+            },
+            Flag {
+                long: "format",
+                value: Some("FORMAT"),
+                help: "Output format: text (default) or json",
+            },
+        ],
+        examples: &[
+            "bzcat enwiki-latest-pages-articles.xml.bz2 | td5 wiki --threads 8 -",
+            "td5 wiki --limit 1000 --pages --format json dump.xml | jq .title",
+        ],
+    },
+    Command {
+        name: "replay",
+        args: "<BUNDLE>",
+        about: "Rerun a bundle written by `bench --record` and diff the results",
+        positional: Positional::Files,
+        flags: &[],
+        examples: &["td5 replay run.td5"],
+    },
+    Command {
+        name: "probe",
+        args: "--analyzer <NAME> <FILE>",
+        about: "Run one analyzer once on FILE and print nothing, for profilers (used by `bench --cachegrind`)",
+        positional: Positional::Files,
+        flags: &[
+            Flag {
+                long: "analyzer",
+                value: Some("NAME"),
+                help: "Analyzer to run (default fast)",
+            },
+            Flag {
+                long: "skip-analysis",
+                value: None,
+                help: "Do everything but the analysis, to measure the overhead",
+            },
+            Flag {
+                long: "stack-size",
+                value: Some("BYTES"),
+                help: "Run the analysis on a thread with a stack of BYTES (used by `selftest --stack`)",
+            },
+        ],
+        examples: &["valgrind --tool=callgrind td5 probe --analyzer fast book.txt"],
+    },
+    Command {
+        name: "selftest",
+        args: "",
+        about: "Check that every analyzer agrees with the reference on edge cases",
+        positional: Positional::None,
+        flags: &[
+            Flag {
+                long: "allocations",
+                value: None,
+                help: "Instead, check that the fast path makes no heap allocation per word on hot-only input",
+            },
+            Flag {
+                long: "stack",
+                value: None,
+                help: "Instead, measure the stack every analyzer needs and check that it stays small and bounded",
+            },
+            Flag {
+                long: "differential",
+                value: None,
+                help: "Instead, compare the fast path with the reference on random ASCII and Unicode texts",
+            },
+            Flag {
+                long: "cases",
+                value: Some("N"),
+                help: "With --differential, the number of random texts (default 10000)",
+            },
+            Flag {
+                long: "seed",
+                value: Some("N"),
+                help: "With --differential, the seed of the first text (default 0)",
+            },
+        ],
+        examples: &[
+            "td5 selftest",
+            "td5 selftest --allocations",
+            "td5 selftest --stack",
+            "td5 selftest --differential --cases 100000 --seed 7",
+        ],
+    },
+    Command {
+        name: "serve",
+        args: "[OPTIONS]",
+        about: "Speak the Language Server Protocol on stdin/stdout: live word stats and lint diagnostics for an editor",
+        positional: Positional::None,
+        flags: LINT_FLAGS,
+        examples: &["td5 serve --max-sentence 30"],
+    },
+    Command {
+        name: "repl",
+        args: "[FILE...]",
+        about: "Load documents once and query them interactively",
+        positional: Positional::Files,
+        flags: &[],
+        examples: &[
+            "td5 repl notes.txt draft.txt",
+            "printf 'top 5\\nkwic memory\\n' | td5 repl notes.txt",
+        ],
+    },
+    Command {
+        name: "completions",
+        args: "<SHELL>",
+        about: "Print a completion script for bash, zsh or fish",
+        positional: Positional::Choices(SHELLS),
+        flags: &[],
+        examples: &[
+            "td5 completions bash > ~/.local/share/bash-completion/completions/td5",
+            "td5 completions zsh > ~/.zfunc/_td5",
+            "td5 completions fish > ~/.config/fish/completions/td5.fish",
+        ],
+    },
+    Command {
+        name: "help",
+        args: "[COMMAND]",
+        about: "Show help for td5 or one of its commands",
+        positional: Positional::Command,
+        flags: &[],
+        examples: &["td5 help", "td5 help repl"],
+    },
+];
+
+const HELP_FLAG: Flag = Flag {
+    long: "help",
+    value: None,
+    help: "Show help for this command",
+};
+
+/// Accepted by every command.
+const GLOBAL_FLAGS: &[Flag] = &[
+    Flag {
+        long: "quiet",
+        value: None,
+        help: "Only print results, warnings and errors",
+    },
+    Flag {
+        long: "verbose",
+        value: None,
+        help: "Also print debug notes on stderr",
+    },
+    Flag {
+        long: "lang",
+        value: Some("LANG"),
+        help: "Language of messages: en or fr (default: from the locale)",
+    },
+    Flag {
+        long: "numbers",
+        value: Some("STYLE"),
+        help: "Number style in reports: en (1,234.5), fr (1 234,5) or plain (default: from the locale)",
+    },
+    Flag {
+        long: "decimals",
+        value: Some("N"),
+        help: "Decimals of every fractional number in reports",
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static Command> {
+    COMMANDS.iter().find(|c| c.name == name)
+}
+
+pub fn is_help_flag(arg: &str) -> bool {
+    arg == "--help" || arg == "-h"
+}
+
+/// Flags and positionals of one invocation, checked against its `Command`.
+pub struct Args {
+    flags: Vec<(&'static str, Option<String>)>,
+    pub positional: Vec<String>,
+}
+
+impl Args {
+    /// Accepts `--flag value` and `--flag=value`; unknown flags are errors.
+    pub fn parse(cmd: &Command, args: &[String]) -> Result<Args, String> {
+        let mut parsed = Args {
+            flags: Vec::new(),
+            positional: Vec::new(),
+        };
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let Some(name) = arg.strip_prefix("--") else {
+                parsed.positional.push(arg.clone());
+                continue;
+            };
+            let (name, inline) = match name.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (name, None),
+            };
+            let flag = all_flags(cmd).find(|f| f.long == name).ok_or_else(|| {
+                tr!(
+                    "unknown option `--{name}` for `{command}`",
+                    name = name,
+                    command = cmd.name
+                )
+            })?;
+            let value =
+                match (flag.value, inline) {
+                    (Some(_), Some(v)) => Some(v),
+                    (Some(v), None) => Some(iter.next().cloned().ok_or_else(|| {
+                        tr!("`--{name}` expects <{value}>", name = name, value = v)
+                    })?),
+                    (None, Some(_)) => return Err(tr!("`--{name}` takes no value", name = name)),
+                    (None, None) => None,
+                };
+            if flag.long == "preset" {
+                let preset = find_preset(value.as_deref().unwrap_or_default())?;
+                let flags: Vec<String> = preset.flags.iter().map(|f| f.to_string()).collect();
+                parsed.flags.extend(Args::parse(cmd, &flags)?.flags);
+            }
+            parsed.flags.push((flag.long, value));
+        }
+        Ok(parsed)
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.flags.iter().any(|(n, _)| *n == name)
+    }
+
+    /// Last value given for `name`.
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.flags
+            .iter()
+            .rev()
+            .find(|(n, _)| *n == name)
+            .and_then(|(_, v)| v.as_deref())
+    }
+
+    pub fn number<T: std::str::FromStr>(&self, name: &str, default: T) -> Result<T, String> {
+        match self.value(name) {
+            None => Ok(default),
+            Some(v) => v.parse().map_err(|_| {
+                tr!(
+                    "`--{name}` expects a number, got `{value}`",
+                    name = name,
+                    value = v
+                )
+            }),
+        }
+    }
+
+    /// Every value given for a repeatable flag, in order.
+    pub fn values(&self, name: &str) -> Vec<&str> {
+        self.flags
+            .iter()
+            .filter(|(n, _)| *n == name)
+            .filter_map(|(_, v)| v.as_deref())
+            .collect()
+    }
+
+    /// Comma-separated values of `name`, or `None` when the flag is absent.
+    pub fn list(&self, name: &str) -> Option<Vec<&str>> {
+        self.value(name).map(|v| {
+            v.split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+    }
+}
+
+fn all_flags(cmd: &Command) -> impl Iterator<Item = &Flag> {
+    cmd.flags
+        .iter()
+        .chain(GLOBAL_FLAGS)
+        .chain(std::iter::once(&HELP_FLAG))
+}
+
+pub fn usage() -> String {
+    let mut out = format!(
+        "{}\n\n{} td5 [COMMAND] [ARGS]\n\n{}\n",
+        tr!("td5: fast/slow text analyzer"),
+        tr!("Usage:"),
+        tr!("Commands:")
+    );
+    for cmd in COMMANDS {
+        let _ = writeln!(out, "  {:<14}{}", cmd.name, tr(cmd.about));
+    }
+    let _ = writeln!(
+        out,
+        "\n{}",
+        tr!("Run `td5 help <COMMAND>` for options and examples.")
+    );
+    out
+}
+
+pub fn command_help(cmd: &Command) -> String {
+    let mut out = format!("{}\n\n{} td5 {}", tr(cmd.about), tr!("Usage:"), cmd.name);
+    if !cmd.args.is_empty() {
+        out.push(' ');
+        out.push_str(cmd.args);
+    }
+    let _ = write!(out, "\n\n{}\n", tr!("Options:"));
+    for flag in all_flags(cmd) {
+        let name = match flag.value {
+            Some(v) => format!("--{} <{v}>", flag.long),
+            None => format!("--{}", flag.long),
+        };
+        let _ = writeln!(out, "  {name:<23} {}", tr(flag.help));
+    }
+    if all_flags(cmd).any(|flag| flag.long == "preset") {
+        let _ = write!(out, "\n{}\n", tr!("Presets:"));
+        for preset in PRESETS {
+            let _ = writeln!(out, "  {:<8} {}", preset.name, tr(preset.about));
+            let _ = writeln!(out, "  {:<8} {}", "", preset.flags.join(" "));
+        }
+    }
+    if !cmd.examples.is_empty() {
+        let _ = write!(out, "\n{}\n", tr!("Examples:"));
+        for example in cmd.examples {
+            let _ = writeln!(out, "  {example}");
+        }
+    }
+    out
+}
+
+pub fn completions(shell: &str) -> Option<String> {
+    match shell {
+        "bash" => Some(bash()),
+        "zsh" => Some(zsh()),
+        "fish" => Some(fish()),
+        _ => None,
+    }
+}
+
+fn command_names() -> String {
+    COMMANDS
+        .iter()
+        .map(|c| c.name)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn bash() -> String {
+    let mut out = String::from(
+        "_td5() {\n    local cur=\"${COMP_WORDS[COMP_CWORD]}\"\n    if [ \"$COMP_CWORD\" -eq 1 ]; then\n",
+    );
+    let _ = writeln!(
+        out,
+        "        COMPREPLY=( $(compgen -W \"{}\" -- \"$cur\") )\n        return\n    fi\n    case \"${{COMP_WORDS[1]}}\" in",
+        command_names()
+    );
+    for cmd in COMMANDS {
+        let mut words: Vec<String> = all_flags(cmd).map(|f| format!("--{}", f.long)).collect();
+        let files = match &cmd.positional {
+            Positional::Files => " $(compgen -f -- \"$cur\")",
+            Positional::Choices(values) => {
+                words.extend(values.iter().map(|v| v.to_string()));
+                ""
+            }
+            Positional::Command => {
+                words.push(command_names());
+                ""
+            }
+            Positional::None => "",
+        };
+        let _ = writeln!(
+            out,
+            "        {}) COMPREPLY=( $(compgen -W \"{}\" -- \"$cur\"){files} ) ;;",
+            cmd.name,
+            words.join(" ")
+        );
+    }
+    out.push_str("    esac\n}\ncomplete -F _td5 td5\n");
+    out
+}
+
+fn zsh() -> String {
+    let mut out = String::from("#compdef td5\n\n_td5() {\n    local -a commands\n    commands=(\n");
+    for cmd in COMMANDS {
+        let _ = writeln!(
+            out,
+            "        {}",
+            zsh_quote(&format!("{}:{}", cmd.name, cmd.about))
+        );
+    }
+    out.push_str(
+        "    )\n    if (( CURRENT == 2 )); then\n        _describe 'command' commands\n        return\n    fi\n    shift words\n    (( CURRENT-- ))\n    case $words[1] in\n",
+    );
+    for cmd in COMMANDS {
+        let mut specs: Vec<String> = all_flags(cmd)
+            .map(|f| {
+                let help = zsh_escape(f.help);
+                zsh_quote(&match f.value {
+                    Some(v) => format!("--{}[{help}]:{}:", f.long, v.to_lowercase()),
+                    None => format!("--{}[{help}]", f.long),
+                })
+            })
+            .collect();
+        match &cmd.positional {
+            Positional::Files => specs.push("'*:file:_files'".into()),
+            Positional::Choices(values) => specs.push(format!("'1:value:({})'", values.join(" "))),
+            Positional::Command => specs.push(format!("'1:command:({})'", command_names())),
+            Positional::None => {}
+        }
+        let _ = writeln!(
+            out,
+            "        {}) _arguments {} ;;",
+            cmd.name,
+            specs.join(" ")
+        );
+    }
+    out.push_str("    esac\n}\n\n_td5 \"$@\"\n");
+    out
+}
+
+/// `text` in single quotes for zsh, which has no escape inside them: a
+/// quote closes them, is escaped, and opens them again.
+fn zsh_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+/// `text` as the description of an `_arguments` spec, which brackets
+/// delimit.
+fn zsh_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if matches!(ch, '[' | ']' | '\\') {
+            out.push('\\');
+        }
+        out.push(ch);
+    }
+    out
+}
+
+/// `text` in single quotes for fish, where `\'` and `\\` are escapes.
+fn fish_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+fn fish() -> String {
+    let mut out = String::from("complete -c td5 -f\n");
+    for cmd in COMMANDS {
+        let _ = writeln!(
+            out,
+            "complete -c td5 -n __fish_use_subcommand -a {} -d {}",
+            cmd.name,
+            fish_quote(cmd.about)
+        );
+    }
+    for cmd in COMMANDS {
+        let cond = format!("'__fish_seen_subcommand_from {}'", cmd.name);
+        for flag in all_flags(cmd) {
+            let requires = if flag.value.is_some() { " -r" } else { "" };
+            let _ = writeln!(
+                out,
+                "complete -c td5 -n {cond} -l {}{requires} -d {}",
+                flag.long,
+                fish_quote(flag.help)
+            );
+        }
+        match &cmd.positional {
+            Positional::Files => {
+                let _ = writeln!(out, "complete -c td5 -n {cond} -F");
+            }
+            Positional::Choices(values) => {
+                let _ = writeln!(out, "complete -c td5 -n {cond} -a '{}'", values.join(" "));
+            }
+            Positional::Command => {
+                let _ = writeln!(out, "complete -c td5 -n {cond} -a '{}'", command_names());
+            }
+            Positional::None => {}
+        }
+    }
+    out
+}
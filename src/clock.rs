@@ -0,0 +1,139 @@
+//! The time sources of the library.
+//!
+//! What waits or expires (streaming snapshots, sliding windows, download
+//! pacing, gRPC deadlines and idle connections) reads a `Clock` rather than
+//! calling `Instant::now()` itself. `SystemClock` is the default.
+//! `ManualClock` only moves when told to, so a test can step through a
+//! fetch interval or an emit period without sleeping, and a target without
+//! `Instant` (wasm32 in a browser) can drive one from its own timer.
+//!
+//! What only measures how long some work took (the analyzers' `Timings` and
+//! `PhaseTimings`, benchmark samples, tuning sweeps, external tools) uses a
+//! `Stopwatch`, which in a browser reads `performance.now()` instead of
+//! `Instant`, so the fast path runs there without a clock being set up.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+pub trait Clock: Send + Sync {
+    /// Time since a fixed, arbitrary origin; never goes backwards.
+    fn now(&self) -> Duration;
+
+    /// Blocks for `duration`, as measured by this clock.
+    fn sleep(&self, duration: Duration);
+}
+
+/// `Instant` and `thread::sleep`.
+#[derive(Debug, Clone, Copy)]
+pub struct SystemClock {
+    origin: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        SystemClock {
+            origin: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+/// A clock that only moves through `advance` and `sleep`; `sleep` returns at
+/// once, having moved the clock forward.
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    nanos: AtomicU64,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        self.nanos.fetch_add(nanos, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::SeqCst))
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+    }
+}
+
+/// How an analysis measures its `Timings`: `Instant` where there is
+/// one. On wasm32 in a browser, where `Instant::now` panics, it reads the
+/// page's `performance.now()`: through wasm-bindgen with the `wasm`
+/// feature, else as the `env.performance_now` import the embedding
+/// JavaScript provides.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Stopwatch {
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    start: Instant,
+    /// Milliseconds, as `performance.now()` gives them.
+    #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+    start: f64,
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown", not(feature = "wasm")))]
+unsafe extern "C" {
+    fn performance_now() -> f64;
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown", not(feature = "wasm")))]
+fn now_ms() -> f64 {
+    // SAFETY: the import takes nothing and returns a number.
+    unsafe { performance_now() }
+}
+
+/// With wasm-bindgen, its glue calls `performance.now()` itself.
+#[cfg(all(target_arch = "wasm32", target_os = "unknown", feature = "wasm"))]
+#[wasm_bindgen::prelude::wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = performance, js_name = now)]
+    fn now_ms() -> f64;
+}
+
+impl Stopwatch {
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub(crate) fn start() -> Self {
+        Stopwatch {
+            start: Instant::now(),
+        }
+    }
+
+    #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+    pub(crate) fn start() -> Self {
+        Stopwatch { start: now_ms() }
+    }
+
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub(crate) fn elapsed_ns(&self) -> u128 {
+        self.start.elapsed().as_nanos()
+    }
+
+    #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+    pub(crate) fn elapsed_ns(&self) -> u128 {
+        ((now_ms() - self.start).max(0.0) * 1e6) as u128
+    }
+}
+
+/// A fresh `SystemClock`, shared.
+pub fn system() -> Arc<dyn Clock> {
+    Arc::new(SystemClock::default())
+}
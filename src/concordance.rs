@@ -0,0 +1,161 @@
+//! Keyword in context (`td5 concordance`): every occurrence of a word with
+//! the words around it.
+//!
+//! A `Concordance` tokenizes the text once with `tokenize`, the fast
+//! analyzer's own scanners, recording the byte span of every word and where
+//! each word occurs. The analyzers never build it, so statistics pay nothing
+//! for it. Words are looked up as the analyzers count them: `Cat` finds `cat`
+//! unless `AnalyzerConfig::case_sensitive`.
+
+use crate::config::AnalyzerConfig;
+use crate::json::Json;
+use crate::tokens::tokenize;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Where the words of one text occur.
+///
+/// ```
+/// use td5::{AnalyzerConfig, concordance::Concordance};
+///
+/// let text = "The cat sat.\nThen the CAT ran off, the cat!";
+/// let concordance = Concordance::new(text, &AnalyzerConfig::default());
+/// assert_eq!(concordance.count("Cat"), 3);
+/// let lines = concordance.lines("cat", 2);
+/// assert_eq!(lines[1].left, "Then the");
+/// assert_eq!(lines[1].word, "CAT");
+/// assert_eq!(lines[1].right, "ran off");
+/// assert_eq!(&text[lines[1].offset..][..3], "CAT");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Concordance<'a> {
+    text: &'a str,
+    config: AnalyzerConfig,
+    /// Every word of the text, in order.
+    spans: Vec<Range<usize>>,
+    /// Indices into `spans`, per word.
+    index: HashMap<String, Vec<usize>>,
+}
+
+/// One occurrence of a word, with its context as written, line breaks and
+/// runs of whitespace made single spaces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KwicLine {
+    /// Byte offset of the occurrence in the text.
+    pub offset: usize,
+    pub left: String,
+    /// The occurrence as written: `CAT` for the word `cat`, and on non-ASCII
+    /// text its whole whitespace-separated token, punctuation included.
+    pub word: String,
+    pub right: String,
+}
+
+impl<'a> Concordance<'a> {
+    /// Indexes every word of `text`, for repeated lookups.
+    pub fn new(text: &'a str, config: &AnalyzerConfig) -> Self {
+        Self::build(text, config, None)
+    }
+
+    /// Indexes only `word`, for a single lookup without a table of every word.
+    /// The spans of the other words are still recorded, for context.
+    ///
+    /// ```
+    /// use td5::{AnalyzerConfig, concordance::Concordance};
+    ///
+    /// let concordance = Concordance::of("a rose is a rose", "rose", &AnalyzerConfig::default());
+    /// assert_eq!(concordance.count("rose"), 2);
+    /// assert_eq!(concordance.count("is"), 0);
+    /// ```
+    pub fn of(text: &'a str, word: &str, config: &AnalyzerConfig) -> Self {
+        let key = key(word, config).unwrap_or_default();
+        Self::build(text, config, Some(&key))
+    }
+
+    fn build(text: &'a str, config: &AnalyzerConfig, only: Option<&str>) -> Self {
+        let mut spans = Vec::new();
+        let mut index: HashMap<String, Vec<usize>> = HashMap::new();
+        tokenize(text, config, |word, span| {
+            match index.get_mut(word) {
+                Some(positions) => positions.push(spans.len()),
+                None if only.is_none_or(|only| only == word) => {
+                    index.insert(word.to_string(), vec![spans.len()]);
+                }
+                None => {}
+            }
+            spans.push(span);
+        });
+        Concordance {
+            text,
+            config: config.clone(),
+            spans,
+            index,
+        }
+    }
+
+    /// Words in the text.
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Occurrences of `word`.
+    pub fn count(&self, word: &str) -> usize {
+        self.positions(word).len()
+    }
+
+    fn positions(&self, word: &str) -> &[usize] {
+        key(word, &self.config)
+            .and_then(|key| self.index.get(&key))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Every occurrence of `word` in text order, with up to `context` words
+    /// on each side.
+    pub fn lines(&self, word: &str, context: usize) -> Vec<KwicLine> {
+        let last = self.spans.len().saturating_sub(1);
+        self.positions(word)
+            .iter()
+            .map(|&i| {
+                let span = &self.spans[i];
+                let first = &self.spans[i.saturating_sub(context)];
+                let end = match context {
+                    0 => span.end,
+                    _ => self.spans[(i + context).min(last)].end,
+                };
+                KwicLine {
+                    offset: span.start,
+                    left: flatten(&self.text[first.start..span.start]),
+                    word: self.text[span.clone()].to_string(),
+                    right: flatten(&self.text[span.end..end]),
+                }
+            })
+            .collect()
+    }
+}
+
+impl KwicLine {
+    pub fn to_json_value(&self) -> Json {
+        Json::Object(vec![
+            ("offset".into(), self.offset.into()),
+            ("left".into(), self.left.as_str().into()),
+            ("word".into(), self.word.as_str().into()),
+            ("right".into(), self.right.as_str().into()),
+        ])
+    }
+}
+
+/// `word` as the analyzers count it, if it holds a word at all.
+fn key(word: &str, config: &AnalyzerConfig) -> Option<String> {
+    let mut key = None;
+    tokenize(word, config, |word, _| {
+        key.get_or_insert_with(|| word.to_string());
+    });
+    key
+}
+
+fn flatten(context: &str) -> String {
+    context.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,970 @@
+use crate::error::{Result, Td5Error};
+use crate::hotwords::HotWords;
+#[cfg(feature = "stemming")]
+use crate::stem::Language;
+use crate::stopwords::StopwordList;
+use crate::tokenizer::Tokenizer;
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::Arc;
+
+/// What to do with a token longer than `AnalyzerConfig::long_token_limit`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LongTokenPolicy {
+    /// Count it like any other word.
+    #[default]
+    Keep,
+    /// Drop it from the word counts.
+    Skip,
+    /// Count its first `long_token_limit` bytes.
+    Truncate,
+    /// Count it as `LONG_TOKEN_SENTINEL`.
+    Sentinel,
+}
+
+/// What becomes of control characters: C0 and C1 controls and DEL, NUL
+/// included, but not the tab, line and page breaks `char::is_whitespace`
+/// accepts. Every policy counts them in `Diagnostics::control_chars`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ControlChars {
+    /// Drop them, as if never there: `a\0b` counts as `ab`.
+    #[default]
+    Strip,
+    /// Treat them as whitespace: `a\0b` counts as `a` and `b`.
+    Separate,
+    /// Refuse the text: `AnalyzerConfig::check_control_chars` fails on the
+    /// first one. The analyzers cannot fail, so they strip them.
+    Error,
+}
+
+/// How text is cut into words.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Segmentation {
+    /// Whitespace-separated tokens, keeping only their word characters:
+    /// `don't` counts as `dont`. The fast default.
+    #[default]
+    Whitespace,
+    /// Unicode word boundaries (`td5::segment`): `don't` and `3.14` stay
+    /// whole, each Han character is a word and combining marks stay on
+    /// their letter. A segment is counted whole, inner punctuation and
+    /// digits included, when it holds at least one word character. Slower:
+    /// every word takes the Unicode path.
+    Words,
+}
+
+/// The Unicode normalization form words are put in before they are
+/// counted (`td5::normalize`). ASCII text reads the same in every form.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Normalization {
+    /// Count words as written: `café` with `é` and with `e` and a
+    /// combining accent are two words.
+    #[default]
+    None,
+    /// Canonical composition: the two `café` are one word.
+    Nfc,
+    /// Compatibility composition too: `ﬁne` counts as `fine` and full-width
+    /// `ｗｏｒｄ` as `word`.
+    Nfkc,
+}
+
+/// What becomes of the punctuation inside a whitespace-separated token
+/// (`Segmentation::Whitespace` only). By default it is dropped, so `don't`
+/// counts as `dont` and `state-of-the-art` as `stateoftheart`. Digits are
+/// word characters with `AnalyzerConfig::digits`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Punctuation {
+    /// Cut the token into words at punctuation instead of dropping it:
+    /// `state-of-the-art` counts as `state`, `of`, `the` and `art`.
+    pub split: bool,
+    /// Keep an apostrophe (`'` or `’`, counted as `'`) between two word
+    /// characters: `don't`, `l'homme`.
+    pub apostrophes: bool,
+    /// Keep a hyphen (`-` or `‐`, counted as `-`) between two word
+    /// characters: `state-of-the-art`.
+    pub hyphens: bool,
+}
+
+/// `top_words`, `surface_forms` and `word_count` ranked by stem.
+pub(crate) type TopStems = (Vec<(String, usize)>, Vec<String>, usize);
+
+/// Word every over-long token is counted as under `LongTokenPolicy::Sentinel`.
+/// It cannot collide with a real word since `<` is never part of one.
+pub const LONG_TOKEN_SENTINEL: &str = "<long>";
+
+/// Default of `AnalyzerConfig::progress_every`: a few tens of milliseconds
+/// of the fast path.
+pub const PROGRESS_BYTES: usize = 16 << 20;
+
+/// A callback told how far an analysis has got: `(bytes_done, tokens_done)`,
+/// the input bytes counted so far and the words counted in them.
+#[derive(Clone)]
+pub struct Progress(Arc<dyn Fn(usize, usize) + Send + Sync>);
+
+impl Progress {
+    pub fn new(report: impl Fn(usize, usize) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(report))
+    }
+
+    pub fn report(&self, bytes_done: usize, tokens_done: usize) {
+        (self.0)(bytes_done, tokens_done)
+    }
+}
+
+impl fmt::Debug for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Progress")
+    }
+}
+
+impl PartialEq for Progress {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Progress {}
+
+/// A `Tokenizer` shared by the clones of a config, compared by identity.
+#[derive(Clone)]
+pub(crate) struct SharedTokenizer(pub(crate) Arc<dyn Tokenizer>);
+
+impl fmt::Debug for SharedTokenizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Tokenizer")
+    }
+}
+
+impl PartialEq for SharedTokenizer {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SharedTokenizer {}
+
+/// Options honored by every analyzer. `Default` reproduces the reference output.
+///
+/// ```
+/// use td5::{AnalyzerConfig, analyze_text_fast_with, analyze_text_slow_with};
+///
+/// let text = "The MP3 of the 1984 touring, the best of the A side";
+/// let config = AnalyzerConfig::new()
+///     .top_words(2)
+///     .longest_words(1)
+///     .min_word_length(2)
+///     .case_sensitive(true)
+///     .digits(true);
+/// for stats in [
+///     analyze_text_slow_with(text, &config),
+///     analyze_text_fast_with(text, &config),
+/// ] {
+///     assert_eq!(stats.top_words, [("the".to_string(), 3), ("of".to_string(), 2)]);
+///     assert_eq!(stats.longest_words, ["touring"]);
+///     assert_eq!(stats.diagnostics.short_words, 1);
+///     assert_eq!(stats.char_count, 39);
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnalyzerConfig {
+    pub(crate) long_token_limit: usize,
+    pub(crate) long_token_policy: LongTokenPolicy,
+    pub(crate) unseen_hot_words: bool,
+    pub(crate) top_k: usize,
+    pub(crate) longest_n: usize,
+    pub(crate) min_word_length: usize,
+    pub(crate) case_sensitive: bool,
+    pub(crate) digits: bool,
+    pub(crate) segmentation: Segmentation,
+    pub(crate) punctuation: Punctuation,
+    pub(crate) control_chars: ControlChars,
+    pub(crate) tokenizer: Option<SharedTokenizer>,
+    pub(crate) normalization: Normalization,
+    pub(crate) fold_diacritics: bool,
+    /// `None` for the vocabulary compiled in from `hot-words.txt`.
+    pub(crate) hot_words: Option<Arc<HotWords>>,
+    pub(crate) ngrams: usize,
+    pub(crate) stopwords: Option<Arc<StopwordList>>,
+    #[cfg(feature = "stemming")]
+    pub(crate) stemming: Option<Language>,
+    #[cfg(feature = "language-detection")]
+    pub(crate) detect_language: bool,
+    pub(crate) progress: Option<Progress>,
+    pub(crate) progress_bytes: usize,
+}
+
+impl Default for AnalyzerConfig {
+    fn default() -> Self {
+        Self {
+            long_token_limit: 32,
+            long_token_policy: LongTokenPolicy::Keep,
+            unseen_hot_words: false,
+            top_k: 10,
+            longest_n: 5,
+            min_word_length: 1,
+            case_sensitive: false,
+            digits: false,
+            segmentation: Segmentation::Whitespace,
+            punctuation: Punctuation::default(),
+            control_chars: ControlChars::Strip,
+            tokenizer: None,
+            normalization: Normalization::None,
+            fold_diacritics: false,
+            hot_words: None,
+            ngrams: 0,
+            stopwords: None,
+            #[cfg(feature = "stemming")]
+            stemming: None,
+            #[cfg(feature = "language-detection")]
+            detect_language: false,
+            progress: None,
+            progress_bytes: PROGRESS_BYTES,
+        }
+    }
+}
+
+impl AnalyzerConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tokens longer than `limit` bytes are handled by `policy` and counted in
+    /// `Diagnostics::long_tokens`.
+    pub fn long_tokens(mut self, limit: usize, policy: LongTokenPolicy) -> Self {
+        self.long_token_limit = limit.max(1);
+        self.long_token_policy = policy;
+        self
+    }
+
+    pub fn long_token_limit(&self) -> usize {
+        self.long_token_limit
+    }
+
+    pub fn long_token_policy(&self) -> LongTokenPolicy {
+        self.long_token_policy
+    }
+
+    /// With `keep`, text made only of hot words lists every hot word in
+    /// `top_words`, unseen ones with a count of 0, and counts all of them in
+    /// `word_count`: the fast path's output before it matched the reference.
+    /// Other text and the reference analyzer are unaffected.
+    pub fn unseen_hot_words(mut self, keep: bool) -> Self {
+        self.unseen_hot_words = keep;
+        self
+    }
+
+    pub fn keeps_unseen_hot_words(&self) -> bool {
+        self.unseen_hot_words
+    }
+
+    /// Length of `top_words` (default 10).
+    pub fn top_words(mut self, k: usize) -> Self {
+        self.top_k = k;
+        self
+    }
+
+    pub fn top_k(&self) -> usize {
+        self.top_k
+    }
+
+    /// Length of `longest_words` (default 5).
+    pub fn longest_words(mut self, n: usize) -> Self {
+        self.longest_n = n;
+        self
+    }
+
+    pub fn longest_n(&self) -> usize {
+        self.longest_n
+    }
+
+    /// Words of fewer than `chars` characters are not counted, only recorded
+    /// in `Diagnostics::short_words` (default 1: every word counts).
+    pub fn min_word_length(mut self, chars: usize) -> Self {
+        self.min_word_length = chars.max(1);
+        self
+    }
+
+    /// Shortest word counted, in characters.
+    pub fn shortest_word(&self) -> usize {
+        self.min_word_length
+    }
+
+    /// With `sensitive`, words keep their case: `The` and `the` are counted
+    /// apart (default: both count as `the`).
+    pub fn case_sensitive(mut self, sensitive: bool) -> Self {
+        self.case_sensitive = sensitive;
+        self
+    }
+
+    pub fn is_case_sensitive(&self) -> bool {
+        self.case_sensitive
+    }
+
+    /// With `count`, digits are word characters like letters: `mp3` stays
+    /// `mp3` instead of `mp`, `1984` is a word and digits add to `char_count`
+    /// (default: only letters count).
+    pub fn digits(mut self, count: bool) -> Self {
+        self.digits = count;
+        self
+    }
+
+    pub fn counts_digits(&self) -> bool {
+        self.digits
+    }
+
+    /// How words are found (default `Segmentation::Whitespace`).
+    ///
+    /// ```
+    /// use td5::{AnalyzerConfig, Segmentation, analyze_text_fast_with, analyze_text_slow_with};
+    ///
+    /// // "café" with a combining accent, then Japanese without spaces.
+    /// let text = "Don't cafe\u{301} don't 東京に行く";
+    /// let config = AnalyzerConfig::new().segmentation(Segmentation::Words);
+    /// for stats in [
+    ///     analyze_text_slow_with(text, &config),
+    ///     analyze_text_fast_with(text, &config),
+    /// ] {
+    ///     assert_eq!(stats.top_words[0], ("don't".to_string(), 2));
+    ///     assert_eq!(stats.total_words, 8);
+    ///     assert!(stats.top_words.contains(&("cafe\u{301}".to_string(), 1)));
+    /// }
+    /// let whitespace = analyze_text_fast_with(text, &AnalyzerConfig::new());
+    /// assert_eq!(whitespace.top_words[0], ("dont".to_string(), 2));
+    /// assert_eq!(whitespace.total_words, 4);
+    /// ```
+    pub fn segmentation(mut self, segmentation: Segmentation) -> Self {
+        self.segmentation = segmentation;
+        self
+    }
+
+    pub fn word_segmentation(&self) -> Segmentation {
+        self.segmentation
+    }
+
+    /// What becomes of punctuation inside a token (default: dropped). Any
+    /// other policy takes the Unicode path on every text, like
+    /// `Segmentation::Words`, so the analyzers agree on it.
+    ///
+    /// ```
+    /// use td5::{AnalyzerConfig, Punctuation, analyze_text_fast_with, analyze_text_slow_with};
+    ///
+    /// let text = "Don't use state-of-the-art tools, don't!";
+    /// let keep = AnalyzerConfig::new().punctuation(Punctuation {
+    ///     apostrophes: true,
+    ///     hyphens: true,
+    ///     ..Punctuation::default()
+    /// });
+    /// let split = AnalyzerConfig::new().punctuation(Punctuation {
+    ///     split: true,
+    ///     apostrophes: true,
+    ///     ..Punctuation::default()
+    /// });
+    /// for analyze in [analyze_text_slow_with, analyze_text_fast_with] {
+    ///     let stats = analyze(text, &keep);
+    ///     assert_eq!(stats.top_words[0], ("don't".to_string(), 2));
+    ///     assert_eq!(stats.longest_words[0], "state-of-the-art");
+    ///     assert_eq!(stats.total_words, 5);
+    ///     let stats = analyze(text, &split);
+    ///     assert_eq!(stats.top_words[0], ("don't".to_string(), 2));
+    ///     assert!(stats.top_words.contains(&("art".to_string(), 1)));
+    ///     assert_eq!(stats.total_words, 8);
+    /// }
+    /// ```
+    pub fn punctuation(mut self, punctuation: Punctuation) -> Self {
+        self.punctuation = punctuation;
+        self
+    }
+
+    pub fn punctuation_policy(&self) -> Punctuation {
+        self.punctuation
+    }
+
+    /// Finds words with `tokenizer` instead of the scanners, the
+    /// segmentation and the punctuation policy (see `tokenizer`). Every token
+    /// counts as written, punctuation included, cased as the config says.
+    pub fn tokenizer(mut self, tokenizer: impl Tokenizer + 'static) -> Self {
+        self.tokenizer = Some(SharedTokenizer(Arc::new(tokenizer)));
+        self
+    }
+
+    /// The tokenizer set by `tokenizer`, if any.
+    pub fn custom_tokenizer(&self) -> Option<&dyn Tokenizer> {
+        self.tokenizer.as_ref().map(|shared| &*shared.0)
+    }
+
+    /// Puts every token in `normalization` form before cutting it into
+    /// words (default: `Normalization::None`). Text that is not ASCII then
+    /// costs a normalization per token.
+    ///
+    /// ```
+    /// use td5::{AnalyzerConfig, Normalization, analyze_text_fast_with, analyze_text_slow_with};
+    ///
+    /// let text = "café cafe\u{301} ﬁne fine";
+    /// let nfc = AnalyzerConfig::new().normalization(Normalization::Nfc);
+    /// let nfkc = AnalyzerConfig::new().normalization(Normalization::Nfkc);
+    /// for analyze in [analyze_text_slow_with, analyze_text_fast_with] {
+    ///     // The combining accent is not a letter, so it is dropped.
+    ///     let stats = analyze(text, &AnalyzerConfig::new());
+    ///     assert!(stats.top_words.contains(&("cafe".to_string(), 1)));
+    ///     let stats = analyze(text, &nfc);
+    ///     assert_eq!(stats.top_words[0], ("café".to_string(), 2));
+    ///     assert_eq!(stats.word_count, 3);
+    ///     let stats = analyze(text, &nfkc);
+    ///     assert_eq!(stats.word_count, 2);
+    ///     assert_eq!(stats.char_count, 16);
+    /// }
+    /// ```
+    pub fn normalization(mut self, form: Normalization) -> Self {
+        self.normalization = form;
+        self
+    }
+
+    pub fn normalization_form(&self) -> Normalization {
+        self.normalization
+    }
+
+    /// With `fold`, diacritics are dropped from every token before it is
+    /// cut into words (`normalize::fold_diacritics`), so `café`, `cafe` and
+    /// `CAFÉ` count as one word. Tokens are then in NFC, or NFKC under
+    /// `Normalization::Nfkc`. Stopword lists are matched as given, so list
+    /// the folded forms in them.
+    ///
+    /// ```
+    /// use td5::{AnalyzerConfig, analyze_text_fast_with, analyze_text_slow_with};
+    ///
+    /// let text = "Café cafe\u{301} CAFE naïve naive Łódź";
+    /// let config = AnalyzerConfig::new().fold_diacritics(true);
+    /// for analyze in [analyze_text_slow_with, analyze_text_fast_with] {
+    ///     let stats = analyze(text, &config);
+    ///     assert_eq!(stats.top_words[0], ("cafe".to_string(), 3));
+    ///     assert_eq!(stats.top_words[1], ("naive".to_string(), 2));
+    ///     assert!(stats.top_words.contains(&("lodz".to_string(), 1)));
+    /// }
+    /// ```
+    pub fn fold_diacritics(mut self, fold: bool) -> Self {
+        self.fold_diacritics = fold;
+        self
+    }
+
+    pub fn folds_diacritics(&self) -> bool {
+        self.fold_diacritics
+    }
+
+    /// `token` under the normalization options, borrowed when they leave it
+    /// as it is, as they do any ASCII token.
+    #[inline]
+    pub(crate) fn normalized<'a>(&self, token: &'a str) -> Cow<'a, str> {
+        if !self.normalizes() || token.is_ascii() {
+            return Cow::Borrowed(token);
+        }
+        match (self.normalization, self.fold_diacritics) {
+            (Normalization::None | Normalization::Nfc, fold) => {
+                crate::normalize::normalize(token, false, true, fold)
+            }
+            (Normalization::Nfkc, fold) => crate::normalize::normalize(token, true, true, fold),
+        }
+    }
+
+    /// Whether `normalized` may change tokens.
+    #[inline]
+    pub(crate) fn normalizes(&self) -> bool {
+        self.normalization != Normalization::None || self.fold_diacritics
+    }
+
+    /// What becomes of control characters. Every analyzer applies it before
+    /// tokenizing, so they agree on text holding NUL bytes and the like.
+    ///
+    /// ```
+    /// use td5::{AnalyzerConfig, ControlChars, analyze_text_fast_with, analyze_text_slow_with};
+    ///
+    /// let text = "nul\0byte and bell\u{7}s";
+    /// let separate = AnalyzerConfig::new().control_chars(ControlChars::Separate);
+    /// for analyze in [analyze_text_slow_with, analyze_text_fast_with] {
+    ///     let stats = analyze(text, &AnalyzerConfig::new());
+    ///     assert_eq!(stats.longest_words[0], "nulbyte");
+    ///     assert_eq!(stats.total_words, 3);
+    ///     assert_eq!(stats.diagnostics.control_chars, 2);
+    ///     let stats = analyze(text, &separate);
+    ///     assert_eq!(stats.total_words, 5);
+    ///     assert_eq!(stats.diagnostics.control_chars, 2);
+    /// }
+    /// ```
+    pub fn control_chars(mut self, policy: ControlChars) -> Self {
+        self.control_chars = policy;
+        self
+    }
+
+    pub fn control_policy(&self) -> ControlChars {
+        self.control_chars
+    }
+
+    /// Fails on the first control character of `text` under
+    /// `ControlChars::Error`, naming it and its byte offset.
+    ///
+    /// ```
+    /// use td5::{AnalyzerConfig, ControlChars};
+    ///
+    /// let config = AnalyzerConfig::new().control_chars(ControlChars::Error);
+    /// assert!(config.check_control_chars("tab\tand newline\n").is_ok());
+    /// let err = config.check_control_chars("a\0b").unwrap_err();
+    /// assert_eq!(err.to_string(), "control character U+0000 at byte 1");
+    /// assert!(AnalyzerConfig::new().check_control_chars("a\0b").is_ok());
+    /// ```
+    pub fn check_control_chars(&self, text: &str) -> Result<()> {
+        if self.control_chars != ControlChars::Error || !may_hold_controls(text) {
+            return Ok(());
+        }
+        match text.char_indices().find(|&(_, ch)| is_control(ch)) {
+            Some((offset, character)) => Err(Td5Error::ControlChar { offset, character }),
+            None => Ok(()),
+        }
+    }
+
+    /// `text` under the control character policy, with how many it held.
+    /// Borrowed when it holds none, the common case.
+    pub(crate) fn control_text<'a>(&self, text: &'a str) -> (Cow<'a, str>, usize) {
+        if !may_hold_controls(text) {
+            return (Cow::Borrowed(text), 0);
+        }
+        let count = text.chars().filter(|&ch| is_control(ch)).count();
+        if count == 0 {
+            return (Cow::Borrowed(text), 0);
+        }
+        let mut kept = String::with_capacity(text.len());
+        for ch in text.chars() {
+            match (is_control(ch), self.control_chars) {
+                (false, _) => kept.push(ch),
+                // Spaces of the same byte length, so that offsets still hold.
+                (true, ControlChars::Separate) => {
+                    kept.extend(std::iter::repeat_n(' ', ch.len_utf8()))
+                }
+                (true, ControlChars::Strip | ControlChars::Error) => {}
+            }
+        }
+        (Cow::Owned(kept), count)
+    }
+
+    /// `word` as counted: lowercased as a whole unless the config is
+    /// case-sensitive, so that a final `Σ` becomes `ς`. The combining dot
+    /// `İ` lowercases to is dropped, like any mark on the Whitespace
+    /// segmentation, so `İstanbul` counts as `istanbul`.
+    #[inline]
+    pub(crate) fn cased(&self, word: &mut String) {
+        if self.case_sensitive {
+            return;
+        }
+        if word.is_ascii() {
+            word.make_ascii_lowercase();
+            return;
+        }
+        let mut lower = word.to_lowercase();
+        if word.contains('İ') {
+            lower.retain(|ch| ch != '\u{307}');
+        }
+        *word = lower;
+    }
+
+    /// Whether tokens go through `punctuated_words` rather than the
+    /// analyzers' own cleaning.
+    #[inline]
+    pub(crate) fn splits_punctuation(&self) -> bool {
+        self.segmentation == Segmentation::Whitespace && self.punctuation != Punctuation::default()
+    }
+
+    /// Passes each word of the whitespace-separated `token` under the
+    /// punctuation policy to `word`, cased as counted (`cased`), building it
+    /// in `buffer`. Returns the number of word characters in the token.
+    pub(crate) fn punctuated_words(
+        &self,
+        token: &str,
+        buffer: &mut String,
+        mut word: impl FnMut(&str),
+    ) -> usize {
+        let policy = self.punctuation;
+        let mut letters = 0;
+        let mut after_letter = false;
+        let mut chars = token.chars().peekable();
+        buffer.clear();
+        while let Some(ch) = chars.next() {
+            if self.is_word_char(ch) {
+                letters += 1;
+                buffer.push(ch);
+                after_letter = true;
+                continue;
+            }
+            let joiner = match ch {
+                '\'' | '’' if policy.apostrophes => Some('\''),
+                '-' | '‐' if policy.hyphens => Some('-'),
+                _ => None,
+            };
+            if let Some(joiner) = joiner
+                && after_letter
+                && chars.peek().is_some_and(|&next| self.is_word_char(next))
+            {
+                buffer.push(joiner);
+            } else if policy.split && !buffer.is_empty() {
+                self.cased(buffer);
+                word(buffer);
+                buffer.clear();
+            }
+            after_letter = false;
+        }
+        if !buffer.is_empty() {
+            self.cased(buffer);
+            word(buffer);
+        }
+        letters
+    }
+
+    /// With `n` of 2, `top_bigrams` lists the most frequent pairs of
+    /// consecutive words, as many as `top_words`; with 3, `top_trigrams`
+    /// the triples too (default 0: neither). N-grams run across line and
+    /// sentence ends, over the words as counted: a word left out by
+    /// `min_word_length` or `LongTokenPolicy::Skip` joins its neighbours.
+    ///
+    /// ```
+    /// use td5::{AnalyzerConfig, analyze_text_fast_with, analyze_text_slow_with};
+    ///
+    /// let text = "Once upon a time, once upon a hill";
+    /// let config = AnalyzerConfig::new().ngrams(3);
+    /// for stats in [
+    ///     analyze_text_slow_with(text, &config),
+    ///     analyze_text_fast_with(text, &config),
+    /// ] {
+    ///     assert_eq!(stats.top_bigrams[0], ("once upon".to_string(), 2));
+    ///     assert_eq!(stats.top_trigrams[0], ("once upon a".to_string(), 2));
+    ///     assert_eq!(stats.top_trigrams.len(), 5);
+    /// }
+    /// assert!(td5::analyze(text).top_bigrams.is_empty());
+    /// ```
+    pub fn ngrams(mut self, n: usize) -> Self {
+        self.ngrams = if n < 2 { 0 } else { n.min(3) };
+        self
+    }
+
+    /// Longest n-gram counted: 0, 2 or 3.
+    pub fn ngram_length(&self) -> usize {
+        self.ngrams
+    }
+
+    /// Leaves the words of `list` out of `top_words`, and the n-grams that
+    /// start or end with one out of `top_bigrams` and `top_trigrams`, so
+    /// that the lists show what a text is about rather than its function
+    /// words. They are still counted in `total_words`, `word_count` and
+    /// everywhere else.
+    ///
+    /// ```
+    /// use td5::AnalyzerConfig;
+    /// use td5::stopwords::StopwordList;
+    ///
+    /// let text = "The cat and the hat and the bat";
+    /// let config = AnalyzerConfig::new().stopwords(StopwordList::builtin("en").unwrap());
+    /// for stats in [
+    ///     td5::analyze_text_slow_with(text, &config),
+    ///     td5::analyze_text_fast_with(text, &config),
+    /// ] {
+    ///     let mut top: Vec<_> = stats.top_words.iter().map(|(w, _)| w.as_str()).collect();
+    ///     top.sort();
+    ///     assert_eq!(top, ["bat", "cat", "hat"]);
+    ///     assert_eq!((stats.total_words, stats.word_count), (8, 5));
+    /// }
+    /// // "the red", "cat and" and "and the" are left out; "red cat" stays.
+    /// let config = config.ngrams(2);
+    /// for stats in [
+    ///     td5::analyze_text_slow_with("the red cat and the red cat", &config),
+    ///     td5::analyze_text_fast_with("the red cat and the red cat", &config),
+    /// ] {
+    ///     assert_eq!(stats.top_bigrams, [("red cat".to_string(), 2)]);
+    /// }
+    /// ```
+    pub fn stopwords(mut self, list: StopwordList) -> Self {
+        self.stopwords = (!list.is_empty()).then(|| Arc::new(list));
+        self
+    }
+
+    pub fn stopword_list(&self) -> Option<&StopwordList> {
+        self.stopwords.as_deref()
+    }
+
+    /// Ranks words by stem in `top_words` and counts stems in `word_count`,
+    /// so that `optimization`, `optimizations` and `optimizing` make one
+    /// entry, `optim`. `surface_forms` gives the most frequent word counted
+    /// under each top stem. The other stats, `longest_words` included, keep
+    /// the words as written. A stem is left out as a stopword when its most
+    /// frequent word is one.
+    ///
+    /// ```
+    /// use td5::AnalyzerConfig;
+    /// use td5::stem::Language;
+    ///
+    /// let text = "Optimizing code: one optimization, then two optimizations.";
+    /// let config = AnalyzerConfig::new().stemming(Language::English);
+    /// for stats in [
+    ///     td5::analyze_text_slow_with(text, &config),
+    ///     td5::analyze_text_fast_with(text, &config),
+    /// ] {
+    ///     assert_eq!(stats.top_words[0], ("optim".to_string(), 3));
+    ///     assert_eq!(stats.surface_forms[0], "optimization");
+    ///     assert_eq!((stats.total_words, stats.word_count), (7, 5));
+    /// }
+    /// ```
+    #[cfg(feature = "stemming")]
+    pub fn stemming(mut self, language: Language) -> Self {
+        self.stemming = Some(language);
+        self
+    }
+
+    #[cfg(feature = "stemming")]
+    pub fn stemming_language(&self) -> Option<Language> {
+        self.stemming
+    }
+
+    /// With `stemming`, `top_words`, `surface_forms` and `word_count` from
+    /// the counted `words`: stems most frequent first, ties alphabetically,
+    /// each with its most frequent word (alphabetically first among equals).
+    #[cfg(feature = "stemming")]
+    pub(crate) fn top_stems<'a>(
+        &self,
+        words: impl IntoIterator<Item = (&'a str, usize)>,
+    ) -> Option<TopStems> {
+        let language = self.stemming?;
+        let mut stems: std::collections::HashMap<String, (usize, &str, usize)> =
+            std::collections::HashMap::new();
+        for (word, count) in words {
+            let stem = if self.case_sensitive {
+                language.stem(&word.to_lowercase()).into_owned()
+            } else {
+                language.stem(word).into_owned()
+            };
+            let (total, surface, most) = stems.entry(stem).or_insert((0, word, 0));
+            *total += count;
+            if count > *most || (count == *most && word < *surface) {
+                (*surface, *most) = (word, count);
+            }
+        }
+        let mut ranked: Vec<(String, usize, &str)> = stems
+            .into_iter()
+            .map(|(stem, (total, surface, _))| (stem, total, surface))
+            .collect();
+        ranked.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let unique = ranked.len();
+        let (top_words, surface_forms) = ranked
+            .into_iter()
+            .filter(|(_, _, surface)| !self.is_stopword(surface))
+            .take(self.top_k)
+            .map(|(stem, count, surface)| ((stem, count), surface.to_string()))
+            .unzip();
+        Some((top_words, surface_forms, unique))
+    }
+
+    #[cfg(not(feature = "stemming"))]
+    pub(crate) fn top_stems<'a>(
+        &self,
+        _: impl IntoIterator<Item = (&'a str, usize)>,
+    ) -> Option<TopStems> {
+        None
+    }
+
+    /// Fills `TextStats::detected_language` from the counted words (see
+    /// `td5::detect`). Results do not change otherwise.
+    ///
+    /// ```
+    /// use td5::AnalyzerConfig;
+    ///
+    /// let config = AnalyzerConfig::new().detect_language(true);
+    /// let text = "Le chat dort sur le tapis et les enfants sont dans le jardin.";
+    /// for stats in [
+    ///     td5::analyze_text_slow_with(text, &config),
+    ///     td5::analyze_text_fast_with(text, &config),
+    /// ] {
+    ///     assert_eq!(stats.detected_language.as_deref(), Some("fr"));
+    /// }
+    /// let stats = td5::analyze_text_fast_with(text, &AnalyzerConfig::new());
+    /// assert_eq!(stats.detected_language, None);
+    /// ```
+    #[cfg(feature = "language-detection")]
+    pub fn detect_language(mut self, detect: bool) -> Self {
+        self.detect_language = detect;
+        self
+    }
+
+    #[cfg(feature = "language-detection")]
+    pub fn detects_language(&self) -> bool {
+        self.detect_language
+    }
+
+    /// With `detect_language`, the language of the counted `words`.
+    #[cfg(feature = "language-detection")]
+    pub(crate) fn detected_language<'a>(
+        &self,
+        words: impl IntoIterator<Item = (&'a str, usize)>,
+    ) -> Option<String> {
+        if !self.detect_language {
+            return None;
+        }
+        crate::detect::language_of_counts(words).map(str::to_string)
+    }
+
+    #[cfg(not(feature = "language-detection"))]
+    pub(crate) fn detected_language<'a>(
+        &self,
+        _: impl IntoIterator<Item = (&'a str, usize)>,
+    ) -> Option<String> {
+        None
+    }
+
+    /// Whether the stats need every counted word, to rank stems or detect
+    /// the language, so that the fast path cannot rank its hot words on
+    /// their own.
+    pub(crate) fn needs_words(&self) -> bool {
+        #[cfg(feature = "stemming")]
+        if self.stemming.is_some() {
+            return true;
+        }
+        #[cfg(feature = "language-detection")]
+        if self.detect_language {
+            return true;
+        }
+        false
+    }
+
+    /// Calls `report(bytes_done, tokens_done)` about every
+    /// `progress_every` bytes of input while it is counted, and once at the
+    /// end, for a progress bar on inputs that take minutes. The fast and
+    /// slow analyzers report from their counting loops, the chunked ones
+    /// (`parallel`, `sharded`) as each chunk is counted, and
+    /// `StreamingAnalyzer` as each piece is pushed. The chunked analyzers
+    /// call it from their counting threads, so reports may arrive out of
+    /// order. Results do not change.
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use td5::AnalyzerConfig;
+    ///
+    /// let reports = Arc::new(Mutex::new(Vec::new()));
+    /// let config = AnalyzerConfig::new().progress_every(16).on_progress({
+    ///     let reports = Arc::clone(&reports);
+    ///     move |bytes, tokens| reports.lock().unwrap().push((bytes, tokens))
+    /// });
+    /// let stats = td5::analyze_text_fast_with("one two three four five six ten", &config);
+    /// assert_eq!(stats.total_words, 7);
+    /// assert_eq!(*reports.lock().unwrap(), [(18, 4), (31, 7)]);
+    /// ```
+    pub fn on_progress(mut self, report: impl Fn(usize, usize) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Progress::new(report));
+        self
+    }
+
+    pub fn progress(&self) -> Option<&Progress> {
+        self.progress.as_ref()
+    }
+
+    /// Input bytes between two progress reports (default `PROGRESS_BYTES`).
+    /// Smaller steps report more often and cost the chunked analyzers more
+    /// merging.
+    pub fn progress_every(mut self, bytes: usize) -> Self {
+        self.progress_bytes = bytes.max(1);
+        self
+    }
+
+    pub fn progress_bytes(&self) -> usize {
+        self.progress_bytes
+    }
+
+    /// Whether `word`, as counted, is left out of `top_words`.
+    /// Whether the n-gram `gram`, its words joined by spaces, starts or ends
+    /// with a stopword, which leaves it out of the n-gram lists.
+    pub(crate) fn is_stop_gram(&self, gram: &str) -> bool {
+        self.stopwords.is_some() && {
+            let first = gram.split(' ').next().unwrap_or(gram);
+            let last = gram.rsplit(' ').next().unwrap_or(gram);
+            self.is_stopword(first) || self.is_stopword(last)
+        }
+    }
+
+    #[inline]
+    pub(crate) fn is_stopword(&self, word: &str) -> bool {
+        self.stopwords.as_ref().is_some_and(|list| {
+            if self.case_sensitive {
+                list.contains(&word.to_lowercase())
+            } else {
+                list.contains(word)
+            }
+        })
+    }
+
+    /// Counts `words` in the fast path's array instead of the hot words
+    /// compiled in from `hot-words.txt`, so text from another domain gets
+    /// the no-hashing path without rebuilding td5. Results do not change,
+    /// only which words skip the hash map (and the hot words listed with
+    /// `unseen_hot_words`). Fails on words `HotWords::new` rejects.
+    ///
+    /// ```
+    /// let config = td5::AnalyzerConfig::new().with_hot_words(&["error", "warn", "info"])?;
+    /// let stats = td5::analyze_text_fast_with("info warn info error info", &config);
+    /// assert_eq!(stats.top_words[0], ("info".to_string(), 3));
+    /// assert_eq!(stats.longest_words, ["error", "info", "warn"]);
+    /// # Ok::<(), td5::Td5Error>(())
+    /// ```
+    pub fn with_hot_words(self, words: &[&str]) -> Result<Self> {
+        Ok(self.hot_vocabulary(HotWords::new(words)?))
+    }
+
+    /// `with_hot_words` with an already built vocabulary, e.g. one read by
+    /// `HotWords::parse` from a `td5 learn-hot` file.
+    pub fn hot_vocabulary(mut self, words: HotWords) -> Self {
+        self.hot_words = Some(Arc::new(words));
+        self
+    }
+
+    /// The vocabulary set by `with_hot_words`, if any.
+    pub fn custom_hot_words(&self) -> Option<&HotWords> {
+        self.hot_words.as_deref()
+    }
+
+    /// Whether `ch` belongs to a word.
+    #[inline]
+    pub(crate) fn is_word_char(&self, ch: char) -> bool {
+        ch.is_alphabetic() || (self.digits && ch.is_numeric())
+    }
+
+    /// Applies the long-token policy to a cleaned word. Returns the word to
+    /// count (if any) and whether the token was over-long.
+    pub(crate) fn limit_word<'a>(&self, word: &'a str) -> (Option<&'a str>, bool) {
+        if word.len() <= self.long_token_limit {
+            return (Some(word), false);
+        }
+        let kept = match self.long_token_policy {
+            LongTokenPolicy::Keep => Some(word),
+            LongTokenPolicy::Skip => None,
+            LongTokenPolicy::Truncate => {
+                let mut end = self.long_token_limit;
+                while !word.is_char_boundary(end) {
+                    end -= 1;
+                }
+                if end == 0 {
+                    end = word.chars().next().map_or(0, char::len_utf8);
+                }
+                Some(&word[..end])
+            }
+            LongTokenPolicy::Sentinel => Some(LONG_TOKEN_SENTINEL),
+        };
+        (kept, true)
+    }
+}
+
+/// Whether `ch` falls under `ControlChars`.
+#[inline]
+pub(crate) fn is_control(ch: char) -> bool {
+    ch.is_control() && !ch.is_whitespace()
+}
+
+/// False when `text` certainly holds no control character: no C0 byte other
+/// than whitespace, no DEL and no 0xC2, the lead byte of the C1 controls.
+/// Branch-free over each block, so it vectorizes.
+fn may_hold_controls(text: &str) -> bool {
+    text.as_bytes().chunks(64).any(|block| {
+        block.iter().fold(false, |found, &b| {
+            found | (b < 0x20 && !(0x09..=0x0d).contains(&b)) | (b == 0x7f) | (b == 0xc2)
+        })
+    })
+}
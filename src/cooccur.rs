@@ -0,0 +1,172 @@
+//! Word co-occurrence networks (`td5 cooccur`), exported as GraphML for
+//! Gephi or DOT for Graphviz.
+//!
+//! The nodes are the most frequent words; two of them are linked each time
+//! they appear within `window` words of each other, the edge weight being
+//! that number of times. Tokenization follows the reference analyzer:
+//! whitespace-separated tokens, lowercased, letters only, so words need no
+//! quoting or escaping in either format.
+
+use crate::json::Json;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Graph {
+    /// Words with their count, most frequent first, ties alphabetically.
+    pub nodes: Vec<(String, usize)>,
+    /// `(a, b, weight)` with `a < b` indices into `nodes`, heaviest first.
+    pub edges: Vec<(usize, usize, usize)>,
+}
+
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split_whitespace()
+        .map(|token| {
+            token
+                .to_lowercase()
+                .chars()
+                .filter(|c| c.is_alphabetic())
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+}
+
+impl Graph {
+    /// The network of the `top` most frequent words of `documents`, linked
+    /// when at most `window` words apart in the same document (`window` 1
+    /// links neighbours only). Edges lighter than `min_weight` are dropped,
+    /// and so are words left without an edge.
+    pub fn build(documents: &[&str], window: usize, top: usize, min_weight: usize) -> Graph {
+        let documents: Vec<Vec<String>> = documents.iter().map(|d| words(d).collect()).collect();
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for word in documents.iter().flatten() {
+            *counts.entry(word).or_insert(0) += 1;
+        }
+        let mut ranked: Vec<(&str, usize)> = counts.into_iter().collect();
+        ranked.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        ranked.truncate(top);
+        let index: HashMap<&str, usize> = ranked
+            .iter()
+            .enumerate()
+            .map(|(i, &(word, _))| (word, i))
+            .collect();
+
+        let mut weights: HashMap<(usize, usize), usize> = HashMap::new();
+        for tokens in &documents {
+            let nodes: Vec<Option<usize>> = tokens
+                .iter()
+                .map(|word| index.get(word.as_str()).copied())
+                .collect();
+            for (i, &a) in nodes.iter().enumerate() {
+                let Some(a) = a else { continue };
+                for &b in nodes[i + 1..].iter().take(window).flatten() {
+                    if a != b {
+                        *weights.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+        let mut edges: Vec<(usize, usize, usize)> = weights
+            .into_iter()
+            .filter(|&(_, w)| w >= min_weight.max(1))
+            .map(|((a, b), w)| (a, b, w))
+            .collect();
+        edges.sort_unstable_by(|x, y| y.2.cmp(&x.2).then_with(|| (x.0, x.1).cmp(&(y.0, y.1))));
+
+        // Renumber the nodes that kept an edge, in rank order.
+        let mut linked = vec![false; ranked.len()];
+        for &(a, b, _) in &edges {
+            linked[a] = true;
+            linked[b] = true;
+        }
+        let mut renumber = vec![0; ranked.len()];
+        let mut kept = Vec::new();
+        for (i, &(word, count)) in ranked.iter().enumerate() {
+            if linked[i] {
+                renumber[i] = kept.len();
+                kept.push((word.to_string(), count));
+            }
+        }
+        for edge in &mut edges {
+            edge.0 = renumber[edge.0];
+            edge.1 = renumber[edge.1];
+        }
+        Graph { nodes: kept, edges }
+    }
+
+    /// An undirected GraphML graph; nodes carry `label` and `count`, edges
+    /// `weight`.
+    pub fn to_graphml(&self) -> String {
+        let mut out = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+            "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n",
+            "  <key id=\"count\" for=\"node\" attr.name=\"count\" attr.type=\"int\"/>\n",
+            "  <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"int\"/>\n",
+            "  <graph id=\"cooccurrence\" edgedefault=\"undirected\">\n",
+        ));
+        for (i, (word, count)) in self.nodes.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "    <node id=\"n{i}\"><data key=\"label\">{word}</data><data key=\"count\">{count}</data></node>"
+            );
+        }
+        for (a, b, weight) in &self.edges {
+            let _ = writeln!(
+                out,
+                "    <edge source=\"n{a}\" target=\"n{b}\"><data key=\"weight\">{weight}</data></edge>"
+            );
+        }
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+
+    /// An undirected Graphviz graph with `weight` and a matching `penwidth`
+    /// on each edge.
+    pub fn to_dot(&self) -> String {
+        let heaviest = self.edges.first().map_or(1, |e| e.2) as f64;
+        let mut out = String::from("graph cooccurrence {\n  node [shape=ellipse];\n");
+        for (word, count) in &self.nodes {
+            let _ = writeln!(out, "  \"{word}\" [count={count}];");
+        }
+        for &(a, b, weight) in &self.edges {
+            let _ = writeln!(
+                out,
+                "  \"{}\" -- \"{}\" [weight={weight}, penwidth={:.2}];",
+                self.nodes[a].0,
+                self.nodes[b].0,
+                1.0 + 4.0 * weight as f64 / heaviest
+            );
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    pub fn to_json_value(&self) -> Json {
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|(word, count)| {
+                Json::Object(vec![
+                    ("word".into(), word.as_str().into()),
+                    ("count".into(), (*count).into()),
+                ])
+            })
+            .collect();
+        let edges = self
+            .edges
+            .iter()
+            .map(|&(a, b, weight)| {
+                Json::Object(vec![
+                    ("a".into(), self.nodes[a].0.as_str().into()),
+                    ("b".into(), self.nodes[b].0.as_str().into()),
+                    ("weight".into(), weight.into()),
+                ])
+            })
+            .collect();
+        Json::Object(vec![
+            ("nodes".into(), Json::Array(nodes)),
+            ("edges".into(), Json::Array(edges)),
+        ])
+    }
+}
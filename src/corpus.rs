@@ -0,0 +1,157 @@
+//! Real-world benchmark corpora, cached on disk.
+//!
+//! `gutenberg:<id>` names a Project Gutenberg plain-text book. Books are looked
+//! up in the cache first (`$TD5_CACHE_DIR`, else `$XDG_CACHE_HOME/td5`, else
+//! `~/.cache/td5`). Downloading a missing book needs the `gutenberg` feature,
+//! which fetches it with the system `curl`; without it, drop the file into the
+//! cache directory by hand.
+//!
+//! `sample:<name>` names one of the texts embedded by the `samples` feature.
+
+use crate::encoding;
+use crate::error::{Result, Td5Error};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corpus {
+    Gutenberg(u32),
+    Sample(&'static str),
+}
+
+impl Corpus {
+    pub fn parse(spec: &str) -> Result<Corpus> {
+        match spec.split_once(':') {
+            Some(("gutenberg", id)) => id
+                .parse()
+                .map(Corpus::Gutenberg)
+                .map_err(|_| Td5Error::config(format!("invalid Gutenberg book id `{id}`"))),
+            Some(("sample", name)) => sample(name).map(|(name, _)| Corpus::Sample(name)),
+            _ => Err(Td5Error::config(format!(
+                "unknown corpus `{spec}` (expected gutenberg:<id> or sample:<name>)"
+            ))),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Corpus::Gutenberg(id) => format!("gutenberg:{id}"),
+            Corpus::Sample(name) => format!("sample:{name}"),
+        }
+    }
+
+    /// Where a downloaded corpus is cached; `None` for embedded samples.
+    pub fn cache_path(&self) -> Option<PathBuf> {
+        match self {
+            Corpus::Gutenberg(id) => Some(cache_dir().join("gutenberg").join(format!("{id}.txt"))),
+            Corpus::Sample(_) => None,
+        }
+    }
+
+    /// The corpus text with the Gutenberg license header and footer removed,
+    /// downloading it into the cache first if needed and enabled.
+    pub fn load(&self) -> Result<String> {
+        let Some(path) = self.cache_path() else {
+            let Corpus::Sample(name) = self else {
+                unreachable!("only samples have no cache path")
+            };
+            return sample(name).map(|(_, text)| text.to_string());
+        };
+        if !path.exists() {
+            fetch(self, &path)?;
+        }
+        let bytes = std::fs::read(&path).map_err(|source| Td5Error::Io {
+            path: Some(path.clone()),
+            source,
+        })?;
+        let text = encoding::decode(&bytes).text;
+        Ok(strip_gutenberg_boilerplate(&text).to_string())
+    }
+}
+
+pub(crate) fn cache_dir() -> PathBuf {
+    let env = |name| std::env::var_os(name).filter(|v| !v.is_empty());
+    if let Some(dir) = env("TD5_CACHE_DIR") {
+        return dir.into();
+    }
+    if let Some(dir) = env("XDG_CACHE_HOME") {
+        return PathBuf::from(dir).join("td5");
+    }
+    env("HOME")
+        .map(|home| PathBuf::from(home).join(".cache").join("td5"))
+        .unwrap_or_else(|| std::env::temp_dir().join("td5"))
+}
+
+#[cfg(feature = "samples")]
+fn sample(name: &str) -> Result<(&'static str, &'static str)> {
+    crate::samples::all()
+        .into_iter()
+        .find(|(n, _)| *n == name)
+        .ok_or_else(|| Td5Error::config(format!("unknown sample `{name}`")))
+}
+
+#[cfg(not(feature = "samples"))]
+fn sample(name: &str) -> Result<(&'static str, &'static str)> {
+    Err(Td5Error::config(format!(
+        "sample `{name}` needs td5 built with the `samples` feature"
+    )))
+}
+
+#[cfg(feature = "gutenberg")]
+fn fetch(corpus: &Corpus, path: &std::path::Path) -> Result<()> {
+    let id = match corpus {
+        Corpus::Gutenberg(id) => id,
+        Corpus::Sample(_) => unreachable!("samples are never fetched"),
+    };
+    let url = format!("https://www.gutenberg.org/cache/epub/{id}/pg{id}.txt");
+    let io_error = |source| Td5Error::Io {
+        path: Some(path.to_path_buf()),
+        source,
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(io_error)?;
+    }
+    // Download next to the target and rename, so an interrupted fetch never
+    // leaves a truncated book in the cache.
+    let partial = path.with_extension("part");
+    let status = std::process::Command::new("curl")
+        .args([
+            "--fail",
+            "--silent",
+            "--show-error",
+            "--location",
+            "--output",
+        ])
+        .arg(&partial)
+        .arg(&url)
+        .status()
+        .map_err(|e| Td5Error::config(format!("cannot run curl to fetch {url}: {e}")))?;
+    if !status.success() {
+        let _ = std::fs::remove_file(&partial);
+        return Err(Td5Error::config(format!(
+            "downloading {url} failed ({status})"
+        )));
+    }
+    std::fs::rename(&partial, path).map_err(io_error)
+}
+
+#[cfg(not(feature = "gutenberg"))]
+fn fetch(corpus: &Corpus, path: &std::path::Path) -> Result<()> {
+    Err(Td5Error::config(format!(
+        "{} is not cached at {} and td5 was built without the `gutenberg` feature",
+        corpus.name(),
+        path.display()
+    )))
+}
+
+/// The text between the `*** START OF` and `*** END OF` marker lines, or all
+/// of it when the markers are missing.
+pub fn strip_gutenberg_boilerplate(text: &str) -> &str {
+    let start = text
+        .find("*** START OF")
+        .and_then(|i| text[i..].find('\n').map(|nl| i + nl + 1))
+        .unwrap_or(0);
+    let end = text[start..]
+        .find("*** END OF")
+        .map_or(text.len(), |i| start + i);
+    &text[start..end]
+}
@@ -0,0 +1,67 @@
+//! CSV output of `TextStats` (`--format csv`): one row per analyzed input,
+//! for spreadsheets and scripts that want a table rather than documents.
+//!
+//! Lists fit in one column each: `top_words` as `word:count` pairs and
+//! `longest_words` as words, separated by spaces (words never contain one).
+//! Fields are quoted when they hold a comma, a quote or a line break, and
+//! left empty when unknown (`line_count` and `sentence_count` of stats built
+//! from a word table).
+
+use crate::TextStats;
+use std::fmt::Write;
+
+pub const HEADER: &str = "source,word_count,total_words,char_count,line_count,sentence_count,mean_word_length,median_word_length,top_words,longest_words,time_ns";
+
+/// `value` as one CSV field.
+pub fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// A count, or an empty field when it is not known.
+pub(crate) fn known(n: Option<usize>) -> String {
+    n.map(|n| n.to_string()).unwrap_or_default()
+}
+
+/// `word:count` pairs separated by spaces, as the `top_words` column.
+pub(crate) fn counted(list: &[(String, usize)]) -> String {
+    let mut out = String::new();
+    for (i, (word, count)) in list.iter().enumerate() {
+        if i > 0 {
+            out.push(' ');
+        }
+        let _ = write!(out, "{word}:{count}");
+    }
+    out
+}
+
+impl TextStats {
+    /// The row of `source` under `HEADER`, without a line break; `time_ns`
+    /// is how long the analysis took, as the caller measured it.
+    ///
+    /// ```
+    /// let row = td5::analyze("the cat and the hat").to_csv_row("a, b.txt", 0);
+    /// assert!(row.starts_with("\"a, b.txt\",4,5,15,1,1,3,3,the:2 and:1 cat:1 hat:1,"));
+    /// assert!(row.ends_with(",0"));
+    /// ```
+    pub fn to_csv_row(&self, source: &str, time_ns: u128) -> String {
+        let top = counted(&self.top_words);
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{}",
+            field(source),
+            self.word_count,
+            self.total_words,
+            self.char_count,
+            known(self.line_count),
+            known(self.sentence_count),
+            self.mean_word_length,
+            self.median_word_length,
+            field(&top),
+            field(&self.longest_words.join(" ")),
+            time_ns
+        )
+    }
+}
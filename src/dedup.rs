@@ -0,0 +1,152 @@
+//! Near-duplicate documents in a corpus (`td5 dedup`), found before they
+//! skew aggregate counts.
+//!
+//! Each document is reduced to a MinHash fingerprint of its word shingles
+//! (runs of `shingle` consecutive lowercase words): the smallest value of
+//! each of `HASHES` hash functions over the shingle set. The share of equal
+//! positions between two fingerprints estimates the Jaccard similarity of
+//! their shingle sets. Candidate pairs come from locality-sensitive hashing
+//! (fingerprints cut into bands, documents sharing a band compared) so a
+//! corpus is not compared pair by pair.
+
+use crate::seed::mix;
+use std::collections::HashMap;
+
+/// Hash functions per fingerprint.
+pub const HASHES: usize = 64;
+/// Fingerprint positions per LSH band: pairs around 50% similar or more
+/// share a band with good odds.
+const ROWS: usize = 4;
+
+/// FNV-1a of the lowercased word.
+fn word_hash(word: &str) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    let mut buf = [0; 4];
+    for c in word.chars().flat_map(char::to_lowercase) {
+        for &byte in c.encode_utf8(&mut buf).as_bytes() {
+            hash = (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fingerprint([u64; HASHES]);
+
+impl Fingerprint {
+    /// The fingerprint of `text`'s `shingle`-word runs, or `None` without
+    /// any word. A text shorter than `shingle` words is one shingle.
+    pub fn of(text: &str, shingle: usize) -> Option<Fingerprint> {
+        Self::of_seeded(text, shingle, 0)
+    }
+
+    /// `of` with the hash functions picked by `seed`; only fingerprints of
+    /// the same seed can be compared. Seed 0 gives those of `of`.
+    ///
+    /// ```
+    /// use td5::dedup::Fingerprint;
+    ///
+    /// let text = "the quick brown fox jumps over the lazy dog";
+    /// assert_eq!(Fingerprint::of_seeded(text, 3, 0), Fingerprint::of(text, 3));
+    /// assert_ne!(Fingerprint::of_seeded(text, 3, 7), Fingerprint::of(text, 3));
+    /// ```
+    pub fn of_seeded(text: &str, shingle: usize, seed: u64) -> Option<Fingerprint> {
+        let seeds: [u64; HASHES] = std::array::from_fn(|i| mix((i as u64 + 1) ^ mix(seed)));
+        let words: Vec<u64> = text
+            .split(|c: char| !c.is_alphabetic())
+            .filter(|w| !w.is_empty())
+            .map(word_hash)
+            .collect();
+        if words.is_empty() {
+            return None;
+        }
+        let mut mins = [u64::MAX; HASHES];
+        for run in words.windows(shingle.clamp(1, words.len())) {
+            let shingle = run
+                .iter()
+                .fold(0, |acc: u64, &w| mix(acc.rotate_left(17) ^ w));
+            for (min, seed) in mins.iter_mut().zip(seeds) {
+                *min = (*min).min(mix(shingle ^ seed));
+            }
+        }
+        Some(Fingerprint(mins))
+    }
+
+    /// Estimated Jaccard similarity of the two shingle sets.
+    pub fn similarity(&self, other: &Fingerprint) -> f64 {
+        let equal = self.0.iter().zip(&other.0).filter(|(a, b)| a == b).count();
+        equal as f64 / HASHES as f64
+    }
+}
+
+/// Documents at least `threshold` similar to one another, directly or
+/// through other members.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cluster {
+    /// Indices into the fingerprints, in order, each with its similarity to
+    /// the first (1 for the first itself).
+    pub members: Vec<(usize, f64)>,
+}
+
+fn root(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+/// Groups near-duplicates among `fingerprints` (`None` for documents without
+/// words, never grouped). Only clusters of two or more documents are
+/// returned, ordered by their first member.
+pub fn clusters(fingerprints: &[Option<Fingerprint>], threshold: f64) -> Vec<Cluster> {
+    let mut buckets: HashMap<(usize, &[u64]), Vec<usize>> = HashMap::new();
+    for (i, fingerprint) in fingerprints.iter().enumerate() {
+        if let Some(Fingerprint(hashes)) = fingerprint {
+            for (band, rows) in hashes.chunks(ROWS).enumerate() {
+                buckets.entry((band, rows)).or_default().push(i);
+            }
+        }
+    }
+    let mut parent: Vec<usize> = (0..fingerprints.len()).collect();
+    // Pairs already joined are skipped, so the clusters are the connected
+    // similar pairs whatever the bucket order.
+    for bucket in buckets.values() {
+        for (n, &i) in bucket.iter().enumerate() {
+            for &j in &bucket[..n] {
+                let (ri, rj) = (root(&mut parent, i), root(&mut parent, j));
+                if ri == rj {
+                    continue;
+                }
+                if let (Some(a), Some(b)) = (&fingerprints[i], &fingerprints[j])
+                    && a.similarity(b) >= threshold
+                {
+                    parent[ri.max(rj)] = ri.min(rj);
+                }
+            }
+        }
+    }
+    let mut groups: Vec<Vec<usize>> = vec![Vec::new(); fingerprints.len()];
+    for i in 0..fingerprints.len() {
+        let r = root(&mut parent, i);
+        groups[r].push(i);
+    }
+    groups
+        .into_iter()
+        .filter(|group| group.len() > 1)
+        .map(|group| {
+            let first = fingerprints[group[0]].as_ref();
+            let members = group
+                .iter()
+                .map(|&i| {
+                    let similarity = match (first, &fingerprints[i]) {
+                        (Some(a), Some(b)) => a.similarity(b),
+                        _ => 0.0,
+                    };
+                    (i, similarity)
+                })
+                .collect();
+            Cluster { members }
+        })
+        .collect()
+}
@@ -0,0 +1,92 @@
+//! Deny lists: words or phrases that must not appear, e.g. profanity in
+//! user-generated content or banned terms in documentation.
+//!
+//! Entries use the phrase pattern syntax of `phrases` (`damn`, `shit*`,
+//! `kill|hurt yourself`), one per line; blank lines and `#` comments are
+//! ignored. Matching is case-insensitive, and a multi-word entry does not
+//! match across a sentence boundary.
+
+use crate::error::Result;
+use crate::lint;
+use crate::phrases::{Pattern, PhraseList, PhraseSet};
+use std::collections::HashMap;
+
+pub struct DenyList {
+    set: PhraseSet,
+}
+
+/// One occurrence of a denied entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DenyMatch {
+    pub line: usize,
+    pub column: usize,
+    /// The words that matched, lowercase.
+    pub text: String,
+    /// The entry they matched.
+    pub entry: String,
+}
+
+impl DenyList {
+    pub fn new(entries: &[&str]) -> Result<DenyList> {
+        Ok(Self::from_list(PhraseList::new("deny", entries)?))
+    }
+
+    pub fn parse(source: &str) -> Result<DenyList> {
+        let patterns = source
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(Pattern::parse)
+            .collect::<Result<_>>()?;
+        Ok(Self::from_list(PhraseList {
+            name: "deny".into(),
+            patterns,
+        }))
+    }
+
+    fn from_list(list: PhraseList) -> DenyList {
+        DenyList {
+            set: PhraseSet::new(vec![list]),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.set.lists()[0].patterns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Every match in `text`, in reading order.
+    pub fn scan(&self, text: &str) -> Vec<DenyMatch> {
+        let words = lint::words(text);
+        let lower: Vec<&str> = words.iter().map(|w| w.lower.as_str()).collect();
+        let breaks: Vec<bool> = words.iter().map(|w| w.starts_sentence).collect();
+        let patterns = &self.set.lists()[0].patterns;
+        let mut matches: Vec<DenyMatch> = self
+            .set
+            .find(&lower, &breaks)
+            .into_iter()
+            .map(|m| DenyMatch {
+                line: words[m.start].line,
+                column: words[m.start].column,
+                text: lower[m.start..m.start + m.len].join(" "),
+                entry: patterns[m.pattern].source.clone(),
+            })
+            .collect();
+        matches.sort_by_key(|m| (m.line, m.column));
+        matches
+    }
+}
+
+/// Matches per entry, most frequent first, ties broken alphabetically.
+pub fn counts(matches: &[DenyMatch]) -> Vec<(&str, usize)> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for m in matches {
+        *counts.entry(m.entry.as_str()).or_default() += 1;
+    }
+    let mut counts: Vec<(&str, usize)> = counts.into_iter().collect();
+    counts.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    counts
+}
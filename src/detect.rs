@@ -0,0 +1,132 @@
+//! Language detection for `AnalyzerConfig::detect_language` and
+//! `analyze --auto`: which language a text is written in, from how often
+//! its most common function words occur.
+//!
+//! Each language has a profile of frequent words that the others rarely
+//! use (`the`, `und`, `het`, `não`...), so a few sentences are enough. Words
+//! shared by several languages, such as `de` or `en`, are left out of every
+//! profile. Text too short or too mixed to tell gives `None`.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Languages that can be detected, as ISO 639-1 codes.
+pub const LANGUAGES: &[&str] = &["de", "en", "es", "fr", "it", "nl", "pt"];
+
+/// Bytes of the start of a text that `language` reads.
+pub const SAMPLE_BYTES: usize = 64 << 10;
+
+/// Profile words a text needs at least before its language is named.
+pub const MIN_HITS: usize = 5;
+
+/// Profiles, in the order of `LANGUAGES`. No word is in two of them.
+const PROFILES: [&[&str]; 7] = [
+    &[
+        "der", "die", "und", "das", "ist", "nicht", "ein", "eine", "zu", "den", "mit", "sich",
+        "auf", "dem", "auch", "ich", "sie", "wir", "von", "wird", "werden", "sind", "oder", "dass",
+        "nach", "bei", "aus", "noch",
+    ],
+    &[
+        "the", "and", "of", "to", "is", "that", "it", "was", "for", "with", "you", "this", "be",
+        "are", "have", "not", "but", "they", "his", "from", "which", "were", "she", "what",
+        "would", "he", "had", "at", "by",
+    ],
+    &[
+        "el", "los", "las", "y", "más", "pero", "sus", "fue", "muy", "también", "cuando", "hay",
+        "ya", "yo", "hasta", "así", "puede", "tiene", "ahora", "mucho", "donde",
+    ],
+    &[
+        "le", "les", "des", "et", "est", "une", "dans", "qui", "pour", "pas", "sur", "au", "avec",
+        "ce", "du", "elle", "sont", "nous", "vous", "ne", "cette", "aux", "été", "être", "leur",
+    ],
+    &[
+        "di", "che", "non", "per", "della", "sono", "anche", "più", "nel", "alla", "questo", "gli",
+        "ed", "degli", "delle", "dei", "essere", "molto", "perché",
+    ],
+    &[
+        "het", "een", "van", "dat", "niet", "zijn", "op", "voor", "met", "aan", "ook", "maar",
+        "bij", "nog", "wordt", "worden", "deze", "naar", "uit", "hij", "zij", "heeft", "hebben",
+        "kan",
+    ],
+    &[
+        "não", "uma", "com", "os", "em", "ao", "dos", "das", "mais", "foi", "também", "muito",
+        "ele", "isso", "são", "quando", "pelo", "pela", "seu", "sua",
+    ],
+];
+
+/// Each profile word with the index of its language.
+fn profiles() -> &'static HashMap<&'static str, usize> {
+    static PROFILES_BY_WORD: OnceLock<HashMap<&'static str, usize>> = OnceLock::new();
+    PROFILES_BY_WORD.get_or_init(|| {
+        PROFILES
+            .iter()
+            .enumerate()
+            .flat_map(|(language, words)| words.iter().map(move |&word| (word, language)))
+            .collect()
+    })
+}
+
+/// The language of a text from its word counts: the one whose profile words
+/// occur most, if at least `MIN_HITS` times and twice as often as those of
+/// any other language.
+///
+/// ```
+/// use td5::detect::language_of_counts;
+///
+/// let counts = [("het", 3), ("is", 2), ("een", 2), ("boek", 1)];
+/// assert_eq!(language_of_counts(counts), Some("nl"));
+/// assert_eq!(language_of_counts([("het", 3), ("the", 3)]), None);
+/// ```
+pub fn language_of_counts<'a>(
+    words: impl IntoIterator<Item = (&'a str, usize)>,
+) -> Option<&'static str> {
+    let profiles = profiles();
+    let mut hits = [0; LANGUAGES.len()];
+    for (word, count) in words {
+        // No profile word is longer than 8 bytes.
+        if word.len() > 8 {
+            continue;
+        }
+        let language = if word.chars().any(char::is_uppercase) {
+            profiles.get(word.to_lowercase().as_str())
+        } else {
+            profiles.get(word)
+        };
+        if let Some(&language) = language {
+            hits[language] += count;
+        }
+    }
+    let best = (0..hits.len()).max_by_key(|&i| hits[i])?;
+    let runner_up = (0..hits.len())
+        .filter(|&i| i != best)
+        .map(|i| hits[i])
+        .max()
+        .unwrap_or(0);
+    (hits[best] >= MIN_HITS && hits[best] >= 2 * runner_up).then_some(LANGUAGES[best])
+}
+
+/// The language of `text`, from its first `SAMPLE_BYTES` cut into runs of
+/// letters.
+///
+/// ```
+/// use td5::detect::language;
+///
+/// assert_eq!(language("The cat sat on the mat, and it was happy with that."), Some("en"));
+/// assert_eq!(language("Le chat est sur le tapis et il dort avec les autres."), Some("fr"));
+/// assert_eq!(language("Der Hund ist nicht im Haus, und die Katze auch nicht."), Some("de"));
+/// assert_eq!(language("Hello world"), None);
+/// ```
+pub fn language(text: &str) -> Option<&'static str> {
+    let mut end = text.len().min(SAMPLE_BYTES);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for word in text[..end]
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|word| !word.is_empty())
+    {
+        *counts.entry(word).or_insert(0) += 1;
+    }
+    language_of_counts(counts)
+}
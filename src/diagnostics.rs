@@ -0,0 +1,48 @@
+/// Declares `Diagnostics` from a list of counters so that merging and
+/// serialization pick up new counters automatically.
+macro_rules! diagnostics {
+    ($($(#[$doc:meta])* $name:ident,)*) => {
+        /// Counters explaining how the input was treated.
+        #[derive(Debug, Clone, Default, PartialEq, Eq)]
+        pub struct Diagnostics {
+            $($(#[$doc])* pub $name: usize,)*
+        }
+
+        impl Diagnostics {
+            /// Every counter with its serialized name.
+            pub fn counters(&self) -> impl Iterator<Item = (&'static str, usize)> {
+                [$((stringify!($name), self.$name)),*].into_iter()
+            }
+
+            pub fn counter_mut(&mut self, name: &str) -> Option<&mut usize> {
+                match name {
+                    $(stringify!($name) => Some(&mut self.$name),)*
+                    _ => None,
+                }
+            }
+
+            pub fn merge(&mut self, other: &Diagnostics) {
+                $(self.$name += other.$name;)*
+            }
+        }
+    };
+}
+
+diagnostics! {
+    /// Tokens longer than `AnalyzerConfig::long_token_limit`.
+    long_tokens,
+    /// Over-long tokens dropped under `LongTokenPolicy::Skip`.
+    long_tokens_skipped,
+    /// Whitespace-separated tokens without a single letter (numbers,
+    /// punctuation, symbols; digits are letters under
+    /// `AnalyzerConfig::digits`), which are not counted as words.
+    non_alphabetic,
+    /// Words shorter than `AnalyzerConfig::min_word_length`, which are not
+    /// counted.
+    short_words,
+    /// Invalid UTF-8 sequences replaced by `analyze_bytes`.
+    invalid_utf8,
+    /// Control characters (NUL, other C0 and C1 controls, DEL), handled by
+    /// `AnalyzerConfig::control_chars`.
+    control_chars,
+}
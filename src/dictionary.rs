@@ -0,0 +1,81 @@
+//! Word lists to check a vocabulary against: what a text uses that the
+//! dictionary lacks (OCR errors, typos, jargon).
+//!
+//! A dictionary file has one word per line, matched case-insensitively. Blank
+//! lines and `#` comments are ignored, and Hunspell `.dic` files work as they
+//! are: the leading entry count is skipped and `/FLAGS` suffixes are dropped
+//! (affix rules are not applied, so only the listed stems are known).
+//!
+//! Filtering never touches the counting loop: the text is counted as usual
+//! and each distinct word is looked up once afterwards, so a dictionary costs
+//! one hash probe per vocabulary entry rather than per token.
+
+use crate::fast::{self, Counts, FastHasher, Shape};
+use crate::{AnalyzerConfig, TextStats, WordFrequency};
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Default)]
+pub struct Dictionary {
+    words: HashSet<String, FastHasher>,
+}
+
+impl Dictionary {
+    pub fn parse(source: &str) -> Dictionary {
+        let mut lines = source.lines().map(str::trim).peekable();
+        if lines
+            .peek()
+            .is_some_and(|l| !l.is_empty() && l.bytes().all(|b| b.is_ascii_digit()))
+        {
+            lines.next();
+        }
+        let words = lines
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(|l| {
+                let word = l.split_once('/').map_or(l, |(stem, _)| stem);
+                word.trim().to_lowercase()
+            })
+            .filter(|w| !w.is_empty())
+            .collect();
+        Dictionary { words }
+    }
+
+    /// Whether `word` is listed, ignoring case.
+    pub fn contains(&self, word: &str) -> bool {
+        self.words.contains(word) || self.words.contains(&word.to_lowercase())
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Stats over only the words the dictionary knows, counted like
+    /// `analyze_text_fast`. `char_count` and word lengths cover the kept
+    /// words; lines and sentences are the whole text's.
+    pub fn analyze_known(&self, text: &str) -> TextStats {
+        let config = AnalyzerConfig::default();
+        let counts = fast::count(text, &config);
+        let (diagnostics, shape) = (counts.diagnostics.clone(), counts.shape.clone());
+        let mut map = counts.into_map();
+        // Counted words are already lowercase.
+        map.retain(|word, _| self.words.contains(word.as_str()));
+        let char_count = map.iter().map(|(w, c)| w.chars().count() * c).sum();
+        let lengths = Shape::of_words(map.iter().map(|(w, &c)| (w.as_str(), c)));
+        let mut known = Counts::from_map(map, char_count);
+        known.diagnostics = diagnostics;
+        known.shape = lengths.with_lines_of(&shape);
+        fast::finish(known, &config)
+    }
+
+    /// Words of `freq` missing from the dictionary, most frequent first, ties
+    /// broken alphabetically.
+    pub fn unknown<'a>(&self, freq: &'a WordFrequency) -> Vec<(&'a str, usize)> {
+        let mut unknown: Vec<(&str, usize)> =
+            freq.iter().filter(|(w, _)| !self.contains(w)).collect();
+        unknown.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        unknown
+    }
+}
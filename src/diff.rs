@@ -0,0 +1,282 @@
+//! What changed between two texts (`td5 compare`): the headline metrics of
+//! each, the vocabulary they share, the words only one of them uses and the
+//! words whose frequency moved the most.
+//!
+//! Frequencies are compared per thousand words, so that a revision twice as
+//! long as the original does not make every word look more frequent.
+//! `TextStats::diff` only has the top words of each side to go on, so its
+//! word lists are those of the top lists; `StatsDiff::between` takes the
+//! full frequency tables and is exact. The former is what compares two
+//! results saved earlier, when the texts are no longer at hand.
+
+use crate::TextStats;
+use crate::check::Metric;
+#[cfg(feature = "fs")]
+use crate::clock::Stopwatch;
+#[cfg(feature = "fs")]
+use crate::error::Result;
+use crate::freq::WordFrequency;
+use crate::json::Json;
+use std::collections::HashMap;
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+/// The metrics set side by side.
+pub const METRICS: &[Metric] = &[
+    Metric::TotalWords,
+    Metric::UniqueWords,
+    Metric::TypeTokenRatio,
+    Metric::Chars,
+    Metric::Lines,
+    Metric::Sentences,
+    Metric::MeanWordLength,
+    Metric::Top10Share,
+];
+
+/// A word whose frequency changed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shift {
+    pub word: String,
+    pub a: usize,
+    pub b: usize,
+    /// Occurrences per thousand words in `b` minus those in `a`.
+    pub change: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatsDiff {
+    /// Each of `METRICS` in `a` and in `b`.
+    pub metrics: Vec<(Metric, f64, f64)>,
+    /// Distinct words compared on each side.
+    pub vocabulary: (usize, usize),
+    /// Distinct words found in both.
+    pub shared: usize,
+    /// Shared words over the union of both vocabularies.
+    pub jaccard: f64,
+    /// The most frequent words of each side missing from the other.
+    pub only_a: Vec<(String, usize)>,
+    pub only_b: Vec<(String, usize)>,
+    /// The largest changes first, rises and falls alike.
+    pub shifts: Vec<Shift>,
+}
+
+impl TextStats {
+    /// How `other` differs from `self`, from the top words of each.
+    ///
+    /// ```
+    /// let draft = td5::analyze("the cat sat on the mat");
+    /// let revision = td5::analyze("the dog sat on the dog bed");
+    /// let diff = draft.diff(&revision);
+    /// assert_eq!(diff.shared, 3);
+    /// assert_eq!(diff.only_a, [("cat".to_string(), 1), ("mat".to_string(), 1)]);
+    /// assert_eq!(diff.only_b, [("dog".to_string(), 2), ("bed".to_string(), 1)]);
+    /// assert_eq!(diff.shifts[0].word, "dog");
+    /// ```
+    pub fn diff(&self, other: &TextStats) -> StatsDiff {
+        StatsDiff::build(
+            self,
+            other,
+            &top_table(self),
+            &top_table(other),
+            self.top_words.len().max(other.top_words.len()),
+        )
+    }
+}
+
+impl StatsDiff {
+    /// How `b` differs from `a`, with the words of their full frequency
+    /// tables, `top` per list.
+    pub fn between(
+        a: &TextStats,
+        b: &TextStats,
+        freq_a: &WordFrequency,
+        freq_b: &WordFrequency,
+        top: usize,
+    ) -> StatsDiff {
+        Self::build(a, b, &full_table(freq_a), &full_table(freq_b), top)
+    }
+
+    fn build(
+        a: &TextStats,
+        b: &TextStats,
+        words_a: &HashMap<&str, usize>,
+        words_b: &HashMap<&str, usize>,
+        top: usize,
+    ) -> StatsDiff {
+        let shared = words_a.keys().filter(|w| words_b.contains_key(*w)).count();
+        let union = words_a.len() + words_b.len() - shared;
+        // Most frequent first, ties alphabetically.
+        let only = |x: &HashMap<&str, usize>, y: &HashMap<&str, usize>| {
+            let mut only: Vec<(String, usize)> = x
+                .iter()
+                .filter(|(w, _)| !y.contains_key(*w))
+                .map(|(w, n)| (w.to_string(), *n))
+                .collect();
+            only.sort_unstable_by(|p, q| q.1.cmp(&p.1).then_with(|| p.0.cmp(&q.0)));
+            only.truncate(top);
+            only
+        };
+        let per_thousand = |count: usize, total: usize| 1000.0 * count as f64 / total.max(1) as f64;
+        let mut shifts: Vec<Shift> = words_a
+            .keys()
+            .chain(words_b.keys().filter(|w| !words_a.contains_key(*w)))
+            .map(|&word| {
+                let (n_a, n_b) = (
+                    words_a.get(word).copied().unwrap_or(0),
+                    words_b.get(word).copied().unwrap_or(0),
+                );
+                Shift {
+                    word: word.to_string(),
+                    a: n_a,
+                    b: n_b,
+                    change: per_thousand(n_b, b.total_words) - per_thousand(n_a, a.total_words),
+                }
+            })
+            .filter(|shift| shift.change != 0.0)
+            .collect();
+        shifts.sort_unstable_by(|p, q| {
+            q.change
+                .abs()
+                .total_cmp(&p.change.abs())
+                .then_with(|| p.word.cmp(&q.word))
+        });
+        shifts.truncate(top);
+        StatsDiff {
+            metrics: METRICS
+                .iter()
+                .map(|&m| (m, m.value(a), m.value(b)))
+                .collect(),
+            vocabulary: (words_a.len(), words_b.len()),
+            shared,
+            jaccard: if union == 0 {
+                0.0
+            } else {
+                shared as f64 / union as f64
+            },
+            only_a: only(words_a, words_b),
+            only_b: only(words_b, words_a),
+            shifts,
+        }
+    }
+
+    pub fn to_json_value(&self) -> Json {
+        let list = |words: &[(String, usize)]| {
+            Json::Array(
+                words
+                    .iter()
+                    .map(|(w, n)| Json::Array(vec![w.as_str().into(), (*n).into()]))
+                    .collect(),
+            )
+        };
+        let metrics = self
+            .metrics
+            .iter()
+            .map(|&(metric, a, b)| {
+                (
+                    metric.name().to_string(),
+                    Json::Object(vec![
+                        ("a".into(), a.into()),
+                        ("b".into(), b.into()),
+                        ("change".into(), (b - a).into()),
+                    ]),
+                )
+            })
+            .collect();
+        let shifts = self
+            .shifts
+            .iter()
+            .map(|shift| {
+                Json::Object(vec![
+                    ("word".into(), shift.word.as_str().into()),
+                    ("a".into(), shift.a.into()),
+                    ("b".into(), shift.b.into()),
+                    ("change".into(), shift.change.into()),
+                ])
+            })
+            .collect();
+        Json::Object(vec![
+            ("metrics".into(), Json::Object(metrics)),
+            ("vocabulary_a".into(), self.vocabulary.0.into()),
+            ("vocabulary_b".into(), self.vocabulary.1.into()),
+            ("shared".into(), self.shared.into()),
+            ("jaccard".into(), self.jaccard.into()),
+            ("only_a".into(), list(&self.only_a)),
+            ("only_b".into(), list(&self.only_b)),
+            ("shifts".into(), Json::Array(shifts)),
+        ])
+    }
+}
+
+/// A side of `td5 compare`: a result saved by `analyze --format json`, or a
+/// text analyzed with its full frequency table.
+#[cfg(feature = "fs")]
+#[derive(Debug, Clone)]
+pub struct Side {
+    pub stats: TextStats,
+    /// The saved `time_ns`, or the time of the analysis.
+    pub time_ns: u128,
+    /// `None` for a saved result, which only has its top words.
+    pub freq: Option<WordFrequency>,
+}
+
+#[cfg(feature = "fs")]
+impl Side {
+    /// Reads `path`: the file's only JSON document or its last line, the
+    /// whole of the last input, else the file as a text.
+    pub fn read(path: impl AsRef<Path>) -> Result<Side> {
+        let (text, _) = crate::input::read_transcoded(path)?;
+        let last = text.lines().rev().find(|line| !line.trim().is_empty());
+        let saved = [Some(text.as_ref()), last]
+            .into_iter()
+            .flatten()
+            .find_map(|doc| {
+                let doc = crate::json::parse(doc).ok()?;
+                let time_ns = doc.get("time_ns").and_then(Json::as_u64).unwrap_or(0);
+                Some((TextStats::from_json_value(doc).ok()?, u128::from(time_ns)))
+            });
+        if let Some((stats, time_ns)) = saved {
+            return Ok(Side {
+                stats,
+                time_ns,
+                freq: None,
+            });
+        }
+        let watch = Stopwatch::start();
+        let stats = crate::analyze(&text);
+        let time_ns = watch.elapsed_ns();
+        Ok(Side {
+            stats,
+            time_ns,
+            freq: Some(WordFrequency::from_text(&text)),
+        })
+    }
+
+    /// How `b` differs from `self`, `top` words per list: exact when both
+    /// sides were texts, else from the top words of each.
+    pub fn diff(&self, b: &Side, top: usize) -> StatsDiff {
+        match (&self.freq, &b.freq) {
+            (Some(freq_a), Some(freq_b)) => {
+                StatsDiff::between(&self.stats, &b.stats, freq_a, freq_b, top)
+            }
+            _ => {
+                let mut diff = self.stats.diff(&b.stats);
+                diff.only_a.truncate(top);
+                diff.only_b.truncate(top);
+                diff.shifts.truncate(top);
+                diff
+            }
+        }
+    }
+}
+
+fn top_table(stats: &TextStats) -> HashMap<&str, usize> {
+    stats
+        .top_words
+        .iter()
+        .map(|(w, n)| (w.as_str(), *n))
+        .collect()
+}
+
+fn full_table(freq: &WordFrequency) -> HashMap<&str, usize> {
+    freq.iter().collect()
+}
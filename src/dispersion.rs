@@ -0,0 +1,154 @@
+//! How evenly the most frequent words are spread through a text, to tell a
+//! word mentioned everywhere from one mentioned often in a single passage.
+//!
+//! Two measures are reported for each word, both from the positions of its
+//! occurrences among the text's tokens:
+//!
+//! - Juilland's D over `PARTS` equal slices of the text: 1 when every slice
+//!   has the same count, 0 when one slice has them all.
+//! - Burstiness, `(σ - μ) / (σ + μ)` of the gaps between successive
+//!   occurrences, the text wrapping around so the first and last gaps join:
+//!   -1 for evenly spaced occurrences, about 0 for random ones, towards 1 for
+//!   occurrences packed together.
+//!
+//! Words are those the analyzers count under the same config
+//! (`td5::tokenize`). Stopwords take up their positions among the tokens but
+//! are not ranked, as they are left out of `top_words`.
+
+use crate::config::AnalyzerConfig;
+use crate::json::Json;
+use std::collections::HashMap;
+
+/// Slices of the text for Juilland's D and the peak share.
+pub const PARTS: usize = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WordDispersion {
+    pub word: String,
+    pub count: usize,
+    /// Juilland's D, 0 to 1.
+    pub dispersion: f64,
+    /// -1 to 1.
+    pub burstiness: f64,
+    /// Share of the occurrences in the busiest slice.
+    pub peak: f64,
+    /// That slice, 0-based.
+    pub peak_part: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dispersion {
+    /// Tokens in the text.
+    pub tokens: usize,
+    /// Most bursty first, ties by count.
+    pub words: Vec<WordDispersion>,
+}
+
+impl Dispersion {
+    /// The dispersion of `text`'s `top` most frequent words other than
+    /// `config`'s stopwords; words seen once have no spread and are left out.
+    ///
+    /// ```
+    /// use td5::AnalyzerConfig;
+    /// use td5::dispersion::Dispersion;
+    /// use td5::stopwords::StopwordList;
+    ///
+    /// let text = "the cat saw the dog and the cat ran";
+    /// let config = AnalyzerConfig::new().stopwords(StopwordList::builtin("en").unwrap());
+    /// let dispersion = Dispersion::of(text, 10, &config);
+    /// assert_eq!(dispersion.tokens, 9);
+    /// let words: Vec<&str> = dispersion.words.iter().map(|w| w.word.as_str()).collect();
+    /// assert_eq!(words, ["cat"]);
+    /// ```
+    pub fn of(text: &str, top: usize, config: &AnalyzerConfig) -> Dispersion {
+        let mut positions: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut tokens = 0;
+        crate::tokenize(text, config, |word, _| {
+            match positions.get_mut(word) {
+                Some(at) => at.push(tokens),
+                None => {
+                    positions.insert(word.to_string(), vec![tokens]);
+                }
+            }
+            tokens += 1;
+        });
+        let mut ranked: Vec<(String, Vec<usize>)> = positions
+            .into_iter()
+            .filter(|(word, at)| at.len() > 1 && !config.is_stopword(word))
+            .collect();
+        ranked.sort_unstable_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.cmp(&b.0)));
+        ranked.truncate(top);
+        let mut words: Vec<WordDispersion> = ranked
+            .into_iter()
+            .map(|(word, at)| measure(word, &at, tokens))
+            .collect();
+        words.sort_by(|a, b| {
+            b.burstiness
+                .total_cmp(&a.burstiness)
+                .then_with(|| b.count.cmp(&a.count))
+                .then_with(|| a.word.cmp(&b.word))
+        });
+        Dispersion { tokens, words }
+    }
+
+    pub fn to_json_value(&self) -> Json {
+        let words = self
+            .words
+            .iter()
+            .map(|w| {
+                Json::Object(vec![
+                    ("word".into(), w.word.as_str().into()),
+                    ("count".into(), w.count.into()),
+                    ("dispersion".into(), w.dispersion.into()),
+                    ("burstiness".into(), w.burstiness.into()),
+                    ("peak".into(), w.peak.into()),
+                    ("peak_part".into(), w.peak_part.into()),
+                ])
+            })
+            .collect();
+        Json::Object(vec![
+            ("tokens".into(), self.tokens.into()),
+            ("parts".into(), PARTS.into()),
+            ("words".into(), Json::Array(words)),
+        ])
+    }
+}
+
+/// Mean and standard deviation (population).
+fn spread(values: impl Iterator<Item = f64> + Clone) -> (f64, f64) {
+    let n = values.clone().count() as f64;
+    let mean = values.clone().sum::<f64>() / n;
+    let variance = values.map(|v| (v - mean) * (v - mean)).sum::<f64>() / n;
+    (mean, variance.sqrt())
+}
+
+/// `at` holds increasing token positions out of `tokens`.
+fn measure(word: String, at: &[usize], tokens: usize) -> WordDispersion {
+    let mut parts = [0usize; PARTS];
+    for &i in at {
+        parts[i * PARTS / tokens] += 1;
+    }
+    let (mean, sd) = spread(parts.iter().map(|&n| n as f64));
+    let dispersion = (1.0 - sd / mean / ((PARTS - 1) as f64).sqrt()).clamp(0.0, 1.0);
+    let (peak_part, &busiest) = parts
+        .iter()
+        .enumerate()
+        .rev()
+        .max_by_key(|&(_, n)| n)
+        .unwrap_or((0, &0));
+
+    let wrap = tokens - at[at.len() - 1] + at[0];
+    let gaps = at
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]) as f64)
+        .chain([wrap as f64]);
+    let (mean, sd) = spread(gaps);
+    WordDispersion {
+        word,
+        count: at.len(),
+        dispersion,
+        burstiness: (sd - mean) / (sd + mean),
+        peak: busiest as f64 / at.len() as f64,
+        peak_part,
+    }
+}
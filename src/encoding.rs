@@ -0,0 +1,182 @@
+//! Encoding detection and transcoding to UTF-8.
+//!
+//! Detection order: byte-order mark, then a NUL-pattern check for BOM-less
+//! UTF-16, then UTF-8 validation, and finally Windows-1252 (a superset of
+//! Latin-1, which is what older French documents usually are).
+
+use std::borrow::Cow;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    /// Windows-1252, which also decodes ISO-8859-1 (Latin-1) text.
+    Windows1252,
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+            Encoding::Windows1252 => "windows-1252",
+        })
+    }
+}
+
+impl Encoding {
+    /// The encoding displayed as `name`.
+    pub fn from_name(name: &str) -> Option<Encoding> {
+        [
+            Encoding::Utf8,
+            Encoding::Utf16Le,
+            Encoding::Utf16Be,
+            Encoding::Windows1252,
+        ]
+        .into_iter()
+        .find(|encoding| encoding.to_string() == name)
+    }
+}
+
+/// How a file's bytes became the text analyzed, for reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transcoding {
+    pub encoding: Encoding,
+    pub had_bom: bool,
+    /// Whether the text differs from the file: a BOM dropped, another
+    /// encoding converted or invalid sequences replaced.
+    pub transcoded: bool,
+}
+
+#[derive(Debug)]
+pub struct Decoded<'a> {
+    pub text: Cow<'a, str>,
+    pub encoding: Encoding,
+    pub had_bom: bool,
+    /// Sequences that could not be decoded and became U+FFFD.
+    pub replaced: usize,
+}
+
+impl Decoded<'_> {
+    /// Whether `text` differs from the input bytes.
+    pub fn transcoded(&self) -> bool {
+        matches!(self.text, Cow::Owned(_))
+    }
+
+    /// ```
+    /// use td5::encoding::{self, Encoding};
+    ///
+    /// let latin1 = encoding::decode(b"caf\xe9").transcoding();
+    /// assert_eq!(latin1.encoding, Encoding::Windows1252);
+    /// assert!(latin1.transcoded);
+    /// let bom = encoding::decode(b"\xef\xbb\xbfcafe").transcoding();
+    /// assert!(bom.had_bom && bom.transcoded);
+    /// assert!(!encoding::decode(b"cafe").transcoding().transcoded);
+    /// ```
+    pub fn transcoding(&self) -> Transcoding {
+        Transcoding {
+            encoding: self.encoding,
+            had_bom: self.had_bom,
+            transcoded: self.had_bom || self.transcoded(),
+        }
+    }
+}
+
+/// Guesses the encoding of `bytes`. Returns it with the length of any BOM.
+pub fn detect(bytes: &[u8]) -> (Encoding, usize) {
+    match bytes {
+        [0xEF, 0xBB, 0xBF, ..] => return (Encoding::Utf8, 3),
+        [0xFF, 0xFE, ..] => return (Encoding::Utf16Le, 2),
+        [0xFE, 0xFF, ..] => return (Encoding::Utf16Be, 2),
+        _ => {}
+    }
+    if let Some(utf16) = sniff_utf16(bytes) {
+        return (utf16, 0);
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        (Encoding::Utf8, 0)
+    } else {
+        (Encoding::Windows1252, 0)
+    }
+}
+
+/// Mostly-Latin UTF-16 has a zero in every other byte.
+fn sniff_utf16(bytes: &[u8]) -> Option<Encoding> {
+    let sample = &bytes[..bytes.len().min(4096) & !1];
+    let pairs = sample.len() / 2;
+    if pairs < 2 {
+        return None;
+    }
+    let even_zeros = sample.iter().step_by(2).filter(|&&b| b == 0).count();
+    let odd_zeros = sample
+        .iter()
+        .skip(1)
+        .step_by(2)
+        .filter(|&&b| b == 0)
+        .count();
+    if odd_zeros * 10 >= pairs * 4 && even_zeros * 10 < pairs {
+        Some(Encoding::Utf16Le)
+    } else if even_zeros * 10 >= pairs * 4 && odd_zeros * 10 < pairs {
+        Some(Encoding::Utf16Be)
+    } else {
+        None
+    }
+}
+
+/// Detects the encoding and converts to UTF-8, borrowing when possible.
+pub fn decode(bytes: &[u8]) -> Decoded<'_> {
+    let (encoding, bom) = detect(bytes);
+    decode_as(&bytes[bom..], encoding, bom > 0)
+}
+
+pub fn decode_as(bytes: &[u8], encoding: Encoding, had_bom: bool) -> Decoded<'_> {
+    let (text, replaced) = match encoding {
+        Encoding::Utf8 => crate::bytes::decode_lossy(bytes),
+        Encoding::Utf16Le => decode_utf16(bytes, u16::from_le_bytes),
+        Encoding::Utf16Be => decode_utf16(bytes, u16::from_be_bytes),
+        Encoding::Windows1252 => (
+            Cow::Owned(bytes.iter().map(|&b| windows_1252(b)).collect()),
+            0,
+        ),
+    };
+    Decoded {
+        text,
+        encoding,
+        had_bom,
+        replaced,
+    }
+}
+
+fn decode_utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> (Cow<'static, str>, usize) {
+    let units = bytes.chunks_exact(2).map(|pair| unit([pair[0], pair[1]]));
+    let mut replaced = bytes.len() % 2;
+    let mut text = String::with_capacity(bytes.len() / 2);
+    for ch in char::decode_utf16(units) {
+        text.push(ch.unwrap_or_else(|_| {
+            replaced += 1;
+            char::REPLACEMENT_CHARACTER
+        }));
+    }
+    if bytes.len() % 2 == 1 {
+        text.push(char::REPLACEMENT_CHARACTER);
+    }
+    (Cow::Owned(text), replaced)
+}
+
+/// 0x80..=0x9F differ from Latin-1; undefined slots map to the C1 control.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+
+fn windows_1252(b: u8) -> char {
+    match b {
+        0x80..=0x9F => WINDOWS_1252_HIGH[(b - 0x80) as usize],
+        _ => b as char,
+    }
+}
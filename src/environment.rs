@@ -0,0 +1,115 @@
+//! Machine and build description attached to benchmark reports.
+//!
+//! Timings only compare meaningfully on the same CPU, governor and build; a
+//! report loaded from disk carries its `Environment` so `differences` can say
+//! why two runs disagree before anyone reads a speedup out of them.
+
+use crate::json::Json;
+use std::fs;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Environment {
+    pub cpu_model: String,
+    pub cores: usize,
+    /// cpufreq scaling governor of cpu0 (`performance`, `powersave`...).
+    pub governor: String,
+    pub os: String,
+    pub rustc: String,
+    pub profile: String,
+    pub opt_level: String,
+    pub target: String,
+    /// Target features enabled at compile time, comma-separated.
+    pub target_features: String,
+}
+
+const UNKNOWN: &str = "unknown";
+
+impl Environment {
+    pub fn capture() -> Self {
+        Self {
+            cpu_model: cpu_model().unwrap_or_else(|| UNKNOWN.into()),
+            cores: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            governor: read_trimmed("/sys/devices/system/cpu/cpu0/cpufreq/scaling_governor")
+                .unwrap_or_else(|| UNKNOWN.into()),
+            os: format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH),
+            rustc: env!("TD5_RUSTC_VERSION").into(),
+            profile: env!("TD5_PROFILE").into(),
+            opt_level: env!("TD5_OPT_LEVEL").into(),
+            target: env!("TD5_TARGET").into(),
+            target_features: env!("TD5_TARGET_FEATURES").into(),
+        }
+    }
+
+    /// Fields that differ, as `name: ours vs theirs` lines.
+    pub fn differences(&self, other: &Environment) -> Vec<String> {
+        self.fields()
+            .into_iter()
+            .zip(other.fields())
+            .filter(|((_, a), (_, b))| a != b)
+            .map(|((name, a), (_, b))| format!("{name}: {a} vs {b}"))
+            .collect()
+    }
+
+    fn fields(&self) -> [(&'static str, String); 9] {
+        [
+            ("cpu_model", self.cpu_model.clone()),
+            ("cores", self.cores.to_string()),
+            ("governor", self.governor.clone()),
+            ("os", self.os.clone()),
+            ("rustc", self.rustc.clone()),
+            ("profile", self.profile.clone()),
+            ("opt_level", self.opt_level.clone()),
+            ("target", self.target.clone()),
+            ("target_features", self.target_features.clone()),
+        ]
+    }
+
+    pub fn to_json_value(&self) -> Json {
+        Json::Object(
+            self.fields()
+                .into_iter()
+                .map(|(name, value)| {
+                    let value = match name {
+                        "cores" => self.cores.into(),
+                        _ => value.into(),
+                    };
+                    (name.to_string(), value)
+                })
+                .collect(),
+        )
+    }
+
+    /// Missing fields decode as `unknown`, so older reports still load.
+    pub fn from_json_value(doc: &Json) -> Self {
+        let text = |name: &str| {
+            doc.get(name)
+                .and_then(Json::as_str)
+                .unwrap_or(UNKNOWN)
+                .to_string()
+        };
+        Self {
+            cpu_model: text("cpu_model"),
+            cores: doc.get("cores").and_then(Json::as_u64).unwrap_or(0) as usize,
+            governor: text("governor"),
+            os: text("os"),
+            rustc: text("rustc"),
+            profile: text("profile"),
+            opt_level: text("opt_level"),
+            target: text("target"),
+            target_features: text("target_features"),
+        }
+    }
+}
+
+fn read_trimmed(path: &str) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+fn cpu_model() -> Option<String> {
+    let cpuinfo = fs::read_to_string("/proc/cpuinfo").ok()?;
+    cpuinfo
+        .lines()
+        .find(|l| l.starts_with("model name") || l.starts_with("Model"))
+        .and_then(|l| l.split_once(':'))
+        .map(|(_, v)| v.trim().to_string())
+}
@@ -0,0 +1,167 @@
+//! EPUB books (the `epub` feature): the text of each chapter in reading
+//! order.
+//!
+//! The archive's `META-INF/container.xml` names the package document, whose
+//! spine lists the chapters; each XHTML chapter goes through `html::to_text`.
+//! A chapter's title is its first `h1`–`h3` heading, else its `<title>`, else
+//! its file name. Chapters without any word (cover pages, image plates) are
+//! left out. DRM-protected books cannot be read.
+
+use crate::error::{Result, Td5Error};
+use crate::html::{self, attribute, tag_name};
+use crate::zip::Archive;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chapter {
+    pub title: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Book {
+    /// The `dc:title` of the package, if any.
+    pub title: Option<String>,
+    pub chapters: Vec<Chapter>,
+}
+
+/// Tags of an XML or HTML document: the element name, whether it closes,
+/// the text inside `<...>` and the byte range of the whole tag.
+fn tags(doc: &str) -> impl Iterator<Item = (String, bool, &str, usize, usize)> {
+    let mut at = 0;
+    std::iter::from_fn(move || {
+        let open = at + doc[at..].find('<')?;
+        let close = open + doc[open..].find('>')?;
+        at = close + 1;
+        let inside = &doc[open + 1..close];
+        let (name, closing) = tag_name(inside);
+        Some((name, closing, inside, open, close + 1))
+    })
+}
+
+fn invalid(message: &str) -> Td5Error {
+    Td5Error::config(format!("EPUB: {message}"))
+}
+
+fn read_text(archive: &Archive, name: &str) -> Result<String> {
+    let bytes = archive.read(name)?;
+    String::from_utf8(bytes).map_err(|_| invalid(&format!("`{name}` is not UTF-8")))
+}
+
+/// `href` relative to the directory of `base`, with `%XX` escapes and any
+/// `#fragment` removed.
+fn resolve(base: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or("");
+    let mut decoded = Vec::with_capacity(href.len());
+    let mut bytes = href.bytes();
+    while let Some(b) = bytes.next() {
+        let escaped = (b == b'%')
+            .then(|| {
+                let hex = [bytes.clone().next()?, bytes.clone().nth(1)?];
+                u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()
+            })
+            .flatten();
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                bytes.nth(1);
+            }
+            None => decoded.push(b),
+        }
+    }
+    let href = String::from_utf8_lossy(&decoded);
+    let mut parts: Vec<&str> = base.split('/').collect();
+    parts.pop();
+    for part in href.split('/') {
+        match part {
+            ".." => {
+                parts.pop();
+            }
+            "." | "" => {}
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+/// The text of the first element named one of `names` in `doc`.
+fn element_text(doc: &str, names: &[&str]) -> Option<String> {
+    let mut tags = tags(doc);
+    let (name, _, _, _, start) =
+        tags.find(|(n, closing, ..)| !closing && names.contains(&n.as_str()))?;
+    let (.., end, _) = tags.find(|(n, closing, ..)| *closing && *n == name)?;
+    let text = html::to_text(&doc[start..end]);
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+impl Book {
+    pub fn parse(bytes: &[u8]) -> Result<Book> {
+        let archive = Archive::parse(bytes)?;
+        if archive.names().any(|n| n == "META-INF/encryption.xml") {
+            return Err(invalid("the book is encrypted"));
+        }
+        let container = read_text(&archive, "META-INF/container.xml")?;
+        let package_path = tags(&container)
+            .find(|(name, ..)| name == "rootfile")
+            .and_then(|(_, _, inside, ..)| attribute(inside, "full-path"))
+            .ok_or_else(|| invalid("no rootfile in container.xml"))?;
+        let package = read_text(&archive, &package_path)?;
+
+        let mut items: Vec<(String, String)> = Vec::new();
+        let mut spine: Vec<String> = Vec::new();
+        for (name, closing, inside, ..) in tags(&package) {
+            match name.as_str() {
+                "item" if !closing => {
+                    let media = attribute(inside, "media-type").unwrap_or_default();
+                    if let (Some(id), Some(href), true) = (
+                        attribute(inside, "id"),
+                        attribute(inside, "href"),
+                        media.contains("html"),
+                    ) {
+                        items.push((id, resolve(&package_path, &href)));
+                    }
+                }
+                "itemref" if !closing => spine.extend(attribute(inside, "idref")),
+                _ => {}
+            }
+        }
+        if spine.is_empty() {
+            return Err(invalid("empty spine"));
+        }
+
+        let mut chapters = Vec::new();
+        for idref in &spine {
+            let Some((_, path)) = items.iter().find(|(id, _)| id == idref) else {
+                continue;
+            };
+            let doc = read_text(&archive, path)?;
+            let text = html::to_text(&doc);
+            if text.split_whitespace().next().is_none() {
+                continue;
+            }
+            let title = element_text(&doc, &["h1", "h2", "h3"])
+                .or_else(|| element_text(&doc, &["title"]))
+                .unwrap_or_else(|| path.rsplit('/').next().unwrap_or(path).to_string());
+            chapters.push(Chapter { title, text });
+        }
+        Ok(Book {
+            title: element_text(&package, &["title"]),
+            chapters,
+        })
+    }
+
+    /// Every chapter's text, separated by blank lines, and the byte offset
+    /// at which each chapter starts.
+    pub fn text(&self) -> (String, Vec<usize>) {
+        let mut text = String::new();
+        let mut starts = Vec::with_capacity(self.chapters.len());
+        for chapter in &self.chapters {
+            if !text.is_empty() {
+                text.push_str("\n\n");
+            }
+            starts.push(text.len());
+            text.push_str(chapter.text.trim_end());
+        }
+        (text, starts)
+    }
+}
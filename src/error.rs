@@ -0,0 +1,90 @@
+use crate::schema::DecodeError;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// Every way a `td5` operation can fail.
+#[derive(Debug)]
+pub enum Td5Error {
+    Io {
+        path: Option<PathBuf>,
+        source: io::Error,
+    },
+    InvalidUtf8 {
+        path: Option<PathBuf>,
+        valid_up_to: usize,
+    },
+    Config(String),
+    LimitExceeded {
+        limit: &'static str,
+        max: usize,
+        actual: usize,
+    },
+    Decode(DecodeError),
+    ControlChar {
+        offset: usize,
+        character: char,
+    },
+}
+
+pub type Result<T, E = Td5Error> = std::result::Result<T, E>;
+
+impl Td5Error {
+    pub fn config(message: impl Into<String>) -> Self {
+        Td5Error::Config(message.into())
+    }
+}
+
+fn write_path(f: &mut fmt::Formatter<'_>, path: &Option<PathBuf>) -> fmt::Result {
+    match path {
+        Some(path) => write!(f, "{}: ", path.display()),
+        None => Ok(()),
+    }
+}
+
+impl fmt::Display for Td5Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Td5Error::Io { path, source } => {
+                write_path(f, path)?;
+                write!(f, "{source}")
+            }
+            Td5Error::InvalidUtf8 { path, valid_up_to } => {
+                write_path(f, path)?;
+                write!(f, "invalid UTF-8 after byte {valid_up_to}")
+            }
+            Td5Error::Config(message) => write!(f, "invalid configuration: {message}"),
+            Td5Error::LimitExceeded { limit, max, actual } => {
+                write!(f, "{limit} limit exceeded: {actual} > {max}")
+            }
+            Td5Error::Decode(e) => e.fmt(f),
+            Td5Error::ControlChar { offset, character } => write!(
+                f,
+                "control character U+{:04X} at byte {offset}",
+                *character as u32
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Td5Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Td5Error::Io { source, .. } => Some(source),
+            Td5Error::Decode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Td5Error {
+    fn from(source: io::Error) -> Self {
+        Td5Error::Io { path: None, source }
+    }
+}
+
+impl From<DecodeError> for Td5Error {
+    fn from(e: DecodeError) -> Self {
+        Td5Error::Decode(e)
+    }
+}
@@ -0,0 +1,75 @@
+//! Rich display in Rust notebooks (`evcxr` feature).
+//!
+//! evcxr, the kernel behind Rust in Jupyter, shows a value by calling its
+//! `evcxr_display` method when it has one; the method prints the content
+//! between `EVCXR_BEGIN_CONTENT <mime>` and `EVCXR_END_CONTENT` lines. Nothing
+//! else is needed from the kernel, so the feature adds no dependency. With
+//! it, evaluating an analysis in a cell shows tables instead of the `Debug`
+//! dump.
+
+use crate::TextStats;
+use crate::html;
+
+impl TextStats {
+    /// The stats as HTML tables: the totals, the top words with their share
+    /// of the text, the longest words and any non-zero diagnostic.
+    ///
+    /// ```
+    /// let html = td5::analyze("the cat and the hat").to_html();
+    /// assert!(html.contains("<td>the</td><td>2</td><td>40.0%</td>"));
+    /// ```
+    pub fn to_html(&self) -> String {
+        let mut out = String::from("<div class=\"td5-stats\">\n<table>\n");
+        let rows = [
+            ("Words", self.total_words.to_string()),
+            ("Distinct words", self.word_count.to_string()),
+            ("Letters", self.char_count.to_string()),
+        ];
+        for (label, value) in rows {
+            out.push_str(&format!("<tr><th>{label}</th><td>{value}</td></tr>\n"));
+        }
+        out.push_str("</table>\n");
+
+        if !self.top_words.is_empty() {
+            out.push_str("<table>\n<tr><th>Top word</th><th>Count</th><th>Share</th></tr>\n");
+            let total = self.total_words.max(1) as f64;
+            for (word, count) in &self.top_words {
+                out.push_str(&format!(
+                    "<tr><td>{}</td><td>{count}</td><td>{:.1}%</td></tr>\n",
+                    html::escape(word),
+                    *count as f64 / total * 100.0
+                ));
+            }
+            out.push_str("</table>\n");
+        }
+
+        if !self.longest_words.is_empty() {
+            let words: Vec<String> = self
+                .longest_words
+                .iter()
+                .map(|w| format!("<code>{}</code>", html::escape(w)))
+                .collect();
+            out.push_str(&format!("<p>Longest: {}</p>\n", words.join(", ")));
+        }
+
+        let diagnostics: Vec<String> = self
+            .diagnostics
+            .counters()
+            .filter(|&(_, n)| n > 0)
+            .map(|(name, n)| format!("{name}: {n}"))
+            .collect();
+        if !diagnostics.is_empty() {
+            out.push_str(&format!("<p>Diagnostics: {}</p>\n", diagnostics.join(", ")));
+        }
+        out.push_str("</div>");
+        out
+    }
+
+    /// Called by evcxr to show the value of a cell.
+    pub fn evcxr_display(&self) {
+        println!(
+            "EVCXR_BEGIN_CONTENT text/html\n{}\nEVCXR_END_CONTENT",
+            self.to_html()
+        );
+    }
+}
@@ -0,0 +1,102 @@
+//! Classic Unix tools timed next to the analyzers (`td5 bench --against`),
+//! to answer how much faster the Rust code really is than the one-liners it
+//! replaces.
+//!
+//! Each tool reads the input from a temporary file and is timed as a whole
+//! process, start-up included, while analyzers work on text already in
+//! memory; on small inputs the tools mostly measure `fork`/`exec`.
+
+use crate::bench::{BenchInput, BenchResult, Summary};
+use crate::clock::Stopwatch;
+use crate::error::{Result, Td5Error};
+use crate::input::TempFile;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExternalTool {
+    /// `wc -w`: a word count, no frequencies.
+    Wc,
+    /// `grep -c`: lines containing a letter, a single scan.
+    Grep,
+    /// `tr | sort | uniq -c`: the textbook word-frequency pipeline.
+    Uniq,
+}
+
+impl ExternalTool {
+    pub const ALL: [ExternalTool; 3] = [ExternalTool::Wc, ExternalTool::Grep, ExternalTool::Uniq];
+
+    pub fn parse(name: &str) -> Option<ExternalTool> {
+        Self::ALL.into_iter().find(|t| t.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ExternalTool::Wc => "wc",
+            ExternalTool::Grep => "grep",
+            ExternalTool::Uniq => "uniq",
+        }
+    }
+
+    /// The shell command run on the input file `$1`.
+    pub fn script(self) -> &'static str {
+        match self {
+            ExternalTool::Wc => r#"wc -w < "$1""#,
+            ExternalTool::Grep => r#"grep -c '[[:alpha:]]' "$1""#,
+            ExternalTool::Uniq => {
+                r#"tr -cs '[:alpha:]' '\n' < "$1" | tr '[:upper:]' '[:lower:]' | sort | uniq -c"#
+            }
+        }
+    }
+
+    fn run_once(self, path: &Path) -> Result<u128> {
+        let start = Stopwatch::start();
+        let status = Command::new("sh")
+            .args(["-c", self.script(), "sh"])
+            .arg(path)
+            .stdout(Stdio::null())
+            .status()
+            .map_err(|e| Td5Error::config(format!("cannot run `{}`: {e}", self.name())))?;
+        let elapsed = start.elapsed_ns();
+        // grep exits with 1 when no line matches.
+        let ok = status.success() || (self == ExternalTool::Grep && status.code() == Some(1));
+        if !ok {
+            return Err(Td5Error::config(format!(
+                "`{}` failed ({status})",
+                self.script()
+            )));
+        }
+        Ok(elapsed)
+    }
+}
+
+/// Times every tool on every input, one warm-up run then `iterations` timed
+/// ones, as results named after the tool.
+pub fn run(
+    tools: &[ExternalTool],
+    inputs: &[BenchInput],
+    iterations: usize,
+) -> Result<Vec<BenchResult>> {
+    let file = TempFile::new("bench");
+    let mut results = Vec::with_capacity(tools.len() * inputs.len());
+    for input in inputs {
+        std::fs::write(file.path(), &input.text).map_err(|source| Td5Error::Io {
+            path: Some(file.path().to_path_buf()),
+            source,
+        })?;
+        for &tool in tools {
+            tool.run_once(file.path())?;
+            let samples = (0..iterations.max(1))
+                .map(|_| tool.run_once(file.path()))
+                .collect::<Result<Vec<u128>>>()?;
+            results.push(BenchResult {
+                analyzer: tool.name().to_string(),
+                input: input.name.clone(),
+                summary: Summary::from_samples(&samples),
+                samples,
+                energy_uj: Vec::new(),
+            });
+        }
+    }
+    Ok(results)
+}
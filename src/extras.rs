@@ -0,0 +1,221 @@
+//! What `td5 analyze` reports about an input beside its stats: the optional
+//! analyses its flags ask for (`--dispersion`, `--distribution`, `--pii` and
+//! the rest) and what is known of how the input was read (`--sample`,
+//! `--range`, the standard-input buffer).
+//!
+//! `Extras::of` runs the analyses of the text that `Wanted` names; the caller
+//! fills in the rest, which comes from reading the input rather than from its
+//! text. `Extras::add_to` writes each part found under its own key of the
+//! input's JSON document, in a fixed order, so templates and JSON output see
+//! the same document.
+
+use crate::AnalyzerConfig;
+use crate::acronyms::Acronyms;
+use crate::categories::Categories;
+use crate::chat::SpeakerStats;
+use crate::dispersion::Dispersion;
+use crate::fast::{PathStats, Timings, analyze_text_fast_instrumented, analyze_text_fast_timed};
+use crate::freq::{Distribution, WordFrequency};
+use crate::input::BufferReport;
+use crate::json::Json;
+use crate::keywords::{CodeStats, Language};
+use crate::pii::PiiStats;
+use crate::quotes::DialogueStats;
+use crate::sample::Estimate;
+use crate::slice::Slice;
+use crate::social::SocialStats;
+use crate::subtitles::SpeechRate;
+use crate::units::Quantities;
+use crate::warnings::Warnings;
+
+/// The analyses of the text to run, each off by default.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Wanted {
+    /// Scan for personal data, keeping up to this many redacted examples of
+    /// each kind.
+    pub pii: Option<usize>,
+    /// The spread of the counts; needs the full frequency table.
+    pub distribution: bool,
+    /// The dispersion of this many of the most frequent words.
+    pub dispersion: Option<usize>,
+    /// Up to this many probable nouns, verbs and adjectives; needs the full
+    /// frequency table.
+    pub categories: Option<usize>,
+    /// The quote pairs that mark dialogue.
+    pub quotes: Option<Vec<(char, char)>>,
+    pub acronyms: bool,
+    /// The keywords and identifiers of source code in this language.
+    pub code: Option<Language>,
+    /// Numbers with units.
+    pub units: bool,
+    /// The paths the fast analyzer took through the text and the time of its
+    /// phases.
+    pub instrument: bool,
+}
+
+impl Wanted {
+    /// Whether an analysis needs the full frequency table of the text.
+    pub fn needs_frequencies(&self) -> bool {
+        self.distribution || self.categories.is_some()
+    }
+}
+
+/// Everything reported about an input beside its stats; `None` (or empty)
+/// for what was not asked for.
+#[derive(Debug, Clone, Default)]
+pub struct Extras {
+    pub pii: Option<PiiStats>,
+    pub social: Option<SocialStats>,
+    pub speech: Option<SpeechRate>,
+    pub speakers: Option<Vec<SpeakerStats>>,
+    /// The speakers are the senders of a mailbox.
+    pub mail: bool,
+    pub distribution: Option<Distribution>,
+    pub dispersion: Option<Dispersion>,
+    pub categories: Option<Categories>,
+    pub dialogue: Option<DialogueStats>,
+    pub acronyms: Option<Acronyms>,
+    pub code: Option<CodeStats>,
+    pub quantities: Option<Quantities>,
+    pub paths: Option<PathStats>,
+    pub phases: Option<Timings>,
+    pub buffer: Option<BufferReport>,
+    /// `plugin.metric` and its value.
+    pub plugins: Vec<(String, f64)>,
+    pub estimate: Option<Estimate>,
+    pub range: Option<Slice>,
+    pub warnings: Warnings,
+}
+
+impl Extras {
+    /// The analyses of `wanted` on `text`, as counted under `config`. The
+    /// personal-data scan reads `source`, the text before hashtags,
+    /// mentions and URLs were taken out; `freq` is the full frequency table
+    /// of `text`, when `wanted` needs one.
+    ///
+    /// ```
+    /// use td5::AnalyzerConfig;
+    /// use td5::extras::{Extras, Wanted};
+    /// use td5::json::Json;
+    ///
+    /// let text = "The NASA probe flew 300 km. The probe landed.";
+    /// let wanted = Wanted {
+    ///     acronyms: true,
+    ///     units: true,
+    ///     dispersion: Some(5),
+    ///     ..Wanted::default()
+    /// };
+    /// let extras = Extras::of(text, text, None, &wanted, &AnalyzerConfig::default());
+    /// assert_eq!(extras.acronyms.as_ref().unwrap().total, 1);
+    /// assert!(extras.pii.is_none());
+    ///
+    /// let mut doc = Json::Object(Vec::new());
+    /// extras.add_to(&mut doc);
+    /// let Json::Object(fields) = doc else { unreachable!() };
+    /// let keys: Vec<&str> = fields.iter().map(|(key, _)| key.as_str()).collect();
+    /// assert_eq!(keys, ["dispersion", "acronyms", "units"]);
+    /// ```
+    pub fn of(
+        text: &str,
+        source: &str,
+        freq: Option<&WordFrequency>,
+        wanted: &Wanted,
+        config: &AnalyzerConfig,
+    ) -> Extras {
+        let instrument = |text| {
+            let default = AnalyzerConfig::default();
+            (
+                analyze_text_fast_instrumented(text, &default).1,
+                analyze_text_fast_timed(text, &default).1,
+            )
+        };
+        let (paths, phases) = wanted.instrument.then(|| instrument(text)).unzip();
+        Extras {
+            pii: wanted.pii.map(|samples| PiiStats::scan(source, samples)),
+            distribution: freq
+                .filter(|_| wanted.distribution)
+                .map(WordFrequency::distribution),
+            dispersion: wanted
+                .dispersion
+                .map(|top| Dispersion::of(text, top, config)),
+            categories: wanted
+                .categories
+                .zip(freq)
+                .map(|(top, freq)| Categories::of(freq, top)),
+            dialogue: wanted.quotes.as_ref().map(|q| DialogueStats::of(text, q)),
+            acronyms: wanted.acronyms.then(|| Acronyms::extract(text)),
+            code: wanted.code.map(|language| CodeStats::of(text, language)),
+            quantities: wanted.units.then(|| Quantities::extract(text)),
+            paths,
+            phases,
+            ..Extras::default()
+        }
+    }
+
+    /// Sets each part found in `doc`, an input's stats as `to_json_value`
+    /// writes them.
+    pub fn add_to(&self, doc: &mut Json) {
+        if let Some(pii) = &self.pii {
+            doc.set("pii", pii.to_json_value());
+        }
+        if let Some(social) = &self.social {
+            doc.set("social", social.to_json_value());
+        }
+        if let Some(speech) = &self.speech {
+            doc.set("speech", speech.to_json_value());
+        }
+        if let Some(speakers) = &self.speakers {
+            doc.set(
+                if self.mail { "senders" } else { "speakers" },
+                Json::Array(speakers.iter().map(SpeakerStats::to_json_value).collect()),
+            );
+        }
+        if let Some(distribution) = &self.distribution {
+            doc.set("distribution", distribution.to_json_value());
+        }
+        if let Some(dispersion) = &self.dispersion {
+            doc.set("dispersion", dispersion.to_json_value());
+        }
+        if let Some(categories) = &self.categories {
+            doc.set("categories", categories.to_json_value());
+        }
+        if let Some(dialogue) = &self.dialogue {
+            doc.set("dialogue", dialogue.to_json_value());
+        }
+        if let Some(acronyms) = &self.acronyms {
+            doc.set("acronyms", acronyms.to_json_value());
+        }
+        if let Some(code) = &self.code {
+            doc.set("code", code.to_json_value());
+        }
+        if let Some(quantities) = &self.quantities {
+            doc.set("units", quantities.to_json_value());
+        }
+        if let Some(paths) = &self.paths {
+            doc.set("paths", paths.to_json_value());
+        }
+        if let Some(phases) = &self.phases {
+            doc.set("phases", phases.to_json_value());
+        }
+        if let Some(buffer) = &self.buffer {
+            doc.set("buffer", buffer.to_json_value());
+        }
+        if !self.plugins.is_empty() {
+            let metrics = self
+                .plugins
+                .iter()
+                .map(|(name, value)| (name.clone(), (*value).into()))
+                .collect();
+            doc.set("plugins", Json::Object(metrics));
+        }
+        if let Some(estimate) = &self.estimate {
+            doc.set("estimate", estimate.to_json_value());
+        }
+        if let Some(range) = &self.range {
+            doc.set("range", range.to_json_value());
+        }
+        if !self.warnings.is_empty() {
+            doc.set("warnings", self.warnings.to_json_value());
+        }
+    }
+}
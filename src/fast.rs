@@ -0,0 +1,1287 @@
+use crate::clock::Stopwatch;
+use crate::config::{AnalyzerConfig, Progress, Segmentation};
+use crate::error::{Result, Td5Error};
+use crate::freq::{by_count, select_top};
+use crate::hotwords::HotWords;
+use crate::json::Json;
+use crate::ngrams::NGrams;
+use crate::parallel::split_chunks;
+use crate::segment::WordBounds;
+use crate::tokenizer::Tokenizer;
+use crate::tuning::Tuning;
+use crate::wordmap::WordMap;
+use crate::{Diagnostics, TextStats};
+use rustc_hash::FxHasher;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::hash::BuildHasherDefault;
+use std::ops::Range;
+use std::sync::Arc;
+
+pub(crate) type FastHasher = BuildHasherDefault<FxHasher>;
+pub(crate) type FastMap<K, V> = HashMap<K, V, FastHasher>;
+
+// `HOT`, `HOT_ORDER`, `LONGEST_ORDER` and `match_hot_lower`, generated by
+// build.rs from `hot-words.txt` (or `$TD5_HOT_WORDS`).
+include!(concat!(env!("OUT_DIR"), "/hot.rs"));
+
+/// Receives what the scanners find. Scanners are generic over it, so each
+/// backend gets its own monomorphized copy of the tight loops; sinks that
+/// ignore `span` pay nothing for it.
+pub(crate) trait Sink {
+    /// An ASCII word, lowercase unless the config is case-sensitive: the
+    /// word characters of a whitespace-separated token, `span` running from
+    /// its first to its last.
+    fn ascii_word(&mut self, word: &[u8], span: Range<usize>);
+    /// A cleaned word from the Unicode fallback, lowercased unless the
+    /// config is case-sensitive, read from the whitespace-separated token at
+    /// `span` (the word segment under `Segmentation::Words`).
+    fn unicode_word(&mut self, word: &str, span: Range<usize>);
+    fn add_chars(&mut self, n: usize);
+    /// Tokens dropped for having no letter. Only `Limited` records them.
+    fn non_alphabetic(&mut self, _n: usize) {}
+    /// `\n` characters.
+    fn newlines(&mut self, _n: usize) {}
+    /// `n` sentence ends (see `is_sentence_end`), the last one ending at
+    /// byte `last` of the text.
+    fn sentence_ends(&mut self, _n: usize, _last: usize) {}
+}
+
+/// Whether `ch` ends a sentence when a token ends with it.
+#[inline]
+pub(crate) fn is_sentence_end(ch: char) -> bool {
+    matches!(ch, '.' | '!' | '?' | '…' | '。' | '！' | '？')
+}
+
+/// Word lengths counted one by one; longer words share the last slot.
+pub(crate) const LENGTH_SLOTS: usize = 32;
+
+/// Lines, sentences and word lengths, counted in the same scan as the words.
+#[derive(Debug, Clone)]
+pub(crate) struct Shape {
+    newlines: usize,
+    /// Whether the text ends inside a line; `None` for no text.
+    open_line: Option<bool>,
+    sentences: usize,
+    /// Whether words follow the last sentence end, once sealed.
+    open_sentence: bool,
+    /// Byte offsets while scanning, turned into `open_sentence` by `seal`.
+    last_word_end: Option<usize>,
+    last_sentence_end: Option<usize>,
+    /// `lengths[i]`: words of `i + 1` characters, repeats included.
+    lengths: [usize; LENGTH_SLOTS],
+    length_sum: usize,
+    /// Whether lines and sentences were counted: false for a shape taken
+    /// from a word table, which only has lengths (`of_words`).
+    scanned: bool,
+}
+
+impl Default for Shape {
+    fn default() -> Self {
+        Self {
+            newlines: 0,
+            open_line: None,
+            sentences: 0,
+            open_sentence: false,
+            last_word_end: None,
+            last_sentence_end: None,
+            lengths: [0; LENGTH_SLOTS],
+            length_sum: 0,
+            scanned: true,
+        }
+    }
+}
+
+impl Shape {
+    /// The word lengths of a word → count table; lines and sentences are
+    /// unknown until `with_lines_of`.
+    pub(crate) fn of_words<'a>(words: impl IntoIterator<Item = (&'a str, usize)>) -> Self {
+        let mut shape = Shape {
+            scanned: false,
+            ..Shape::default()
+        };
+        for (word, count) in words {
+            let chars = word.chars().count();
+            shape.lengths[chars.clamp(1, LENGTH_SLOTS) - 1] += count;
+            shape.length_sum += chars * count;
+        }
+        shape
+    }
+
+    /// This shape's word lengths with the lines and sentences of `scanned`,
+    /// for words transformed or filtered after the scan.
+    pub(crate) fn with_lines_of(self, scanned: &Shape) -> Self {
+        Shape {
+            lengths: self.lengths,
+            length_sum: self.length_sum,
+            ..scanned.clone()
+        }
+    }
+
+    #[inline]
+    pub(crate) fn word(&mut self, chars: usize, end: usize) {
+        self.lengths[chars.clamp(1, LENGTH_SLOTS) - 1] += 1;
+        self.length_sum += chars;
+        self.last_word_end = Some(end);
+    }
+
+    #[inline]
+    pub(crate) fn newlines(&mut self, n: usize) {
+        self.newlines += n;
+    }
+
+    #[inline]
+    pub(crate) fn sentence_ends(&mut self, n: usize, last: usize) {
+        if n > 0 {
+            self.sentences += n;
+            self.last_sentence_end = Some(last);
+        }
+    }
+
+    /// Ends the scan of `text`.
+    pub(crate) fn seal(&mut self, text: &str) {
+        self.open_line = text.as_bytes().last().map(|&b| b != b'\n');
+        self.open_sentence = self.last_word_end > self.last_sentence_end;
+        (self.last_word_end, self.last_sentence_end) = (None, None);
+    }
+
+    /// Ends a document: its last line and sentence count as finished.
+    pub(crate) fn close(&mut self) {
+        self.newlines += usize::from(self.open_line == Some(true));
+        self.sentences += usize::from(self.open_sentence);
+        (self.open_line, self.open_sentence) = (None, false);
+    }
+
+    /// Folds in the shape of the text right after this one.
+    pub(crate) fn merge(&mut self, next: &Shape) {
+        self.newlines += next.newlines;
+        if next.open_line.is_some() {
+            self.open_line = next.open_line;
+        }
+        self.open_sentence = if next.sentences > 0 {
+            next.open_sentence
+        } else {
+            self.open_sentence || next.open_sentence
+        };
+        self.sentences += next.sentences;
+        for (a, b) in self.lengths.iter_mut().zip(next.lengths) {
+            *a += b;
+        }
+        self.length_sum += next.length_sum;
+        self.scanned &= next.scanned;
+    }
+
+    /// Words counted so far.
+    pub(crate) fn words(&self) -> usize {
+        self.lengths.iter().sum()
+    }
+
+    /// Lines as `str::lines` counts them; `None` if not scanned.
+    pub(crate) fn lines(&self) -> Option<usize> {
+        self.scanned
+            .then(|| self.newlines + usize::from(self.open_line == Some(true)))
+    }
+
+    /// Sentence ends, plus the unfinished sentence after the last one;
+    /// `None` if not scanned.
+    pub(crate) fn sentences(&self) -> Option<usize> {
+        self.scanned
+            .then(|| self.sentences + usize::from(self.open_sentence))
+    }
+
+    /// `lengths`, without the empty slots at the end.
+    pub(crate) fn histogram(&self) -> Vec<usize> {
+        let used = self
+            .lengths
+            .iter()
+            .rposition(|&n| n > 0)
+            .map_or(0, |i| i + 1);
+        self.lengths[..used].to_vec()
+    }
+
+    pub(crate) fn mean_length(&self) -> f64 {
+        let words: usize = self.lengths.iter().sum();
+        self.length_sum as f64 / words.max(1) as f64
+    }
+
+    /// Length of the middle word in length order (the upper one of two).
+    pub(crate) fn median_length(&self) -> usize {
+        median(&self.lengths)
+    }
+}
+
+/// The median of the values counted in `histogram`, `histogram[i]` being
+/// how many there are of `i + 1`; 0 for none.
+pub(crate) fn median(histogram: &[usize]) -> usize {
+    let middle = histogram.iter().sum::<usize>() / 2;
+    let mut seen = 0;
+    for (i, &n) in histogram.iter().enumerate() {
+        seen += n;
+        if seen > middle {
+            return i + 1;
+        }
+    }
+    0
+}
+
+/// Counters of the hot vocabulary in use.
+#[derive(Clone)]
+enum Hot {
+    /// The words compiled in by build.rs, matched by the generated
+    /// `match_hot_lower`.
+    Builtin([usize; HOT.len()]),
+    /// `AnalyzerConfig::with_hot_words`, one counter per word.
+    Custom(Arc<HotWords>, Vec<usize>),
+}
+
+impl Hot {
+    fn new(config: &AnalyzerConfig) -> Self {
+        match &config.hot_words {
+            None => Hot::Builtin([0; HOT.len()]),
+            Some(words) => Hot::Custom(Arc::clone(words), vec![0; words.words().len()]),
+        }
+    }
+
+    fn counts(&self) -> &[usize] {
+        match self {
+            Hot::Builtin(counts) => counts,
+            Hot::Custom(_, counts) => counts,
+        }
+    }
+
+    fn word(&self, idx: usize) -> &str {
+        match self {
+            Hot::Builtin(_) => HOT[idx],
+            Hot::Custom(words, _) => &words.words()[idx],
+        }
+    }
+}
+
+/// Raw output of a counting pass: hot words live in a fixed array, everything
+/// else in a lazily allocated map.
+#[derive(Clone)]
+pub(crate) struct Counts {
+    hot: Hot,
+    map: Option<WordMap>,
+    char_count: usize,
+    capacity_hint: usize,
+    /// Only with `AnalyzerConfig::ngrams`.
+    pub(crate) ngrams: Option<NGrams>,
+    pub(crate) shape: Shape,
+    pub(crate) diagnostics: Diagnostics,
+    paths: PathStats,
+}
+
+impl Counts {
+    fn new(capacity_hint: usize, config: &AnalyzerConfig) -> Self {
+        Self {
+            hot: Hot::new(config),
+            map: None,
+            char_count: 0,
+            capacity_hint,
+            ngrams: NGrams::new(config.ngrams),
+            shape: Shape::default(),
+            diagnostics: Diagnostics::default(),
+            paths: PathStats::default(),
+        }
+    }
+
+    /// Counts that bypass the hot array entirely.
+    pub(crate) fn from_map(map: FastMap<String, usize>, char_count: usize) -> Self {
+        let mut words = WordMap::with_capacity(map.len());
+        for (word, count) in map {
+            words.add(&word, count);
+        }
+        Self::from_words(words, char_count)
+    }
+
+    pub(crate) fn from_words(words: WordMap, char_count: usize) -> Self {
+        Self {
+            hot: Hot::Builtin([0; HOT.len()]),
+            map: Some(words),
+            char_count,
+            capacity_hint: 0,
+            ngrams: None,
+            shape: Shape::default(),
+            diagnostics: Diagnostics::default(),
+            paths: PathStats::default(),
+        }
+    }
+
+    /// Folds `other` into `self`, iterating over the smaller map. N-grams
+    /// are only right if `other` counted the text right after `self`'s.
+    pub(crate) fn merge(&mut self, mut other: Counts) {
+        match (&mut self.hot, &other.hot) {
+            (Hot::Builtin(ours), Hot::Builtin(theirs)) => {
+                for (a, b) in ours.iter_mut().zip(theirs) {
+                    *a += b;
+                }
+            }
+            (Hot::Custom(words, ours), Hot::Custom(other_words, theirs))
+                if Arc::ptr_eq(words, other_words) || words == other_words =>
+            {
+                for (a, b) in ours.iter_mut().zip(theirs) {
+                    *a += b;
+                }
+            }
+            // Counted with another vocabulary: its hot words join the map.
+            (_, theirs) => {
+                let map = other.map.get_or_insert_with(WordMap::default);
+                for (idx, &count) in theirs.counts().iter().enumerate() {
+                    if count > 0 {
+                        map.add(theirs.word(idx), count);
+                    }
+                }
+            }
+        }
+        self.char_count += other.char_count;
+        self.diagnostics.merge(&other.diagnostics);
+        self.paths.merge(&other.paths);
+        self.shape.merge(&other.shape);
+        match (&mut self.ngrams, other.ngrams.take()) {
+            (_, None) => {}
+            (None, theirs) => self.ngrams = theirs,
+            (Some(ours), Some(theirs)) => ours.merge(theirs),
+        }
+        match (&mut self.map, other.map.take()) {
+            (_, None) => {}
+            (None, Some(theirs)) => self.map = Some(theirs),
+            (Some(ours), Some(mut theirs)) => {
+                if theirs.len() > ours.len() {
+                    std::mem::swap(ours, &mut theirs);
+                }
+                ours.merge(&theirs);
+            }
+        }
+    }
+
+    /// Ends a document, so that merging it with the counts of another text
+    /// joins no line, sentence or n-gram across the two.
+    pub(crate) fn close(&mut self) {
+        self.shape.close();
+        if let Some(ngrams) = &mut self.ngrams {
+            ngrams.close();
+        }
+    }
+
+    pub(crate) fn char_count(&self) -> usize {
+        self.char_count
+    }
+
+    /// Words counted so far, repeats included.
+    pub(crate) fn tokens(&self) -> usize {
+        self.shape.words()
+    }
+
+    /// Only hot words were counted and `config` needs no word list, so
+    /// `finish` can rank the hot array without a map or a sort.
+    fn hot_only(&self, config: &AnalyzerConfig) -> bool {
+        self.map.is_none() && !config.needs_words()
+    }
+
+    /// Every counted word with its frequency, as owned strings.
+    pub(crate) fn into_map(self) -> FastMap<String, usize> {
+        let words = self.into_words();
+        let mut map = FastMap::with_capacity_and_hasher(words.len(), FastHasher::default());
+        for (word, count) in words.iter() {
+            map.insert(word.to_string(), count);
+        }
+        map
+    }
+
+    /// Every counted word with its frequency, hot words included.
+    fn into_words(self) -> WordMap {
+        let mut words = self.map.unwrap_or_default();
+        for (idx, &count) in self.hot.counts().iter().enumerate() {
+            if count > 0 {
+                words.add(self.hot.word(idx), count);
+            }
+        }
+        words
+    }
+}
+
+impl Sink for Counts {
+    #[inline]
+    fn ascii_word(&mut self, word: &[u8], span: Range<usize>) {
+        self.shape.word(word.len(), span.end);
+        let counter = match &mut self.hot {
+            Hot::Builtin(counts) => match_hot_lower(word).map(|idx| &mut counts[idx]),
+            Hot::Custom(words, counts) => words.find(word).map(|idx| &mut counts[idx]),
+        };
+        if let Some(counter) = counter {
+            *counter += 1;
+        } else {
+            let hint = self.capacity_hint;
+            let map = self.map.get_or_insert_with(|| WordMap::with_capacity(hint));
+            // SAFETY: word is ASCII
+            map.add(unsafe { std::str::from_utf8_unchecked(word) }, 1);
+        }
+        if let Some(ngrams) = &mut self.ngrams {
+            // SAFETY: word is ASCII
+            ngrams.push(unsafe { std::str::from_utf8_unchecked(word) });
+        }
+    }
+
+    #[inline]
+    fn unicode_word(&mut self, word: &str, span: Range<usize>) {
+        self.shape.word(word.chars().count(), span.end);
+        self.map.get_or_insert_with(WordMap::default).add(word, 1);
+        if let Some(ngrams) = &mut self.ngrams {
+            ngrams.push(word);
+        }
+    }
+
+    #[inline]
+    fn add_chars(&mut self, n: usize) {
+        self.char_count += n;
+    }
+
+    fn newlines(&mut self, n: usize) {
+        self.shape.newlines(n);
+    }
+
+    fn sentence_ends(&mut self, n: usize, last: usize) {
+        self.shape.sentence_ends(n, last);
+    }
+}
+
+/// A scan for lines, sentences and word lengths alone (`shape`).
+impl Sink for Shape {
+    #[inline]
+    fn ascii_word(&mut self, word: &[u8], span: Range<usize>) {
+        self.word(word.len(), span.end);
+    }
+
+    #[inline]
+    fn unicode_word(&mut self, word: &str, span: Range<usize>) {
+        self.word(word.chars().count(), span.end);
+    }
+
+    #[inline]
+    fn add_chars(&mut self, _n: usize) {}
+
+    fn newlines(&mut self, n: usize) {
+        Shape::newlines(self, n);
+    }
+
+    fn sentence_ends(&mut self, n: usize, last: usize) {
+        Shape::sentence_ends(self, n, last);
+    }
+}
+
+/// Applies the configured minimum length and long-token policy in front of
+/// another sink and records the tokens it and the scanner left out.
+pub(crate) struct Limited<'a, S> {
+    inner: &'a mut S,
+    config: &'a AnalyzerConfig,
+    pub(crate) diagnostics: Diagnostics,
+}
+
+impl<'a, S: Sink> Limited<'a, S> {
+    pub(crate) fn new(inner: &'a mut S, config: &'a AnalyzerConfig) -> Self {
+        Self {
+            inner,
+            config,
+            diagnostics: Diagnostics::default(),
+        }
+    }
+}
+
+impl<S: Sink> Sink for Limited<'_, S> {
+    #[inline]
+    fn ascii_word(&mut self, word: &[u8], span: Range<usize>) {
+        if word.len() < self.config.min_word_length {
+            self.diagnostics.short_words += 1;
+            return;
+        }
+        if word.len() <= self.config.long_token_limit {
+            return self.inner.ascii_word(word, span);
+        }
+        self.diagnostics.long_tokens += 1;
+        // SAFETY: word is ASCII
+        let word = unsafe { std::str::from_utf8_unchecked(word) };
+        match self.config.limit_word(word) {
+            (Some(kept), _) => self.inner.ascii_word(kept.as_bytes(), span),
+            (None, _) => self.diagnostics.long_tokens_skipped += 1,
+        }
+    }
+
+    #[inline]
+    fn unicode_word(&mut self, word: &str, span: Range<usize>) {
+        if self.config.min_word_length > 1 && word.chars().count() < self.config.min_word_length {
+            self.diagnostics.short_words += 1;
+            return;
+        }
+        if word.len() <= self.config.long_token_limit {
+            return self.inner.unicode_word(word, span);
+        }
+        self.diagnostics.long_tokens += 1;
+        match self.config.limit_word(word) {
+            (Some(kept), _) => self.inner.unicode_word(kept, span),
+            (None, _) => self.diagnostics.long_tokens_skipped += 1,
+        }
+    }
+
+    #[inline]
+    fn add_chars(&mut self, n: usize) {
+        self.inner.add_chars(n);
+    }
+
+    fn non_alphabetic(&mut self, n: usize) {
+        self.diagnostics.non_alphabetic += n;
+    }
+
+    fn newlines(&mut self, n: usize) {
+        self.inner.newlines(n);
+    }
+
+    fn sentence_ends(&mut self, n: usize, last: usize) {
+        self.inner.sentence_ends(n, last);
+    }
+}
+
+// Unicode fallback
+fn scan_unicode<S: Sink>(text: &str, config: &AnalyzerConfig, sink: &mut S) {
+    let mut char_count = 0usize;
+    let mut non_alphabetic = 0usize;
+    let (mut newlines, mut ends, mut last_end) = (0usize, 0usize, 0usize);
+    let mut previous_end = 0;
+    // One buffer for every token: sinks copy what they keep.
+    let mut clean = String::new();
+    for token in text.split_whitespace() {
+        let start = token.as_ptr() as usize - text.as_ptr() as usize;
+        newlines += count_newlines(&text[previous_end..start]);
+        previous_end = start + token.len();
+        if token.ends_with(is_sentence_end) {
+            (ends, last_end) = (ends + 1, previous_end);
+        }
+        clean.clear();
+        for ch in config.normalized(token).chars() {
+            if config.is_word_char(ch) {
+                char_count += 1;
+                clean.push(ch);
+            }
+        }
+        config.cased(&mut clean);
+        if clean.is_empty() {
+            non_alphabetic += 1;
+        } else {
+            sink.unicode_word(&clean, start..previous_end);
+        }
+    }
+    newlines += count_newlines(&text[previous_end..]);
+    sink.add_chars(char_count);
+    sink.non_alphabetic(non_alphabetic);
+    sink.newlines(newlines);
+    sink.sentence_ends(ends, last_end);
+}
+
+fn count_newlines(whitespace: &str) -> usize {
+    whitespace.bytes().filter(|&b| b == b'\n').count()
+}
+
+/// `AnalyzerConfig::punctuation` other than the default, whatever the text:
+/// the ASCII scanners drop all punctuation inside a token.
+fn scan_punctuation<S: Sink>(text: &str, config: &AnalyzerConfig, sink: &mut S) {
+    let mut char_count = 0usize;
+    let mut non_alphabetic = 0usize;
+    let (mut newlines, mut ends, mut last_end) = (0usize, 0usize, 0usize);
+    let mut previous_end = 0;
+    let mut buffer = String::new();
+    for token in text.split_whitespace() {
+        let start = token.as_ptr() as usize - text.as_ptr() as usize;
+        newlines += count_newlines(&text[previous_end..start]);
+        previous_end = start + token.len();
+        if token.ends_with(is_sentence_end) {
+            (ends, last_end) = (ends + 1, previous_end);
+        }
+        let mut found = false;
+        char_count += config.punctuated_words(&config.normalized(token), &mut buffer, |word| {
+            found = true;
+            sink.unicode_word(word, start..previous_end);
+        });
+        non_alphabetic += usize::from(!found);
+    }
+    newlines += count_newlines(&text[previous_end..]);
+    sink.add_chars(char_count);
+    sink.non_alphabetic(non_alphabetic);
+    sink.newlines(newlines);
+    sink.sentence_ends(ends, last_end);
+}
+
+/// `Segmentation::Words`, whatever the text: ASCII contractions need it too.
+fn scan_words<S: Sink>(text: &str, config: &AnalyzerConfig, sink: &mut S) {
+    let mut char_count = 0usize;
+    let mut non_alphabetic = 0usize;
+    let (mut newlines, mut ends, mut last_end) = (0usize, 0usize, 0usize);
+    // End of the last segment if it ends a sentence, until whitespace
+    // confirms it ends its token.
+    let mut stop: Option<usize> = None;
+    let mut start = 0;
+    for segment in WordBounds::new(text) {
+        let span = start..start + segment.len();
+        start = span.end;
+        if segment.trim().is_empty() {
+            newlines += count_newlines(segment);
+            if let Some(end) = stop {
+                (ends, last_end) = (ends + 1, end);
+            }
+            stop = None;
+            continue;
+        }
+        stop = segment.ends_with(is_sentence_end).then_some(span.end);
+        let segment = config.normalized(segment);
+        let letters = segment
+            .chars()
+            .filter(|&ch| config.is_word_char(ch))
+            .count();
+        if letters > 0 {
+            char_count += letters;
+            if config.case_sensitive {
+                sink.unicode_word(&segment, span);
+            } else {
+                sink.unicode_word(&segment.to_lowercase(), span);
+            }
+        } else {
+            non_alphabetic += 1;
+        }
+    }
+    if let Some(end) = stop {
+        (ends, last_end) = (ends + 1, end);
+    }
+    sink.add_chars(char_count);
+    sink.non_alphabetic(non_alphabetic);
+    sink.newlines(newlines);
+    sink.sentence_ends(ends, last_end);
+}
+
+/// `AnalyzerConfig::tokenizer`, whatever the text: the tokenizer is handed
+/// each whitespace-separated token and its tokens are counted as written.
+fn scan_tokens<S: Sink>(
+    text: &str,
+    tokenizer: &dyn Tokenizer,
+    config: &AnalyzerConfig,
+    sink: &mut S,
+) {
+    let mut char_count = 0usize;
+    let (mut newlines, mut ends, mut last_end) = (0usize, 0usize, 0usize);
+    let mut previous_end = 0;
+    for token in text.split_whitespace() {
+        let start = token.as_ptr() as usize - text.as_ptr() as usize;
+        newlines += count_newlines(&text[previous_end..start]);
+        previous_end = start + token.len();
+        if token.ends_with(is_sentence_end) {
+            (ends, last_end) = (ends + 1, previous_end);
+        }
+        tokenizer.tokens(token, &mut |span| {
+            if span.is_empty() {
+                return;
+            }
+            let word = config.normalized(&token[span.clone()]);
+            char_count += word.chars().count();
+            let span = start + span.start..start + span.end;
+            if config.case_sensitive {
+                sink.unicode_word(&word, span);
+            } else {
+                sink.unicode_word(&word.to_lowercase(), span);
+            }
+        });
+    }
+    newlines += count_newlines(&text[previous_end..]);
+    sink.add_chars(char_count);
+    sink.newlines(newlines);
+    sink.sentence_ends(ends, last_end);
+}
+
+/// Longest word the simple path's stack buffer can hold.
+pub(crate) const SIMPLE_WORD_MAX: usize = 32;
+
+// ASCII hot path: specialize for lowercase letters + spaces (generator case).
+fn scan_simple_lower<S: Sink>(text: &str, sink: &mut S) -> Result<()> {
+    let bytes = text.as_bytes();
+    let mut char_count = 0usize;
+    let mut buf = [0u8; SIMPLE_WORD_MAX];
+    let mut len = 0usize;
+    for (i, &b) in bytes.iter().enumerate() {
+        if b == b' ' {
+            if len > 0 {
+                sink.ascii_word(&buf[..len], i - len..i);
+                len = 0;
+            }
+        } else {
+            if len == buf.len() {
+                return Err(Td5Error::LimitExceeded {
+                    limit: "simple-path word length",
+                    max: SIMPLE_WORD_MAX,
+                    actual: len + bytes[i..].iter().take_while(|&&b| b != b' ').count(),
+                });
+            }
+            char_count += 1;
+            buf[len] = b;
+            len += 1;
+        }
+    }
+    if len > 0 {
+        sink.ascii_word(&buf[..len], bytes.len() - len..bytes.len());
+    }
+    sink.add_chars(char_count);
+    Ok(())
+}
+
+// Generic ASCII hot path: manual byte scan, hot vocab avoids hashing entirely.
+// A word is the word characters of a whitespace-separated token, as on the
+// Unicode path: `don't` counts as `dont`.
+// `FOLD` lowercases words, `DIGITS` makes digits word characters; each
+// combination of options gets its own loop. The `simd` feature swaps in a
+// vectorized scanner with the same output.
+#[cfg(feature = "simd")]
+use crate::simd::scan_ascii;
+
+#[cfg(not(feature = "simd"))]
+fn scan_ascii<S: Sink, const FOLD: bool, const DIGITS: bool>(text: &str, sink: &mut S) {
+    let mut char_count = 0usize;
+    // The word characters of the current token, punctuation between them
+    // dropped, and where the first and the last are.
+    let mut buf: Vec<u8> = Vec::with_capacity(32);
+    let (mut word_start, mut word_end) = (0usize, 0usize);
+    // Whitespace-separated token state, only to count the ones without letters
+    // the way the Unicode path and the reference do.
+    let (mut in_token, mut has_letter) = (false, false);
+    let mut non_alphabetic = 0usize;
+    let (mut newlines, mut ends, mut last_end) = (0usize, 0usize, 0usize);
+    let mut previous = b' ';
+    for (i, &b) in text.as_bytes().iter().enumerate() {
+        if b.is_ascii_alphabetic() || (DIGITS && b.is_ascii_digit()) {
+            char_count += 1;
+            if buf.is_empty() {
+                word_start = i;
+            }
+            buf.push(if FOLD { b | 0b0010_0000 } else { b });
+            word_end = i + 1;
+            (in_token, has_letter) = (true, true);
+            previous = b;
+            continue;
+        }
+        // The ASCII characters `char::is_whitespace` accepts.
+        if matches!(b, b' ' | b'\t' | b'\n' | b'\r' | 0x0b | 0x0c) {
+            if !buf.is_empty() {
+                sink.ascii_word(&buf, word_start..word_end);
+                buf.clear();
+            }
+            non_alphabetic += usize::from(in_token && !has_letter);
+            (in_token, has_letter) = (false, false);
+            newlines += usize::from(b == b'\n');
+            if matches!(previous, b'.' | b'!' | b'?') {
+                (ends, last_end) = (ends + 1, i);
+            }
+        } else {
+            in_token = true;
+        }
+        previous = b;
+    }
+    if !buf.is_empty() {
+        sink.ascii_word(&buf, word_start..word_end);
+    }
+    if matches!(previous, b'.' | b'!' | b'?') {
+        (ends, last_end) = (ends + 1, text.len());
+    }
+    non_alphabetic += usize::from(in_token && !has_letter);
+    sink.add_chars(char_count);
+    sink.non_alphabetic(non_alphabetic);
+    sink.newlines(newlines);
+    sink.sentence_ends(ends, last_end);
+}
+
+/// Which scanner a text needs. Decided once for the whole input so that
+/// chunks of the same text are always tokenized the same way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Mode {
+    Unicode,
+    SimpleLower,
+    Ascii,
+}
+
+pub(crate) fn detect(text: &str) -> Mode {
+    if !text.is_ascii() {
+        return Mode::Unicode;
+    }
+    let is_simple_lower_ascii = text
+        .as_bytes()
+        .iter()
+        .all(|b| *b == b' ' || (b.is_ascii_alphabetic() && b.is_ascii_lowercase()));
+    if is_simple_lower_ascii {
+        Mode::SimpleLower
+    } else {
+        Mode::Ascii
+    }
+}
+
+/// Only the simple path can fail (`LimitExceeded` on a word longer than
+/// `SIMPLE_WORD_MAX`); the sink may then hold a partial count. Lowercase
+/// letters and spaces read the same under every option, so the simple path
+/// ignores `config`.
+pub(crate) fn scan<S: Sink>(
+    text: &str,
+    mode: Mode,
+    config: &AnalyzerConfig,
+    sink: &mut S,
+) -> Result<()> {
+    if let Some(tokenizer) = &config.tokenizer {
+        scan_tokens(text, &*tokenizer.0, config, sink);
+        return Ok(());
+    }
+    if config.segmentation == Segmentation::Words {
+        scan_words(text, config, sink);
+        return Ok(());
+    }
+    if config.splits_punctuation() && mode != Mode::SimpleLower {
+        scan_punctuation(text, config, sink);
+        return Ok(());
+    }
+    match (mode, config.case_sensitive, config.digits) {
+        (Mode::Unicode, ..) => scan_unicode(text, config, sink),
+        (Mode::SimpleLower, ..) => return scan_simple_lower(text, sink),
+        (Mode::Ascii, false, false) => scan_ascii::<S, true, false>(text, sink),
+        (Mode::Ascii, false, true) => scan_ascii::<S, true, true>(text, sink),
+        (Mode::Ascii, true, false) => scan_ascii::<S, false, false>(text, sink),
+        (Mode::Ascii, true, true) => scan_ascii::<S, false, true>(text, sink),
+    }
+    Ok(())
+}
+
+fn empty_counts(text: &str, mode: Mode, config: &AnalyzerConfig) -> Counts {
+    match mode {
+        // The Unicode path never uses the hot array, so it always takes the sort path.
+        Mode::Unicode => Counts {
+            ngrams: NGrams::new(config.ngrams),
+            ..Counts::from_map(FastMap::default(), 0)
+        },
+        _ => Counts::new(
+            text.len()
+                .checked_div(Tuning::current().bytes_per_slot)
+                .unwrap_or(0),
+            config,
+        ),
+    }
+}
+
+pub(crate) fn count_with(text: &str, mode: Mode, config: &AnalyzerConfig) -> Result<Counts> {
+    // Lowercase letters and spaces hold no control character.
+    let (text, controls) = match mode {
+        Mode::SimpleLower => (Cow::Borrowed(text), 0),
+        _ => config.control_text(text),
+    };
+    let mut counts = empty_counts(&text, mode, config);
+    let mut sink = Limited::new(&mut counts, config);
+    scan(&text, mode, config, &mut sink)?;
+    let diagnostics = sink.diagnostics;
+    counts.diagnostics.merge(&diagnostics);
+    counts.diagnostics.control_chars += controls;
+    counts.paths.record(mode, config);
+    counts.shape.seal(&text);
+    Ok(counts)
+}
+
+/// Like `count_with`, but a word too long for the simple path re-counts `text`
+/// with the generic ASCII scanner, which tokenizes lowercase text identically.
+pub(crate) fn count_lenient(text: &str, mode: Mode, config: &AnalyzerConfig) -> Counts {
+    count_with(text, mode, config)
+        .or_else(|_| {
+            count_with(text, Mode::Ascii, config).map(|mut counts| {
+                counts.paths.simple_fallbacks += 1;
+                counts
+            })
+        })
+        .expect("the ASCII scanner has no length limit")
+}
+
+pub(crate) fn count(text: &str, config: &AnalyzerConfig) -> Counts {
+    count_lenient(text, detect(text), config)
+}
+
+/// The shape `count` would find in `text`, without counting its words.
+pub(crate) fn shape(text: &str, config: &AnalyzerConfig) -> Shape {
+    let (text, _) = config.control_text(text);
+    let mut shape = Shape::default();
+    if scan(
+        &text,
+        detect(&text),
+        config,
+        &mut Limited::new(&mut shape, config),
+    )
+    .is_err()
+    {
+        shape = Shape::default();
+        scan(
+            &text,
+            Mode::Ascii,
+            config,
+            &mut Limited::new(&mut shape, config),
+        )
+        .expect("the ASCII scanner has no length limit");
+    }
+    shape.seal(&text);
+    shape
+}
+
+// --------------------------- VERSION RAPIDE -------------------------
+pub fn analyze_text_fast(text: &str) -> TextStats {
+    analyze_text_fast_with(text, &AnalyzerConfig::default())
+}
+
+pub fn analyze_text_fast_with(text: &str, config: &AnalyzerConfig) -> TextStats {
+    analyze_text_fast_timed(text, config).0
+}
+
+/// Where the time of one analysis went, from `analyze_text_fast_timed` and
+/// `analyze_text_slow_timed`. Phases not listed (lines, sentences, n-grams,
+/// language detection) make up the rest of `total_ns`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Timings {
+    /// Splitting text into words outside of counting: the slow analyzer's
+    /// second pass for word lengths and longest words. The fast path
+    /// tokenizes as it counts, so 0 there.
+    pub tokenize_ns: u128,
+    /// The counting pass, tokenizing included.
+    pub count_ns: u128,
+    /// Ranking words by count and picking `top_words`.
+    pub top_k_ns: u128,
+    /// Picking `longest_words`.
+    pub longest_ns: u128,
+    pub total_ns: u128,
+}
+
+impl Timings {
+    pub fn to_json_value(&self) -> Json {
+        Json::Object(vec![
+            ("tokenize_ns".into(), self.tokenize_ns.into()),
+            ("count_ns".into(), self.count_ns.into()),
+            ("top_k_ns".into(), self.top_k_ns.into()),
+            ("longest_ns".into(), self.longest_ns.into()),
+            ("total_ns".into(), self.total_ns.into()),
+        ])
+    }
+}
+
+/// `analyze_text_fast_with`, with the time of each phase. The stats are
+/// the same as without timing.
+///
+/// ```
+/// use td5::{AnalyzerConfig, analyze_text_fast_timed, analyze_text_fast_with};
+///
+/// let text = td5::generate_test_text(10_000);
+/// let (stats, timings) = analyze_text_fast_timed(&text, &AnalyzerConfig::new());
+/// assert_eq!(stats, analyze_text_fast_with(&text, &AnalyzerConfig::new()));
+/// assert_eq!(timings.tokenize_ns, 0);
+/// assert!(timings.count_ns > 0);
+/// assert!(timings.count_ns + timings.top_k_ns + timings.longest_ns <= timings.total_ns);
+/// ```
+pub fn analyze_text_fast_timed(text: &str, config: &AnalyzerConfig) -> (TextStats, Timings) {
+    let start = Stopwatch::start();
+    let counts = match &config.progress {
+        Some(progress) => count_reporting(text, config, progress),
+        None => count(text, config),
+    };
+    let count_ns = start.elapsed_ns();
+    let (stats, timings) = finish_timed(counts, config, start);
+    (
+        stats,
+        Timings {
+            count_ns,
+            ..timings
+        },
+    )
+}
+
+/// Which of the fast analyzer's specialized paths a text took, from
+/// `analyze_text_fast_instrumented`: whether the data benefits from them.
+/// Pass counts are counting passes, one per text, or one per piece with
+/// `AnalyzerConfig::progress`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PathStats {
+    /// Passes of the lowercase-letters-and-spaces scanner.
+    pub simple_lower: usize,
+    /// Passes of the generic ASCII scanner, simple-path retries included.
+    pub ascii: usize,
+    /// Passes of the Unicode fallback.
+    pub unicode: usize,
+    /// Passes of the scanners options pick whatever the text: punctuation
+    /// splitting, `Segmentation::Words` and a custom tokenizer.
+    pub other: usize,
+    /// Simple-path passes that met a word longer than `SIMPLE_WORD_MAX` and
+    /// were counted again by the ASCII scanner.
+    pub simple_fallbacks: usize,
+    /// Finishing took the hot-only shortcut: no map, no sort.
+    pub hot_only: bool,
+    /// Tokens counted in the hot array, without hashing.
+    pub hot_tokens: usize,
+    /// Tokens counted in the hashmap.
+    pub map_tokens: usize,
+}
+
+impl PathStats {
+    /// Mirrors the choice `scan` makes.
+    fn record(&mut self, mode: Mode, config: &AnalyzerConfig) {
+        let counter = if config.tokenizer.is_some()
+            || config.segmentation == Segmentation::Words
+            || (config.splits_punctuation() && mode != Mode::SimpleLower)
+        {
+            &mut self.other
+        } else {
+            match mode {
+                Mode::SimpleLower => &mut self.simple_lower,
+                Mode::Ascii => &mut self.ascii,
+                Mode::Unicode => &mut self.unicode,
+            }
+        };
+        *counter += 1;
+    }
+
+    fn merge(&mut self, other: &PathStats) {
+        self.simple_lower += other.simple_lower;
+        self.ascii += other.ascii;
+        self.unicode += other.unicode;
+        self.other += other.other;
+        self.simple_fallbacks += other.simple_fallbacks;
+    }
+
+    /// Share of the tokens counted without hashing, 0 without tokens.
+    pub fn hot_share(&self) -> f64 {
+        match self.hot_tokens + self.map_tokens {
+            0 => 0.0,
+            total => self.hot_tokens as f64 / total as f64,
+        }
+    }
+
+    pub fn to_json_value(&self) -> Json {
+        Json::Object(vec![
+            ("simple_lower".into(), self.simple_lower.into()),
+            ("ascii".into(), self.ascii.into()),
+            ("unicode".into(), self.unicode.into()),
+            ("other".into(), self.other.into()),
+            ("simple_fallbacks".into(), self.simple_fallbacks.into()),
+            ("hot_only".into(), self.hot_only.into()),
+            ("hot_tokens".into(), self.hot_tokens.into()),
+            ("map_tokens".into(), self.map_tokens.into()),
+        ])
+    }
+}
+
+/// `analyze_text_fast_with`, with the paths the text took.
+///
+/// ```
+/// use td5::{AnalyzerConfig, analyze_text_fast_instrumented};
+///
+/// let config = AnalyzerConfig::default();
+/// let (_, paths) = analyze_text_fast_instrumented(&td5::hot_words_text(1_000), &config);
+/// assert_eq!((paths.simple_lower, paths.ascii), (1, 0));
+/// assert!(paths.hot_only);
+/// assert_eq!(paths.map_tokens, 0);
+///
+/// let (stats, paths) = analyze_text_fast_instrumented("Rust data, cat data.", &config);
+/// assert_eq!((paths.simple_lower, paths.ascii), (0, 1));
+/// assert_eq!((paths.hot_tokens, paths.map_tokens), (3, 1));
+/// assert_eq!(paths.hot_tokens + paths.map_tokens, stats.total_words);
+///
+/// let (_, paths) = analyze_text_fast_instrumented("café crème", &config);
+/// assert_eq!((paths.unicode, paths.hot_tokens), (1, 0));
+/// ```
+pub fn analyze_text_fast_instrumented(
+    text: &str,
+    config: &AnalyzerConfig,
+) -> (TextStats, PathStats) {
+    let counts = match &config.progress {
+        Some(progress) => count_reporting(text, config, progress),
+        None => count(text, config),
+    };
+    let hot_tokens = counts.hot.counts().iter().sum();
+    let paths = PathStats {
+        hot_only: counts.hot_only(config),
+        hot_tokens,
+        map_tokens: counts.tokens() - hot_tokens,
+        ..counts.paths
+    };
+    (finish(counts, config), paths)
+}
+
+/// `count` in pieces of about `config.progress_bytes`, cut at whitespace and
+/// merged in order, with a report after each.
+fn count_reporting(text: &str, config: &AnalyzerConfig, progress: &Progress) -> Counts {
+    let mode = detect(text);
+    let pieces = split_chunks(text, text.len().div_ceil(config.progress_bytes));
+    let mut total: Option<Counts> = None;
+    let mut done = 0;
+    for piece in pieces {
+        let counts = count_lenient(piece, mode, config);
+        let total = match &mut total {
+            Some(total) => {
+                total.merge(counts);
+                total
+            }
+            None => total.insert(counts),
+        };
+        done += piece.len();
+        progress.report(done, total.tokens());
+    }
+    total.expect("split_chunks returns at least one piece")
+}
+
+/// Turns raw counts into `TextStats` with `config`'s list lengths.
+pub(crate) fn finish(counts: Counts, config: &AnalyzerConfig) -> TextStats {
+    finish_timed(counts, config, Stopwatch::start()).0
+}
+
+/// `finish`, with the time spent on `top_words` and `longest_words`.
+fn finish_timed(
+    mut counts: Counts,
+    config: &AnalyzerConfig,
+    start: Stopwatch,
+) -> (TextStats, Timings) {
+    let mut timings = Timings::default();
+    let char_count = counts.char_count;
+    let diagnostics = counts.diagnostics.clone();
+    let shape = counts.shape.clone();
+    let (top_bigrams, top_trigrams) = match counts.ngrams.take() {
+        Some(ngrams) => (ngrams.top(2, config), ngrams.top(3, config)),
+        None => Default::default(),
+    };
+
+    // Hot-only fast path: no hashmap, no sort; we know the desired ordering.
+    if counts.hot_only(config) {
+        // Both lists come from one sort of at most 256 hot words.
+        let phase = Stopwatch::start();
+        let (top_words, longest_words, word_count) = match &counts.hot {
+            Hot::Builtin(_) => {
+                let mut order = HOT_ORDER;
+                hot_only(&counts.hot, &mut order, &LONGEST_ORDER, config)
+            }
+            Hot::Custom(words, _) => {
+                let mut order = words.alphabetical().to_vec();
+                hot_only(&counts.hot, &mut order, words.longest(), config)
+            }
+        };
+        timings.top_k_ns = phase.elapsed_ns();
+        let stats = TextStats {
+            word_count,
+            total_words: counts.hot.counts().iter().sum(),
+            char_count,
+            line_count: shape.lines(),
+            sentence_count: shape.sentences(),
+            mean_word_length: shape.mean_length(),
+            median_word_length: shape.median_length(),
+            word_lengths: shape.histogram(),
+            top_words,
+            surface_forms: Vec::new(),
+            detected_language: None,
+            longest_words,
+            top_bigrams,
+            top_trigrams,
+            diagnostics,
+        };
+        timings.total_ns = start.elapsed_ns();
+        return (stats, timings);
+    }
+
+    let words = counts.into_words();
+    let mut freq_vec: Vec<(&str, usize)> = words.iter().collect();
+    let unique = freq_vec.len();
+    let total_words = freq_vec.iter().map(|(_, c)| c).sum();
+
+    // Only the words that make the lists are sorted (`select_top`); the
+    // others are left unordered.
+    let phase = Stopwatch::start();
+    let (top_words, surface_forms, word_count) = match config.top_stems(freq_vec.iter().copied()) {
+        Some(stems) => stems,
+        None => {
+            let mut kept;
+            let ranked = match config.stopwords {
+                Some(_) => {
+                    kept = freq_vec
+                        .iter()
+                        .copied()
+                        .filter(|(word, _)| !config.is_stopword(word))
+                        .collect::<Vec<_>>();
+                    &mut kept[..]
+                }
+                None => &mut freq_vec[..],
+            };
+            (
+                select_top(ranked, config.top_k, by_count)
+                    .iter()
+                    .map(|&(word, count)| (word.to_string(), count))
+                    .collect(),
+                Vec::new(),
+                unique,
+            )
+        }
+    };
+    timings.top_k_ns = phase.elapsed_ns();
+
+    let phase = Stopwatch::start();
+    let mut longest: Vec<&str> = freq_vec.iter().map(|&(w, _)| w).collect();
+    let longest_words: Vec<String> = select_top(&mut longest, config.longest_n, |a, b| {
+        b.len().cmp(&a.len()).then_with(|| a.cmp(b))
+    })
+    .iter()
+    .map(|w| w.to_string())
+    .collect();
+    timings.longest_ns = phase.elapsed_ns();
+
+    let stats = TextStats {
+        word_count,
+        total_words,
+        char_count,
+        line_count: shape.lines(),
+        sentence_count: shape.sentences(),
+        mean_word_length: shape.mean_length(),
+        median_word_length: shape.median_length(),
+        word_lengths: shape.histogram(),
+        top_words,
+        surface_forms,
+        detected_language: config.detected_language(freq_vec.iter().copied()),
+        longest_words,
+        top_bigrams,
+        top_trigrams,
+        diagnostics,
+    };
+    timings.total_ns = start.elapsed_ns();
+    (stats, timings)
+}
+
+/// `top_words`, `longest_words` and `word_count` of text made only of the
+/// hot words of `vocabulary`. `order` starts alphabetical; `longest` is
+/// longest first, alphabetical among equal lengths.
+fn hot_only(
+    vocabulary: &Hot,
+    order: &mut [usize],
+    longest: &[usize],
+    config: &AnalyzerConfig,
+) -> (Vec<(String, usize)>, Vec<String>, usize) {
+    let hot = vocabulary.counts();
+    // Unseen hot words are left out, as the map path never has them.
+    let seen = |idx: &usize| config.unseen_hot_words || hot[*idx] > 0;
+    // Stable, so equal counts stay in alphabetical order.
+    order.sort_by_key(|&idx| std::cmp::Reverse(hot[idx]));
+    let mut top_words = Vec::with_capacity(config.top_k.min(hot.len()));
+    for &idx in order
+        .iter()
+        .filter(|&idx| seen(idx) && !config.is_stopword(vocabulary.word(*idx)))
+        .take(config.top_k)
+    {
+        top_words.push((vocabulary.word(idx).to_string(), hot[idx]));
+    }
+    let mut longest_words = Vec::with_capacity(config.longest_n.min(hot.len()));
+    for &idx in longest
+        .iter()
+        .filter(|&&idx| hot[idx] > 0)
+        .take(config.longest_n)
+    {
+        longest_words.push(vocabulary.word(idx).to_string());
+    }
+    (
+        top_words,
+        longest_words,
+        (0..hot.len()).filter(seen).count(),
+    )
+}
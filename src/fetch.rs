@@ -0,0 +1,239 @@
+//! Inputs given as `http://` or `https://` URLs, downloaded with the system
+//! `curl` (the `urls` feature) under a `FetchPolicy`, so a batch run over a
+//! list of links neither hangs on nor fills memory with a bad one.
+//!
+//! The body is read from curl's output and the download cut as soon as it
+//! exceeds `max_bytes`, whether or not the server announced a length.
+//! Connection failures, timeouts, `429 Too Many Requests` and `5xx` answers
+//! are retried after `backoff`, doubled on each attempt; other failures are
+//! not.
+
+use crate::clock::{self, Clock};
+use crate::error::{Result, Td5Error};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchPolicy {
+    /// Largest body accepted, in bytes.
+    pub max_bytes: u64,
+    /// Accepted media types: `text/plain`, or `text/*` for a whole family,
+    /// or `*/*` for anything.
+    pub content_types: Vec<String>,
+    pub max_redirects: u32,
+    /// Attempts after the first for transient failures.
+    pub retries: u32,
+    /// Wait before the first retry.
+    pub backoff: Duration,
+    /// Shortest time between the starts of two downloads.
+    pub interval: Duration,
+    /// Longest a single attempt may take.
+    pub timeout: Duration,
+}
+
+impl Default for FetchPolicy {
+    fn default() -> Self {
+        FetchPolicy {
+            max_bytes: 50 * 1024 * 1024,
+            content_types: [
+                "text/*",
+                "application/json",
+                "application/xml",
+                "application/xhtml+xml",
+            ]
+            .map(String::from)
+            .to_vec(),
+            max_redirects: 5,
+            retries: 3,
+            backoff: Duration::from_millis(500),
+            interval: Duration::ZERO,
+            timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+impl FetchPolicy {
+    /// Whether a `Content-Type` header value is accepted.
+    pub fn accepts(&self, content_type: &str) -> bool {
+        let media = content_type
+            .split(';')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_ascii_lowercase();
+        let family = media.split('/').next().unwrap_or("");
+        self.content_types.iter().any(|allowed| {
+            allowed == "*/*"
+                || *allowed == media
+                || allowed
+                    .strip_suffix("/*")
+                    .is_some_and(|prefix| prefix == family)
+        })
+    }
+}
+
+pub fn is_url(input: &str) -> bool {
+    input.starts_with("http://") || input.starts_with("https://")
+}
+
+/// Downloads URLs one after the other under a shared policy.
+pub struct Fetcher {
+    policy: FetchPolicy,
+    clock: Arc<dyn Clock>,
+    last_start: Option<Duration>,
+}
+
+impl fmt::Debug for Fetcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Fetcher")
+            .field("policy", &self.policy)
+            .field("last_start", &self.last_start)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Why an attempt failed, and whether another one may succeed.
+enum Failure {
+    Transient(String),
+    Permanent(String),
+}
+
+impl Fetcher {
+    pub fn new(policy: FetchPolicy) -> Self {
+        Fetcher {
+            policy,
+            clock: clock::system(),
+            last_start: None,
+        }
+    }
+
+    /// Paces downloads and waits out backoffs on `clock`.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// The body at `url`.
+    pub fn get(&mut self, url: &str) -> Result<Vec<u8>> {
+        if !cfg!(feature = "urls") {
+            return Err(Td5Error::config(format!(
+                "{url}: URL inputs need td5 built with the `urls` feature"
+            )));
+        }
+        let mut backoff = self.policy.backoff;
+        for attempt in 0.. {
+            if let Some(last) = self.last_start {
+                let elapsed = self.clock.now().saturating_sub(last);
+                self.clock
+                    .sleep(self.policy.interval.saturating_sub(elapsed));
+            }
+            self.last_start = Some(self.clock.now());
+            match download(url, &self.policy) {
+                Ok(body) => return Ok(body),
+                Err(Failure::Transient(_)) if attempt < self.policy.retries => {}
+                Err(Failure::Transient(message) | Failure::Permanent(message)) => {
+                    return Err(Td5Error::config(format!("{url}: {message}")));
+                }
+            }
+            self.clock.sleep(backoff);
+            backoff *= 2;
+        }
+        unreachable!("the loop only ends by returning")
+    }
+}
+
+fn download(url: &str, policy: &FetchPolicy) -> std::result::Result<Vec<u8>, Failure> {
+    use std::io::Read;
+    use std::process::{Command, Stdio};
+
+    const STATUS: &str = "td5-status:";
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--location"])
+        .args(["--proto", "=http,https", "--proto-redir", "=http,https"])
+        .arg("--max-redirs")
+        .arg(policy.max_redirects.to_string())
+        .arg("--max-filesize")
+        .arg(policy.max_bytes.to_string())
+        .arg("--max-time")
+        .arg(policy.timeout.as_secs().max(1).to_string())
+        .arg("--write-out")
+        .arg(format!(
+            "%{{stderr}}\n{STATUS} %{{http_code}} %{{content_type}}\n"
+        ))
+        .arg("--")
+        .arg(url)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| Failure::Permanent(format!("cannot run curl: {e}")))?;
+    let mut body = Vec::new();
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let read = stdout
+        .take(policy.max_bytes + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| Failure::Transient(e.to_string()));
+    if body.len() as u64 > policy.max_bytes {
+        let _ = child.kill();
+        let _ = child.wait();
+        return Err(Failure::Permanent(format!(
+            "larger than the {} byte limit",
+            policy.max_bytes
+        )));
+    }
+    read?;
+    let mut stderr = String::new();
+    if let Some(mut pipe) = child.stderr.take() {
+        let _ = pipe.read_to_string(&mut stderr);
+    }
+    let status = child
+        .wait()
+        .map_err(|e| Failure::Permanent(e.to_string()))?;
+    let (report, errors): (Vec<&str>, Vec<&str>) = stderr
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .partition(|l| l.starts_with(STATUS));
+    let report = report
+        .last()
+        .and_then(|l| l.strip_prefix(STATUS))
+        .unwrap_or("");
+    let (code, content_type) = report.trim().split_once(' ').unwrap_or((report.trim(), ""));
+    let code: u16 = code.parse().unwrap_or(0);
+    match status.code() {
+        Some(0) => {}
+        // Could not resolve or connect, timed out, TLS handshake failed,
+        // nothing received, send or receive failed.
+        Some(6 | 7 | 28 | 35 | 52 | 55 | 56) => {
+            return Err(Failure::Transient(errors.join("; ")));
+        }
+        Some(47) => {
+            return Err(Failure::Permanent(format!(
+                "more than {} redirects",
+                policy.max_redirects
+            )));
+        }
+        Some(63) => {
+            return Err(Failure::Permanent(format!(
+                "larger than the {} byte limit",
+                policy.max_bytes
+            )));
+        }
+        _ => return Err(Failure::Permanent(errors.join("; "))),
+    }
+    match code {
+        200..=299 => {}
+        429 | 500..=599 => return Err(Failure::Transient(format!("HTTP {code}"))),
+        _ => return Err(Failure::Permanent(format!("HTTP {code}"))),
+    }
+    if !policy.accepts(content_type) {
+        let content_type = if content_type.is_empty() {
+            "none"
+        } else {
+            content_type
+        };
+        return Err(Failure::Permanent(format!(
+            "content type {content_type} is not accepted"
+        )));
+    }
+    Ok(body)
+}
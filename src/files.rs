@@ -0,0 +1,257 @@
+//! Many files at once (`td5 analyze --jobs 4 corpus/`): each file read and
+//! analyzed on a worker thread, reported in input order, and optionally
+//! counted into an `Aggregate` for the corpus as a whole.
+//!
+//! A file goes through what `td5 analyze` does to plain text: the skip
+//! rules, gzip decompression, EPUB extraction and decoding. Workers take
+//! the next file as they finish one, so a large file does not hold up the
+//! others; results that arrive early wait until those before them are
+//! reported. The aggregate is merged from the word counts of each worker,
+//! as `parallel` merges the chunks of one text, so it is exact rather than
+//! a sum of top-word lists.
+
+use crate::clock::Stopwatch;
+use crate::error::{Result, Td5Error};
+use crate::fast::{self, Counts};
+use crate::parallel::effective_threads;
+use crate::skip::{Skip, SkipRules};
+use crate::sniff::{self, Kind};
+use crate::{AnalyzerConfig, TextStats, encoding};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+/// The word counts of several texts, merged.
+#[derive(Clone)]
+pub struct Aggregate {
+    config: AnalyzerConfig,
+    counts: Option<Counts>,
+    texts: usize,
+    start: Stopwatch,
+}
+
+impl Aggregate {
+    /// An empty aggregate, counting as `analyze_text_fast_with(_, config)`.
+    pub fn new(config: &AnalyzerConfig) -> Self {
+        Self {
+            config: config.clone(),
+            counts: None,
+            texts: 0,
+            start: Stopwatch::start(),
+        }
+    }
+
+    /// Counts `text` in. Line, sentence and n-gram counts do not run on
+    /// from one text into the next.
+    ///
+    /// ```
+    /// use td5::AnalyzerConfig;
+    /// use td5::files::Aggregate;
+    ///
+    /// let mut all = Aggregate::new(&AnalyzerConfig::default());
+    /// all.add("The cat sat.");
+    /// all.add("The dog sat on the cat.");
+    /// let stats = all.finish();
+    /// assert_eq!((stats.total_words, stats.word_count, stats.line_count), (9, 5, Some(2)));
+    /// assert_eq!(stats.top_words[0], ("the".to_string(), 3));
+    /// ```
+    pub fn add(&mut self, text: &str) {
+        let mut counts = fast::count_lenient(text, fast::detect(text), &self.config);
+        counts.close();
+        self.merge_counts(counts);
+        self.texts += 1;
+    }
+
+    /// Adds the texts of `other`, counted with the same configuration.
+    pub fn merge(&mut self, other: Aggregate) {
+        if let Some(counts) = other.counts {
+            self.merge_counts(counts);
+        }
+        self.texts += other.texts;
+    }
+
+    fn merge_counts(&mut self, counts: Counts) {
+        match &mut self.counts {
+            Some(total) => total.merge(counts),
+            None => self.counts = Some(counts),
+        }
+    }
+
+    /// Texts added so far.
+    pub fn texts(&self) -> usize {
+        self.texts
+    }
+
+    /// Time since `new`.
+    pub fn elapsed_ns(&self) -> u128 {
+        self.start.elapsed_ns()
+    }
+
+    /// The stats of all texts added.
+    pub fn finish(self) -> TextStats {
+        let counts = self
+            .counts
+            .unwrap_or_else(|| fast::count_lenient("", fast::detect(""), &self.config));
+        fast::finish(counts, &self.config)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FilesOptions {
+    /// Worker threads; 0 uses all available cores.
+    pub threads: usize,
+    pub skip: SkipRules,
+}
+
+/// What became of one file.
+#[derive(Debug)]
+pub enum FileResult {
+    /// The stats, and how long `analyze` took over them in nanoseconds.
+    Analyzed(Box<TextStats>, u128),
+    Skipped(Skip),
+    Failed(Td5Error),
+}
+
+/// Analyzes `paths` with `analyze` on `options.threads` workers, calling
+/// `on_file` for each in the order of `paths`. Files that are analyzed are
+/// also counted into `aggregate`, if given. An error from `on_file` stops
+/// the run: files not yet started are left alone and the error returned.
+pub fn analyze_files<A, E>(
+    paths: &[PathBuf],
+    analyze: &A,
+    options: &FilesOptions,
+    aggregate: Option<&mut Aggregate>,
+    mut on_file: impl FnMut(&Path, FileResult) -> std::result::Result<(), E>,
+) -> std::result::Result<(), E>
+where
+    A: Fn(&str) -> TextStats + Sync,
+{
+    let workers = effective_threads(options.threads).min(paths.len()).max(1);
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let (sender, receiver) = mpsc::channel::<(usize, FileResult)>();
+    let template = aggregate.as_deref().map(|a| Aggregate::new(&a.config));
+    let (reported, parts) = thread::scope(|s| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                let sender = sender.clone();
+                let (next, stop) = (&next, &stop);
+                let mut part = template.clone();
+                s.spawn(move || {
+                    while !stop.load(Ordering::Relaxed) {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(path) = paths.get(i) else { break };
+                        let result = analyze_file(path, analyze, &options.skip, part.as_mut());
+                        if sender.send((i, result)).is_err() {
+                            break;
+                        }
+                    }
+                    part
+                })
+            })
+            .collect();
+        drop(sender);
+        let reported = (|| {
+            let mut waiting = BTreeMap::new();
+            let mut due = 0;
+            for (i, result) in &receiver {
+                waiting.insert(i, result);
+                while let Some(result) = waiting.remove(&due) {
+                    on_file(&paths[due], result)?;
+                    due += 1;
+                }
+            }
+            Ok(())
+        })();
+        if reported.is_err() {
+            stop.store(true, Ordering::Relaxed);
+        }
+        drop(receiver);
+        let parts: Vec<Aggregate> = handles
+            .into_iter()
+            .filter_map(|h| h.join().expect("analysis thread panicked"))
+            .collect();
+        (reported, parts)
+    });
+    reported?;
+    if let Some(aggregate) = aggregate {
+        for part in parts {
+            aggregate.merge(part);
+        }
+    }
+    Ok(())
+}
+
+fn analyze_file<A>(
+    path: &Path,
+    analyze: &A,
+    rules: &SkipRules,
+    aggregate: Option<&mut Aggregate>,
+) -> FileResult
+where
+    A: Fn(&str) -> TextStats,
+{
+    let io_error = |source| Td5Error::Io {
+        path: Some(path.to_path_buf()),
+        source,
+    };
+    let size = match std::fs::metadata(path) {
+        Ok(meta) => meta.len(),
+        Err(e) => return FileResult::Failed(io_error(e)),
+    };
+    if let Some(skip) = rules.check_size(size) {
+        return FileResult::Skipped(skip);
+    }
+    let mut bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) => return FileResult::Failed(io_error(e)),
+    };
+    let mut kind = sniff::sniff(&bytes);
+    if let Kind::Compressed(compression) = kind
+        && compression.is_supported()
+    {
+        bytes = match sniff::decompress(&bytes, compression) {
+            Ok(inner) => inner,
+            Err(e) => return FileResult::Failed(e),
+        };
+        kind = sniff::sniff(&bytes);
+    }
+    if let Some(skip) = rules.check_content(&bytes) {
+        return FileResult::Skipped(skip);
+    }
+    let (text, replaced) = if kind == Kind::Epub {
+        match epub_text(&bytes) {
+            Ok(text) => (Cow::Owned(text), 0),
+            Err(e) => return FileResult::Failed(e),
+        }
+    } else {
+        let decoded = encoding::decode(&bytes);
+        (decoded.text, decoded.replaced)
+    };
+    let start = Stopwatch::start();
+    let mut stats = analyze(&text);
+    let time_ns = start.elapsed_ns();
+    stats.diagnostics.invalid_utf8 += replaced;
+    if let Some(skip) = rules.check_words(&stats) {
+        return FileResult::Skipped(skip);
+    }
+    if let Some(aggregate) = aggregate {
+        aggregate.add(&text);
+    }
+    FileResult::Analyzed(Box::new(stats), time_ns)
+}
+
+#[cfg(feature = "epub")]
+fn epub_text(bytes: &[u8]) -> Result<String> {
+    Ok(crate::epub::Book::parse(bytes)?.text().0)
+}
+
+#[cfg(not(feature = "epub"))]
+fn epub_text(_: &[u8]) -> Result<String> {
+    Err(Td5Error::config(
+        "reading EPUB needs a build with `--features epub`",
+    ))
+}
@@ -0,0 +1,205 @@
+//! A binary wire format for streaming snapshots (`td5 consume --frames`), for
+//! consumers in other languages that want a feed they can parse without
+//! guessing where one report ends.
+//!
+//! Each snapshot is one frame, integers little-endian:
+//!
+//! | Bytes | Field |
+//! |---|---|
+//! | 4 | magic, `TD5F` |
+//! | 1 | format version, `FRAME_VERSION` |
+//! | 1 | flags: bit 0 is set on the final snapshot, the others are 0 |
+//! | 4 | payload length |
+//! | n | payload: the snapshot's JSON document, encoded as CBOR |
+//! | 4 | CRC-32 (IEEE, as zlib's `crc32`) of everything before it |
+//!
+//! A reader checks the magic and version before trusting the length, and
+//! the checksum before decoding the payload, so a torn write or a feed
+//! joined mid-frame is reported instead of misread. The version is bumped
+//! whenever the layout changes; fields added to the payload do not bump it.
+
+use crate::cbor;
+use crate::checksum::crc32_update;
+use crate::error::{Result, Td5Error};
+use crate::json::Json;
+use crate::schema::DecodeError;
+use crate::streaming::PartialStats;
+use std::io::{self, Read, Write};
+
+pub const MAGIC: [u8; 4] = *b"TD5F";
+
+/// Version of the frame layout.
+pub const FRAME_VERSION: u8 = 1;
+
+/// Bytes before the payload.
+pub const HEADER_BYTES: usize = 10;
+
+/// Largest payload `FrameReader` accepts, so a corrupt length cannot make
+/// it allocate gigabytes.
+pub const MAX_PAYLOAD: usize = 64 << 20;
+
+const FINAL: u8 = 1;
+
+/// One decoded frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// The snapshot is the last of its stream.
+    pub done: bool,
+    /// CBOR-encoded snapshot document.
+    pub payload: Vec<u8>,
+}
+
+/// `doc` as a frame.
+///
+/// ```
+/// use td5::frame::{self, HEADER_BYTES};
+///
+/// let doc = td5::analyze("the cat").to_json_value();
+/// let bytes = frame::encode(&doc, true);
+/// assert_eq!(&bytes[..6], b"TD5F\x01\x01");
+/// assert_eq!(bytes.len(), HEADER_BYTES + td5::cbor::encode(&doc).len() + 4);
+/// ```
+pub fn encode(doc: &Json, done: bool) -> Vec<u8> {
+    let payload = cbor::encode(doc);
+    let mut out = Vec::with_capacity(HEADER_BYTES + payload.len() + 4);
+    out.extend_from_slice(&MAGIC);
+    out.push(FRAME_VERSION);
+    out.push(if done { FINAL } else { 0 });
+    out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    out.extend_from_slice(&payload);
+    let crc = crc32(&out);
+    out.extend_from_slice(&crc.to_le_bytes());
+    out
+}
+
+impl PartialStats {
+    /// The `--format json` document of `stats` plus `time_ns`, `bytes` and
+    /// `chunks`.
+    pub fn to_json_value(&self) -> Json {
+        let mut doc = self.stats.to_json_value();
+        doc.set("time_ns", self.time_ns.into());
+        doc.set("bytes", self.bytes.into());
+        doc.set("chunks", self.chunks.into());
+        doc
+    }
+
+    /// `to_json_value` as a frame, final if `done`.
+    pub fn to_frame(&self) -> Vec<u8> {
+        encode(&self.to_json_value(), self.done)
+    }
+}
+
+/// Writes frames to `W`, flushing after each so a reader sees it at once.
+pub struct FrameWriter<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> FrameWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner }
+    }
+
+    pub fn write(&mut self, doc: &Json, done: bool) -> io::Result<()> {
+        self.inner.write_all(&encode(doc, done))?;
+        self.inner.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+/// Reads and checks frames from `R`.
+///
+/// ```
+/// use td5::frame::{FrameReader, FrameWriter};
+/// use td5::streaming::StreamingAnalyzer;
+///
+/// let mut analyzer = StreamingAnalyzer::default();
+/// let mut writer = FrameWriter::new(Vec::new());
+/// analyzer.push("the cat ");
+/// writer.write(&analyzer.snapshot().to_json_value(), false).unwrap();
+/// let last = analyzer.finish();
+/// writer.write(&last.to_json_value(), last.done).unwrap();
+/// let mut bytes = writer.into_inner();
+///
+/// let mut reader = FrameReader::new(&bytes[..]);
+/// assert!(!reader.next_frame().unwrap().unwrap().done);
+/// assert!(reader.next_frame().unwrap().unwrap().done);
+/// assert!(reader.next_frame().unwrap().is_none());
+///
+/// // A flipped bit is caught by the checksum.
+/// bytes[12] ^= 1;
+/// assert!(FrameReader::new(&bytes[..]).next_frame().is_err());
+/// ```
+pub struct FrameReader<R: Read> {
+    inner: R,
+}
+
+impl<R: Read> FrameReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self { inner }
+    }
+
+    /// The next frame, or `None` at a clean end of input. Input ending
+    /// inside a frame is an `UnexpectedEof` error.
+    pub fn next_frame(&mut self) -> Result<Option<Frame>> {
+        let mut header = [0; HEADER_BYTES];
+        let read = read_full(&mut self.inner, &mut header)?;
+        if read == 0 {
+            return Ok(None);
+        }
+        if read < HEADER_BYTES {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        if header[..4] != MAGIC {
+            return Err(DecodeError::InvalidField("magic").into());
+        }
+        if header[4] != FRAME_VERSION {
+            return Err(DecodeError::InvalidField("version").into());
+        }
+        let length = u32::from_le_bytes(header[6..10].try_into().expect("4 bytes")) as usize;
+        if length > MAX_PAYLOAD {
+            return Err(Td5Error::LimitExceeded {
+                limit: "frame payload",
+                max: MAX_PAYLOAD,
+                actual: length,
+            });
+        }
+        let mut rest = vec![0; length + 4];
+        self.inner.read_exact(&mut rest)?;
+        let (payload, crc) = rest.split_at(length);
+        let expected = crc32_update(crc32_update(!0, &header), payload) ^ !0;
+        if crc != expected.to_le_bytes() {
+            return Err(DecodeError::InvalidField("checksum").into());
+        }
+        rest.truncate(length);
+        Ok(Some(Frame {
+            done: header[5] & FINAL != 0,
+            payload: rest,
+        }))
+    }
+}
+
+/// Fills `buf` unless the input ends first; the bytes read.
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// CRC-32 with the IEEE polynomial, reflected, as zlib and PNG use.
+///
+/// ```
+/// assert_eq!(td5::frame::crc32(b"123456789"), 0xCBF4_3926);
+/// ```
+pub fn crc32(data: &[u8]) -> u32 {
+    crc32_update(!0, data) ^ !0
+}
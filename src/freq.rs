@@ -0,0 +1,562 @@
+use crate::error::{Result, Td5Error};
+use crate::fast::{self, Counts, FastMap, Shape};
+use crate::interner::Interner;
+use crate::json::Json;
+use crate::{AnalyzerConfig, TextStats};
+use std::borrow::Cow;
+use std::io;
+use std::sync::Arc;
+
+/// Full word → count table, tokenized exactly like `analyze_text_fast`.
+///
+/// Words are reference-counted so that tables of many documents can share
+/// them through an `Interner`. Unlike `TextStats`, nothing is truncated, so
+/// tables of several texts or shards add up exactly and the stats are taken
+/// once at the end.
+///
+/// ```
+/// use td5::WordFrequency;
+///
+/// let mut all = WordFrequency::default();
+/// all.add_text("the cat sat on the mat");
+/// all.merge(WordFrequency::from_text("the dog sat"));
+/// assert_eq!(all.count("The"), 3);
+/// assert_eq!(all.top_k(2), [("the", 3), ("sat", 2)]);
+/// assert_eq!(all.iter().count(), 6);
+/// let stats = all.to_stats();
+/// assert_eq!((stats.total_words, stats.word_count), (9, 6));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct WordFrequency {
+    counts: FastMap<Arc<str>, usize>,
+    char_count: usize,
+}
+
+impl WordFrequency {
+    pub fn from_text(text: &str) -> Self {
+        Self::from_counts(fast::count(text, &AnalyzerConfig::default()))
+    }
+
+    pub(crate) fn from_counts(counts: Counts) -> Self {
+        Self {
+            char_count: counts.char_count(),
+            counts: counts
+                .into_map()
+                .into_iter()
+                .map(|(word, count)| (Arc::from(word), count))
+                .collect(),
+        }
+    }
+
+    /// A table of `words`, as saved by `to_json_value`.
+    #[cfg(feature = "fs")]
+    pub(crate) fn from_parts<'a>(
+        words: impl IntoIterator<Item = (&'a str, usize)>,
+        char_count: usize,
+    ) -> Self {
+        Self {
+            counts: words
+                .into_iter()
+                .map(|(word, count)| (Arc::from(word), count))
+                .collect(),
+            char_count,
+        }
+    }
+
+    /// Counts `text` into the table, as `merge(WordFrequency::from_text(text))`.
+    pub fn add_text(&mut self, text: &str) {
+        self.merge(Self::from_text(text));
+    }
+
+    /// Counts `text` with its words stored in `interner`.
+    pub fn from_text_interned(text: &str, interner: &Interner) -> Self {
+        let mut freq = Self::from_text(text);
+        freq.intern(interner);
+        freq
+    }
+
+    /// Replaces every word with `interner`'s copy, so the words this table
+    /// shares with other interned tables are stored once.
+    pub fn intern(&mut self, interner: &Interner) {
+        self.counts = std::mem::take(&mut self.counts)
+            .into_iter()
+            .map(|(word, count)| (interner.intern_arc(word), count))
+            .collect();
+    }
+
+    /// Occurrences of `word`, matched case-insensitively.
+    pub fn count(&self, word: &str) -> usize {
+        self.counts
+            .get(word.to_lowercase().as_str())
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Number of distinct words.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Total number of counted tokens.
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    pub fn char_count(&self) -> usize {
+        self.char_count
+    }
+
+    /// Unordered iteration over every word and its count.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.counts.iter().map(|(w, &c)| (&**w, c))
+    }
+
+    /// Every word, most frequent first, ties broken alphabetically. Sorts
+    /// borrowed entries once; no word is copied.
+    pub fn by_count(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.sorted_by(by_count)
+    }
+
+    /// Up to `limit` words from the `offset`th on in the `by_count` order:
+    /// one page of a vocabulary too large to list at once. Only the words
+    /// before the end of the page are sorted. To go through several pages,
+    /// keep a `pages` cursor instead, which sorts the vocabulary once.
+    ///
+    /// ```
+    /// use td5::WordFrequency;
+    ///
+    /// let freq = WordFrequency::from_text("a a a b b c d e e e e");
+    /// assert_eq!(freq.frequencies_page(0, 2), [("e", 4), ("a", 3)]);
+    /// assert_eq!(freq.frequencies_page(2, 2), [("b", 2), ("c", 1)]);
+    /// assert_eq!(freq.frequencies_page(4, 2), [("d", 1)]);
+    /// assert!(freq.frequencies_page(5, 2).is_empty());
+    /// ```
+    pub fn frequencies_page(&self, offset: usize, limit: usize) -> Vec<(&str, usize)> {
+        self.pages().page(offset, limit).to_vec()
+    }
+
+    /// A cursor over the pages of `frequencies_page`.
+    pub fn pages(&self) -> Pages<'_> {
+        Pages {
+            words: self.iter().collect(),
+            sorted: 0,
+        }
+    }
+
+    /// Every word in alphabetical (byte) order.
+    pub fn alphabetical(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.sorted_by(|a, b| a.0.cmp(b.0))
+    }
+
+    /// Every word, longest (in characters) first, ties broken alphabetically.
+    pub fn by_length(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.sorted_by(|a, b| {
+            let chars = |w: &str| w.chars().count();
+            chars(b.0).cmp(&chars(a.0)).then_with(|| a.0.cmp(b.0))
+        })
+    }
+
+    fn sorted_by(
+        &self,
+        order: impl FnMut(&(&str, usize), &(&str, usize)) -> std::cmp::Ordering,
+    ) -> impl Iterator<Item = (&str, usize)> {
+        let mut all: Vec<(&str, usize)> = self.iter().collect();
+        all.sort_unstable_by(order);
+        all.into_iter()
+    }
+
+    /// The `n` most frequent words, ties broken alphabetically, as the first
+    /// `n` of `by_count` but without sorting the whole vocabulary.
+    ///
+    /// ```
+    /// use td5::WordFrequency;
+    ///
+    /// let freq = WordFrequency::from_text("b a c b d a e b");
+    /// assert_eq!(freq.top_k(3), [("b", 3), ("a", 2), ("c", 1)]);
+    /// assert_eq!(freq.top_k(3), freq.by_count().take(3).collect::<Vec<_>>());
+    /// assert_eq!(freq.top_k(9).len(), 5);
+    /// ```
+    pub fn top_k(&self, n: usize) -> Vec<(&str, usize)> {
+        let mut words: Vec<(&str, usize)> = self.iter().collect();
+        let top = select_top(&mut words, n, by_count).len();
+        words.truncate(top);
+        words
+    }
+}
+
+/// The vocabulary of a `WordFrequency` in the `by_count` order, a page at a
+/// time. The first page sorts only the words up to its end; a page past them
+/// sorts the rest, once, so going through every page takes one full sort.
+///
+/// ```
+/// use td5::WordFrequency;
+///
+/// let freq = WordFrequency::from_text("a a a b b c d e e e e");
+/// let mut pages = freq.pages();
+/// assert_eq!(pages.page(0, 2), [("e", 4), ("a", 3)]);
+/// assert_eq!(pages.page(2, 2), [("b", 2), ("c", 1)]);
+/// assert_eq!(pages.page(4, 2), [("d", 1)]);
+/// assert!(pages.page(5, 2).is_empty());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Pages<'a> {
+    words: Vec<(&'a str, usize)>,
+    /// How many words at the front of `words` are in their final order.
+    sorted: usize,
+}
+
+impl<'a> Pages<'a> {
+    /// Up to `limit` words from the `offset`th on.
+    pub fn page(&mut self, offset: usize, limit: usize) -> &[(&'a str, usize)] {
+        let end = offset.saturating_add(limit).min(self.words.len());
+        if end > self.sorted {
+            if self.sorted == 0 {
+                select_top(&mut self.words, end, by_count);
+                self.sorted = end;
+            } else {
+                // The words behind the sorted ones all come after them.
+                self.words[self.sorted..].sort_unstable_by(by_count);
+                self.sorted = self.words.len();
+            }
+        }
+        self.words.get(offset..end).unwrap_or_default()
+    }
+
+    /// Number of words over all the pages.
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+/// Word-list layouts understood by spellcheckers and prediction tools.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListFormat {
+    /// `count<TAB>word`, most frequent first.
+    Tsv,
+    /// Hunspell `.dic`: the number of entries, then one word per line, most
+    /// frequent first (the order Hunspell uses for suggestions).
+    Dic,
+}
+
+impl ListFormat {
+    pub fn parse(name: &str) -> Result<ListFormat> {
+        match name {
+            "tsv" => Ok(ListFormat::Tsv),
+            "dic" => Ok(ListFormat::Dic),
+            _ => Err(Td5Error::config(format!(
+                "unknown list format `{name}` (expected tsv or dic)"
+            ))),
+        }
+    }
+
+    /// Writes `words`, in their order, to `out` a line at a time, e.g. a
+    /// page from `WordFrequency::frequencies_page`.
+    pub fn write(self, words: &[(&str, usize)], out: impl io::Write) -> io::Result<()> {
+        self.write_iter(words.len(), words.iter().copied(), out)
+    }
+
+    /// `write` for words taken from an iterator as they are written; `len`
+    /// is their number, which a `.dic` gives first.
+    pub fn write_iter<'a>(
+        self,
+        len: usize,
+        words: impl IntoIterator<Item = (&'a str, usize)>,
+        mut out: impl io::Write,
+    ) -> io::Result<()> {
+        match self {
+            ListFormat::Tsv => {
+                for (word, count) in words {
+                    writeln!(out, "{count}\t{word}")?;
+                }
+            }
+            ListFormat::Dic => {
+                writeln!(out, "{len}")?;
+                for (word, _) in words {
+                    writeln!(out, "{word}")?;
+                }
+            }
+        }
+        out.flush()
+    }
+}
+
+/// Most frequent first, ties broken alphabetically.
+pub(crate) fn by_count(a: &(&str, usize), b: &(&str, usize)) -> std::cmp::Ordering {
+    b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0))
+}
+
+/// The first `k` of `items` in `order`, sorted, at the front of `items`; the
+/// rest stay behind them in no particular order. Selecting them first
+/// (`select_nth_unstable_by`, linear on average) and sorting only those
+/// takes O(n + k log k) instead of the O(n log n) of a full sort. `order`
+/// must be total, as ties broken alphabetically make it, for the result to
+/// be that of the full sort.
+pub(crate) fn select_top<T>(
+    items: &mut [T],
+    k: usize,
+    mut order: impl FnMut(&T, &T) -> std::cmp::Ordering,
+) -> &mut [T] {
+    let k = k.min(items.len());
+    if k < items.len() {
+        items.select_nth_unstable_by(k, &mut order);
+    }
+    let top = &mut items[..k];
+    top.sort_unstable_by(order);
+    top
+}
+
+impl WordFrequency {
+    /// Adds the counts of `other`, e.g. another file of the same corpus.
+    pub fn merge(&mut self, other: WordFrequency) {
+        for (word, count) in other.counts {
+            *self.counts.entry(word).or_insert(0) += count;
+        }
+        self.char_count += other.char_count;
+    }
+
+    /// The table with every word replaced by `f(word)`; words mapped to the
+    /// same one are counted together and words mapped to "" dropped.
+    pub(crate) fn map_words<'a>(&'a self, mut f: impl FnMut(&'a str) -> Cow<'a, str>) -> Self {
+        let mut counts: FastMap<Arc<str>, usize> = FastMap::default();
+        counts.reserve(self.counts.len());
+        for (word, &count) in &self.counts {
+            let mapped = f(word);
+            if mapped.is_empty() {
+                continue;
+            }
+            // An unchanged word keeps sharing its key.
+            let key = if *mapped == **word {
+                Arc::clone(word)
+            } else {
+                Arc::from(mapped.as_ref())
+            };
+            *counts.entry(key).or_insert(0) += count;
+        }
+        Self {
+            counts,
+            char_count: self.char_count,
+        }
+    }
+
+    /// Removes the counts of `other`, which must have been merged in (or
+    /// counted from part of the same text) before.
+    pub(crate) fn subtract(&mut self, other: WordFrequency) {
+        for (word, count) in other.counts {
+            if let Some(current) = self.counts.get_mut(&word) {
+                *current -= count;
+                if *current == 0 {
+                    self.counts.remove(&word);
+                }
+            }
+        }
+        self.char_count -= other.char_count;
+    }
+
+    /// The stats of the counted text as `analyze_text_fast` computes them,
+    /// word lengths included, but without diagnostics, lines or sentences,
+    /// which the table does not keep (`line_count` and `sentence_count` are
+    /// `None`).
+    ///
+    /// ```
+    /// use td5::freq::WordFrequency;
+    ///
+    /// let stats = WordFrequency::from_text("the cat and the hat.").to_stats();
+    /// assert_eq!((stats.word_lengths, stats.median_word_length), (vec![0, 0, 5], 3));
+    /// assert_eq!(stats.line_count, None);
+    /// ```
+    pub fn to_stats(&self) -> TextStats {
+        self.to_stats_with(None)
+    }
+
+    /// `to_stats`, with the lines and sentences of `scanned`, the shape of
+    /// the text these words were counted from.
+    pub(crate) fn to_stats_with(&self, scanned: Option<&Shape>) -> TextStats {
+        let counts = self
+            .counts
+            .iter()
+            .map(|(word, &count)| (word.to_string(), count))
+            .collect();
+        let mut counts = Counts::from_map(counts, self.char_count);
+        let shape = Shape::of_words(self.counts.iter().map(|(word, &count)| (&**word, count)));
+        counts.shape = match scanned {
+            Some(scanned) => shape.with_lines_of(scanned),
+            None => shape,
+        };
+        fast::finish(counts, &AnalyzerConfig::default())
+    }
+
+    /// The words seen at least `min_count` times as a word → count object,
+    /// most frequent first, ties broken alphabetically.
+    pub fn to_json_value(&self, min_count: usize) -> Json {
+        Json::Object(
+            self.by_count()
+                .take_while(|&(_, count)| count >= min_count)
+                .map(|(word, count)| (word.to_string(), count.into()))
+                .collect(),
+        )
+    }
+
+    /// Every word seen at least `min_count` times in `format`, ties broken
+    /// alphabetically. Words are lowercase, as counted.
+    pub fn to_list(&self, format: ListFormat, min_count: usize) -> String {
+        let mut out = Vec::new();
+        self.write_list(format, min_count, &mut out)
+            .expect("writing to a Vec cannot fail");
+        String::from_utf8(out).expect("words are UTF-8")
+    }
+
+    /// `to_list` written to `out` a word at a time, so that the list of a
+    /// large vocabulary is never held as one string. `out` should be
+    /// buffered.
+    ///
+    /// ```
+    /// use td5::WordFrequency;
+    /// use td5::freq::ListFormat;
+    ///
+    /// let mut out = Vec::new();
+    /// let freq = WordFrequency::from_text("the cat and the hat");
+    /// freq.write_list(ListFormat::Tsv, 2, &mut out)?;
+    /// assert_eq!(out, b"2\tthe\n");
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn write_list(
+        &self,
+        format: ListFormat,
+        min_count: usize,
+        out: impl io::Write,
+    ) -> io::Result<()> {
+        let len = self
+            .counts
+            .values()
+            .filter(|&&count| count >= min_count)
+            .count();
+        let words = self.by_count().take_while(|&(_, count)| count >= min_count);
+        format.write_iter(len, words, out)
+    }
+}
+
+/// Upper bounds of the `Distribution::bands`; the last band is open.
+const BAND_LIMITS: [usize; 5] = [1, 5, 20, 100, 1000];
+/// Quantiles reported by `Distribution::quantiles`.
+const QUANTILES: [f64; 5] = [0.25, 0.5, 0.75, 0.9, 0.99];
+
+/// Words sharing a range of counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Band {
+    pub min: usize,
+    /// `None` for the last, open-ended band.
+    pub max: Option<usize>,
+    /// Distinct words counted `min..=max` times.
+    pub words: usize,
+    /// Their occurrences.
+    pub tokens: usize,
+}
+
+/// How counts are spread over the vocabulary: the frequency of each
+/// frequency, grouped into bands, and quantiles of the count per word.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Distribution {
+    /// `(count, words)`: how many words occur exactly `count` times, by
+    /// increasing count.
+    pub spectrum: Vec<(usize, usize)>,
+    /// 1, 2–5, 6–20, 21–100, 101–1000 and more than 1000 occurrences.
+    pub bands: Vec<Band>,
+    /// `(q, count)`: the count below which a share `q` of the words fall
+    /// (nearest rank).
+    pub quantiles: Vec<(f64, usize)>,
+}
+
+impl WordFrequency {
+    /// The spread of the counts, from the table alone (no pass over the text,
+    /// no sort of the words).
+    pub fn distribution(&self) -> Distribution {
+        let mut spectrum: FastMap<usize, usize> = FastMap::default();
+        for &count in self.counts.values() {
+            *spectrum.entry(count).or_insert(0) += 1;
+        }
+        let mut spectrum: Vec<(usize, usize)> = spectrum.into_iter().collect();
+        spectrum.sort_unstable();
+
+        let mut bands: Vec<Band> = Vec::with_capacity(BAND_LIMITS.len() + 1);
+        let mut min = 1;
+        for max in BAND_LIMITS.map(Some).into_iter().chain([None]) {
+            bands.push(Band {
+                min,
+                max,
+                words: 0,
+                tokens: 0,
+            });
+            min = max.map_or(min, |max| max + 1);
+        }
+        for &(count, words) in &spectrum {
+            let band = bands
+                .iter_mut()
+                .find(|b| b.max.is_none_or(|max| count <= max))
+                .expect("the last band is open");
+            band.words += words;
+            band.tokens += count * words;
+        }
+
+        let total_words: usize = spectrum.iter().map(|&(_, words)| words).sum();
+        let quantiles = QUANTILES
+            .iter()
+            .map(|&q| {
+                let rank = ((q * total_words as f64).ceil() as usize).max(1);
+                let mut seen = 0;
+                let count = spectrum
+                    .iter()
+                    .find(|&&(_, words)| {
+                        seen += words;
+                        seen >= rank
+                    })
+                    .map_or(0, |&(count, _)| count);
+                (q, count)
+            })
+            .collect();
+        Distribution {
+            spectrum,
+            bands,
+            quantiles,
+        }
+    }
+}
+
+impl Distribution {
+    pub fn to_json_value(&self) -> Json {
+        let bands = self
+            .bands
+            .iter()
+            .map(|b| {
+                Json::Object(vec![
+                    ("min".into(), b.min.into()),
+                    ("max".into(), b.max.map_or(Json::Null, Json::from)),
+                    ("words".into(), b.words.into()),
+                    ("tokens".into(), b.tokens.into()),
+                ])
+            })
+            .collect();
+        let quantiles = self
+            .quantiles
+            .iter()
+            .map(|&(q, count)| (format!("p{}", (q * 100.0).round()), count.into()))
+            .collect();
+        let spectrum = self
+            .spectrum
+            .iter()
+            .map(|&(count, words)| Json::Array(vec![count.into(), words.into()]))
+            .collect();
+        Json::Object(vec![
+            ("bands".into(), Json::Array(bands)),
+            ("quantiles".into(), Json::Object(quantiles)),
+            ("spectrum".into(), Json::Array(spectrum)),
+        ])
+    }
+}
@@ -0,0 +1,307 @@
+//! Test text behind one trait, for `td5 demo --source`, `cargo bench` and
+//! users' own tests and benchmarks.
+//!
+//! A `TextSource` makes a text of any number of words, the same one on every
+//! call. The sources are:
+//!
+//! | Source | Text |
+//! |---|---|
+//! | `Cyclic` | a word list repeated in order, by default the ten hot words |
+//! | `shapes::TextGenerator` | Zipf-distributed prose (`generate_test_text`) |
+//! | `shapes::Shape` | one of the scanner-specific shapes, seed 0 |
+//! | `Markov` | a word chain trained on a sample text |
+//! | `FileText` | a file, repeated as needed |
+//!
+//! `source` builds one from a spec such as `zipf` or `markov:book.txt`.
+
+use crate::error::{Result, Td5Error};
+use crate::shapes::{HOT_WORDS, Rng, Shape, TextGenerator};
+use std::collections::HashMap;
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+/// Something that makes test text.
+///
+/// ```
+/// use td5::generator::{Cyclic, TextSource};
+/// use td5::shapes::TextGenerator;
+///
+/// let sources: Vec<Box<dyn TextSource>> = vec![
+///     Box::new(Cyclic::hot_words()),
+///     Box::new(TextGenerator::new().seed(3)),
+/// ];
+/// for source in &sources {
+///     let text = source.text(500);
+///     assert_eq!(td5::analyze(&text).total_words, 500, "{}", source.name());
+///     assert_eq!(text, source.text(500));
+/// }
+/// ```
+pub trait TextSource {
+    /// A short label for reports, such as `hot` or `markov`.
+    fn name(&self) -> String;
+
+    /// A text of `words` whitespace-separated words.
+    fn text(&self, words: usize) -> String;
+}
+
+/// A word list repeated in order, separated by single spaces.
+///
+/// ```
+/// use td5::generator::{Cyclic, TextSource};
+///
+/// let source = Cyclic::new(["to", "be", "or", "not"]);
+/// assert_eq!(source.text(6), "to be or not to be");
+/// assert_eq!(Cyclic::hot_words().text(2000), td5::hot_words_text(2000));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cyclic {
+    words: Vec<String>,
+}
+
+impl Cyclic {
+    pub fn new<S: Into<String>>(words: impl IntoIterator<Item = S>) -> Self {
+        Cyclic {
+            words: words.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// The ten hot words: text that never leaves the fast path's hot-only
+    /// shortcut.
+    pub fn hot_words() -> Self {
+        Self::new(HOT_WORDS)
+    }
+}
+
+impl TextSource for Cyclic {
+    fn name(&self) -> String {
+        match self.words == HOT_WORDS {
+            true => "hot".into(),
+            false => "cyclic".into(),
+        }
+    }
+
+    /// Empty when the list is.
+    fn text(&self, words: usize) -> String {
+        self.words
+            .iter()
+            .cycle()
+            .take(if self.words.is_empty() { 0 } else { words })
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl TextSource for TextGenerator {
+    fn name(&self) -> String {
+        "prose".into()
+    }
+
+    fn text(&self, words: usize) -> String {
+        self.generate(words)
+    }
+}
+
+impl TextSource for Shape {
+    fn name(&self) -> String {
+        Shape::name(*self).into()
+    }
+
+    fn text(&self, words: usize) -> String {
+        self.generate(words, 0)
+    }
+}
+
+/// A `Shape` with another seed than 0.
+struct SeededShape(Shape, u64);
+
+impl TextSource for SeededShape {
+    fn name(&self) -> String {
+        self.0.name().into()
+    }
+
+    fn text(&self, words: usize) -> String {
+        self.0.generate(words, self.1)
+    }
+}
+
+/// Text from a first-order word chain: each word is followed by one of the
+/// words that follow it in the sample, drawn in proportion to how often they
+/// do. Words keep their punctuation and case, so the text reads like the
+/// sample, and the vocabulary and word frequencies come out close to it.
+///
+/// ```
+/// use td5::generator::{Markov, TextSource};
+///
+/// let sample = "the cat sat on the mat. the dog sat on the cat.";
+/// let source = Markov::train(sample).seed(1);
+/// let text = source.text(100);
+/// assert_eq!(text.split_whitespace().count(), 100);
+/// assert!(text.split_whitespace().all(|word| sample.split_whitespace().any(|w| w == word)));
+/// assert_eq!(text, source.text(100));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Markov {
+    words: Vec<String>,
+    /// Indices into `words` of every word that follows each word, once per
+    /// occurrence.
+    next: Vec<Vec<u32>>,
+    seed: u64,
+}
+
+impl Markov {
+    /// The chain of `sample`'s whitespace-separated words. The last word of
+    /// the sample leads back to its first.
+    pub fn train(sample: &str) -> Self {
+        let mut index: HashMap<&str, u32> = HashMap::new();
+        let mut words = Vec::new();
+        let mut next: Vec<Vec<u32>> = Vec::new();
+        let mut previous = None;
+        for word in sample.split_whitespace() {
+            let id = *index.entry(word).or_insert_with(|| {
+                words.push(word.to_string());
+                next.push(Vec::new());
+                (words.len() - 1) as u32
+            });
+            if let Some(previous) = previous {
+                next[previous as usize].push(id);
+            }
+            previous = Some(id);
+        }
+        if let Some(last) = previous {
+            next[last as usize].push(0);
+        }
+        Markov {
+            words,
+            next,
+            seed: 0,
+        }
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+impl TextSource for Markov {
+    fn name(&self) -> String {
+        "markov".into()
+    }
+
+    /// Starts from a random word of the sample; empty if the sample had
+    /// none.
+    fn text(&self, words: usize) -> String {
+        if self.words.is_empty() {
+            return String::new();
+        }
+        let mut rng = Rng(self.seed ^ 0x9E37_79B9_7F4A_7C15);
+        let mut word = rng.below(self.words.len());
+        let mut out = String::with_capacity(words * 8);
+        for i in 0..words {
+            if i > 0 {
+                out.push(' ');
+            }
+            out.push_str(&self.words[word]);
+            let next = &self.next[word];
+            word = next[rng.below(next.len())] as usize;
+        }
+        out
+    }
+}
+
+/// A file's text, cut after the wanted number of words, or repeated with a
+/// line break between copies when it has fewer.
+#[derive(Debug, Clone)]
+pub struct FileText {
+    text: String,
+}
+
+impl FileText {
+    /// Reads `path` in whatever encoding it uses, as `td5 analyze` does.
+    ///
+    /// ```
+    /// use td5::generator::{FileText, TextSource};
+    ///
+    /// let path = std::env::temp_dir().join(format!("td5-file-text-{}.txt", std::process::id()));
+    /// std::fs::write(&path, "One two.\nThree\n").unwrap();
+    /// let source = FileText::open(&path).unwrap();
+    /// assert_eq!(source.text(2), "One two.");
+    /// assert_eq!(source.text(5), "One two.\nThree\nOne two.");
+    /// std::fs::remove_file(&path).unwrap();
+    /// ```
+    #[cfg(feature = "fs")]
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let (text, _) = crate::input::read_transcoded(path)?;
+        Ok(Self::from(text))
+    }
+}
+
+impl From<String> for FileText {
+    fn from(text: String) -> Self {
+        FileText { text }
+    }
+}
+
+impl TextSource for FileText {
+    fn name(&self) -> String {
+        "file".into()
+    }
+
+    /// Empty when the file has no words.
+    fn text(&self, words: usize) -> String {
+        let body = self.text.trim_end();
+        let per_copy = body.split_whitespace().count();
+        if per_copy == 0 || words == 0 {
+            return String::new();
+        }
+        let mut out = String::with_capacity(words / per_copy * (body.len() + 1) + body.len());
+        let mut left = words;
+        while left > per_copy {
+            out.push_str(body);
+            out.push('\n');
+            left -= per_copy;
+        }
+        // The end of the `left`th word of one more copy.
+        let end = body
+            .split_whitespace()
+            .nth(left - 1)
+            .map(|word| word.as_ptr() as usize - body.as_ptr() as usize + word.len())
+            .unwrap_or(body.len());
+        out.push_str(&body[..end]);
+        out
+    }
+}
+
+/// The source a spec names: `hot`, `prose`, a `Shape` name such as `zipf`
+/// or `unicode`, `markov:PATH` (trained on a file) or `file:PATH`. `seed`
+/// applies to every generated source.
+///
+/// ```
+/// use td5::generator;
+///
+/// let source = generator::source("unicode", 0).unwrap();
+/// assert_eq!(source.name(), "unicode");
+/// assert!(generator::source("lorem", 0).is_err());
+/// ```
+pub fn source(spec: &str, seed: u64) -> Result<Box<dyn TextSource>> {
+    #[cfg(feature = "fs")]
+    if let Some(path) = spec.strip_prefix("markov:") {
+        let (sample, _) = crate::input::read_transcoded(path)?;
+        return Ok(Box::new(Markov::train(&sample).seed(seed)));
+    }
+    #[cfg(feature = "fs")]
+    if let Some(path) = spec.strip_prefix("file:") {
+        return Ok(Box::new(FileText::open(path)?));
+    }
+    match spec {
+        "hot" => Ok(Box::new(Cyclic::hot_words())),
+        "prose" => Ok(Box::new(TextGenerator::new().seed(seed))),
+        _ => match Shape::from_name(spec) {
+            Some(shape) => Ok(Box::new(SeededShape(shape, seed))),
+            None => Err(Td5Error::config(format!(
+                "unknown text source `{spec}` (expected hot, prose, a shape such as zipf, markov:PATH or file:PATH)"
+            ))),
+        },
+    }
+}
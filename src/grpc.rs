@@ -0,0 +1,1004 @@
+//! A gRPC server for the `Analyzer` service of `proto/td5.proto` (the
+//! `grpc` feature, behind `td5 grpc`).
+//!
+//! It speaks HTTP/2 over cleartext TCP with prior knowledge (h2c), as gRPC
+//! clients do for `http://` targets, on `std::net` alone: one thread per
+//! connection, whose calls are answered in turn. There is no TLS (put a
+//! proxy in front for that) and no message compression. `Analyze` runs the
+//! requested analyzer over the text; `AnalyzeStream` feeds each `TextChunk`
+//! to a `StreamingAnalyzer` as it arrives, keeping only the counts. Both
+//! answer with the `TextStats` message of `TextStats::to_protobuf`.
+//!
+//! `Limits` bounds what a client can take: message size, connections, calls
+//! per connection, and how long calls and idle connections last. A
+//! `Shutdown` handle stops the server gracefully: it accepts nothing more,
+//! tells each connection with a GOAWAY, and waits for the calls in progress.
+//! Deadlines and idle time are read from the server's `Clock`.
+
+use crate::TextStats;
+use crate::analyzer;
+use crate::clock::{self, Clock, Stopwatch};
+use crate::config::AnalyzerConfig;
+use crate::error::Result;
+use crate::hpack::{self, Decoder};
+use crate::proto;
+use crate::streaming::StreamingAnalyzer;
+use std::collections::HashMap;
+use std::io::{self, BufWriter, Read, Write};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+// Frame types.
+const DATA: u8 = 0;
+const HEADERS: u8 = 1;
+const RST_STREAM: u8 = 3;
+const SETTINGS: u8 = 4;
+const PUSH_PROMISE: u8 = 5;
+const PING: u8 = 6;
+const GOAWAY: u8 = 7;
+const WINDOW_UPDATE: u8 = 8;
+const CONTINUATION: u8 = 9;
+
+// Frame flags.
+const END_STREAM: u8 = 0x1;
+const ACK: u8 = 0x1;
+const END_HEADERS: u8 = 0x4;
+const PADDED: u8 = 0x8;
+const PRIORITY: u8 = 0x20;
+
+// Settings.
+const MAX_CONCURRENT_STREAMS: u16 = 0x3;
+const INITIAL_WINDOW_SIZE: u16 = 0x4;
+const MAX_FRAME_SIZE: u16 = 0x5;
+
+// HTTP/2 error codes.
+const NO_ERROR: u32 = 0x0;
+const PROTOCOL_ERROR: u32 = 0x1;
+const FLOW_CONTROL_ERROR: u32 = 0x3;
+const FRAME_SIZE_ERROR: u32 = 0x6;
+const REFUSED_STREAM: u32 = 0x7;
+const CANCEL: u32 = 0x8;
+const COMPRESSION_ERROR: u32 = 0x9;
+const ENHANCE_YOUR_CALM: u32 = 0xb;
+
+// gRPC status codes.
+const INVALID_ARGUMENT: u32 = 3;
+const DEADLINE_EXCEEDED: u32 = 4;
+const RESOURCE_EXHAUSTED: u32 = 8;
+const UNIMPLEMENTED: u32 = 12;
+
+/// The frame size every peer must accept, and the largest this server reads.
+const MAX_FRAME: usize = 16 << 10;
+/// The flow-control window before any setting or update.
+const DEFAULT_WINDOW: i64 = 65_535;
+/// The receive window of each stream and of the connection, given back as
+/// data arrives.
+const WINDOW: u32 = 1 << 20;
+/// A header block, CONTINUATION frames included.
+const MAX_HEADER_BLOCK: usize = 64 << 10;
+/// How often a connection waiting for input checks deadlines and shutdown.
+const TICK: Duration = Duration::from_millis(100);
+
+const ANALYZE: &str = "/td5.v1.Analyzer/Analyze";
+const ANALYZE_STREAM: &str = "/td5.v1.Analyzer/AnalyzeStream";
+
+/// What a `Server` lets its clients use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Largest request message, in bytes; larger ones fail with
+    /// `RESOURCE_EXHAUSTED` before they are read.
+    pub max_message: usize,
+    /// Connections served at once; more are sent a GOAWAY and closed.
+    pub max_connections: usize,
+    /// Calls open at once on a connection; more are refused.
+    pub max_streams: usize,
+    /// Longest a call may take, from its headers to its answer; a shorter
+    /// `grpc-timeout` from the client wins. Later ones fail with
+    /// `DEADLINE_EXCEEDED`. Also how long a write may block.
+    pub timeout: Duration,
+    /// Longest a connection is kept with no call open.
+    pub idle_timeout: Duration,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            // gRPC's own default.
+            max_message: 4 << 20,
+            max_connections: 256,
+            max_streams: 100,
+            timeout: Duration::from_secs(30),
+            idle_timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Serves the `Analyzer` service on a TCP listener.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use td5::grpc::{Limits, Server};
+///
+/// let server = Server::bind("127.0.0.1:50051", Default::default())
+///     .unwrap()
+///     .limits(Limits {
+///         max_message: 1 << 20,
+///         timeout: Duration::from_secs(5),
+///         ..Limits::default()
+///     });
+/// let shutdown = server.shutdown_handle().unwrap();
+/// std::thread::spawn(move || {
+///     std::thread::sleep(Duration::from_secs(3600));
+///     shutdown.shutdown();
+/// });
+/// server.run().unwrap();
+/// ```
+pub struct Server {
+    listener: TcpListener,
+    config: Arc<AnalyzerConfig>,
+    limits: Limits,
+    stop: Arc<AtomicBool>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Server {
+    /// Listens on `address`; calls are analyzed with `config`.
+    pub fn bind(address: impl ToSocketAddrs, config: AnalyzerConfig) -> Result<Server> {
+        let listener = TcpListener::bind(address)?;
+        Ok(Server {
+            listener,
+            config: Arc::new(config),
+            limits: Limits::default(),
+            stop: Arc::new(AtomicBool::new(false)),
+            clock: clock::system(),
+        })
+    }
+
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Reads call deadlines and idle time from `clock` instead of the
+    /// system's. Sockets still wait in real time between checks.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// The address listened on, with the port picked for port 0.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// A handle stopping `run` from another thread.
+    pub fn shutdown_handle(&self) -> Result<Shutdown> {
+        let mut address = self.listener.local_addr()?;
+        if address.ip().is_unspecified() {
+            address.set_ip(match address {
+                SocketAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+                SocketAddr::V6(_) => Ipv6Addr::LOCALHOST.into(),
+            });
+        }
+        Ok(Shutdown {
+            stop: Arc::clone(&self.stop),
+            address,
+        })
+    }
+
+    /// Accepts connections, each served on its own thread, until accepting
+    /// fails or a `Shutdown`; then waits for the open connections to finish
+    /// their calls. A connection that breaks the protocol is sent a GOAWAY
+    /// and closed; the others go on.
+    pub fn run(&self) -> Result<()> {
+        let mut connections: Vec<JoinHandle<()>> = Vec::new();
+        for socket in self.listener.incoming() {
+            if self.stop.load(Ordering::SeqCst) {
+                break;
+            }
+            let socket = socket?;
+            connections.retain(|connection| !connection.is_finished());
+            if connections.len() >= self.limits.max_connections {
+                let _ = refuse(socket);
+                continue;
+            }
+            let config = Arc::clone(&self.config);
+            let stop = Arc::clone(&self.stop);
+            let limits = self.limits;
+            let clock = Arc::clone(&self.clock);
+            // A client's broken connection is its own business.
+            connections.push(std::thread::spawn(move || {
+                let _ = serve(socket, config, limits, stop, clock);
+            }));
+        }
+        for connection in connections {
+            let _ = connection.join();
+        }
+        Ok(())
+    }
+}
+
+/// Stops a running `Server`: no new connections, and a GOAWAY on the open
+/// ones, which close once their calls are answered.
+#[derive(Debug, Clone)]
+pub struct Shutdown {
+    stop: Arc<AtomicBool>,
+    address: SocketAddr,
+}
+
+impl Shutdown {
+    pub fn shutdown(&self) {
+        self.stop.store(true, Ordering::SeqCst);
+        // Wakes up the thread blocked accepting connections.
+        let _ = TcpStream::connect(self.address);
+    }
+}
+
+fn write_frame(
+    out: &mut impl Write,
+    kind: u8,
+    flags: u8,
+    id: u32,
+    payload: &[u8],
+) -> io::Result<()> {
+    let len = payload.len() as u32;
+    out.write_all(&len.to_be_bytes()[1..])?;
+    out.write_all(&[kind, flags])?;
+    out.write_all(&id.to_be_bytes())?;
+    out.write_all(payload)
+}
+
+fn goaway(last_stream: u32, code: u32, reason: &str) -> Vec<u8> {
+    let mut payload = last_stream.to_be_bytes().to_vec();
+    payload.extend_from_slice(&code.to_be_bytes());
+    payload.extend_from_slice(reason.as_bytes());
+    payload
+}
+
+/// Turns away a connection over `Limits::max_connections`.
+fn refuse(mut socket: TcpStream) -> io::Result<()> {
+    socket.set_write_timeout(Some(TICK))?;
+    let mut out = Vec::new();
+    write_frame(&mut out, SETTINGS, 0, 0, &[])?;
+    let payload = goaway(0, ENHANCE_YOUR_CALM, "too many connections");
+    write_frame(&mut out, GOAWAY, 0, 0, &payload)?;
+    socket.write_all(&out)
+}
+
+/// Why a connection ends early: a failed socket, or an HTTP/2 connection
+/// error to report in a GOAWAY.
+enum Failure {
+    Io(io::Error),
+    Protocol(u32, &'static str),
+}
+
+impl From<io::Error> for Failure {
+    fn from(e: io::Error) -> Self {
+        Failure::Io(e)
+    }
+}
+
+fn serve(
+    socket: TcpStream,
+    config: Arc<AnalyzerConfig>,
+    limits: Limits,
+    stop: Arc<AtomicBool>,
+    clock: Arc<dyn Clock>,
+) -> io::Result<()> {
+    socket.set_read_timeout(Some(TICK))?;
+    socket.set_write_timeout(Some(limits.timeout))?;
+    let writer = socket.try_clone()?;
+    let now = clock.now();
+    let mut connection = Connection {
+        socket,
+        input: Vec::new(),
+        writer: BufWriter::new(writer),
+        config,
+        limits,
+        stop,
+        clock,
+        going_away: false,
+        idle_since: now,
+        last_tick: now,
+        decoder: Decoder::new(),
+        streams: HashMap::new(),
+        last_stream: 0,
+        partial: None,
+        send_window: DEFAULT_WINDOW,
+        initial_window: DEFAULT_WINDOW,
+        max_frame: MAX_FRAME,
+    };
+    match connection.run() {
+        Ok(()) => Ok(()),
+        Err(Failure::Io(e)) => Err(e),
+        Err(Failure::Protocol(code, reason)) => {
+            let payload = goaway(connection.last_stream, code, reason);
+            connection.frame(GOAWAY, 0, 0, &payload)?;
+            connection.writer.flush()
+        }
+    }
+}
+
+struct Connection {
+    /// Read with a `TICK` timeout, into `input`.
+    socket: TcpStream,
+    input: Vec<u8>,
+    writer: BufWriter<TcpStream>,
+    config: Arc<AnalyzerConfig>,
+    limits: Limits,
+    stop: Arc<AtomicBool>,
+    clock: Arc<dyn Clock>,
+    /// A shutdown GOAWAY was sent: no new calls.
+    going_away: bool,
+    /// When the last call closed, or the connection opened, by `clock`.
+    idle_since: Duration,
+    last_tick: Duration,
+    decoder: Decoder,
+    streams: HashMap<u32, Stream>,
+    /// The highest stream the client opened.
+    last_stream: u32,
+    /// A header block continued in CONTINUATION frames: its stream, the
+    /// fragments so far, and whether it ends the stream.
+    partial: Option<(u32, Vec<u8>, bool)>,
+    /// What the client lets us send on the connection.
+    send_window: i64,
+    /// What it lets us send on a new stream.
+    initial_window: i64,
+    max_frame: usize,
+}
+
+struct Stream {
+    call: Call,
+    /// Received bytes short of a whole gRPC message.
+    buffer: Vec<u8>,
+    /// The client ended its side.
+    ended: bool,
+    /// By the connection's `clock`.
+    deadline: Duration,
+    send_window: i64,
+    /// Response data waiting for flow-control window, then the trailers.
+    pending: Vec<u8>,
+    trailers: Option<Vec<u8>>,
+}
+
+enum Call {
+    /// `Analyze`, with its request once received.
+    Analyze(Option<Vec<u8>>),
+    AnalyzeStream(Box<StreamingAnalyzer>),
+    /// Answered; whatever else arrives is dropped.
+    Answered,
+}
+
+/// A frame's type, flags, stream and payload.
+type Frame = (u8, u8, u32, Vec<u8>);
+
+/// A gRPC error status and its message.
+type Status = (u32, String);
+
+/// What a read of the socket got.
+enum Input {
+    Data,
+    /// Nothing within a `TICK`.
+    Timeout,
+    Closed,
+}
+
+impl Connection {
+    fn run(&mut self) -> Result<(), Failure> {
+        let mut settings = Vec::new();
+        for (id, value) in [
+            (MAX_CONCURRENT_STREAMS, self.limits.max_streams as u32),
+            (INITIAL_WINDOW_SIZE, WINDOW),
+        ] {
+            settings.extend_from_slice(&id.to_be_bytes());
+            settings.extend_from_slice(&value.to_be_bytes());
+        }
+        self.frame(SETTINGS, 0, 0, &settings)?;
+        self.window_update(0, WINDOW - DEFAULT_WINDOW as u32)?;
+        self.writer.flush()?;
+        if !self.wait_for(PREFACE.len())? {
+            return Ok(());
+        }
+        if !self.input.starts_with(PREFACE) {
+            return Err(Failure::Protocol(PROTOCOL_ERROR, "not an HTTP/2 preface"));
+        }
+        self.input.drain(..PREFACE.len());
+        while let Some((kind, flags, id, payload)) = self.read_frame()? {
+            if let Some((partial_id, ..)) = self.partial
+                && (kind != CONTINUATION || id != partial_id)
+            {
+                return Err(Failure::Protocol(PROTOCOL_ERROR, "expected CONTINUATION"));
+            }
+            match kind {
+                DATA => self.data(flags, id, &payload)?,
+                HEADERS => self.headers(flags, id, &payload)?,
+                CONTINUATION => {
+                    let Some((_, block, _)) = &mut self.partial else {
+                        return Err(Failure::Protocol(PROTOCOL_ERROR, "unexpected CONTINUATION"));
+                    };
+                    if block.len() + payload.len() > MAX_HEADER_BLOCK {
+                        return Err(Failure::Protocol(PROTOCOL_ERROR, "header block too large"));
+                    }
+                    block.extend_from_slice(&payload);
+                    if flags & END_HEADERS != 0 {
+                        let (id, block, end) = self.partial.take().expect("checked above");
+                        self.header_block(id, &block, end)?;
+                    }
+                }
+                RST_STREAM => self.forget(id),
+                SETTINGS if flags & ACK == 0 => self.settings(id, &payload)?,
+                PING if flags & ACK == 0 => {
+                    if payload.len() != 8 {
+                        return Err(Failure::Protocol(
+                            FRAME_SIZE_ERROR,
+                            "PING of the wrong size",
+                        ));
+                    }
+                    self.frame(PING, ACK, 0, &payload)?;
+                }
+                WINDOW_UPDATE => self.window(id, &payload)?,
+                PUSH_PROMISE => {
+                    return Err(Failure::Protocol(
+                        PROTOCOL_ERROR,
+                        "PUSH_PROMISE from a client",
+                    ));
+                }
+                GOAWAY => return Ok(()),
+                // PRIORITY, acknowledgements and unknown frames.
+                _ => {}
+            }
+            self.writer.flush()?;
+        }
+        Ok(())
+    }
+
+    /// The next frame, `None` once the connection is to close: the client
+    /// closed it, or it has been idle too long, or the server is shutting
+    /// down and no call is left.
+    fn read_frame(&mut self) -> Result<Option<Frame>, Failure> {
+        if !self.wait_for(9)? {
+            return Ok(None);
+        }
+        let len = u32::from_be_bytes([0, self.input[0], self.input[1], self.input[2]]) as usize;
+        if len > MAX_FRAME {
+            return Err(Failure::Protocol(FRAME_SIZE_ERROR, "frame too large"));
+        }
+        if !self.wait_for(9 + len)? {
+            return Ok(None);
+        }
+        let header: Vec<u8> = self.input.drain(..9).collect();
+        let payload = self.input.drain(..len).collect();
+        let id = u32::from_be_bytes([header[5], header[6], header[7], header[8]]) & 0x7fff_ffff;
+        Ok(Some((header[3], header[4], id, payload)))
+    }
+
+    /// Reads until `input` holds `len` bytes, checking deadlines every
+    /// `TICK`. `false` if the connection is to close first.
+    fn wait_for(&mut self, len: usize) -> Result<bool, Failure> {
+        while self.input.len() < len {
+            let input = self.read()?;
+            if matches!(input, Input::Closed) {
+                return Ok(false);
+            }
+            if self.clock.now() - self.last_tick >= TICK && !self.tick()? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn read(&mut self) -> io::Result<Input> {
+        let mut chunk = [0; 32 << 10];
+        match self.socket.read(&mut chunk) {
+            Ok(0) => Ok(Input::Closed),
+            Ok(n) => {
+                self.input.extend_from_slice(&chunk[..n]);
+                Ok(Input::Data)
+            }
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock
+                        | io::ErrorKind::TimedOut
+                        | io::ErrorKind::Interrupted
+                ) =>
+            {
+                Ok(Input::Timeout)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Fails the calls past their deadline and sends the shutdown GOAWAY.
+    /// `false` once the connection is to close: idle too long, or shutting
+    /// down with no call left.
+    fn tick(&mut self) -> Result<bool, Failure> {
+        let now = self.clock.now();
+        self.last_tick = now;
+        let mut late: Vec<u32> = self
+            .streams
+            .iter()
+            .filter(|(_, stream)| stream.deadline <= now)
+            .map(|(&id, _)| id)
+            .collect();
+        late.sort_unstable();
+        for id in late {
+            let answered = self
+                .streams
+                .get(&id)
+                .is_some_and(|stream| matches!(stream.call, Call::Answered));
+            if answered {
+                // Its answer is stuck behind flow control.
+                self.frame(RST_STREAM, 0, id, &CANCEL.to_be_bytes())?;
+                self.forget(id);
+            } else {
+                self.fail(id, (DEADLINE_EXCEEDED, "deadline exceeded".into()))?;
+            }
+        }
+        if self.stop.load(Ordering::SeqCst) && !self.going_away {
+            self.going_away = true;
+            let payload = goaway(self.last_stream, NO_ERROR, "shutting down");
+            self.frame(GOAWAY, 0, 0, &payload)?;
+        }
+        let idle = self.streams.is_empty()
+            && (self.going_away || now - self.idle_since >= self.limits.idle_timeout);
+        if idle && !self.going_away {
+            let payload = goaway(self.last_stream, NO_ERROR, "idle");
+            self.frame(GOAWAY, 0, 0, &payload)?;
+        }
+        self.writer.flush()?;
+        Ok(!idle)
+    }
+
+    fn frame(&mut self, kind: u8, flags: u8, id: u32, payload: &[u8]) -> io::Result<()> {
+        write_frame(&mut self.writer, kind, flags, id, payload)
+    }
+
+    fn window_update(&mut self, id: u32, increment: u32) -> io::Result<()> {
+        self.frame(WINDOW_UPDATE, 0, id, &increment.to_be_bytes())
+    }
+
+    fn data(&mut self, flags: u8, id: u32, payload: &[u8]) -> Result<(), Failure> {
+        if id == 0 {
+            return Err(Failure::Protocol(PROTOCOL_ERROR, "DATA on stream 0"));
+        }
+        let data = unpad(flags, payload)?;
+        // Everything received is used or dropped at once, so the window is
+        // given back straight away.
+        if !payload.is_empty() {
+            self.window_update(0, payload.len() as u32)?;
+            if flags & END_STREAM == 0 && self.streams.contains_key(&id) {
+                self.window_update(id, payload.len() as u32)?;
+            }
+        }
+        self.receive(id, data, flags & END_STREAM != 0)?;
+        Ok(())
+    }
+
+    fn headers(&mut self, flags: u8, id: u32, payload: &[u8]) -> Result<(), Failure> {
+        if id == 0 {
+            return Err(Failure::Protocol(PROTOCOL_ERROR, "HEADERS on stream 0"));
+        }
+        let mut fragment = unpad(flags, payload)?;
+        if flags & PRIORITY != 0 {
+            fragment = fragment
+                .get(5..)
+                .ok_or(Failure::Protocol(FRAME_SIZE_ERROR, "HEADERS too short"))?;
+        }
+        let end = flags & END_STREAM != 0;
+        match flags & END_HEADERS {
+            0 => self.partial = Some((id, fragment.to_vec(), end)),
+            _ => self.header_block(id, fragment, end)?,
+        }
+        Ok(())
+    }
+
+    /// A complete header block: a new call, or trailers ending one.
+    fn header_block(&mut self, id: u32, block: &[u8], end: bool) -> Result<(), Failure> {
+        // Decoded in any case, to keep the dynamic table in step.
+        let fields = self
+            .decoder
+            .decode(block)
+            .map_err(|reason| Failure::Protocol(COMPRESSION_ERROR, reason))?;
+        if id <= self.last_stream {
+            if end {
+                self.receive(id, &[], true)?;
+            }
+            return Ok(());
+        }
+        if id.is_multiple_of(2) {
+            return Err(Failure::Protocol(
+                PROTOCOL_ERROR,
+                "even stream from a client",
+            ));
+        }
+        // After a GOAWAY, calls past its last stream are ignored.
+        if self.going_away {
+            self.frame(RST_STREAM, 0, id, &REFUSED_STREAM.to_be_bytes())?;
+            return Ok(());
+        }
+        self.last_stream = id;
+        if self.streams.len() >= self.limits.max_streams {
+            self.frame(RST_STREAM, 0, id, &REFUSED_STREAM.to_be_bytes())?;
+            return Ok(());
+        }
+        let field = |name: &str| {
+            fields
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, value)| value.as_str())
+        };
+        let grpc = field(":method") == Some("POST")
+            && field("content-type").is_some_and(|t| t.starts_with("application/grpc"));
+        let call = match field(":path") {
+            _ if !grpc => None,
+            Some(ANALYZE) => Some(Call::Analyze(None)),
+            Some(ANALYZE_STREAM) => Some(Call::AnalyzeStream(Box::new(StreamingAnalyzer::new(
+                (*self.config).clone(),
+            )))),
+            _ => None,
+        };
+        let unknown = call.is_none();
+        let timeout = match field("grpc-timeout").and_then(grpc_timeout) {
+            Some(timeout) => timeout.min(self.limits.timeout),
+            None => self.limits.timeout,
+        };
+        self.idle_since = self.clock.now();
+        self.streams.insert(
+            id,
+            Stream {
+                call: call.unwrap_or(Call::Answered),
+                buffer: Vec::new(),
+                ended: end,
+                deadline: self.idle_since + timeout,
+                send_window: self.initial_window,
+                pending: Vec::new(),
+                trailers: None,
+            },
+        );
+        if !grpc {
+            let block = hpack::encode(&[(":status", "415")]);
+            self.frame(HEADERS, END_HEADERS | END_STREAM, id, &block)?;
+            self.close(id)?;
+        } else if unknown {
+            let path = field(":path").unwrap_or("").to_string();
+            self.fail(id, (UNIMPLEMENTED, format!("unknown method {path}")))?;
+        } else if end {
+            self.receive(id, &[], true)?;
+        }
+        Ok(())
+    }
+
+    /// Request data of stream `id`, and whether the client ended the stream.
+    fn receive(&mut self, id: u32, data: &[u8], end: bool) -> io::Result<()> {
+        let Some(stream) = self.streams.get_mut(&id) else {
+            return Ok(());
+        };
+        stream.ended |= end;
+        if matches!(stream.call, Call::Answered) {
+            if end {
+                self.close(id)?;
+            }
+            return Ok(());
+        }
+        stream.buffer.extend_from_slice(data);
+        let outcome = match messages(stream, self.limits.max_message) {
+            Ok(()) if !end => return Ok(()),
+            Ok(()) if !stream.buffer.is_empty() => {
+                Err((INVALID_ARGUMENT, "truncated message".to_string()))
+            }
+            Ok(()) => match std::mem::replace(&mut stream.call, Call::Answered) {
+                Call::Analyze(Some(request)) => analyze(&request, &self.config),
+                Call::AnalyzeStream(analyzer) => {
+                    let partial = analyzer.finish();
+                    Ok((partial.stats, partial.time_ns))
+                }
+                _ => Err((INVALID_ARGUMENT, "Analyze takes one request".to_string())),
+            },
+            Err(status) => Err(status),
+        };
+        match outcome {
+            Ok((stats, time_ns)) => self.reply(id, &stats, time_ns),
+            Err(status) => self.fail(id, status),
+        }
+    }
+
+    /// Answers with `stats`, as much as the flow-control windows allow.
+    fn reply(&mut self, id: u32, stats: &TextStats, time_ns: u128) -> io::Result<()> {
+        let block = hpack::encode(&[(":status", "200"), ("content-type", "application/grpc")]);
+        self.frame(HEADERS, END_HEADERS, id, &block)?;
+        let Some(stream) = self.streams.get_mut(&id) else {
+            return Ok(());
+        };
+        stream.call = Call::Answered;
+        let message = stats.to_protobuf(time_ns);
+        stream.pending = vec![0];
+        stream
+            .pending
+            .extend_from_slice(&(message.len() as u32).to_be_bytes());
+        stream.pending.extend(message);
+        stream.trailers = Some(hpack::encode(&[("grpc-status", "0")]));
+        self.send_pending(id)
+    }
+
+    /// Answers with an error status, in a single header block.
+    fn fail(&mut self, id: u32, (code, message): Status) -> io::Result<()> {
+        let code = code.to_string();
+        let message = percent_encode(&message);
+        let block = hpack::encode(&[
+            (":status", "200"),
+            ("content-type", "application/grpc"),
+            ("grpc-status", &code),
+            ("grpc-message", &message),
+        ]);
+        self.frame(HEADERS, END_HEADERS | END_STREAM, id, &block)?;
+        self.close(id)
+    }
+
+    /// Sends what the windows allow of the pending response of `id`, and the
+    /// trailers once it is all out.
+    fn send_pending(&mut self, id: u32) -> io::Result<()> {
+        let Some(stream) = self.streams.get_mut(&id) else {
+            return Ok(());
+        };
+        let mut sent = 0;
+        let mut frames = Vec::new();
+        while sent < stream.pending.len() {
+            let room = self
+                .send_window
+                .min(stream.send_window)
+                .min(self.max_frame as i64);
+            if room <= 0 {
+                break;
+            }
+            let len = (room as usize).min(stream.pending.len() - sent);
+            frames.push(stream.pending[sent..sent + len].to_vec());
+            sent += len;
+            self.send_window -= len as i64;
+            stream.send_window -= len as i64;
+        }
+        stream.pending.drain(..sent);
+        let trailers = match stream.pending.is_empty() {
+            true => stream.trailers.take(),
+            false => None,
+        };
+        for data in frames {
+            self.frame(DATA, 0, id, &data)?;
+        }
+        if let Some(trailers) = trailers {
+            self.frame(HEADERS, END_HEADERS | END_STREAM, id, &trailers)?;
+            self.close(id)?;
+        }
+        Ok(())
+    }
+
+    /// Forgets a stream once answered, resetting it if the client is still
+    /// sending so it stops.
+    fn close(&mut self, id: u32) -> io::Result<()> {
+        let Some(stream) = self.streams.get(&id) else {
+            return Ok(());
+        };
+        if stream.trailers.is_some() || !stream.pending.is_empty() {
+            return Ok(());
+        }
+        if !stream.ended {
+            self.frame(RST_STREAM, 0, id, &NO_ERROR.to_be_bytes())?;
+        }
+        self.forget(id);
+        Ok(())
+    }
+
+    fn forget(&mut self, id: u32) {
+        if self.streams.remove(&id).is_some() {
+            self.idle_since = self.clock.now();
+        }
+    }
+
+    fn settings(&mut self, id: u32, payload: &[u8]) -> Result<(), Failure> {
+        if id != 0 {
+            return Err(Failure::Protocol(PROTOCOL_ERROR, "SETTINGS on a stream"));
+        }
+        if !payload.len().is_multiple_of(6) {
+            return Err(Failure::Protocol(
+                FRAME_SIZE_ERROR,
+                "SETTINGS of the wrong size",
+            ));
+        }
+        for setting in payload.chunks(6) {
+            let value = u32::from_be_bytes([setting[2], setting[3], setting[4], setting[5]]);
+            match u16::from_be_bytes([setting[0], setting[1]]) {
+                INITIAL_WINDOW_SIZE => {
+                    if value > 0x7fff_ffff {
+                        return Err(Failure::Protocol(FLOW_CONTROL_ERROR, "window too large"));
+                    }
+                    let delta = i64::from(value) - self.initial_window;
+                    self.initial_window = i64::from(value);
+                    for stream in self.streams.values_mut() {
+                        stream.send_window += delta;
+                    }
+                }
+                MAX_FRAME_SIZE => {
+                    if !(MAX_FRAME as u32..=0xff_ffff).contains(&value) {
+                        return Err(Failure::Protocol(PROTOCOL_ERROR, "invalid frame size"));
+                    }
+                    self.max_frame = value as usize;
+                }
+                _ => {}
+            }
+        }
+        self.frame(SETTINGS, ACK, 0, &[])?;
+        self.send_all_pending()?;
+        Ok(())
+    }
+
+    fn window(&mut self, id: u32, payload: &[u8]) -> Result<(), Failure> {
+        let bytes: [u8; 4] = payload
+            .try_into()
+            .map_err(|_| Failure::Protocol(FRAME_SIZE_ERROR, "WINDOW_UPDATE of the wrong size"))?;
+        let increment = i64::from(u32::from_be_bytes(bytes) & 0x7fff_ffff);
+        let window = match id {
+            0 => &mut self.send_window,
+            _ => match self.streams.get_mut(&id) {
+                Some(stream) => &mut stream.send_window,
+                None => return Ok(()),
+            },
+        };
+        *window += increment;
+        if increment == 0 || *window > 0x7fff_ffff {
+            return Err(Failure::Protocol(
+                FLOW_CONTROL_ERROR,
+                "invalid window update",
+            ));
+        }
+        self.send_all_pending()?;
+        Ok(())
+    }
+
+    fn send_all_pending(&mut self) -> io::Result<()> {
+        let mut waiting: Vec<u32> = self
+            .streams
+            .iter()
+            .filter(|(_, s)| s.trailers.is_some())
+            .map(|(&id, _)| id)
+            .collect();
+        waiting.sort_unstable();
+        for id in waiting {
+            self.send_pending(id)?;
+        }
+        Ok(())
+    }
+}
+
+/// Takes the whole gRPC messages out of the stream's buffer: the request of
+/// `Analyze`, the chunks of `AnalyzeStream`, fed to its analyzer. A message
+/// over `max` bytes is an error as soon as its length is known.
+fn messages(stream: &mut Stream, max: usize) -> Result<(), Status> {
+    while stream.buffer.len() >= 5 {
+        let len = u32::from_be_bytes([
+            stream.buffer[1],
+            stream.buffer[2],
+            stream.buffer[3],
+            stream.buffer[4],
+        ]) as usize;
+        if len > max {
+            return Err((
+                RESOURCE_EXHAUSTED,
+                format!("message of {len} bytes over the {max}-byte limit"),
+            ));
+        }
+        if stream.buffer.len() - 5 < len {
+            break;
+        }
+        if stream.buffer[0] != 0 {
+            return Err((
+                UNIMPLEMENTED,
+                "compressed messages are not supported".into(),
+            ));
+        }
+        let message: Vec<u8> = stream.buffer.drain(..5 + len).skip(5).collect();
+        match &mut stream.call {
+            Call::Analyze(Some(_)) => {
+                return Err((INVALID_ARGUMENT, "Analyze takes one request".into()));
+            }
+            Call::Analyze(request) => *request = Some(message),
+            Call::AnalyzeStream(analyzer) => {
+                let mut text = "";
+                for (field, data) in fields(&message)? {
+                    if field == 1 {
+                        text = data;
+                    }
+                }
+                analyzer.push(text);
+            }
+            Call::Answered => {}
+        }
+    }
+    Ok(())
+}
+
+/// The string fields of a request.
+fn fields(message: &[u8]) -> Result<Vec<(u32, &str)>, Status> {
+    let invalid = |reason: &str| (INVALID_ARGUMENT, reason.to_string());
+    proto::len_fields(message)
+        .map_err(invalid)?
+        .into_iter()
+        .map(|(field, data)| {
+            let text = std::str::from_utf8(data).map_err(|_| invalid("string not valid UTF-8"))?;
+            Ok((field, text))
+        })
+        .collect()
+}
+
+/// The stats of an `Analyze` request and how long the analysis took.
+fn analyze(request: &[u8], config: &AnalyzerConfig) -> Result<(TextStats, u128), Status> {
+    let (mut text, mut name) = ("", "fast");
+    for (field, data) in fields(request)? {
+        match field {
+            1 => text = data,
+            2 if !data.is_empty() => name = data,
+            _ => {}
+        }
+    }
+    let registered = analyzer::configured(name, 0, config.clone())
+        .ok_or_else(|| (INVALID_ARGUMENT, format!("unknown analyzer `{name}`")))?;
+    let start = Stopwatch::start();
+    let stats = registered.analyzer.analyze(text);
+    Ok((stats, start.elapsed_ns()))
+}
+
+/// A `grpc-timeout` header: up to 8 digits and a unit, `H`, `M`, `S`, `m`,
+/// `u` or `n`.
+fn grpc_timeout(value: &str) -> Option<Duration> {
+    if !value.is_ascii() {
+        return None;
+    }
+    let (digits, unit) = value.split_at(value.len().checked_sub(1)?);
+    if digits.is_empty() || digits.len() > 8 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let n: u64 = digits.parse().ok()?;
+    Some(match unit {
+        "H" => Duration::from_secs(n * 3600),
+        "M" => Duration::from_secs(n * 60),
+        "S" => Duration::from_secs(n),
+        "m" => Duration::from_millis(n),
+        "u" => Duration::from_micros(n),
+        "n" => Duration::from_nanos(n),
+        _ => return None,
+    })
+}
+
+/// A frame's payload without its padding.
+fn unpad(flags: u8, payload: &[u8]) -> Result<&[u8], Failure> {
+    if flags & PADDED == 0 {
+        return Ok(payload);
+    }
+    let (&pad, rest) = payload.split_first().ok_or(Failure::Protocol(
+        FRAME_SIZE_ERROR,
+        "padded frame too short",
+    ))?;
+    rest.len()
+        .checked_sub(usize::from(pad))
+        .map(|len| &rest[..len])
+        .ok_or(Failure::Protocol(
+            PROTOCOL_ERROR,
+            "padding longer than the frame",
+        ))
+}
+
+/// `grpc-message` is percent-encoded UTF-8.
+fn percent_encode(message: &str) -> String {
+    let mut out = String::with_capacity(message.len());
+    for &byte in message.as_bytes() {
+        match byte {
+            b' '..=b'~' if byte != b'%' => out.push(byte as char),
+            _ => out.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    out
+}
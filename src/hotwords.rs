@@ -0,0 +1,189 @@
+//! The fast path's hot vocabulary: learning it from a corpus (`td5
+//! learn-hot`) and choosing one at run time (`HotWords`).
+//!
+//! A hot word is counted in a fixed array instead of being hashed, so the best
+//! list of `n` words is the one covering the most tokens: the `n` most
+//! frequent words the generated matcher accepts (lowercase ASCII, at most 32
+//! letters). The list is written in the `hot-words.txt` format read by
+//! `build.rs`, which derives the ordering constants from it, or by
+//! `HotWords::parse`, which builds the same tables without a rebuild.
+
+use crate::WordFrequency;
+use crate::error::{Result, Td5Error};
+use std::fmt::Write as _;
+
+/// Longest word `build.rs` accepts.
+pub const HOT_WORD_MAX: usize = 32;
+/// Largest vocabulary `build.rs` accepts.
+pub const HOT_WORDS_MAX: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HotVocabulary {
+    /// Most frequent first.
+    pub words: Vec<(String, usize)>,
+    /// Tokens in the corpus.
+    pub total: usize,
+}
+
+impl HotVocabulary {
+    /// The `top` (at most `HOT_WORDS_MAX`) most frequent eligible words of
+    /// `freq`, ties broken alphabetically.
+    pub fn learn(freq: &WordFrequency, top: usize) -> HotVocabulary {
+        let words = freq
+            .by_count()
+            .filter(|(w, _)| w.len() <= HOT_WORD_MAX && w.bytes().all(|b| b.is_ascii_lowercase()))
+            .take(top.min(HOT_WORDS_MAX));
+        HotVocabulary {
+            words: words.map(|(w, c)| (w.to_string(), c)).collect(),
+            total: freq.total(),
+        }
+    }
+
+    /// Tokens that would be counted without hashing.
+    pub fn covered(&self) -> usize {
+        self.words.iter().map(|(_, c)| c).sum()
+    }
+
+    /// Share of the corpus' tokens covered, in percent.
+    pub fn coverage(&self) -> f64 {
+        100.0 * self.covered() as f64 / self.total.max(1) as f64
+    }
+
+    /// The list with a comment header stating the coverage, one word per
+    /// line with its count as a comment.
+    pub fn to_file(&self) -> String {
+        let mut out = format!(
+            "# Learned by `td5 learn-hot`: {} words covering {:.1}% of {} tokens.\n\
+             # Build with TD5_HOT_WORDS=<this file> cargo build --release\n",
+            self.words.len(),
+            self.coverage(),
+            self.total
+        );
+        let width = self.words.iter().map(|(w, _)| w.len()).max().unwrap_or(0);
+        for (word, count) in &self.words {
+            let _ = writeln!(out, "{word:<width$} # {count}");
+        }
+        out
+    }
+}
+
+/// Letters a hot word can start with, one dispatch bucket per length each.
+const FIRST_LETTERS: usize = 26;
+
+/// A hot vocabulary for `AnalyzerConfig::with_hot_words`, replacing the one
+/// compiled in from `hot-words.txt`. Matching dispatches on word length and
+/// first letter, like the generated matcher, then compares the few words
+/// sharing both, so counting stays free of hashing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HotWords {
+    /// In counter order.
+    words: Vec<String>,
+    /// Every word, alphabetically.
+    alphabetical: Vec<usize>,
+    /// Every word, longest first, alphabetically among equal lengths.
+    longest: Vec<usize>,
+    /// Range of `candidates` per `(length - 1, first letter)`.
+    buckets: Vec<(usize, usize)>,
+    candidates: Vec<usize>,
+}
+
+impl HotWords {
+    /// The vocabulary of `words`: at most `HOT_WORDS_MAX` distinct lowercase
+    /// ASCII words of at most `HOT_WORD_MAX` letters, the rules `build.rs`
+    /// applies to `hot-words.txt`.
+    ///
+    /// ```
+    /// let hot = td5::hotwords::HotWords::new(&["error", "warn", "info"])?;
+    /// assert_eq!(hot.words(), ["error", "warn", "info"]);
+    /// assert!(td5::hotwords::HotWords::new(&["Error"]).is_err());
+    /// # Ok::<(), td5::Td5Error>(())
+    /// ```
+    pub fn new(words: &[&str]) -> Result<HotWords> {
+        if words.len() > HOT_WORDS_MAX {
+            return Err(Td5Error::config(format!(
+                "more than {HOT_WORDS_MAX} hot words ({})",
+                words.len()
+            )));
+        }
+        for (i, word) in words.iter().enumerate() {
+            if word.is_empty()
+                || word.len() > HOT_WORD_MAX
+                || !word.bytes().all(|b| b.is_ascii_lowercase())
+            {
+                return Err(Td5Error::config(format!(
+                    "hot word `{word}` is not a lowercase ASCII word of at most {HOT_WORD_MAX} letters"
+                )));
+            }
+            if words[..i].contains(word) {
+                return Err(Td5Error::config(format!(
+                    "hot word `{word}` is listed twice"
+                )));
+            }
+        }
+        let words: Vec<String> = words.iter().map(|w| w.to_string()).collect();
+
+        let mut alphabetical: Vec<usize> = (0..words.len()).collect();
+        alphabetical.sort_by_key(|&i| &words[i]);
+        let mut longest = alphabetical.clone();
+        longest.sort_by_key(|&i| std::cmp::Reverse(words[i].len()));
+
+        let bucket =
+            |word: &str| (word.len() - 1) * FIRST_LETTERS + usize::from(word.as_bytes()[0] - b'a');
+        let mut candidates: Vec<usize> = (0..words.len()).collect();
+        candidates.sort_by_key(|&i| bucket(&words[i]));
+        let mut buckets = vec![(0, 0); HOT_WORD_MAX * FIRST_LETTERS];
+        for (at, &i) in candidates.iter().enumerate() {
+            let range = &mut buckets[bucket(&words[i])];
+            if range.0 == range.1 {
+                range.0 = at;
+            }
+            range.1 = at + 1;
+        }
+        Ok(HotWords {
+            words,
+            alphabetical,
+            longest,
+            buckets,
+            candidates,
+        })
+    }
+
+    /// The words of a list in the `hot-words.txt` format: one word per line,
+    /// `#` starting a comment, as `td5 learn-hot` writes it.
+    pub fn parse(text: &str) -> Result<HotWords> {
+        let words: Vec<&str> = text
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or("").trim())
+            .filter(|word| !word.is_empty())
+            .collect();
+        HotWords::new(&words)
+    }
+
+    /// In counter order, as given.
+    pub fn words(&self) -> &[String] {
+        &self.words
+    }
+
+    pub(crate) fn alphabetical(&self) -> &[usize] {
+        &self.alphabetical
+    }
+
+    pub(crate) fn longest(&self) -> &[usize] {
+        &self.longest
+    }
+
+    /// Index of `word` (lowercase ASCII bytes) in the vocabulary.
+    #[inline]
+    pub(crate) fn find(&self, word: &[u8]) -> Option<usize> {
+        let &first = word.first()?;
+        if word.len() > HOT_WORD_MAX || !first.is_ascii_lowercase() {
+            return None;
+        }
+        let (start, end) =
+            self.buckets[(word.len() - 1) * FIRST_LETTERS + usize::from(first - b'a')];
+        self.candidates[start..end]
+            .iter()
+            .copied()
+            .find(|&i| self.words[i].as_bytes() == word)
+    }
+}
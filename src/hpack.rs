@@ -0,0 +1,306 @@
+//! HPACK (RFC 7541), the header compression of HTTP/2, for `grpc`.
+//!
+//! The decoder handles everything a client may send: the static and dynamic
+//! tables, table size updates and Huffman-coded strings. The encoder only
+//! writes literals that are neither indexed nor Huffman-coded, which every
+//! decoder accepts and which is plenty for a few response headers.
+
+use std::collections::VecDeque;
+use std::sync::OnceLock;
+
+/// The static table of RFC 7541 Appendix A; index 1 is the first entry.
+const STATIC: [(&str, &str); 61] = [
+    (":authority", ""),
+    (":method", "GET"),
+    (":method", "POST"),
+    (":path", "/"),
+    (":path", "/index.html"),
+    (":scheme", "http"),
+    (":scheme", "https"),
+    (":status", "200"),
+    (":status", "204"),
+    (":status", "206"),
+    (":status", "304"),
+    (":status", "400"),
+    (":status", "404"),
+    (":status", "500"),
+    ("accept-charset", ""),
+    ("accept-encoding", "gzip, deflate"),
+    ("accept-language", ""),
+    ("accept-ranges", ""),
+    ("accept", ""),
+    ("access-control-allow-origin", ""),
+    ("age", ""),
+    ("allow", ""),
+    ("authorization", ""),
+    ("cache-control", ""),
+    ("content-disposition", ""),
+    ("content-encoding", ""),
+    ("content-language", ""),
+    ("content-length", ""),
+    ("content-location", ""),
+    ("content-range", ""),
+    ("content-type", ""),
+    ("cookie", ""),
+    ("date", ""),
+    ("etag", ""),
+    ("expect", ""),
+    ("expires", ""),
+    ("from", ""),
+    ("host", ""),
+    ("if-match", ""),
+    ("if-modified-since", ""),
+    ("if-none-match", ""),
+    ("if-range", ""),
+    ("if-unmodified-since", ""),
+    ("last-modified", ""),
+    ("link", ""),
+    ("location", ""),
+    ("max-forwards", ""),
+    ("proxy-authenticate", ""),
+    ("proxy-authorization", ""),
+    ("range", ""),
+    ("referer", ""),
+    ("refresh", ""),
+    ("retry-after", ""),
+    ("server", ""),
+    ("set-cookie", ""),
+    ("strict-transport-security", ""),
+    ("transfer-encoding", ""),
+    ("user-agent", ""),
+    ("vary", ""),
+    ("via", ""),
+    ("www-authenticate", ""),
+];
+
+/// Code lengths of the Huffman code of Appendix B, byte values then EOS.
+/// The code is canonical, so the lengths are all it takes.
+const CODE_LENGTHS: [u8; 257] = [
+    13, 23, 28, 28, 28, 28, 28, 28, 28, 24, 30, 28, 28, 30, 28, 28, 28, 28, 28, 28, 28, 28, 30, 28,
+    28, 28, 28, 28, 28, 28, 28, 28, 6, 10, 10, 12, 13, 6, 8, 11, 10, 10, 8, 11, 8, 6, 6, 6, 5, 5,
+    5, 6, 6, 6, 6, 6, 6, 6, 7, 8, 15, 6, 12, 10, 13, 6, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7,
+    7, 7, 7, 7, 7, 7, 7, 7, 8, 7, 8, 13, 19, 13, 14, 6, 15, 5, 6, 5, 6, 5, 6, 6, 6, 5, 7, 7, 6, 6,
+    6, 5, 6, 7, 6, 5, 5, 6, 7, 7, 7, 7, 7, 15, 11, 14, 13, 28, 20, 22, 20, 20, 22, 22, 22, 23, 22,
+    23, 23, 23, 23, 23, 24, 23, 24, 24, 22, 23, 24, 23, 23, 23, 23, 21, 22, 23, 22, 23, 23, 24, 22,
+    21, 20, 22, 22, 23, 23, 21, 23, 22, 22, 24, 21, 22, 23, 23, 21, 21, 22, 21, 23, 22, 23, 23, 20,
+    22, 22, 22, 23, 22, 22, 23, 26, 26, 20, 19, 22, 23, 22, 25, 26, 26, 26, 27, 27, 26, 24, 25, 19,
+    21, 26, 27, 27, 26, 27, 24, 21, 21, 26, 26, 28, 27, 27, 27, 20, 24, 20, 21, 22, 21, 21, 23, 22,
+    22, 25, 25, 24, 24, 26, 23, 26, 27, 26, 26, 27, 27, 27, 27, 27, 28, 27, 27, 27, 27, 27, 26, 30,
+];
+
+const EOS: u16 = 256;
+
+/// The size the dynamic table starts with and may grow back to, the
+/// `SETTINGS_HEADER_TABLE_SIZE` default.
+pub(crate) const TABLE_SIZE: usize = 4096;
+
+struct Huffman {
+    /// Codes of each length.
+    counts: [u16; 31],
+    /// Symbols ordered by code.
+    symbols: Vec<u16>,
+}
+
+fn huffman() -> &'static Huffman {
+    static HUFFMAN: OnceLock<Huffman> = OnceLock::new();
+    HUFFMAN.get_or_init(|| {
+        let mut counts = [0u16; 31];
+        for &len in &CODE_LENGTHS {
+            counts[usize::from(len)] += 1;
+        }
+        let mut symbols: Vec<u16> = (0..=EOS).collect();
+        symbols.sort_by_key(|&symbol| CODE_LENGTHS[usize::from(symbol)]);
+        Huffman { counts, symbols }
+    })
+}
+
+/// Decodes a Huffman-coded string. The last byte is padded with the high
+/// bits of EOS, all ones, and an EOS itself is an error.
+fn unhuffman(data: &[u8]) -> Result<Vec<u8>, &'static str> {
+    let table = huffman();
+    let mut out = Vec::with_capacity(data.len() * 8 / 5);
+    let (mut code, mut first, mut index, mut len) = (0i32, 0i32, 0i32, 0usize);
+    for &byte in data {
+        for shift in (0..8).rev() {
+            code |= i32::from(byte >> shift & 1);
+            len += 1;
+            let count = i32::from(table.counts[len]);
+            if code - count < first {
+                let symbol = table.symbols[(index + code - first) as usize];
+                if symbol == EOS {
+                    return Err("EOS in a Huffman-coded string");
+                }
+                out.push(symbol as u8);
+                (code, first, index, len) = (0, 0, 0, 0);
+                continue;
+            }
+            if len == 30 {
+                return Err("invalid Huffman code");
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+    }
+    // `code` was shifted once more than the bits read.
+    if len > 7 || code >> 1 != (1 << len) - 1 {
+        return Err("invalid Huffman padding");
+    }
+    Ok(out)
+}
+
+/// A header block decoder; one per connection, as the dynamic table spans
+/// the blocks.
+pub(crate) struct Decoder {
+    /// Newest first, as indices count them.
+    dynamic: VecDeque<(String, String)>,
+    size: usize,
+    max_size: usize,
+}
+
+impl Decoder {
+    pub(crate) fn new() -> Self {
+        Decoder {
+            dynamic: VecDeque::new(),
+            size: 0,
+            max_size: TABLE_SIZE,
+        }
+    }
+
+    /// The header fields of a complete header block, in order.
+    pub(crate) fn decode(&mut self, block: &[u8]) -> Result<Vec<(String, String)>, &'static str> {
+        let mut fields = Vec::new();
+        let mut at = 0;
+        while at < block.len() {
+            let byte = block[at];
+            if byte & 0x80 != 0 {
+                let index = integer(block, &mut at, 7)?;
+                fields.push(self.entry(index)?);
+            } else if byte & 0xe0 == 0x20 {
+                let size = integer(block, &mut at, 5)?;
+                if size > TABLE_SIZE {
+                    return Err("dynamic table size over the limit");
+                }
+                self.max_size = size;
+                self.evict(0);
+            } else {
+                // Literals: with incremental indexing (01), or without (0000)
+                // or never (0001) indexed.
+                let indexed = byte & 0xc0 == 0x40;
+                let index = integer(block, &mut at, if indexed { 6 } else { 4 })?;
+                let name = match index {
+                    0 => string(block, &mut at)?,
+                    _ => self.entry(index)?.0,
+                };
+                let value = string(block, &mut at)?;
+                if indexed {
+                    self.insert(name.clone(), value.clone());
+                }
+                fields.push((name, value));
+            }
+        }
+        Ok(fields)
+    }
+
+    fn entry(&self, index: usize) -> Result<(String, String), &'static str> {
+        let (name, value) = match index {
+            0 => return Err("header index 0"),
+            1..=61 => STATIC[index - 1],
+            _ => {
+                let (name, value) = self
+                    .dynamic
+                    .get(index - 62)
+                    .ok_or("header index past the tables")?;
+                (name.as_str(), value.as_str())
+            }
+        };
+        Ok((name.to_string(), value.to_string()))
+    }
+
+    fn insert(&mut self, name: String, value: String) {
+        let size = entry_size(&name, &value);
+        self.evict(size);
+        // An entry larger than the table empties it and is not added.
+        if size <= self.max_size {
+            self.size += size;
+            self.dynamic.push_front((name, value));
+        }
+    }
+
+    /// Evicts the oldest entries until `room` more bytes fit.
+    fn evict(&mut self, room: usize) {
+        while self.size + room > self.max_size {
+            let Some((name, value)) = self.dynamic.pop_back() else {
+                break;
+            };
+            self.size -= entry_size(&name, &value);
+        }
+    }
+}
+
+fn entry_size(name: &str, value: &str) -> usize {
+    name.len() + value.len() + 32
+}
+
+/// An integer with a `prefix`-bit prefix, starting at `block[*at]`.
+fn integer(block: &[u8], at: &mut usize, prefix: u8) -> Result<usize, &'static str> {
+    let max = (1usize << prefix) - 1;
+    let mut n = usize::from(block[*at]) & max;
+    *at += 1;
+    if n < max {
+        return Ok(n);
+    }
+    let mut shift = 0;
+    loop {
+        let &byte = block.get(*at).ok_or("truncated integer")?;
+        *at += 1;
+        n += usize::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(n);
+        }
+        shift += 7;
+        if shift > 28 {
+            return Err("integer too large");
+        }
+    }
+}
+
+fn string(block: &[u8], at: &mut usize) -> Result<String, &'static str> {
+    let &first = block.get(*at).ok_or("truncated header block")?;
+    let len = integer(block, at, 7)?;
+    let raw = block.get(*at..*at + len).ok_or("truncated header block")?;
+    *at += len;
+    let bytes = match first & 0x80 {
+        0 => raw.to_vec(),
+        _ => unhuffman(raw)?,
+    };
+    String::from_utf8(bytes).map_err(|_| "header not valid UTF-8")
+}
+
+/// A header block of literals that are neither indexed nor Huffman-coded.
+pub(crate) fn encode(fields: &[(&str, &str)]) -> Vec<u8> {
+    let mut block = Vec::new();
+    for (name, value) in fields {
+        block.push(0);
+        for text in [name, value] {
+            literal_length(&mut block, text.len());
+            block.extend_from_slice(text.as_bytes());
+        }
+    }
+    block
+}
+
+/// A string length: a 7-bit prefix, the Huffman bit clear.
+fn literal_length(block: &mut Vec<u8>, mut n: usize) {
+    if n < 0x7f {
+        block.push(n as u8);
+        return;
+    }
+    block.push(0x7f);
+    n -= 0x7f;
+    while n >= 0x80 {
+        block.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    block.push(n as u8);
+}
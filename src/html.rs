@@ -0,0 +1,196 @@
+//! HTML and XHTML to plain text: tags dropped, `script`/`style`/`head`
+//! skipped, block elements turned into line breaks and character references
+//! decoded. Meant for word counting, not for faithful rendering. `escape` goes
+//! the other way, for the HTML td5 writes.
+
+/// Elements whose end starts a new line.
+const BLOCKS: &[&str] = &[
+    "p",
+    "div",
+    "br",
+    "li",
+    "tr",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "blockquote",
+    "pre",
+    "section",
+    "article",
+    "table",
+    "ul",
+    "ol",
+    "dt",
+    "dd",
+    "hr",
+    "title",
+];
+/// Elements whose content is not text.
+const SKIPPED: &[&str] = &["script", "style", "head", "svg", "math"];
+
+/// The text of `html`, one line per block element.
+pub fn to_text(html: &str) -> String {
+    let mut out = String::with_capacity(html.len() / 2);
+    let mut rest = html;
+    while let Some(open) = rest.find('<') {
+        push_text(&mut out, &rest[..open]);
+        rest = &rest[open..];
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let Some(close) = rest.find('>') else {
+            rest = "";
+            break;
+        };
+        let (name, closing) = tag_name(&rest[1..close]);
+        let self_closing = rest[..close].ends_with('/');
+        rest = &rest[close + 1..];
+        if !closing && !self_closing && SKIPPED.contains(&name.as_str()) {
+            let end = format!("</{name}");
+            rest = find_ignore_case(rest, &end)
+                .and_then(|at| rest[at..].find('>').map(|gt| &rest[at + gt + 1..]))
+                .unwrap_or("");
+            continue;
+        }
+        if BLOCKS.contains(&name.as_str()) && !out.ends_with('\n') && !out.is_empty() {
+            out.push('\n');
+        }
+    }
+    push_text(&mut out, rest);
+    out
+}
+
+/// The lowercase element name of a tag's inside, and whether it is a closing
+/// tag.
+pub(crate) fn tag_name(inside: &str) -> (String, bool) {
+    let (inside, closing) = match inside.strip_prefix('/') {
+        Some(rest) => (rest, true),
+        None => (inside, false),
+    };
+    let name: String = inside
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || matches!(c, ':' | '-'))
+        .collect();
+    // Namespaced XHTML (`html:p`) counts as the local name.
+    let local = name.rsplit(':').next().unwrap_or("").to_ascii_lowercase();
+    (local, closing)
+}
+
+/// The value of attribute `name` in a tag's inside, entities decoded.
+#[cfg(feature = "epub")]
+pub(crate) fn attribute(inside: &str, name: &str) -> Option<String> {
+    let mut rest = inside;
+    while let Some(at) = find_ignore_case(rest, name) {
+        let before = rest[..at].chars().next_back();
+        let after = rest[at + name.len()..].trim_start();
+        rest = &rest[at + name.len()..];
+        if !before.is_some_and(char::is_whitespace) {
+            continue;
+        }
+        let Some(value) = after.strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start();
+        let quote = value.chars().next()?;
+        let raw = if matches!(quote, '"' | '\'') {
+            let body = &value[1..];
+            &body[..body.find(quote)?]
+        } else {
+            value
+                .split(|c: char| c.is_whitespace() || c == '>')
+                .next()?
+        };
+        return Some(decode_entities(raw));
+    }
+    None
+}
+
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    let needle = needle.as_bytes();
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|w| w.eq_ignore_ascii_case(needle))
+}
+
+fn push_text(out: &mut String, text: &str) {
+    if !text.is_empty() {
+        out.push_str(&decode_entities(text));
+    }
+}
+
+/// Decodes `&amp;`-style named references (the common ones) and numeric
+/// references; anything else is kept as written.
+pub fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| {
+                let name = &rest[1..end + 1];
+                let c = match name {
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "quot" => Some('"'),
+                    "apos" => Some('\''),
+                    "nbsp" => Some(' '),
+                    "mdash" => Some('—'),
+                    "ndash" => Some('–'),
+                    "hellip" => Some('…'),
+                    "rsquo" => Some('’'),
+                    "lsquo" => Some('‘'),
+                    "rdquo" => Some('”'),
+                    "ldquo" => Some('“'),
+                    _ => {
+                        let number = name.strip_prefix('#')?;
+                        let code = match number.strip_prefix(['x', 'X']) {
+                            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                            None => number.parse().ok()?,
+                        };
+                        char::from_u32(code)
+                    }
+                }?;
+                Some((c, end + 2))
+            });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// `text` with `&`, `<`, `>` and `"` replaced by character references, safe
+/// in element content and quoted attributes.
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
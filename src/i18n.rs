@@ -0,0 +1,1874 @@
+//! Message catalogs for the `td5` binary.
+//!
+//! Messages are written in English in the source and looked up by that text,
+//! so an untranslated message simply stays English. The language comes from
+//! `--lang`, else from `LC_ALL`, `LC_MESSAGES` or `LANG`. Library output
+//! (error details, lint findings, benchmark tables, the REPL) is not
+//! translated.
+//!
+//! Numbers in human-readable reports follow their own locale: digit grouping
+//! and decimal mark come from `--numbers`, else from `LC_ALL`, `LC_NUMERIC`
+//! or `LANG`, and `--decimals` overrides how many decimals every fractional
+//! number keeps. Machine formats (JSON, CSV, protobuf, ...) never use them.
+
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    En,
+    Fr,
+}
+
+pub const LANGS: &[&str] = &["en", "fr"];
+
+impl Lang {
+    pub fn parse(name: &str) -> Option<Lang> {
+        match name {
+            "en" => Some(Lang::En),
+            "fr" => Some(Lang::Fr),
+            _ => None,
+        }
+    }
+
+    /// From a locale such as `fr_FR.UTF-8`; English for anything unknown.
+    fn from_locale(locale: &str) -> Lang {
+        let lang = locale.split(['_', '.', '@', '-']).next().unwrap_or("");
+        Lang::parse(lang).unwrap_or(Lang::En)
+    }
+}
+
+static LANG: AtomicU8 = AtomicU8::new(Lang::En as u8);
+
+pub fn set_lang(lang: Lang) {
+    LANG.store(lang as u8, Ordering::Relaxed);
+}
+
+fn lang() -> Lang {
+    match LANG.load(Ordering::Relaxed) {
+        x if x == Lang::Fr as u8 => Lang::Fr,
+        _ => Lang::En,
+    }
+}
+
+/// The language asked for on the command line, else the locale's. Looked up
+/// before the arguments are parsed so that parse errors are translated too.
+/// `--lang` is removed from `args`, so it may also come before the command.
+pub fn detect(args: &mut Vec<String>) -> Result<Lang, String> {
+    let mut requested = None;
+    let mut i = 0;
+    while i < args.len() {
+        if let Some(value) = args[i].strip_prefix("--lang=") {
+            requested = Some(value.to_string());
+            args.remove(i);
+        } else if args[i] == "--lang" {
+            args.remove(i);
+            if i < args.len() {
+                requested = Some(args.remove(i));
+            }
+        } else {
+            i += 1;
+        }
+    }
+    if let Some(name) = requested {
+        return Lang::parse(&name).ok_or_else(|| {
+            fill(
+                tr("unknown language `{name}` (expected {known})"),
+                &[("name", &name), ("known", &LANGS.join(", "))],
+            )
+        });
+    }
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()));
+    Ok(locale.map_or(Lang::En, |l| Lang::from_locale(&l)))
+}
+
+/// How numbers are written in human-readable output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Numbers {
+    /// `1234567.5`
+    Plain,
+    /// `1,234,567.5`
+    En,
+    /// `1 234 567,5`, grouped with narrow no-break spaces.
+    Fr,
+}
+
+pub const NUMBER_STYLES: &[&str] = &["plain", "en", "fr"];
+
+impl Numbers {
+    pub fn parse(name: &str) -> Option<Numbers> {
+        match name {
+            "plain" => Some(Numbers::Plain),
+            "en" => Some(Numbers::En),
+            "fr" => Some(Numbers::Fr),
+            _ => None,
+        }
+    }
+
+    /// The locale's style: French grouping for `fr*`, English otherwise.
+    pub fn from_env() -> Numbers {
+        let locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()));
+        match locale.map(|l| Lang::from_locale(&l)) {
+            Some(Lang::Fr) => Numbers::Fr,
+            _ => Numbers::En,
+        }
+    }
+}
+
+static NUMBERS: AtomicU8 = AtomicU8::new(Numbers::En as u8);
+/// `usize::MAX` keeps each report's own precision.
+static DECIMALS: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+pub fn set_numbers(numbers: Numbers, decimals: Option<usize>) {
+    NUMBERS.store(numbers as u8, Ordering::Relaxed);
+    DECIMALS.store(decimals.unwrap_or(usize::MAX), Ordering::Relaxed);
+}
+
+fn grouped(digits: &str, fraction: Option<&str>) -> String {
+    let (separator, mark) = match NUMBERS.load(Ordering::Relaxed) {
+        x if x == Numbers::Plain as u8 => ("", "."),
+        x if x == Numbers::Fr as u8 => ("\u{202f}", ","),
+        _ => (",", "."),
+    };
+    let (sign, digits) = match digits.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", digits),
+    };
+    let mut out = String::from(sign);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            out.push_str(separator);
+        }
+        out.push(digit);
+    }
+    if let Some(fraction) = fraction {
+        out.push_str(mark);
+        out.push_str(fraction);
+    }
+    out
+}
+
+/// A whole number, digits grouped.
+pub fn number(n: impl std::fmt::Display) -> String {
+    grouped(&n.to_string(), None)
+}
+
+/// `x` with `decimals` decimals, or the `--decimals` ones, digits grouped.
+pub fn decimal(x: f64, decimals: usize) -> String {
+    let decimals = match DECIMALS.load(Ordering::Relaxed) {
+        usize::MAX => decimals,
+        forced => forced,
+    };
+    let mut text = format!("{x:.decimals$}");
+    if !x.is_finite() {
+        return text;
+    }
+    // A tiny negative rounds to "-0.00"; print it as zero.
+    if text.starts_with('-') && text.bytes().all(|b| matches!(b, b'-' | b'0' | b'.')) {
+        text.remove(0);
+    }
+    let (digits, fraction) = match text.split_once('.') {
+        Some((digits, fraction)) => (digits, Some(fraction)),
+        None => (text.as_str(), None),
+    };
+    grouped(digits, fraction)
+}
+
+/// `message` in the current language.
+pub fn tr(message: &'static str) -> &'static str {
+    match lang() {
+        Lang::En => message,
+        Lang::Fr => FR
+            .iter()
+            .find(|(en, _)| *en == message)
+            .map_or(message, |(_, fr)| fr),
+    }
+}
+
+/// Replaces each `{name}` in `template` with its value.
+pub fn fill(template: &str, values: &[(&str, &dyn std::fmt::Display)]) -> String {
+    let mut out = template.to_string();
+    for (name, value) in values {
+        out = out.replace(&format!("{{{name}}}"), &value.to_string());
+    }
+    out
+}
+
+/// `tr!("Saved {path}", path = p)`: translated, then placeholders filled in.
+macro_rules! tr {
+    ($msg:literal) => {
+        $crate::i18n::tr($msg)
+    };
+    ($msg:literal, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::fill(
+            $crate::i18n::tr($msg),
+            &[$((stringify!($name), &$value as &dyn std::fmt::Display)),+],
+        )
+    };
+}
+
+const FR: &[(&str, &str)] = &[
+    // Help and usage.
+    (
+        "td5: fast/slow text analyzer",
+        "td5 : analyseur de texte lent/rapide",
+    ),
+    ("Usage:", "Utilisation :"),
+    ("Commands:", "Commandes :"),
+    ("Options:", "Options :"),
+    ("Examples:", "Exemples :"),
+    (
+        "Run `td5 help <COMMAND>` for options and examples.",
+        "Lancez `td5 help <COMMANDE>` pour les options et des exemples.",
+    ),
+    (
+        "Analyze generated text, a file or stdin with the slow and fast analyzers (default)",
+        "Analyse un texte généré, un fichier ou l'entrée standard avec les analyseurs lent et rapide (par défaut)",
+    ),
+    (
+        "Print word statistics for files or directories (or stdin)",
+        "Affiche les statistiques de mots de fichiers ou de répertoires (ou de l'entrée standard)",
+    ),
+    (
+        "Output format: text (default), json (one document per line), yaml (a document stream), csv (one row per input), length-delimited protobuf, msgpack or cbor",
+        "Format de sortie : text (par défaut), json (un document par ligne), yaml (un flux de documents), csv (une ligne par entrée), protobuf délimité, msgpack ou cbor",
+    ),
+    (
+        "Analyzer to use (default fast)",
+        "Analyseur à utiliser (par défaut fast)",
+    ),
+    (
+        "Exit with status 3 unless e.g. 'unique_words>=500' holds for every input (repeatable)",
+        "Sort avec le code 3 si par ex. 'unique_words>=500' n'est pas vrai pour chaque entrée (répétable)",
+    ),
+    (
+        "Render each input's stats through a Handlebars-style template instead",
+        "Rend les statistiques de chaque entrée avec un modèle de style Handlebars",
+    ),
+    (
+        "Only count the last SIZE tokens ('10000 tokens') or, for timestamped lines, the last SIZE of time ('5m')",
+        "Ne compte que les SIZE derniers mots ('10000 tokens') ou, pour des lignes horodatées, la dernière durée SIZE ('5m')",
+    ),
+    (
+        "Word statistics and trending terms per minute, hour or day of timestamped lines",
+        "Statistiques de mots et termes en hausse par minute, heure ou jour de lignes horodatées",
+    ),
+    (
+        "Bucket size: minute, hour (default) or day",
+        "Taille des tranches : minute, hour (par défaut) ou day",
+    ),
+    (
+        "Compare the vocabularies of two files: Jaccard similarity, words unique to each, shared top terms",
+        "Compare les vocabulaires de deux fichiers : similarité de Jaccard, mots propres à chacun, termes fréquents communs",
+    ),
+    (
+        "Compare two versions of a text, or two results saved by analyze --format json: metrics and timing side by side, shared vocabulary, words unique to each and the largest frequency shifts",
+        "Compare deux versions d'un texte, ou deux résultats enregistrés par analyze --format json : métriques et temps côte à côte, vocabulaire commun, mots propres à chacune et plus forts changements de fréquence",
+    ),
+    (
+        "comparing saved results: the word lists only see their top words",
+        "comparaison de résultats enregistrés : les listes de mots ne voient que leurs mots les plus fréquents",
+    ),
+    ("change", "écart"),
+    (
+        "Largest shifts (per thousand words):",
+        "Plus forts changements (pour mille mots) :",
+    ),
+    (
+        "Words per list (default 10)",
+        "Mots par liste (10 par défaut)",
+    ),
+    (
+        "Compare corpus snapshots, oldest first, and report the words rising and falling the most",
+        "Compare des instantanés d'un corpus, du plus ancien au plus récent, et indique les mots en plus forte hausse et baisse",
+    ),
+    (
+        "Leave out words seen fewer than N times over all snapshots (default 5)",
+        "Ignore les mots vus moins de N fois sur l'ensemble des instantanés (5 par défaut)",
+    ),
+    (
+        "Output format: text (default), csv (the full trend table) or json",
+        "Format de sortie : text (par défaut), csv (la table complète des tendances) ou json",
+    ),
+    (
+        "Chart readability and sentence length through a document to find its hardest passages",
+        "Trace la lisibilité et la longueur des phrases au fil d'un document pour trouver ses passages les plus difficiles",
+    ),
+    (
+        "Words per window (default 200)",
+        "Mots par fenêtre (200 par défaut)",
+    ),
+    (
+        "Words between the starts of two windows (default 50)",
+        "Mots entre les débuts de deux fenêtres (50 par défaut)",
+    ),
+    (
+        "Passages to list as hardest to read (default 3)",
+        "Passages les plus difficiles à lister (3 par défaut)",
+    ),
+    (
+        "Output format: text (default), csv or json",
+        "Format de sortie : text (par défaut), csv ou json",
+    ),
+    (
+        "Export the network of words appearing near one another, for Gephi or Graphviz",
+        "Exporte le réseau des mots apparaissant près les uns des autres, pour Gephi ou Graphviz",
+    ),
+    (
+        "Link words at most N words apart (default 5)",
+        "Relie les mots séparés d'au plus N mots (5 par défaut)",
+    ),
+    (
+        "Most frequent words to use as nodes (default 50)",
+        "Mots les plus fréquents utilisés comme nœuds (50 par défaut)",
+    ),
+    (
+        "Drop links seen fewer than N times (default 2)",
+        "Ignore les liens vus moins de N fois (2 par défaut)",
+    ),
+    (
+        "Output format: text (default), json, dot or graphml",
+        "Format de sortie : text (par défaut), json, dot ou graphml",
+    ),
+    (
+        "Show every occurrence of a word with the words around it (keyword in context)",
+        "Affiche chaque occurrence d'un mot avec les mots qui l'entourent (mot-clé en contexte)",
+    ),
+    (
+        "Top words of input of any length in fixed memory, with error bounds (Count-Min Sketch and Space-Saving)",
+        "Mots les plus fréquents d'une entrée de toute longueur en mémoire fixe, avec des bornes d'erreur (Count-Min Sketch et Space-Saving)",
+    ),
+    (
+        "Counts may be over by at most E times the words read (default 0.0001)",
+        "Les comptes dépassent d'au plus E fois le nombre de mots lus (0.0001 par défaut)",
+    ),
+    (
+        "Probability that a count is over by more (default 0.01)",
+        "Probabilité qu'un compte dépasse davantage (0.01 par défaut)",
+    ),
+    (
+        "Candidate words kept (default 1/epsilon)",
+        "Mots candidats conservés (1/epsilon par défaut)",
+    ),
+    (
+        "Words to list (default 10)",
+        "Mots à lister (10 par défaut)",
+    ),
+    (
+        "Also print the top words this often while reading, for streams that do not end",
+        "Afficher aussi les mots les plus fréquents à cet intervalle pendant la lecture, pour les flux sans fin",
+    ),
+    (
+        "{total} words, in {memory} KiB:",
+        "{total} mots, en {memory} Kio :",
+    ),
+    ("(at least {min})", "(au moins {min})"),
+    (
+        "Counts are at most {bound} too high with probability {confidence}.",
+        "Les comptes sont trop élevés d'au plus {bound} avec une probabilité de {confidence}.",
+    ),
+    (
+        "Analyze a file again each time it is saved and print what changed",
+        "Réanalyser un fichier à chaque enregistrement et afficher ce qui a changé",
+    ),
+    (
+        "How often to check the file for changes (default 500)",
+        "Intervalle de vérification du fichier en millisecondes (500 par défaut)",
+    ),
+    (
+        "Top words to follow (default 10)",
+        "Mots les plus fréquents à suivre (10 par défaut)",
+    ),
+    (
+        "{total} words ({total_change}), {unique} distinct ({unique_change})",
+        "{total} mots ({total_change}), {unique} distincts ({unique_change})",
+    ),
+    (
+        "Words shown on each side (default 5)",
+        "Mots affichés de chaque côté (5 par défaut)",
+    ),
+    (
+        "expected a word and at least one file",
+        "attendu un mot et au moins un fichier",
+    ),
+    (
+        "{count} occurrences of `{word}`",
+        "{count} occurrences de `{word}`",
+    ),
+    (
+        "Find clusters of near-duplicate documents among files or directories",
+        "Trouve les groupes de documents quasi identiques parmi des fichiers ou dossiers",
+    ),
+    (
+        "Estimated similarity, 0 to 1, above which two documents are duplicates (default 0.8)",
+        "Similarité estimée, de 0 à 1, à partir de laquelle deux documents sont des doublons (0.8 par défaut)",
+    ),
+    (
+        "Words per shingle (default 3)",
+        "Mots par bardeau (3 par défaut)",
+    ),
+    (
+        "Print only the files to keep: the first of each cluster and every other file",
+        "N'affiche que les fichiers à garder : le premier de chaque groupe et tous les autres fichiers",
+    ),
+    (
+        "--threshold must be between 0 and 1",
+        "--threshold doit être compris entre 0 et 1",
+    ),
+    (
+        "{clusters} clusters of near-duplicates among {files} files ({duplicates} redundant, threshold {threshold})",
+        "{clusters} groupes de quasi-doublons parmi {files} fichiers ({duplicates} redondants, seuil {threshold})",
+    ),
+    ("Cluster {n}:", "Groupe {n} :"),
+    (
+        "Speak the Language Server Protocol on stdin/stdout: live word stats and lint diagnostics for an editor",
+        "Parle le Language Server Protocol sur stdin/stdout : statistiques de mots et diagnostics de relecture en direct pour un éditeur",
+    ),
+    (
+        "Output format: text (default) or json",
+        "Format de sortie : text (par défaut) ou json",
+    ),
+    (
+        "Output format: text (default), csv (one row per input with its path, size, modification time and language, then the combined total) or json",
+        "Format de sortie : text (par défaut), csv (une ligne par entrée avec son chemin, sa taille, sa date de modification et sa langue, puis le total combiné) ou json",
+    ),
+    (
+        "Output format: text (default), csv or json (one document per bucket)",
+        "Format de sortie : text (par défaut), csv ou json (un document par tranche)",
+    ),
+    (
+        "Also report each section, cut at every heading, chapter or regex:PATTERN line",
+        "Rapporte aussi chaque section, coupée à chaque titre, chapitre ou ligne regex:PATTERN",
+    ),
+    (
+        "Count words and phrases from a deny list, failing above a threshold",
+        "Compte les mots et expressions d'une liste d'exclusion, en échec au-delà d'un seuil",
+    ),
+    (
+        "Denied words or phrase patterns, one per line",
+        "Mots ou motifs d'expressions exclus, un par ligne",
+    ),
+    (
+        "Also print where each match is",
+        "Affiche aussi l'emplacement de chaque occurrence",
+    ),
+    (
+        "Exit with status 3 if there are more than N matches in total (default 0)",
+        "Sort avec le code 3 s'il y a plus de N occurrences au total (par défaut 0)",
+    ),
+    (
+        "Export the word frequencies of files for spellcheckers and prediction tools",
+        "Exporte les fréquences des mots de fichiers pour correcteurs et outils de prédiction",
+    ),
+    (
+        "tsv (count<TAB>word, default), dic (Hunspell word list), msgpack or cbor (word-to-count map)",
+        "tsv (nombre<TAB>mot, par défaut), dic (liste de mots Hunspell), msgpack ou cbor (table mot → nombre)",
+    ),
+    (
+        "Leave out words seen fewer than N times (default 1)",
+        "Omet les mots vus moins de N fois (par défaut 1)",
+    ),
+    (
+        "Skip the N most frequent words (default 0)",
+        "Saute les N mots les plus fréquents (par défaut 0)",
+    ),
+    (
+        "List at most N words, for one page of a large vocabulary",
+        "Liste au plus N mots, pour une page d'un grand vocabulaire",
+    ),
+    (
+        "List the words of files that are not in a dictionary, most frequent first",
+        "Liste les mots de fichiers absents d'un dictionnaire, les plus fréquents d'abord",
+    ),
+    (
+        "Known words, one per line (Hunspell .dic files work)",
+        "Mots connus, un par ligne (les fichiers .dic de Hunspell conviennent)",
+    ),
+    (
+        "Known words, one per line; logs how many words of each input it lacks",
+        "Mots connus, un par ligne ; indique combien de mots de chaque entrée y manquent",
+    ),
+    (
+        "Count only words in the --dictionary",
+        "Ne compte que les mots du --dictionary",
+    ),
+    (
+        "Read SRT/WebVTT subtitles and report the speech rate (automatic for .srt and .vtt files)",
+        "Lit des sous-titres SRT/WebVTT et indique le débit de parole (automatique pour les fichiers .srt et .vtt)",
+    ),
+    (
+        "Read a chat log (name: message lines, or a WhatsApp, Slack or Telegram JSON export) and also report stats per speaker",
+        "Lit un historique de discussion (lignes nom: message, ou export JSON WhatsApp, Slack ou Telegram) et donne aussi des statistiques par participant",
+    ),
+    (
+        "{path}: {messages} messages from {speakers} speakers",
+        "{path} : {messages} messages de {speakers} participants",
+    ),
+    ("Speaker", "Participant"),
+    (
+        "{path}: {messages} messages from {senders} senders",
+        "{path} : {messages} messages de {senders} expéditeurs",
+    ),
+    ("Sender", "Expéditeur"),
+    ("Messages", "Messages"),
+    ("Words/msg", "Mots/msg"),
+    ("Longest", "Plus long"),
+    (
+        "Count hashtags, @mentions and URLs separately instead of as words",
+        "Compte les hashtags, @mentions et URL à part plutôt que comme des mots",
+    ),
+    (
+        "Also report how many words occur once, 2-5 times, ... and count quantiles",
+        "Indique aussi combien de mots apparaissent une fois, 2 à 5 fois, ... et les quantiles des fréquences",
+    ),
+    (
+        "Transform the counted words through the stages in FILE (default: pipeline.conf in the config directory, if any)",
+        "Transforme les mots comptés par les étapes de FILE (par défaut : pipeline.conf du dossier de configuration, s'il existe)",
+    ),
+    (
+        "Merge variant spellings into one word, from a file of variant<TAB>canonical lines",
+        "Fusionne les variantes orthographiques en un mot, d'après un fichier de lignes variante<TAB>canonique",
+    ),
+    (
+        "--pipeline and --merge-map cannot be combined with --only-known or --window",
+        "--pipeline et --merge-map ne peuvent pas être combinés avec --only-known ou --window",
+    ),
+    (
+        "Also analyze the files and URLs listed in FILE, one per line",
+        "Analyse aussi les fichiers et URL listés dans FILE, un par ligne",
+    ),
+    (
+        "Largest body accepted from a URL, in bytes or with a K, M or G suffix (default: 50M)",
+        "Taille maximale acceptée pour une URL, en octets ou avec un suffixe K, M ou G (par défaut : 50M)",
+    ),
+    (
+        "Media types accepted from a URL, comma-separated, `text/*` for a family (default: text/*, application/json, application/xml, application/xhtml+xml)",
+        "Types de contenu acceptés pour une URL, séparés par des virgules, `text/*` pour une famille (par défaut : text/*, application/json, application/xml, application/xhtml+xml)",
+    ),
+    (
+        "Redirects followed for a URL (default: 5)",
+        "Redirections suivies pour une URL (par défaut : 5)",
+    ),
+    (
+        "Retries of a URL after a connection failure, timeout, 429 or 5xx answer (default: 3)",
+        "Nouvelles tentatives d'une URL après un échec de connexion, un délai dépassé ou une réponse 429 ou 5xx (par défaut : 3)",
+    ),
+    (
+        "Wait before the first retry, doubled on each one (default: 500)",
+        "Attente avant la première nouvelle tentative, doublée à chacune (par défaut : 500)",
+    ),
+    (
+        "Shortest time between two downloads (default: 0)",
+        "Délai minimal entre deux téléchargements (par défaut : 0)",
+    ),
+    (
+        "Longest a single download may take (default: 60)",
+        "Durée maximale d'un téléchargement (par défaut : 60)",
+    ),
+    (
+        "`--{name}` expects a size such as 500000, 64K or 50M, got `{value}`",
+        "`--{name}` attend une taille comme 500000, 64K ou 50M, reçu `{value}`",
+    ),
+    (
+        "Leave the words of LIST out of the top words, still counting them: en, fr, or a file with one word per line (repeatable)",
+        "Retire les mots de LIST des mots les plus fréquents, sans cesser de les compter : en, fr, ou un fichier d'un mot par ligne (répétable)",
+    ),
+    (
+        "Skip files smaller than SIZE, such as 200 or 4K",
+        "Ignore les fichiers de moins de SIZE, comme 200 ou 4K",
+    ),
+    (
+        "Skip files larger than SIZE, such as 50M",
+        "Ignore les fichiers de plus de SIZE, comme 50M",
+    ),
+    (
+        "Leave out documents of fewer than N words",
+        "Laisse de côté les documents de moins de N mots",
+    ),
+    (
+        "Analyze binary inputs too (skipped by default: a NUL byte or over 30% control bytes at the start)",
+        "Analyse aussi les entrées binaires (ignorées par défaut : un octet NUL ou plus de 30 % d'octets de contrôle au début)",
+    ),
+    ("{path}: skipped ({reason})", "{path} : ignoré ({reason})"),
+    (
+        "{skipped} of {total} inputs skipped:",
+        "{skipped} entrées sur {total} ignorées :",
+    ),
+    (
+        "`--max-download` expects a size such as 500000, 64K or 50M, got `{value}`",
+        "`--max-download` attend une taille comme 500000, 64K ou 50M, reçu `{value}`",
+    ),
+    (
+        "Also list acronyms and all-caps terms as written, with their counts",
+        "Liste aussi les acronymes et termes en majuscules tels qu'écrits, avec leur nombre",
+    ),
+    (
+        "Also read the input as source code and count LANG's keywords (rust, python or c, or auto to go by each file's extension) apart from its identifiers",
+        "Lit aussi l'entrée comme du code source et compte les mots-clés de LANG (rust, python ou c, ou auto pour suivre l'extension de chaque fichier) à part des identifiants",
+    ),
+    (
+        "Start from the flags of a use case: seo, prose, logs or code (listed below); flags after it override them",
+        "Part des options d'un cas d'usage : seo, prose, logs ou code (listés plus bas) ; les options qui suivent les remplacent",
+    ),
+    ("Presets:", "Préréglages :"),
+    (
+        "unknown preset `{name}` (expected {names})",
+        "préréglage `{name}` inconnu (attendu : {names})",
+    ),
+    (
+        "Web pages: keywords and key phrases without stopwords, links and hashtags apart, how evenly keywords spread",
+        "Pages web : mots-clés et expressions sans mots vides, liens et hashtags à part, régularité de la répartition des mots-clés",
+    ),
+    (
+        "Books and essays: top words and word pairs without stopwords, dialogue and narration, how often words recur",
+        "Livres et essais : mots et paires de mots les plus fréquents sans mots vides, dialogue et narration, fréquence de retour des mots",
+    ),
+    (
+        "Log files: numbers with units, IP and email addresses, all-caps levels and codes, one JSON document per file",
+        "Journaux : nombres avec unités, adresses IP et e-mail, niveaux et codes en majuscules, un document JSON par fichier",
+    ),
+    (
+        "Source code: keywords apart from identifiers, the language going by the file extension, and word pairs",
+        "Code source : mots-clés à part des identifiants, le langage suivant l'extension du fichier, et paires de mots",
+    ),
+    (
+        "Also count numbers with units (15 ms, 3.2 GB, 99%) and summarize them per unit",
+        "Compte aussi les nombres avec unité (15 ms, 3.2 GB, 99%) et les résume par unité",
+    ),
+    (
+        "Also report the share of text inside quotation marks and the top words of dialogue and narration",
+        "Indique aussi la part du texte entre guillemets et les mots les plus fréquents du dialogue et de la narration",
+    ),
+    (
+        "With --dialogue, the quotation marks as opening and closing pairs (default '\"\",“”,«»')",
+        "Avec --dialogue, les guillemets en paires ouvrant-fermant (par défaut '\"\",“”,«»')",
+    ),
+    (
+        "Also report how evenly the N most frequent words spread through the text, most bursty first",
+        "Indique aussi la répartition dans le texte des N mots les plus fréquents, les plus groupés d'abord",
+    ),
+    (
+        "Also list the N most frequent probable nouns, verbs and adjectives, guessed from English suffixes",
+        "Liste aussi les N noms, verbes et adjectifs probables les plus fréquents, devinés d'après les suffixes anglais",
+    ),
+    (
+        "{path}: probable nouns, verbs and adjectives (from suffixes)",
+        "{path} : noms, verbes et adjectifs probables (d'après les suffixes)",
+    ),
+    ("Nouns:", "Noms :"),
+    ("Verbs:", "Verbes :"),
+    ("Adjectives:", "Adjectifs :"),
+    (
+        "Also count email addresses, phone numbers and IP addresses",
+        "Compte aussi les adresses e-mail, numéros de téléphone et adresses IP",
+    ),
+    (
+        "With --pii, show up to N redacted examples of each",
+        "Avec --pii, montre jusqu'à N exemples masqués de chaque",
+    ),
+    (
+        "Flag repeated words, long sentences and overused fillers",
+        "Signale les mots répétés, les phrases longues et les mots de remplissage abusifs",
+    ),
+    (
+        "Longest sentence allowed, in words (default 40)",
+        "Longueur maximale d'une phrase, en mots (par défaut 40)",
+    ),
+    (
+        "Filler word to watch, replacing the built-in list (repeatable)",
+        "Mot de remplissage à surveiller, à la place de la liste intégrée (répétable)",
+    ),
+    (
+        "Report a filler above this share of all words (default 0.01)",
+        "Signale un mot de remplissage au-delà de cette part des mots (par défaut 0.01)",
+    ),
+    (
+        "Phrase lists ([name] headers, one pattern per line) replacing the built-in ones",
+        "Listes d'expressions (en-têtes [nom], un motif par ligne) à la place des listes intégrées",
+    ),
+    (
+        "Time every analyzer on a matrix of generated input shapes",
+        "Chronomètre chaque analyseur sur une matrice de formes d'entrée générées",
+    ),
+    (
+        "Words per generated input (default 200000)",
+        "Mots par entrée générée (par défaut 200000)",
+    ),
+    (
+        "Timed runs per cell (default 10)",
+        "Exécutions chronométrées par cellule (par défaut 10)",
+    ),
+    (
+        "Comma-separated shapes (default: all)",
+        "Formes séparées par des virgules (par défaut : toutes)",
+    ),
+    (
+        "Comma-separated analyzers (default: all)",
+        "Analyseurs séparés par des virgules (par défaut : tous)",
+    ),
+    (
+        "Seed for the input generators (default 0)",
+        "Graine des générateurs d'entrées (par défaut 0)",
+    ),
+    (
+        "Also benchmark FILE (repeatable)",
+        "Chronomètre aussi FILE (répétable)",
+    ),
+    (
+        "Also benchmark a cached/downloaded corpus, e.g. gutenberg:1342 (repeatable)",
+        "Chronomètre aussi un corpus en cache ou téléchargé, par ex. gutenberg:1342 (répétable)",
+    ),
+    (
+        "Write the vocabulary of files with counts as a prefix automaton for autocomplete",
+        "Écrit le vocabulaire des fichiers avec leurs fréquences sous forme d'automate de préfixes pour l'autocomplétion",
+    ),
+    ("Where to write the automaton", "Où écrire l'automate"),
+    (
+        "Most frequent words starting with a prefix, from a `prefix-tree` file",
+        "Mots les plus fréquents commençant par un préfixe, d'après un fichier `prefix-tree`",
+    ),
+    (
+        "Number of completions (default 10)",
+        "Nombre de complétions (10 par défaut)",
+    ),
+    (
+        "Pick the fast path's hot words from a corpus of files or directories",
+        "Choisit les mots chauds du chemin rapide d'après un corpus de fichiers ou de répertoires",
+    ),
+    (
+        "Number of hot words, at most 256 (default 10)",
+        "Nombre de mots chauds, 256 au plus (10 par défaut)",
+    ),
+    (
+        "Write the list to FILE instead of standard output",
+        "Écrit la liste dans FILE plutôt que sur la sortie standard",
+    ),
+    (
+        "Measure chunk, map and buffer sizes on this machine and save the fastest",
+        "Mesure les tailles de morceaux, de table et de tampon sur cette machine et enregistre les plus rapides",
+    ),
+    (
+        "Words of generated Zipf text to tune on (default 1000000)",
+        "Mots de texte Zipf généré pour le réglage (1000000 par défaut)",
+    ),
+    (
+        "Tune on FILE instead of generated text",
+        "Règle sur FILE plutôt que sur du texte généré",
+    ),
+    (
+        "Timed runs per candidate value (default 5)",
+        "Exécutions chronométrées par valeur candidate (5 par défaut)",
+    ),
+    (
+        "Write the config to FILE instead of the default location",
+        "Écrit la configuration dans FILE plutôt qu'à l'emplacement par défaut",
+    ),
+    (
+        "Only print the measurements",
+        "Affiche seulement les mesures",
+    ),
+    (
+        "Also time external tools on each input: wc, grep, uniq (comma-separated)",
+        "Chronomètre aussi des outils externes sur chaque entrée : wc, grep, uniq (séparés par des virgules)",
+    ),
+    (
+        "Keep generated inputs on disk: auto (1M words or more, the default), always or off",
+        "Garde les entrées générées sur disque : auto (1M mots ou plus, par défaut), always ou off",
+    ),
+    (
+        "Write samples and environment to FILE as JSON",
+        "Écrit les mesures et l'environnement dans FILE en JSON",
+    ),
+    (
+        "Compare against a report saved with --save",
+        "Compare à un rapport enregistré avec --save",
+    ),
+    (
+        "Write a replayable bundle (inputs, options, environment, results)",
+        "Écrit un paquet rejouable (entrées, options, environnement, résultats)",
+    ),
+    (
+        "Render the results through a Handlebars-style template instead of the grid",
+        "Rend les résultats avec un modèle de style Handlebars au lieu du tableau",
+    ),
+    (
+        "Rerun a bundle written by `bench --record` and diff the results",
+        "Rejoue un paquet écrit par `bench --record` et compare les résultats",
+    ),
+    (
+        "Check that every analyzer agrees with the reference on edge cases",
+        "Vérifie que chaque analyseur s'accorde avec la référence sur des cas limites",
+    ),
+    (
+        "Load documents once and query them interactively",
+        "Charge des documents une fois et les interroge de façon interactive",
+    ),
+    (
+        "Print a completion script for bash, zsh or fish",
+        "Affiche un script de complétion pour bash, zsh ou fish",
+    ),
+    (
+        "Show help for td5 or one of its commands",
+        "Affiche l'aide de td5 ou de l'une de ses commandes",
+    ),
+    (
+        "Show help for this command",
+        "Affiche l'aide de cette commande",
+    ),
+    (
+        "Only print results, warnings and errors",
+        "N'affiche que les résultats, avertissements et erreurs",
+    ),
+    (
+        "Also print debug notes on stderr",
+        "Affiche aussi les notes de débogage sur stderr",
+    ),
+    (
+        "Language of messages: en or fr (default: from the locale)",
+        "Langue des messages : en ou fr (par défaut : selon la locale)",
+    ),
+    (
+        "Number style in reports: en (1,234.5), fr (1 234,5) or plain (default: from the locale)",
+        "Format des nombres des rapports : en (1,234.5), fr (1 234,5) ou plain (par défaut : selon la locale)",
+    ),
+    (
+        "Decimals of every fractional number in reports",
+        "Décimales de tous les nombres fractionnaires des rapports",
+    ),
+    (
+        "unknown number style `{name}` (expected {known})",
+        "format de nombres `{name}` inconnu (attendu : {known})",
+    ),
+    // Argument errors.
+    (
+        "unknown option `--{name}` for `{command}`",
+        "option `--{name}` inconnue pour `{command}`",
+    ),
+    (
+        "`--{name}` expects <{value}>",
+        "`--{name}` attend <{value}>",
+    ),
+    (
+        "`--{name}` takes no value",
+        "`--{name}` ne prend pas de valeur",
+    ),
+    (
+        "`--{name}` expects a number, got `{value}`",
+        "`--{name}` attend un nombre, reçu `{value}`",
+    ),
+    (
+        "unknown language `{name}` (expected {known})",
+        "langue `{name}` inconnue (attendu : {known})",
+    ),
+    ("unknown command `{name}`", "commande `{name}` inconnue"),
+    ("expected one of: {choices}", "attendu l'un de : {choices}"),
+    ("error: {message}", "erreur : {message}"),
+    ("warning: {message}", "avertissement : {message}"),
+    // Reports.
+    ("Unique words:", "Mots uniques :"),
+    ("Total words:", "Mots au total :"),
+    ("Total chars:", "Caractères au total :"),
+    ("Lines:", "Lignes :"),
+    ("Sentences (approx.):", "Phrases (environ) :"),
+    (
+        "Word length (mean / median):",
+        "Longueur des mots (moyenne / médiane) :",
+    ),
+    ("Word lengths:", "Longueurs des mots :"),
+    ("Top 10 words:", "10 mots les plus fréquents :"),
+    ("Longest words:", "Mots les plus longs :"),
+    ("Top bigrams:", "Bigrammes les plus fréquents :"),
+    ("Top trigrams:", "Trigrammes les plus fréquents :"),
+    ("Time:", "Temps :"),
+    ("Diagnostics:", "Diagnostics :"),
+    ("Phases ({threads} threads):", "Phases ({threads} fils) :"),
+    ("Phases:", "Phases :"),
+    ("{rounds} rounds", "{rounds} tours"),
+    (
+        "Analyzing {bytes} bytes of text...",
+        "Analyse de {bytes} octets de texte...",
+    ),
+    ("Results:", "Résultats :"),
+    ("Slow", "Lent"),
+    ("Fast", "Rapide"),
+    ("Parallel", "Parallèle"),
+    ("analyzer", "analyseur"),
+    (
+        "Output format: text (default), json or csv, both with every sample and summary",
+        "Format de sortie : text (par défaut), json ou csv, tous deux avec chaque mesure et son résumé",
+    ),
+    (
+        "--template and --baseline need --format text",
+        "--template et --baseline nécessitent --format text",
+    ),
+    ("MB/s", "Mo/s"),
+    ("speedup", "accélération"),
+    ("allocations", "allocations"),
+    ("peak MiB", "pic Mio"),
+    (
+        "Words of generated text when no file is given (default 50000)",
+        "Mots de texte généré quand aucun fichier n'est donné (50000 par défaut)",
+    ),
+    (
+        "Seed of the generated text (default 0)",
+        "Graine du texte généré (0 par défaut)",
+    ),
+    (
+        "Distinct words of the generated text, Zipf-distributed (default 10000)",
+        "Mots distincts du texte généré, distribués selon Zipf (10000 par défaut)",
+    ),
+    (
+        "Generated text: prose (default), hot, a shape such as zipf or unicode, markov:FILE or file:FILE",
+        "Texte généré : prose (par défaut), hot, une forme comme zipf ou unicode, markov:FICHIER ou file:FICHIER",
+    ),
+    (
+        "Analyzer the others are compared to (default slow)",
+        "Analyseur auquel les autres sont comparés (slow par défaut)",
+    ),
+    ("Phrase counts:", "Occurrences des expressions :"),
+    ("{path}: {count} sections", "{path} : {count} sections"),
+    ("{path}: personal data", "{path} : données personnelles"),
+    (
+        "{path}: hashtags, mentions and URLs",
+        "{path} : hashtags, mentions et URL",
+    ),
+    ("Hashtags:", "Hashtags :"),
+    ("Mentions:", "Mentions :"),
+    ("URLs:", "URL :"),
+    ("{path}: speech rate", "{path} : débit de parole"),
+    (
+        "{wpm} words per minute over {minutes} min",
+        "{wpm} mots par minute sur {minutes} min",
+    ),
+    (
+        "{wpm} words per minute while speaking ({minutes} min)",
+        "{wpm} mots par minute de parole ({minutes} min)",
+    ),
+    (
+        "{wpm} words in the busiest minute",
+        "{wpm} mots dans la minute la plus dense",
+    ),
+    ("line", "ligne"),
+    ("words", "mots"),
+    ("unique", "distincts"),
+    ("ratio", "ratio"),
+    ("section", "section"),
+    ("(before the first section)", "(avant la première section)"),
+    (
+        "{total} words, {unique} unique",
+        "{total} mots, {unique} distincts",
+    ),
+    ("Top words:", "Mots les plus fréquents :"),
+    ("Trending:", "En hausse :"),
+    (
+        "{failed} of {total} cases diverge",
+        "{failed} cas sur {total} divergent",
+    ),
+    ("Against {path}:", "Par rapport à {path} :"),
+    ("Against the recording:", "Par rapport à l'enregistrement :"),
+    // Progress and diagnostics.
+    ("unknown shape `{name}`", "forme `{name}` inconnue"),
+    ("unknown analyzer `{name}`", "analyseur `{name}` inconnu"),
+    ("unknown tool `{name}`", "outil `{name}` inconnu"),
+    (
+        "unknown input cache mode `{name}` (expected auto, always or off)",
+        "mode de cache d'entrées `{name}` inconnu (attendu : auto, always ou off)",
+    ),
+    (
+        "{words} words in {states} states, {bytes} bytes written to {path}",
+        "{words} mots en {states} états, {bytes} octets écrits dans {path}",
+    ),
+    (
+        "expected a prefix tree and a prefix",
+        "un arbre de préfixes et un préfixe sont attendus",
+    ),
+    (
+        "{path}: count distribution",
+        "{path} : distribution des fréquences",
+    ),
+    (
+        "{path}: dispersion over {parts} parts of {tokens} tokens",
+        "{path} : dispersion sur {parts} parties de {tokens} occurrences",
+    ),
+    (
+        "{path}: {share}% of the text in {quotes} quotes",
+        "{path} : {share} % du texte dans {quotes} citations",
+    ),
+    (
+        "{path}: {total} acronyms and all-caps terms, {distinct} distinct",
+        "{path} : {total} acronymes et termes en majuscules, {distinct} distincts",
+    ),
+    (
+        "{path}: {keywords} {language} keywords ({share}% of {tokens} tokens), {distinct} distinct identifiers",
+        "{path} : {keywords} mots-clés {language} ({share} % de {tokens} symboles), {distinct} identifiants distincts",
+    ),
+    ("Top identifiers:", "Identifiants les plus fréquents :"),
+    (
+        "{path}: numbers with units ({bare} without)",
+        "{path} : nombres avec unité ({bare} sans)",
+    ),
+    ("Unit", "Unité"),
+    ("mean", "moyenne"),
+    ("Dialogue:", "Dialogue :"),
+    ("Narration:", "Narration :"),
+    ("Word", "Mot"),
+    ("Count", "Nombre"),
+    ("Burst", "Rafale"),
+    ("Busiest part", "Partie la plus dense"),
+    ("tokens", "occurrences"),
+    ("Quantiles:", "Quantiles :"),
+    (
+        "{words} hot words cover {share}% of {total} tokens in {files} files",
+        "{words} mots chauds couvrent {share} % des {total} occurrences de {files} fichiers",
+    ),
+    (
+        "tuning on {bytes} bytes, {iterations} iterations per value",
+        "réglage sur {bytes} octets, {iterations} itérations par valeur",
+    ),
+    (
+        "no config location: set TD5_CONFIG or HOME",
+        "aucun emplacement de configuration : définissez TD5_CONFIG ou HOME",
+    ),
+    (
+        "ignoring saved tuning: {message}",
+        "réglages enregistrés ignorés : {message}",
+    ),
+    (
+        "--only-known needs --dictionary",
+        "--only-known exige --dictionary",
+    ),
+    (
+        "reading EPUB needs a build with `--features epub`",
+        "la lecture d'EPUB exige une compilation avec `--features epub`",
+    ),
+    (
+        "reading mbox needs a build with `--features mbox`",
+        "la lecture de mbox exige une compilation avec `--features mbox`",
+    ),
+    (
+        "--plugin needs a build with `--features plugins` on Unix",
+        "--plugin exige une compilation avec `--features plugins` sous Unix",
+    ),
+    (
+        "{name}: plugin word filters cannot be combined with --only-known or --window",
+        "{name} : les filtres de mots des plugins ne peuvent pas être combinés avec --only-known ou --window",
+    ),
+    ("{path}: plugin metrics", "{path} : métriques des plugins"),
+    (
+        "Load a shared library with the td5 plugin ABI: its word filter drops words from the counts, its metrics join each report (repeatable; plugins feature)",
+        "Charge une bibliothèque partagée suivant l'ABI des plugins td5 : son filtre retire des mots des comptes, ses métriques rejoignent chaque rapport (répétable ; fonctionnalité plugins)",
+    ),
+    (
+        "--only-known and --window cannot be combined",
+        "--only-known et --window ne peuvent pas être combinés",
+    ),
+    (
+        "{path}: {count} words not in the dictionary",
+        "{path} : {count} mots absents du dictionnaire",
+    ),
+    ("expected --list <FILE>", "--list <FILE> attendu"),
+    ("expected --out <FILE>", "--out <FILE> attendu"),
+    ("{count} matches", "{count} occurrences"),
+    (
+        "expected --dictionary <WORDLIST>",
+        "--dictionary <WORDLIST> attendu",
+    ),
+    (
+        "{words} unknown words, {share}% of {total} tokens",
+        "{words} mots inconnus, {share} % des {total} mots",
+    ),
+    (
+        "--split-on and --window cannot be combined",
+        "--split-on et --window ne peuvent pas être combinés",
+    ),
+    (
+        "--split-on does not support protobuf output",
+        "--split-on ne prend pas en charge la sortie protobuf",
+    ),
+    (
+        "--aggregate cannot be combined with --sample or --window",
+        "--aggregate ne peut pas être combiné avec --sample ou --window",
+    ),
+    (
+        "--aggregate does not support protobuf output",
+        "--aggregate ne prend pas en charge la sortie protobuf",
+    ),
+    (
+        "--jobs cannot be combined with --{flag}",
+        "--jobs ne peut pas être combiné avec --{flag}",
+    ),
+    (
+        "--jobs needs local files, not `{path}`",
+        "--jobs exige des fichiers locaux, pas `{path}`",
+    ),
+    ("All {count} inputs", "Les {count} entrées"),
+    (
+        "Analyze N files at a time (0 for every core), still reporting them in order; plain text, gzip and EPUB only",
+        "Analyse N fichiers à la fois (0 pour tous les cœurs), en les rapportant toujours dans l'ordre ; texte brut, gzip et EPUB uniquement",
+    ),
+    (
+        "After the per-input reports, report all inputs together, their word counts merged",
+        "Après les rapports par entrée, rapporte toutes les entrées ensemble, leurs comptes de mots fusionnés",
+    ),
+    (
+        "unknown format `{name}` (expected text, csv or json)",
+        "format `{name}` inconnu (attendu : text, csv ou json)",
+    ),
+    (
+        "Also report which specialized paths of the fast analyzer each input takes, how many tokens it counts without hashing, the time of each of its phases, and the read buffer sizes of stdin",
+        "Rapporte aussi les chemins spécialisés de l'analyseur rapide que prend chaque entrée, combien de mots il compte sans hachage, le temps de chacune de ses phases, et les tailles du tampon de lecture de l'entrée standard",
+    ),
+    ("{path}: read buffer", "{path} : tampon de lecture"),
+    (
+        "{bytes} bytes in {reads} reads",
+        "{bytes} octets en {reads} lectures",
+    ),
+    (
+        "{first} bytes at first, {last} at the end ({smallest} to {largest}, {changes} changes)",
+        "{first} octets au début, {last} à la fin (de {smallest} à {largest}, {changes} changements)",
+    ),
+    (
+        "{path}: paths of the fast analyzer",
+        "{path} : chemins de l'analyseur rapide",
+    ),
+    (
+        "{count} simple-path passes counted again as ASCII",
+        "{count} passes du chemin simple recomptées en ASCII",
+    ),
+    (
+        "{hot} tokens in the hot array ({share}%), {map} in the hash map",
+        "{hot} mots dans le tableau chaud ({share} %), {map} dans la table de hachage",
+    ),
+    (
+        "finished from the hot array alone, without a sort",
+        "terminé à partir du seul tableau chaud, sans tri",
+    ),
+    (
+        "unknown format `{name}` (expected html or markdown)",
+        "format `{name}` inconnu (attendu : html ou markdown)",
+    ),
+    (
+        "Write a self-contained HTML or Markdown report of a text: top words, word lengths, coverage and how long each analyzer took",
+        "Écrit un rapport HTML ou Markdown autonome d'un texte : mots les plus fréquents, longueurs des mots, couverture et durée de chaque analyseur",
+    ),
+    (
+        "Write the report to FILE instead of standard output",
+        "Écrit le rapport dans FILE au lieu de la sortie standard",
+    ),
+    (
+        "html or markdown (default: markdown for a .md --out, else html)",
+        "html ou markdown (par défaut : markdown pour un --out en .md, sinon html)",
+    ),
+    (
+        "Comma-separated analyzers to time, the first one the baseline (default slow,fast)",
+        "Analyseurs à chronométrer séparés par des virgules, le premier servant de référence (par défaut slow,fast)",
+    ),
+    (
+        "unknown format `{name}` (expected text or json)",
+        "format `{name}` inconnu (attendu : text ou json)",
+    ),
+    (
+        "expected exactly two files",
+        "exactement deux fichiers attendus",
+    ),
+    ("expected exactly one file", "exactement un fichier attendu"),
+    (
+        "expected exactly one manifest",
+        "exactement un manifeste attendu",
+    ),
+    (
+        "Save a checkpoint to FILE after each input and, if FILE exists, carry on after the inputs it has done (removed once the run completes)",
+        "Enregistre un point de reprise dans FILE après chaque entrée et, si FILE existe, reprend après les entrées qu'il a traitées (supprimé une fois l'exécution terminée)",
+    ),
+    (
+        "Also write each input as UTF-8 without a BOM under DIR, at its path from the manifest's directory",
+        "Écrit aussi chaque entrée en UTF-8 sans BOM sous DIR, à son chemin depuis le dossier du manifeste",
+    ),
+    (
+        "{path}: resuming after {done} of {total} inputs",
+        "{path} : reprise après {done} entrées sur {total}",
+    ),
+    (
+        "When an input cannot be read: abort, continue (default; exit status 4 if any failed) or ignore (exit status 0 unless all failed)",
+        "Quand une entrée est illisible : abort (arrêter), continue (par défaut ; code de sortie 4 si l'une a échoué) ou ignore (code 0 sauf si toutes ont échoué)",
+    ),
+    (
+        "Read only a deterministic share of each file, such as 10%, and scale the counts up as estimates",
+        "Ne lit qu'une part déterministe de chaque fichier, par exemple 10 %, et extrapole les comptes en estimations",
+    ),
+    (
+        "--sample cannot be combined with --split-on or --window",
+        "--sample ne peut pas être combiné avec --split-on ou --window",
+    ),
+    (
+        "{path}: estimated from a {share}% sample ({read} of {total} bytes read, {seed})",
+        "{path} : estimation d'après un échantillon de {share} % ({read} octets lus sur {total}, {seed})",
+    ),
+    (
+        "counts scaled by {scale}; unique words are those seen in the sample",
+        "comptes multipliés par {scale} ; les mots distincts sont ceux de l'échantillon",
+    ),
+    (
+        "Analyze only the lines starting between these byte offsets of each file, such as 1GB..2GB",
+        "N'analyse que les lignes de chaque fichier commençant entre ces positions en octets, par exemple 1GB..2GB",
+    ),
+    (
+        "Analyze only these lines of each file, counted from 1, such as 1000..2000",
+        "N'analyse que ces lignes de chaque fichier, comptées à partir de 1, par exemple 1000..2000",
+    ),
+    (
+        "--range and --lines cannot be combined",
+        "--range et --lines ne peuvent pas être combinés",
+    ),
+    (
+        "--sample cannot be combined with --range or --lines",
+        "--sample ne peut pas être combiné avec --range ou --lines",
+    ),
+    (
+        "{path}: bytes {start}..{end} of {total}",
+        "{path} : octets {start}..{end} sur {total}",
+    ),
+    (
+        "Analyze only the first N words of each input, without reading the rest",
+        "N'analyse que les N premiers mots de chaque entrée, sans lire la suite",
+    ),
+    (
+        "Analyze only the last N words of each input, reading files backwards from the end",
+        "N'analyse que les N derniers mots de chaque entrée, en lisant les fichiers à rebours depuis la fin",
+    ),
+    (
+        "--first-n-words and --last-n-words cannot be combined",
+        "--first-n-words et --last-n-words ne peuvent pas être combinés",
+    ),
+    (
+        "--first-n-words and --last-n-words cannot be combined with --sample, --range or --lines",
+        "--first-n-words et --last-n-words ne peuvent pas être combinés avec --sample, --range ou --lines",
+    ),
+    (
+        "Instead, check that the fast path makes no heap allocation per word on hot-only input",
+        "Vérifie plutôt que le chemin rapide ne fait aucune allocation par mot sur une entrée de mots fréquents",
+    ),
+    (
+        "{case}, {words} words: {counting} allocations counting, {analyze} in all",
+        "{case}, {words} mots : {counting} allocations au comptage, {analyze} au total",
+    ),
+    (
+        "the hot path allocates nothing per word",
+        "le chemin rapide n'alloue rien par mot",
+    ),
+    (
+        "Instead of timing, count instructions and simulated cache misses under valgrind",
+        "Au lieu de chronométrer, compte les instructions et les défauts de cache simulés sous valgrind",
+    ),
+    (
+        "Also estimate the energy of each run from the RAPL counters (needs the rapl feature on Linux, usually root)",
+        "Estime aussi l'énergie de chaque exécution d'après les compteurs RAPL (exige la fonctionnalité rapl sous Linux, en général root)",
+    ),
+    (
+        "--energy needs a build with `--features rapl` on Linux",
+        "--energy exige une compilation avec `--features rapl` sous Linux",
+    ),
+    (
+        "--energy cannot be combined with --cachegrind or --cache-sweep",
+        "--energy ne peut pas être combiné avec --cachegrind ou --cache-sweep",
+    ),
+    (
+        "reading the energy of {packages} CPU packages",
+        "lecture de l'énergie de {packages} processeurs",
+    ),
+    (
+        "Run one analyzer once on FILE and print nothing, for profilers (used by `bench --cachegrind`)",
+        "Exécute une fois un analyseur sur FICHIER sans rien afficher, pour les profileurs (utilisé par `bench --cachegrind`)",
+    ),
+    (
+        "Analyzer to run (default fast)",
+        "Analyseur à exécuter (par défaut fast)",
+    ),
+    (
+        "Do everything but the analysis, to measure the overhead",
+        "Fait tout sauf l'analyse, pour mesurer le surcoût",
+    ),
+    (
+        "--cachegrind cannot be combined with --against, --save, --record, --template or --baseline",
+        "--cachegrind ne se combine pas avec --against, --save, --record, --template ou --baseline",
+    ),
+    (
+        "running each cell twice under valgrind",
+        "chaque cellule est exécutée deux fois sous valgrind",
+    ),
+    ("input", "entrée"),
+    ("instructions", "instructions"),
+    ("data accesses", "accès mémoire"),
+    ("L1 misses", "défauts L1"),
+    ("LL misses", "défauts LL"),
+    (
+        "Instead, time words per vocabulary size to show the cache cliffs of counting (default analyzer: fast)",
+        "Chronomètre plutôt le temps par mot selon la taille du vocabulaire, pour montrer les paliers de cache du comptage (analyseur par défaut : fast)",
+    ),
+    (
+        "Vocabulary sizes for --cache-sweep, e.g. 1K,64K,1M (default 1K to 1M by 4)",
+        "Tailles de vocabulaire pour --cache-sweep, p. ex. 1K,64K,1M (par défaut de 1K à 1M par 4)",
+    ),
+    (
+        "--cache-sweep cannot be combined with --against, --save, --record, --template, --baseline or --cachegrind",
+        "--cache-sweep ne se combine pas avec --against, --save, --record, --template, --baseline ou --cachegrind",
+    ),
+    (
+        "expected a vocabulary size such as 64K, got `{value}`",
+        "taille de vocabulaire attendue, comme 64K, reçu `{value}`",
+    ),
+    ("cache sizes: unknown", "tailles de cache : inconnues"),
+    ("cache sizes: {sizes}", "tailles de cache : {sizes}"),
+    ("vocabulary", "vocabulaire"),
+    ("table", "table"),
+    ("fits in", "tient en"),
+    ("RAM", "RAM"),
+    (
+        "Run two backends or option sets on FILE and report how well their top words agree (Kendall tau)",
+        "Exécute deux moteurs ou jeux d'options sur FICHIER et mesure l'accord de leurs mots les plus fréquents (tau de Kendall)",
+    ),
+    (
+        "First side: an analyzer, optionally with :sample=SHARE and seed=N (default slow)",
+        "Premier côté : un analyseur, éventuellement avec :sample=PART et seed=N (par défaut slow)",
+    ),
+    (
+        "Second side, same syntax (default fast)",
+        "Second côté, même syntaxe (par défaut fast)",
+    ),
+    (
+        "Top words compared, at most 10 (default 10)",
+        "Mots les plus fréquents comparés, 10 au plus (par défaut 10)",
+    ),
+    (
+        "unknown option `{option}` in `{spec}` (expected sample=SHARE or seed=N)",
+        "option `{option}` inconnue dans `{spec}` (attendu sample=PART ou seed=N)",
+    ),
+    (
+        "expected a seed such as 7, got `{value}`",
+        "graine attendue, comme 7, reçu `{value}`",
+    ),
+    (
+        "`{spec}`: seed=N needs sample=SHARE",
+        "`{spec}` : seed=N exige sample=PART",
+    ),
+    (
+        "{spec}: {share}% sample, {seed}",
+        "{spec} : échantillon de {share} %, {seed}",
+    ),
+    (
+        "Kendall tau-b over the top {top}: {tau} ({shared} words in both lists)",
+        "Tau-b de Kendall sur les {top} premiers : {tau} ({shared} mots dans les deux listes)",
+    ),
+    ("word", "mot"),
+    (
+        "--words, --seed, --vocabulary and --source only apply to generated text",
+        "--words, --seed, --vocabulary et --source ne s'appliquent qu'au texte généré",
+    ),
+    (
+        "--vocabulary only applies to the prose source",
+        "--vocabulary ne s'applique qu'à la source prose",
+    ),
+    ("expected at most one file", "au plus un fichier attendu"),
+    (
+        "Worker threads of the parallel or sharded analyzer (0 for every core; implies --analyzer parallel)",
+        "Threads de travail de l'analyseur parallel ou sharded (0 pour tous les cœurs ; implique --analyzer parallel)",
+    ),
+    (
+        "Show a progress bar on stderr while each input is counted (implies --analyzer parallel; only on a terminal)",
+        "Affiche une barre de progression sur stderr pendant le comptage de chaque entrée (implique --analyzer parallel ; seulement dans un terminal)",
+    ),
+    (
+        "Also list the most frequent word pairs (2) or pairs and triples (3)",
+        "Liste aussi les paires (2) ou les paires et triplets (3) de mots les plus fréquents",
+    ),
+    (
+        "--ngrams takes 2 or 3, not {n}",
+        "--ngrams prend 2 ou 3, pas {n}",
+    ),
+    (
+        "--threads needs a multi-threaded analyzer (parallel or sharded), not `{name}`",
+        "--threads demande un analyseur multi-thread (parallel ou sharded), pas `{name}`",
+    ),
+    (
+        "--progress needs an analyzer that reports progress (fast, slow, parallel or sharded), not `{name}`",
+        "--progress demande un analyseur qui rend compte de sa progression (fast, slow, parallel ou sharded), pas `{name}`",
+    ),
+    (
+        "{done} of {total} MB, {words} words",
+        "{done} sur {total} Mo, {words} mots",
+    ),
+    (
+        "Pack the top words of files into a URL-safe payload for a share link, or read one back",
+        "Condenser les mots les plus fréquents de fichiers en une charge utile sûre pour une URL de partage, ou la relire",
+    ),
+    (
+        "Words to include, most frequent first (default 20)",
+        "Mots à inclure, les plus fréquents d'abord (20 par défaut)",
+    ),
+    (
+        "Print a link: URL, then `#` and the payload",
+        "Afficher un lien : URL, puis `#` et la charge utile",
+    ),
+    (
+        "Print a payload or share link as JSON instead",
+        "Afficher plutôt une charge utile ou un lien de partage en JSON",
+    ),
+    ("--decode takes no file", "--decode ne prend aucun fichier"),
+    (
+        "Also write a self-contained HTML page with charts of the analysis (one input only)",
+        "Écrire aussi une page HTML autonome avec des graphiques de l'analyse (une seule entrée)",
+    ),
+    (
+        "--report needs exactly one input",
+        "--report demande exactement une entrée",
+    ),
+    (
+        "--report and --window cannot be combined",
+        "--report et --window ne peuvent pas être combinés",
+    ),
+    ("report written to {path}", "rapport écrit dans {path}"),
+    (
+        "--split-on does not support csv output",
+        "--split-on ne prend pas en charge la sortie csv",
+    ),
+    (
+        "Run the analysis on a thread with a stack of BYTES (used by `selftest --stack`)",
+        "Lance l'analyse sur un thread avec une pile de BYTES octets (utilisé par `selftest --stack`)",
+    ),
+    (
+        "Instead, measure the stack every analyzer needs and check that it stays small and bounded",
+        "Mesure plutôt la pile nécessaire à chaque analyseur et vérifie qu'elle reste petite et bornée",
+    ),
+    (
+        "Instead, compare the fast path with the reference on random ASCII and Unicode texts",
+        "Compare plutôt le chemin rapide à la référence sur des textes ASCII et Unicode aléatoires",
+    ),
+    (
+        "With --differential, the number of random texts (default 10000)",
+        "Avec --differential, le nombre de textes aléatoires (10000 par défaut)",
+    ),
+    (
+        "With --differential, the seed of the first text (default 0)",
+        "Avec --differential, la graine du premier texte (0 par défaut)",
+    ),
+    (
+        "{cases} random texts from seed {seed}: the fast path agrees with the reference",
+        "{cases} textes aléatoires depuis la graine {seed} : le chemin rapide concorde avec la référence",
+    ),
+    (
+        "seed {seed} diverges, shrunk to {text}",
+        "la graine {seed} diverge, réduite à {text}",
+    ),
+    (
+        "seed {seed}, seed version {version}",
+        "graine {seed}, version de graine {version}",
+    ),
+    ("Generated inputs: {seed}", "Entrées générées : {seed}"),
+    ("MinHash functions: {seed}", "Fonctions MinHash : {seed}"),
+    ("Surface forms:", "Formes de surface :"),
+    ("Language:", "Langue :"),
+    (
+        "Detect each input's language and use its stopwords and stemmer (needs the language-detection feature)",
+        "Détecte la langue de chaque entrée et utilise ses mots vides et sa racinisation (exige la fonctionnalité language-detection)",
+    ),
+    (
+        "What becomes of control characters such as NUL: strip (default) drops them, separate cuts words at them, error fails the input",
+        "Traitement des caractères de contrôle comme NUL : strip (par défaut) les supprime, separate coupe les mots à leur place, error fait échouer l'entrée",
+    ),
+    (
+        "What a word is: ascii (letter runs), unicode (whitespace-separated tokens) or regex:PATTERN, e.g. 'regex:#?\\w+' for hashtags apart",
+        "Ce qu'est un mot : ascii (suites de lettres), unicode (jetons séparés par des blancs) ou regex:PATTERN, p. ex. 'regex:#?\\w+' pour distinguer les hashtags",
+    ),
+    (
+        "Put words in a Unicode normalization form before counting them: nfc (café is one word however its accent is written) or nfkc (also ligatures and full-width letters)",
+        "Met les mots dans une forme de normalisation Unicode avant de les compter : nfc (café est un seul mot quelle que soit l'écriture de son accent) ou nfkc (aussi les ligatures et les lettres pleine chasse)",
+    ),
+    (
+        "Drop accents and other diacritics before counting, so café, cafe and CAFÉ are one word",
+        "Supprime les accents et autres diacritiques avant de compter, pour que café, cafe et CAFÉ soient un seul mot",
+    ),
+    (
+        "What serialized output keeps of time_ns: exact (default), omit, or a width in milliseconds to round it to, for golden files",
+        "Ce que la sortie sérialisée garde de time_ns : exact (par défaut), omit, ou une largeur en millisecondes à laquelle l'arrondir, pour les fichiers de référence",
+    ),
+    (
+        "--auto needs a build with `--features language-detection`",
+        "--auto exige une compilation avec `--features language-detection`",
+    ),
+    (
+        "--auto cannot be combined with --stopwords or --stem",
+        "--auto ne peut pas être combiné avec --stopwords ou --stem",
+    ),
+    (
+        "--auto cannot be combined with --only-known, --window or a word pipeline",
+        "--auto ne peut pas être combiné avec --only-known, --window ou un pipeline de mots",
+    ),
+    (
+        "--auto needs an analyzer that takes options (fast, slow, parallel or sharded), not `{name}`",
+        "--auto exige un analyseur qui accepte des options (fast, slow, parallel ou sharded), pas `{name}`",
+    ),
+    (
+        "unknown stemming language `{name}` (expected en)",
+        "langue de racinisation `{name}` inconnue (attendu en)",
+    ),
+    (
+        "--stem needs a build with `--features stemming`",
+        "--stem exige une compilation avec `--features stemming`",
+    ),
+    (
+        "Rank the top words by stem, with the most frequent form of each: en (needs the stemming feature)",
+        "Classe les mots les plus fréquents par racine, avec la forme la plus fréquente de chacune : en (exige la fonctionnalité stemming)",
+    ),
+    ("--seed needs --sample", "--seed exige --sample"),
+    (
+        "--seed needs generated text, not --file",
+        "--seed exige un texte généré, pas --file",
+    ),
+    (
+        "With --sample, the seed picking the blocks read (default 0)",
+        "Avec --sample, la graine qui choisit les blocs lus (0 par défaut)",
+    ),
+    (
+        "Seed of the generated text (default 0)",
+        "Graine du texte généré (0 par défaut)",
+    ),
+    (
+        "Seed of the MinHash functions (default 0)",
+        "Graine des fonctions MinHash (0 par défaut)",
+    ),
+    ("the analysis panicked", "l'analyse a paniqué"),
+    ("at most {kib} KiB", "au plus {kib} Kio"),
+    ("{kib} KiB", "{kib} Kio"),
+    ("overflow", "débordement"),
+    (
+        "{analyzer} on {case}, {size}: {needed} of stack",
+        "{analyzer} sur {case}, {size} : {needed} de pile",
+    ),
+    (
+        "every analyzer fits in {kib} KiB of stack",
+        "chaque analyseur tient dans {kib} Kio de pile",
+    ),
+    (
+        "Send snapshots to -, file:PATH, rotate:PATH:MAX_BYTES[:KEEP] or an http(s) webhook instead of stdout (repeatable)",
+        "Envoie les instantanés vers -, file:CHEMIN, rotate:CHEMIN:OCTETS_MAX[:GARDER] ou un webhook http(s) au lieu de la sortie standard (répétable)",
+    ),
+    (
+        "Also append each snapshot to PATH as a checksummed binary frame, or write frames alone to stdout with - (frames feature)",
+        "Ajoute aussi chaque instantané à CHEMIN sous forme de trame binaire avec somme de contrôle, ou écrit les trames seules sur la sortie standard avec - (fonctionnalité frames)",
+    ),
+    (
+        "--frames needs a build with `--features frames`",
+        "--frames exige une compilation avec `--features frames`",
+    ),
+    ("output {spec}: {message}", "sortie {spec} : {message}"),
+    (
+        "Alert when e.g. 'unique_words>5000' starts or stops holding, checked at each snapshot (repeatable)",
+        "Alerte quand par exemple 'unique_words>5000' devient vrai ou cesse de l'être, vérifié à chaque instantané (répétable)",
+    ),
+    (
+        "Alert on every message matching this deny list",
+        "Alerte sur chaque message qui correspond à cette liste d'exclusion",
+    ),
+    (
+        "Send alerts to an http(s) webhook, file:PATH, rotate:… or - (repeatable, default stdout)",
+        "Envoie les alertes vers un webhook http(s), file:CHEMIN, rotate:… ou - (répétable, sortie standard par défaut)",
+    ),
+    (
+        "Template for alert payloads, e.g. {\"text\": \"{{alert}} on {{subject}}\"}",
+        "Modèle du contenu des alertes, par exemple {\"text\": \"{{alert}} sur {{subject}}\"}",
+    ),
+    (
+        "unknown error policy `{name}` (expected abort, continue or ignore)",
+        "politique d'erreur `{name}` inconnue (attendu abort, continue ou ignore)",
+    ),
+    (
+        "unknown time policy `{name}` (expected exact, omit or a bucket width in milliseconds)",
+        "politique de temps `{name}` inconnue (attendu exact, omit ou une largeur d'intervalle en millisecondes)",
+    ),
+    (
+        "unknown control character policy `{name}` (expected strip, separate or error)",
+        "politique de caractères de contrôle `{name}` inconnue (attendu strip, separate ou error)",
+    ),
+    (
+        "unknown normalization form `{name}` (expected nfc or nfkc)",
+        "forme de normalisation `{name}` inconnue (attendu nfc ou nfkc)",
+    ),
+    (
+        "{failed} of {total} inputs failed:",
+        "{failed} entrées sur {total} en échec :",
+    ),
+    ("Combined", "Ensemble"),
+    ("Encoding:", "Encodage :"),
+    ("{encoding} with a BOM", "{encoding} avec BOM"),
+    (
+        "{count} of {total} inputs transcoded to UTF-8",
+        "{count} entrées sur {total} transcodées en UTF-8",
+    ),
+    (
+        "{path}: written as UTF-8 to {target}",
+        "{path} : écrit en UTF-8 dans {target}",
+    ),
+    (
+        "Analyze the inputs listed in a JSON or TOML manifest, each with its own analyzer, language and stopwords, plus a combined total",
+        "Analyse les entrées listées dans un manifeste JSON ou TOML, chacune avec son analyseur, sa langue et ses mots vides, plus un total d'ensemble",
+    ),
+    (
+        "expected at least two snapshots",
+        "au moins deux instantanés attendus",
+    ),
+    (
+        "Rising (log2 change, then per million words):",
+        "En hausse (variation log2, puis par million de mots) :",
+    ),
+    (
+        "Falling (log2 change, then per million words):",
+        "En baisse (variation log2, puis par million de mots) :",
+    ),
+    (
+        "{words} words in {sentences} sentences: {length} words per sentence, grade {grade}, reading ease {ease}",
+        "{words} mots en {sentences} phrases : {length} mots par phrase, niveau {grade}, facilité de lecture {ease}",
+    ),
+    ("Lines", "Lignes"),
+    ("Words/s.", "Mots/ph."),
+    ("Grade", "Niveau"),
+    ("Ease", "Facilité"),
+    ("Hardest passages:", "Passages les plus difficiles :"),
+    (
+        "lines {first}-{last}: grade {grade}, {length} words per sentence",
+        "lignes {first}-{last} : niveau {grade}, {length} mots par phrase",
+    ),
+    (
+        "Jaccard similarity: {jaccard} ({shared} shared of {a} and {b} distinct words)",
+        "Similarité de Jaccard : {jaccard} ({shared} mots communs sur {a} et {b} mots distincts)",
+    ),
+    ("Only in {path}:", "Seulement dans {path} :"),
+    ("Shared top terms:", "Termes fréquents communs :"),
+    (
+        "unknown format `{name}` (expected text, json, dot or graphml)",
+        "format `{name}` inconnu (attendu : text, json, dot ou graphml)",
+    ),
+    (
+        "{words} words, {links} links",
+        "{words} mots, {links} liens",
+    ),
+    (
+        "{count} lines before the first timestamp skipped",
+        "{count} lignes avant le premier horodatage ignorées",
+    ),
+    (
+        "unknown format `{name}` (expected text, json, yaml, csv, protobuf, msgpack or cbor)",
+        "format `{name}` inconnu (attendu : text, json, yaml, csv, protobuf, msgpack ou cbor)",
+    ),
+    (
+        "--format {name} needs a build with `--features {name}`",
+        "--format {name} nécessite une compilation avec `--features {name}`",
+    ),
+    (
+        "{iterations} iterations per cell",
+        "{iterations} itérations par cellule",
+    ),
+    ("saved {path}", "{path} enregistré"),
+    (
+        "recorded {path}; reproduce with `td5 replay {path}`",
+        "{path} enregistré ; rejouez-le avec `td5 replay {path}`",
+    ),
+    (
+        "baseline was recorded in a different environment: {difference}",
+        "la référence a été mesurée dans un autre environnement : {difference}",
+    ),
+    (
+        "{path}: decoded as {encoding}",
+        "{path} : décodé en {encoding}",
+    ),
+    (
+        "{path}: decompressed {compression} ({bytes} bytes)",
+        "{path} : décompressé ({compression}, {bytes} octets)",
+    ),
+    (
+        "{path}: {count} undecodable sequences replaced",
+        "{path} : {count} séquences indécodables remplacées",
+    ),
+    (
+        "{path}: {count} over-long tokens",
+        "{path} : {count} mots trop longs",
+    ),
+    (
+        "{path}: {count} control characters",
+        "{path} : {count} caractères de contrôle",
+    ),
+    (
+        "With --baseline, exit with status 3 if a cell's median got more than PERCENT slower, significantly",
+        "Avec --baseline, sort avec le code 3 si la médiane d'une cellule a ralenti de plus de PERCENT %, de façon significative",
+    ),
+    (
+        "--max-regression needs --baseline",
+        "--max-regression nécessite --baseline",
+    ),
+    (
+        "regression: {input} / {analyzer} is {change}% slower than in {path} (p = {p})",
+        "régression : {input} / {analyzer} est {change} % plus lent que dans {path} (p = {p})",
+    ),
+    (
+        "assertion failed: {path}: {assertion} (actual {actual})",
+        "assertion non vérifiée : {path} : {assertion} (valeur {actual})",
+    ),
+    ("{count} findings", "{count} signalements"),
+    (
+        "expected at least one file",
+        "au moins un fichier est attendu",
+    ),
+    (
+        "expected exactly one bundle",
+        "exactement un paquet est attendu",
+    ),
+    (
+        "expected --nats <ADDRESS> and --subject <SUBJECT>, or --kafka <ADDRESS> and --topic <TOPIC>",
+        "--nats <ADRESSE> et --subject <SUJET>, ou --kafka <ADRESSE> et --topic <SUJET>, sont attendus",
+    ),
+    (
+        "--nats needs a build with `--features nats`",
+        "--nats exige une compilation avec `--features nats`",
+    ),
+    (
+        "--kafka needs a build with `--features kafka`",
+        "--kafka exige une compilation avec `--features kafka`",
+    ),
+    (
+        "subscribed to {subject} on {address}",
+        "abonné à {subject} sur {address}",
+    ),
+    ("connection closed", "connexion fermée"),
+    (
+        "td5 was built without the `nats` and `kafka` features",
+        "td5 a été compilé sans les fonctionnalités `nats` et `kafka`",
+    ),
+    (
+        "Word stats per author of a git repository's documentation, from git blame (git feature)",
+        "Statistiques de mots par auteur de la documentation d'un dépôt git, d'après git blame (fonctionnalité git)",
+    ),
+    (
+        "Comma-separated extensions of the files to read (default md,markdown,txt,rst,adoc)",
+        "Extensions des fichiers à lire, séparées par des virgules (md,markdown,txt,rst,adoc par défaut)",
+    ),
+    (
+        "Top words shown per author (default 5)",
+        "Mots les plus fréquents affichés par auteur (5 par défaut)",
+    ),
+    ("expected exactly one repository", "un seul dépôt attendu"),
+    (
+        "{files} files read, {skipped} skipped (not tracked by git?)",
+        "{files} fichiers lus, {skipped} ignorés (non suivis par git ?)",
+    ),
+    ("Author", "Auteur"),
+    ("Words", "Mots"),
+    ("Unique", "Distincts"),
+    ("Files", "Fichiers"),
+    (
+        "td5 was built without the `git` feature",
+        "td5 a été compilé sans la fonctionnalité `git`",
+    ),
+    (
+        "Stream a MediaWiki XML dump and analyze the article text of its pages (- for stdin)",
+        "Lit un dump XML MediaWiki en flux et analyse le texte des articles de ses pages (- pour l'entrée standard)",
+    ),
+    (
+        "Worker threads counting pages (0 for every core, the default)",
+        "Threads qui comptent les pages (0 pour tous les cœurs, par défaut)",
+    ),
+    (
+        "Comma-separated namespaces to read, or `all` (default 0, articles)",
+        "Espaces de noms à lire, séparés par des virgules, ou `all` (0 par défaut, les articles)",
+    ),
+    (
+        "Count redirect pages too",
+        "Compte aussi les pages de redirection",
+    ),
+    ("Stop after N pages", "S'arrête après N pages"),
+    (
+        "Also print the words of each page as it is counted",
+        "Affiche aussi les mots de chaque page dès qu'elle est comptée",
+    ),
+    ("expected exactly one dump", "un seul dump attendu"),
+    (
+        "unknown namespace `{name}`",
+        "espace de noms `{name}` inconnu",
+    ),
+    (
+        "{pages} pages read, {skipped} skipped ({bytes} bytes of XML)",
+        "{pages} pages lues, {skipped} ignorées ({bytes} octets de XML)",
+    ),
+    ("{path} ({pages} pages)", "{path} ({pages} pages)"),
+    (
+        "Keep stats over messages from a NATS subject or Kafka topic and print snapshots (nats or kafka feature)",
+        "Tient des statistiques sur les messages d'un sujet NATS ou Kafka et en affiche des instantanés (fonctionnalité nats ou kafka)",
+    ),
+    (
+        "NATS server, e.g. 127.0.0.1:4222",
+        "Serveur NATS, par ex. 127.0.0.1:4222",
+    ),
+    (
+        "Subject to subscribe to; `*` and `>` wildcards work",
+        "Sujet auquel s'abonner ; les jokers `*` et `>` sont acceptés",
+    ),
+    (
+        "Kafka broker, e.g. 127.0.0.1:9092",
+        "Broker Kafka, par ex. 127.0.0.1:9092",
+    ),
+    ("Kafka topic to read", "Sujet Kafka à lire"),
+    (
+        "Partition of the topic to read (default 0)",
+        "Partition du sujet à lire (0 par défaut)",
+    ),
+    (
+        "Read the partition from its first record instead of its end",
+        "Lit la partition depuis son premier enregistrement plutôt que depuis sa fin",
+    ),
+    (
+        "Print a JSON snapshot this often (default 10)",
+        "Affiche un instantané JSON à cet intervalle (par défaut 10)",
+    ),
+    (
+        "replaying {inputs} ({iterations} iterations per cell)",
+        "rejeu de {inputs} ({iterations} itérations par cellule)",
+    ),
+    (
+        "Serve the Analyzer gRPC service of proto/td5.proto over cleartext HTTP/2 (grpc feature)",
+        "Sert le service gRPC Analyzer de proto/td5.proto en HTTP/2 en clair (fonctionnalité grpc)",
+    ),
+    (
+        "Address to listen on (default 127.0.0.1:50051)",
+        "Adresse d'écoute (127.0.0.1:50051 par défaut)",
+    ),
+    (
+        "Largest request message, e.g. 64K or 16M (default 4M)",
+        "Plus grand message de requête, par ex. 64K ou 16M (4M par défaut)",
+    ),
+    (
+        "Connections served at once (default 256)",
+        "Connexions servies à la fois (256 par défaut)",
+    ),
+    (
+        "Calls open at once per connection (default 100)",
+        "Appels ouverts à la fois par connexion (100 par défaut)",
+    ),
+    (
+        "Longest a call may take before DEADLINE_EXCEEDED (default 30)",
+        "Durée maximale d'un appel avant DEADLINE_EXCEEDED (30 par défaut)",
+    ),
+    (
+        "Close connections with no call open for this long (default 60)",
+        "Ferme les connexions restées sans appel ouvert aussi longtemps (60 par défaut)",
+    ),
+    ("listening on {address}", "en écoute sur {address}"),
+    (
+        "shutting down once the calls in progress are answered",
+        "arrêt une fois les appels en cours traités",
+    ),
+    (
+        "td5 was built without the `grpc` feature",
+        "td5 a été compilé sans la fonctionnalité `grpc`",
+    ),
+];
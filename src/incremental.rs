@@ -0,0 +1,100 @@
+//! Word counts kept up to date through small edits, for editor integrations
+//! that want live stats on every keystroke.
+//!
+//! Both fast-path scanners cut tokens at whitespace, so an edit only
+//! re-tokenizes the whitespace-delimited stretch around it: its words are
+//! uncounted before the change and counted again after. The exception is an
+//! edit that makes an ASCII document non-ASCII or the other way round: the
+//! fast path then switches scanners, which split punctuation differently,
+//! and the whole document is counted again.
+
+use crate::AnalyzerConfig;
+use crate::TextStats;
+use crate::error::{Result, Td5Error};
+use crate::fast::{self, Mode};
+use crate::freq::WordFrequency;
+use std::ops::Range;
+
+#[derive(Debug, Clone)]
+pub struct AnalyzedText {
+    text: String,
+    freq: WordFrequency,
+    /// Non-ASCII bytes in `text`, which decide the scanner.
+    non_ascii: usize,
+}
+
+fn non_ascii(text: &str) -> usize {
+    text.bytes().filter(|b| !b.is_ascii()).count()
+}
+
+impl AnalyzedText {
+    pub fn new(text: impl Into<String>) -> AnalyzedText {
+        let text = text.into();
+        AnalyzedText {
+            freq: WordFrequency::from_text(&text),
+            non_ascii: non_ascii(&text),
+            text,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Counts equal to `WordFrequency::from_text(self.text())`.
+    pub fn freq(&self) -> &WordFrequency {
+        &self.freq
+    }
+
+    /// `freq().to_stats()`, with the lines and sentences of the text, which
+    /// take a pass over it.
+    pub fn stats(&self) -> TextStats {
+        let shape = fast::shape(&self.text, &AnalyzerConfig::default());
+        self.freq.to_stats_with(Some(&shape))
+    }
+
+    /// Replaces the bytes in `range` with `replacement`, like
+    /// `String::replace_range`, and updates the counts. Fails if `range` is
+    /// out of bounds or not on character boundaries.
+    pub fn apply_edit(&mut self, range: Range<usize>, replacement: &str) -> Result<()> {
+        let Range { start, end } = range;
+        if start > end || !self.text.is_char_boundary(start) || !self.text.is_char_boundary(end) {
+            return Err(Td5Error::config(format!(
+                "edit range {start}..{end} is not within the text's {} bytes on character boundaries",
+                self.text.len()
+            )));
+        }
+        let was_ascii = self.non_ascii == 0;
+        self.non_ascii =
+            self.non_ascii - non_ascii(&self.text[start..end]) + non_ascii(replacement);
+        if was_ascii != (self.non_ascii == 0) {
+            self.text.replace_range(start..end, replacement);
+            self.freq = WordFrequency::from_text(&self.text);
+            return Ok(());
+        }
+
+        // Widen to the surrounding whitespace; the characters there stay put.
+        let from = self.text[..start]
+            .char_indices()
+            .rfind(|&(_, c)| c.is_whitespace())
+            .map_or(0, |(i, c)| i + c.len_utf8());
+        let to = self.text[end..]
+            .find(char::is_whitespace)
+            .map_or(self.text.len(), |i| end + i);
+        // Counting ASCII text as `Mode::Ascii` is what `count_lenient` falls
+        // back to for the simple path anyway; both tokenize it the same way.
+        let mode = if self.non_ascii == 0 {
+            Mode::Ascii
+        } else {
+            Mode::Unicode
+        };
+        let count = |text: &str| {
+            WordFrequency::from_counts(fast::count_lenient(text, mode, &AnalyzerConfig::default()))
+        };
+        self.freq.subtract(count(&self.text[from..to]));
+        self.text.replace_range(start..end, replacement);
+        let to = to - (end - start) + replacement.len();
+        self.freq.merge(count(&self.text[from..to]));
+        Ok(())
+    }
+}
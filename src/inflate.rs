@@ -0,0 +1,481 @@
+//! DEFLATE decompression (RFC 1951), enough to read ZIP entries and gzip
+//! files without a compression crate. A straightforward canonical-Huffman
+//! decoder after zlib's `puff`: correct rather than fast. `Inflater` also
+//! decodes as it is read, keeping only the 32 KiB window a stream can refer
+//! back to.
+
+use crate::checksum::crc32_update;
+use std::io::{self, Read};
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// Order in which code length code lengths are stored.
+const CODE_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Bytes of output a stream may refer back to.
+const WINDOW: usize = 32 << 10;
+
+/// Output decoded per step when reading, so a `read` returns long before a
+/// large block is done.
+const STEP: usize = 32 << 10;
+
+struct Bits<R> {
+    inner: R,
+    buf: Box<[u8]>,
+    /// Unread bytes of `buf`.
+    start: usize,
+    end: usize,
+    /// Bits of the current byte not yet read, lowest first.
+    byte: u32,
+    left: u8,
+    /// What `inner` failed with, reported by `Inflater::read` in place of
+    /// the `READ_ERROR` the decoder saw.
+    error: Option<io::Error>,
+}
+
+const READ_ERROR: &str = "read error";
+
+impl<R: Read> Bits<R> {
+    fn new(inner: R) -> Self {
+        Bits {
+            inner,
+            buf: vec![0; 8 << 10].into_boxed_slice(),
+            start: 0,
+            end: 0,
+            byte: 0,
+            left: 0,
+            error: None,
+        }
+    }
+
+    /// The next whole byte, or `None` at the end of the input.
+    fn next_byte(&mut self) -> Result<Option<u8>, &'static str> {
+        if self.start == self.end {
+            loop {
+                match self.inner.read(&mut self.buf) {
+                    Ok(0) => return Ok(None),
+                    Ok(n) => {
+                        (self.start, self.end) = (0, n);
+                        break;
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(e) => {
+                        self.error = Some(e);
+                        return Err(READ_ERROR);
+                    }
+                }
+            }
+        }
+        self.start += 1;
+        Ok(Some(self.buf[self.start - 1]))
+    }
+
+    fn byte(&mut self) -> Result<u8, &'static str> {
+        self.next_byte()?.ok_or("truncated deflate stream")
+    }
+
+    fn bit(&mut self) -> Result<u32, &'static str> {
+        if self.left == 0 {
+            self.byte = u32::from(self.byte()?);
+            self.left = 8;
+        }
+        let bit = self.byte & 1;
+        self.byte >>= 1;
+        self.left -= 1;
+        Ok(bit)
+    }
+
+    fn bits(&mut self, n: u8) -> Result<u32, &'static str> {
+        let mut value = 0;
+        for i in 0..n {
+            value |= self.bit()? << i;
+        }
+        Ok(value)
+    }
+
+    /// Drops the rest of the current byte.
+    fn align(&mut self) {
+        self.left = 0;
+    }
+
+    fn u16_le(&mut self) -> Result<u16, &'static str> {
+        Ok(u16::from_le_bytes([self.byte()?, self.byte()?]))
+    }
+}
+
+struct Huffman {
+    /// Codes of each length.
+    counts: [u16; 16],
+    /// Symbols ordered by code.
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Huffman {
+        let mut counts = [0u16; 16];
+        for &len in lengths {
+            counts[usize::from(len)] += 1;
+        }
+        counts[0] = 0;
+        let mut offsets = [0u16; 16];
+        for len in 1..15 {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[usize::from(offsets[usize::from(len)])] = symbol as u16;
+                offsets[usize::from(len)] += 1;
+            }
+        }
+        Huffman { counts, symbols }
+    }
+
+    fn decode<R: Read>(&self, bits: &mut Bits<R>) -> Result<u16, &'static str> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..16 {
+            code |= bits.bit()? as i32;
+            let count = i32::from(self.counts[len]);
+            if code - count < first {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("invalid Huffman code")
+    }
+}
+
+enum State {
+    /// Before a gzip member's header.
+    Member,
+    /// Before a block header.
+    Block,
+    /// Inside a stored block, with this many bytes left.
+    Stored(usize),
+    /// Inside a compressed block.
+    Codes(Huffman, Huffman),
+    /// After the last block: the gzip trailer, then maybe another member.
+    Trailer,
+    Done,
+}
+
+/// A DEFLATE stream decoder over any reader, raw or wrapped in gzip. It
+/// decodes as it is read: `read` returns output in steps of about
+/// `STEP`, and the history kept is a little over the 32 KiB window.
+pub(crate) struct Inflater<R> {
+    bits: Bits<R>,
+    gzip: bool,
+    state: State,
+    /// The block being decoded is the last of its stream.
+    last: bool,
+    /// Recent output: the window, then what `read` has not returned yet.
+    out: Vec<u8>,
+    /// Start of the output `read` has not returned.
+    served: usize,
+    /// CRC-32 and length of the gzip member's output so far, for its
+    /// trailer.
+    crc: u32,
+    size: u32,
+}
+
+impl<R: Read> Inflater<R> {
+    /// A raw DEFLATE stream, no zlib or gzip header.
+    #[cfg(feature = "epub")]
+    pub(crate) fn new(inner: R) -> Self {
+        Self::with_state(inner, false, State::Block)
+    }
+
+    /// A gzip file (RFC 1952), all its members one after the other, each
+    /// checked against the CRC-32 and length in its trailer.
+    pub(crate) fn gzip(inner: R) -> Self {
+        Self::with_state(inner, true, State::Member)
+    }
+
+    fn with_state(inner: R, gzip: bool, state: State) -> Self {
+        Inflater {
+            bits: Bits::new(inner),
+            gzip,
+            state,
+            last: false,
+            out: Vec::new(),
+            served: 0,
+            crc: !0,
+            size: 0,
+        }
+    }
+
+    /// Everything left, decoded at once.
+    fn decode_all(mut self) -> Result<Vec<u8>, &'static str> {
+        while !matches!(self.state, State::Done) {
+            self.step()?;
+        }
+        Ok(self.out)
+    }
+
+    /// Decodes the next piece: a header, or up to `STEP` bytes of a block.
+    fn step(&mut self) -> Result<(), &'static str> {
+        let start = self.out.len();
+        self.decode()?;
+        if self.gzip {
+            self.crc = crc32_update(self.crc, &self.out[start..]);
+            self.size = self.size.wrapping_add((self.out.len() - start) as u32);
+        }
+        Ok(())
+    }
+
+    fn decode(&mut self) -> Result<(), &'static str> {
+        match std::mem::replace(&mut self.state, State::Done) {
+            State::Member => self.member()?,
+            State::Block => {
+                self.last = self.bits.bit()? == 1;
+                self.state = match self.bits.bits(2)? {
+                    0 => {
+                        self.bits.align();
+                        let len = self.bits.u16_le()?;
+                        if len != !self.bits.u16_le()? {
+                            return Err("corrupt stored block length");
+                        }
+                        State::Stored(usize::from(len))
+                    }
+                    1 => {
+                        let mut lengths = [0u8; 288];
+                        lengths[..144].fill(8);
+                        lengths[144..256].fill(9);
+                        lengths[256..280].fill(7);
+                        lengths[280..].fill(8);
+                        State::Codes(Huffman::new(&lengths), Huffman::new(&[5; 30]))
+                    }
+                    2 => {
+                        let (lit, dist) = dynamic_tables(&mut self.bits)?;
+                        State::Codes(lit, dist)
+                    }
+                    _ => return Err("invalid block type"),
+                };
+            }
+            State::Stored(len) => {
+                let n = len.min(STEP);
+                for _ in 0..n {
+                    let byte = self.bits.next_byte()?.ok_or("truncated stored block")?;
+                    self.out.push(byte);
+                }
+                self.state = if n < len {
+                    State::Stored(len - n)
+                } else {
+                    self.block_done()
+                };
+            }
+            State::Codes(lit, dist) => {
+                let until = self.out.len() + STEP;
+                let ended = codes(&mut self.bits, &mut self.out, &lit, &dist, until)?;
+                self.state = if ended {
+                    self.block_done()
+                } else {
+                    State::Codes(lit, dist)
+                };
+            }
+            State::Trailer => {
+                self.bits.align();
+                let mut trailer = [0u8; 8];
+                for byte in &mut trailer {
+                    *byte = self.bits.next_byte()?.ok_or("truncated trailer")?;
+                }
+                // The length is modulo 2^32, as the trailer holds it.
+                let (crc, size) = trailer.split_at(4);
+                if crc != (self.crc ^ !0).to_le_bytes() {
+                    return Err("CRC-32 mismatch");
+                }
+                if size != self.size.to_le_bytes() {
+                    return Err("length mismatch");
+                }
+                self.crc = !0;
+                self.size = 0;
+                self.state = State::Member;
+            }
+            State::Done => {}
+        }
+        Ok(())
+    }
+
+    fn block_done(&self) -> State {
+        match (self.last, self.gzip) {
+            (false, _) => State::Block,
+            (true, true) => State::Trailer,
+            (true, false) => State::Done,
+        }
+    }
+
+    /// Reads a gzip member header; at the end of the input, ends the stream.
+    fn member(&mut self) -> Result<(), &'static str> {
+        const FHCRC: u8 = 2;
+        const FEXTRA: u8 = 4;
+        const FNAME: u8 = 8;
+        const FCOMMENT: u8 = 16;
+        let Some(first) = self.bits.next_byte()? else {
+            return Ok(());
+        };
+        let mut header = [first, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        for byte in &mut header[1..] {
+            *byte = self.bits.next_byte()?.ok_or("truncated header")?;
+        }
+        if header[..3] != [0x1f, 0x8b, 8] {
+            return Err("not a deflate-compressed gzip file");
+        }
+        let flags = header[3];
+        let truncated = |byte: Result<Option<u8>, &'static str>| byte?.ok_or("truncated header");
+        if flags & FEXTRA != 0 {
+            let len = u16::from_le_bytes([
+                truncated(self.bits.next_byte())?,
+                truncated(self.bits.next_byte())?,
+            ]);
+            for _ in 0..len {
+                truncated(self.bits.next_byte())?;
+            }
+        }
+        for flag in [FNAME, FCOMMENT] {
+            if flags & flag != 0 {
+                while truncated(self.bits.next_byte())? != 0 {}
+            }
+        }
+        if flags & FHCRC != 0 {
+            truncated(self.bits.next_byte())?;
+            truncated(self.bits.next_byte())?;
+        }
+        self.state = State::Block;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for Inflater<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.served == self.out.len() && !matches!(self.state, State::Done) {
+            // Everything returned: keep only the window.
+            if self.out.len() > 2 * WINDOW {
+                self.out.drain(..self.out.len() - WINDOW);
+                self.served = WINDOW;
+            }
+            if let Err(e) = self.step() {
+                return Err(self
+                    .bits
+                    .error
+                    .take()
+                    .unwrap_or_else(|| io::Error::new(io::ErrorKind::InvalidData, e)));
+            }
+        }
+        let n = buf.len().min(self.out.len() - self.served);
+        buf[..n].copy_from_slice(&self.out[self.served..self.served + n]);
+        self.served += n;
+        Ok(n)
+    }
+}
+
+/// Decompresses a gzip file (RFC 1952), every member, checking each
+/// trailer.
+pub(crate) fn gunzip(data: &[u8]) -> Result<Vec<u8>, &'static str> {
+    Inflater::gzip(data).decode_all()
+}
+
+/// Decompresses a raw DEFLATE stream (no zlib or gzip header).
+#[cfg(feature = "epub")]
+pub(crate) fn inflate(data: &[u8]) -> Result<Vec<u8>, &'static str> {
+    Inflater::new(data).decode_all()
+}
+
+fn dynamic_tables<R: Read>(bits: &mut Bits<R>) -> Result<(Huffman, Huffman), &'static str> {
+    let literals = bits.bits(5)? as usize + 257;
+    let distances = bits.bits(5)? as usize + 1;
+    let code_lengths = bits.bits(4)? as usize + 4;
+    if literals > 286 || distances > 30 {
+        return Err("too many length or distance codes");
+    }
+    let mut lengths = [0u8; 19];
+    for &index in &CODE_ORDER[..code_lengths] {
+        lengths[index] = bits.bits(3)? as u8;
+    }
+    let code = Huffman::new(&lengths);
+    let mut lengths = vec![0u8; literals + distances];
+    let mut i = 0;
+    while i < lengths.len() {
+        let symbol = code.decode(bits)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths[..i]
+                    .last()
+                    .ok_or("repeat with no previous length")?;
+                (previous, 3 + bits.bits(2)?)
+            }
+            17 => (0, 3 + bits.bits(3)?),
+            _ => (0, 11 + bits.bits(7)?),
+        };
+        let end = i + repeat as usize;
+        if end > lengths.len() {
+            return Err("too many code lengths");
+        }
+        lengths[i..end].fill(value);
+        i = end;
+    }
+    if lengths[256] == 0 {
+        return Err("no end-of-block code");
+    }
+    Ok((
+        Huffman::new(&lengths[..literals]),
+        Huffman::new(&lengths[literals..]),
+    ))
+}
+
+/// Decodes symbols until the end of the block, `true`, or until `out` holds
+/// `until` bytes, `false`.
+fn codes<R: Read>(
+    bits: &mut Bits<R>,
+    out: &mut Vec<u8>,
+    lit: &Huffman,
+    dist: &Huffman,
+    until: usize,
+) -> Result<bool, &'static str> {
+    while out.len() < until {
+        let symbol = lit.decode(bits)?;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(true),
+            _ => {
+                let index = usize::from(symbol - 257);
+                if index >= LENGTH_BASE.len() {
+                    return Err("invalid length code");
+                }
+                let len =
+                    usize::from(LENGTH_BASE[index]) + bits.bits(LENGTH_EXTRA[index])? as usize;
+                let index = usize::from(dist.decode(bits)?);
+                if index >= DIST_BASE.len() {
+                    return Err("invalid distance code");
+                }
+                let distance =
+                    usize::from(DIST_BASE[index]) + bits.bits(DIST_EXTRA[index])? as usize;
+                if distance > out.len() {
+                    return Err("distance before the start of the output");
+                }
+                let from = out.len() - distance;
+                for k in 0..len {
+                    out.push(out[from + k]);
+                }
+            }
+        }
+    }
+    Ok(false)
+}
@@ -0,0 +1,458 @@
+//! Loading text from disk (the `fs` feature), and reading streams with a
+//! buffer that adapts to the source.
+
+#[cfg(feature = "fs")]
+use crate::encoding::{self, Encoding, Transcoding};
+#[cfg(feature = "fs")]
+use crate::error::{Result, Td5Error};
+use crate::json::Json;
+use crate::tuning::Tuning;
+#[cfg(feature = "fs")]
+use std::collections::HashSet;
+use std::io::{self, Read};
+#[cfg(feature = "fs")]
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[cfg(feature = "fs")]
+fn read_bytes(path: &Path) -> Result<Vec<u8>> {
+    std::fs::read(path).map_err(|source| Td5Error::Io {
+        path: Some(path.to_path_buf()),
+        source,
+    })
+}
+
+/// Reads a whole file as UTF-8, reporting where decoding failed.
+#[cfg(feature = "fs")]
+pub fn read_text(path: impl AsRef<Path>) -> Result<String> {
+    let path = path.as_ref();
+    let bytes = read_bytes(path)?;
+    String::from_utf8(bytes).map_err(|e| Td5Error::InvalidUtf8 {
+        path: Some(path.to_path_buf()),
+        valid_up_to: e.utf8_error().valid_up_to(),
+    })
+}
+
+/// `path` itself, or every file under it when it is a directory, sorted and
+/// without hidden entries. A path that does not exist but has a `*` or `?`
+/// in it is a `glob` pattern, for shells that leave `corpus/**/*.txt` alone.
+/// Symbolic links are followed, but into each directory only once, so a
+/// link back up the tree ends the walk rather than looping.
+#[cfg(feature = "fs")]
+pub fn files(path: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+    walk(path.as_ref(), &mut HashSet::new())
+}
+
+#[cfg(feature = "fs")]
+fn walk(path: &Path, visited: &mut HashSet<PathBuf>) -> Result<Vec<PathBuf>> {
+    if !path.exists()
+        && let Some(pattern) = path.to_str().filter(|p| is_pattern(p))
+    {
+        let mut files = Vec::new();
+        for found in glob(pattern)? {
+            files.extend(walk(&found, visited)?);
+        }
+        files.sort();
+        files.dedup();
+        return Ok(files);
+    }
+    let io_error = |source| Td5Error::Io {
+        path: Some(path.to_path_buf()),
+        source,
+    };
+    if !std::fs::metadata(path).map_err(io_error)?.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    if !visited.insert(std::fs::canonicalize(path).map_err(io_error)?) {
+        return Ok(Vec::new());
+    }
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(path)
+        .map_err(io_error)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<_>>()
+        .map_err(io_error)?;
+    entries.sort();
+    let mut files = Vec::new();
+    for entry in entries {
+        if entry
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().starts_with('.'))
+        {
+            continue;
+        }
+        files.extend(walk(&entry, visited)?);
+    }
+    Ok(files)
+}
+
+#[cfg(feature = "fs")]
+fn is_pattern(path: &str) -> bool {
+    path.contains(['*', '?'])
+}
+
+/// The paths matching `pattern`, sorted: in a name, `*` matches any run of
+/// characters and `?` one character; `**` matches any number of
+/// directories. Hidden entries only match a name pattern starting with a
+/// dot. `**` goes through each directory once, however many links lead
+/// to it. Nothing matching is an error.
+///
+/// ```no_run
+/// let books = td5::input::glob("corpus/**/*.txt")?;
+/// # Ok::<(), td5::Td5Error>(())
+/// ```
+#[cfg(feature = "fs")]
+pub fn glob(pattern: &str) -> Result<Vec<PathBuf>> {
+    let (root, rest) = match pattern.strip_prefix('/') {
+        Some(rest) => (PathBuf::from("/"), rest),
+        None => (PathBuf::new(), pattern),
+    };
+    let parts: Vec<&str> = rest.split('/').filter(|p| !p.is_empty()).collect();
+    let mut found = Vec::new();
+    expand(&root, &parts, &mut found, &mut HashSet::new());
+    found.sort();
+    found.dedup();
+    if found.is_empty() {
+        return Err(Td5Error::config(format!("no file matches `{pattern}`")));
+    }
+    Ok(found)
+}
+
+#[cfg(feature = "fs")]
+fn expand(
+    dir: &Path,
+    parts: &[&str],
+    found: &mut Vec<PathBuf>,
+    visited: &mut HashSet<(PathBuf, usize)>,
+) {
+    let Some((&part, rest)) = parts.split_first() else {
+        found.push(dir.to_path_buf());
+        return;
+    };
+    if !is_pattern(part) {
+        let next = dir.join(part);
+        if rest.is_empty() && next.exists() {
+            found.push(next);
+        } else if next.is_dir() {
+            expand(&next, rest, found, visited);
+        }
+        return;
+    }
+    let listed = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    // The same directory under the same pattern matches the same paths,
+    // under another name: skipping it is what ends a symbolic link loop.
+    let Ok(real) = std::fs::canonicalize(listed) else {
+        return;
+    };
+    if !visited.insert((real, parts.len())) {
+        return;
+    }
+    // Unreadable directories match nothing, as in a shell.
+    let Ok(entries) = std::fs::read_dir(listed) else {
+        return;
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .collect();
+    names.sort();
+    if part == "**" {
+        expand(dir, rest, found, visited);
+    }
+    for name in names {
+        if name.starts_with('.') && !part.starts_with('.') {
+            continue;
+        }
+        let next = dir.join(&name);
+        if part == "**" {
+            if next.is_dir() {
+                expand(&next, parts, found, visited);
+            }
+        } else if wildcard(part, &name) {
+            if rest.is_empty() {
+                found.push(next);
+            } else if next.is_dir() {
+                expand(&next, rest, found, visited);
+            }
+        }
+    }
+}
+
+/// Whether `name` matches `pattern`, with `*` and `?` as wildcards.
+#[cfg(feature = "fs")]
+fn wildcard(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // The last `*` seen and where in `name` it started matching.
+    let mut star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((at, from)) => {
+                    star = Some((at, from + 1));
+                    p = at + 1;
+                    n = from + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Reads `reader` to the end through an `AdaptiveBuffer`.
+pub fn read_all(reader: impl Read) -> io::Result<Vec<u8>> {
+    read_all_sized(reader).map(|(bytes, _)| bytes)
+}
+
+/// `read_all`, with the read sizes it went through.
+///
+/// ```
+/// use td5::input::{AdaptiveBuffer, read_all_sized};
+///
+/// let text = vec![b'a'; 10 << 20];
+/// let (bytes, report) = read_all_sized(&text[..]).unwrap();
+/// assert_eq!(bytes.len(), 10 << 20);
+/// // A slice fills every read, so the buffer grows to its largest.
+/// assert_eq!(report.largest, AdaptiveBuffer::MAX_BYTES);
+/// ```
+pub fn read_all_sized(reader: impl Read) -> io::Result<(Vec<u8>, BufferReport)> {
+    read_all_timed(reader, |_| Duration::ZERO)
+}
+
+/// `read_all_sized`, for a caller that analyzes the bytes after reading
+/// them all: `work` says how long it will take over each read's bytes, for
+/// the buffer to adapt as if it had analyzed them in between.
+///
+/// ```
+/// use std::time::Duration;
+/// use td5::input::{AdaptiveBuffer, read_all_timed};
+///
+/// let text = vec![b'a'; 10 << 20];
+/// let slow = |read: &[u8]| Duration::from_micros(read.len() as u64);
+/// let (_, report) = read_all_timed(&text[..], slow).unwrap();
+/// // 1 µs a byte: 64 KiB takes 65 ms, more than `TARGET_WORK`.
+/// assert!(report.largest < AdaptiveBuffer::MAX_BYTES);
+/// ```
+pub fn read_all_timed(
+    mut reader: impl Read,
+    mut work: impl FnMut(&[u8]) -> Duration,
+) -> io::Result<(Vec<u8>, BufferReport)> {
+    let mut buffer = AdaptiveBuffer::new();
+    let mut bytes = Vec::new();
+    loop {
+        let read = buffer.read(&mut reader)?;
+        if read.is_empty() {
+            return Ok((bytes, buffer.report()));
+        }
+        bytes.extend_from_slice(read);
+        let took = work(read);
+        buffer.adapt(took);
+    }
+}
+
+/// A read buffer that follows its source: it starts at
+/// `Tuning::buffer_bytes` and halves towards what arrives when reads come
+/// back less than half full, as from a terminal or a slow pipe. It doubles
+/// when a read fills it and the caller handled the bytes within
+/// `TARGET_WORK`, as from a fast disk, up to `MAX_BYTES`. Text dense in
+/// words takes longer per byte to analyze, so it settles on smaller reads
+/// than sparse text from the same source.
+///
+/// ```
+/// use std::time::Duration;
+/// use td5::input::AdaptiveBuffer;
+///
+/// let mut buffer = AdaptiveBuffer::with_size(64 << 10);
+/// let mut source = &vec![b'x'; 1 << 20][..];
+/// assert_eq!(buffer.read(&mut source).unwrap().len(), 64 << 10);
+/// buffer.adapt(Duration::from_millis(1));
+/// assert_eq!(buffer.size(), 128 << 10);
+/// buffer.read(&mut source).unwrap();
+/// // Too slow to handle: back down.
+/// buffer.adapt(Duration::from_millis(200));
+/// assert_eq!(buffer.size(), 64 << 10);
+///
+/// let mut typed = &b"one line\n"[..];
+/// buffer.read(&mut typed).unwrap();
+/// buffer.adapt(Duration::ZERO);
+/// assert_eq!(buffer.size(), AdaptiveBuffer::MIN_BYTES);
+/// ```
+#[derive(Debug, Clone)]
+pub struct AdaptiveBuffer {
+    buffer: Vec<u8>,
+    size: usize,
+    /// Bytes the last read returned.
+    filled: usize,
+    report: BufferReport,
+}
+
+/// The read sizes an `AdaptiveBuffer` went through.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BufferReport {
+    /// Reads that returned data.
+    pub reads: usize,
+    pub bytes: u64,
+    /// The size it started with.
+    pub first: usize,
+    pub smallest: usize,
+    pub largest: usize,
+    /// The size at the end.
+    pub last: usize,
+    /// Times the size changed.
+    pub changes: usize,
+}
+
+impl Default for AdaptiveBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AdaptiveBuffer {
+    pub const MIN_BYTES: usize = 4 << 10;
+    pub const MAX_BYTES: usize = 4 << 20;
+    /// Longest a caller should take over one read's bytes before the buffer
+    /// shrinks, so that progress and snapshots keep coming.
+    pub const TARGET_WORK: Duration = Duration::from_millis(50);
+
+    /// Starts at `Tuning::buffer_bytes`.
+    pub fn new() -> Self {
+        Self::with_size(Tuning::current().buffer_bytes)
+    }
+
+    pub fn with_size(bytes: usize) -> Self {
+        let size = bytes.clamp(Self::MIN_BYTES, Self::MAX_BYTES);
+        AdaptiveBuffer {
+            buffer: Vec::new(),
+            size,
+            filled: 0,
+            report: BufferReport {
+                first: size,
+                smallest: size,
+                largest: size,
+                last: size,
+                ..BufferReport::default()
+            },
+        }
+    }
+
+    /// The size of the next read.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// One read from `reader`, retried if interrupted; empty at the end.
+    pub fn read(&mut self, reader: &mut impl Read) -> io::Result<&[u8]> {
+        self.buffer.resize(self.size, 0);
+        self.buffer.shrink_to(self.size);
+        let n = loop {
+            match reader.read(&mut self.buffer) {
+                Ok(n) => break n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        };
+        self.filled = n;
+        if n > 0 {
+            self.report.reads += 1;
+            self.report.bytes += n as u64;
+        }
+        Ok(&self.buffer[..n])
+    }
+
+    /// Picks the next size from the last read and the time `work` its bytes
+    /// took the caller.
+    pub fn adapt(&mut self, work: Duration) {
+        let size = if self.filled == 0 {
+            self.size
+        } else if work > Self::TARGET_WORK {
+            self.size / 2
+        } else if self.filled * 2 < self.size {
+            self.filled.next_power_of_two()
+        } else if self.filled == self.size && work * 2 <= Self::TARGET_WORK {
+            self.size * 2
+        } else {
+            self.size
+        }
+        .clamp(Self::MIN_BYTES, Self::MAX_BYTES);
+        if size != self.size {
+            self.size = size;
+            let report = &mut self.report;
+            report.changes += 1;
+            report.last = size;
+            report.smallest = report.smallest.min(size);
+            report.largest = report.largest.max(size);
+        }
+    }
+
+    pub fn report(&self) -> BufferReport {
+        self.report
+    }
+}
+
+impl BufferReport {
+    pub fn to_json_value(&self) -> Json {
+        Json::Object(vec![
+            ("reads".into(), self.reads.into()),
+            ("bytes".into(), self.bytes.into()),
+            ("first".into(), self.first.into()),
+            ("smallest".into(), self.smallest.into()),
+            ("largest".into(), self.largest.into()),
+            ("last".into(), self.last.into()),
+            ("changes".into(), self.changes.into()),
+        ])
+    }
+}
+
+/// A scratch file in the temporary directory, removed when dropped (even
+/// on an early error return).
+#[cfg(feature = "fs")]
+pub(crate) struct TempFile(PathBuf);
+
+#[cfg(feature = "fs")]
+impl TempFile {
+    pub(crate) fn new(purpose: &str) -> Self {
+        let name = format!("td5-{purpose}-{}.txt", std::process::id());
+        TempFile(std::env::temp_dir().join(name))
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(feature = "fs")]
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Reads a file in whatever encoding it uses (BOM, UTF-16, UTF-8 or
+/// Windows-1252/Latin-1) and returns it as UTF-8 with the detected encoding.
+#[cfg(feature = "fs")]
+pub fn read_transcoded(path: impl AsRef<Path>) -> Result<(String, Encoding)> {
+    let (text, transcoding) = read_decoded(path)?;
+    Ok((text, transcoding.encoding))
+}
+
+/// `read_transcoded`, with whether the text differs from the file.
+#[cfg(feature = "fs")]
+pub fn read_decoded(path: impl AsRef<Path>) -> Result<(String, Transcoding)> {
+    let bytes = read_bytes(path.as_ref())?;
+    let decoded = encoding::decode(&bytes);
+    let transcoding = decoded.transcoding();
+    Ok((decoded.text.into_owned(), transcoding))
+}
@@ -0,0 +1,92 @@
+//! One copy of each word across many documents.
+//!
+//! A corpus of 10,000 files kept in memory (the REPL, a library caller
+//! holding a `WordFrequency` per document) would otherwise store "the" 10,000
+//! times. `WordFrequency` keys are `Arc<str>`; `Interner` hands out one
+//! shared `Arc` per distinct word, and `WordFrequency::intern` swaps a
+//! table's keys for those, so each word is stored once however many tables
+//! hold it.
+//!
+//! The set is split into mutex-protected shards picked by hash, like the
+//! `sharded` backend's map, so documents analyzed on different threads can
+//! intern at the same time and only collide on the same shard.
+
+use crate::fast::FastHasher;
+use rustc_hash::FxHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+
+pub struct Interner {
+    shards: Vec<Mutex<HashSet<Arc<str>, FastHasher>>>,
+}
+
+impl Default for Interner {
+    fn default() -> Self {
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        Self::with_shards(threads * 4)
+    }
+}
+
+impl Interner {
+    /// Four shards per core.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_shards(shards: usize) -> Self {
+        Self {
+            shards: (0..shards.max(1)).map(|_| Mutex::default()).collect(),
+        }
+    }
+
+    fn shard(&self, word: &str) -> &Mutex<HashSet<Arc<str>, FastHasher>> {
+        let mut hasher = FxHasher::default();
+        word.hash(&mut hasher);
+        &self.shards[hasher.finish() as usize % self.shards.len()]
+    }
+
+    /// The shared copy of `word`, allocated on first sight.
+    pub fn intern(&self, word: &str) -> Arc<str> {
+        let mut shard = self.shard(word).lock().expect("interner lock poisoned");
+        if let Some(shared) = shard.get(word) {
+            return Arc::clone(shared);
+        }
+        let shared: Arc<str> = Arc::from(word);
+        shard.insert(Arc::clone(&shared));
+        shared
+    }
+
+    /// Like `intern`, keeping `word` itself when it is the first copy.
+    pub fn intern_arc(&self, word: Arc<str>) -> Arc<str> {
+        let mut shard = self.shard(&word).lock().expect("interner lock poisoned");
+        if let Some(shared) = shard.get(&word) {
+            return Arc::clone(shared);
+        }
+        shard.insert(Arc::clone(&word));
+        word
+    }
+
+    /// Distinct words stored.
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|s| s.lock().expect("interner lock poisoned").len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Bytes of text stored, one copy per word.
+    pub fn text_bytes(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|s| {
+                let shard = s.lock().expect("interner lock poisoned");
+                shard.iter().map(|w| w.len()).sum::<usize>()
+            })
+            .sum()
+    }
+}
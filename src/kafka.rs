@@ -0,0 +1,407 @@
+//! Minimal Kafka consumer for `td5 consume` (the `kafka` feature).
+//!
+//! Speaks just enough of the binary protocol to read one partition:
+//! `Metadata` (v0) to find its leader, `ListOffsets` (v1) for where to start
+//! and `Fetch` (v4) for the records, in v2 record batches. Batches may be
+//! gzip-compressed, or zstd-compressed in a build with the `zstd` feature;
+//! snappy and lz4 are refused. Records are read uncommitted, aborted
+//! transactions included. No consumer groups, committed offsets, TLS, SASL
+//! or reconnects: a restart reads from the end, or from the start with
+//! `from_beginning`.
+
+use crate::error::{Result, Td5Error};
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+
+const FETCH: i16 = 1;
+const LIST_OFFSETS: i16 = 2;
+const METADATA: i16 = 3;
+
+/// How long a fetch waits on the broker for new records.
+const MAX_WAIT_MS: i32 = 500;
+
+/// Records fetched per request, at most.
+const MAX_BYTES: i32 = 1 << 20;
+
+/// Largest response accepted: a fetch is capped at `MAX_BYTES`, but one
+/// batch larger than that is still returned whole.
+const MAX_RESPONSE: usize = 64 << 20;
+
+pub struct Consumer {
+    stream: TcpStream,
+    address: String,
+    topic: String,
+    partition: i32,
+    /// Offset of the next record to fetch.
+    offset: i64,
+    correlation: i32,
+    /// Records fetched but not yet returned.
+    pending: VecDeque<Vec<u8>>,
+}
+
+impl Consumer {
+    /// Connects to the broker at `address` (`host:port`, an optional
+    /// `kafka://` prefix is accepted), moves to the leader of `partition`
+    /// of `topic` if it is another broker, and starts at the end of the
+    /// partition, or at its first record with `from_beginning`.
+    pub fn connect(
+        address: &str,
+        topic: &str,
+        partition: i32,
+        from_beginning: bool,
+    ) -> Result<Consumer> {
+        let address = address.strip_prefix("kafka://").unwrap_or(address);
+        let mut consumer = Consumer {
+            stream: TcpStream::connect(address).map_err(|e| net_error(address, e))?,
+            address: address.to_string(),
+            topic: topic.to_string(),
+            partition,
+            offset: 0,
+            correlation: 0,
+            pending: VecDeque::new(),
+        };
+        let leader = consumer.leader()?;
+        if leader != consumer.address {
+            consumer.stream = TcpStream::connect(&leader).map_err(|e| net_error(&leader, e))?;
+            consumer.address = leader;
+        }
+        consumer.offset = consumer.list_offset(if from_beginning { -2 } else { -1 })?;
+        Ok(consumer)
+    }
+
+    /// The next record's value, fetching more when none are left; a record
+    /// without a value is skipped. `None` once the broker closes the
+    /// connection.
+    pub fn next_message(&mut self) -> Result<Option<Vec<u8>>> {
+        loop {
+            if let Some(value) = self.pending.pop_front() {
+                return Ok(Some(value));
+            }
+            match self.fetch() {
+                Err(Td5Error::Io { source, .. })
+                    if source.kind() == std::io::ErrorKind::UnexpectedEof =>
+                {
+                    return Ok(None);
+                }
+                result => result?,
+            }
+        }
+    }
+
+    /// `host:port` of the partition's leader.
+    fn leader(&mut self) -> Result<String> {
+        let mut body = Vec::new();
+        put_i32(&mut body, 1);
+        put_str(&mut body, &self.topic);
+        let response = self.call(METADATA, 0, &body)?;
+        let mut r = Reader::new(&response);
+        let mut brokers = Vec::new();
+        for _ in 0..r.count()? {
+            let node = r.i32()?;
+            let host = r.string()?;
+            let port = r.i32()?;
+            brokers.push((node, format!("{host}:{port}")));
+        }
+        for _ in 0..r.count()? {
+            self.check(r.i16()?)?;
+            let topic = r.string()?;
+            for _ in 0..r.count()? {
+                let error = r.i16()?;
+                let partition = r.i32()?;
+                let leader = r.i32()?;
+                for _ in 0..2 {
+                    // Replicas, then in-sync replicas.
+                    for _ in 0..r.count()? {
+                        r.i32()?;
+                    }
+                }
+                if topic == self.topic && partition == self.partition {
+                    self.check(error)?;
+                    return brokers
+                        .into_iter()
+                        .find(|&(node, _)| node == leader)
+                        .map(|(_, address)| address)
+                        .ok_or_else(|| self.protocol("the partition has no leader"));
+                }
+            }
+        }
+        Err(self.protocol(&format!("no partition {}", self.partition)))
+    }
+
+    /// The offset at `time`: -1 for the end of the partition, -2 for its
+    /// first record.
+    fn list_offset(&mut self, time: i64) -> Result<i64> {
+        let mut body = Vec::new();
+        put_i32(&mut body, -1);
+        self.put_partition(&mut body);
+        put_i64(&mut body, time);
+        let response = self.call(LIST_OFFSETS, 1, &body)?;
+        let mut r = Reader::new(&response);
+        r.count()?;
+        r.string()?;
+        r.count()?;
+        r.i32()?;
+        self.check(r.i16()?)?;
+        r.i64()?;
+        r.i64()
+    }
+
+    /// Fetches the records from `offset` into `pending`, waiting up to
+    /// `MAX_WAIT_MS` for some.
+    fn fetch(&mut self) -> Result<()> {
+        let mut body = Vec::new();
+        put_i32(&mut body, -1);
+        put_i32(&mut body, MAX_WAIT_MS);
+        put_i32(&mut body, 1);
+        put_i32(&mut body, MAX_BYTES);
+        // Read uncommitted.
+        body.push(0);
+        self.put_partition(&mut body);
+        put_i64(&mut body, self.offset);
+        put_i32(&mut body, MAX_BYTES);
+        let response = self.call(FETCH, 4, &body)?;
+        let mut r = Reader::new(&response);
+        r.i32()?;
+        r.count()?;
+        r.string()?;
+        r.count()?;
+        r.i32()?;
+        self.check(r.i16()?)?;
+        // High watermark, last stable offset, aborted transactions.
+        r.i64()?;
+        r.i64()?;
+        for _ in 0..r.count()? {
+            r.i64()?;
+            r.i64()?;
+        }
+        let records = r.bytes()?.unwrap_or_default();
+        self.offset = batches(records, self.offset, &mut self.pending)
+            .map_err(|message| self.protocol(message))?;
+        Ok(())
+    }
+
+    /// One topic with one partition, the start of most request bodies.
+    fn put_partition(&self, body: &mut Vec<u8>) {
+        put_i32(body, 1);
+        put_str(body, &self.topic);
+        put_i32(body, 1);
+        put_i32(body, self.partition);
+    }
+
+    /// Sends a request and returns its response's body.
+    fn call(&mut self, api: i16, version: i16, body: &[u8]) -> Result<Vec<u8>> {
+        self.correlation += 1;
+        let mut request = Vec::with_capacity(body.len() + 32);
+        put_i32(&mut request, 0);
+        request.extend_from_slice(&api.to_be_bytes());
+        request.extend_from_slice(&version.to_be_bytes());
+        put_i32(&mut request, self.correlation);
+        put_str(&mut request, "td5");
+        request.extend_from_slice(body);
+        let length = (request.len() - 4) as i32;
+        request[..4].copy_from_slice(&length.to_be_bytes());
+        let address = self.address.clone();
+        let io = |e| net_error(&address, e);
+        self.stream.write_all(&request).map_err(io)?;
+        let mut length = [0u8; 4];
+        self.stream.read_exact(&mut length).map_err(io)?;
+        let length = u32::from_be_bytes(length) as usize;
+        if !(4..=MAX_RESPONSE).contains(&length) {
+            return Err(self.protocol(&format!("response of {length} bytes")));
+        }
+        let mut response = vec![0; length];
+        self.stream.read_exact(&mut response).map_err(io)?;
+        if response[..4] != self.correlation.to_be_bytes() {
+            return Err(self.protocol("response to another request"));
+        }
+        response.drain(..4);
+        Ok(response)
+    }
+
+    fn check(&self, error: i16) -> Result<()> {
+        match error {
+            0 => Ok(()),
+            3 => Err(self.protocol(&format!(
+                "no topic `{}` or partition {}",
+                self.topic, self.partition
+            ))),
+            6 => Err(self.protocol("not the partition's leader")),
+            code => Err(self.protocol(&format!("error code {code}"))),
+        }
+    }
+
+    fn protocol(&self, message: &str) -> Td5Error {
+        Td5Error::config(format!("Kafka broker {}: {message}", self.address))
+    }
+}
+
+/// Appends the values of the records of `data`, v2 record batches, from
+/// `offset` on; the offset after the last one. A batch cut short at the end
+/// of `data`, as the broker may send, is left for the next fetch.
+fn batches(
+    mut data: &[u8],
+    mut offset: i64,
+    values: &mut VecDeque<Vec<u8>>,
+) -> std::result::Result<i64, &'static str> {
+    const HEADER: usize = 61;
+    while data.len() >= 12 {
+        let base = i64::from_be_bytes(data[..8].try_into().unwrap());
+        let length = i32::from_be_bytes(data[8..12].try_into().unwrap());
+        let Some(end) = usize::try_from(length).ok().and_then(|l| l.checked_add(12)) else {
+            return Err("malformed record batch");
+        };
+        if end > data.len() {
+            break;
+        }
+        let (batch, rest) = data.split_at(end);
+        data = rest;
+        if end < HEADER {
+            return Err("malformed record batch");
+        }
+        if batch[16] != 2 {
+            return Err("only v2 record batches are supported");
+        }
+        let attributes = i16::from_be_bytes([batch[21], batch[22]]);
+        let last = base + i64::from(i32::from_be_bytes(batch[23..27].try_into().unwrap()));
+        // Control batches mark transactions; they hold no messages.
+        if attributes & 0x20 == 0 {
+            let count = i32::from_be_bytes(batch[57..61].try_into().unwrap());
+            let records = decompress(attributes & 7, &batch[HEADER..])?;
+            let mut r = Reader::new(&records);
+            for _ in 0..count.max(0) {
+                let (delta, value) = record(&mut r).ok_or("malformed record")?;
+                if base + delta >= offset
+                    && let Some(value) = value
+                {
+                    values.push_back(value.to_vec());
+                }
+            }
+        }
+        offset = offset.max(last + 1);
+    }
+    Ok(offset)
+}
+
+/// The records of a batch, by its compression attribute.
+fn decompress(codec: i16, records: &[u8]) -> std::result::Result<Vec<u8>, &'static str> {
+    match codec {
+        0 => Ok(records.to_vec()),
+        1 => crate::inflate::gunzip(records),
+        #[cfg(feature = "zstd")]
+        4 => {
+            let mut out = Vec::new();
+            crate::zstd::ZstdReader::new(records)
+                .read_to_end(&mut out)
+                .map_err(|_| "corrupt zstd record batch")?;
+            Ok(out)
+        }
+        #[cfg(not(feature = "zstd"))]
+        4 => Err("zstd record batches need a build with `--features zstd`"),
+        2 => Err("snappy record batches are not supported"),
+        3 => Err("lz4 record batches are not supported"),
+        _ => Err("unknown record batch compression"),
+    }
+}
+
+/// A record's offset delta and value.
+fn record<'a>(r: &mut Reader<'a>) -> Option<(i64, Option<&'a [u8]>)> {
+    let length = usize::try_from(r.varint()?).ok()?;
+    let mut fields = Reader::new(r.take(length).ok()?);
+    fields.take(1).ok()?;
+    fields.varint()?;
+    let delta = fields.varint()?;
+    fields.varbytes()?;
+    let value = fields.varbytes()?;
+    Some((delta, value))
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Reader { data }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        if n > self.data.len() {
+            return Err(Td5Error::config("Kafka response cut short"));
+        }
+        let (taken, rest) = self.data.split_at(n);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    fn i16(&mut self) -> Result<i16> {
+        Ok(i16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn i64(&mut self) -> Result<i64> {
+        Ok(i64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    /// An array's length; a null array has none.
+    fn count(&mut self) -> Result<usize> {
+        Ok(usize::try_from(self.i32()?).unwrap_or(0))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let length = usize::try_from(self.i16()?).unwrap_or(0);
+        Ok(String::from_utf8_lossy(self.take(length)?).into_owned())
+    }
+
+    /// `None` for null bytes.
+    fn bytes(&mut self) -> Result<Option<&'a [u8]>> {
+        match usize::try_from(self.i32()?) {
+            Ok(length) => self.take(length).map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// A zigzag varint, as records are written.
+    fn varint(&mut self) -> Option<i64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self.take(1).ok()?.first()?;
+            value |= u64::from(byte & 0x7F) << shift;
+            if byte & 0x80 == 0 {
+                return Some((value >> 1) as i64 ^ -((value & 1) as i64));
+            }
+        }
+        None
+    }
+
+    /// Bytes with a varint length, `None` inside for null.
+    fn varbytes(&mut self) -> Option<Option<&'a [u8]>> {
+        match usize::try_from(self.varint()?) {
+            Ok(length) => self.take(length).ok().map(Some),
+            Err(_) => Some(None),
+        }
+    }
+}
+
+fn put_i32(out: &mut Vec<u8>, value: i32) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+fn put_i64(out: &mut Vec<u8>, value: i64) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+fn put_str(out: &mut Vec<u8>, value: &str) {
+    out.extend_from_slice(&(value.len() as i16).to_be_bytes());
+    out.extend_from_slice(value.as_bytes());
+}
+
+fn net_error(address: &str, source: std::io::Error) -> Td5Error {
+    Td5Error::Io {
+        path: Some(format!("kafka://{address}").into()),
+        source,
+    }
+}
@@ -0,0 +1,421 @@
+//! Keyword and identifier counts for source code (`--keywords LANG`), to see
+//! which language features a codebase leans on.
+//!
+//! The source is read as code rather than prose: a token is an identifier
+//! (`_` or a letter, then `_`, letters and digits), keywords are the
+//! identifiers in the language's list, and comments, string and character
+//! literals and numbers are skipped, so `"if"`, `// for each` and `0xff` count
+//! for nothing. Rust lifetimes and labels (`'static`, `'outer`) are skipped
+//! too, and so is the directive name of a C preprocessor line, along with the
+//! header named by `#include`.
+
+use crate::error::{Result, Td5Error};
+use crate::json::Json;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Strict keywords of the 2021 edition, including the reserved ones.
+pub const RUST: &[&str] = &[
+    "Self", "abstract", "as", "async", "await", "become", "box", "break", "const", "continue",
+    "crate", "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Python 3 keywords, without the soft ones (`match`, `case`, `type`), which
+/// are also common names.
+pub const PYTHON: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+/// C17 keywords.
+pub const C: &[&str] = &[
+    "_Alignas",
+    "_Alignof",
+    "_Atomic",
+    "_Bool",
+    "_Complex",
+    "_Generic",
+    "_Imaginary",
+    "_Noreturn",
+    "_Static_assert",
+    "_Thread_local",
+    "auto",
+    "break",
+    "case",
+    "char",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extern",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "register",
+    "restrict",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "struct",
+    "switch",
+    "typedef",
+    "union",
+    "unsigned",
+    "void",
+    "volatile",
+    "while",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Rust,
+    Python,
+    C,
+}
+
+impl Language {
+    pub const ALL: [Language; 3] = [Language::Rust, Language::Python, Language::C];
+
+    pub fn parse(name: &str) -> Result<Language> {
+        Language::ALL
+            .into_iter()
+            .find(|language| language.name() == name)
+            .ok_or_else(|| {
+                Td5Error::config(format!(
+                    "unknown language `{name}` (expected rust, python or c)"
+                ))
+            })
+    }
+
+    /// The language of a source file, from its extension.
+    ///
+    /// ```
+    /// use td5::keywords::Language;
+    ///
+    /// assert_eq!(Language::from_path("src/main.rs".as_ref()), Some(Language::Rust));
+    /// assert_eq!(Language::from_path("include/td5.h".as_ref()), Some(Language::C));
+    /// assert_eq!(Language::from_path("README.md".as_ref()), None);
+    /// ```
+    pub fn from_path(path: &Path) -> Option<Language> {
+        match path.extension()?.to_str()? {
+            "rs" => Some(Language::Rust),
+            "py" | "pyi" => Some(Language::Python),
+            "c" | "h" => Some(Language::C),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::Rust => "rust",
+            Language::Python => "python",
+            Language::C => "c",
+        }
+    }
+
+    pub fn keywords(self) -> &'static [&'static str] {
+        match self {
+            Language::Rust => RUST,
+            Language::Python => PYTHON,
+            Language::C => C,
+        }
+    }
+
+    pub fn is_keyword(self, word: &str) -> bool {
+        self.keywords().contains(&word)
+    }
+
+    /// Whether `word`, followed by `rest`, starts a literal rather than
+    /// naming something: `b"..."`, `r#"..."#`, `f'...'`, `L"..."`.
+    fn is_prefix(self, word: &str, rest: &str) -> bool {
+        let Some(next) = rest.chars().next() else {
+            return false;
+        };
+        match self {
+            Language::Rust => match next {
+                '"' => matches!(word, "b" | "r" | "br" | "c" | "cr"),
+                '\'' => word == "b",
+                '#' => {
+                    matches!(word, "r" | "br" | "cr")
+                        && rest.trim_start_matches('#').starts_with('"')
+                }
+                _ => false,
+            },
+            Language::Python => {
+                matches!(next, '"' | '\'')
+                    && matches!(
+                        word.to_ascii_lowercase().as_str(),
+                        "r" | "u" | "b" | "f" | "br" | "rb" | "fr" | "rf"
+                    )
+            }
+            Language::C => matches!(next, '"' | '\'') && matches!(word, "L" | "u" | "U" | "u8"),
+        }
+    }
+}
+
+/// The identifiers and keywords of `source`, in order.
+///
+/// ```
+/// use td5::keywords::{Identifiers, Language};
+///
+/// let code = "fn main() { let s = \"if\"; // loop\n 'outer: loop { break 'outer; } }";
+/// let tokens: Vec<&str> = Identifiers::new(code, Language::Rust).collect();
+/// assert_eq!(tokens, ["fn", "main", "let", "s", "loop", "break"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Identifiers<'a> {
+    source: &'a str,
+    pos: usize,
+    language: Language,
+}
+
+impl<'a> Identifiers<'a> {
+    pub fn new(source: &'a str, language: Language) -> Self {
+        Identifiers {
+            source,
+            pos: 0,
+            language,
+        }
+    }
+
+    /// Bytes to skip at `rest`, which starts with `ch`, an identifier
+    /// character or not: a comment, a literal, a number, or `ch` alone.
+    fn skipped(&self, rest: &str, ch: char) -> usize {
+        let line_comment = match self.language {
+            Language::Rust | Language::C => rest.starts_with("//"),
+            Language::Python => ch == '#',
+        };
+        if line_comment {
+            return rest.find('\n').unwrap_or(rest.len());
+        }
+        match ch {
+            '/' if rest.starts_with("/*") => block_comment(rest, self.language == Language::Rust),
+            '#' if self.language == Language::C && self.at_line_start() => directive(rest),
+            '"' | '\'' => self.literal(rest),
+            _ if ch.is_ascii_digit() => rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+                .unwrap_or(rest.len()),
+            _ => ch.len_utf8(),
+        }
+    }
+
+    fn at_line_start(&self) -> bool {
+        let line = self.source[..self.pos].rsplit('\n').next().unwrap_or("");
+        line.trim().is_empty()
+    }
+
+    /// The literal at `rest`, including any `#`s of a Rust raw string.
+    fn literal(&self, rest: &str) -> usize {
+        match self.language {
+            Language::Rust if rest.starts_with('#') || self.after_raw_prefix() => {
+                let hashes = rest.len() - rest.trim_start_matches('#').len();
+                let closing = format!("\"{}", "#".repeat(hashes));
+                let body = hashes + 1;
+                rest.get(body..)
+                    .and_then(|tail| tail.find(&closing))
+                    .map_or(rest.len(), |end| body + end + closing.len())
+            }
+            Language::Rust if rest.starts_with('\'') => {
+                // `'a'` and `'\n'` are characters, `'a` a lifetime or label,
+                // skipped along with its name.
+                let mut chars = rest[1..].chars();
+                match (chars.next(), chars.next()) {
+                    (Some('\\'), _) => quoted(rest, false),
+                    (Some(c), Some('\'')) => 1 + c.len_utf8() + 1,
+                    _ => 1 + identifier(&rest[1..]),
+                }
+            }
+            Language::Rust => quoted(rest, true),
+            Language::Python if rest.starts_with("\"\"\"") || rest.starts_with("'''") => {
+                let fence = &rest[..3];
+                rest[3..].find(fence).map_or(rest.len(), |end| 3 + end + 3)
+            }
+            Language::Python | Language::C => quoted(rest, false),
+        }
+    }
+
+    /// Whether the identifier just read was `r`, `br` or `cr`.
+    fn after_raw_prefix(&self) -> bool {
+        let before = &self.source[..self.pos];
+        let prefix = before
+            .rfind(|c: char| !(c == '_' || c.is_alphanumeric()))
+            .map_or(before, |at| &before[at + 1..]);
+        matches!(prefix, "r" | "br" | "cr")
+    }
+}
+
+impl<'a> Iterator for Identifiers<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        loop {
+            let rest = &self.source[self.pos..];
+            let ch = rest.chars().next()?;
+            if ch == '_' || ch.is_alphabetic() {
+                let mut len = identifier(rest);
+                let word = &rest[..len];
+                let after = &rest[len..];
+                if self.language.is_prefix(word, after) {
+                    self.pos += len;
+                    self.pos += self.literal(after);
+                    continue;
+                }
+                // `r#type` names something called `type`: an identifier.
+                if self.language == Language::Rust && word == "r" && after.starts_with('#') {
+                    len += 1 + identifier(&after[1..]);
+                }
+                self.pos += len;
+                return Some(&rest[..len]);
+            } else {
+                self.pos += self.skipped(rest, ch);
+            }
+        }
+    }
+}
+
+/// Length of the identifier characters at the start of `text`.
+fn identifier(text: &str) -> usize {
+    text.find(|c: char| !(c == '_' || c.is_alphanumeric()))
+        .unwrap_or(text.len())
+}
+
+/// Length of the quoted literal at `text` up to its closing quote, or up to
+/// the end of the line when `multiline` is false and it has none there.
+fn quoted(text: &str, multiline: bool) -> usize {
+    let quote = text.as_bytes()[0];
+    let mut bytes = text.bytes().enumerate().skip(1);
+    while let Some((i, b)) = bytes.next() {
+        match b {
+            b'\\' => {
+                bytes.next();
+            }
+            b'\n' if !multiline => return i,
+            _ if b == quote => return i + 1,
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+/// Length of the `/* ... */` comment at `text`; Rust's nest.
+fn block_comment(text: &str, nested: bool) -> usize {
+    let mut depth = 0;
+    let mut i = 0;
+    while i < text.len() {
+        if text[i..].starts_with("/*") && (nested || depth == 0) {
+            depth += 1;
+            i += 2;
+        } else if text[i..].starts_with("*/") {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    text.len()
+}
+
+/// The `#`, spaces and directive name of a C preprocessor line, or the
+/// whole line for `#include`, whose header is a path.
+fn directive(text: &str) -> usize {
+    let after_hash = text[1..].trim_start_matches([' ', '\t']);
+    let name_len = after_hash
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(after_hash.len());
+    if &after_hash[..name_len] == "include" {
+        return text.find('\n').unwrap_or(text.len());
+    }
+    text.len() - after_hash.len() + name_len
+}
+
+/// How often a source file uses its language's keywords, and the other
+/// identifiers it uses.
+///
+/// ```
+/// use td5::keywords::{CodeStats, Language};
+///
+/// let code = "def f(x):\n    # if in a comment\n    if x is None:\n        return 'None'\n    return x\n";
+/// let stats = CodeStats::of(code, Language::Python);
+/// assert_eq!(stats.keywords, [("return".to_string(), 2), ("None".to_string(), 1), ("def".to_string(), 1), ("if".to_string(), 1), ("is".to_string(), 1)]);
+/// assert_eq!(stats.identifiers, [("x".to_string(), 3), ("f".to_string(), 1)]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeStats {
+    pub language: Language,
+    /// Every keyword used, with its count, most frequent first, ties in
+    /// code point order.
+    pub keywords: Vec<(String, usize)>,
+    pub keyword_total: usize,
+    /// Every other identifier, in the same order.
+    pub identifiers: Vec<(String, usize)>,
+    pub identifier_total: usize,
+}
+
+impl CodeStats {
+    pub fn of(source: &str, language: Language) -> CodeStats {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for token in Identifiers::new(source, language) {
+            *counts.entry(token).or_insert(0) += 1;
+        }
+        let (keywords, identifiers): (Vec<_>, Vec<_>) = counts
+            .into_iter()
+            .map(|(token, count)| (token.to_string(), count))
+            .partition(|(token, _)| language.is_keyword(token));
+        let sorted = |mut tokens: Vec<(String, usize)>| {
+            tokens.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            tokens
+        };
+        CodeStats {
+            language,
+            keyword_total: keywords.iter().map(|(_, n)| n).sum(),
+            identifier_total: identifiers.iter().map(|(_, n)| n).sum(),
+            keywords: sorted(keywords),
+            identifiers: sorted(identifiers),
+        }
+    }
+
+    /// Share of tokens that are keywords, 0 for code without any token.
+    pub fn keyword_share(&self) -> f64 {
+        let tokens = self.keyword_total + self.identifier_total;
+        if tokens == 0 {
+            0.0
+        } else {
+            self.keyword_total as f64 / tokens as f64
+        }
+    }
+
+    pub fn to_json_value(&self) -> Json {
+        let counts = |tokens: &[(String, usize)]| {
+            Json::Array(
+                tokens
+                    .iter()
+                    .map(|(t, c)| Json::Array(vec![t.as_str().into(), (*c).into()]))
+                    .collect(),
+            )
+        };
+        Json::Object(vec![
+            ("language".into(), self.language.name().into()),
+            ("keywords".into(), counts(&self.keywords)),
+            ("keyword_total".into(), self.keyword_total.into()),
+            ("identifiers".into(), counts(&self.identifiers)),
+            ("identifier_total".into(), self.identifier_total.into()),
+        ])
+    }
+}
@@ -0,0 +1,260 @@
+//! Slow and fast text analyzers, plus a small harness to benchmark them
+//! (or your own implementations) side by side.
+
+pub mod acronyms;
+pub mod alerts;
+pub mod analyzer;
+pub mod approx;
+pub mod bench;
+#[cfg(feature = "git")]
+pub mod blame;
+pub mod bytes;
+#[cfg(feature = "fs")]
+pub mod cachegrind;
+pub mod categories;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+pub mod chat;
+pub mod check;
+#[cfg(feature = "fs")]
+pub mod checkpoint;
+mod checksum;
+pub mod clock;
+pub mod concordance;
+pub mod config;
+pub mod cooccur;
+pub mod corpus;
+pub mod csv;
+pub mod dedup;
+pub mod deny;
+#[cfg(feature = "language-detection")]
+pub mod detect;
+mod diagnostics;
+pub mod dictionary;
+pub mod diff;
+pub mod dispersion;
+pub mod encoding;
+pub mod environment;
+#[cfg(feature = "epub")]
+pub mod epub;
+pub mod error;
+#[cfg(feature = "evcxr")]
+pub mod evcxr;
+#[cfg(feature = "fs")]
+pub mod external;
+pub mod extras;
+mod fast;
+pub mod fetch;
+#[cfg(feature = "fs")]
+pub mod files;
+#[cfg(feature = "frames")]
+pub mod frame;
+pub mod freq;
+pub mod generator;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod hotwords;
+#[cfg(feature = "grpc")]
+mod hpack;
+pub mod html;
+pub mod incremental;
+mod inflate;
+pub mod input;
+pub mod interner;
+pub mod json;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+pub mod keywords;
+pub mod lint;
+#[cfg(feature = "fs")]
+pub mod manifest;
+#[cfg(feature = "mbox")]
+pub mod mbox;
+#[cfg(all(feature = "mmap", unix, target_pointer_width = "64"))]
+pub mod mmap;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+#[cfg(feature = "nats")]
+pub mod nats;
+mod ngrams;
+pub mod normalize;
+#[cfg(feature = "fs")]
+pub mod output;
+pub mod overlap;
+pub mod parallel;
+pub mod phrases;
+pub mod pii;
+pub mod plugin;
+pub mod prefix;
+pub mod proto;
+pub mod quotes;
+#[cfg(all(feature = "rapl", target_os = "linux"))]
+pub mod rapl;
+pub mod readability;
+pub mod record;
+#[cfg(feature = "fs")]
+pub mod repl;
+pub mod report;
+pub mod sample;
+#[cfg(feature = "samples")]
+pub mod samples;
+pub mod schema;
+pub mod sections;
+pub mod seed;
+pub mod segment;
+pub mod selftest;
+pub mod server;
+pub mod shapes;
+pub mod sharded;
+pub mod share;
+#[cfg(feature = "simd")]
+mod simd;
+pub mod skip;
+pub mod slice;
+mod slow;
+pub mod sniff;
+pub mod social;
+pub mod stability;
+#[cfg(feature = "fs")]
+pub mod stack;
+#[cfg(feature = "stemming")]
+pub mod stem;
+pub mod stopwords;
+pub mod streaming;
+pub mod subtitles;
+pub mod template;
+pub mod timeline;
+pub mod timestamp;
+pub mod tokenizer;
+pub mod tokens;
+pub mod transform;
+pub mod trend;
+pub mod tuning;
+pub mod units;
+pub mod warnings;
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub mod wasm;
+#[cfg(feature = "fs")]
+pub mod watch;
+pub mod wiki;
+pub mod window;
+mod wordmap;
+pub mod working_set;
+pub mod yaml;
+#[cfg(feature = "epub")]
+mod zip;
+#[cfg(feature = "zstd")]
+mod zstd;
+
+pub use analyzer::Analyzer;
+pub use bytes::{analyze_bytes, analyze_bytes_with};
+pub use config::{
+    AnalyzerConfig, ControlChars, LongTokenPolicy, Normalization, Punctuation, Segmentation,
+};
+pub use diagnostics::Diagnostics;
+pub use error::{Result, Td5Error};
+pub use fast::{
+    PathStats, Timings, analyze_text_fast, analyze_text_fast_instrumented, analyze_text_fast_timed,
+    analyze_text_fast_with,
+};
+pub use freq::WordFrequency;
+pub use parallel::analyze_text_parallel;
+pub use schema::TimePolicy;
+pub use slow::{analyze_text_slow, analyze_text_slow_timed, analyze_text_slow_with};
+pub use streaming::{TextAnalyzer, analyze_reader, analyze_reader_with};
+pub use tokens::tokenize;
+
+/// What an analyzer found in a text.
+///
+/// Text without a single word (empty, whitespace, punctuation or digits
+/// only) gives the same stats from every analyzer: zero counts and empty
+/// lists. Only `diagnostics` may differ.
+///
+/// ```
+/// for registered in td5::analyzer::registry() {
+///     for text in ["", " \t\r\n ", "... 42 -- !?"] {
+///         let stats = registered.analyzer.analyze(text);
+///         assert_eq!(stats.word_count, 0, "{} on {text:?}", registered.name);
+///         assert_eq!(stats.total_words, 0);
+///         assert_eq!(stats.char_count, 0);
+///         assert!(stats.top_words.is_empty(), "{} on {text:?}", registered.name);
+///         assert!(stats.longest_words.is_empty());
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextStats {
+    pub word_count: usize,
+    /// Counted tokens, repeats included.
+    pub total_words: usize,
+    pub char_count: usize,
+    /// Lines as `str::lines` counts them; `None` for stats built from a
+    /// word table alone (`WordFrequency::to_stats`), which has no lines.
+    pub line_count: Option<usize>,
+    /// Tokens ending with `.`, `!`, `?` or `…` (or their CJK forms), plus
+    /// the words after the last one if any: an approximation, since `Dr.`
+    /// ends a sentence too. `None` like `line_count`.
+    pub sentence_count: Option<usize>,
+    /// Characters per counted word, repeats included.
+    pub mean_word_length: f64,
+    pub median_word_length: usize,
+    /// `word_lengths[i]`: counted words of `i + 1` characters, repeats
+    /// included, up to the longest; the 32nd slot also counts longer words.
+    pub word_lengths: Vec<usize>,
+    pub top_words: Vec<(String, usize)>,
+    /// With `AnalyzerConfig::stemming`, `top_words` holds stems and
+    /// `surface_forms[i]` is the most frequent word counted under the `i`th;
+    /// empty otherwise.
+    pub surface_forms: Vec<String>,
+    /// ISO 639-1 code of the language the text is written in, with
+    /// `AnalyzerConfig::detect_language`; `None` without it or when the
+    /// text is too short or too mixed to tell.
+    pub detected_language: Option<String>,
+    /// The `AnalyzerConfig::longest_n` longest distinct words, by byte
+    /// length, ties alphabetically.
+    pub longest_words: Vec<String>,
+    /// Most frequent pairs of consecutive words, joined by a space; empty
+    /// unless `AnalyzerConfig::ngrams` asks for them.
+    pub top_bigrams: Vec<(String, usize)>,
+    /// Likewise for three words, with `AnalyzerConfig::ngrams(3)`.
+    pub top_trigrams: Vec<(String, usize)>,
+    pub diagnostics: Diagnostics,
+}
+
+/// Stats of `text` from the fast analyzer with the default options; the
+/// entry point for callers who don't care which implementation runs.
+///
+/// ```
+/// let stats = td5::analyze("Hello world. How are you?\nFine!\n");
+/// assert_eq!((stats.total_words, stats.line_count, stats.sentence_count), (6, Some(2), Some(3)));
+/// assert_eq!(stats.word_lengths, [0, 0, 3, 1, 2]);
+/// assert_eq!(stats.median_word_length, 4);
+/// ```
+pub fn analyze(text: &str) -> TextStats {
+    analyze_text_fast(text)
+}
+
+/// `size` words of prose-like text from `shapes::TextGenerator` with its
+/// defaults: Zipf-distributed over 10,000 words, with sentences, commas and
+/// capitals, the same on every call. `td5::generator` has the other sources.
+///
+/// ```
+/// let text = td5::generate_test_text(1_000);
+/// assert_eq!(td5::analyze(&text).total_words, 1_000);
+/// assert_eq!(text, td5::generate_test_text(1_000));
+/// ```
+pub fn generate_test_text(size: usize) -> String {
+    shapes::TextGenerator::new().generate(size)
+}
+
+/// `size` words cycling through the ten hot words, lowercase and separated
+/// by single spaces: text that never leaves the fast path's hot-only
+/// shortcut, which `generate_test_text` used to be.
+///
+/// ```
+/// assert_eq!(td5::hot_words_text(3), "rust performance optimization");
+/// ```
+pub fn hot_words_text(size: usize) -> String {
+    use generator::TextSource;
+    generator::Cyclic::hot_words().text(size)
+}
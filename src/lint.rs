@@ -0,0 +1,251 @@
+//! Opt-in prose checks: immediately repeated words, overly long sentences and
+//! overused filler words, each reported at a 1-based line and column.
+//!
+//! This has its own small tokenizer since it needs positions and sentence
+//! boundaries, which the counting scanners throw away.
+
+use crate::phrases::{self, PhraseList, PhraseSet};
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintConfig {
+    /// Sentences with more words than this are reported.
+    pub max_sentence_words: usize,
+    /// Words that pad prose without adding meaning, lowercase.
+    pub fillers: Vec<String>,
+    /// A filler is reported once its share of all words exceeds this.
+    pub max_filler_share: f64,
+    /// Legitimate doublings ("had had", "that that") not reported as repeats.
+    pub allowed_repeats: Vec<String>,
+    /// Phrase lists whose matches are reported; `phrases::builtin()` by default.
+    pub phrases: Vec<PhraseList>,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        let words = |list: &[&str]| list.iter().map(|w| w.to_string()).collect();
+        Self {
+            max_sentence_words: 40,
+            fillers: words(&[
+                "very",
+                "really",
+                "just",
+                "basically",
+                "actually",
+                "quite",
+                "literally",
+                "simply",
+                "vraiment",
+                "très",
+                "juste",
+                "simplement",
+                "littéralement",
+            ]),
+            max_filler_share: 0.01,
+            allowed_repeats: words(&["had", "that", "nous", "vous"]),
+            phrases: phrases::builtin(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    RepeatedWord,
+    LongSentence,
+    Filler,
+    Phrase,
+}
+
+impl Rule {
+    pub fn name(self) -> &'static str {
+        match self {
+            Rule::RepeatedWord => "repeated-word",
+            Rule::LongSentence => "long-sentence",
+            Rule::Filler => "filler",
+            Rule::Phrase => "phrase",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub line: usize,
+    pub column: usize,
+    pub rule: Rule,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}: {}",
+            self.line,
+            self.column,
+            self.rule.name(),
+            self.message
+        )
+    }
+}
+
+pub(crate) struct Word {
+    pub(crate) lower: String,
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+    /// A sentence terminator came between the previous word and this one.
+    pub(crate) starts_sentence: bool,
+}
+
+pub(crate) fn words(text: &str) -> Vec<Word> {
+    let mut out = Vec::new();
+    let (mut line, mut column) = (1, 0);
+    let mut current: Option<Word> = None;
+    let mut sentence_ended = true;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        column += 1;
+        // Apostrophes inside a word ("don't", "l'été") keep it together.
+        let inner_apostrophe = matches!(c, '\'' | '’')
+            && current.is_some()
+            && chars.peek().is_some_and(|n| n.is_alphabetic());
+        if c.is_alphabetic() || inner_apostrophe {
+            let word = current.get_or_insert_with(|| Word {
+                lower: String::new(),
+                line,
+                column,
+                starts_sentence: std::mem::take(&mut sentence_ended),
+            });
+            word.lower.extend(c.to_lowercase());
+        } else {
+            out.extend(current.take());
+            if matches!(c, '.' | '!' | '?' | '…') {
+                sentence_ended = true;
+            }
+        }
+        if c == '\n' {
+            line += 1;
+            column = 0;
+        }
+    }
+    out.extend(current);
+    out
+}
+
+/// Occurrences of one matched phrase, e.g. `was written` from `passive`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhraseCount {
+    pub list: String,
+    pub phrase: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LintReport {
+    /// In reading order.
+    pub findings: Vec<Finding>,
+    /// Most frequent first.
+    pub phrase_counts: Vec<PhraseCount>,
+}
+
+pub fn lint(text: &str, config: &LintConfig) -> LintReport {
+    let words = words(text);
+    let mut findings = Vec::new();
+
+    for pair in words.windows(2) {
+        let (prev, word) = (&pair[0], &pair[1]);
+        if !word.starts_sentence
+            && word.lower == prev.lower
+            && !config.allowed_repeats.contains(&word.lower)
+        {
+            findings.push(Finding {
+                line: word.line,
+                column: word.column,
+                rule: Rule::RepeatedWord,
+                message: format!("`{}` repeated", word.lower),
+            });
+        }
+    }
+
+    let mut start = 0;
+    while start < words.len() {
+        let len = words[start + 1..]
+            .iter()
+            .position(|w| w.starts_sentence)
+            .map_or(words.len() - start, |i| i + 1);
+        if len > config.max_sentence_words {
+            findings.push(Finding {
+                line: words[start].line,
+                column: words[start].column,
+                rule: Rule::LongSentence,
+                message: format!(
+                    "sentence has {len} words (max {})",
+                    config.max_sentence_words
+                ),
+            });
+        }
+        start += len;
+    }
+
+    let mut fillers: HashMap<&str, (usize, &Word)> = HashMap::new();
+    for word in &words {
+        if config.fillers.contains(&word.lower) {
+            fillers.entry(&word.lower).or_insert((0, word)).0 += 1;
+        }
+    }
+    for (filler, (count, first)) in fillers {
+        let share = count as f64 / words.len() as f64;
+        if share > config.max_filler_share {
+            findings.push(Finding {
+                line: first.line,
+                column: first.column,
+                rule: Rule::Filler,
+                message: format!(
+                    "`{filler}` used {count} time{} ({:.1}% of words)",
+                    if count == 1 { "" } else { "s" },
+                    share * 100.0
+                ),
+            });
+        }
+    }
+
+    let phrase_counts = match_phrases(&words, &config.phrases, &mut findings);
+
+    findings.sort_by_key(|f| (f.line, f.column));
+    LintReport {
+        findings,
+        phrase_counts,
+    }
+}
+
+fn match_phrases(
+    words: &[Word],
+    lists: &[PhraseList],
+    findings: &mut Vec<Finding>,
+) -> Vec<PhraseCount> {
+    let set = PhraseSet::new(lists.to_vec());
+    let lower: Vec<&str> = words.iter().map(|w| w.lower.as_str()).collect();
+    let breaks: Vec<bool> = words.iter().map(|w| w.starts_sentence).collect();
+    let mut counts: HashMap<(usize, String), usize> = HashMap::new();
+    for m in set.find(&lower, &breaks) {
+        let phrase = lower[m.start..m.start + m.len].join(" ");
+        let list = &set.lists()[m.list].name;
+        findings.push(Finding {
+            line: words[m.start].line,
+            column: words[m.start].column,
+            rule: Rule::Phrase,
+            message: format!("`{phrase}` ({list})"),
+        });
+        *counts.entry((m.list, phrase)).or_default() += 1;
+    }
+    let mut counts: Vec<PhraseCount> = counts
+        .into_iter()
+        .map(|((list, phrase), count)| PhraseCount {
+            list: set.lists()[list].name.clone(),
+            phrase,
+            count,
+        })
+        .collect();
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.phrase.cmp(&b.phrase)));
+    counts
+}
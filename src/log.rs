@@ -0,0 +1,50 @@
+//! Diagnostic output for the `td5` binary.
+//!
+//! Report data goes to stdout with `print!`; everything else (progress,
+//! warnings, notes) goes through these macros to stderr, so stdout can be piped
+//! into `jq` or a file untouched. `--quiet` keeps only errors and warnings,
+//! `--verbose` adds debug notes.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Normal as u8);
+
+pub fn set_level(level: Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+pub fn enabled(level: Level) -> bool {
+    LEVEL.load(Ordering::Relaxed) >= level as u8
+}
+
+/// Always shown, even with `--quiet`.
+macro_rules! warn {
+    ($($arg:tt)*) => {
+        eprintln!("{}", tr!("warning: {message}", message = format_args!($($arg)*)))
+    };
+}
+
+/// Progress and notes; hidden by `--quiet`.
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::Level::Normal) {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+/// Only shown with `--verbose`.
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::Level::Verbose) {
+            eprintln!("debug: {}", format_args!($($arg)*));
+        }
+    };
+}
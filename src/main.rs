@@ -1,423 +1,4425 @@
-use rustc_hash::FxHasher;
-use std::collections::HashMap;
-use std::hash::BuildHasherDefault;
-use std::time::Instant;
-
-type FastHasher = BuildHasherDefault<FxHasher>;
-
-#[derive(Debug)]
-struct TextStats {
-    word_count: usize,
-    char_count: usize,
-    top_words: Vec<(String, usize)>,
-    longest_words: Vec<String>,
-    time_ns: u128,
-}
+#[macro_use]
+mod i18n;
+#[macro_use]
+mod log;
+mod allocations;
+mod cli;
+mod print;
 
-fn match_hot_lower(token: &[u8]) -> Option<usize> {
-    match token.get(0)? {
-        b'r' if token == b"rust" => Some(0),
-        b'p' if token == b"performance" => Some(1),
-        b'o' if token == b"optimization" => Some(2),
-        b'm' if token == b"memory" => Some(3),
-        b's' => match token.len() {
-            5 if token == b"speed" => Some(4),
-            9 if token == b"structure" => Some(9),
-            _ => None,
-        },
-        b'e' if token == b"efficiency" => Some(5),
-        b'b' if token == b"benchmark" => Some(6),
-        b'a' if token == b"algorithm" => Some(7),
-        b'd' if token == b"data" => Some(8),
-        _ => None,
+use allocations::Usage;
+use cli::Args;
+use i18n::{Numbers, decimal, number};
+use print::{
+    print_against, print_approx, print_delta, print_diff, print_extras, print_overlap,
+    print_phases, print_sections, print_stability, print_stats, print_timings, seed_label,
+};
+use std::borrow::Cow;
+use std::hint::black_box;
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use td5::analyzer::{self, Analyzer, registry};
+use td5::approx::ApproxCounter;
+use td5::bench::BenchReport;
+use td5::cachegrind;
+use td5::chat::Chat;
+use td5::check::Assertion;
+use td5::checkpoint::Checkpoint;
+use td5::concordance::Concordance;
+use td5::cooccur::Graph;
+use td5::corpus::Corpus;
+use td5::dedup::{self, Fingerprint};
+use td5::deny::DenyList;
+use td5::dictionary::Dictionary;
+use td5::diff::Side;
+use td5::encoding::{self, Decoded, Encoding};
+use td5::external::{self, ExternalTool};
+use td5::extras::{Extras, Wanted};
+use td5::fetch::{self, FetchPolicy, Fetcher};
+use td5::files::{Aggregate, FileResult, FilesOptions};
+use td5::freq::ListFormat;
+use td5::generator::{self, TextSource};
+use td5::hotwords::HotVocabulary;
+use td5::input::BufferReport;
+use td5::json::Json;
+use td5::keywords::Language;
+use td5::lint::{LintConfig, PhraseCount, lint};
+use td5::manifest::Manifest;
+use td5::overlap::Overlap;
+use td5::parallel::analyze_text_parallel_timed;
+use td5::phrases::PhraseList;
+use td5::plugin::{Extension, Filter};
+use td5::prefix::PrefixTree;
+use td5::quotes::DEFAULT_QUOTES;
+use td5::readability::Profile;
+use td5::record::{Bundle, Plan, RecordedInput};
+use td5::repl::Repl;
+use td5::report::Report;
+use td5::sample::Estimate;
+use td5::sections::{Section, Splitter};
+use td5::selftest;
+use td5::server::Server;
+use td5::shapes::{InputCache, Shape, TextGenerator};
+use td5::share::TopWords;
+use td5::skip::{Skip, SkipRules};
+use td5::slice::{Range, Slice};
+use td5::social::SocialStats;
+use td5::stability::{self, Stability};
+use td5::stack;
+use td5::stopwords::StopwordList;
+use td5::streaming::WordLimit;
+use td5::subtitles::{self, Subtitles};
+use td5::template::Template;
+use td5::timeline::{Bucket, Timeline};
+use td5::transform::{Pipeline, Stage};
+use td5::trend::{Trend, WordTrend};
+use td5::tuning::Tuning;
+use td5::warnings::Warnings;
+use td5::watch::{Delta, Watcher};
+use td5::window::{SlidingWindow, WindowSize};
+use td5::working_set;
+use td5::{
+    AnalyzerConfig, ControlChars, Normalization, TextStats, TimePolicy, WordFrequency,
+    analyze_text_fast_timed, analyze_text_slow_timed,
+};
+
+fn run_demo(args: &Args) -> Result<(), Halt> {
+    let mut out = io::stdout().lock();
+    let baseline = args.value("baseline").unwrap_or("slow");
+    let analyzers = registry();
+    if !analyzers.iter().any(|r| r.name == baseline) {
+        return Err(tr!("unknown analyzer `{name}`", name = baseline).into());
+    }
+    let seed = args.number("seed", 0u64)?;
+    let text = match &args.positional[..] {
+        [] => {
+            let source: Box<dyn TextSource> = match args.value("source") {
+                None | Some("prose") => Box::new(
+                    TextGenerator::new()
+                        .seed(seed)
+                        .vocabulary(args.number("vocabulary", TextGenerator::DEFAULT_VOCABULARY)?),
+                ),
+                Some(_) if args.has("vocabulary") => {
+                    return Err(tr!("--vocabulary only applies to the prose source").into());
+                }
+                Some(spec) => generator::source(spec, seed).map_err(|e| e.to_string())?,
+            };
+            source.text(args.number("words", 50_000)?)
+        }
+        [_] if ["words", "seed", "vocabulary", "source"]
+            .iter()
+            .any(|flag| args.has(flag)) =>
+        {
+            return Err(tr!(
+                "--words, --seed, --vocabulary and --source only apply to generated text"
+            )
+            .into());
+        }
+        [path] => {
+            let bytes = if path == "-" {
+                td5::input::read_all(io::stdin().lock()).map_err(|e| format!("stdin: {e}"))?
+            } else {
+                std::fs::read(path).map_err(|e| format!("{path}: {e}"))?
+            };
+            encoding::decode(&bytes).text.into_owned()
+        }
+        _ => return Err(tr!("expected at most one file").into()),
+    };
+
+    writeln!(
+        out,
+        "{}\n",
+        tr!(
+            "Analyzing {bytes} bytes of text...",
+            bytes = number(text.len())
+        )
+    )?;
+
+    let (slow_stats, slow_timings) = analyze_text_slow_timed(&text, &Default::default());
+    let (fast_stats, fast_timings) = analyze_text_fast_timed(&text, &Default::default());
+    let (parallel_stats, phases) = analyze_text_parallel_timed(&text, 0, &Default::default());
+
+    writeln!(out, "{}", tr!("Results:"))?;
+    print_stats(
+        &mut out,
+        tr!("Slow"),
+        &slow_stats,
+        Some(slow_timings.total_ns),
+    )?;
+    print_timings(&mut out, &slow_timings)?;
+    print_stats(
+        &mut out,
+        tr!("Fast"),
+        &fast_stats,
+        Some(fast_timings.total_ns),
+    )?;
+    print_timings(&mut out, &fast_timings)?;
+    print_stats(
+        &mut out,
+        tr!("Parallel"),
+        &parallel_stats,
+        Some(phases.total_ns()),
+    )?;
+    print_phases(&mut out, &phases)?;
+
+    let runs: Vec<(&str, Duration, Usage)> = analyzers
+        .iter()
+        .map(|r| {
+            let (elapsed, usage) = allocations::measure(|| {
+                let start = Instant::now();
+                black_box(r.analyzer.analyze(black_box(&text)));
+                start.elapsed()
+            });
+            (r.name, elapsed, usage)
+        })
+        .collect();
+    let base = runs
+        .iter()
+        .find(|(name, ..)| *name == baseline)
+        .map_or(1.0, |(_, elapsed, _)| elapsed.as_secs_f64());
+    writeln!(out, "{}", tr!("Against {path}:", path = baseline))?;
+    writeln!(
+        out,
+        "  {:<10} {:>10} {:>10} {:>12} {:>12} {:>10}",
+        tr!("analyzer"),
+        "ms",
+        tr!("MB/s"),
+        tr!("speedup"),
+        tr!("allocations"),
+        tr!("peak MiB")
+    )?;
+    for (name, elapsed, usage) in &runs {
+        let seconds = elapsed.as_secs_f64().max(1e-9);
+        writeln!(
+            out,
+            "  {:<10} {:>10} {:>10} {:>11}x {:>12} {:>10}",
+            name,
+            decimal(seconds * 1000.0, 3),
+            decimal(text.len() as f64 / seconds / 1e6, 1),
+            decimal(base / seconds, 2),
+            number(usage.allocations),
+            decimal(usage.peak_bytes as f64 / (1024.0 * 1024.0), 2)
+        )?;
     }
+    Ok(())
 }
 
-// --------------------------- VERSION LENTE ---------------------------
-fn analyze_text_slow(text: &str) -> TextStats {
-    let start = Instant::now();
+fn run_bench(args: &Args) -> Result<ExitCode, Halt> {
+    let mut out = io::stdout().lock();
+    let format = args.value("format").unwrap_or("text");
+    if !["text", "csv", "json"].contains(&format) {
+        return Err(tr!(
+            "unknown format `{name}` (expected text, csv or json)",
+            name = format
+        )
+        .into());
+    }
+    if format != "text" && (args.has("template") || args.has("baseline")) {
+        return Err(tr!("--template and --baseline need --format text").into());
+    }
+    if args.has("energy") && (args.has("cachegrind") || args.has("cache-sweep")) {
+        return Err(tr!("--energy cannot be combined with --cachegrind or --cache-sweep").into());
+    }
+    if args.has("max-regression") && !args.has("baseline") {
+        return Err(tr!("--max-regression needs --baseline").into());
+    }
+    if args.has("cache-sweep") {
+        return run_cache_sweep(args, format).map(|()| ExitCode::SUCCESS);
+    }
+    let words = args.number("words", 200_000)?;
+    let iterations = args.number("iterations", 10)?;
+    let seed = args.number("seed", 0u64)?;
+    let input_cache = match args.value("input-cache") {
+        None => InputCache::default(),
+        Some(name) => InputCache::from_name(name).ok_or_else(|| {
+            tr!(
+                "unknown input cache mode `{name}` (expected auto, always or off)",
+                name = name
+            )
+        })?,
+    };
+    let shapes = match args.list("shapes") {
+        // Only explicit inputs when some are given and no shape was asked for.
+        None if !args.values("file").is_empty() || !args.values("corpus").is_empty() => Vec::new(),
+        None => Shape::ALL.to_vec(),
+        Some(names) => names
+            .iter()
+            .map(|n| Shape::from_name(n).ok_or_else(|| tr!("unknown shape `{name}`", name = n)))
+            .collect::<Result<_, _>>()?,
+    };
+    let mut inputs: Vec<RecordedInput> = shapes
+        .into_iter()
+        .map(|shape| RecordedInput::Generated { shape, words, seed })
+        .collect();
+    for path in args.values("file") {
+        let (text, _) = td5::input::read_transcoded(path).map_err(|e| e.to_string())?;
+        inputs.push(RecordedInput::Text {
+            name: path.to_string(),
+            text,
+        });
+    }
+    for spec in args.values("corpus") {
+        let corpus = Corpus::parse(spec).map_err(|e| e.to_string())?;
+        let text = corpus.load().map_err(|e| e.to_string())?;
+        inputs.push(RecordedInput::Text {
+            name: corpus.name(),
+            text,
+        });
+    }
+    let analyzers: Vec<String> = match args.list("analyzers") {
+        None => registry().iter().map(|r| r.name.to_string()).collect(),
+        Some(names) => names.iter().map(|n| n.to_string()).collect(),
+    };
+    let against = match args.list("against") {
+        None => Vec::new(),
+        Some(names) => names
+            .iter()
+            .map(|n| ExternalTool::parse(n).ok_or_else(|| tr!("unknown tool `{name}`", name = n)))
+            .collect::<Result<_, _>>()?,
+    };
+    if args.has("cachegrind") {
+        return run_cachegrind(args, format, &inputs, &analyzers, input_cache)
+            .map(|()| ExitCode::SUCCESS);
+    }
+    let plan = Plan {
+        inputs,
+        analyzers,
+        iterations,
+    };
 
-    let mut word_freq = HashMap::new();
-    for line in text.lines() {
-        for word in line.split_whitespace() {
-            let clean_word = word
-                .to_lowercase()
-                .chars()
-                .filter(|c| c.is_alphabetic())
-                .collect::<String>();
+    info!(
+        "{}",
+        tr!("{iterations} iterations per cell", iterations = iterations)
+    );
+    let mut report = run_plan(args, &plan, input_cache)?;
+    if !against.is_empty() {
+        let inputs: Vec<_> = plan
+            .inputs
+            .iter()
+            .map(|input| input.materialize_with(input_cache))
+            .collect();
+        for result in external::run(&against, &inputs, iterations).map_err(|e| e.to_string())? {
+            report.insert(result);
+        }
+    }
+    match (format, template(args)?) {
+        ("json", _) => writeln!(out, "{}", report.to_json())?,
+        ("csv", _) => write!(out, "{}", report.to_csv())?,
+        (_, Some(template)) => write!(out, "{}", template.render(&report.template_context()))?,
+        (_, None) => {
+            write!(out, "{}", report.grid())?;
+            if let Some(seed) = report.seed {
+                writeln!(
+                    out,
+                    "\n{}",
+                    tr!("Generated inputs: {seed}", seed = seed_label(seed))
+                )?;
+            }
+        }
+    }
 
-            if !clean_word.is_empty() {
-                *word_freq.entry(clean_word.clone()).or_insert(0) += 1;
+    let mut regressed = false;
+    if let Some(path) = args.value("baseline") {
+        let saved = td5::input::read_text(path).map_err(|e| e.to_string())?;
+        let baseline = BenchReport::from_json(&saved).map_err(|e| format!("{path}: {e}"))?;
+        print_against(
+            &mut out,
+            &tr!("Against {path}:", path = path),
+            &report,
+            &baseline,
+        )?;
+        if args.has("max-regression") {
+            let percent: f64 = args.number("max-regression", 0.0)?;
+            for cell in report.against(&baseline).regressions(percent / 100.0) {
+                regressed = true;
+                eprintln!(
+                    "{}",
+                    tr!(
+                        "regression: {input} / {analyzer} is {change}% slower than in {path} (p = {p})",
+                        input = cell.input,
+                        analyzer = cell.analyzer,
+                        change = decimal(cell.change * 100.0, 1),
+                        path = path,
+                        p = decimal(cell.p_value, 4)
+                    )
+                );
             }
         }
     }
+    if let Some(path) = args.value("save") {
+        std::fs::write(path, report.to_json() + "\n").map_err(|e| format!("{path}: {e}"))?;
+        info!("{}", tr!("saved {path}", path = path));
+    }
+    if let Some(path) = args.value("record") {
+        let bundle = Bundle { plan, report };
+        std::fs::write(path, bundle.to_json() + "\n").map_err(|e| format!("{path}: {e}"))?;
+        info!(
+            "{}",
+            tr!(
+                "recorded {path}; reproduce with `td5 replay {path}`",
+                path = path
+            )
+        );
+    }
+    Ok(match regressed {
+        true => ExitCode::from(CHECK_FAILED),
+        false => ExitCode::SUCCESS,
+    })
+}
 
-    let mut top_words = Vec::new();
-    for _ in 0..10 {
-        let mut max_word = String::new();
-        let mut max_count = 0;
+fn run_cachegrind(
+    args: &Args,
+    format: &str,
+    inputs: &[RecordedInput],
+    analyzers: &[String],
+    input_cache: InputCache,
+) -> Result<(), Halt> {
+    let mut out = io::stdout().lock();
+    if ["against", "save", "record", "template", "baseline"]
+        .iter()
+        .any(|flag| args.has(flag))
+    {
+        return Err(
+            tr!("--cachegrind cannot be combined with --against, --save, --record, --template or --baseline").into(),
+        );
+    }
+    for name in analyzers {
+        if analyzer::find(name).is_none() {
+            return Err(tr!("unknown analyzer `{name}`", name = name).into());
+        }
+    }
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let inputs: Vec<_> = inputs
+        .iter()
+        .map(|input| input.materialize_with(input_cache))
+        .collect();
+    info!("{}", tr!("running each cell twice under valgrind"));
+    let results = cachegrind::run(&exe, analyzers, &inputs).map_err(|e| e.to_string())?;
+    match format {
+        "json" => writeln!(out, "{}", cachegrind::to_json_value(&results))?,
+        "csv" => write!(out, "{}", cachegrind::to_csv(&results))?,
+        _ => {
+            writeln!(
+                out,
+                "{:<16} {:<10} {:>14} {:>14} {:>12} {:>12}",
+                tr!("input"),
+                tr!("analyzer"),
+                tr!("instructions"),
+                tr!("data accesses"),
+                tr!("L1 misses"),
+                tr!("LL misses")
+            )?;
+            for r in &results {
+                let c = &r.costs;
+                writeln!(
+                    out,
+                    "{:<16} {:<10} {:>14} {:>14} {:>12} {:>12}",
+                    r.input, r.analyzer, c.instructions, c.data_accesses, c.l1_misses, c.ll_misses
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
 
-        for (word, count) in &word_freq {
-            let mut found = false;
-            for (existing_word, _) in &top_words {
-                if word == existing_word {
-                    found = true;
-                    break;
+fn run_cache_sweep(args: &Args, format: &str) -> Result<(), Halt> {
+    let mut out = io::stdout().lock();
+    if [
+        "against",
+        "save",
+        "record",
+        "template",
+        "baseline",
+        "cachegrind",
+    ]
+    .iter()
+    .any(|flag| args.has(flag))
+    {
+        return Err(tr!("--cache-sweep cannot be combined with --against, --save, --record, --template, --baseline or --cachegrind").into());
+    }
+    let vocabularies = match args.list("vocabularies") {
+        None => working_set::DEFAULT_VOCABULARIES.to_vec(),
+        Some(sizes) => sizes
+            .iter()
+            .map(|s| {
+                td5::slice::parse_size(s)
+                    .filter(|&n| n > 0)
+                    .map(|n| n as usize)
+                    .ok_or_else(|| {
+                        tr!(
+                            "expected a vocabulary size such as 64K, got `{value}`",
+                            value = s
+                        )
+                    })
+            })
+            .collect::<Result<_, _>>()?,
+    };
+    let analyzers = args.list("analyzers").unwrap_or_else(|| vec!["fast"]);
+    let words = args.number("words", 1_000_000)?;
+    let iterations = args.number("iterations", 10)?;
+    let seed = args.number("seed", 0u64)?;
+    let levels = working_set::cache_levels();
+    let steps = working_set::sweep(&analyzers, &vocabularies, words, iterations, seed)
+        .map_err(|e| e.to_string())?;
+    match format {
+        "json" => {
+            let mut doc = working_set::to_json_value(&levels, &steps);
+            doc.set("seed", td5::seed::to_json_value(seed));
+            writeln!(out, "{doc}")?;
+        }
+        "csv" => {
+            writeln!(
+                out,
+                "vocabulary,words,table_bytes,fits_in,analyzer,ns_per_word"
+            )?;
+            for s in &steps {
+                let fits = working_set::fits_in(&levels, s.table_bytes).map_or("", |l| &l.name);
+                for (name, ns) in &s.ns_per_word {
+                    writeln!(
+                        out,
+                        "{},{},{},{fits},{name},{ns:.3}",
+                        s.vocabulary, s.words, s.table_bytes
+                    )?;
                 }
             }
-
-            if !found && *count > max_count {
-                max_word = word.clone();
-                max_count = *count;
+        }
+        _ => {
+            if levels.is_empty() {
+                writeln!(out, "{}", tr!("cache sizes: unknown"))?;
+            } else {
+                let sizes: Vec<String> = levels
+                    .iter()
+                    .map(|l| format!("{} {}", l.name, td5::slice::format_size(l.bytes)))
+                    .collect();
+                writeln!(
+                    out,
+                    "{}",
+                    tr!("cache sizes: {sizes}", sizes = sizes.join(", "))
+                )?;
+            }
+            writeln!(
+                out,
+                "{}",
+                tr!("Generated inputs: {seed}", seed = seed_label(seed))
+            )?;
+            write!(
+                out,
+                "{:>10} {:>10} {:>8}",
+                tr!("vocabulary"),
+                tr!("table"),
+                tr!("fits in")
+            )?;
+            for name in &analyzers {
+                write!(out, " {:>12}", format!("{name} ns/w"))?;
+            }
+            writeln!(out)?;
+            for s in &steps {
+                let fits = working_set::fits_in(&levels, s.table_bytes)
+                    .map_or(tr!("RAM"), |l| l.name.as_str());
+                write!(
+                    out,
+                    "{:>10} {:>10} {:>8}",
+                    s.vocabulary,
+                    td5::slice::format_size(s.table_bytes),
+                    fits
+                )?;
+                for (_, ns) in &s.ns_per_word {
+                    write!(out, " {ns:>12.2}")?;
+                }
+                writeln!(out)?;
             }
         }
+    }
+    Ok(())
+}
 
-        if max_count > 0 {
-            top_words.push((max_word, max_count));
+fn run_probe(args: &Args) -> Result<(), String> {
+    let name = args.value("analyzer").unwrap_or("fast");
+    let registered =
+        analyzer::find(name).ok_or_else(|| tr!("unknown analyzer `{name}`", name = name))?;
+    let [path] = &args.positional[..] else {
+        return Err(tr!("expected exactly one file").into());
+    };
+    let (text, _) = td5::input::read_transcoded(path).map_err(|e| e.to_string())?;
+    if args.has("skip-analysis") {
+        return Ok(());
+    }
+    match args.value("stack-size") {
+        None => {
+            black_box(registered.analyzer.analyze(black_box(&text)));
+        }
+        Some(_) => {
+            let size = args.number("stack-size", 0usize)?;
+            // A stack overflow aborts the process, which is the failure
+            // `selftest --stack` looks for.
+            std::thread::scope(|s| {
+                std::thread::Builder::new()
+                    .stack_size(size)
+                    .spawn_scoped(s, || {
+                        black_box(registered.analyzer.analyze(black_box(&text)))
+                    })
+                    .map(|thread| thread.join())
+                    .map_err(|e| e.to_string())
+            })?
+            .map_err(|_| tr!("the analysis panicked"))?;
         }
     }
+    Ok(())
+}
 
-    let mut char_count = 0;
-    for line in text.lines() {
-        for ch in line.chars() {
-            if ch.is_alphabetic() {
-                char_count += 1;
-            }
+/// Exit status when an `--assert` check fails or `lint` finds something,
+/// distinct from errors (1) and usage mistakes (2).
+const CHECK_FAILED: u8 = 3;
+
+/// Exit status when some inputs of a run could not be read but others were
+/// analyzed.
+const PARTIAL_FAILURE: u8 = 4;
+
+/// What a multi-input command does when one input cannot be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OnError {
+    /// Stop at the first failure, like any other error.
+    Abort,
+    /// Go on, and exit with `PARTIAL_FAILURE` if anything failed.
+    Continue,
+    /// Go on, and exit successfully unless every input failed.
+    Ignore,
+}
+
+impl OnError {
+    fn parse(args: &Args) -> Result<OnError, String> {
+        match args.value("on-error").unwrap_or("continue") {
+            "abort" => Ok(OnError::Abort),
+            "continue" => Ok(OnError::Continue),
+            "ignore" => Ok(OnError::Ignore),
+            name => Err(tr!(
+                "unknown error policy `{name}` (expected abort, continue or ignore)",
+                name = name
+            )),
         }
     }
+}
 
-    let mut all_words = Vec::new();
-    for line in text.lines() {
-        for word in line.split_whitespace() {
-            let clean = word
-                .to_lowercase()
-                .chars()
-                .filter(|c| c.is_alphabetic())
-                .collect::<String>();
-            if !clean.is_empty() {
-                all_words.push(clean);
-            }
-        }
+/// Why a command stopped early: an error to report, or standard output
+/// closed by its reader (`td5 analyze big.txt | head`), which ends the run
+/// quietly and successfully, as of any Unix filter.
+enum Halt {
+    Error(String),
+    Closed,
+}
+
+impl From<String> for Halt {
+    fn from(message: String) -> Self {
+        Halt::Error(message)
     }
+}
 
-    all_words.sort_by(|a, b| b.len().cmp(&a.len()));
-    let longest_words: Vec<String> = all_words.iter().take(5).cloned().collect();
+impl From<&str> for Halt {
+    fn from(message: &str) -> Self {
+        Halt::Error(message.to_string())
+    }
+}
 
-    TextStats {
-        word_count: word_freq.len(),
-        char_count,
-        top_words,
-        longest_words,
-        time_ns: start.elapsed().as_nanos(),
+/// Only writes to standard output fail with a bare `io::Error`.
+impl From<io::Error> for Halt {
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::BrokenPipe => Halt::Closed,
+            _ => Halt::Error(format!("stdout: {e}")),
+        }
     }
 }
 
-// --------------------------- VERSION RAPIDE -------------------------
-fn analyze_text_fast(text: &str) -> TextStats {
-    let start = Instant::now();
+/// The inputs of a run that could not be read, with why.
+struct Failures {
+    on_error: OnError,
+    errors: Vec<(String, String)>,
+}
 
-    // Unicode fallback
-    if !text.is_ascii() {
-        let mut word_freq: HashMap<String, usize, FastHasher> =
-            HashMap::with_hasher(FastHasher::default());
-        let mut char_count = 0usize;
-        for token in text.split_whitespace() {
-            let mut clean = String::with_capacity(token.len());
-            for ch in token.chars() {
-                if ch.is_alphabetic() {
-                    char_count += 1;
-                    for lower in ch.to_lowercase() {
-                        clean.push(lower);
-                    }
-                }
-            }
-            if !clean.is_empty() {
-                *word_freq.entry(clean).or_insert(0) += 1;
-            }
+impl Failures {
+    fn new(on_error: OnError) -> Self {
+        Failures {
+            on_error,
+            errors: Vec::new(),
         }
+    }
 
-        let mut freq_vec: Vec<(String, usize)> = word_freq.into_iter().collect();
-        let unique = freq_vec.len();
-        freq_vec.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
-        let top_words = freq_vec.iter().take(10).cloned().collect();
+    fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
 
-        let mut longest_words: Vec<String> = freq_vec.iter().map(|(w, _)| w.clone()).collect();
-        longest_words.sort_unstable_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
-        longest_words.truncate(5);
+    /// Reports `input`'s failure, or returns it under `--on-error abort`.
+    fn record(&mut self, input: &str, message: String) -> Result<(), String> {
+        if self.on_error == OnError::Abort {
+            return Err(message);
+        }
+        eprintln!("{}", tr!("error: {message}", message = message));
+        self.errors.push((input.to_string(), message));
+        Ok(())
+    }
 
-        return TextStats {
-            word_count: unique,
-            char_count,
-            top_words,
-            longest_words,
-            time_ns: start.elapsed().as_nanos(),
-        };
+    /// The summary after a text report, out of `total` inputs.
+    fn print(&self, out: &mut impl Write, total: usize) -> io::Result<()> {
+        writeln!(
+            out,
+            "{}",
+            tr!(
+                "{failed} of {total} inputs failed:",
+                failed = number(self.errors.len()),
+                total = number(total)
+            )
+        )?;
+        for (_, message) in &self.errors {
+            writeln!(out, "  {message}")?;
+        }
+        Ok(())
+    }
+
+    fn status(&self, total: usize) -> ExitCode {
+        let all = self.errors.len() >= total;
+        match self.on_error {
+            _ if self.errors.is_empty() => ExitCode::SUCCESS,
+            _ if all => ExitCode::FAILURE,
+            OnError::Ignore => ExitCode::SUCCESS,
+            OnError::Abort | OnError::Continue => ExitCode::from(PARTIAL_FAILURE),
+        }
     }
+}
+
+/// A ZIP archive named `.epub` or starting with the EPUB `mimetype` entry.
+fn is_epub(path: &str, bytes: &[u8]) -> bool {
+    bytes.starts_with(b"PK\x03\x04")
+        && (path.to_ascii_lowercase().ends_with(".epub")
+            || bytes.get(30..58) == Some(b"mimetypeapplication/epub+zip"))
+}
+
+/// The book's text and each chapter's title and byte offset in it.
+#[cfg(feature = "epub")]
+fn read_epub(bytes: &[u8]) -> Result<(String, Vec<(String, usize)>), String> {
+    let book = td5::epub::Book::parse(bytes).map_err(|e| e.to_string())?;
+    let (text, starts) = book.text();
+    let titles = book.chapters.into_iter().map(|c| c.title);
+    Ok((text, titles.zip(starts).collect()))
+}
+
+#[cfg(not(feature = "epub"))]
+fn read_epub(_: &[u8]) -> Result<(String, Vec<(String, usize)>), String> {
+    Err(tr!("reading EPUB needs a build with `--features epub`").into())
+}
+
+/// A file named `.mbox`, or starting with a `From <sender> <date>` line
+/// followed by a header.
+fn is_mbox(path: &str, text: &str) -> bool {
+    let mut lines = text.lines();
+    let envelope = lines
+        .next()
+        .and_then(|line| line.strip_prefix("From "))
+        .is_some_and(|rest| rest.split_whitespace().count() >= 2);
+    let header = lines.next().is_some_and(|line| {
+        line.split_once(':')
+            .is_some_and(|(name, _)| !name.is_empty() && name.bytes().all(|b| b.is_ascii_graphic()))
+    });
+    path.to_ascii_lowercase().ends_with(".mbox") || (envelope && header)
+}
+
+/// The mailbox's messages, their senders as speakers.
+#[cfg(feature = "mbox")]
+fn read_mbox(text: &str) -> Result<Chat, String> {
+    Ok(td5::mbox::Mailbox::parse(text).chat())
+}
+
+#[cfg(not(feature = "mbox"))]
+fn read_mbox(_: &str) -> Result<Chat, String> {
+    Err(tr!("reading mbox needs a build with `--features mbox`").into())
+}
 
-    // ASCII hot path: specialize for lowercase letters + spaces (generator case).
-    let is_simple_lower_ascii = text
-        .as_bytes()
+fn chapter_sections<'a>(text: &'a str, chapters: &[(String, usize)]) -> Vec<Section<'a>> {
+    let mut line = 1;
+    let mut previous = 0;
+    chapters
         .iter()
-        .all(|b| *b == b' ' || (b.is_ascii_alphabetic() && b.is_ascii_lowercase()));
-    if is_simple_lower_ascii {
-        let mut hot_counts = [0usize; 10];
-        let mut seen_non_hot = false;
-        let mut word_freq: Option<HashMap<String, usize, FastHasher>> = None;
-        let mut char_count: usize = 0;
-
-        let mut buf = [0u8; 32];
-        let mut len = 0usize;
-        for &b in text.as_bytes() {
-            if b == b' ' {
-                if len > 0 {
-                    let word = &buf[..len];
-                    if let Some(idx) = match_hot_lower(word) {
-                        hot_counts[idx] += 1;
-                    } else {
-                        seen_non_hot = true;
-                        let map = word_freq.get_or_insert_with(|| {
-                            HashMap::with_capacity_and_hasher(
-                                text.len() / 64,
-                                FastHasher::default(),
-                            )
-                        });
-                        // SAFETY: word is lowercase ASCII
-                        let key = unsafe { String::from_utf8_unchecked(word.to_vec()) };
-                        map.entry(key).and_modify(|c| *c += 1).or_insert(1);
-                    }
-                    len = 0;
+        .enumerate()
+        .map(|(i, (title, start))| {
+            line += text[previous..*start].matches('\n').count();
+            previous = *start;
+            let end = chapters.get(i + 1).map_or(text.len(), |(_, next)| *next);
+            Section {
+                title: Some(title.clone()),
+                line,
+                text: &text[*start..end],
+            }
+        })
+        .collect()
+}
+
+fn template(args: &Args) -> Result<Option<Template>, String> {
+    let Some(path) = args.value("template") else {
+        return Ok(None);
+    };
+    let source = td5::input::read_text(path).map_err(|e| e.to_string())?;
+    Template::parse(&source)
+        .map(Some)
+        .map_err(|e| format!("{path}: {e}"))
+}
+
+fn run_analyze(args: &Args) -> Result<ExitCode, Halt> {
+    let assertions = args
+        .values("assert")
+        .into_iter()
+        .map(Assertion::parse)
+        .collect::<td5::Result<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+    let mut failed = 0;
+    let format = args.value("format").unwrap_or("text");
+    if !["text", "json", "yaml", "csv", "protobuf", "msgpack", "cbor"].contains(&format) {
+        return Err(tr!(
+            "unknown format `{name}` (expected text, json, yaml, csv, protobuf, msgpack or cbor)",
+            name = format
+        )
+        .into());
+    }
+    check_built(format)?;
+    let template = template(args)?;
+    let timing = time_policy(args)?;
+    let window = args
+        .value("window")
+        .map(WindowSize::parse)
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    let splitter = args
+        .value("split-on")
+        .map(Splitter::parse)
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    if splitter.is_some() && window.is_some() {
+        return Err(tr!("--split-on and --window cannot be combined").into());
+    }
+    let sample = args
+        .value("sample")
+        .map(td5::sample::parse_fraction)
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    if sample.is_some() && (splitter.is_some() || window.is_some()) {
+        return Err(tr!("--sample cannot be combined with --split-on or --window").into());
+    }
+    if args.has("seed") && sample.is_none() {
+        return Err(tr!("--seed needs --sample").into());
+    }
+    let seed = args.number("seed", 0u64)?;
+    let range = match (args.value("range"), args.value("lines")) {
+        (Some(_), Some(_)) => return Err(tr!("--range and --lines cannot be combined").into()),
+        (Some(spec), None) => Some(Range::parse_bytes(spec).map_err(|e| e.to_string())?),
+        (None, Some(spec)) => Some(Range::parse_lines(spec).map_err(|e| e.to_string())?),
+        (None, None) => None,
+    };
+    if range.is_some() && sample.is_some() {
+        return Err(tr!("--sample cannot be combined with --range or --lines").into());
+    }
+    let limit = match (args.value("first-n-words"), args.value("last-n-words")) {
+        (Some(_), Some(_)) => {
+            return Err(tr!("--first-n-words and --last-n-words cannot be combined").into());
+        }
+        (Some(_), None) => Some(WordLimit::First(args.number("first-n-words", 0)?)),
+        (None, Some(_)) => Some(WordLimit::Last(args.number("last-n-words", 0)?)),
+        (None, None) => None,
+    };
+    if limit.is_some() && (range.is_some() || sample.is_some()) {
+        return Err(tr!(
+            "--first-n-words and --last-n-words cannot be combined with --sample, --range or --lines"
+        )
+        .into());
+    }
+    if splitter.is_some() && format == "protobuf" {
+        return Err(tr!("--split-on does not support protobuf output").into());
+    }
+    if splitter.is_some() && format == "csv" {
+        return Err(tr!("--split-on does not support csv output").into());
+    }
+    let dictionary = match args.value("dictionary") {
+        Some(path) => {
+            let (source, _) = td5::input::read_transcoded(path).map_err(|e| e.to_string())?;
+            Some(Dictionary::parse(&source))
+        }
+        None => None,
+    };
+    let only_known = args.has("only-known");
+    if only_known && dictionary.is_none() {
+        return Err(tr!("--only-known needs --dictionary").into());
+    }
+    if only_known && window.is_some() {
+        return Err(tr!("--only-known and --window cannot be combined").into());
+    }
+    let pii_samples = args.number("pii-samples", 0)?;
+    // With `auto`, each input's language is found from its extension.
+    let keywords_by_extension = args.value("keywords") == Some("auto");
+    let wanted = Wanted {
+        pii: (args.has("pii") || pii_samples > 0).then_some(pii_samples),
+        distribution: args.has("distribution"),
+        dispersion: match args.value("dispersion") {
+            Some(_) => Some(args.number("dispersion", 0)?),
+            None => None,
+        },
+        categories: match args.value("categories") {
+            Some(_) => Some(args.number("categories", 0)?),
+            None => None,
+        },
+        quotes: match args.value("quotes") {
+            Some(spec) => Some(td5::quotes::parse_quotes(spec).map_err(|e| e.to_string())?),
+            None => args.has("dialogue").then(|| DEFAULT_QUOTES.to_vec()),
+        },
+        acronyms: args.has("acronyms"),
+        code: match args.value("keywords") {
+            Some(name) if !keywords_by_extension => {
+                Some(Language::parse(name).map_err(|e| e.to_string())?)
+            }
+            _ => None,
+        },
+        units: args.has("units"),
+        instrument: args.has("instrument"),
+    };
+    let mut pipeline = pipeline(args)?;
+    if (args.has("pipeline") || args.has("merge-map")) && (only_known || window.is_some()) {
+        return Err(tr!(
+            "--pipeline and --merge-map cannot be combined with --only-known or --window"
+        )
+        .into());
+    }
+    let plugins = plugins(args)?;
+    for plugin in plugins.iter().filter(|p| p.filters_words()) {
+        if only_known || window.is_some() {
+            return Err(tr!(
+                "{name}: plugin word filters cannot be combined with --only-known or --window",
+                name = plugin.name()
+            )
+            .into());
+        }
+        pipeline
+            .get_or_insert_with(Pipeline::default)
+            .stages
+            .push(Stage::Filter(Filter(Arc::clone(plugin))));
+    }
+    let threads = match args.value("threads") {
+        Some(_) => Some(args.number("threads", 0)?),
+        None => None,
+    };
+    let progress = args.has("progress");
+    let default = if threads.is_some() || progress {
+        "parallel"
+    } else {
+        "fast"
+    };
+    let name = args.value("analyzer").unwrap_or(default);
+    if progress && !["fast", "slow", "parallel", "sharded"].contains(&name) {
+        return Err(tr!(
+            "--progress needs an analyzer that reports progress (fast, slow, parallel or sharded), not `{name}`",
+            name = name
+        ).into());
+    }
+    let mut analyzer =
+        analyzer::find(name).ok_or_else(|| tr!("unknown analyzer `{name}`", name = name))?;
+    if let Some(threads) = threads {
+        analyzer = analyzer::find_threaded(name, threads).ok_or_else(|| {
+            tr!(
+                "--threads needs a multi-threaded analyzer (parallel or sharded), not `{name}`",
+                name = name
+            )
+        })?;
+    }
+    let mut config = AnalyzerConfig::new();
+    if args.value("ngrams").is_some() {
+        let n = args.number("ngrams", 0)?;
+        if !(2..=3).contains(&n) {
+            return Err(tr!("--ngrams takes 2 or 3, not {n}", n = n).into());
+        }
+        config = config.ngrams(n);
+    }
+    if let Some(stopwords) = stopwords(args)? {
+        config = config.stopwords(stopwords);
+    }
+    config = stemming(args, config)?;
+    config = config.control_chars(control_chars(args)?);
+    config = config
+        .normalization(normalization(args)?)
+        .fold_diacritics(args.has("fold-diacritics"));
+    if let Some(spec) = args.value("tokens") {
+        config = config.tokenizer(td5::tokenizer::spec(spec).map_err(|e| e.to_string())?);
+    }
+    let detect = auto(args)?;
+    if detect.is_some() {
+        if args.has("stopwords") || args.has("stem") {
+            return Err(tr!("--auto cannot be combined with --stopwords or --stem").into());
+        }
+        if only_known || window.is_some() || pipeline.is_some() {
+            return Err(tr!(
+                "--auto cannot be combined with --only-known, --window or a word pipeline"
+            )
+            .into());
+        }
+        if !["fast", "slow", "parallel", "sharded"].contains(&name) {
+            return Err(tr!(
+                "--auto needs an analyzer that takes options (fast, slow, parallel or sharded), not `{name}`",
+                name = name
+            ).into());
+        }
+    }
+    // Drawn only where someone can watch it.
+    let bar = (progress && io::stderr().is_terminal() && log::enabled(log::Level::Normal))
+        .then(|| Arc::new(ProgressBar::default()));
+    if let Some(bar) = &bar {
+        let bar = Arc::clone(bar);
+        config = config.on_progress(move |done, words| bar.draw(done, words));
+    }
+    if config != AnalyzerConfig::default() {
+        analyzer = analyzer::configured(name, threads.unwrap_or(0), config.clone())
+            .ok_or_else(|| tr!("unknown analyzer `{name}`", name = name))?;
+    }
+    let mut aggregate = args.has("aggregate").then(|| Aggregate::new(&config));
+    if aggregate.is_some() && (sample.is_some() || window.is_some()) {
+        return Err(tr!("--aggregate cannot be combined with --sample or --window").into());
+    }
+    if aggregate.is_some() && format == "protobuf" {
+        return Err(tr!("--aggregate does not support protobuf output").into());
+    }
+    let jobs = match args.value("jobs") {
+        Some(_) => Some(args.number("jobs", 0)?),
+        None => None,
+    };
+    if jobs.is_some()
+        && let Some(flag) = SEQUENTIAL_FLAGS
+            .iter()
+            .copied()
+            .find(|flag| args.has(flag))
+            .or((dictionary.is_some() && !only_known).then_some("dictionary"))
+    {
+        return Err(tr!("--jobs cannot be combined with --{flag}", flag = flag).into());
+    }
+    let analyze = |text: &str| {
+        if let Some(bar) = &bar {
+            bar.begin(text.len());
+        }
+        let stats = match (&dictionary, &pipeline) {
+            (Some(dictionary), _) if only_known => dictionary.analyze_known(text),
+            (_, Some(pipeline)) => pipeline.analyze(text),
+            _ => match detect {
+                Some(detect) => {
+                    let language = detect(text);
+                    let mut stats = analyzer::configured(
+                        name,
+                        threads.unwrap_or(0),
+                        auto_config(&config, language),
+                    )
+                    .expect("--auto takes a configurable analyzer")
+                    .analyzer
+                    .analyze(text);
+                    stats.detected_language = language.map(str::to_string);
+                    stats
                 }
-            } else {
-                char_count += 1;
-                buf[len] = b;
-                len += 1;
+                None => analyzer.analyzer.analyze(text),
+            },
+        };
+        if let Some(bar) = &bar {
+            bar.clear();
+        }
+        stats
+    };
+    let mut paths = args.positional.clone();
+    if let Some(list) = args.value("links") {
+        let text = td5::input::read_text(std::path::Path::new(list)).map_err(|e| e.to_string())?;
+        paths.extend(
+            text.lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                .map(String::from),
+        );
+    }
+    if paths.is_empty() {
+        paths.push(String::from("-"));
+    }
+    let on_error = OnError::parse(args)?;
+    let mut failures = Failures::new(on_error);
+    let skip_rules = skip_rules(args)?;
+    let mut skipped: Vec<(String, Skip)> = Vec::new();
+    let mut skip = |path: &str, reason: Skip| {
+        info!(
+            "{}",
+            tr!("{path}: skipped ({reason})", path = path, reason = reason)
+        );
+        skipped.push((path.to_string(), reason));
+    };
+    let mut inputs = Vec::new();
+    for path in paths {
+        if path == "-" || fetch::is_url(&path) {
+            inputs.push(path);
+            continue;
+        }
+        match td5::input::files(&path) {
+            Ok(files) => inputs.extend(files.iter().map(|f| f.display().to_string())),
+            Err(e) => failures.record(&path, e.to_string())?,
+        }
+    }
+    let report = args.value("report");
+    if report.is_some() && inputs.len() + failures.errors.len() != 1 {
+        return Err(tr!("--report needs exactly one input").into());
+    }
+    if report.is_some() && window.is_some() {
+        return Err(tr!("--report and --window cannot be combined").into());
+    }
+    let listed = inputs.len() + failures.errors.len();
+    let mut out = io::stdout().lock();
+    let mut fetcher = Fetcher::new(fetch_policy(args)?);
+    let reading = Reading {
+        limit,
+        sample,
+        seed,
+        range,
+    };
+    if format == "csv" {
+        writeln!(out, "{}", td5::csv::HEADER)?;
+    }
+    // With --jobs, the inputs are all analyzed here and none are left for
+    // the loop below.
+    let inputs = match jobs {
+        Some(jobs) => {
+            if let Some(path) = inputs.iter().find(|p| *p == "-" || fetch::is_url(p)) {
+                return Err(tr!("--jobs needs local files, not `{path}`", path = path).into());
             }
+            let paths: Vec<PathBuf> = inputs.iter().map(PathBuf::from).collect();
+            let options = FilesOptions {
+                threads: jobs,
+                skip: skip_rules.clone(),
+            };
+            td5::files::analyze_files(
+                &paths,
+                &analyze,
+                &options,
+                aggregate.as_mut(),
+                |path, result| {
+                    let path = path.display().to_string();
+                    match result {
+                        FileResult::Analyzed(stats, time_ns) => {
+                            write_stats(
+                                &mut out,
+                                format,
+                                template.as_ref(),
+                                timing,
+                                &path,
+                                &stats,
+                                time_ns,
+                            )?;
+                            failed += check_assertions(&assertions, &path, &stats);
+                        }
+                        FileResult::Skipped(reason) => skip(&path, reason),
+                        FileResult::Failed(e) => failures.record(&path, e.to_string())?,
+                    }
+                    Ok::<(), Halt>(())
+                },
+            )?;
+            Vec::new()
         }
-        if len > 0 {
-            let word = &buf[..len];
-            if let Some(idx) = match_hot_lower(word) {
-                hot_counts[idx] += 1;
-            } else {
-                seen_non_hot = true;
-                let map = word_freq.get_or_insert_with(|| {
-                    HashMap::with_capacity_and_hasher(text.len() / 64, FastHasher::default())
-                });
-                let key = unsafe { String::from_utf8_unchecked(word.to_vec()) };
-                map.entry(key).and_modify(|c| *c += 1).or_insert(1);
-            }
-        }
-
-        const HOT: [&str; 10] = [
-            "rust",
-            "performance",
-            "optimization",
-            "memory",
-            "speed",
-            "efficiency",
-            "benchmark",
-            "algorithm",
-            "data",
-            "structure",
-        ];
-        if !seen_non_hot {
-            const HOT_ORDER: [usize; 10] = [7, 6, 8, 5, 3, 2, 1, 0, 4, 9]; // alphabetical
-            const LONGEST_ORDER: [usize; 5] = [2, 1, 5, 7, 6];
-            let mut top_words = Vec::with_capacity(10);
-            for &idx in &HOT_ORDER {
-                top_words.push((HOT[idx].to_string(), hot_counts[idx]));
-            }
-            let mut longest_words = Vec::with_capacity(5);
-            for &idx in &LONGEST_ORDER {
-                if hot_counts[idx] > 0 {
-                    longest_words.push(HOT[idx].to_string());
+        None => inputs,
+    };
+    for path in &inputs {
+        if path != "-"
+            && !fetch::is_url(path)
+            && let Some(too) = std::fs::metadata(path)
+                .ok()
+                .and_then(|meta| skip_rules.check_size(meta.len()))
+        {
+            skip(path, too);
+            continue;
+        }
+        let Input {
+            bytes,
+            estimate,
+            slice,
+            buffer,
+        } = match read_input(path, &reading, &mut fetcher, &*analyzer.analyzer) {
+            Ok(input) => input,
+            Err(e) => {
+                failures.record(path, e)?;
+                continue;
+            }
+        };
+        let epub = is_epub(path, &bytes);
+        if let Some(binary) = skip_rules.check_content(&bytes).filter(|_| !epub) {
+            skip(path, binary);
+            continue;
+        }
+        let (decoded, chapters) = if epub {
+            let (text, chapters) = match read_epub(&bytes) {
+                Ok(book) => book,
+                Err(e) => {
+                    failures.record(path, format!("{path}: {e}"))?;
+                    continue;
+                }
+            };
+            let decoded = Decoded {
+                text: Cow::Owned(text),
+                encoding: Encoding::Utf8,
+                had_bom: false,
+                replaced: 0,
+            };
+            (decoded, chapters)
+        } else {
+            (encoding::decode(&bytes), Vec::new())
+        };
+        if decoded.encoding != Encoding::Utf8 {
+            info!(
+                "{}",
+                tr!(
+                    "{path}: decoded as {encoding}",
+                    path = path,
+                    encoding = decoded.encoding
+                )
+            );
+        }
+        if decoded.replaced > 0 {
+            warn!(
+                "{}",
+                tr!(
+                    "{path}: {count} undecodable sequences replaced",
+                    path = path,
+                    count = decoded.replaced
+                )
+            );
+        }
+        debug!("{path}: {} bytes, analyzer {}", bytes.len(), analyzer.name);
+        let subtitles = if args.has("subtitles") || subtitles::detect(path, &decoded.text) {
+            match Subtitles::parse(&decoded.text) {
+                Ok(subtitles) => Some(subtitles),
+                Err(e) => {
+                    failures.record(path, format!("{path}: {e}"))?;
+                    continue;
                 }
             }
-            return TextStats {
-                word_count: top_words.len(),
-                char_count,
-                top_words,
-                longest_words,
-                time_ns: start.elapsed().as_nanos(),
+        } else {
+            None
+        };
+        let mail = is_mbox(path, &decoded.text);
+        let chat = if mail || args.has("chat") {
+            let chat = if mail {
+                read_mbox(&decoded.text)
+            } else {
+                Chat::parse(&decoded.text).map_err(|e| e.to_string())
             };
+            match chat {
+                Ok(chat) => Some(chat),
+                Err(e) => {
+                    failures.record(path, format!("{path}: {e}"))?;
+                    continue;
+                }
+            }
+        } else {
+            None
+        };
+        let source = match (&subtitles, &chat) {
+            (Some(subtitles), _) => Cow::Owned(subtitles.dialogue()),
+            (None, Some(chat)) => Cow::Owned(chat.text()),
+            (None, None) => Cow::Borrowed(&*decoded.text),
+        };
+        let (social, text) = if args.has("social") {
+            let (social, rest) = SocialStats::extract(&source);
+            (Some(social), Cow::Owned(rest))
+        } else {
+            (None, Cow::Borrowed(&*source))
+        };
+        if let Err(e) = config.check_control_chars(&text) {
+            failures.record(path, format!("{path}: {e}"))?;
+            continue;
+        }
+        let start = Instant::now();
+        let mut stats = match window {
+            Some(size) => {
+                let mut window = SlidingWindow::new(size);
+                window.push_lines(&text);
+                window.stats()
+            }
+            None => analyze(&text),
+        };
+        let time_ns = start.elapsed().as_nanos();
+        // One table for everything that needs more than the top words.
+        let freq = (dictionary.is_some() || report.is_some() || wanted.needs_frequencies())
+            .then(|| WordFrequency::from_text(&text));
+        if let (Some(dictionary), Some(freq)) = (&dictionary, &freq) {
+            info!(
+                "{}",
+                tr!(
+                    "{path}: {count} words not in the dictionary",
+                    path = path,
+                    count = dictionary.unknown(freq).len()
+                )
+            );
+        }
+        stats.diagnostics.invalid_utf8 += decoded.replaced;
+        let mut warnings = Warnings::from_stats(&stats, &AnalyzerConfig::default());
+        if let Some(estimate) = &estimate {
+            estimate.extrapolate(&mut stats);
+            warnings.sampled(estimate);
+        }
+        if let Some(short) = skip_rules.check_words(&stats) {
+            skip(path, short);
+            continue;
+        }
+        if let Some(aggregate) = &mut aggregate {
+            aggregate.add(&text);
+        }
+        if let (Some(out), Some(freq)) = (report, &freq) {
+            let html = Report::new(path, &stats, freq).to_html();
+            std::fs::write(out, html).map_err(|e| format!("{out}: {e}"))?;
+            info!("{}", tr!("report written to {path}", path = out));
+        }
+        if stats.diagnostics.long_tokens > 0 {
+            info!(
+                "{}",
+                tr!(
+                    "{path}: {count} over-long tokens",
+                    path = path,
+                    count = stats.diagnostics.long_tokens
+                )
+            );
+        }
+        if stats.diagnostics.control_chars > 0 {
+            info!(
+                "{}",
+                tr!(
+                    "{path}: {count} control characters",
+                    path = path,
+                    count = stats.diagnostics.control_chars
+                )
+            );
+        }
+        let sections = match &splitter {
+            Some(splitter) => splitter.split(&text),
+            None => chapter_sections(&text, &chapters),
+        };
+        let sections: Vec<(Section, TextStats)> = sections
+            .into_iter()
+            .map(|section| {
+                let stats = analyze(section.text);
+                (section, stats)
+            })
+            .collect();
+        let wanted = Wanted {
+            code: wanted
+                .code
+                .or_else(|| keywords_by_extension.then(|| Language::from_path(path.as_ref()))?),
+            ..wanted.clone()
+        };
+        let mut extras = Extras::of(&text, &source, freq.as_ref(), &wanted, &config);
+        extras.social = social;
+        extras.speech = subtitles.as_ref().map(Subtitles::speech_rate);
+        extras.speakers = chat.as_ref().map(Chat::speakers);
+        extras.mail = mail;
+        extras.buffer = buffer.filter(|_| args.has("instrument"));
+        extras.plugins = plugins
+            .iter()
+            .flat_map(|plugin| {
+                plugin
+                    .metrics(&text)
+                    .into_iter()
+                    .map(|(name, value)| (format!("{}.{name}", plugin.name()), value))
+            })
+            .collect();
+        extras.estimate = estimate;
+        extras.range = slice;
+        extras.warnings = warnings;
+        let section_docs = || {
+            sections.iter().map(|(section, stats)| {
+                let mut doc = stats.to_json_value();
+                doc.set("source", path.as_str().into());
+                doc.set(
+                    "section",
+                    section.title.as_deref().map_or(Json::Null, Json::from),
+                );
+                doc.set("line", section.line.into());
+                timing.apply(&mut doc);
+                doc
+            })
+        };
+        let doc = || {
+            let mut doc = stats.to_json_value();
+            doc.set("time_ns", time_ns.into());
+            doc.set("source", path.as_str().into());
+            extras.add_to(&mut doc);
+            timing.apply(&mut doc);
+            doc
+        };
+        if let Some(template) = &template {
+            for doc in section_docs() {
+                write!(out, "{}", template.render(&doc))?;
+            }
+            write!(out, "{}", template.render(&doc()))?;
+        } else if ["json", "yaml", "msgpack", "cbor"].contains(&format) {
+            for doc in section_docs() {
+                write_document(&mut out, format, &doc)?;
+            }
+            write_document(&mut out, format, &doc())?;
+        } else if format == "protobuf" {
+            let time_ns = timing.ns(time_ns).unwrap_or(0);
+            out.write_all(&stats.to_protobuf_delimited(time_ns))?;
+        } else if format == "csv" {
+            writeln!(
+                out,
+                "{}",
+                stats.to_csv_row(path, timing.ns(time_ns).unwrap_or(0))
+            )?;
+        } else {
+            if !sections.is_empty() {
+                print_sections(&mut out, path, &sections)?;
+            }
+            print_stats(&mut out, path, &stats, Some(time_ns))?;
+            print_extras(&mut out, path, &extras)?;
+        }
+        failed += check_assertions(&assertions, path, &stats);
+    }
+    if let Some(aggregate) = aggregate {
+        let inputs = aggregate.texts();
+        let time_ns = aggregate.elapsed_ns();
+        let stats = aggregate.finish();
+        let mut doc = stats.to_json_value();
+        doc.set("time_ns", time_ns.into());
+        doc.set("aggregate", inputs.into());
+        timing.apply(&mut doc);
+        if let Some(template) = &template {
+            write!(out, "{}", template.render(&doc))?;
+        } else if ["json", "yaml", "msgpack", "cbor"].contains(&format) {
+            write_document(&mut out, format, &doc)?;
+        } else if format == "csv" {
+            let time_ns = timing.ns(time_ns).unwrap_or(0);
+            writeln!(out, "{}", stats.to_csv_row("(aggregate)", time_ns))?;
+        } else {
+            print_stats(
+                &mut out,
+                &tr!("All {count} inputs", count = number(inputs)),
+                &stats,
+                Some(time_ns),
+            )?;
         }
+    }
+    if !skipped.is_empty() {
+        if ["json", "yaml", "msgpack", "cbor"].contains(&format) {
+            for (path, skip) in &skipped {
+                let doc = Json::Object(vec![
+                    ("source".into(), path.as_str().into()),
+                    ("skipped".into(), skip.code().into()),
+                    ("reason".into(), skip.to_string().into()),
+                ]);
+                write_document(&mut out, format, &doc)?;
+            }
+        } else if format == "text" && template.is_none() && listed > 1 {
+            writeln!(
+                out,
+                "{}",
+                tr!(
+                    "{skipped} of {total} inputs skipped:",
+                    skipped = number(skipped.len()),
+                    total = number(listed)
+                )
+            )?;
+            for (path, skip) in &skipped {
+                writeln!(out, "  {path}: {skip}")?;
+            }
+        }
+    }
+    if !failures.is_empty() {
+        if ["json", "yaml", "msgpack", "cbor"].contains(&format) {
+            for (path, message) in &failures.errors {
+                let doc = Json::Object(vec![
+                    ("source".into(), path.as_str().into()),
+                    ("error".into(), message.as_str().into()),
+                ]);
+                write_document(&mut out, format, &doc)?;
+            }
+        } else if format == "text" && template.is_none() && listed > 1 {
+            failures.print(&mut out, listed)?;
+        }
+        return Ok(failures.status(listed));
+    }
+    if failed > 0 {
+        return Ok(ExitCode::from(CHECK_FAILED));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// How `analyze` reads each input: whole, or the part `--first-n-words`,
+/// `--last-n-words`, `--sample`, `--range` or `--lines` selects.
+struct Reading {
+    limit: Option<WordLimit>,
+    sample: Option<f64>,
+    seed: u64,
+    range: Option<Range>,
+}
 
-        let mut freq_vec: Vec<(String, usize)> = Vec::with_capacity(10 + 8);
-        for (idx, count) in hot_counts.iter().enumerate() {
-            if *count > 0 {
-                freq_vec.push((HOT[idx].to_string(), *count));
+/// An input as read and decompressed, with what the read found out.
+struct Input {
+    bytes: Vec<u8>,
+    estimate: Option<Estimate>,
+    slice: Option<Slice>,
+    buffer: Option<BufferReport>,
+}
+
+/// Reads `path`: standard input for `-`, a URL through `fetcher`, else a
+/// file. Standard input is read with a buffer sized to `analyzer`'s speed.
+fn read_input(
+    path: &str,
+    reading: &Reading,
+    fetcher: &mut Fetcher,
+    analyzer: &dyn Analyzer,
+) -> Result<Input, String> {
+    let (mut estimate, mut slice, mut buffer) = (None, None, None);
+    let bytes = if path == "-" {
+        match &reading.limit {
+            Some(limit) => limit.read(io::stdin().lock()),
+            None => {
+                // The text is analyzed once read; the buffer adapts to
+                // the analyzer's speed timed on the first read.
+                let mut ns_per_byte = None;
+                let work = |read: &[u8]| {
+                    let rate = *ns_per_byte.get_or_insert_with(|| {
+                        let start = Instant::now();
+                        analyzer.analyze(&String::from_utf8_lossy(read));
+                        start.elapsed().as_nanos() as f64 / read.len() as f64
+                    });
+                    Duration::from_nanos((rate * read.len() as f64) as u64)
+                };
+                td5::input::read_all_timed(io::stdin().lock(), work)
             }
+            .map(|(bytes, reads)| {
+                buffer = Some(reads);
+                debug!(
+                    "stdin: {} bytes in {} reads, buffer {} -> {} bytes ({} to {}, {} changes)",
+                    reads.bytes,
+                    reads.reads,
+                    reads.first,
+                    reads.last,
+                    reads.smallest,
+                    reads.largest,
+                    reads.changes
+                );
+                bytes
+            }),
         }
-        if let Some(map) = word_freq {
-            freq_vec.extend(map.into_iter());
+        .map_err(|e| format!("stdin: {e}"))
+    } else if fetch::is_url(path) {
+        fetcher
+            .get(path)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| match &reading.limit {
+                Some(limit) => limit.read(&bytes[..]).map_err(|e| format!("{path}: {e}")),
+                None => Ok(bytes),
+            })
+    } else if let Some(limit) = &reading.limit {
+        limit.read_file(path.as_ref()).map_err(|e| e.to_string())
+    } else if let Some(fraction) = reading.sample {
+        td5::sample::read_seeded(path.as_ref(), fraction, reading.seed)
+            .map(|(bytes, sampled)| {
+                estimate = Some(sampled);
+                bytes
+            })
+            .map_err(|e| e.to_string())
+    } else if let Some(range) = &reading.range {
+        range
+            .read(path.as_ref())
+            .map(|(bytes, read)| {
+                info!(
+                    "{}",
+                    tr!(
+                        "{path}: bytes {start}..{end} of {total}",
+                        path = path,
+                        start = read.start,
+                        end = read.end,
+                        total = read.file_bytes
+                    )
+                );
+                slice = Some(read);
+                bytes
+            })
+            .map_err(|e| e.to_string())
+    } else {
+        // Decompressed as it is read, so never held compressed.
+        std::fs::File::open(path)
+            .and_then(td5::sniff::decompressing)
+            .and_then(|(reader, compression)| {
+                let bytes = td5::input::read_all(reader)?;
+                if let Some(compression) = compression {
+                    info!(
+                        "{}",
+                        tr!(
+                            "{path}: decompressed {compression} ({bytes} bytes)",
+                            path = path,
+                            compression = compression,
+                            bytes = bytes.len()
+                        )
+                    );
+                }
+                Ok(bytes)
+            })
+            .map_err(|e| format!("{path}: {e}"))
+    };
+    let bytes = bytes?;
+    let bytes = match td5::sniff::sniff(&bytes) {
+        td5::sniff::Kind::Compressed(compression) if compression.is_supported() => {
+            match td5::sniff::decompress(&bytes, compression) {
+                Ok(inner) => {
+                    info!(
+                        "{}",
+                        tr!(
+                            "{path}: decompressed {compression} ({bytes} bytes)",
+                            path = path,
+                            compression = compression,
+                            bytes = inner.len()
+                        )
+                    );
+                    inner
+                }
+                Err(e) => return Err(format!("{path}: {e}")),
+            }
         }
+        _ => bytes,
+    };
+    Ok(Input {
+        bytes,
+        estimate,
+        slice,
+        buffer,
+    })
+}
 
-        let unique = freq_vec.len();
-        freq_vec.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
-        let top_words: Vec<(String, usize)> = freq_vec.iter().take(10).cloned().collect();
+/// Flags of `analyze` that need each input read in the main loop, so not
+/// with `--jobs`.
+const SEQUENTIAL_FLAGS: &[&str] = &[
+    "window",
+    "split-on",
+    "sample",
+    "range",
+    "lines",
+    "first-n-words",
+    "last-n-words",
+    "report",
+    "subtitles",
+    "chat",
+    "social",
+    "distribution",
+    "acronyms",
+    "keywords",
+    "units",
+    "dialogue",
+    "quotes",
+    "dispersion",
+    "categories",
+    "pii",
+    "pii-samples",
+    "plugin",
+    "progress",
+    "instrument",
+];
 
-        let mut longest_words: Vec<String> = freq_vec.iter().map(|(w, _)| w.clone()).collect();
-        longest_words.sort_unstable_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
-        longest_words.truncate(5);
+/// `--progress`: a bar on stderr, redrawn at most ten times a second as the
+/// analyzer reports and cleared once the input is counted.
+#[derive(Default)]
+struct ProgressBar {
+    state: Mutex<BarState>,
+}
 
-        return TextStats {
-            word_count: unique,
-            char_count,
-            top_words,
-            longest_words,
-            time_ns: start.elapsed().as_nanos(),
-        };
+#[derive(Default)]
+struct BarState {
+    /// Size of the input being counted.
+    total: usize,
+    /// Bytes shown.
+    done: usize,
+    /// When the bar was last drawn, `None` while nothing is on screen.
+    drawn: Option<Instant>,
+}
+
+impl ProgressBar {
+    const WIDTH: usize = 30;
+
+    /// Starts an input of `total` bytes.
+    fn begin(&self, total: usize) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        (state.total, state.done) = (total, 0);
     }
 
-    // Generic ASCII hot path: manual byte scan, hot vocab avoids hashing entirely.
-    let mut word_freq: Option<HashMap<String, usize, FastHasher>> = None;
-    let mut char_count: usize = 0;
-
-    const HOT: [&str; 10] = [
-        "rust",
-        "performance",
-        "optimization",
-        "memory",
-        "speed",
-        "efficiency",
-        "benchmark",
-        "algorithm",
-        "data",
-        "structure",
-    ];
-    let mut hot_counts = [0usize; HOT.len()];
-    let mut buf: Vec<u8> = Vec::with_capacity(32);
-    let mut seen_non_hot = false;
-
-    for &b in text.as_bytes() {
-        if b.is_ascii_alphabetic() {
-            char_count += 1;
-            buf.push(b | 0b0010_0000);
-        } else if !buf.is_empty() {
-            let word = buf.as_slice();
-            if let Some(idx) = match_hot_lower(word) {
-                hot_counts[idx] += 1;
-            } else {
-                seen_non_hot = true;
-                let map = word_freq.get_or_insert_with(|| {
-                    HashMap::with_capacity_and_hasher(text.len() / 64, FastHasher::default())
-                });
-                // SAFETY: word is lowercase ASCII
-                let key = unsafe { String::from_utf8_unchecked(word.to_vec()) };
-                map.entry(key).and_modify(|c| *c += 1).or_insert(1);
-            }
-            buf.clear();
+    fn draw(&self, done: usize, words: usize) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let total = state.total;
+        // Reports from the counting threads may arrive out of order.
+        if total == 0
+            || done <= state.done
+            || state
+                .drawn
+                .is_some_and(|at| at.elapsed() < Duration::from_millis(100))
+        {
+            return;
         }
+        (state.done, state.drawn) = (done, Some(Instant::now()));
+        let share = done.min(total) as f64 / total as f64;
+        let filled = (share * Self::WIDTH as f64) as usize;
+        eprint!(
+            "\r[{}{}] {:>3}% {}",
+            "#".repeat(filled),
+            " ".repeat(Self::WIDTH - filled),
+            (share * 100.0) as usize,
+            tr!(
+                "{done} of {total} MB, {words} words",
+                done = decimal(done as f64 / 1e6, 1),
+                total = decimal(total as f64 / 1e6, 1),
+                words = number(words)
+            )
+        );
+        let _ = io::stderr().flush();
     }
-    if !buf.is_empty() {
-        let word = buf.as_slice();
-        if let Some(idx) = match_hot_lower(word) {
-            hot_counts[idx] += 1;
-        } else {
-            seen_non_hot = true;
-            let map = word_freq.get_or_insert_with(|| {
-                HashMap::with_capacity_and_hasher(text.len() / 64, FastHasher::default())
-            });
-            // SAFETY: word is lowercase ASCII
-            let key = unsafe { String::from_utf8_unchecked(word.to_vec()) };
-            map.entry(key).and_modify(|c| *c += 1).or_insert(1);
+
+    fn clear(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.drawn.is_some() {
+            eprint!("\r\x1b[K");
         }
+        *state = BarState::default();
     }
+}
 
-    // Hot-only fast path: no hashmap, no sort; we know the desired ordering.
-    if !seen_non_hot {
-        const HOT_ORDER: [usize; 10] = [7, 6, 8, 5, 3, 2, 1, 0, 4, 9]; // alphabetical
-        const LONGEST_ORDER: [usize; 5] = [2, 1, 5, 7, 6];
-        let mut top_words = Vec::with_capacity(10);
-        for &idx in &HOT_ORDER {
-            top_words.push((HOT[idx].to_string(), hot_counts[idx]));
+/// An input's stats as `analyze` reports them without extras; `time_ns` is
+/// how long they took.
+fn write_stats(
+    out: &mut impl Write,
+    format: &str,
+    template: Option<&Template>,
+    timing: TimePolicy,
+    path: &str,
+    stats: &TextStats,
+    time_ns: u128,
+) -> Result<(), Halt> {
+    if template.is_some() || ["json", "yaml", "msgpack", "cbor"].contains(&format) {
+        let mut doc = stats.to_json_value();
+        doc.set("time_ns", time_ns.into());
+        doc.set("source", path.into());
+        let warnings = Warnings::from_stats(stats, &AnalyzerConfig::default());
+        if !warnings.is_empty() {
+            doc.set("warnings", warnings.to_json_value());
         }
-        let mut longest_words = Vec::with_capacity(5);
-        for &idx in &LONGEST_ORDER {
-            if hot_counts[idx] > 0 {
-                longest_words.push(HOT[idx].to_string());
-            }
+        timing.apply(&mut doc);
+        match template {
+            Some(template) => write!(out, "{}", template.render(&doc))?,
+            None => write_document(out, format, &doc)?,
         }
-        return TextStats {
-            word_count: top_words.len(),
-            char_count,
-            top_words,
-            longest_words,
-            time_ns: start.elapsed().as_nanos(),
-        };
+    } else if format == "protobuf" {
+        out.write_all(&stats.to_protobuf_delimited(timing.ns(time_ns).unwrap_or(0)))?;
+    } else if format == "csv" {
+        writeln!(
+            out,
+            "{}",
+            stats.to_csv_row(path, timing.ns(time_ns).unwrap_or(0))
+        )?;
+    } else {
+        print_stats(out, path, stats, Some(time_ns))?;
     }
+    Ok(())
+}
 
-    let mut freq_vec: Vec<(String, usize)> = Vec::with_capacity(hot_counts.len() + 8);
-    for (idx, count) in hot_counts.iter().enumerate() {
-        if *count > 0 {
-            freq_vec.push((HOT[idx].to_string(), *count));
+/// Reports each of `assertions` that fails on `stats`; returns how many did.
+fn check_assertions(assertions: &[Assertion], path: &str, stats: &TextStats) -> usize {
+    let mut failed = 0;
+    for assertion in assertions {
+        if let Err(actual) = assertion.check(stats) {
+            failed += 1;
+            eprintln!(
+                "{}",
+                tr!(
+                    "assertion failed: {path}: {assertion} (actual {actual})",
+                    path = path,
+                    assertion = assertion,
+                    actual = actual
+                )
+            );
         }
     }
-    if let Some(map) = word_freq {
-        freq_vec.extend(map.into_iter());
+    failed
+}
+
+/// The lists of `--stopwords`: built-in ones by language code, others read
+/// from files.
+fn stopwords(args: &Args) -> Result<Option<StopwordList>, String> {
+    let mut all: Option<StopwordList> = None;
+    for list in args.values("stopwords") {
+        let words = match StopwordList::builtin(list) {
+            Some(words) => words,
+            None => {
+                let text = td5::input::read_text(list).map_err(|e| e.to_string())?;
+                StopwordList::parse(&text)
+            }
+        };
+        all.get_or_insert_with(StopwordList::default).extend(words);
     }
-    let unique = freq_vec.len();
+    Ok(all)
+}
 
-    freq_vec.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
-    let top_words: Vec<(String, usize)> = freq_vec.iter().take(10).cloned().collect();
+/// `config` ranking by the stems of `--stem`, if given.
+#[cfg(feature = "stemming")]
+fn stemming(args: &Args, config: AnalyzerConfig) -> Result<AnalyzerConfig, String> {
+    let Some(name) = args.value("stem") else {
+        return Ok(config);
+    };
+    let language = td5::stem::Language::from_name(name).ok_or_else(|| {
+        tr!(
+            "unknown stemming language `{name}` (expected en)",
+            name = name
+        )
+    })?;
+    Ok(config.stemming(language))
+}
 
-    let mut longest_words: Vec<String> = freq_vec.iter().map(|(w, _)| w.clone()).collect();
-    longest_words.sort_unstable_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
-    longest_words.truncate(5);
+#[cfg(not(feature = "stemming"))]
+fn stemming(args: &Args, config: AnalyzerConfig) -> Result<AnalyzerConfig, String> {
+    if args.has("stem") {
+        return Err(tr!("--stem needs a build with `--features stemming`").into());
+    }
+    Ok(config)
+}
 
-    TextStats {
-        word_count: unique,
-        char_count,
-        top_words,
-        longest_words,
-        time_ns: start.elapsed().as_nanos(),
+/// `--time`: what serialized output keeps of `time_ns`.
+fn time_policy(args: &Args) -> Result<TimePolicy, String> {
+    match args.value("time") {
+        None | Some("exact") => Ok(TimePolicy::Exact),
+        Some("omit") => Ok(TimePolicy::Omit),
+        Some(width) => match width.parse::<f64>() {
+            Ok(ms) if ms > 0.0 && ms.is_finite() => Ok(TimePolicy::Bucket((ms * 1e6) as u128)),
+            _ => Err(tr!(
+                "unknown time policy `{name}` (expected exact, omit or a bucket width in milliseconds)",
+                name = width
+            )),
+        },
     }
 }
 
-fn generate_test_text(size: usize) -> String {
-    let words = vec![
-        "rust",
-        "performance",
-        "optimization",
-        "memory",
-        "speed",
-        "efficiency",
-        "benchmark",
-        "algorithm",
-        "data",
-        "structure",
-    ];
+/// `--control`: what becomes of control characters.
+fn control_chars(args: &Args) -> Result<ControlChars, String> {
+    match args.value("control") {
+        None | Some("strip") => Ok(ControlChars::Strip),
+        Some("separate") => Ok(ControlChars::Separate),
+        Some("error") => Ok(ControlChars::Error),
+        Some(name) => Err(tr!(
+            "unknown control character policy `{name}` (expected strip, separate or error)",
+            name = name
+        )),
+    }
+}
 
-    (0..size)
-        .map(|i| words[i % words.len()])
-        .collect::<Vec<_>>()
-        .join(" ")
+fn normalization(args: &Args) -> Result<Normalization, String> {
+    match args.value("normalize") {
+        None => Ok(Normalization::None),
+        Some("nfc") => Ok(Normalization::Nfc),
+        Some("nfkc") => Ok(Normalization::Nfkc),
+        Some(name) => Err(tr!(
+            "unknown normalization form `{name}` (expected nfc or nfkc)",
+            name = name
+        )),
+    }
 }
 
-fn print_stats(label: &str, stats: &TextStats) {
-    println!("{label}:");
-    println!("  Unique words: {}", stats.word_count);
-    println!("  Total chars: {}", stats.char_count);
-    println!("  Top 10 words: {:?}", stats.top_words);
-    println!("  Longest words: {:?}", stats.longest_words);
-    println!(
-        "  Time: {:.3} ms ({:?})\n",
-        stats.time_ns as f64 / 1_000_000.0,
-        stats.time_ns
-    );
+/// Finds the language of a text, as `td5::detect::language` does.
+type Detector = fn(&str) -> Option<&'static str>;
+
+/// `--auto`: how each input's language is detected, if it is.
+#[cfg(feature = "language-detection")]
+fn auto(args: &Args) -> Result<Option<Detector>, String> {
+    Ok(args
+        .has("auto")
+        .then_some(td5::detect::language as Detector))
 }
 
-fn main() {
-    let text = generate_test_text(50_000);
+#[cfg(not(feature = "language-detection"))]
+fn auto(args: &Args) -> Result<Option<Detector>, String> {
+    if args.has("auto") {
+        return Err(tr!("--auto needs a build with `--features language-detection`").into());
+    }
+    Ok(None)
+}
 
-    println!("Analyzing {} bytes of text...\n", text.len());
+/// `config` with the built-in stopwords of `language` and, built with
+/// stemming, its stemmer, where there are some.
+fn auto_config(config: &AnalyzerConfig, language: Option<&str>) -> AnalyzerConfig {
+    let mut config = config.clone();
+    let Some(language) = language else {
+        return config;
+    };
+    if let Some(list) = StopwordList::builtin(language) {
+        config = config.stopwords(list);
+    }
+    #[cfg(feature = "stemming")]
+    if let Some(stemmer) = td5::stem::Language::from_name(language) {
+        config = config.stemming(stemmer);
+    }
+    config
+}
 
-    let slow_stats = analyze_text_slow(&text);
-    let fast_stats = analyze_text_fast(&text);
+/// What `--min-bytes`, `--max-bytes`, `--min-words` and `--keep-binary`
+/// leave out.
+fn skip_rules(args: &Args) -> Result<SkipRules, String> {
+    let size = |name: &str| {
+        args.value(name)
+            .map(|value| {
+                td5::slice::parse_size(value).ok_or_else(|| {
+                    tr!(
+                        "`--{name}` expects a size such as 500000, 64K or 50M, got `{value}`",
+                        name = name,
+                        value = value
+                    )
+                })
+            })
+            .transpose()
+    };
+    Ok(SkipRules {
+        min_bytes: size("min-bytes")?.unwrap_or(0),
+        max_bytes: size("max-bytes")?,
+        binary: !args.has("keep-binary"),
+        min_words: args.number("min-words", 0)?,
+        ..SkipRules::default()
+    })
+}
 
-    println!("Results:");
-    print_stats("Slow", &slow_stats);
-    print_stats("Fast", &fast_stats);
+/// The limits for URL inputs, from `--max-download` and friends.
+fn fetch_policy(args: &Args) -> Result<FetchPolicy, String> {
+    let defaults = FetchPolicy::default();
+    let max_bytes = match args.value("max-download") {
+        Some(size) => td5::slice::parse_size(size).ok_or_else(|| {
+            tr!(
+                "`--max-download` expects a size such as 500000, 64K or 50M, got `{value}`",
+                value = size
+            )
+        })?,
+        None => defaults.max_bytes,
+    };
+    let content_types = match args.list("content-types") {
+        Some(types) => types.iter().map(|t| t.to_ascii_lowercase()).collect(),
+        None => defaults.content_types,
+    };
+    Ok(FetchPolicy {
+        max_bytes,
+        content_types,
+        max_redirects: args.number("max-redirects", defaults.max_redirects)?,
+        retries: args.number("retries", defaults.retries)?,
+        backoff: Duration::from_millis(
+            args.number("retry-backoff", defaults.backoff.as_millis() as u64)?,
+        ),
+        interval: Duration::from_millis(
+            args.number("fetch-interval", defaults.interval.as_millis() as u64)?,
+        ),
+        timeout: Duration::from_secs(args.number("fetch-timeout", defaults.timeout.as_secs())?),
+    })
+}
 
-    let speedup = slow_stats.time_ns as f64 / fast_stats.time_ns as f64;
-    println!("Speedup: {:.2}x faster", speedup);
+/// Times `plan`, reading the energy counters too with `--energy`.
+#[cfg(all(feature = "rapl", target_os = "linux"))]
+fn run_plan(args: &Args, plan: &Plan, cache: InputCache) -> Result<BenchReport, String> {
+    if !args.has("energy") {
+        return plan.run_with(cache).map_err(|e| e.to_string());
+    }
+    let rapl = td5::rapl::Rapl::open().map_err(|e| e.to_string())?;
+    info!(
+        "{}",
+        tr!(
+            "reading the energy of {packages} CPU packages",
+            packages = rapl.packages()
+        )
+    );
+    plan.run_with_energy(cache, rapl).map_err(|e| e.to_string())
+}
+
+#[cfg(not(all(feature = "rapl", target_os = "linux")))]
+fn run_plan(args: &Args, plan: &Plan, cache: InputCache) -> Result<BenchReport, String> {
+    if args.has("energy") {
+        return Err(tr!("--energy needs a build with `--features rapl` on Linux").into());
+    }
+    plan.run_with(cache).map_err(|e| e.to_string())
+}
+
+/// The extensions of `--plugin`, in order.
+#[cfg(all(feature = "plugins", unix))]
+fn plugins(args: &Args) -> Result<Vec<Arc<dyn Extension>>, String> {
+    args.values("plugin")
+        .into_iter()
+        .map(|path| {
+            let plugin = td5::plugin::Plugin::load(path).map_err(|e| e.to_string())?;
+            debug!("plugin {} from {path}", plugin.name());
+            Ok(Arc::new(plugin) as Arc<dyn Extension>)
+        })
+        .collect()
+}
+
+#[cfg(not(all(feature = "plugins", unix)))]
+fn plugins(args: &Args) -> Result<Vec<Arc<dyn Extension>>, String> {
+    if args.has("plugin") {
+        return Err(tr!("--plugin needs a build with `--features plugins` on Unix").into());
+    }
+    Ok(Vec::new())
+}
+
+/// The word pipeline of `--pipeline`, else of `pipeline.conf` in the config
+/// directory if there is one, after the `--merge-map` if any; `None`
+/// without stages.
+fn pipeline(args: &Args) -> Result<Option<Pipeline>, String> {
+    let path = match args.value("pipeline") {
+        Some(path) => Some(PathBuf::from(path)),
+        None => Pipeline::path().filter(|p| p.exists()),
+    };
+    let mut pipeline = match &path {
+        Some(path) => Pipeline::load(path).map_err(|e| e.to_string())?,
+        None => Pipeline::default(),
+    };
+    if let Some(map) = args.value("merge-map") {
+        let stage = Stage::load_merge_map(map.as_ref()).map_err(|e| e.to_string())?;
+        pipeline.stages.insert(0, stage);
+    }
+    let stages: Vec<&str> = pipeline.stages.iter().map(Stage::name).collect();
+    debug!("pipeline: {}", stages.join(" -> "));
+    Ok(Some(pipeline).filter(|p| !p.is_empty()))
+}
+
+/// Fails for a binary `--format` left out of this build.
+fn check_built(format: &str) -> Result<(), String> {
+    let missing = (format == "msgpack" && !cfg!(feature = "msgpack"))
+        || (format == "cbor" && !cfg!(feature = "cbor"));
+    if missing {
+        return Err(tr!(
+            "--format {name} needs a build with `--features {name}`",
+            name = format
+        ));
+    }
+    Ok(())
+}
+
+/// Writes `doc` as a JSON line, a YAML document or a MessagePack or CBOR
+/// item.
+fn write_document(out: &mut impl Write, format: &str, doc: &Json) -> io::Result<()> {
+    let bytes = match format {
+        "yaml" => td5::yaml::encode(doc).into_bytes(),
+        #[cfg(feature = "msgpack")]
+        "msgpack" => td5::msgpack::encode(doc),
+        #[cfg(feature = "cbor")]
+        "cbor" => td5::cbor::encode(doc),
+        "json" => format!("{doc}\n").into_bytes(),
+        _ => return check_built(format).map_err(io::Error::other),
+    };
+    out.write_all(&bytes)
+}
+
+/// The `lint` and `serve` options.
+fn lint_config(args: &Args) -> Result<LintConfig, String> {
+    let mut config = LintConfig {
+        max_sentence_words: args.number("max-sentence", 40)?,
+        max_filler_share: args.number("max-filler-share", 0.01)?,
+        ..LintConfig::default()
+    };
+    let fillers = args.values("filler");
+    if !fillers.is_empty() {
+        config.fillers = fillers.iter().map(|w| w.to_lowercase()).collect();
+    }
+    if let Some(path) = args.value("phrases") {
+        let source = td5::input::read_text(path).map_err(|e| e.to_string())?;
+        config.phrases = PhraseList::parse_file(&source).map_err(|e| format!("{path}: {e}"))?;
+    }
+    Ok(config)
+}
+
+fn run_lint(args: &Args) -> Result<ExitCode, Halt> {
+    let mut out = io::stdout().lock();
+    let config = lint_config(args)?;
+    if args.positional.is_empty() {
+        return Err(tr!("expected at least one file").into());
+    }
+    let mut found = 0;
+    let mut phrase_counts: Vec<PhraseCount> = Vec::new();
+    for path in &args.positional {
+        let (text, _) = td5::input::read_transcoded(path).map_err(|e| e.to_string())?;
+        let report = lint(&text, &config);
+        for finding in &report.findings {
+            found += 1;
+            writeln!(out, "{path}:{finding}")?;
+        }
+        for count in report.phrase_counts {
+            match phrase_counts
+                .iter_mut()
+                .find(|c| c.list == count.list && c.phrase == count.phrase)
+            {
+                Some(total) => total.count += count.count,
+                None => phrase_counts.push(count),
+            }
+        }
+    }
+    if !phrase_counts.is_empty() {
+        phrase_counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.phrase.cmp(&b.phrase)));
+        writeln!(out, "\n{}", tr!("Phrase counts:"))?;
+        for c in &phrase_counts {
+            writeln!(out, "  {:>5}  {:<16} {}", number(c.count), c.list, c.phrase)?;
+        }
+    }
+    info!("{}", tr!("{count} findings", count = found));
+    Ok(if found > 0 {
+        ExitCode::from(CHECK_FAILED)
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+fn run_tune(args: &Args) -> Result<(), Halt> {
+    let mut out = io::stdout().lock();
+    let iterations = args.number("iterations", 5)?;
+    let text = match args.value("file") {
+        Some(path) => {
+            if args.has("seed") {
+                return Err(tr!("--seed needs generated text, not --file").into());
+            }
+            td5::input::read_transcoded(path)
+                .map_err(|e| e.to_string())?
+                .0
+        }
+        None => {
+            let seed = args.number("seed", 0u64)?;
+            writeln!(
+                out,
+                "{}",
+                tr!("Generated inputs: {seed}", seed = seed_label(seed))
+            )?;
+            Shape::Zipf.generate(args.number("words", 1_000_000)?, seed)
+        }
+    };
+    info!(
+        "{}",
+        tr!(
+            "tuning on {bytes} bytes, {iterations} iterations per value",
+            bytes = number(text.len()),
+            iterations = iterations
+        )
+    );
+    let (best, sweeps) = td5::tuning::tune(&text, iterations).map_err(|e| e.to_string())?;
+    for sweep in &sweeps {
+        writeln!(out, "{}:", sweep.knob)?;
+        for &(value, ns) in &sweep.timings {
+            let mark = if value == sweep.best() { " *" } else { "" };
+            let ms = decimal(ns as f64 / 1_000_000.0, 3);
+            writeln!(out, "  {value:>10} {ms:>10} ms{mark}")?;
+        }
+    }
+    if args.has("dry-run") {
+        return Ok(());
+    }
+    let path = match args.value("out") {
+        Some(path) => path.into(),
+        None => Tuning::path().ok_or_else(|| tr!("no config location: set TD5_CONFIG or HOME"))?,
+    };
+    let environment = td5::environment::Environment::capture();
+    let header = format!(
+        "Written by `td5 tune` on {} ({} cores).\nRerun it after changing machines.",
+        environment.cpu_model, environment.cores
+    );
+    best.save(&path, &header).map_err(|e| e.to_string())?;
+    info!("{}", tr!("saved {path}", path = path.display()));
+    Ok(())
+}
+
+fn run_timeline(args: &Args) -> Result<(), Halt> {
+    let mut out = io::stdout().lock();
+    let bucket =
+        Bucket::parse(args.value("bucket").unwrap_or("hour")).map_err(|e| e.to_string())?;
+    let format = args.value("format").unwrap_or("text");
+    if !["text", "csv", "json"].contains(&format) {
+        return Err(tr!(
+            "unknown format `{name}` (expected text, csv or json)",
+            name = format
+        )
+        .into());
+    }
+    if args.positional.is_empty() {
+        return Err(tr!("expected at least one file").into());
+    }
+    let mut timeline = Timeline::new(bucket);
+    for path in &args.positional {
+        let (text, _) = td5::input::read_transcoded(path).map_err(|e| e.to_string())?;
+        timeline.push_lines(&text);
+    }
+    if timeline.untimestamped_lines() > 0 {
+        warn!(
+            "{}",
+            tr!(
+                "{count} lines before the first timestamp skipped",
+                count = timeline.untimestamped_lines()
+            )
+        );
+    }
+    let series = timeline.finish();
+    match format {
+        "csv" => write!(out, "{}", td5::timeline::to_csv(&series))?,
+        "json" => {
+            for b in &series {
+                writeln!(out, "{}", b.to_json_value())?;
+            }
+        }
+        _ => {
+            for b in &series {
+                let top: Vec<String> = b
+                    .stats
+                    .top_words
+                    .iter()
+                    .take(5)
+                    .map(|(w, c)| format!("{w} ({c})"))
+                    .collect();
+                let trending: Vec<String> = b
+                    .trending
+                    .iter()
+                    .map(|t| format!("{} ({} -> {})", t.word, t.previous, t.count))
+                    .collect();
+                writeln!(
+                    out,
+                    "{}  {}",
+                    td5::timestamp::format(b.start),
+                    tr!(
+                        "{total} words, {unique} unique",
+                        total = number(b.stats.total_words),
+                        unique = number(b.stats.word_count)
+                    )
+                )?;
+                writeln!(out, "  {} {}", tr!("Top words:"), top.join(", "))?;
+                if !trending.is_empty() {
+                    writeln!(out, "  {} {}", tr!("Trending:"), trending.join(", "))?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_deny(args: &Args) -> Result<ExitCode, Halt> {
+    let mut out = io::stdout().lock();
+    let Some(list) = args.value("list") else {
+        return Err(tr!("expected --list <FILE>").into());
+    };
+    let source = td5::input::read_text(list).map_err(|e| e.to_string())?;
+    let deny = DenyList::parse(&source).map_err(|e| format!("{list}: {e}"))?;
+    let max = args.number("max", 0)?;
+    if args.positional.is_empty() {
+        return Err(tr!("expected at least one file").into());
+    }
+    let mut matches = Vec::new();
+    for path in &args.positional {
+        let (text, _) = td5::input::read_transcoded(path).map_err(|e| e.to_string())?;
+        let found = deny.scan(&text);
+        if args.has("locations") {
+            for m in &found {
+                writeln!(
+                    out,
+                    "{path}:{}:{}: `{}` ({})",
+                    m.line, m.column, m.text, m.entry
+                )?;
+            }
+        }
+        matches.extend(found);
+    }
+    for (entry, count) in td5::deny::counts(&matches) {
+        writeln!(out, "  {count:>5}  {entry}")?;
+    }
+    info!("{}", tr!("{count} matches", count = matches.len()));
+    Ok(if matches.len() > max {
+        ExitCode::from(CHECK_FAILED)
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+fn run_wordlist(args: &Args) -> Result<(), Halt> {
+    let name = args.value("format").unwrap_or("tsv");
+    let format = if ["msgpack", "cbor"].contains(&name) {
+        check_built(name)?;
+        None
+    } else {
+        Some(ListFormat::parse(name).map_err(|e| e.to_string())?)
+    };
+    let min_count = args.number("min-count", 1)?;
+    let offset = args.number("offset", 0)?;
+    let limit = match args.value("limit") {
+        Some(_) => args.number("limit", 0)?,
+        None => usize::MAX,
+    };
+    if args.positional.is_empty() {
+        return Err(tr!("expected at least one file").into());
+    }
+    let mut freq = WordFrequency::default();
+    for path in &args.positional {
+        let (text, _) = td5::input::read_transcoded(path).map_err(|e| e.to_string())?;
+        freq.merge(WordFrequency::from_text(&text));
+    }
+    if let Some(pipeline) = pipeline(args)? {
+        freq = pipeline.transform(&freq);
+    }
+    let mut pages = freq.pages();
+    let page = pages.page(offset, limit);
+    let words = &page[..page.partition_point(|&(_, count)| count >= min_count)];
+    match format {
+        Some(format) => format.write(words, io::BufWriter::new(io::stdout().lock()))?,
+        None => write_document(
+            &mut io::stdout().lock(),
+            name,
+            &Json::Object(
+                words
+                    .iter()
+                    .map(|&(word, count)| (word.to_string(), count.into()))
+                    .collect(),
+            ),
+        )?,
+    }
+    Ok(())
+}
+
+fn run_share(args: &Args) -> Result<(), Halt> {
+    let mut out = io::stdout().lock();
+    if let Some(payload) = args.value("decode") {
+        if !args.positional.is_empty() {
+            return Err(tr!("--decode takes no file").into());
+        }
+        let top = TopWords::decode(payload).map_err(|e| e.to_string())?;
+        write_document(&mut out, "json", &top.to_json_value())?;
+        return Ok(());
+    }
+    if args.positional.is_empty() {
+        return Err(tr!("expected at least one file").into());
+    }
+    let mut freq = WordFrequency::default();
+    for path in &args.positional {
+        let (text, _) = td5::input::read_transcoded(path).map_err(|e| e.to_string())?;
+        freq.merge(WordFrequency::from_text(&text));
+    }
+    let payload = TopWords::from_freq(&freq, args.number("top", 20)?).encode();
+    match args.value("base") {
+        Some(base) => writeln!(out, "{base}#{payload}")?,
+        None => writeln!(out, "{payload}")?,
+    }
+    Ok(())
+}
+
+fn run_prefix_tree(args: &Args) -> Result<(), String> {
+    let Some(out) = args.value("out") else {
+        return Err(tr!("expected --out <FILE>").into());
+    };
+    let min_count = args.number("min-count", 1)?;
+    if args.positional.is_empty() {
+        return Err(tr!("expected at least one file").into());
+    }
+    let mut freq = WordFrequency::default();
+    for path in &args.positional {
+        let (text, _) = td5::input::read_transcoded(path).map_err(|e| e.to_string())?;
+        freq.merge(WordFrequency::from_text(&text));
+    }
+    let words: Vec<(&str, usize)> = freq
+        .iter()
+        .filter(|&(_, count)| count >= min_count)
+        .collect();
+    let tree = PrefixTree::build(words.iter().copied());
+    let bytes = tree.to_bytes();
+    std::fs::write(out, &bytes).map_err(|e| format!("{out}: {e}"))?;
+    info!(
+        "{}",
+        tr!(
+            "{words} words in {states} states, {bytes} bytes written to {path}",
+            words = words.len(),
+            states = tree.states(),
+            bytes = bytes.len(),
+            path = out
+        )
+    );
+    Ok(())
+}
+
+fn run_complete(args: &Args) -> Result<(), Halt> {
+    let mut out = io::stdout().lock();
+    let [path, prefix] = &args.positional[..] else {
+        return Err(tr!("expected a prefix tree and a prefix").into());
+    };
+    let bytes = std::fs::read(path).map_err(|e| format!("{path}: {e}"))?;
+    let tree = PrefixTree::from_bytes(&bytes).map_err(|e| format!("{path}: {e}"))?;
+    for (word, count) in tree.complete(&prefix.to_lowercase(), args.number("top", 10)?) {
+        writeln!(out, "{count}\t{word}")?;
+    }
+    Ok(())
+}
+
+fn run_learn_hot(args: &Args) -> Result<(), Halt> {
+    let mut out = io::stdout().lock();
+    let top = args.number("top", 10)?;
+    if args.positional.is_empty() {
+        return Err(tr!("expected at least one file").into());
+    }
+    let mut freq = WordFrequency::default();
+    let mut files = 0;
+    for path in &args.positional {
+        for file in td5::input::files(path).map_err(|e| e.to_string())? {
+            let (text, _) = td5::input::read_transcoded(&file).map_err(|e| e.to_string())?;
+            freq.merge(WordFrequency::from_text(&text));
+            files += 1;
+        }
+    }
+    let hot = HotVocabulary::learn(&freq, top);
+    info!(
+        "{}",
+        tr!(
+            "{words} hot words cover {share}% of {total} tokens in {files} files",
+            words = hot.words.len(),
+            share = decimal(hot.coverage(), 1),
+            total = number(hot.total),
+            files = files
+        )
+    );
+    match args.value("out") {
+        Some(path) => {
+            std::fs::write(path, hot.to_file()).map_err(|e| format!("{path}: {e}"))?;
+            info!("{}", tr!("saved {path}", path = path));
+        }
+        None => write!(out, "{}", hot.to_file())?,
+    }
+    Ok(())
+}
+
+fn run_dedup(args: &Args) -> Result<(), Halt> {
+    let mut out = io::stdout().lock();
+    let format = args.value("format").unwrap_or("text");
+    if !["text", "json"].contains(&format) {
+        return Err(tr!(
+            "unknown format `{name}` (expected text or json)",
+            name = format
+        )
+        .into());
+    }
+    let threshold = args.number("threshold", 0.8)?;
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(tr!("--threshold must be between 0 and 1").into());
+    }
+    let shingle = args.number("shingle", 3)?;
+    let seed = args.number("seed", 0u64)?;
+    if args.positional.is_empty() {
+        return Err(tr!("expected at least one file").into());
+    }
+    let mut files = Vec::new();
+    for path in &args.positional {
+        files.extend(td5::input::files(path).map_err(|e| e.to_string())?);
+    }
+    let fingerprints = files
+        .iter()
+        .map(|file| {
+            td5::input::read_transcoded(file)
+                .map(|(text, _)| Fingerprint::of_seeded(&text, shingle, seed))
+                .map_err(|e| e.to_string())
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let clusters = dedup::clusters(&fingerprints, threshold);
+    if args.has("unique") {
+        let mut duplicate = vec![false; files.len()];
+        for cluster in &clusters {
+            for &(i, _) in &cluster.members[1..] {
+                duplicate[i] = true;
+            }
+        }
+        for (file, _) in files.iter().zip(duplicate).filter(|(_, d)| !d) {
+            writeln!(out, "{}", file.display())?;
+        }
+        return Ok(());
+    }
+    if format == "json" {
+        let clusters = clusters
+            .iter()
+            .map(|cluster| {
+                let members = cluster
+                    .members
+                    .iter()
+                    .map(|&(i, similarity)| {
+                        let mut member = Json::Object(Vec::new());
+                        member.set("path", files[i].display().to_string().into());
+                        member.set("similarity", similarity.into());
+                        member
+                    })
+                    .collect();
+                Json::Array(members)
+            })
+            .collect();
+        let mut doc = Json::Object(Vec::new());
+        doc.set("files", files.len().into());
+        doc.set("threshold", threshold.into());
+        doc.set("seed", td5::seed::to_json_value(seed));
+        doc.set("clusters", Json::Array(clusters));
+        writeln!(out, "{doc}")?;
+        return Ok(());
+    }
+    let duplicates: usize = clusters.iter().map(|c| c.members.len() - 1).sum();
+    writeln!(
+        out,
+        "{}",
+        tr!(
+            "{clusters} clusters of near-duplicates among {files} files ({duplicates} redundant, threshold {threshold})",
+            clusters = number(clusters.len()),
+            files = number(files.len()),
+            duplicates = number(duplicates),
+            threshold = decimal(threshold, 2)
+        )
+    )?;
+    writeln!(
+        out,
+        "{}",
+        tr!("MinHash functions: {seed}", seed = seed_label(seed))
+    )?;
+    for (n, cluster) in clusters.iter().enumerate() {
+        writeln!(out, "\n{}", tr!("Cluster {n}:", n = n + 1))?;
+        for &(i, similarity) in &cluster.members {
+            writeln!(out, "  {}  {}", decimal(similarity, 2), files[i].display())?;
+        }
+    }
+    Ok(())
+}
+
+/// One side of `td5 stability`: `NAME`, or `NAME:sample=SHARE` with an
+/// optional `seed=N`, and its sample if it read one. `top_words` runs past
+/// the `top`th word until it holds every word tied with it.
+fn stability_side(
+    spec: &str,
+    path: &str,
+    top: usize,
+) -> Result<(TextStats, Option<Estimate>), String> {
+    let (name, options) = spec.split_once(':').unwrap_or((spec, ""));
+    if analyzer::find(name).is_none() {
+        return Err(tr!("unknown analyzer `{name}`", name = name));
+    }
+    let (mut sample, mut seed) = (None, None);
+    for option in options.split(',').filter(|o| !o.is_empty()) {
+        match option.split_once('=') {
+            Some(("sample", share)) => {
+                sample = Some(td5::sample::parse_fraction(share).map_err(|e| e.to_string())?);
+            }
+            Some(("seed", n)) => {
+                seed = Some(
+                    n.parse::<u64>()
+                        .map_err(|_| tr!("expected a seed such as 7, got `{value}`", value = n))?,
+                );
+            }
+            _ => {
+                return Err(tr!(
+                    "unknown option `{option}` in `{spec}` (expected sample=SHARE or seed=N)",
+                    option = option,
+                    spec = spec
+                ));
+            }
+        }
+    }
+    if seed.is_some() && sample.is_none() {
+        return Err(tr!("`{spec}`: seed=N needs sample=SHARE", spec = spec));
+    }
+    let (bytes, estimate) = match sample {
+        Some(fraction) => td5::sample::read_seeded(path.as_ref(), fraction, seed.unwrap_or(0))
+            .map(|(bytes, estimate)| (bytes, Some(estimate)))
+            .map_err(|e| e.to_string())?,
+        None => (
+            std::fs::read(path).map_err(|e| format!("{path}: {e}"))?,
+            None,
+        ),
+    };
+    let text = encoding::decode(&bytes).text;
+    let mut stats = stability::analyze_top(name, &text, top).expect("found above");
+    if let Some(estimate) = &estimate {
+        estimate.extrapolate(&mut stats);
+    }
+    Ok((stats, estimate))
+}
+
+fn run_stability(args: &Args) -> Result<(), Halt> {
+    let mut out = io::stdout().lock();
+    let format = args.value("format").unwrap_or("text");
+    if !["text", "json"].contains(&format) {
+        return Err(tr!(
+            "unknown format `{name}` (expected text or json)",
+            name = format
+        )
+        .into());
+    }
+    let [path] = &args.positional[..] else {
+        return Err(tr!("expected exactly one file").into());
+    };
+    let (a, b) = (
+        args.value("a").unwrap_or("slow"),
+        args.value("b").unwrap_or("fast"),
+    );
+    let top = args.number("top", 10)?;
+    let ((stats_a, estimate_a), (stats_b, estimate_b)) =
+        (stability_side(a, path, top)?, stability_side(b, path, top)?);
+    let stability = Stability::between(&stats_a.top_words, &stats_b.top_words, top);
+    if format == "json" {
+        let mut doc = stability.to_json_value();
+        doc.set("a", a.into());
+        doc.set("b", b.into());
+        for (key, estimate) in [("estimate_a", &estimate_a), ("estimate_b", &estimate_b)] {
+            if let Some(estimate) = estimate {
+                doc.set(key, estimate.to_json_value());
+            }
+        }
+        writeln!(out, "{doc}")?;
+        return Ok(());
+    }
+    print_stability(
+        &mut out,
+        [(a, estimate_a.as_ref()), (b, estimate_b.as_ref())],
+        top,
+        &stability,
+    )?;
+    Ok(())
+}
+
+fn run_overlap(args: &Args) -> Result<(), Halt> {
+    let mut out = io::stdout().lock();
+    let format = args.value("format").unwrap_or("text");
+    if !["text", "json"].contains(&format) {
+        return Err(tr!(
+            "unknown format `{name}` (expected text or json)",
+            name = format
+        )
+        .into());
+    }
+    let [a, b] = &args.positional[..] else {
+        return Err(tr!("expected exactly two files").into());
+    };
+    let read = |path: &str| {
+        td5::input::read_transcoded(path)
+            .map(|(text, _)| WordFrequency::from_text(&text))
+            .map_err(|e| e.to_string())
+    };
+    let (freq_a, freq_b) = (read(a)?, read(b)?);
+    let overlap = Overlap::between(&freq_a, &freq_b, args.number("top", 10)?);
+    if format == "json" {
+        let mut doc = overlap.to_json_value();
+        doc.set("a", a.as_str().into());
+        doc.set("b", b.as_str().into());
+        writeln!(out, "{doc}")?;
+        return Ok(());
+    }
+    print_overlap(&mut out, a, b, &overlap)?;
+    Ok(())
+}
+
+fn run_compare(args: &Args) -> Result<(), Halt> {
+    let mut out = io::stdout().lock();
+    let format = args.value("format").unwrap_or("text");
+    if !["text", "json"].contains(&format) {
+        return Err(tr!(
+            "unknown format `{name}` (expected text or json)",
+            name = format
+        )
+        .into());
+    }
+    let [a, b] = &args.positional[..] else {
+        return Err(tr!("expected exactly two files").into());
+    };
+    let top = args.number("top", 10)?;
+    let read = |path: &str| Side::read(path).map_err(|e| e.to_string());
+    let (side_a, side_b) = (read(a)?, read(b)?);
+    if side_a.freq.is_none() || side_b.freq.is_none() {
+        info!(
+            "{}",
+            tr!("comparing saved results: the word lists only see their top words")
+        );
+    }
+    let diff = side_a.diff(&side_b, top);
+    let (time_a, time_b) = (side_a.time_ns, side_b.time_ns);
+    if format == "json" {
+        let mut doc = Json::Object(vec![
+            ("a".into(), a.as_str().into()),
+            ("b".into(), b.as_str().into()),
+        ]);
+        let mut diff = diff.to_json_value();
+        diff.set(
+            "time_ns",
+            Json::Object(vec![
+                ("a".into(), time_a.into()),
+                ("b".into(), time_b.into()),
+                ("change".into(), (time_b as f64 - time_a as f64).into()),
+            ]),
+        );
+        doc.set("diff", diff);
+        writeln!(out, "{doc}")?;
+        return Ok(());
+    }
+    print_diff(&mut out, (a, time_a), (b, time_b), &diff)?;
+    Ok(())
+}
+
+/// The counter `--epsilon`, `--delta` and `--capacity` ask for; an error
+/// is a usage error, as a sketch too large to allocate is.
+fn approx_counter(args: &Args) -> Result<ApproxCounter, String> {
+    let mut counter =
+        ApproxCounter::new(args.number("epsilon", 0.0001)?, args.number("delta", 0.01)?)
+            .map_err(|e| e.to_string())?;
+    if args.has("capacity") {
+        counter = counter.capacity(args.number("capacity", 0)?);
+    }
+    Ok(counter)
+}
+
+fn run_approx(args: &Args, mut counter: ApproxCounter) -> Result<(), Halt> {
+    let mut out = io::stdout().lock();
+    let format = args.value("format").unwrap_or("text");
+    if !["text", "json"].contains(&format) {
+        return Err(tr!(
+            "unknown format `{name}` (expected text or json)",
+            name = format
+        )
+        .into());
+    }
+    if let Some(stopwords) = stopwords(args)? {
+        counter = counter.config(AnalyzerConfig::new().stopwords(stopwords));
+    }
+    let top = args.number("top", 10)?;
+    let every = args
+        .value("every")
+        .map(|_| args.number("every", 0.0).map(Duration::from_secs_f64))
+        .transpose()?;
+    let mut report = |counter: &ApproxCounter| match format {
+        "json" => writeln!(out, "{}", counter.to_json_value(top)),
+        _ => print_approx(&mut out, counter, top),
+    };
+    let inputs = match args.positional.as_slice() {
+        [] => vec!["-".to_string()],
+        paths => paths.to_vec(),
+    };
+    let mut last = Instant::now();
+    // Line by line, so memory stays that of the counter however long the
+    // input runs.
+    let mut line = Vec::new();
+    for input in &inputs {
+        let mut reader: Box<dyn io::BufRead> = match input.as_str() {
+            "-" => Box::new(io::stdin().lock()),
+            path => Box::new(io::BufReader::new(
+                std::fs::File::open(path).map_err(|e| format!("{path}: {e}"))?,
+            )),
+        };
+        loop {
+            line.clear();
+            let read = reader
+                .read_until(b'\n', &mut line)
+                .map_err(|e| format!("{input}: {e}"))?;
+            if read == 0 {
+                break;
+            }
+            counter.add_text(&String::from_utf8_lossy(&line));
+            if let Some(every) = every
+                && last.elapsed() >= every
+            {
+                report(&counter)?;
+                last = Instant::now();
+            }
+        }
+    }
+    report(&counter)?;
+    Ok(())
+}
+
+fn run_watch(args: &Args) -> Result<(), Halt> {
+    let mut out = io::stdout().lock();
+    let format = args.value("format").unwrap_or("text");
+    if !["text", "json"].contains(&format) {
+        return Err(tr!(
+            "unknown format `{name}` (expected text or json)",
+            name = format
+        )
+        .into());
+    }
+    let [path] = args.positional.as_slice() else {
+        return Err(tr!("expected exactly one file").into());
+    };
+    let interval = Duration::from_millis(args.number("interval", 500)?);
+    let config = AnalyzerConfig::new().top_words(args.number("top", 10)?);
+    let mut watcher = Watcher::new(path);
+    let mut last: Option<TextStats> = None;
+    loop {
+        watcher.wait(interval).map_err(|e| e.to_string())?;
+        // A read can catch the file half-written; the next save fixes it.
+        let text = match td5::input::read_transcoded(path) {
+            Ok((text, _)) => text,
+            Err(e) => {
+                warn!("{e}");
+                continue;
+            }
+        };
+        let start = Instant::now();
+        let stats = td5::analyze_text_fast_with(&text, &config);
+        let time_ns = start.elapsed().as_nanos();
+        match (
+            last.as_ref().map(|before| Delta::between(before, &stats)),
+            format,
+        ) {
+            (None, "json") => {
+                let mut doc = stats.to_json_value();
+                doc.set("time_ns", time_ns.into());
+                doc.set("source", path.as_str().into());
+                writeln!(out, "{doc}")?;
+            }
+            (None, _) => print_stats(&mut out, path, &stats, Some(time_ns))?,
+            (Some(delta), _) if delta.is_empty() => {}
+            (Some(delta), "json") => {
+                let mut doc = delta.to_json_value();
+                doc.set("source", path.as_str().into());
+                writeln!(out, "{doc}")?;
+            }
+            (Some(delta), _) => print_delta(&mut out, path, &delta)?,
+        }
+        last = Some(stats);
+    }
+}
+
+/// Runs of each analyzer timed by `td5 report`; the fastest counts.
+const REPORT_RUNS: usize = 3;
+
+fn run_report(args: &Args) -> Result<(), Halt> {
+    let out = args.value("out");
+    let markdown = out.is_some_and(|out| out.ends_with(".md") || out.ends_with(".markdown"));
+    let format = args
+        .value("format")
+        .unwrap_or(if markdown { "markdown" } else { "html" });
+    if !["html", "markdown"].contains(&format) {
+        return Err(tr!(
+            "unknown format `{name}` (expected html or markdown)",
+            name = format
+        )
+        .into());
+    }
+    let analyzers = args
+        .list("analyzers")
+        .unwrap_or_else(|| vec!["slow", "fast"])
+        .into_iter()
+        .map(|name| {
+            analyzer::find(name).ok_or_else(|| tr!("unknown analyzer `{name}`", name = name))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let [path] = &args.positional[..] else {
+        return Err(tr!("expected exactly one file").into());
+    };
+    let text = if path == "-" {
+        let bytes = td5::input::read_all(io::stdin().lock()).map_err(|e| format!("stdin: {e}"))?;
+        encoding::decode(&bytes).text.into_owned()
+    } else {
+        td5::input::read_transcoded(path)
+            .map_err(|e| e.to_string())?
+            .0
+    };
+    let timings = analyzers
+        .iter()
+        .map(|r| {
+            let fastest = (0..REPORT_RUNS)
+                .map(|_| {
+                    let start = Instant::now();
+                    black_box(r.analyzer.analyze(black_box(&text)));
+                    start.elapsed().as_nanos()
+                })
+                .min()
+                .unwrap_or(0);
+            (r.name.to_string(), fastest)
+        })
+        .collect();
+    let title = if path == "-" { "stdin" } else { path.as_str() };
+    let report = Report::new(
+        title,
+        &td5::analyze(&text),
+        &WordFrequency::from_text(&text),
+    )
+    .with_timings(timings);
+    let page = match format {
+        "markdown" => report.to_markdown(),
+        _ => report.to_html(),
+    };
+    match out {
+        Some(out) => {
+            std::fs::write(out, page).map_err(|e| format!("{out}: {e}"))?;
+            info!("{}", tr!("report written to {path}", path = out));
+        }
+        None => write!(io::stdout().lock(), "{page}")?,
+    }
+    Ok(())
+}
+
+fn run_batch(args: &Args) -> Result<ExitCode, Halt> {
+    let mut out = io::stdout().lock();
+    let format = args.value("format").unwrap_or("text");
+    if !["text", "csv", "json"].contains(&format) {
+        return Err(tr!(
+            "unknown format `{name}` (expected text, csv or json)",
+            name = format
+        )
+        .into());
+    }
+    let [path] = &args.positional[..] else {
+        return Err(tr!("expected exactly one manifest").into());
+    };
+    let on_error = OnError::parse(args)?;
+    let manifest = Manifest::load(path.as_ref()).map_err(|e| e.to_string())?;
+    let keep_going = on_error != OnError::Abort;
+    let report = match args.value("resume") {
+        Some(saved) => {
+            let saved = std::path::Path::new(saved);
+            let checkpoint = Checkpoint::load(saved)
+                .map_err(|e| e.to_string())?
+                .unwrap_or_default();
+            if !checkpoint.done.is_empty() {
+                info!(
+                    "{}",
+                    tr!(
+                        "{path}: resuming after {done} of {total} inputs",
+                        path = saved.display(),
+                        done = checkpoint.done.len(),
+                        total = manifest.items.len()
+                    )
+                );
+            }
+            let report = manifest
+                .resume(checkpoint, keep_going, |checkpoint| checkpoint.save(saved))
+                .map_err(|e| e.to_string())?;
+            match std::fs::remove_file(saved) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                    return Err(format!("{}: {e}", saved.display()).into());
+                }
+                _ => {}
+            }
+            report
+        }
+        None => manifest.run(keep_going).map_err(|e| e.to_string())?,
+    };
+    let mut failures = Failures::new(on_error);
+    for (label, message) in &report.errors {
+        failures.record(label, message.clone())?;
+    }
+    let total = manifest.items.len();
+    let transcoded = report
+        .encodings
+        .iter()
+        .filter(|(_, t)| t.transcoded)
+        .count();
+    if transcoded > 0 {
+        info!(
+            "{}",
+            tr!(
+                "{count} of {total} inputs transcoded to UTF-8",
+                count = transcoded,
+                total = report.encodings.len()
+            )
+        );
+    }
+    if let Some(dir) = args.value("write-normalized") {
+        let base = std::path::Path::new(path)
+            .parent()
+            .unwrap_or(std::path::Path::new(""));
+        for item in &manifest.items {
+            if report.encoding(&item.label).is_none() {
+                continue;
+            }
+            match item.write_normalized(dir.as_ref(), base) {
+                Ok(target) => info!(
+                    "{}",
+                    tr!(
+                        "{path}: written as UTF-8 to {target}",
+                        path = item.path.display(),
+                        target = target.display()
+                    )
+                ),
+                Err(e) => failures.record(&item.label, e.to_string())?,
+            }
+        }
+    }
+    if format == "json" {
+        writeln!(out, "{}", report.to_json_value())?;
+        return Ok(failures.status(total));
+    }
+    if format == "csv" {
+        write!(out, "{}", report.to_csv(&manifest))?;
+        return Ok(failures.status(total));
+    }
+    for (label, stats) in &report.items {
+        print_stats(&mut out, label, stats, report.time_ns(label))?;
+        if let Some(transcoding) = report.encoding(label) {
+            let mut encoding = transcoding.encoding.to_string();
+            if transcoding.had_bom {
+                encoding = tr!("{encoding} with a BOM", encoding = encoding);
+            }
+            if transcoding.transcoded {
+                encoding.push_str(" → UTF-8");
+            }
+            writeln!(out, "  {} {encoding}", tr!("Encoding:"))?;
+        }
+    }
+    print_stats(
+        &mut out,
+        tr!("Combined"),
+        &report.combined,
+        Some(report.combined_time_ns()),
+    )?;
+    if !failures.is_empty() {
+        failures.print(&mut out, total)?;
+    }
+    Ok(failures.status(total))
+}
+
+fn run_trend(args: &Args) -> Result<(), Halt> {
+    let mut out = io::stdout().lock();
+    let format = args.value("format").unwrap_or("text");
+    if !["text", "csv", "json"].contains(&format) {
+        return Err(tr!(
+            "unknown format `{name}` (expected text, csv or json)",
+            name = format
+        )
+        .into());
+    }
+    if args.positional.len() < 2 {
+        return Err(tr!("expected at least two snapshots").into());
+    }
+    let mut snapshots = Vec::new();
+    for path in &args.positional {
+        let mut freq = WordFrequency::default();
+        for file in td5::input::files(path).map_err(|e| e.to_string())? {
+            let (text, _) = td5::input::read_transcoded(&file).map_err(|e| e.to_string())?;
+            freq.merge(WordFrequency::from_text(&text));
+        }
+        snapshots.push((path.clone(), freq));
+    }
+    let trend = Trend::of(&snapshots, args.number("min-count", 5)?);
+    let top = args.number("top", 10)?;
+    match format {
+        "csv" => {
+            write!(out, "{}", trend.to_csv())?;
+            return Ok(());
+        }
+        "json" => {
+            writeln!(out, "{}", trend.to_json_value(top))?;
+            return Ok(());
+        }
+        _ => {}
+    }
+    for (label, total) in trend.labels.iter().zip(&trend.totals) {
+        writeln!(out, "  {label:<24} {:>12} {}", number(total), tr!("words"))?;
+    }
+    let print = |out: &mut io::StdoutLock, w: &WordTrend| {
+        let series: Vec<String> = w.per_million.iter().map(|&f| decimal(f, 1)).collect();
+        let sign = if w.change > 0.0 { "+" } else { "" };
+        let change = format!("{sign}{}", decimal(w.change, 2));
+        writeln!(out, "  {:<20} {change:>7}  {}", w.word, series.join(" → "))
+    };
+    writeln!(
+        out,
+        "\n{}",
+        tr!("Rising (log2 change, then per million words):")
+    )?;
+    for w in trend.rising(top) {
+        print(&mut out, w)?;
+    }
+    writeln!(
+        out,
+        "\n{}",
+        tr!("Falling (log2 change, then per million words):")
+    )?;
+    for w in trend.falling(top) {
+        print(&mut out, w)?;
+    }
+    Ok(())
+}
+
+fn run_readability(args: &Args) -> Result<(), Halt> {
+    let mut out = io::stdout().lock();
+    let format = args.value("format").unwrap_or("text");
+    if !["text", "csv", "json"].contains(&format) {
+        return Err(tr!(
+            "unknown format `{name}` (expected text, csv or json)",
+            name = format
+        )
+        .into());
+    }
+    let [path] = &args.positional[..] else {
+        return Err(tr!("expected exactly one file").into());
+    };
+    let (text, _) = td5::input::read_transcoded(path).map_err(|e| e.to_string())?;
+    let profile = Profile::of(&text, args.number("window", 200)?, args.number("step", 50)?);
+    match format {
+        "csv" => {
+            write!(out, "{}", profile.to_csv())?;
+            return Ok(());
+        }
+        "json" => {
+            writeln!(out, "{}", profile.to_json_value())?;
+            return Ok(());
+        }
+        _ => {}
+    }
+    let overall = &profile.overall;
+    writeln!(
+        out,
+        "{}",
+        tr!(
+            "{words} words in {sentences} sentences: {length} words per sentence, grade {grade}, reading ease {ease}",
+            words = number(overall.words),
+            sentences = number(overall.sentences),
+            length = decimal(overall.words_per_sentence(), 1),
+            grade = decimal(overall.grade(), 1),
+            ease = decimal(overall.reading_ease(), 0)
+        )
+    )?;
+    writeln!(
+        out,
+        "\n  {:<13} {:>9} {:>6} {:>6}",
+        tr!("Lines"),
+        tr!("Words/s."),
+        tr!("Grade"),
+        tr!("Ease")
+    )?;
+    for point in &profile.points {
+        let r = &point.readability;
+        let bar = "#".repeat(r.grade().clamp(0.0, 40.0).round() as usize);
+        writeln!(
+            out,
+            "  {:<13} {:>9} {:>6} {:>6}  {bar}",
+            format!("{}-{}", point.lines.0, point.lines.1),
+            decimal(r.words_per_sentence(), 1),
+            decimal(r.grade(), 1),
+            decimal(r.reading_ease(), 0)
+        )?;
+    }
+    let hardest = profile.hardest(args.number("hardest", 3)?);
+    if !hardest.is_empty() {
+        writeln!(out, "\n{}", tr!("Hardest passages:"))?;
+        for point in hardest {
+            writeln!(
+                out,
+                "  {}",
+                tr!(
+                    "lines {first}-{last}: grade {grade}, {length} words per sentence",
+                    first = point.lines.0,
+                    last = point.lines.1,
+                    grade = decimal(point.readability.grade(), 1),
+                    length = decimal(point.readability.words_per_sentence(), 1)
+                )
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn run_cooccur(args: &Args) -> Result<(), Halt> {
+    let mut out = io::stdout().lock();
+    let format = args.value("format").unwrap_or("text");
+    if !["text", "json", "dot", "graphml"].contains(&format) {
+        return Err(tr!(
+            "unknown format `{name}` (expected text, json, dot or graphml)",
+            name = format
+        )
+        .into());
+    }
+    if args.positional.is_empty() {
+        return Err(tr!("expected at least one file").into());
+    }
+    let mut texts = Vec::new();
+    for path in &args.positional {
+        for file in td5::input::files(path).map_err(|e| e.to_string())? {
+            let (text, _) = td5::input::read_transcoded(&file).map_err(|e| e.to_string())?;
+            texts.push(text);
+        }
+    }
+    let documents: Vec<&str> = texts.iter().map(String::as_str).collect();
+    let graph = Graph::build(
+        &documents,
+        args.number("window", 5)?,
+        args.number("top", 50)?,
+        args.number("min-weight", 2)?,
+    );
+    match format {
+        "json" => writeln!(out, "{}", graph.to_json_value())?,
+        "dot" => write!(out, "{}", graph.to_dot())?,
+        "graphml" => write!(out, "{}", graph.to_graphml())?,
+        _ => {
+            writeln!(
+                out,
+                "{}",
+                tr!(
+                    "{words} words, {links} links",
+                    words = number(graph.nodes.len()),
+                    links = number(graph.edges.len())
+                )
+            )?;
+            for &(a, b, weight) in &graph.edges {
+                writeln!(
+                    out,
+                    "  {:>8}  {} -- {}",
+                    number(weight),
+                    graph.nodes[a].0,
+                    graph.nodes[b].0
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_concordance(args: &Args) -> Result<(), Halt> {
+    let mut out = io::stdout().lock();
+    let format = args.value("format").unwrap_or("text");
+    if !["text", "json"].contains(&format) {
+        return Err(tr!(
+            "unknown format `{name}` (expected text or json)",
+            name = format
+        )
+        .into());
+    }
+    let [word, paths @ ..] = args.positional.as_slice() else {
+        return Err(tr!("expected a word and at least one file").into());
+    };
+    if paths.is_empty() {
+        return Err(tr!("expected a word and at least one file").into());
+    }
+    let context = args.number("context", 5)?;
+    let config = AnalyzerConfig::default();
+    let mut count = 0;
+    for path in paths {
+        for file in td5::input::files(path).map_err(|e| e.to_string())? {
+            let (text, _) = td5::input::read_transcoded(&file).map_err(|e| e.to_string())?;
+            let lines = Concordance::of(&text, word, &config).lines(word, context);
+            count += lines.len();
+            let file = file.display();
+            if format == "json" {
+                let doc = Json::Object(vec![
+                    ("path".into(), file.to_string().into()),
+                    ("word".into(), word.as_str().into()),
+                    (
+                        "lines".into(),
+                        Json::Array(lines.iter().map(|line| line.to_json_value()).collect()),
+                    ),
+                ]);
+                writeln!(out, "{doc}")?;
+                continue;
+            }
+            // Left contexts right-aligned, so the occurrences line up.
+            let width = lines.iter().map(|l| l.left.chars().count()).max();
+            for line in &lines {
+                let row = format!(
+                    "{file}:{}  {:>width$}  {}  {}",
+                    line.offset,
+                    line.left,
+                    line.word,
+                    line.right,
+                    width = width.unwrap_or(0)
+                );
+                writeln!(out, "{}", row.trim_end())?;
+            }
+        }
+    }
+    info!(
+        "{}",
+        tr!(
+            "{count} occurrences of `{word}`",
+            count = count,
+            word = word
+        )
+    );
+    Ok(())
+}
+
+fn run_unknown(args: &Args) -> Result<(), Halt> {
+    let mut out = io::stdout().lock();
+    let Some(path) = args.value("dictionary") else {
+        return Err(tr!("expected --dictionary <WORDLIST>").into());
+    };
+    let (source, _) = td5::input::read_transcoded(path).map_err(|e| e.to_string())?;
+    let dictionary = Dictionary::parse(&source);
+    debug!("{path}: {} words", dictionary.len());
+    let min_count = args.number("min-count", 1)?;
+    if args.positional.is_empty() {
+        return Err(tr!("expected at least one file").into());
+    }
+    let mut freq = WordFrequency::default();
+    for path in &args.positional {
+        let (text, _) = td5::input::read_transcoded(path).map_err(|e| e.to_string())?;
+        freq.merge(WordFrequency::from_text(&text));
+    }
+    let unknown = dictionary.unknown(&freq);
+    let occurrences: usize = unknown.iter().map(|(_, c)| c).sum();
+    for (word, count) in unknown.iter().take_while(|(_, c)| *c >= min_count) {
+        writeln!(out, "{count}\t{word}")?;
+    }
+    info!(
+        "{}",
+        tr!(
+            "{words} unknown words, {share}% of {total} tokens",
+            words = number(unknown.len()),
+            share = decimal(100.0 * occurrences as f64 / freq.total().max(1) as f64, 1),
+            total = number(freq.total())
+        )
+    );
+    Ok(())
+}
+
+#[cfg(any(feature = "nats", feature = "kafka"))]
+fn run_consume(args: &Args) -> Result<(), String> {
+    use std::sync::mpsc::{self, RecvTimeoutError};
+    use std::time::Duration;
+    use td5::clock;
+    use td5::streaming::StreamingAnalyzer;
+
+    let source = match (args.value("nats"), args.value("kafka")) {
+        (Some(address), None) => args.value("subject").map(|subject| (address, subject)),
+        (None, Some(address)) => args.value("topic").map(|topic| (address, topic)),
+        _ => None,
+    };
+    let Some((address, subject)) = source else {
+        return Err(tr!(
+            "expected --nats <ADDRESS> and --subject <SUBJECT>, or --kafka <ADDRESS> and --topic <TOPIC>"
+        )
+        .into());
+    };
+    let every = Duration::from_secs_f64(args.number("every", 10.0)?);
+    let mut frames = frames(args)?;
+    let specs = match args.values("output") {
+        // Binary frames on stdout leave no room for JSON lines.
+        specs if specs.is_empty() && args.value("frames") == Some("-") => Vec::new(),
+        specs if specs.is_empty() => vec!["-"],
+        specs => specs,
+    };
+    let mut sinks = open_outputs(specs)?;
+    let mut alerts = consume_alerts(args)?;
+    let mut alert_sinks = if alerts.is_empty() {
+        Vec::new()
+    } else {
+        match args.values("alert-to") {
+            specs if specs.is_empty() => open_outputs(vec!["-"])?,
+            specs => open_outputs(specs)?,
+        }
+    };
+    let alert_context = |messages: usize| {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        [
+            ("subject", Json::from(subject)),
+            ("messages", messages.into()),
+            ("time", td5::timestamp::format(now).into()),
+        ]
+    };
+    let mut next_message = connect(args, address, subject)?;
+    info!(
+        "{}",
+        tr!(
+            "subscribed to {subject} on {address}",
+            subject = subject,
+            address = address
+        )
+    );
+
+    // Bounded, so a slow analyzer stops the reader and TCP pushes back on
+    // the server instead of messages piling up here.
+    let (tx, rx) = mpsc::sync_channel(1024);
+    std::thread::spawn(move || {
+        loop {
+            let message = next_message().map_err(|e| e.to_string());
+            let end = !matches!(message, Ok(Some(_)));
+            if tx.send(message).is_err() || end {
+                break;
+            }
+        }
+    });
+
+    let clock = clock::system();
+    let mut analyzer = StreamingAnalyzer::with_clock(Default::default(), clock.clone());
+    let (mut messages, mut recent, mut invalid_utf8) = (0usize, 0usize, 0usize);
+    let mut next = clock.now() + every;
+    let mut emit = |partial: td5::streaming::PartialStats,
+                    messages: usize,
+                    recent: usize,
+                    invalid_utf8: usize| {
+        let mut doc = partial.stats.to_json_value();
+        if let Some(counter) = doc
+            .get_mut("diagnostics")
+            .and_then(|d| d.get_mut("invalid_utf8"))
+        {
+            *counter = invalid_utf8.into();
+        }
+        doc.set("messages", messages.into());
+        doc.set("recent_messages", recent.into());
+        doc.set("bytes", partial.bytes.into());
+        deliver(&mut sinks, &doc.to_string());
+        if let Some(write) = &mut frames
+            && let Err(e) = write(&doc, partial.done)
+        {
+            warn!(
+                "{}",
+                tr!("output {spec}: {message}", spec = "--frames", message = e)
+            );
+        }
+    };
+    loop {
+        match rx.recv_timeout(next.saturating_sub(clock.now())) {
+            Ok(Ok(Some(payload))) => {
+                let (text, invalid) = td5::bytes::decode_lossy(&payload);
+                // Messages are separate documents: never join their edge words.
+                analyzer.push(&format!("{text}\n"));
+                messages += 1;
+                recent += 1;
+                invalid_utf8 += invalid;
+                if let Some(event) = alerts.scan(&text) {
+                    let payload = alerts.payload(&event, &alert_context(messages));
+                    deliver(&mut alert_sinks, &payload);
+                }
+            }
+            Ok(Ok(None)) | Err(RecvTimeoutError::Disconnected) => {
+                info!("{}", tr!("connection closed"));
+                let partial = analyzer.finish();
+                for event in alerts.check(&partial.stats) {
+                    let payload = alerts.payload(&event, &alert_context(messages));
+                    deliver(&mut alert_sinks, &payload);
+                }
+                emit(partial, messages, recent, invalid_utf8);
+                for (spec, sink) in sinks.iter_mut().chain(&mut alert_sinks) {
+                    if let Err(e) = sink.flush() {
+                        warn!(
+                            "{}",
+                            tr!("output {spec}: {message}", spec = spec, message = e)
+                        );
+                    }
+                }
+                return Ok(());
+            }
+            Ok(Err(e)) => return Err(e),
+            Err(RecvTimeoutError::Timeout) => {
+                let partial = analyzer.snapshot();
+                for event in alerts.check(&partial.stats) {
+                    let payload = alerts.payload(&event, &alert_context(messages));
+                    deliver(&mut alert_sinks, &payload);
+                }
+                emit(partial, messages, recent, invalid_utf8);
+                recent = 0;
+                next += every;
+            }
+        }
+    }
+}
+
+/// The next message of a `consume` source, `None` once it closes.
+#[cfg(any(feature = "nats", feature = "kafka"))]
+type NextMessage = Box<dyn FnMut() -> td5::Result<Option<Vec<u8>>> + Send>;
+
+/// Subscribes to `subject` of the NATS server at `address`, or reads the
+/// Kafka topic `subject` from the broker at `address`.
+#[cfg(any(feature = "nats", feature = "kafka"))]
+fn connect(args: &Args, address: &str, subject: &str) -> Result<NextMessage, String> {
+    if args.has("nats") {
+        #[cfg(feature = "nats")]
+        {
+            let mut subscriber =
+                td5::nats::Subscriber::connect(address, subject).map_err(|e| e.to_string())?;
+            return Ok(Box::new(move || subscriber.next_message()));
+        }
+        #[cfg(not(feature = "nats"))]
+        return Err(tr!("--nats needs a build with `--features nats`").into());
+    }
+    #[cfg(feature = "kafka")]
+    {
+        let partition = args.number("partition", 0)?;
+        let mut consumer =
+            td5::kafka::Consumer::connect(address, subject, partition, args.has("from-beginning"))
+                .map_err(|e| e.to_string())?;
+        Ok(Box::new(move || consumer.next_message()))
+    }
+    #[cfg(not(feature = "kafka"))]
+    Err(tr!("--kafka needs a build with `--features kafka`").into())
+}
+
+/// Writes a snapshot as a binary frame, `true` for the last one.
+#[cfg(any(feature = "nats", feature = "kafka"))]
+type FrameSink = Box<dyn FnMut(&Json, bool) -> io::Result<()>>;
+
+/// `consume --frames PATH`: snapshots appended to PATH, or written to
+/// stdout for `-`, as `td5::frame` frames.
+#[cfg(all(any(feature = "nats", feature = "kafka"), feature = "frames"))]
+fn frames(args: &Args) -> Result<Option<FrameSink>, String> {
+    let Some(path) = args.value("frames") else {
+        return Ok(None);
+    };
+    let out: Box<dyn Write> = if path == "-" {
+        Box::new(io::stdout())
+    } else {
+        Box::new(
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| format!("{path}: {e}"))?,
+        )
+    };
+    let mut writer = td5::frame::FrameWriter::new(out);
+    Ok(Some(Box::new(move |doc, done| writer.write(doc, done))))
+}
+
+#[cfg(all(any(feature = "nats", feature = "kafka"), not(feature = "frames")))]
+fn frames(args: &Args) -> Result<Option<FrameSink>, String> {
+    if args.has("frames") {
+        return Err(tr!("--frames needs a build with `--features frames`").into());
+    }
+    Ok(None)
+}
+
+/// An output sink with the spec it was opened from, for messages.
+#[cfg(any(feature = "nats", feature = "kafka"))]
+type Output<'a> = (&'a str, Box<dyn td5::output::OutputSink>);
+
+#[cfg(any(feature = "nats", feature = "kafka"))]
+fn open_outputs(specs: Vec<&str>) -> Result<Vec<Output<'_>>, String> {
+    specs
+        .into_iter()
+        .map(|spec| Ok((spec, td5::output::open(spec).map_err(|e| e.to_string())?)))
+        .collect()
+}
+
+/// Writes `report` to every sink. One failing destination must not stop the
+/// others or the stream, so failures are only reported.
+#[cfg(any(feature = "nats", feature = "kafka"))]
+fn deliver(sinks: &mut [Output], report: &str) {
+    for (spec, sink) in sinks {
+        if let Err(e) = sink.write_report(report) {
+            warn!(
+                "{}",
+                tr!("output {spec}: {message}", spec = spec, message = e)
+            );
+        }
+    }
+}
+
+/// `--alert`, `--alert-deny` and `--alert-template` of `td5 consume`.
+#[cfg(any(feature = "nats", feature = "kafka"))]
+fn consume_alerts(args: &Args) -> Result<td5::alerts::Alerts, String> {
+    let rules = args
+        .values("alert")
+        .into_iter()
+        .map(Assertion::parse)
+        .collect::<td5::Result<_>>()
+        .map_err(|e| e.to_string())?;
+    let mut alerts = td5::alerts::Alerts::new(rules);
+    if let Some(list) = args.value("alert-deny") {
+        let source = td5::input::read_text(list).map_err(|e| e.to_string())?;
+        alerts = alerts.deny(DenyList::parse(&source).map_err(|e| format!("{list}: {e}"))?);
+    }
+    if let Some(path) = args.value("alert-template") {
+        let source = td5::input::read_text(path).map_err(|e| e.to_string())?;
+        alerts = alerts.template(Template::parse(&source).map_err(|e| format!("{path}: {e}"))?);
+    }
+    Ok(alerts)
+}
+
+#[cfg(not(any(feature = "nats", feature = "kafka")))]
+fn run_consume(_args: &Args) -> Result<(), String> {
+    Err(tr!("td5 was built without the `nats` and `kafka` features").into())
+}
+
+#[cfg(feature = "grpc")]
+fn run_grpc(args: &Args) -> Result<(), String> {
+    use std::time::Duration;
+    use td5::grpc::{Limits, Server};
+
+    let defaults = Limits::default();
+    let max_message = match args.value("max-message") {
+        Some(size) => td5::slice::parse_size(size).ok_or_else(|| {
+            tr!(
+                "`--{name}` expects a size such as 500000, 64K or 50M, got `{value}`",
+                name = "max-message",
+                value = size
+            )
+        })? as usize,
+        None => defaults.max_message,
+    };
+    let limits = Limits {
+        max_message,
+        max_connections: args.number("max-connections", defaults.max_connections)?,
+        max_streams: args.number("max-streams", defaults.max_streams)?,
+        timeout: Duration::from_secs_f64(args.number("timeout", defaults.timeout.as_secs_f64())?),
+        idle_timeout: Duration::from_secs_f64(
+            args.number("idle-timeout", defaults.idle_timeout.as_secs_f64())?,
+        ),
+    };
+    let address = args.value("listen").unwrap_or("127.0.0.1:50051");
+    let server = Server::bind(address, Default::default())
+        .map_err(|e| format!("{address}: {e}"))?
+        .limits(limits);
+    let address = server.local_addr().map_err(|e| e.to_string())?;
+    #[cfg(unix)]
+    stop_on_signals(server.shutdown_handle().map_err(|e| e.to_string())?);
+    info!("{}", tr!("listening on {address}", address = address));
+    server.run().map_err(|e| e.to_string())
+}
+
+/// Set by SIGINT and SIGTERM.
+#[cfg(all(feature = "grpc", unix))]
+static STOP: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// Shuts the server down gracefully on SIGINT (Ctrl-C) or SIGTERM.
+#[cfg(all(feature = "grpc", unix))]
+fn stop_on_signals(shutdown: td5::grpc::Shutdown) {
+    use std::sync::atomic::Ordering;
+
+    const SIGINT: i32 = 2;
+    const SIGTERM: i32 = 15;
+    unsafe extern "C" {
+        fn signal(signum: i32, handler: extern "C" fn(i32)) -> usize;
+    }
+    extern "C" fn handle(_: i32) {
+        STOP.store(true, Ordering::SeqCst);
+    }
+    // SAFETY: the handler only stores to an atomic, which is
+    // async-signal-safe.
+    unsafe {
+        signal(SIGINT, handle);
+        signal(SIGTERM, handle);
+    }
+    std::thread::spawn(move || {
+        while !STOP.load(Ordering::SeqCst) {
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+        info!(
+            "{}",
+            tr!("shutting down once the calls in progress are answered")
+        );
+        shutdown.shutdown();
+    });
+}
+
+#[cfg(not(feature = "grpc"))]
+fn run_grpc(_args: &Args) -> Result<(), String> {
+    Err(tr!("td5 was built without the `grpc` feature").into())
+}
+
+#[cfg(feature = "git")]
+fn run_authors(args: &Args) -> Result<(), Halt> {
+    let mut out = io::stdout().lock();
+    let format = args.value("format").unwrap_or("text");
+    if !["text", "json"].contains(&format) {
+        return Err(tr!(
+            "unknown format `{name}` (expected text or json)",
+            name = format
+        )
+        .into());
+    }
+    let [repo] = &args.positional[..] else {
+        return Err(tr!("expected exactly one repository").into());
+    };
+    let extensions: Vec<&str> = match args.value("ext") {
+        Some(list) => list
+            .split(',')
+            .map(|e| e.trim().trim_start_matches('.'))
+            .filter(|e| !e.is_empty())
+            .collect(),
+        None => td5::blame::DOC_EXTENSIONS.to_vec(),
+    };
+    let top = args.number("top", 5)?;
+    let authorship =
+        td5::blame::authors(std::path::Path::new(repo), &extensions).map_err(|e| e.to_string())?;
+    for (path, e) in &authorship.skipped {
+        debug!("{}: {e}", path.display());
+    }
+    info!(
+        "{}",
+        tr!(
+            "{files} files read, {skipped} skipped (not tracked by git?)",
+            files = number(authorship.files),
+            skipped = number(authorship.skipped.len())
+        )
+    );
+    if format == "json" {
+        for author in &authorship.authors {
+            writeln!(out, "{}", author.to_json_value())?;
+        }
+        return Ok(());
+    }
+    let width = authorship
+        .authors
+        .iter()
+        .map(|a| a.author.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(6);
+    writeln!(
+        out,
+        "  {:<width$} {:>8} {:>9} {:>8} {:>8}  {}",
+        tr!("Author"),
+        tr!("Lines"),
+        tr!("Words"),
+        tr!("Unique"),
+        tr!("Files"),
+        tr!("Top words:").trim_end_matches([':', ' '])
+    )?;
+    for author in &authorship.authors {
+        let words: Vec<String> = author
+            .stats
+            .top_words
+            .iter()
+            .take(top)
+            .map(|(w, n)| format!("{w} {n}"))
+            .collect();
+        writeln!(
+            out,
+            "  {:<width$} {:>8} {:>9} {:>8} {:>8}  {}",
+            author.author,
+            number(author.lines),
+            number(author.stats.total_words),
+            number(author.stats.word_count),
+            number(author.files),
+            words.join(", ")
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "git"))]
+fn run_authors(_args: &Args) -> Result<(), Halt> {
+    Err(tr!("td5 was built without the `git` feature").into())
+}
+
+fn run_wiki(args: &Args) -> Result<(), Halt> {
+    let format = args.value("format").unwrap_or("text");
+    if !["text", "json"].contains(&format) {
+        return Err(tr!(
+            "unknown format `{name}` (expected text or json)",
+            name = format
+        )
+        .into());
+    }
+    let [path] = &args.positional[..] else {
+        return Err(tr!("expected exactly one dump").into());
+    };
+    let mut options = td5::wiki::DumpOptions {
+        threads: args.number("threads", 0)?,
+        redirects: args.has("redirects"),
+        limit: args
+            .value("limit")
+            .map(|_| args.number("limit", 0))
+            .transpose()?,
+        ..Default::default()
+    };
+    match args.value("ns") {
+        Some("all") => options.namespaces.clear(),
+        Some(list) => {
+            options.namespaces = list
+                .split(',')
+                .map(|ns| {
+                    ns.trim()
+                        .parse()
+                        .map_err(|_| tr!("unknown namespace `{name}`", name = ns))
+                })
+                .collect::<Result<_, _>>()?;
+        }
+        None => {}
+    }
+    let mut config = AnalyzerConfig::new();
+    if args.value("ngrams").is_some() {
+        let n = args.number("ngrams", 0)?;
+        if !(2..=3).contains(&n) {
+            return Err(tr!("--ngrams takes 2 or 3, not {n}", n = n).into());
+        }
+        config = config.ngrams(n);
+    }
+    let json = format == "json";
+    // Pages are reported from the worker threads, each line under its own
+    // lock. Once the reader is gone the rest of the dump is read unprinted.
+    let closed = Mutex::new(None);
+    let on_page = |title: &str, stats: &TextStats, time_ns: u128| {
+        let mut closed = closed.lock().unwrap();
+        if closed.is_some() {
+            return;
+        }
+        let mut out = io::stdout().lock();
+        let written = if json {
+            let mut doc = stats.to_json_value();
+            doc.set("time_ns", time_ns.into());
+            doc.set("title", title.into());
+            writeln!(out, "{doc}")
+        } else {
+            writeln!(out, "{title}\t{}\t{}", stats.total_words, stats.word_count)
+        };
+        *closed = written.err();
+    };
+    let reader: Box<dyn io::BufRead> = if path == "-" {
+        Box::new(io::stdin().lock())
+    } else {
+        let file = std::fs::File::open(path).map_err(|e| format!("{path}: {e}"))?;
+        Box::new(io::BufReader::with_capacity(1 << 20, file))
+    };
+    let dump = if args.has("pages") {
+        td5::wiki::analyze_dump_pages(reader, &config, &options, on_page)
+    } else {
+        td5::wiki::analyze_dump(reader, &config, &options)
+    }
+    .map_err(|e| format!("{path}: {e}"))?;
+    if let Some(e) = closed.into_inner().unwrap() {
+        return Err(e.into());
+    }
+    info!(
+        "{}",
+        tr!(
+            "{pages} pages read, {skipped} skipped ({bytes} bytes of XML)",
+            pages = number(dump.pages),
+            skipped = number(dump.skipped),
+            bytes = number(dump.bytes)
+        )
+    );
+    let mut out = io::stdout().lock();
+    if json {
+        let mut doc = dump.stats.to_json_value();
+        doc.set("time_ns", dump.time_ns.into());
+        doc.set("source", path.as_str().into());
+        doc.set("pages", dump.pages.into());
+        doc.set("skipped", dump.skipped.into());
+        writeln!(out, "{doc}")?;
+    } else {
+        print_stats(
+            &mut out,
+            &tr!(
+                "{path} ({pages} pages)",
+                path = path,
+                pages = number(dump.pages)
+            ),
+            &dump.stats,
+            Some(dump.time_ns),
+        )?;
+    }
+    Ok(())
+}
+
+fn run_replay(args: &Args) -> Result<(), Halt> {
+    let mut out = io::stdout().lock();
+    let [path] = &args.positional[..] else {
+        return Err(tr!("expected exactly one bundle").into());
+    };
+    let saved = td5::input::read_text(path).map_err(|e| e.to_string())?;
+    let bundle = Bundle::from_json(&saved).map_err(|e| format!("{path}: {e}"))?;
+    let inputs: Vec<&str> = bundle.plan.inputs.iter().map(|i| i.name()).collect();
+    info!(
+        "{}",
+        tr!(
+            "replaying {inputs} ({iterations} iterations per cell)",
+            inputs = inputs.join(", "),
+            iterations = bundle.plan.iterations
+        )
+    );
+    let report = bundle.plan.run().map_err(|e| e.to_string())?;
+    write!(out, "{}", report.grid())?;
+    print_against(
+        &mut out,
+        tr!("Against the recording:"),
+        &report,
+        &bundle.report,
+    )?;
+    Ok(())
+}
+
+fn run_selftest() -> Result<ExitCode, Halt> {
+    let mut out = io::stdout().lock();
+    let divergences = selftest::run();
+    let cases = selftest::corpus();
+    let mut failed = 0;
+    for (case, _) in &cases {
+        let found: Vec<_> = divergences.iter().filter(|d| d.case == *case).collect();
+        if found.is_empty() {
+            writeln!(out, "ok    {case}")?;
+            continue;
+        }
+        failed += 1;
+        writeln!(out, "FAIL  {case}")?;
+        for d in found {
+            writeln!(
+                out,
+                "        {} {}: expected {}",
+                d.analyzer, d.field, d.expected
+            )?;
+            writeln!(
+                out,
+                "        {:>w$}  got      {}",
+                "",
+                d.actual,
+                w = d.analyzer.len() + d.field.len() + 1
+            )?;
+        }
+    }
+    writeln!(
+        out,
+        "\n{}",
+        tr!(
+            "{failed} of {total} cases diverge",
+            failed = failed,
+            total = cases.len()
+        )
+    )?;
+    if failed == 0 {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+/// `selftest --allocations`: the hot-only fast path must not allocate per
+/// word, checked with the binary's counting allocator.
+fn run_allocation_check() -> Result<ExitCode, Halt> {
+    let mut out = io::stdout().lock();
+    let runs = selftest::hot_path_allocations(&|f| allocations::measure(f).1.allocations);
+    for run in &runs {
+        writeln!(
+            out,
+            "{}",
+            tr!(
+                "{case}, {words} words: {counting} allocations counting, {analyze} in all",
+                case = run.case,
+                words = number(run.words),
+                counting = number(run.counting),
+                analyze = number(run.analyze)
+            )
+        )?;
+    }
+    let failures = selftest::allocation_failures(&runs);
+    for failure in &failures {
+        writeln!(out, "FAIL  {failure}")?;
+    }
+    if failures.is_empty() {
+        writeln!(out, "\n{}", tr!("the hot path allocates nothing per word"))?;
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+/// `selftest --differential`: slow and fast must agree on random texts.
+fn run_differential_check(args: &Args) -> Result<ExitCode, Halt> {
+    let mut out = io::stdout().lock();
+    let cases = args.number("cases", 10_000)?;
+    let seed = args.number("seed", 0)?;
+    let Some(counterexample) = selftest::differential(cases, seed) else {
+        writeln!(
+            out,
+            "{}",
+            tr!(
+                "{cases} random texts from seed {seed}: the fast path agrees with the reference",
+                cases = number(cases),
+                seed = seed
+            )
+        )?;
+        return Ok(ExitCode::SUCCESS);
+    };
+    writeln!(
+        out,
+        "FAIL  {}",
+        tr!(
+            "seed {seed} diverges, shrunk to {text}",
+            seed = counterexample.seed,
+            text = format!("{:?}", counterexample.text)
+        )
+    )?;
+    for (field, expected, actual) in &counterexample.differences {
+        writeln!(out, "        {field}: expected {expected}")?;
+        writeln!(
+            out,
+            "        {:>w$}  got      {actual}",
+            "",
+            w = field.len()
+        )?;
+    }
+    Ok(ExitCode::FAILURE)
+}
+
+/// `selftest --stack`: every analyzer must run on a small thread stack,
+/// whatever the input size.
+fn run_stack_check() -> Result<ExitCode, Halt> {
+    let mut out = io::stdout().lock();
+    let analyzers: Vec<String> = registry().iter().map(|r| r.name.to_string()).collect();
+    let runs = match std::env::current_exe()
+        .map_err(td5::Td5Error::from)
+        .and_then(|exe| stack::run(&exe, &analyzers))
+    {
+        Ok(runs) => runs,
+        Err(e) => {
+            eprintln!("{}", tr!("error: {message}", message = e));
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+    for run in &runs {
+        let needed = match run.needed {
+            Some(bytes) if bytes <= stack::SMALLEST_STACK => {
+                tr!("at most {kib} KiB", kib = bytes / 1024)
+            }
+            Some(bytes) => tr!("{kib} KiB", kib = bytes / 1024),
+            None => tr!("overflow").to_string(),
+        };
+        writeln!(
+            out,
+            "{}",
+            tr!(
+                "{analyzer} on {case}, {size}: {needed} of stack",
+                analyzer = run.analyzer,
+                case = run.case,
+                size = number(run.size),
+                needed = needed
+            )
+        )?;
+    }
+    let failures = stack::failures(&runs);
+    for failure in &failures {
+        writeln!(out, "FAIL  {failure}")?;
+    }
+    if failures.is_empty() {
+        writeln!(
+            out,
+            "\n{}",
+            tr!(
+                "every analyzer fits in {kib} KiB of stack",
+                kib = stack::STACK_BUDGET / 1024
+            )
+        )?;
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+fn run_serve(args: &Args) -> Result<(), String> {
+    let mut server = Server::new(lint_config(args)?);
+    server
+        .run(io::stdin().lock(), io::stdout().lock())
+        .map_err(|e| e.to_string())
+}
+
+fn run_repl(paths: &[String]) -> ExitCode {
+    let mut repl = Repl::new();
+    for path in paths {
+        if let Err(e) = repl.load(path) {
+            eprintln!("{}", tr!("error: {message}", message = e));
+            return ExitCode::FAILURE;
+        }
+    }
+    match repl.run(io::stdin().lock(), io::stdout().lock()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", tr!("error: {message}", message = e));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run_completions(args: &[String]) -> Result<ExitCode, Halt> {
+    let mut out = io::stdout().lock();
+    match args.first().and_then(|shell| cli::completions(shell)) {
+        Some(script) => {
+            write!(out, "{script}")?;
+            Ok(ExitCode::SUCCESS)
+        }
+        None => {
+            eprintln!(
+                "{}",
+                tr!(
+                    "expected one of: {choices}",
+                    choices = cli::SHELLS.join(", ")
+                )
+            );
+            Ok(ExitCode::from(2))
+        }
+    }
+}
+
+fn run_help(args: &[String]) -> Result<ExitCode, Halt> {
+    let mut out = io::stdout().lock();
+    match args.first() {
+        None => write!(out, "{}", cli::usage())?,
+        Some(name) => match cli::find(name) {
+            Some(cmd) => write!(out, "{}", cli::command_help(cmd))?,
+            None => {
+                eprintln!("{}", tr!("unknown command `{name}`", name = name));
+                return Ok(ExitCode::from(2));
+            }
+        },
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Applies `--numbers` and `--decimals`.
+fn number_style(args: &Args) -> Result<(), String> {
+    let numbers = match args.value("numbers") {
+        None => Numbers::from_env(),
+        Some(name) => Numbers::parse(name).ok_or_else(|| {
+            tr!(
+                "unknown number style `{name}` (expected {known})",
+                name = name,
+                known = i18n::NUMBER_STYLES.join(", ")
+            )
+        })?,
+    };
+    let decimals = match args.value("decimals") {
+        Some(_) => Some(args.number("decimals", 0)?),
+        None => None,
+    };
+    i18n::set_numbers(numbers, decimals);
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    match i18n::detect(&mut args) {
+        Ok(lang) => i18n::set_lang(lang),
+        Err(e) => {
+            eprintln!("{}", tr!("error: {message}", message = e));
+            return ExitCode::from(2);
+        }
+    }
+    let (name, rest) = match args.split_first() {
+        None => ("demo", &[][..]),
+        Some((first, _)) if cli::is_help_flag(first) => ("help", &[][..]),
+        Some((first, rest)) => (first.as_str(), rest),
+    };
+    let Some(cmd) = cli::find(name) else {
+        eprintln!(
+            "{}\n\n{}",
+            tr!("unknown command `{name}`", name = name),
+            cli::usage()
+        );
+        return ExitCode::from(2);
+    };
+    if rest.iter().any(|a| cli::is_help_flag(a)) {
+        return match write!(io::stdout().lock(), "{}", cli::command_help(cmd)) {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+                eprintln!(
+                    "{}",
+                    tr!("error: {message}", message = format!("stdout: {e}"))
+                );
+                ExitCode::FAILURE
+            }
+            _ => ExitCode::SUCCESS,
+        };
+    }
+    let args = match Args::parse(cmd, rest) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", tr!("error: {message}", message = e));
+            return ExitCode::from(2);
+        }
+    };
+    if args.has("quiet") {
+        log::set_level(log::Level::Quiet);
+    }
+    if args.has("verbose") {
+        log::set_level(log::Level::Verbose);
+    }
+    if let Err(e) = number_style(&args) {
+        eprintln!("{}", tr!("error: {message}", message = e));
+        return ExitCode::from(2);
+    }
+    match Tuning::load() {
+        Ok(Some(tuning)) => tuning.apply(),
+        Ok(None) => {}
+        Err(e) => warn!("{}", tr!("ignoring saved tuning: {message}", message = e)),
+    }
+    match cmd.name {
+        "analyze" => match run_analyze(&args) {
+            Ok(code) => code,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "lint" => match run_lint(&args) {
+            Ok(code) => code,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "probe" => match run_probe(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "stability" => match run_stability(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "bench" => match run_bench(&args) {
+            Ok(code) => code,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "prefix-tree" => match run_prefix_tree(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "complete" => match run_complete(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "dedup" => match run_dedup(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "overlap" => match run_overlap(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "compare" => match run_compare(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "batch" => match run_batch(&args) {
+            Ok(code) => code,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "trend" => match run_trend(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "readability" => match run_readability(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "cooccur" => match run_cooccur(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "approx" => match approx_counter(&args) {
+            Err(e) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::from(2)
+            }
+            Ok(counter) => match run_approx(&args, counter) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(Halt::Closed) => ExitCode::SUCCESS,
+                Err(Halt::Error(e)) => {
+                    eprintln!("{}", tr!("error: {message}", message = e));
+                    ExitCode::FAILURE
+                }
+            },
+        },
+        "watch" => match run_watch(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "concordance" => match run_concordance(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "learn-hot" => match run_learn_hot(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "tune" => match run_tune(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "timeline" => match run_timeline(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "deny" => match run_deny(&args) {
+            Ok(code) => code,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "report" => match run_report(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "share" => match run_share(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "wordlist" => match run_wordlist(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "unknown" => match run_unknown(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "consume" => match run_consume(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "grpc" => match run_grpc(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "authors" => match run_authors(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "wiki" => match run_wiki(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "replay" => match run_replay(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "demo" => match run_demo(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "selftest" if args.has("allocations") => match run_allocation_check() {
+            Ok(code) => code,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "selftest" if args.has("stack") => match run_stack_check() {
+            Ok(code) => code,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "selftest" if args.has("differential") => match run_differential_check(&args) {
+            Ok(code) => code,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "selftest" => match run_selftest() {
+            Ok(code) => code,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "serve" => match run_serve(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        "repl" => run_repl(&args.positional),
+        "completions" => match run_completions(&args.positional) {
+            Ok(code) => code,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+        _ => match run_help(&args.positional) {
+            Ok(code) => code,
+            Err(Halt::Closed) => ExitCode::SUCCESS,
+            Err(Halt::Error(e)) => {
+                eprintln!("{}", tr!("error: {message}", message = e));
+                ExitCode::FAILURE
+            }
+        },
+    }
 }
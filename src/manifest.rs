@@ -0,0 +1,676 @@
+//! Batch manifests (`td5 batch`): a list of inputs, each with its own
+//! analyzer, stopwords and word pipeline, analyzed in one run with one row
+//! per input and a combined total.
+//!
+//! A manifest is JSON, an object whose `inputs` array holds one object per
+//! input, or a TOML subset with one `[[inputs]]` table per input, string
+//! values and single-line string arrays:
+//!
+//! ```toml
+//! [[inputs]]
+//! path = "en/report.txt"
+//! language = "en"
+//!
+//! [[inputs]]
+//! path = "fr/rapport.txt"
+//! label = "rapport"
+//! language = "fr"
+//! stopwords = ["ainsi", "donc"]
+//! analyzer = "parallel"
+//! ```
+//!
+//! Only `path` is required. `language` (`en` or `fr`) drops that language's
+//! built-in stopwords; `stopwords` adds words of its own, or names a
+//! built-in list (`en`, `fr`) as `--stopwords` does, or else a file of them,
+//! one per line; `pipeline` names a pipeline config run first;
+//! `analyzer` picks the implementation, `fast` by default, for inputs with
+//! neither stopwords nor a pipeline. Relative paths are taken from the
+//! manifest's directory.
+//!
+//! Besides text and JSON, a run can be written as one CSV table for BI tools
+//! (`td5 batch --format csv`, `BatchReport::to_csv`): a row per input with
+//! its file's size, modification time and language, then the combined row.
+
+use crate::TextStats;
+use crate::analyzer;
+use crate::checkpoint::Checkpoint;
+use crate::clock::Stopwatch;
+use crate::csv::{self, field};
+use crate::encoding::Transcoding;
+use crate::error::{Result, Td5Error};
+use crate::freq::WordFrequency;
+use crate::input;
+use crate::json::{self, Json};
+use crate::stopwords::{self, StopwordList};
+use crate::timestamp;
+use crate::transform::{Pipeline, Stage};
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Columns of `BatchReport::to_csv`.
+pub const SUMMARY_HEADER: &str = "kind,label,path,bytes,modified,language,word_count,total_words,char_count,line_count,sentence_count,mean_word_length,top_words,time_ns,encoding,transcoded";
+
+/// The `stopwords` of an input.
+///
+/// ```
+/// use td5::manifest::{Manifest, Stopwords};
+///
+/// let manifest = Manifest::parse_json(
+///     r#"{"inputs": [{"path": "a.txt", "stopwords": "en"}, {"path": "b.txt", "stopwords": "mine.txt"}]}"#,
+///     "corpus".as_ref(),
+/// )
+/// .unwrap();
+/// assert_eq!(manifest.items[0].stopwords, Some(Stopwords::Builtin("en".into())));
+/// assert_eq!(manifest.items[1].stopwords, Some(Stopwords::File("corpus/mine.txt".into())));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stopwords {
+    Words(Vec<String>),
+    /// A built-in list, named by language.
+    Builtin(String),
+    /// A file of stopwords, one per line.
+    File(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    pub path: PathBuf,
+    /// Name of the input in the report; its path as written by default.
+    pub label: String,
+    pub analyzer: String,
+    pub language: Option<String>,
+    pub stopwords: Option<Stopwords>,
+    pub pipeline: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    pub items: Vec<Item>,
+}
+
+/// A value of the manifest, whichever syntax it came from.
+enum Value {
+    String(String),
+    List(Vec<String>),
+}
+
+type Table = Vec<(String, Value)>;
+
+const KEYS: &[&str] = &[
+    "path",
+    "label",
+    "analyzer",
+    "language",
+    "stopwords",
+    "pipeline",
+];
+
+impl Manifest {
+    /// Reads a manifest, as TOML if its name ends in `.toml` and as JSON
+    /// otherwise.
+    pub fn load(path: &Path) -> Result<Manifest> {
+        let text = input::read_text(path)?;
+        let base = path.parent().unwrap_or(Path::new(""));
+        let toml = path.extension().is_some_and(|e| e == "toml");
+        let parsed = if toml {
+            Self::parse_toml(&text, base)
+        } else {
+            Self::parse_json(&text, base)
+        };
+        parsed.map_err(|e| match e {
+            Td5Error::Config(message) => Td5Error::Config(format!("{}: {message}", path.display())),
+            e => e,
+        })
+    }
+
+    /// A JSON manifest whose relative paths are under `base`.
+    pub fn parse_json(text: &str, base: &Path) -> Result<Manifest> {
+        let doc = json::parse(text).map_err(|e| Td5Error::config(e.to_string()))?;
+        let Some(inputs) = doc.get("inputs").and_then(Json::as_array) else {
+            return Err(Td5Error::config("expected an `inputs` array"));
+        };
+        let mut tables = Vec::new();
+        for (i, input) in inputs.iter().enumerate() {
+            let invalid = |key: &str| {
+                Td5Error::config(format!(
+                    "input {}: `{key}` must be a string or an array of strings",
+                    i + 1
+                ))
+            };
+            let Json::Object(fields) = input else {
+                return Err(Td5Error::config(format!(
+                    "input {}: expected an object",
+                    i + 1
+                )));
+            };
+            let mut table = Table::new();
+            for (key, value) in fields {
+                let value = match value {
+                    Json::String(s) => Value::String(s.clone()),
+                    Json::Array(items) => Value::List(
+                        items
+                            .iter()
+                            .map(|item| item.as_str().map(String::from))
+                            .collect::<Option<_>>()
+                            .ok_or_else(|| invalid(key))?,
+                    ),
+                    _ => return Err(invalid(key)),
+                };
+                table.push((key.clone(), value));
+            }
+            tables.push(table);
+        }
+        Self::from_tables(tables, base)
+    }
+
+    /// A TOML manifest whose relative paths are under `base`.
+    pub fn parse_toml(text: &str, base: &Path) -> Result<Manifest> {
+        let mut tables: Vec<Table> = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let error = |message: &str| Td5Error::config(format!("line {}: {message}", i + 1));
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line == "[[inputs]]" {
+                tables.push(Table::new());
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected `[[inputs]]` or `key = value`"))?;
+            let table = tables
+                .last_mut()
+                .ok_or_else(|| error("expected `[[inputs]]` before the first key"))?;
+            let value = toml_value(value.trim())
+                .ok_or_else(|| error("expected a quoted string or an array of quoted strings"))?;
+            table.push((key.trim().to_string(), value));
+        }
+        Self::from_tables(tables, base)
+    }
+
+    /// Checks each input's keys.
+    fn from_tables(tables: Vec<Table>, base: &Path) -> Result<Manifest> {
+        if tables.is_empty() {
+            return Err(Td5Error::config("the manifest lists no inputs"));
+        }
+        let mut items = Vec::with_capacity(tables.len());
+        for (i, table) in tables.into_iter().enumerate() {
+            let error = |message: String| Td5Error::config(format!("input {}: {message}", i + 1));
+            let mut item = Item {
+                path: PathBuf::new(),
+                label: String::new(),
+                analyzer: "fast".into(),
+                language: None,
+                stopwords: None,
+                pipeline: None,
+            };
+            for (key, value) in table {
+                if !KEYS.contains(&key.as_str()) {
+                    return Err(error(format!(
+                        "unknown key `{key}` (expected {})",
+                        KEYS.join(", ")
+                    )));
+                }
+                let value = match (key.as_str(), value) {
+                    ("stopwords", Value::List(words)) => {
+                        item.stopwords = Some(Stopwords::Words(words));
+                        continue;
+                    }
+                    (_, Value::String(value)) => value,
+                    (key, Value::List(_)) => {
+                        return Err(error(format!("`{key}` must be a string")));
+                    }
+                };
+                match key.as_str() {
+                    "path" => {
+                        item.path = base.join(&value);
+                        if item.label.is_empty() {
+                            item.label = value;
+                        }
+                    }
+                    "label" => item.label = value,
+                    "analyzer" => {
+                        if analyzer::find(&value).is_none() {
+                            return Err(error(format!("unknown analyzer `{value}`")));
+                        }
+                        item.analyzer = value;
+                    }
+                    "language" => {
+                        if stopwords::builtin(&value).is_none() {
+                            return Err(error(format!(
+                                "unknown language `{value}` (expected {})",
+                                stopwords::LANGUAGES.join(", ")
+                            )));
+                        }
+                        item.language = Some(value);
+                    }
+                    // Resolved as `--stopwords` resolves it: a built-in
+                    // list's name before a file's.
+                    "stopwords" => {
+                        item.stopwords = Some(match StopwordList::builtin(&value) {
+                            Some(_) => Stopwords::Builtin(value),
+                            None => Stopwords::File(base.join(value)),
+                        });
+                    }
+                    "pipeline" => item.pipeline = Some(base.join(value)),
+                    _ => unreachable!("checked against KEYS"),
+                }
+            }
+            if item.path.as_os_str().is_empty() {
+                return Err(error("missing `path`".into()));
+            }
+            items.push(item);
+        }
+        Ok(Manifest { items })
+    }
+}
+
+/// `line` up to a `#` outside quotes.
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// A quoted string, or a one-line array of them.
+fn toml_value(text: &str) -> Option<Value> {
+    if let Some(inner) = text.strip_prefix('[') {
+        let mut rest = inner.strip_suffix(']')?.trim();
+        let mut list = Vec::new();
+        while !rest.is_empty() {
+            let (s, after) = toml_string(rest)?;
+            list.push(s);
+            let after = after.trim_start();
+            rest = match after.strip_prefix(',') {
+                Some(after) => after.trim_start(),
+                None if after.is_empty() => after,
+                None => return None,
+            };
+        }
+        return Some(Value::List(list));
+    }
+    match toml_string(text)? {
+        (s, "") => Some(Value::String(s)),
+        _ => None,
+    }
+}
+
+/// The basic string at the start of `text` and what follows it.
+fn toml_string(text: &str) -> Option<(String, &str)> {
+    let mut chars = text.strip_prefix('"')?.char_indices();
+    let mut out = String::new();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((out, &text[i + 2..])),
+            '\\' => out.push(match chars.next()?.1 {
+                'n' => '\n',
+                't' => '\t',
+                c @ ('"' | '\\') => c,
+                _ => return None,
+            }),
+            c => out.push(c),
+        }
+    }
+    None
+}
+
+impl Item {
+    /// The input's stats, how long they took in nanoseconds, its
+    /// transformed word counts and how its file was decoded.
+    fn run(&self) -> Result<(TextStats, u128, WordFrequency, Transcoding)> {
+        let (text, transcoding) = input::read_decoded(&self.path)?;
+        let pipeline = self.pipeline()?;
+        let start = Stopwatch::start();
+        let stats = if pipeline.is_empty() {
+            let registered = analyzer::find(&self.analyzer)
+                .ok_or_else(|| Td5Error::config(format!("unknown analyzer `{}`", self.analyzer)))?;
+            registered.analyzer.analyze(&text)
+        } else {
+            pipeline.analyze(&text)
+        };
+        let time_ns = start.elapsed_ns();
+        let freq = pipeline.transform(&WordFrequency::from_text(&text));
+        Ok((stats, time_ns, freq, transcoding))
+    }
+
+    /// Writes the input as UTF-8 without a BOM under `dir`, at its path
+    /// relative to `base` (the manifest's directory), or at its file name
+    /// when it lies outside. Returns the path written.
+    ///
+    /// ```
+    /// use td5::manifest::Manifest;
+    ///
+    /// let dir = std::env::temp_dir().join("td5-normalize");
+    /// std::fs::create_dir_all(dir.join("in")).unwrap();
+    /// std::fs::write(dir.join("in/latin1.txt"), b"caf\xe9 cr\xe8me").unwrap();
+    /// let manifest = Manifest::parse_json(r#"{"inputs": [{"path": "in/latin1.txt"}]}"#, &dir).unwrap();
+    /// let written = manifest.items[0].write_normalized(&dir.join("out"), &dir).unwrap();
+    /// assert_eq!(written, dir.join("out/in/latin1.txt"));
+    /// assert_eq!(std::fs::read_to_string(written).unwrap(), "café crème");
+    /// ```
+    pub fn write_normalized(&self, dir: &Path, base: &Path) -> Result<PathBuf> {
+        let (text, _) = input::read_decoded(&self.path)?;
+        let relative = self
+            .path
+            .strip_prefix(base)
+            .ok()
+            .filter(|relative| {
+                relative
+                    .components()
+                    .all(|c| matches!(c, std::path::Component::Normal(_)))
+            })
+            .or_else(|| self.path.file_name().map(Path::new))
+            .ok_or_else(|| Td5Error::config(format!("`{}` names no file", self.path.display())))?;
+        let target = dir.join(relative);
+        let io = |source| Td5Error::Io {
+            path: Some(target.clone()),
+            source,
+        };
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(io)?;
+        }
+        std::fs::write(&target, text).map_err(io)?;
+        Ok(target)
+    }
+
+    /// The item's pipeline, then its stopwords.
+    pub fn pipeline(&self) -> Result<Pipeline> {
+        let mut pipeline = match &self.pipeline {
+            Some(path) => Pipeline::load(path)?,
+            None => Pipeline::default(),
+        };
+        let mut words: HashSet<String> = HashSet::new();
+        if let Some(language) = &self.language {
+            let list = stopwords::builtin(language).unwrap_or_default();
+            words.extend(list.iter().map(|w| w.to_string()));
+        }
+        match &self.stopwords {
+            Some(Stopwords::Words(list)) => words.extend(list.iter().map(|w| w.to_lowercase())),
+            Some(Stopwords::Builtin(name)) => {
+                let list = StopwordList::builtin(name).unwrap_or_default();
+                words.extend(list.iter().map(String::from));
+            }
+            Some(Stopwords::File(path)) => {
+                let text = input::read_text(path)?;
+                words.extend(StopwordList::parse(&text).iter().map(String::from));
+            }
+            None => {}
+        }
+        if !words.is_empty() {
+            pipeline.stages.push(Stage::Stopwords(words));
+        }
+        Ok(pipeline)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchReport {
+    /// Each input's label and stats, in manifest order.
+    pub items: Vec<(String, TextStats)>,
+    /// All inputs counted together, each through its own pipeline. Lines
+    /// and sentences are the inputs' summed, `None` if any input's are.
+    pub combined: TextStats,
+    /// The label and error of each input that could not be analyzed.
+    pub errors: Vec<(String, String)>,
+    /// The label of each input analyzed, with how its file was decoded.
+    pub encodings: Vec<(String, Transcoding)>,
+    /// The label of each input analyzed, with how long its analysis took in
+    /// nanoseconds.
+    pub times: Vec<(String, u128)>,
+}
+
+impl BatchReport {
+    /// How the input labeled `label` was decoded, if it was.
+    pub fn encoding(&self, label: &str) -> Option<Transcoding> {
+        self.encodings
+            .iter()
+            .find(|(l, _)| l == label)
+            .map(|&(_, transcoding)| transcoding)
+    }
+
+    /// How long the analysis of the input labeled `label` took, if it ran.
+    pub fn time_ns(&self, label: &str) -> Option<u128> {
+        self.times
+            .iter()
+            .find(|(l, _)| l == label)
+            .map(|&(_, ns)| ns)
+    }
+
+    /// The inputs' analysis times, summed.
+    pub fn combined_time_ns(&self) -> u128 {
+        self.times.iter().map(|&(_, ns)| ns).sum()
+    }
+
+    pub fn to_json_value(&self) -> Json {
+        let items = self
+            .items
+            .iter()
+            .map(|(label, stats)| {
+                let mut doc = stats.to_json_value();
+                doc.set("time_ns", self.time_ns(label).unwrap_or(0).into());
+                if let Json::Object(fields) = &mut doc {
+                    fields.insert(0, ("label".into(), label.as_str().into()));
+                    if let Some(transcoding) = self.encoding(label) {
+                        fields.insert(
+                            1,
+                            ("encoding".into(), transcoding.encoding.to_string().into()),
+                        );
+                        fields.insert(2, ("transcoded".into(), transcoding.transcoded.into()));
+                    }
+                }
+                doc
+            })
+            .collect();
+        let errors = self
+            .errors
+            .iter()
+            .map(|(label, message)| {
+                Json::Object(vec![
+                    ("label".into(), label.as_str().into()),
+                    ("error".into(), message.as_str().into()),
+                ])
+            })
+            .collect();
+        let mut combined = self.combined.to_json_value();
+        combined.set("time_ns", self.combined_time_ns().into());
+        Json::Object(vec![
+            ("inputs".into(), Json::Array(items)),
+            ("combined".into(), combined),
+            ("errors".into(), Json::Array(errors)),
+        ])
+    }
+
+    /// The run as one table under `SUMMARY_HEADER`, header included: an
+    /// `input` row per input analyzed, with the size in bytes, modification
+    /// time (`YYYY-MM-DDTHH:MM:SSZ`) and language of its file, from
+    /// `manifest`, and its encoding and whether it was transcoded, then a
+    /// `combined` row, which leaves those two empty. That row holds `combined`,
+    /// as the other formats do, with the summed sizes, the latest
+    /// modification time, and a language only if every input shares it.
+    /// Metadata that cannot be read is left empty.
+    ///
+    /// ```
+    /// use td5::manifest::{Manifest, SUMMARY_HEADER};
+    ///
+    /// let dir = std::env::temp_dir();
+    /// std::fs::write(dir.join("td5-summary.txt"), "the cat and the dog").unwrap();
+    /// let manifest = Manifest::parse_json(
+    ///     r#"{"inputs": [{"path": "td5-summary.txt", "label": "pets", "language": "en"}]}"#,
+    ///     &dir,
+    /// )
+    /// .unwrap();
+    /// let csv = manifest.run(false).unwrap().to_csv(&manifest);
+    /// let rows: Vec<&str> = csv.lines().collect();
+    /// assert_eq!(rows[0], SUMMARY_HEADER);
+    /// assert!(rows[1].starts_with("input,pets,"));
+    /// assert!(rows[1].contains(",19,"));
+    /// assert!(rows[1].ends_with(",UTF-8,false"));
+    /// assert!(rows[2].starts_with("combined,,,19,"));
+    /// assert!(rows[2].contains("Z,en,"));
+    /// // One input, "the" and "and" dropped as English stopwords: the
+    /// // combined counts, lines and word length are its own.
+    /// let counts = |row: &str| row.split(',').skip(6).take(6).collect::<Vec<_>>().join(",");
+    /// assert_eq!(counts(rows[2]), counts(rows[1]));
+    /// assert_eq!(counts(rows[1]), "2,2,15,1,1,3");
+    /// ```
+    pub fn to_csv(&self, manifest: &Manifest) -> String {
+        let mut out = format!("{SUMMARY_HEADER}\n");
+        let mut items = manifest.items.iter();
+        let (mut bytes, mut modified, mut languages) = (0, None, Vec::new());
+        for (label, stats) in &self.items {
+            // Inputs that failed are missing from `items`, the rest in order.
+            let item = items.find(|item| item.label == *label);
+            let metadata = item.and_then(|item| std::fs::metadata(&item.path).ok());
+            let size = metadata.as_ref().map(|m| m.len());
+            let mtime = metadata
+                .and_then(|m| m.modified().ok())
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs() as i64);
+            let language = item
+                .and_then(|item| item.language.clone())
+                .or_else(|| stats.detected_language.clone());
+            bytes += size.unwrap_or(0);
+            modified = modified.max(mtime);
+            languages.push(language.clone());
+            summary_row(
+                &mut out,
+                "input",
+                label,
+                &item
+                    .map(|item| item.path.display().to_string())
+                    .unwrap_or_default(),
+                size,
+                mtime,
+                language.as_deref(),
+                stats,
+                self.time_ns(label).unwrap_or(0),
+                self.encoding(label),
+            );
+        }
+        let language = match languages.split_first() {
+            Some((first, rest)) if rest.iter().all(|l| l == first) => first.as_deref(),
+            _ => None,
+        };
+        summary_row(
+            &mut out,
+            "combined",
+            "",
+            "",
+            Some(bytes),
+            modified,
+            language,
+            &self.combined,
+            self.combined_time_ns(),
+            None,
+        );
+        out
+    }
+}
+
+/// A row of `BatchReport::to_csv`.
+#[allow(clippy::too_many_arguments)]
+fn summary_row(
+    out: &mut String,
+    kind: &str,
+    label: &str,
+    path: &str,
+    bytes: Option<u64>,
+    modified: Option<i64>,
+    language: Option<&str>,
+    stats: &TextStats,
+    time_ns: u128,
+    transcoding: Option<Transcoding>,
+) {
+    let _ = writeln!(
+        out,
+        "{kind},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+        field(label),
+        field(path),
+        bytes.map(|b| b.to_string()).unwrap_or_default(),
+        modified.map(timestamp::format).unwrap_or_default(),
+        language.map(field).unwrap_or_default(),
+        stats.word_count,
+        stats.total_words,
+        stats.char_count,
+        csv::known(stats.line_count),
+        csv::known(stats.sentence_count),
+        stats.mean_word_length,
+        field(&csv::counted(&stats.top_words)),
+        time_ns,
+        transcoding
+            .map(|t| t.encoding.to_string())
+            .unwrap_or_default(),
+        transcoding
+            .map(|t| t.transcoded.to_string())
+            .unwrap_or_default(),
+    );
+}
+
+impl Manifest {
+    /// Analyzes every input. With `keep_going`, an input that cannot be
+    /// read is recorded in `BatchReport::errors` and the others still run;
+    /// otherwise its error is returned.
+    pub fn run(&self, keep_going: bool) -> Result<BatchReport> {
+        self.resume(Checkpoint::default(), keep_going, |_| Ok(()))
+    }
+
+    /// Like `run`, for the inputs after those `checkpoint` has done, calling
+    /// `save` with the checkpoint after each. Fails if the inputs done are
+    /// not the first ones of the manifest, e.g. after it was edited.
+    pub fn resume(
+        &self,
+        mut checkpoint: Checkpoint,
+        keep_going: bool,
+        mut save: impl FnMut(&Checkpoint) -> Result<()>,
+    ) -> Result<BatchReport> {
+        if let Some((i, path)) = checkpoint
+            .done
+            .iter()
+            .enumerate()
+            .find(|&(i, path)| self.items.get(i).is_none_or(|item| item.path != *path))
+        {
+            return Err(Td5Error::config(format!(
+                "the checkpoint does not match the manifest: input {} was `{}`",
+                i + 1,
+                path.display()
+            )));
+        }
+        for item in &self.items[checkpoint.done.len()..] {
+            match item.run() {
+                Ok((stats, time_ns, freq, transcoding)) => {
+                    checkpoint.combined.merge(freq);
+                    checkpoint.items.push((item.label.clone(), stats));
+                    checkpoint.encodings.push((item.label.clone(), transcoding));
+                    checkpoint.times.push((item.label.clone(), time_ns));
+                }
+                Err(e) if keep_going => checkpoint.errors.push((item.label.clone(), e.to_string())),
+                Err(e) => return Err(e),
+            }
+            checkpoint.done.push(item.path.clone());
+            save(&checkpoint)?;
+        }
+        let mut combined = checkpoint.combined.to_stats();
+        let items = || checkpoint.items.iter().map(|(_, stats)| stats);
+        combined.line_count = items().map(|stats| stats.line_count).sum();
+        combined.sentence_count = items().map(|stats| stats.sentence_count).sum();
+        Ok(BatchReport {
+            combined,
+            items: checkpoint.items,
+            errors: checkpoint.errors,
+            encodings: checkpoint.encodings,
+            times: checkpoint.times,
+        })
+    }
+}
@@ -0,0 +1,373 @@
+//! Mailboxes (the `mbox` feature): the body of each message with its sender,
+//! for per-sender stats through `chat::Chat::speakers`.
+//!
+//! Messages start at a `From ` line at the top of the file or after a blank
+//! line, as mboxrd and mboxo write them. Only the `From` and `Subject`
+//! headers are kept; the sender is the address in `From`, lowercased. Of a
+//! multipart message, the text parts are read (`text/plain` first,
+//! `text/html` through `html::to_text` when there is no plain one) and
+//! attachments are left out. Quoted-printable and base64 bodies are decoded,
+//! and `charset`s other than UTF-8 are read as Windows-1252.
+//!
+//! What the sender did not write is dropped from the body: quoted lines
+//! (`> ...`), the `On ..., Ada wrote:` line introducing them and the
+//! signature after a `-- ` line.
+
+use crate::chat::{Chat, Message};
+use crate::encoding::{self, Encoding};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mail {
+    /// The sender's address, lowercased; the whole `From` header when it
+    /// has no address.
+    pub from: String,
+    pub subject: String,
+    /// Without headers, quoted replies or signature.
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Mailbox {
+    /// In file order.
+    pub messages: Vec<Mail>,
+}
+
+impl Mailbox {
+    /// The messages of an mbox file.
+    ///
+    /// ```
+    /// use td5::mbox::Mailbox;
+    ///
+    /// let mbox = "\
+    /// From ada@example.org Mon Dec 31 21:41:00 2001
+    /// From: Ada Lovelace <Ada@example.org>
+    /// Subject: Engines
+    ///
+    /// The engine works.
+    ///
+    /// From grace@example.org Tue Jan  1 09:00:00 2002
+    /// From: grace@example.org
+    /// Subject: Re: Engines
+    /// Content-Transfer-Encoding: quoted-printable
+    ///
+    /// On Mon, Ada Lovelace wrote:
+    /// > The engine works.
+    /// Congratulations, it=20works!
+    /// >From now on, it computes.
+    /// --=20
+    /// Grace
+    /// ";
+    /// let mailbox = Mailbox::parse(mbox);
+    /// assert_eq!(mailbox.messages.len(), 2);
+    /// assert_eq!(mailbox.messages[0].from, "ada@example.org");
+    /// assert_eq!(mailbox.messages[1].subject, "Re: Engines");
+    /// assert_eq!(mailbox.messages[1].body, "Congratulations, it works!\nFrom now on, it computes.");
+    /// ```
+    pub fn parse(source: &str) -> Mailbox {
+        let mut messages = Vec::new();
+        let mut current: Option<Vec<&str>> = None;
+        let mut blank = true;
+        for line in source.lines() {
+            let line = line.trim_end_matches('\r');
+            if blank && line.starts_with("From ") {
+                messages.extend(current.take().map(|lines| mail(&lines)));
+                current = Some(Vec::new());
+            } else if let Some(lines) = &mut current {
+                lines.push(line);
+            }
+            blank = line.is_empty();
+        }
+        messages.extend(current.map(|lines| mail(&lines)));
+        Mailbox { messages }
+    }
+
+    /// The messages as a chat whose speakers are the senders.
+    pub fn chat(&self) -> Chat {
+        Chat {
+            messages: self
+                .messages
+                .iter()
+                .filter(|mail| !mail.body.trim().is_empty())
+                .map(|mail| Message {
+                    speaker: mail.from.clone(),
+                    text: mail.body.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A message from the lines after its `From ` line.
+fn mail(lines: &[&str]) -> Mail {
+    let (headers, body) = split_headers(lines);
+    // mboxrd escapes `From ` at the start of a body line with `>`s.
+    let body: Vec<String> = body
+        .iter()
+        .map(
+            |line| match line.trim_start_matches('>').strip_prefix("From ") {
+                Some(_) if line.starts_with('>') => line[1..].to_string(),
+                _ => line.to_string(),
+            },
+        )
+        .collect();
+    let from = headers.get("from").map_or("", String::as_str);
+    Mail {
+        from: address(from),
+        subject: headers.get("subject").cloned().unwrap_or_default(),
+        body: own_text(&text_of(&headers, &body.join("\n"))),
+    }
+}
+
+/// Header fields by lowercased name, continuation lines unfolded, and the
+/// lines after them.
+fn split_headers<'a, 'b>(lines: &'b [&'a str]) -> (Headers, &'b [&'a str]) {
+    let mut headers = Headers::default();
+    let mut last: Option<String> = None;
+    for (i, line) in lines.iter().enumerate() {
+        if line.is_empty() {
+            return (headers, &lines[i + 1..]);
+        }
+        if line.starts_with([' ', '\t']) {
+            if let Some(value) = last.as_ref().and_then(|name| headers.get_mut(name)) {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = header(line) {
+            let name = name.to_ascii_lowercase();
+            headers.insert(name.clone(), value.trim().to_string());
+            last = Some(name);
+        }
+    }
+    (headers, &[])
+}
+
+type Headers = std::collections::HashMap<String, String>;
+
+/// The name and value of a `Name: value` header line.
+fn header(line: &str) -> Option<(&str, &str)> {
+    let (name, value) = line.split_once(':')?;
+    let token = !name.is_empty() && name.bytes().all(|b| b.is_ascii_graphic() && b != b':');
+    token.then_some((name, value))
+}
+
+/// The address of a `From` header: between `<>`, else the word with an `@`.
+fn address(from: &str) -> String {
+    let address = match (from.rfind('<'), from.rfind('>')) {
+        (Some(open), Some(close)) if open < close => &from[open + 1..close],
+        _ => from
+            .split_whitespace()
+            .find(|word| word.contains('@'))
+            .map_or(from, |word| word.trim_matches(['(', ')', '"', ','])),
+    };
+    address.trim().to_lowercase()
+}
+
+/// The text of a message or MIME part with these headers and body.
+fn text_of(headers: &Headers, body: &str) -> String {
+    let content_type = headers
+        .get("content-type")
+        .map_or("text/plain", String::as_str);
+    let media = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+    if media.starts_with("multipart/") {
+        let Some(boundary) = parameter(content_type, "boundary") else {
+            return String::new();
+        };
+        let mut parts: Vec<(Headers, &str)> =
+            parts(body, &boundary).into_iter().map(split_part).collect();
+        let html = |headers: &Headers| {
+            headers
+                .get("content-type")
+                .is_some_and(|t| t.trim_start().to_ascii_lowercase().starts_with("text/html"))
+        };
+        // `multipart/alternative` has the same text in several forms.
+        let alternative = media == "multipart/alternative";
+        if alternative {
+            parts.sort_by_key(|(headers, _)| html(headers));
+        }
+        let attached = |headers: &Headers| {
+            headers.get("content-disposition").is_some_and(|d| {
+                d.trim_start()
+                    .to_ascii_lowercase()
+                    .starts_with("attachment")
+            })
+        };
+        let texts: Vec<String> = parts
+            .iter()
+            .filter(|(headers, _)| !attached(headers))
+            .map(|(headers, body)| text_of(headers, body))
+            .filter(|text| !text.trim().is_empty())
+            .collect();
+        return if alternative {
+            texts.into_iter().next().unwrap_or_default()
+        } else {
+            texts.join("\n")
+        };
+    }
+    if media != "text/plain" && media != "text/html" && !media.is_empty() {
+        return String::new();
+    }
+    let transfer = headers
+        .get("content-transfer-encoding")
+        .map_or(String::new(), |e| e.trim().to_ascii_lowercase());
+    let bytes = match transfer.as_str() {
+        "quoted-printable" => quoted_printable(body),
+        "base64" => base64(body),
+        _ => return html_or_plain(&media, body.to_string()),
+    };
+    let charset = parameter(content_type, "charset").unwrap_or_default();
+    let text = if charset.is_empty() || charset.eq_ignore_ascii_case("utf-8") {
+        encoding::decode(&bytes).text.into_owned()
+    } else {
+        encoding::decode_as(&bytes, Encoding::Windows1252, false)
+            .text
+            .into_owned()
+    };
+    html_or_plain(&media, text)
+}
+
+fn html_or_plain(media: &str, text: String) -> String {
+    if media == "text/html" {
+        crate::html::to_text(&text)
+    } else {
+        text
+    }
+}
+
+/// The value of `name=value` in a header's parameters, unquoted.
+fn parameter(header: &str, name: &str) -> Option<String> {
+    header.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+/// The parts of a multipart body between its `--boundary` lines, the
+/// preamble and epilogue left out.
+fn parts<'a>(body: &'a str, boundary: &str) -> Vec<&'a str> {
+    let delimiter = format!("--{boundary}");
+    let mut parts = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut offset = 0;
+    for line in body.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if trimmed.starts_with(&delimiter) {
+            if let Some(start) = start {
+                parts.push(&body[start..offset]);
+            }
+            if trimmed[delimiter.len()..].starts_with("--") {
+                return parts;
+            }
+            start = Some(offset + line.len());
+        }
+        offset += line.len();
+    }
+    parts.extend(start.map(|start| &body[start..]));
+    parts
+}
+
+/// The headers and body of a MIME part.
+fn split_part(part: &str) -> (Headers, &str) {
+    let mut offset = 0;
+    let mut lines = Vec::new();
+    for line in part.split_inclusive('\n') {
+        offset += line.len();
+        let line = line.trim_end_matches(['\n', '\r']);
+        if line.is_empty() {
+            break;
+        }
+        lines.push(line);
+    }
+    let (headers, _) = split_headers(&lines);
+    (headers, part.get(offset..).unwrap_or(""))
+}
+
+fn quoted_printable(body: &str) -> Vec<u8> {
+    let mut out = Vec::with_capacity(body.len());
+    for line in body.lines() {
+        let line = line.trim_end();
+        let (line, soft) = match line.strip_suffix('=') {
+            Some(line) => (line, true),
+            None => (line, false),
+        };
+        let bytes = line.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            let hex = (bytes[i] == b'=')
+                .then(|| line.get(i + 1..i + 3))
+                .flatten()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            match hex {
+                Some(byte) => {
+                    out.push(byte);
+                    i += 3;
+                }
+                None => {
+                    out.push(bytes[i]);
+                    i += 1;
+                }
+            }
+        }
+        if !soft {
+            out.push(b'\n');
+        }
+    }
+    out
+}
+
+/// Decodes base64, skipping whitespace and stopping at padding or any
+/// other character.
+fn base64(body: &str) -> Vec<u8> {
+    let value = |b: u8| match b {
+        b'A'..=b'Z' => Some(b - b'A'),
+        b'a'..=b'z' => Some(b - b'a' + 26),
+        b'0'..=b'9' => Some(b - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    };
+    let mut out = Vec::with_capacity(body.len() * 3 / 4);
+    let (mut bits, mut count) = (0u32, 0);
+    for b in body.bytes().filter(|b| !b.is_ascii_whitespace()) {
+        let Some(v) = value(b) else { break };
+        bits = bits << 6 | u32::from(v);
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            out.push((bits >> count) as u8);
+        }
+    }
+    out
+}
+
+/// `body` without quoted lines, the line introducing them or the signature.
+fn own_text(body: &str) -> String {
+    let lines: Vec<&str> = body.lines().map(|l| l.trim_end_matches('\r')).collect();
+    let mut kept: Vec<&str> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if *line == "-- " || *line == "--" {
+            break;
+        }
+        if line.starts_with('>') {
+            continue;
+        }
+        let quoting = lines[i + 1..]
+            .iter()
+            .find(|l| !l.trim().is_empty())
+            .is_some_and(|l| l.starts_with('>'));
+        let attribution = ["wrote:", "écrit :", "écrit:"]
+            .iter()
+            .any(|end| line.trim_end().ends_with(end));
+        if quoting && attribution {
+            continue;
+        }
+        kept.push(line);
+    }
+    kept.join("\n").trim().to_string()
+}
@@ -0,0 +1,108 @@
+//! Files mapped into memory instead of read (the `mmap` feature, Unix only).
+//!
+//! A mapping costs no copy and no heap: the kernel pages the file in as the
+//! scanner reaches it, so a 2 GB log is analyzed by the `analyze_bytes` hot
+//! path without 2 GB of RAM to hold it. The file must not shrink while mapped;
+//! reading a page past the new end raises `SIGBUS`.
+
+use crate::bytes::analyze_bytes_with;
+use crate::error::{Result, Td5Error};
+use crate::{AnalyzerConfig, TextStats};
+use std::ffi::{c_int, c_void};
+use std::fs::File;
+use std::ops::Deref;
+use std::os::fd::AsRawFd;
+use std::path::Path;
+
+const PROT_READ: c_int = 1;
+const MAP_PRIVATE: c_int = 2;
+
+unsafe extern "C" {
+    fn mmap(
+        addr: *mut c_void,
+        len: usize,
+        prot: c_int,
+        flags: c_int,
+        fd: c_int,
+        offset: i64,
+    ) -> *mut c_void;
+    fn munmap(addr: *mut c_void, len: usize) -> c_int;
+}
+
+/// A read-only view of a whole file, unmapped on drop.
+#[derive(Debug)]
+pub struct Mapping {
+    ptr: *mut c_void,
+    len: usize,
+}
+
+// SAFETY: the mapping is private and read-only; nothing writes through it.
+unsafe impl Send for Mapping {}
+unsafe impl Sync for Mapping {}
+
+impl Mapping {
+    pub fn open(path: impl AsRef<Path>) -> Result<Mapping> {
+        let path = path.as_ref();
+        let io_error = |source| Td5Error::Io {
+            path: Some(path.to_path_buf()),
+            source,
+        };
+        let file = File::open(path).map_err(io_error)?;
+        let len = usize::try_from(file.metadata().map_err(io_error)?.len())
+            .map_err(|_| io_error(std::io::Error::other("file too large to map")))?;
+        if len == 0 {
+            // mmap rejects empty mappings.
+            return Ok(Mapping {
+                ptr: std::ptr::null_mut(),
+                len,
+            });
+        }
+        // SAFETY: a fresh private read-only mapping of an open file; the
+        // descriptor may be closed once it exists.
+        let ptr = unsafe {
+            mmap(
+                std::ptr::null_mut(),
+                len,
+                PROT_READ,
+                MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr as isize == -1 {
+            return Err(io_error(std::io::Error::last_os_error()));
+        }
+        Ok(Mapping { ptr, len })
+    }
+}
+
+impl Deref for Mapping {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        if self.len == 0 {
+            return &[];
+        }
+        // SAFETY: `ptr` maps `len` readable bytes until drop.
+        unsafe { std::slice::from_raw_parts(self.ptr.cast::<u8>(), self.len) }
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        if self.len > 0 {
+            // SAFETY: `ptr` and `len` come from a successful `mmap`.
+            unsafe { munmap(self.ptr, self.len) };
+        }
+    }
+}
+
+/// `analyze_bytes` over the mapped file.
+pub fn analyze_file(path: impl AsRef<Path>) -> Result<TextStats> {
+    analyze_file_with(path, &AnalyzerConfig::default())
+}
+
+pub fn analyze_file_with(path: impl AsRef<Path>, config: &AnalyzerConfig) -> Result<TextStats> {
+    let mapping = Mapping::open(path)?;
+    Ok(analyze_bytes_with(&mapping, config))
+}
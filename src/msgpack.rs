@@ -0,0 +1,115 @@
+//! MessagePack encoding of `TextStats` and frequency tables (`msgpack`
+//! feature), for pipelines where JSON's size and parsing cost matter.
+//!
+//! The documents are the JSON ones (`TextStats::to_json_value`,
+//! `WordFrequency::to_json_value`) in MessagePack's types: whole numbers
+//! become integers in the smallest width, others float 64, and objects maps
+//! with string keys in the same order. Values are self-delimiting, so several
+//! can be written back to back. Hand-written like `proto`: the crate has no
+//! serde dependency.
+
+use crate::json::Json;
+use crate::{TextStats, WordFrequency};
+
+/// `small | len` when `len <= small_max`, else the first of the 8, 16 and
+/// 32-bit forms in `tags` that fits (`None` where the type has no such form).
+fn length(out: &mut Vec<u8>, len: usize, small: u8, small_max: usize, tags: [Option<u8>; 3]) {
+    match (len, tags) {
+        (len, _) if len <= small_max => out.push(small | len as u8),
+        (len, [Some(tag), _, _]) if len <= 0xff => out.extend([tag, len as u8]),
+        (len, [_, Some(tag), _]) if len <= 0xffff => {
+            out.push(tag);
+            out.extend((len as u16).to_be_bytes());
+        }
+        (len, [_, _, Some(tag)]) => {
+            out.push(tag);
+            out.extend((len as u32).to_be_bytes());
+        }
+        _ => unreachable!("every type has a 32-bit form"),
+    }
+}
+
+fn integer(out: &mut Vec<u8>, n: i64) {
+    match n {
+        0..=0x7f => out.push(n as u8),
+        -32..=-1 => out.push(n as u8),
+        0x80..=0xff => out.extend([0xcc, n as u8]),
+        0x100..=0xffff => {
+            out.push(0xcd);
+            out.extend((n as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(0xce);
+            out.extend((n as u32).to_be_bytes());
+        }
+        0x1_0000_0000.. => {
+            out.push(0xcf);
+            out.extend((n as u64).to_be_bytes());
+        }
+        -0x80..=-33 => out.extend([0xd0, n as u8]),
+        -0x8000..=-0x81 => {
+            out.push(0xd1);
+            out.extend((n as i16).to_be_bytes());
+        }
+        -0x8000_0000..=-0x8001 => {
+            out.push(0xd2);
+            out.extend((n as i32).to_be_bytes());
+        }
+        _ => {
+            out.push(0xd3);
+            out.extend(n.to_be_bytes());
+        }
+    }
+}
+
+fn string(out: &mut Vec<u8>, s: &str) {
+    length(out, s.len(), 0xa0, 31, [Some(0xd9), Some(0xda), Some(0xdb)]);
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn write(out: &mut Vec<u8>, value: &Json) {
+    match value {
+        Json::Null => out.push(0xc0),
+        Json::Bool(b) => out.push(if *b { 0xc3 } else { 0xc2 }),
+        Json::Number(n) => match value.as_i64() {
+            Some(i) => integer(out, i),
+            None => {
+                out.push(0xcb);
+                out.extend(n.to_be_bytes());
+            }
+        },
+        Json::String(s) => string(out, s),
+        Json::Array(items) => {
+            length(out, items.len(), 0x90, 15, [None, Some(0xdc), Some(0xdd)]);
+            for item in items {
+                write(out, item);
+            }
+        }
+        Json::Object(fields) => {
+            length(out, fields.len(), 0x80, 15, [None, Some(0xde), Some(0xdf)]);
+            for (key, value) in fields {
+                string(out, key);
+                write(out, value);
+            }
+        }
+    }
+}
+
+pub fn encode(value: &Json) -> Vec<u8> {
+    let mut out = Vec::new();
+    write(&mut out, value);
+    out
+}
+
+impl TextStats {
+    pub fn to_msgpack(&self) -> Vec<u8> {
+        encode(&self.to_json_value())
+    }
+}
+
+impl WordFrequency {
+    /// The words seen at least `min_count` times as a word → count map.
+    pub fn to_msgpack(&self, min_count: usize) -> Vec<u8> {
+        encode(&self.to_json_value(min_count))
+    }
+}
@@ -0,0 +1,116 @@
+//! Minimal NATS subscriber for `td5 consume` (the `nats` feature).
+//!
+//! Speaks just enough of the text protocol to receive messages: `CONNECT`,
+//! `SUB`, `MSG`/`HMSG` and `PING`/`PONG`. No TLS, authentication or
+//! reconnects; put a local NATS leaf node in front of anything fancier. Kafka
+//! topics are read by `td5::kafka` instead.
+
+use crate::error::{Result, Td5Error};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+
+pub struct Subscriber {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    address: String,
+}
+
+impl Subscriber {
+    /// Connects to `address` (`host:port`, an optional `nats://` prefix is
+    /// accepted) and subscribes to `subject`, which may use `*` and `>`.
+    pub fn connect(address: &str, subject: &str) -> Result<Subscriber> {
+        let address = address.strip_prefix("nats://").unwrap_or(address);
+        let stream = TcpStream::connect(address).map_err(|e| net_error(address, e))?;
+        let writer = stream.try_clone().map_err(|e| net_error(address, e))?;
+        let mut subscriber = Subscriber {
+            reader: BufReader::new(stream),
+            writer,
+            address: address.to_string(),
+        };
+        let info = subscriber.line()?;
+        if !info.starts_with("INFO") {
+            return Err(subscriber.protocol(&format!("expected INFO, got `{info}`")));
+        }
+        subscriber.send(&format!(
+            "CONNECT {{\"verbose\":false,\"pedantic\":false,\"name\":\"td5\",\"lang\":\"rust\",\"version\":\"{}\"}}\r\nSUB {subject} 1\r\n",
+            env!("CARGO_PKG_VERSION")
+        ))?;
+        Ok(subscriber)
+    }
+
+    /// The next message payload, answering server pings on the way. `None`
+    /// once the server closes the connection.
+    pub fn next_message(&mut self) -> Result<Option<Vec<u8>>> {
+        loop {
+            let line = match self.line() {
+                Ok(line) => line,
+                Err(Td5Error::Io { source, .. })
+                    if source.kind() == std::io::ErrorKind::UnexpectedEof =>
+                {
+                    return Ok(None);
+                }
+                Err(e) => return Err(e),
+            };
+            let mut parts = line.split_ascii_whitespace();
+            match parts.next() {
+                Some("PING") => self.send("PONG\r\n")?,
+                Some("PONG" | "+OK" | "INFO") => {}
+                Some("-ERR") => return Err(self.protocol(&line)),
+                // MSG <subject> <sid> [reply-to] <#bytes>
+                // HMSG <subject> <sid> [reply-to] <#header bytes> <#total bytes>
+                Some(kind @ ("MSG" | "HMSG")) => {
+                    let fields: Vec<&str> = parts.collect();
+                    let number = |i: usize| fields.get(i).and_then(|n| n.parse::<usize>().ok());
+                    let (header, total) = match kind {
+                        "MSG" => (0, number(fields.len().wrapping_sub(1))),
+                        _ => (
+                            number(fields.len().wrapping_sub(2)).unwrap_or(0),
+                            number(fields.len().wrapping_sub(1)),
+                        ),
+                    };
+                    let Some(total) = total.filter(|&t| t >= header) else {
+                        return Err(self.protocol(&format!("malformed `{line}`")));
+                    };
+                    // The payload is followed by its own CRLF.
+                    let mut payload = vec![0; total + 2];
+                    self.reader
+                        .read_exact(&mut payload)
+                        .map_err(|e| net_error(&self.address, e))?;
+                    payload.truncate(total);
+                    payload.drain(..header);
+                    return Ok(Some(payload));
+                }
+                _ => return Err(self.protocol(&format!("unexpected `{line}`"))),
+            }
+        }
+    }
+
+    fn line(&mut self) -> Result<String> {
+        let mut line = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) => Err(net_error(
+                &self.address,
+                std::io::ErrorKind::UnexpectedEof.into(),
+            )),
+            Ok(_) => Ok(line.trim_end().to_string()),
+            Err(e) => Err(net_error(&self.address, e)),
+        }
+    }
+
+    fn send(&mut self, command: &str) -> Result<()> {
+        self.writer
+            .write_all(command.as_bytes())
+            .map_err(|e| net_error(&self.address, e))
+    }
+
+    fn protocol(&self, message: &str) -> Td5Error {
+        Td5Error::config(format!("NATS server {}: {message}", self.address))
+    }
+}
+
+fn net_error(address: &str, source: std::io::Error) -> Td5Error {
+    Td5Error::Io {
+        path: Some(format!("nats://{address}").into()),
+        source,
+    }
+}
@@ -0,0 +1,152 @@
+//! Bigram and trigram counts for `AnalyzerConfig::ngrams`.
+//!
+//! The fast path hands each counted word to `NGrams::push`, which pairs it
+//! with the words in a ring of the last `n - 1` before it, so n-grams come
+//! out of the same scan as the word counts. Chunks of one text (threads,
+//! streamed pieces) are counted apart; each also keeps its first `n - 1`
+//! words, and `merge` counts the n-grams that straddle the boundary between
+//! two consecutive chunks, so the result does not depend on where the text
+//! was cut. An n-gram is its words joined by single spaces.
+
+use crate::config::AnalyzerConfig;
+use crate::fast::{FastHasher, FastMap};
+use crate::freq::{by_count, select_top};
+
+#[derive(Debug, Clone)]
+pub(crate) struct NGrams {
+    /// 2 for bigrams only, 3 for bigrams and trigrams.
+    n: usize,
+    /// The first `n - 1` words pushed.
+    head: Vec<String>,
+    /// The last `n - 1` words pushed, oldest first; their buffers are reused.
+    ring: Vec<String>,
+    bigrams: FastMap<String, usize>,
+    trigrams: FastMap<String, usize>,
+    /// Scratch buffer for the key of the n-gram being counted.
+    key: String,
+}
+
+impl NGrams {
+    /// Counters for n-grams of up to `n` words, `None` below 2.
+    pub(crate) fn new(n: usize) -> Option<Self> {
+        (n >= 2).then(|| Self {
+            n: n.min(3),
+            head: Vec::new(),
+            ring: Vec::with_capacity(2),
+            bigrams: FastMap::with_hasher(FastHasher::default()),
+            trigrams: FastMap::with_hasher(FastHasher::default()),
+            key: String::new(),
+        })
+    }
+
+    #[inline]
+    pub(crate) fn push(&mut self, word: &str) {
+        let context = self.n - 1;
+        if self.head.len() < context {
+            self.head.push(word.to_string());
+        }
+        for start in 0..self.ring.len() {
+            self.key.clear();
+            for previous in &self.ring[start..] {
+                self.key.push_str(previous);
+                self.key.push(' ');
+            }
+            self.key.push_str(word);
+            let table = if self.ring.len() - start == 1 {
+                &mut self.bigrams
+            } else {
+                &mut self.trigrams
+            };
+            add(table, &self.key, 1);
+        }
+        let mut slot = if self.ring.len() == context {
+            self.ring.remove(0)
+        } else {
+            String::new()
+        };
+        slot.clear();
+        slot.push_str(word);
+        self.ring.push(slot);
+    }
+
+    fn table(&mut self, grams: usize) -> &mut FastMap<String, usize> {
+        if grams == 2 {
+            &mut self.bigrams
+        } else {
+            &mut self.trigrams
+        }
+    }
+
+    /// Forgets the words at either end, so no n-gram straddles this text and
+    /// the ones merged with it.
+    pub(crate) fn close(&mut self) {
+        self.head.clear();
+        self.ring.clear();
+    }
+
+    /// Folds in the counts of the text right after this one.
+    pub(crate) fn merge(&mut self, next: NGrams) {
+        let tail = self.ring.len();
+        let joined: Vec<&str> = self
+            .ring
+            .iter()
+            .chain(&next.head)
+            .map(String::as_str)
+            .collect();
+        let mut straddling: Vec<(usize, String)> = Vec::new();
+        for start in 0..tail {
+            for end in tail + 1..=joined.len().min(start + self.n) {
+                straddling.push((end - start, joined[start..end].join(" ")));
+            }
+        }
+        for (grams, key) in straddling {
+            add(self.table(grams), &key, 1);
+        }
+        for (key, count) in next.bigrams {
+            add(&mut self.bigrams, &key, count);
+        }
+        for (key, count) in next.trigrams {
+            add(&mut self.trigrams, &key, count);
+        }
+        let context = self.n - 1;
+        for word in &next.head {
+            if self.head.len() == context {
+                break;
+            }
+            self.head.push(word.clone());
+        }
+        self.ring.extend(next.ring);
+        let excess = self.ring.len().saturating_sub(context);
+        self.ring.drain(..excess);
+    }
+
+    /// The `config.top_k` most frequent n-grams of `length` words (2 or 3),
+    /// ties in alphabetical order, without those that start or end with a
+    /// stopword. Trigrams are empty unless counted.
+    pub(crate) fn top(&self, length: usize, config: &AnalyzerConfig) -> Vec<(String, usize)> {
+        let table = if length == 2 {
+            &self.bigrams
+        } else {
+            &self.trigrams
+        };
+        let mut grams: Vec<(&str, usize)> = table
+            .iter()
+            .filter(|(g, _)| !config.is_stop_gram(g))
+            .map(|(g, &n)| (g.as_str(), n))
+            .collect();
+        select_top(&mut grams, config.top_k, by_count)
+            .iter()
+            .map(|&(gram, count)| (gram.to_string(), count))
+            .collect()
+    }
+}
+
+#[inline]
+fn add(table: &mut FastMap<String, usize>, key: &str, count: usize) {
+    match table.get_mut(key) {
+        Some(n) => *n += count,
+        None => {
+            table.insert(key.to_string(), count);
+        }
+    }
+}
@@ -0,0 +1,244 @@
+//! Unicode normalization and diacritics folding, so that `café` written
+//! with `é` and written with `e` and a combining accent count as one word.
+//!
+//! The four normalization forms follow UAX #15: text is decomposed (fully,
+//! and with the compatibility mappings too for the K forms), its combining
+//! marks put in canonical order, and for the C forms composed again. The
+//! tables are generated by `build.rs` from `unicode-normalization.txt`
+//! (Unicode 14.0); Hangul syllables are decomposed and composed by
+//! algorithm.
+//!
+//! `AnalyzerConfig::normalization` and `AnalyzerConfig::fold_diacritics`
+//! apply them to every token before it is counted.
+
+use std::borrow::Cow;
+
+include!(concat!(env!("OUT_DIR"), "/normalization.rs"));
+
+const S_BASE: u32 = 0xAC00;
+const L_BASE: u32 = 0x1100;
+const V_BASE: u32 = 0x1161;
+const T_BASE: u32 = 0x11A7;
+const L_COUNT: u32 = 19;
+const V_COUNT: u32 = 21;
+const T_COUNT: u32 = 28;
+const N_COUNT: u32 = V_COUNT * T_COUNT;
+const S_COUNT: u32 = L_COUNT * N_COUNT;
+
+/// Canonical composition (NFC): `e` and a combining acute accent become
+/// `é`.
+///
+/// ```
+/// use td5::normalize::nfc;
+///
+/// assert_eq!(nfc("cafe\u{301}"), "café");
+/// assert_eq!(nfc("\u{1100}\u{1161}\u{11a8}"), "각");
+/// // Compatibility characters are left alone.
+/// assert_eq!(nfc("ﬁ"), "ﬁ");
+/// ```
+pub fn nfc(text: &str) -> Cow<'_, str> {
+    normalize(text, false, true, false)
+}
+
+/// Compatibility composition (NFKC): NFC, and compatibility characters
+/// such as ligatures, full-width forms and superscripts replaced by what
+/// they stand for.
+///
+/// ```
+/// use td5::normalize::nfkc;
+///
+/// assert_eq!(nfkc("ﬁne ｗｉｄｅ x²"), "fine wide x2");
+/// ```
+pub fn nfkc(text: &str) -> Cow<'_, str> {
+    normalize(text, true, true, false)
+}
+
+/// Canonical decomposition (NFD): `é` becomes `e` and a combining acute
+/// accent.
+///
+/// ```
+/// assert_eq!(td5::normalize::nfd("é"), "e\u{301}");
+/// ```
+pub fn nfd(text: &str) -> Cow<'_, str> {
+    normalize(text, false, false, false)
+}
+
+/// Compatibility decomposition (NFKD).
+///
+/// ```
+/// assert_eq!(td5::normalize::nfkd("ﬁé"), "fie\u{301}");
+/// ```
+pub fn nfkd(text: &str) -> Cow<'_, str> {
+    normalize(text, true, false, false)
+}
+
+/// NFC without diacritics: the combining marks of the diacritical mark
+/// blocks (U+0300–036F, 1AB0–1AFF, 1DC0–1DFF, 20D0–20FF, FE20–FE2F) are
+/// dropped after decomposition, and the letters whose stroke or bar is not
+/// a combining mark (`ø đ ħ ł ŧ ı` and their capitals) lose it too. Marks
+/// of other scripts, such as Hebrew points, stay.
+///
+/// ```
+/// use td5::normalize::fold_diacritics;
+///
+/// assert_eq!(fold_diacritics("Ångström à Łódź"), "Angstrom a Lodz");
+/// assert_eq!(fold_diacritics("cafe\u{301}"), "cafe");
+/// assert_eq!(fold_diacritics("naïve"), fold_diacritics("naive"));
+/// ```
+pub fn fold_diacritics(text: &str) -> Cow<'_, str> {
+    normalize(text, false, true, true)
+}
+
+/// `text` decomposed, canonically or with the compatibility mappings,
+/// stripped of diacritics if `fold`, and composed again if `compose`.
+/// Borrowed when that changes nothing, as for any ASCII text.
+pub(crate) fn normalize(text: &str, compat: bool, compose: bool, fold: bool) -> Cow<'_, str> {
+    if text.is_ascii() {
+        return Cow::Borrowed(text);
+    }
+    let mut chars = Vec::with_capacity(text.len());
+    for ch in text.chars() {
+        match fold.then(|| folded_letter(ch)).flatten() {
+            Some(plain) => chars.push(plain),
+            None => decompose(ch, compat, &mut chars),
+        }
+    }
+    if fold {
+        chars.retain(|&ch| !is_diacritic(ch));
+    }
+    reorder(&mut chars);
+    if compose {
+        compose_all(&mut chars);
+    }
+    match text.chars().eq(chars.iter().copied()) {
+        true => Cow::Borrowed(text),
+        false => Cow::Owned(chars.into_iter().collect()),
+    }
+}
+
+fn decompose(ch: char, compat: bool, out: &mut Vec<char>) {
+    let code = ch as u32;
+    if (S_BASE..S_BASE + S_COUNT).contains(&code) {
+        let index = code - S_BASE;
+        let jamo = |code| char::from_u32(code).expect("Hangul jamo are chars");
+        out.push(jamo(L_BASE + index / N_COUNT));
+        out.push(jamo(V_BASE + (index % N_COUNT) / T_COUNT));
+        if !index.is_multiple_of(T_COUNT) {
+            out.push(jamo(T_BASE + index % T_COUNT));
+        }
+        return;
+    }
+    let table = |table: &[(char, &'static [char])]| {
+        table
+            .binary_search_by_key(&ch, |&(c, _)| c)
+            .ok()
+            .map(|i| table[i].1)
+    };
+    let full = match compat {
+        true => table(&COMPATIBILITY).or_else(|| table(&CANONICAL)),
+        false => table(&CANONICAL),
+    };
+    match full {
+        Some(full) => out.extend_from_slice(full),
+        None => out.push(ch),
+    }
+}
+
+fn combining_class(ch: char) -> u8 {
+    if ch < '\u{300}' {
+        return 0;
+    }
+    COMBINING_CLASSES
+        .binary_search_by_key(&ch, |&(c, _)| c)
+        .map_or(0, |i| COMBINING_CLASSES[i].1)
+}
+
+/// Sorts each run of combining marks by class, keeping the order of marks
+/// of the same class.
+fn reorder(chars: &mut [char]) {
+    let mut start = 0;
+    while start < chars.len() {
+        if combining_class(chars[start]) == 0 {
+            start += 1;
+            continue;
+        }
+        let len = chars[start..]
+            .iter()
+            .take_while(|&&ch| combining_class(ch) != 0)
+            .count();
+        chars[start..start + len].sort_by_key(|&ch| combining_class(ch));
+        start += len;
+    }
+}
+
+/// Canonical composition of decomposed, reordered text: each character
+/// joins the last starter unless a character between them blocks it, one
+/// of class 0 or of a class at least its own.
+fn compose_all(chars: &mut Vec<char>) {
+    let mut out: Vec<char> = Vec::with_capacity(chars.len());
+    let mut starter: Option<usize> = None;
+    // Class of the last character kept since the starter, if any.
+    let mut last_class: Option<u8> = None;
+    for &ch in chars.iter() {
+        let class = combining_class(ch);
+        if let Some(s) = starter
+            && !last_class.is_some_and(|last| last == 0 || last >= class)
+            && let Some(composite) = compose(out[s], ch)
+        {
+            out[s] = composite;
+            continue;
+        }
+        match class {
+            0 => (starter, last_class) = (Some(out.len()), None),
+            _ => last_class = Some(class),
+        }
+        out.push(ch);
+    }
+    *chars = out;
+}
+
+fn compose(a: char, b: char) -> Option<char> {
+    let (a_code, b_code) = (a as u32, b as u32);
+    if (L_BASE..L_BASE + L_COUNT).contains(&a_code) && (V_BASE..V_BASE + V_COUNT).contains(&b_code)
+    {
+        let index = (a_code - L_BASE) * N_COUNT + (b_code - V_BASE) * T_COUNT;
+        return char::from_u32(S_BASE + index);
+    }
+    if (S_BASE..S_BASE + S_COUNT).contains(&a_code)
+        && (a_code - S_BASE).is_multiple_of(T_COUNT)
+        && (T_BASE + 1..T_BASE + T_COUNT).contains(&b_code)
+    {
+        return char::from_u32(a_code + b_code - T_BASE);
+    }
+    COMPOSITIONS
+        .binary_search_by(|&(x, y, _)| (x, y).cmp(&(a, b)))
+        .ok()
+        .map(|i| COMPOSITIONS[i].2)
+}
+
+fn is_diacritic(ch: char) -> bool {
+    matches!(ch,
+        '\u{300}'..='\u{36f}'
+        | '\u{1ab0}'..='\u{1aff}'
+        | '\u{1dc0}'..='\u{1dff}'
+        | '\u{20d0}'..='\u{20ff}'
+        | '\u{fe20}'..='\u{fe2f}')
+}
+
+/// Letters with a diacritic that does not decompose.
+fn folded_letter(ch: char) -> Option<char> {
+    Some(match ch {
+        'ø' => 'o',
+        'Ø' => 'O',
+        'đ' => 'd',
+        'Đ' => 'D',
+        'ħ' => 'h',
+        'Ħ' => 'H',
+        'ł' => 'l',
+        'Ł' => 'L',
+        'ŧ' => 't',
+        'Ŧ' => 'T',
+        'ı' => 'i',
+        _ => return None,
+    })
+}
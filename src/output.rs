@@ -0,0 +1,376 @@
+//! Where periodic reports go (`td5 consume --output SPEC`): standard output,
+//! a file, a set of rotating files, memory, or an HTTP webhook, behind one
+//! `OutputSink` trait so a long-running mode can push its results to another
+//! system without a shell pipeline in between.
+//!
+//! A report is one line of text, a JSON document in practice. Sinks are
+//! built from specs:
+//!
+//! | Spec | Sink |
+//! |---|---|
+//! | `-` or `stdout` | `Stdout` |
+//! | `file:PATH` | `FileSink`, appending one line per report |
+//! | `rotate:PATH:MAX_BYTES[:KEEP]` | `RotatingFiles`, keeping 5 old files by default |
+//! | `http://…` or `https://…` | `Webhook`, one POST per report |
+//!
+//! Plain `http://` webhooks are posted over a std `TcpStream`; `https://`
+//! ones go through the system `curl`, as `td5::fetch` does, since there is
+//! no TLS here.
+
+use crate::error::{Result, Td5Error};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// A destination for reports.
+pub trait OutputSink: Send {
+    /// Writes one report; `report` has no trailing line break.
+    fn write_report(&mut self, report: &str) -> Result<()>;
+
+    /// Pushes out anything buffered. Called once the last report is written.
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Builds the sink a spec names (see the module docs).
+///
+/// ```
+/// use td5::output::{self, OutputSink};
+///
+/// let dir = std::env::temp_dir().join(format!("td5-output-doc-{}", std::process::id()));
+/// std::fs::create_dir_all(&dir).unwrap();
+/// let path = dir.join("reports.jsonl");
+/// let mut sink = output::open(&format!("rotate:{}:40:2", path.display())).unwrap();
+/// for n in 0..5 {
+///     sink.write_report(&format!("{{\"report\": {n}, \"padding\": \"....\"}}")).unwrap();
+/// }
+/// // Each report is 33 bytes with its line break, so every file holds one.
+/// assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\"report\": 4, \"padding\": \"....\"}\n");
+/// assert!(dir.join("reports.jsonl.2").exists());
+/// assert!(!dir.join("reports.jsonl.3").exists());
+/// std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+pub fn open(spec: &str) -> Result<Box<dyn OutputSink>> {
+    if spec == "-" || spec == "stdout" {
+        return Ok(Box::new(Stdout));
+    }
+    if spec.starts_with("http://") || spec.starts_with("https://") {
+        return Ok(Box::new(Webhook::new(spec)?));
+    }
+    if let Some(path) = spec.strip_prefix("file:") {
+        return Ok(Box::new(FileSink::create(path)?));
+    }
+    if let Some(rest) = spec.strip_prefix("rotate:") {
+        let invalid = || {
+            Td5Error::config(format!(
+                "invalid output `{spec}` (expected rotate:PATH:MAX_BYTES[:KEEP])"
+            ))
+        };
+        // Numbers are taken from the right: PATH may itself hold colons.
+        let (head, last) = rest.rsplit_once(':').ok_or_else(invalid)?;
+        let (path, max_bytes, keep) = match head.rsplit_once(':') {
+            Some((path, max)) if max.parse::<u64>().is_ok() => {
+                (path, max, last.parse().map_err(|_| invalid())?)
+            }
+            _ => (head, last, RotatingFiles::DEFAULT_KEEP),
+        };
+        let max_bytes: u64 = max_bytes.parse().map_err(|_| invalid())?;
+        if path.is_empty() || max_bytes == 0 {
+            return Err(invalid());
+        }
+        return Ok(Box::new(RotatingFiles::create(path, max_bytes, keep)?));
+    }
+    Err(Td5Error::config(format!(
+        "unknown output `{spec}` (expected -, file:PATH, rotate:PATH:MAX_BYTES[:KEEP] or an http(s) URL)"
+    )))
+}
+
+/// Standard output, flushed after every report so a reader downstream sees
+/// it at once.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Stdout;
+
+impl OutputSink for Stdout {
+    fn write_report(&mut self, report: &str) -> Result<()> {
+        let mut out = std::io::stdout().lock();
+        writeln!(out, "{report}")
+            .and_then(|()| out.flush())
+            .map_err(|source| Td5Error::Io { path: None, source })
+    }
+}
+
+/// One file, appended to.
+#[derive(Debug)]
+pub struct FileSink {
+    path: PathBuf,
+    file: File,
+}
+
+impl FileSink {
+    pub fn create(path: impl AsRef<Path>) -> Result<FileSink> {
+        let path = path.as_ref().to_path_buf();
+        let file = append(&path)?;
+        Ok(FileSink { path, file })
+    }
+}
+
+impl OutputSink for FileSink {
+    fn write_report(&mut self, report: &str) -> Result<()> {
+        writeln!(self.file, "{report}").map_err(|source| io_error(&self.path, source))
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.file
+            .sync_data()
+            .map_err(|source| io_error(&self.path, source))
+    }
+}
+
+/// A file that is moved aside once a report would take it past `max_bytes`:
+/// `PATH` becomes `PATH.1`, `PATH.1` becomes `PATH.2`, and so on up to
+/// `PATH.KEEP`, the oldest, which is deleted on the next rotation. A report
+/// is never split across files, so one larger than `max_bytes` gets a file
+/// of its own.
+#[derive(Debug)]
+pub struct RotatingFiles {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+    file: File,
+    written: u64,
+}
+
+impl RotatingFiles {
+    pub const DEFAULT_KEEP: usize = 5;
+
+    /// Appends to `path` if it exists, counting what it already holds.
+    pub fn create(path: impl AsRef<Path>, max_bytes: u64, keep: usize) -> Result<RotatingFiles> {
+        let path = path.as_ref().to_path_buf();
+        let file = append(&path)?;
+        let written = file
+            .metadata()
+            .map_err(|source| io_error(&path, source))?
+            .len();
+        Ok(RotatingFiles {
+            path,
+            max_bytes,
+            keep,
+            file,
+            written,
+        })
+    }
+
+    fn rotated(&self, n: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{n}"));
+        name.into()
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        if self.keep == 0 {
+            self.file.set_len(0).map_err(|e| io_error(&self.path, e))?;
+            self.written = 0;
+            return Ok(());
+        }
+        let _ = std::fs::remove_file(self.rotated(self.keep));
+        for n in (1..self.keep).rev() {
+            let from = self.rotated(n);
+            if from.exists() {
+                std::fs::rename(&from, self.rotated(n + 1)).map_err(|e| io_error(&from, e))?;
+            }
+        }
+        std::fs::rename(&self.path, self.rotated(1)).map_err(|e| io_error(&self.path, e))?;
+        self.file = append(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl OutputSink for RotatingFiles {
+    fn write_report(&mut self, report: &str) -> Result<()> {
+        let len = report.len() as u64 + 1;
+        if self.written > 0 && self.written + len > self.max_bytes {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{report}").map_err(|source| io_error(&self.path, source))?;
+        self.written += len;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.file
+            .sync_data()
+            .map_err(|source| io_error(&self.path, source))
+    }
+}
+
+/// Reports kept in memory, for tests and for embedding td5 in a program
+/// that does its own delivery. Clones share the same buffer.
+///
+/// ```
+/// use td5::output::{MemorySink, OutputSink};
+///
+/// let reports = MemorySink::default();
+/// let mut sink = reports.clone();
+/// sink.write_report(&td5::analyze("the cat").to_json_value().to_string()).unwrap();
+/// assert!(reports.reports()[0].contains("\"total_words\":2"));
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct MemorySink(Arc<Mutex<Vec<String>>>);
+
+impl MemorySink {
+    /// Every report written so far, oldest first.
+    pub fn reports(&self) -> Vec<String> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+impl OutputSink for MemorySink {
+    fn write_report(&mut self, report: &str) -> Result<()> {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(report.to_string());
+        Ok(())
+    }
+}
+
+/// An HTTP endpoint each report is POSTed to as `application/json`. Any
+/// answer but a `2xx` is an error; nothing is retried, the next report is
+/// the next try.
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    url: String,
+    pub timeout: Duration,
+}
+
+impl Webhook {
+    pub fn new(url: &str) -> Result<Webhook> {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(Td5Error::config(format!(
+                "webhook `{url}` is not an http:// or https:// URL"
+            )));
+        }
+        Ok(Webhook {
+            url: url.to_string(),
+            timeout: Duration::from_secs(10),
+        })
+    }
+
+    /// Posts over a plain TCP connection, one per report.
+    fn post_http(&self, rest: &str, body: &str) -> Result<u16> {
+        let (host, path) = match rest.find('/') {
+            Some(slash) => (&rest[..slash], &rest[slash..]),
+            None => (rest, "/"),
+        };
+        let address = if host.contains(':') {
+            host.to_string()
+        } else {
+            format!("{host}:80")
+        };
+        let net = |source| Td5Error::Io {
+            path: Some(self.url.clone().into()),
+            source,
+        };
+        let socket = address
+            .to_socket_addrs()
+            .map_err(net)?
+            .next()
+            .ok_or_else(|| net(std::io::ErrorKind::NotFound.into()))?;
+        let mut stream = TcpStream::connect_timeout(&socket, self.timeout).map_err(net)?;
+        stream.set_read_timeout(Some(self.timeout)).map_err(net)?;
+        stream.set_write_timeout(Some(self.timeout)).map_err(net)?;
+        write!(
+            stream,
+            "POST {path} HTTP/1.1\r\nHost: {host}\r\nUser-Agent: td5\r\n\
+             Content-Type: application/json\r\nContent-Length: {}\r\n\
+             Connection: close\r\n\r\n{body}",
+            body.len()
+        )
+        .map_err(net)?;
+        let mut status = String::new();
+        BufReader::new(stream).read_line(&mut status).map_err(net)?;
+        status
+            .split_whitespace()
+            .nth(1)
+            .and_then(|code| code.parse().ok())
+            .ok_or_else(|| {
+                Td5Error::config(format!(
+                    "webhook {}: unexpected answer `{}`",
+                    self.url,
+                    status.trim_end()
+                ))
+            })
+    }
+
+    /// Posts with the system `curl`, which brings TLS.
+    fn post_curl(&self, body: &str) -> Result<u16> {
+        let mut child = Command::new("curl")
+            .args(["--silent", "--show-error", "--output", "/dev/null"])
+            .args(["--write-out", "%{http_code}", "--max-time"])
+            .arg(self.timeout.as_secs_f64().to_string())
+            .args(["--header", "Content-Type: application/json"])
+            .args(["--data-binary", "@-"])
+            .arg(&self.url)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| Td5Error::config(format!("cannot run curl: {e}")))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            // A write error shows up as curl's own failure below.
+            let _ = stdin.write_all(body.as_bytes());
+        }
+        let output = child
+            .wait_with_output()
+            .map_err(|e| Td5Error::config(format!("cannot run curl: {e}")))?;
+        if !output.status.success() {
+            return Err(Td5Error::config(format!(
+                "webhook {}: {}",
+                self.url,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .parse()
+            .map_err(|_| Td5Error::config(format!("webhook {}: no HTTP status", self.url)))
+    }
+}
+
+impl OutputSink for Webhook {
+    fn write_report(&mut self, report: &str) -> Result<()> {
+        let status = match self.url.strip_prefix("http://") {
+            Some(rest) => self.post_http(rest, report)?,
+            None => self.post_curl(report)?,
+        };
+        if (200..300).contains(&status) {
+            Ok(())
+        } else {
+            Err(Td5Error::config(format!(
+                "webhook {}: HTTP {status}",
+                self.url
+            )))
+        }
+    }
+}
+
+fn append(path: &Path) -> Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|source| io_error(path, source))
+}
+
+fn io_error(path: &Path, source: std::io::Error) -> Td5Error {
+    Td5Error::Io {
+        path: Some(path.to_path_buf()),
+        source,
+    }
+}
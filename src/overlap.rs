@@ -0,0 +1,80 @@
+//! Vocabulary overlap between two documents: a light alternative to TF-IDF
+//! for telling how close two texts are.
+
+use crate::freq::WordFrequency;
+use crate::json::Json;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Overlap<'a> {
+    /// Distinct words of each document.
+    pub vocabulary: (usize, usize),
+    /// Distinct words found in both.
+    pub shared: usize,
+    /// Shared words over the union of both vocabularies.
+    pub jaccard: f64,
+    /// The most frequent words of each document missing from the other.
+    pub only_a: Vec<(&'a str, usize)>,
+    pub only_b: Vec<(&'a str, usize)>,
+    /// The shared words with their count in each document, by combined
+    /// count.
+    pub shared_top: Vec<(&'a str, usize, usize)>,
+}
+
+impl<'a> Overlap<'a> {
+    /// Compares `a` and `b`, keeping `top` words in each list. Ties are
+    /// broken alphabetically.
+    pub fn between(a: &'a WordFrequency, b: &'a WordFrequency, top: usize) -> Overlap<'a> {
+        let only = |x: &'a WordFrequency, y: &WordFrequency| -> Vec<(&'a str, usize)> {
+            x.by_count()
+                .filter(|(w, _)| y.count(w) == 0)
+                .take(top)
+                .collect()
+        };
+        let mut shared_top: Vec<(&str, usize, usize)> = a
+            .iter()
+            .filter_map(|(w, n)| Some((w, n, Some(b.count(w)).filter(|&m| m > 0)?)))
+            .collect();
+        let shared = shared_top.len();
+        let union = a.len() + b.len() - shared;
+        shared_top
+            .sort_unstable_by(|x, y| (y.1 + y.2).cmp(&(x.1 + x.2)).then_with(|| x.0.cmp(y.0)));
+        shared_top.truncate(top);
+        Overlap {
+            vocabulary: (a.len(), b.len()),
+            shared,
+            jaccard: if union == 0 {
+                0.0
+            } else {
+                shared as f64 / union as f64
+            },
+            only_a: only(a, b),
+            only_b: only(b, a),
+            shared_top,
+        }
+    }
+
+    pub fn to_json_value(&self) -> Json {
+        let list = |words: &[(&str, usize)]| {
+            Json::Array(
+                words
+                    .iter()
+                    .map(|&(w, n)| Json::Array(vec![w.into(), n.into()]))
+                    .collect(),
+            )
+        };
+        let shared_top = self
+            .shared_top
+            .iter()
+            .map(|&(w, a, b)| Json::Array(vec![w.into(), a.into(), b.into()]))
+            .collect();
+        Json::Object(vec![
+            ("vocabulary_a".into(), self.vocabulary.0.into()),
+            ("vocabulary_b".into(), self.vocabulary.1.into()),
+            ("shared".into(), self.shared.into()),
+            ("jaccard".into(), self.jaccard.into()),
+            ("only_a".into(), list(&self.only_a)),
+            ("only_b".into(), list(&self.only_b)),
+            ("shared_top".into(), Json::Array(shared_top)),
+        ])
+    }
+}
@@ -0,0 +1,196 @@
+//! Multi-threaded fast path.
+//!
+//! The input is cut at whitespace into one chunk per thread, or into chunks of
+//! `Tuning::chunk_bytes` that the threads take in turn. Each thread counts
+//! into its own map (no shared state, no locks), then the partial results are
+//! merged pairwise in a tree: `log2(threads)` rounds, each round merging
+//! disjoint pairs in parallel. `PhaseTimings` shows how long each step took,
+//! which is where parallel scaling visibly stops: counting shrinks with more
+//! threads, merging does not.
+//!
+//! With an `AnalyzerConfig::on_progress` callback, each counted chunk is
+//! reported, and chunks are capped at `AnalyzerConfig::progress_every` bytes
+//! so that a large text reports more than once per thread.
+
+use crate::clock::Stopwatch;
+use crate::fast::{self, Counts};
+use crate::tuning::Tuning;
+use crate::{Analyzer, AnalyzerConfig, TextStats};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PhaseTimings {
+    pub threads: usize,
+    pub split_ns: u128,
+    pub count_ns: u128,
+    pub merge_ns: u128,
+    pub merge_rounds: usize,
+    pub finish_ns: u128,
+}
+
+impl PhaseTimings {
+    /// The phases together: the whole analysis.
+    pub fn total_ns(&self) -> u128 {
+        self.split_ns + self.count_ns + self.merge_ns + self.finish_ns
+    }
+}
+
+/// Parallel analyzer; `threads == 0` uses all available cores.
+#[derive(Debug, Clone, Default)]
+pub struct Parallel {
+    pub threads: usize,
+    pub config: AnalyzerConfig,
+}
+
+impl Analyzer for Parallel {
+    fn analyze(&self, text: &str) -> TextStats {
+        analyze_text_parallel_timed(text, self.threads, &self.config).0
+    }
+}
+
+pub fn analyze_text_parallel(text: &str, threads: usize) -> TextStats {
+    analyze_text_parallel_timed(text, threads, &AnalyzerConfig::default()).0
+}
+
+pub fn analyze_text_parallel_timed(
+    text: &str,
+    threads: usize,
+    config: &AnalyzerConfig,
+) -> (TextStats, PhaseTimings) {
+    let mut phase = Stopwatch::start();
+    let threads = effective_threads(threads);
+
+    let mode = fast::detect(text);
+    let mut pieces = match Tuning::current().chunk_bytes {
+        0 => threads,
+        size => text.len().div_ceil(size).max(threads),
+    };
+    if config.progress.is_some() {
+        pieces = pieces.max(text.len().div_ceil(config.progress_bytes));
+    }
+    let chunks = split_chunks(text, pieces);
+    let workers = threads.min(chunks.len());
+    let split_ns = phase.elapsed_ns();
+    phase = Stopwatch::start();
+
+    let next = AtomicUsize::new(0);
+    let processed = AtomicUsize::new(0);
+    let tokens = AtomicUsize::new(0);
+    // N-grams straddle chunk boundaries, so with them each chunk's counts
+    // are kept apart and merged in text order.
+    let ordered = config.ngrams > 0;
+    let mut parts: Vec<(usize, Counts)> = thread::scope(|s| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                s.spawn(|| {
+                    let mut counts: Vec<(usize, Counts)> = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(chunk) = chunks.get(index) else {
+                            break;
+                        };
+                        let len = chunk.len();
+                        let chunk = fast::count_lenient(chunk, mode, config);
+                        if let Some(progress) = &config.progress {
+                            let done = processed.fetch_add(len, Ordering::Relaxed) + len;
+                            let words = chunk.tokens();
+                            let counted = tokens.fetch_add(words, Ordering::Relaxed) + words;
+                            progress.report(done, counted);
+                        }
+                        match counts.last_mut() {
+                            Some((_, counts)) if !ordered => counts.merge(chunk),
+                            _ => counts.push((index, chunk)),
+                        }
+                    }
+                    counts
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().expect("counting thread panicked"))
+            .collect()
+    });
+    parts.sort_unstable_by_key(|&(index, _)| index);
+    let parts = parts.into_iter().map(|(_, counts)| counts).collect();
+    let count_ns = phase.elapsed_ns();
+    phase = Stopwatch::start();
+
+    let (counts, merge_rounds) = tree_merge(parts);
+    let merge_ns = phase.elapsed_ns();
+    phase = Stopwatch::start();
+
+    let stats = fast::finish(counts, config);
+    let timings = PhaseTimings {
+        threads: workers,
+        split_ns,
+        count_ns,
+        merge_ns,
+        merge_rounds,
+        finish_ns: phase.elapsed_ns(),
+    };
+    (stats, timings)
+}
+
+pub(crate) fn effective_threads(requested: usize) -> usize {
+    if requested > 0 {
+        requested
+    } else {
+        thread::available_parallelism().map_or(1, |n| n.get())
+    }
+}
+
+/// Cuts `text` into at most `n` pieces, moving each cut forward to the next
+/// whitespace so no word straddles two chunks.
+pub(crate) fn split_chunks(text: &str, n: usize) -> Vec<&str> {
+    let bytes = text.as_bytes();
+    let target = text.len().div_ceil(n.max(1)).max(1);
+    let mut chunks = Vec::with_capacity(n);
+    let mut begin = 0;
+    while begin < bytes.len() {
+        let mut end = (begin + target).min(bytes.len());
+        while end < bytes.len() && !bytes[end].is_ascii_whitespace() {
+            end += 1;
+        }
+        // Every ASCII whitespace byte is a char boundary.
+        chunks.push(&text[begin..end]);
+        begin = end;
+    }
+    if chunks.is_empty() {
+        chunks.push(text);
+    }
+    chunks
+}
+
+/// Merges neighbours pairwise until one map is left. Returns the result and
+/// the number of rounds.
+pub(crate) fn tree_merge(mut parts: Vec<Counts>) -> (Counts, usize) {
+    let mut rounds = 0;
+    while parts.len() > 1 {
+        rounds += 1;
+        let mut pairs = Vec::with_capacity(parts.len().div_ceil(2));
+        let mut iter = parts.into_iter();
+        while let Some(a) = iter.next() {
+            pairs.push((a, iter.next()));
+        }
+        parts = thread::scope(|s| {
+            let handles: Vec<_> = pairs
+                .into_iter()
+                .map(|(mut a, b)| {
+                    s.spawn(move || {
+                        if let Some(b) = b {
+                            a.merge(b);
+                        }
+                        a
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().expect("merge thread panicked"))
+                .collect()
+        });
+    }
+    (parts.pop().expect("at least one chunk"), rounds)
+}
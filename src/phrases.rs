@@ -0,0 +1,240 @@
+//! Multi-word phrase lists matched over a word stream.
+//!
+//! A pattern is a sequence of space-separated token matchers. Each matcher is
+//! a lowercase word, a `*` glob (`*ed`, `un*`) or `|`-separated alternatives
+//! of those, e.g. `was|were|is|are *ed|*en`. Patterns are compiled into one
+//! NFA whose states are (pattern, position); every word advances all live
+//! states at once, so the text is read a single time however many patterns
+//! there are.
+
+use crate::error::{Result, Td5Error};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Matcher {
+    Word(String),
+    Prefix(String),
+    Suffix(String),
+    Any,
+}
+
+impl Matcher {
+    fn parse(alternative: &str) -> Matcher {
+        match (alternative.strip_prefix('*'), alternative.strip_suffix('*')) {
+            _ if alternative == "*" => Matcher::Any,
+            (Some(suffix), _) => Matcher::Suffix(suffix.to_string()),
+            (_, Some(prefix)) => Matcher::Prefix(prefix.to_string()),
+            _ => Matcher::Word(alternative.to_string()),
+        }
+    }
+
+    fn matches(&self, word: &str) -> bool {
+        match self {
+            Matcher::Word(w) => w == word,
+            // The glob must stand for at least one character.
+            Matcher::Prefix(p) => word.len() > p.len() && word.starts_with(p.as_str()),
+            Matcher::Suffix(s) => word.len() > s.len() && word.ends_with(s.as_str()),
+            Matcher::Any => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    pub source: String,
+    steps: Vec<Vec<Matcher>>,
+}
+
+impl Pattern {
+    pub fn parse(source: &str) -> Result<Pattern> {
+        let steps: Vec<Vec<Matcher>> = source
+            .split_whitespace()
+            .map(|token| {
+                token
+                    .to_lowercase()
+                    .split('|')
+                    .map(Matcher::parse)
+                    .collect()
+            })
+            .collect();
+        if steps.is_empty() {
+            return Err(Td5Error::config("empty phrase pattern"));
+        }
+        Ok(Pattern {
+            source: source.trim().to_string(),
+            steps,
+        })
+    }
+}
+
+/// A named group of patterns, e.g. `passive` or `filler-phrases`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhraseList {
+    pub name: String,
+    pub patterns: Vec<Pattern>,
+}
+
+impl PhraseList {
+    pub fn new(name: &str, patterns: &[&str]) -> Result<PhraseList> {
+        Ok(PhraseList {
+            name: name.to_string(),
+            patterns: patterns
+                .iter()
+                .map(|p| Pattern::parse(p))
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    /// Lists from a file of `[name]` headers each followed by one pattern per
+    /// line. Blank lines and lines starting with `#` are ignored.
+    pub fn parse_file(source: &str) -> Result<Vec<PhraseList>> {
+        let mut lists: Vec<PhraseList> = Vec::new();
+        for (n, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                lists.push(PhraseList {
+                    name: name.trim().to_string(),
+                    patterns: Vec::new(),
+                });
+                continue;
+            }
+            let list = lists.last_mut().ok_or_else(|| {
+                Td5Error::config(format!("line {}: pattern before any [list] header", n + 1))
+            })?;
+            list.patterns.push(Pattern::parse(line)?);
+        }
+        Ok(lists)
+    }
+}
+
+/// Passive constructions (a form of "to be" followed by a participle) and
+/// wordy phrases with a shorter equivalent, in English and French. The passive
+/// patterns are heuristics: "was tired" is flagged too.
+pub fn builtin() -> Vec<PhraseList> {
+    vec![
+        PhraseList::new(
+            "passive",
+            &[
+                "am|is|are|was|were|be|been|being *ed|made|done|built|sent|held|kept|told|found|paid|sold|written|given|taken|broken|chosen|driven|spoken|stolen|known|grown|thrown|drawn|shown|seen|forgotten|hidden",
+                "est|sont|était|étaient|été|sera|seront *é|*ée|*és|*ées|*it|*ite|*is|*ise|fait|faite|faits|faites",
+            ],
+        )
+        .expect("built-in passive patterns are valid"),
+        PhraseList::new(
+            "filler-phrases",
+            &[
+                "in order to",
+                "due to the fact that",
+                "at this point in time",
+                "it should be noted that",
+                "needless to say",
+                "for all intents and purposes",
+                "the fact that",
+                "en fait",
+                "il faut noter que",
+                "au jour d'aujourd'hui",
+                "dans le but de",
+            ],
+        )
+        .expect("built-in filler phrases are valid"),
+    ]
+}
+
+/// One occurrence: `words[start..start + len]` matched `pattern` of `list`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match {
+    pub list: usize,
+    pub pattern: usize,
+    pub start: usize,
+    pub len: usize,
+}
+
+/// Every list's patterns compiled into one NFA.
+pub struct PhraseSet {
+    lists: Vec<PhraseList>,
+    /// Patterns whose first step is a plain word, keyed by that word, so
+    /// starting states are a lookup rather than a scan of every pattern.
+    by_first_word: HashMap<String, Vec<(usize, usize)>>,
+    /// Patterns whose first step needs a glob test.
+    other_starts: Vec<(usize, usize)>,
+}
+
+impl PhraseSet {
+    pub fn new(lists: Vec<PhraseList>) -> PhraseSet {
+        let mut by_first_word: HashMap<String, Vec<(usize, usize)>> = HashMap::new();
+        let mut other_starts = Vec::new();
+        for (l, list) in lists.iter().enumerate() {
+            for (p, pattern) in list.patterns.iter().enumerate() {
+                match &pattern.steps[0][..] {
+                    words if words.iter().all(|m| matches!(m, Matcher::Word(_))) => {
+                        for m in words {
+                            if let Matcher::Word(w) = m {
+                                by_first_word.entry(w.clone()).or_default().push((l, p));
+                            }
+                        }
+                    }
+                    _ => other_starts.push((l, p)),
+                }
+            }
+        }
+        PhraseSet {
+            lists,
+            by_first_word,
+            other_starts,
+        }
+    }
+
+    pub fn lists(&self) -> &[PhraseList] {
+        &self.lists
+    }
+
+    fn step(&self, (l, p): (usize, usize), pos: usize) -> &[Matcher] {
+        &self.lists[l].patterns[p].steps[pos]
+    }
+
+    fn pattern_len(&self, (l, p): (usize, usize)) -> usize {
+        self.lists[l].patterns[p].steps.len()
+    }
+
+    /// Matches over lowercase `words`, in order of their end. `breaks[i]` marks
+    /// a sentence boundary before word `i`; no match crosses one.
+    pub fn find(&self, words: &[&str], breaks: &[bool]) -> Vec<Match> {
+        // Live states: (pattern, index of its next step, start word).
+        let mut live: Vec<((usize, usize), usize, usize)> = Vec::new();
+        let mut next = Vec::new();
+        let mut found = Vec::new();
+        for (i, word) in words.iter().enumerate() {
+            if breaks.get(i).copied().unwrap_or(false) {
+                live.clear();
+            }
+            let starts = self
+                .by_first_word
+                .get(*word)
+                .into_iter()
+                .flatten()
+                .copied()
+                .chain(self.other_starts.iter().copied())
+                .map(|id| (id, 0, i));
+            for (id, pos, start) in live.drain(..).chain(starts) {
+                if !self.step(id, pos).iter().any(|m| m.matches(word)) {
+                    continue;
+                }
+                if pos + 1 == self.pattern_len(id) {
+                    found.push(Match {
+                        list: id.0,
+                        pattern: id.1,
+                        start,
+                        len: i + 1 - start,
+                    });
+                } else {
+                    next.push((id, pos + 1, start));
+                }
+            }
+            std::mem::swap(&mut live, &mut next);
+        }
+        found
+    }
+}
@@ -0,0 +1,273 @@
+//! Counts of personal data that should not end up in a published dataset:
+//! email addresses, phone numbers and IPv4/IPv6 addresses, with optional
+//! redacted samples to see what was found.
+//!
+//! The detectors are hand-written heuristics rather than regexes (the crate
+//! has no regex dependency):
+//!
+//! - email: `local@domain.tld`, with a letters-only top-level domain;
+//! - IPv4: four dot-separated numbers from 0 to 255;
+//! - IPv6: hex groups separated by `:`, either eight of them or with one `::`;
+//! - phone: 7 to 15 digits in groups separated by `-`, `.` or spaces, e.g.
+//!   `555-123-4567`, `(555) 123-4567` or `+33 1 23 45 67 89`. Spaces only
+//!   count as separators after a leading `+` or `(...)`, so runs of numbers
+//!   in prose are not taken for phone numbers; dates (`2024-05-01`) and IPv4
+//!   addresses are not either.
+
+use crate::json::Json;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PiiKind {
+    Email,
+    Phone,
+    Ipv4,
+    Ipv6,
+}
+
+impl PiiKind {
+    pub const ALL: [PiiKind; 4] = [PiiKind::Email, PiiKind::Phone, PiiKind::Ipv4, PiiKind::Ipv6];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PiiKind::Email => "emails",
+            PiiKind::Phone => "phones",
+            PiiKind::Ipv4 => "ipv4",
+            PiiKind::Ipv6 => "ipv6",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PiiStats {
+    /// Matches per kind, in `PiiKind::ALL` order.
+    counts: [usize; 4],
+    /// Up to the requested number of distinct redacted matches per kind.
+    pub samples: Vec<(PiiKind, String)>,
+}
+
+impl PiiStats {
+    /// Scans `text`, keeping up to `samples` redacted examples of each kind.
+    pub fn scan(text: &str, samples: usize) -> PiiStats {
+        let mut stats = PiiStats::default();
+        let mut found = |kind: PiiKind, value: &str| {
+            stats.counts[kind as usize] += 1;
+            let redacted = redact(kind, value);
+            let kept = stats.samples.iter().filter(|(k, _)| *k == kind).count();
+            if kept < samples
+                && !stats
+                    .samples
+                    .iter()
+                    .any(|(k, s)| *k == kind && *s == redacted)
+            {
+                stats.samples.push((kind, redacted));
+            }
+        };
+        for token in text.split_whitespace() {
+            let token = token.trim_matches(|c: char| "<>()[]{}\"',;!?".contains(c));
+            let token = token.trim_end_matches(['.', ':']);
+            if is_email(token) {
+                found(PiiKind::Email, token);
+            } else if is_ipv4(token) {
+                found(PiiKind::Ipv4, token);
+            } else if is_ipv6(token) {
+                found(PiiKind::Ipv6, token);
+            }
+        }
+        for phone in phones(text) {
+            found(PiiKind::Phone, phone);
+        }
+        stats
+    }
+
+    pub fn count(&self, kind: PiiKind) -> usize {
+        self.counts[kind as usize]
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    /// `{"emails": n, ..., "samples": {"emails": [...], ...}}`, the samples
+    /// only when some were kept.
+    pub fn to_json_value(&self) -> Json {
+        let mut fields: Vec<(String, Json)> = PiiKind::ALL
+            .iter()
+            .map(|&kind| (kind.name().to_string(), self.count(kind).into()))
+            .collect();
+        if !self.samples.is_empty() {
+            let samples = PiiKind::ALL
+                .iter()
+                .map(|&kind| {
+                    let of_kind = self
+                        .samples
+                        .iter()
+                        .filter(|(k, _)| *k == kind)
+                        .map(|(_, s)| s.as_str().into())
+                        .collect();
+                    (kind.name().to_string(), Json::Array(of_kind))
+                })
+                .collect();
+            fields.push(("samples".into(), Json::Object(samples)));
+        }
+        Json::Object(fields)
+    }
+}
+
+fn is_email(token: &str) -> bool {
+    let Some((local, domain)) = token.split_once('@') else {
+        return false;
+    };
+    let local_ok = !local.is_empty()
+        && local
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "._%+-".contains(c));
+    let labels: Vec<&str> = domain.split('.').collect();
+    let tld = labels.last().copied().unwrap_or("");
+    local_ok
+        && labels.len() >= 2
+        && labels.iter().all(|l| {
+            !l.is_empty()
+                && !l.starts_with('-')
+                && l.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+        && tld.len() >= 2
+        && tld.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+fn is_ipv4(token: &str) -> bool {
+    let parts: Vec<&str> = token.split('.').collect();
+    parts.len() == 4
+        && parts.iter().all(|p| {
+            (1..=3).contains(&p.len())
+                && p.bytes().all(|b| b.is_ascii_digit())
+                && (p.len() == 1 || !p.starts_with('0'))
+                && p.parse::<u16>().is_ok_and(|n| n <= 255)
+        })
+}
+
+fn is_ipv6(token: &str) -> bool {
+    let compressed = token.matches("::").count();
+    if compressed > 1 || token.contains(":::") {
+        return false;
+    }
+    let groups: Vec<&str> = token.split(':').filter(|g| !g.is_empty()).collect();
+    let hex = groups
+        .iter()
+        .all(|g| (1..=4).contains(&g.len()) && g.chars().all(|c| c.is_ascii_hexdigit()));
+    hex && match compressed {
+        0 => groups.len() == 8,
+        _ => groups.len() < 8 && token.len() > 2,
+    }
+}
+
+/// Phone-number candidates of `text`, in order.
+fn phones(text: &str) -> Vec<&str> {
+    let bytes = text.as_bytes();
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let starts = matches!(bytes[i], b'+' | b'(') || bytes[i].is_ascii_digit();
+        let boundary = i == 0 || !(bytes[i - 1].is_ascii_alphanumeric() || bytes[i - 1] == b'.');
+        if !(starts && boundary) {
+            i += 1;
+            continue;
+        }
+        let end = phone_end(bytes, i);
+        if let Some(end) = end {
+            found.push(&text[i..end]);
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+    found
+}
+
+/// End of the phone number starting at `start`, if there is one.
+fn phone_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let plus = bytes[start] == b'+';
+    let mut spaced = plus;
+    let mut groups: Vec<usize> = Vec::new();
+    let mut separators = Vec::new();
+    let mut digits = 0;
+    let mut i = start + usize::from(plus);
+    let mut end = i;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'0'..=b'9' => {
+                digits += 1;
+                if groups.len() == separators.len() {
+                    groups.push(0);
+                }
+                *groups.last_mut().expect("pushed above") += 1;
+                i += 1;
+                end = i;
+            }
+            b'(' if groups.is_empty() => {
+                spaced = true;
+                i += 1;
+            }
+            b')' if groups.len() == 1 => {
+                separators.push(b')');
+                i += 1;
+                if bytes.get(i) == Some(&b' ') {
+                    i += 1;
+                }
+            }
+            sep @ (b'-' | b'.' | b' ')
+                if groups.len() > separators.len()
+                    && (sep != b' ' || spaced)
+                    && bytes
+                        .get(i + 1)
+                        .is_some_and(|b| b.is_ascii_digit() || *b == b'(') =>
+            {
+                separators.push(sep);
+                i += 1;
+            }
+            _ => break,
+        }
+    }
+    // A number glued to letters or more digits is something else.
+    if bytes.get(end).is_some_and(|b| b.is_ascii_alphanumeric()) {
+        return None;
+    }
+    let date = groups == [4, 2, 2] || groups == [2, 2, 4];
+    let dotted_quad = groups.len() == 4 && separators.iter().all(|&s| s == b'.');
+    let separated = plus || !separators.is_empty();
+    ((7..=15).contains(&digits) && separated && groups.len() >= 2 && !date && !dotted_quad)
+        .then_some(end)
+}
+
+fn redact(kind: PiiKind, value: &str) -> String {
+    match kind {
+        PiiKind::Email => {
+            let (local, domain) = value.split_once('@').unwrap_or((value, ""));
+            let tld = domain.rsplit('.').next().unwrap_or("");
+            format!("{}***@***.{tld}", local.chars().next().unwrap_or('*'))
+        }
+        // Keep the last two digits, enough to tell samples apart.
+        PiiKind::Phone => {
+            let digits = value.bytes().filter(u8::is_ascii_digit).count();
+            let mut seen = 0;
+            value
+                .chars()
+                .map(|c| match c {
+                    '0'..='9' => {
+                        seen += 1;
+                        if seen + 2 > digits { c } else { '*' }
+                    }
+                    c => c,
+                })
+                .collect()
+        }
+        PiiKind::Ipv4 => {
+            let mut parts = value.split('.');
+            format!(
+                "{}.{}.*.*",
+                parts.next().unwrap_or("*"),
+                parts.next().unwrap_or("*")
+            )
+        }
+        PiiKind::Ipv6 => format!("{}:…", value.split(':').next().unwrap_or("")),
+    }
+}
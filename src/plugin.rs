@@ -0,0 +1,278 @@
+//! Extensions: word filters and extra metrics kept outside this crate, for
+//! analyses an organization cannot or will not upstream.
+//!
+//! `Extension` is the Rust side. Its word filter goes into a `Pipeline` as
+//! a `Stage::Filter`, dropping words from the counts like a stopword list;
+//! its metrics are whatever numbers it computes from a text. With the
+//! `plugins` feature (Unix), `Plugin::load` opens a shared library that
+//! exports `td5_plugin`, so an extension can be written in any language
+//! with a C ABI and shipped without rebuilding td5:
+//!
+//! ```c
+//! struct td5_plugin {
+//!     uint32_t abi;                /* 1 */
+//!     const char *name;
+//!     /* nonzero to count the word; NULL to count every word */
+//!     int (*keep_word)(const char *word, size_t len);
+//!     /* metric names, NULL-terminated; NULL for none */
+//!     const char *const *metrics;
+//!     /* writes one value per metric into out */
+//!     void (*measure)(const char *text, size_t len, double *out);
+//! };
+//! const struct td5_plugin *td5_plugin(void);
+//! ```
+//!
+//! Words and texts are UTF-8 and not NUL-terminated. Each distinct word is
+//! filtered once per text, after the other stages of its pipeline. The
+//! functions may be called from several threads at once.
+
+use std::fmt;
+use std::sync::Arc;
+
+pub trait Extension: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Whether `keep_word` drops anything, so that a metrics-only
+    /// extension does not send every text through a pipeline.
+    fn filters_words(&self) -> bool {
+        false
+    }
+
+    /// Whether `word` is counted.
+    fn keep_word(&self, word: &str) -> bool {
+        let _ = word;
+        true
+    }
+
+    /// Extra metrics of `text`, by name.
+    fn metrics(&self, text: &str) -> Vec<(String, f64)> {
+        let _ = text;
+        Vec::new()
+    }
+}
+
+/// An extension as a pipeline stage.
+///
+/// ```
+/// use std::sync::Arc;
+/// use td5::plugin::{Extension, Filter};
+/// use td5::transform::{Pipeline, Stage};
+///
+/// struct NoShortWords;
+///
+/// impl Extension for NoShortWords {
+///     fn name(&self) -> &str {
+///         "no-short-words"
+///     }
+///     fn filters_words(&self) -> bool {
+///         true
+///     }
+///     fn keep_word(&self, word: &str) -> bool {
+///         word.chars().count() > 2
+///     }
+/// }
+///
+/// let pipeline = Pipeline {
+///     stages: vec![Stage::Filter(Filter(Arc::new(NoShortWords)))],
+/// };
+/// let stats = pipeline.analyze("an error at line 4, an error again");
+/// assert_eq!(stats.top_words[0], ("error".to_string(), 2));
+/// assert_eq!(stats.total_words, 4);
+/// ```
+#[derive(Clone)]
+pub struct Filter(pub Arc<dyn Extension>);
+
+impl fmt::Debug for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Filter").field(&self.0.name()).finish()
+    }
+}
+
+impl PartialEq for Filter {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Filter {}
+
+#[cfg(all(feature = "plugins", unix))]
+pub use self::dynamic::Plugin;
+
+#[cfg(all(feature = "plugins", unix))]
+mod dynamic {
+    use super::Extension;
+    use crate::error::{Result, Td5Error};
+    use std::ffi::{CStr, CString, c_char, c_int, c_void};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+
+    const RTLD_NOW: c_int = 2;
+    const ABI: u32 = 1;
+
+    unsafe extern "C" {
+        fn dlopen(filename: *const c_char, flags: c_int) -> *mut c_void;
+        fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+        fn dlclose(handle: *mut c_void) -> c_int;
+        fn dlerror() -> *const c_char;
+    }
+
+    #[repr(C)]
+    struct RawPlugin {
+        abi: u32,
+        name: *const c_char,
+        keep_word: Option<unsafe extern "C" fn(*const c_char, usize) -> c_int>,
+        metrics: *const *const c_char,
+        measure: Option<unsafe extern "C" fn(*const c_char, usize, *mut f64)>,
+    }
+
+    /// A shared library implementing the plugin ABI, closed on drop.
+    pub struct Plugin {
+        handle: *mut c_void,
+        raw: *const RawPlugin,
+        name: String,
+        metrics: Vec<String>,
+        path: PathBuf,
+    }
+
+    // SAFETY: the ABI requires the plugin's functions to be thread-safe, and
+    // its static data is only read.
+    unsafe impl Send for Plugin {}
+    unsafe impl Sync for Plugin {}
+
+    /// The message of the last `dl*` failure.
+    fn last_error() -> String {
+        // SAFETY: `dlerror` returns NULL or a NUL-terminated string valid
+        // until the next `dl*` call.
+        let message = unsafe { dlerror() };
+        if message.is_null() {
+            return "unknown error".to_string();
+        }
+        unsafe { CStr::from_ptr(message) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    impl Plugin {
+        /// Opens the library at `path`. A bare file name is looked up in the
+        /// current directory, not the library search path.
+        pub fn load(path: impl AsRef<Path>) -> Result<Plugin> {
+            let path = path.as_ref();
+            let invalid = |why: String| Td5Error::config(format!("{}: {why}", path.display()));
+            let lookup = if path.components().count() == 1 {
+                Path::new(".").join(path)
+            } else {
+                path.to_path_buf()
+            };
+            let file = CString::new(lookup.as_os_str().as_bytes())
+                .map_err(|_| invalid("NUL byte in path".to_string()))?;
+            // SAFETY: loading runs the library's initializers; loading a
+            // plugin means trusting it.
+            let handle = unsafe { dlopen(file.as_ptr(), RTLD_NOW) };
+            if handle.is_null() {
+                // The message names the file.
+                return Err(Td5Error::config(last_error()));
+            }
+            let mut plugin = Plugin {
+                handle,
+                raw: std::ptr::null(),
+                name: String::new(),
+                metrics: Vec::new(),
+                path: path.to_path_buf(),
+            };
+            // SAFETY: `handle` is open; the symbol is the ABI's entry point.
+            let entry = unsafe { dlsym(handle, c"td5_plugin".as_ptr()) };
+            if entry.is_null() {
+                return Err(invalid("no `td5_plugin` function".to_string()));
+            }
+            // SAFETY: the ABI declares `td5_plugin` as
+            // `const struct td5_plugin *(void)`.
+            let raw = unsafe {
+                let entry: unsafe extern "C" fn() -> *const RawPlugin = std::mem::transmute(entry);
+                entry()
+            };
+            // SAFETY: a non-null result points to a static `td5_plugin`.
+            let Some(table) = (unsafe { raw.as_ref() }) else {
+                return Err(invalid("`td5_plugin` returned NULL".to_string()));
+            };
+            if table.abi != ABI {
+                return Err(invalid(format!(
+                    "plugin ABI {} (this td5 supports {ABI})",
+                    table.abi
+                )));
+            }
+            let string = |ptr: *const c_char| {
+                // SAFETY: the ABI's strings are NUL-terminated and static.
+                (!ptr.is_null()).then(|| {
+                    unsafe { CStr::from_ptr(ptr) }
+                        .to_string_lossy()
+                        .into_owned()
+                })
+            };
+            plugin.raw = raw;
+            plugin.name = string(table.name).ok_or_else(|| invalid("no name".to_string()))?;
+            if !table.metrics.is_null() {
+                for i in 0.. {
+                    // SAFETY: `metrics` is a NULL-terminated array.
+                    let Some(name) = string(unsafe { *table.metrics.add(i) }) else {
+                        break;
+                    };
+                    plugin.metrics.push(name);
+                }
+            }
+            if !plugin.metrics.is_empty() && table.measure.is_none() {
+                return Err(invalid("metrics without a `measure` function".to_string()));
+            }
+            Ok(plugin)
+        }
+
+        pub fn path(&self) -> &Path {
+            &self.path
+        }
+
+        fn table(&self) -> &RawPlugin {
+            // SAFETY: checked non-null in `load`; static in the library,
+            // which stays open as long as `self`.
+            unsafe { &*self.raw }
+        }
+    }
+
+    impl Extension for Plugin {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn filters_words(&self) -> bool {
+            self.table().keep_word.is_some()
+        }
+
+        fn keep_word(&self, word: &str) -> bool {
+            match self.table().keep_word {
+                // SAFETY: `word` is valid for `len` bytes during the call.
+                Some(keep) => unsafe { keep(word.as_ptr().cast(), word.len()) != 0 },
+                None => true,
+            }
+        }
+
+        fn metrics(&self, text: &str) -> Vec<(String, f64)> {
+            let Some(measure) = self.table().measure else {
+                return Vec::new();
+            };
+            if self.metrics.is_empty() {
+                return Vec::new();
+            }
+            let mut values = vec![0.0; self.metrics.len()];
+            // SAFETY: `text` is valid for `len` bytes and `values` has a
+            // slot for each metric the plugin declared.
+            unsafe { measure(text.as_ptr().cast(), text.len(), values.as_mut_ptr()) };
+            self.metrics.iter().cloned().zip(values).collect()
+        }
+    }
+
+    impl Drop for Plugin {
+        fn drop(&mut self) {
+            // SAFETY: `handle` comes from a successful `dlopen`.
+            unsafe { dlclose(self.handle) };
+        }
+    }
+}
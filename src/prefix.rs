@@ -0,0 +1,231 @@
+//! The vocabulary as a prefix automaton with counts, for autocomplete.
+//!
+//! Words are inserted byte by byte (UTF-8) into a trie, then identical
+//! subtrees are merged, which shares common suffixes the way an FST does.
+//! Each state stores the count of the word ending there (0 if none) and the
+//! largest count reachable from it, so the most frequent completions of a
+//! prefix are found best-first without visiting the whole subtree.
+//!
+//! Binary layout (integers are unsigned LEB128 varints):
+//!
+//! ```text
+//! b"TD5P" version:u8 states
+//! per state: count best edges (label:u8 target)*
+//! ```
+//!
+//! A state only points to states written before it, edges are sorted by
+//! label and the last state is the root.
+
+use crate::error::{Result, Td5Error};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+const MAGIC: &[u8; 4] = b"TD5P";
+const VERSION: u8 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct State {
+    count: usize,
+    best: usize,
+    edges: Vec<(u8, u32)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrefixTree {
+    /// Children before parents; the root is last.
+    states: Vec<State>,
+}
+
+impl PrefixTree {
+    /// Builds the automaton of `words`. A word given twice keeps the sum of
+    /// its counts; words with a count of 0 are left out.
+    pub fn build<'a>(words: impl IntoIterator<Item = (&'a str, usize)>) -> PrefixTree {
+        // Trie nodes: count and children. Children are always created after
+        // their parent, so walking the indices backwards visits them first.
+        let mut trie: Vec<(usize, Vec<(u8, usize)>)> = vec![(0, Vec::new())];
+        for (word, count) in words {
+            if count == 0 {
+                continue;
+            }
+            let mut node = 0;
+            for &byte in word.as_bytes() {
+                node = match trie[node].1.iter().find(|&&(b, _)| b == byte) {
+                    Some(&(_, child)) => child,
+                    None => {
+                        trie.push((0, Vec::new()));
+                        let child = trie.len() - 1;
+                        trie[node].1.push((byte, child));
+                        child
+                    }
+                };
+            }
+            trie[node].0 += count;
+        }
+
+        let mut states: Vec<State> = Vec::new();
+        let mut ids: HashMap<State, u32> = HashMap::new();
+        let mut state_of = vec![0u32; trie.len()];
+        for node in (0..trie.len()).rev() {
+            let (count, children) = &mut trie[node];
+            children.sort_unstable_by_key(|&(b, _)| b);
+            let edges: Vec<(u8, u32)> = children
+                .iter()
+                .map(|&(b, child)| (b, state_of[child]))
+                .collect();
+            let best = edges
+                .iter()
+                .map(|&(_, s)| states[s as usize].best)
+                .fold(*count, usize::max);
+            let state = State {
+                count: *count,
+                best,
+                edges,
+            };
+            state_of[node] = match ids.get(&state) {
+                Some(&id) => id,
+                None => {
+                    let id = states.len() as u32;
+                    ids.insert(state.clone(), id);
+                    states.push(state);
+                    id
+                }
+            };
+        }
+        // No state below the root can equal it, so it was added last.
+        PrefixTree { states }
+    }
+
+    /// Number of states after sharing.
+    pub fn states(&self) -> usize {
+        self.states.len()
+    }
+
+    /// The state reached by reading `prefix` from the root.
+    fn walk(&self, prefix: &str) -> Option<usize> {
+        let mut id = self.states.len() - 1;
+        for byte in prefix.bytes() {
+            let edges = &self.states[id].edges;
+            let at = edges.binary_search_by_key(&byte, |&(b, _)| b).ok()?;
+            id = edges[at].1 as usize;
+        }
+        Some(id)
+    }
+
+    /// The count of `word`, 0 if absent.
+    pub fn count(&self, word: &str) -> usize {
+        self.walk(word).map_or(0, |id| self.states[id].count)
+    }
+
+    /// Up to `k` words starting with `prefix`, most frequent first, ties
+    /// broken alphabetically.
+    pub fn complete(&self, prefix: &str, k: usize) -> Vec<(String, usize)> {
+        let mut out = Vec::with_capacity(k.min(64));
+        let Some(start) = self.walk(prefix) else {
+            return out;
+        };
+        // Entries are (bound, path, is_word, state): a word is emitted once
+        // its exact count is the largest bound left. A state's path sorts
+        // before the words under it, which keeps ties alphabetical.
+        let mut queue = BinaryHeap::new();
+        let best = self.states[start].best;
+        queue.push((best, Reverse(prefix.as_bytes().to_vec()), false, start));
+        while let Some((bound, Reverse(path), is_word, id)) = queue.pop() {
+            if out.len() == k || bound == 0 {
+                break;
+            }
+            if is_word {
+                out.push((String::from_utf8_lossy(&path).into_owned(), bound));
+                continue;
+            }
+            let state = &self.states[id];
+            if state.count > 0 {
+                queue.push((state.count, Reverse(path.clone()), true, id));
+            }
+            for &(byte, target) in &state.edges {
+                let mut next = path.clone();
+                next.push(byte);
+                let best = self.states[target as usize].best;
+                queue.push((best, Reverse(next), false, target as usize));
+            }
+        }
+        out
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.states.len() * 8);
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        put_varint(&mut out, self.states.len());
+        for state in &self.states {
+            put_varint(&mut out, state.count);
+            put_varint(&mut out, state.best);
+            put_varint(&mut out, state.edges.len());
+            for &(byte, target) in &state.edges {
+                out.push(byte);
+                put_varint(&mut out, target as usize);
+            }
+        }
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<PrefixTree> {
+        let invalid = |what: &str| Td5Error::config(format!("prefix tree: {what}"));
+        let rest = bytes
+            .strip_prefix(MAGIC)
+            .ok_or_else(|| invalid("not a td5 prefix tree"))?;
+        let (&version, mut rest) = rest.split_first().ok_or_else(|| invalid("truncated"))?;
+        if version != VERSION {
+            return Err(invalid(&format!("unsupported version {version}")));
+        }
+        let truncated = || invalid("truncated");
+        let count = take_varint(&mut rest).ok_or_else(truncated)?;
+        let mut states = Vec::with_capacity(count.min(bytes.len()));
+        for id in 0..count {
+            let mut next = || take_varint(&mut rest).ok_or_else(truncated);
+            let (count, best, edges) = (next()?, next()?, next()?);
+            let mut state = State {
+                count,
+                best,
+                edges: Vec::with_capacity(edges.min(256)),
+            };
+            for _ in 0..edges {
+                let (&byte, after) = rest.split_first().ok_or_else(truncated)?;
+                rest = after;
+                let target = take_varint(&mut rest).ok_or_else(truncated)?;
+                if target >= id {
+                    return Err(invalid("edge to a later state"));
+                }
+                if state.edges.last().is_some_and(|&(b, _)| b >= byte) {
+                    return Err(invalid("unsorted edges"));
+                }
+                state.edges.push((byte, target as u32));
+            }
+            states.push(state);
+        }
+        if states.is_empty() {
+            return Err(invalid("no root"));
+        }
+        Ok(PrefixTree { states })
+    }
+}
+
+fn put_varint(out: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn take_varint(bytes: &mut &[u8]) -> Option<usize> {
+    let mut value = 0usize;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes.split_first()?;
+        *bytes = rest;
+        value |= usize::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
@@ -0,0 +1,988 @@
+//! Text output of the `td5` binary: the stats of an input and the reports
+//! beside them, as `td5 analyze` and the other commands print them without
+//! `--format`. Labels go through `i18n`; the JSON forms are the library's.
+
+use crate::i18n::{decimal, number};
+use std::io::{self, Write};
+use td5::TextStats;
+use td5::acronyms::Acronyms;
+use td5::approx::ApproxCounter;
+use td5::bench::BenchReport;
+use td5::categories::{Categories, Category};
+use td5::chat::SpeakerStats;
+use td5::check::Metric;
+use td5::diff::StatsDiff;
+use td5::dispersion::Dispersion;
+use td5::extras::Extras;
+use td5::freq::Distribution;
+use td5::input::BufferReport;
+use td5::keywords::CodeStats;
+use td5::overlap::Overlap;
+use td5::parallel::PhaseTimings;
+use td5::pii::{PiiKind, PiiStats};
+use td5::quotes::DialogueStats;
+use td5::sample::Estimate;
+use td5::sections::Section;
+use td5::social::SocialStats;
+use td5::stability::Stability;
+use td5::subtitles::SpeechRate;
+use td5::units::Quantities;
+use td5::watch::Delta;
+use td5::{PathStats, Timings};
+
+/// `stats` under `label`, with the time they took when it was measured.
+pub fn print_stats(
+    out: &mut impl Write,
+    label: &str,
+    stats: &TextStats,
+    time_ns: Option<u128>,
+) -> io::Result<()> {
+    writeln!(out, "{label}:")?;
+    writeln!(
+        out,
+        "  {} {}",
+        tr!("Unique words:"),
+        number(stats.word_count)
+    )?;
+    writeln!(
+        out,
+        "  {} {}",
+        tr!("Total words:"),
+        number(stats.total_words)
+    )?;
+    writeln!(
+        out,
+        "  {} {}",
+        tr!("Total chars:"),
+        number(stats.char_count)
+    )?;
+    // Unknown for stats built from word counts alone.
+    if let Some(lines) = stats.line_count {
+        writeln!(out, "  {} {}", tr!("Lines:"), number(lines))?;
+    }
+    if let Some(sentences) = stats.sentence_count {
+        writeln!(
+            out,
+            "  {} {}",
+            tr!("Sentences (approx.):"),
+            number(sentences)
+        )?;
+    }
+    writeln!(
+        out,
+        "  {} {} / {}",
+        tr!("Word length (mean / median):"),
+        decimal(stats.mean_word_length, 2),
+        stats.median_word_length
+    )?;
+    print_lengths(out, &stats.word_lengths)?;
+    if let Some(language) = &stats.detected_language {
+        writeln!(out, "  {} {language}", tr!("Language:"))?;
+    }
+    writeln!(out, "  {} {:?}", tr!("Top 10 words:"), stats.top_words)?;
+    if !stats.surface_forms.is_empty() {
+        writeln!(out, "  {} {:?}", tr!("Surface forms:"), stats.surface_forms)?;
+    }
+    writeln!(out, "  {} {:?}", tr!("Longest words:"), stats.longest_words)?;
+    if !stats.top_bigrams.is_empty() {
+        writeln!(out, "  {} {:?}", tr!("Top bigrams:"), stats.top_bigrams)?;
+    }
+    if !stats.top_trigrams.is_empty() {
+        writeln!(out, "  {} {:?}", tr!("Top trigrams:"), stats.top_trigrams)?;
+    }
+    let diagnostics: Vec<String> = stats
+        .diagnostics
+        .counters()
+        .filter(|&(_, n)| n > 0)
+        .map(|(name, n)| format!("{name}={n}"))
+        .collect();
+    if !diagnostics.is_empty() {
+        writeln!(out, "  {} {}", tr!("Diagnostics:"), diagnostics.join(", "))?;
+    }
+    if let Some(time_ns) = time_ns {
+        writeln!(
+            out,
+            "  {} {} ms ({} ns)",
+            tr!("Time:"),
+            decimal(time_ns as f64 / 1_000_000.0, 3),
+            number(time_ns)
+        )?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+/// One bar per word length, the longest scaled to 40 columns.
+fn print_lengths(out: &mut impl Write, lengths: &[usize]) -> io::Result<()> {
+    let Some(&most) = lengths.iter().max().filter(|&&n| n > 0) else {
+        return Ok(());
+    };
+    writeln!(out, "  {}", tr!("Word lengths:"))?;
+    for (i, &n) in lengths.iter().enumerate() {
+        let bar = "#".repeat((n * 40).div_ceil(most));
+        // The 32nd slot also counts longer words.
+        let length = if i == 31 {
+            "32+".to_string()
+        } else {
+            (i + 1).to_string()
+        };
+        writeln!(out, "    {length:>3} {bar} {}", number(n))?;
+    }
+    Ok(())
+}
+
+pub fn print_phases(out: &mut impl Write, timings: &PhaseTimings) -> io::Result<()> {
+    let ms = |ns: u128| decimal(ns as f64 / 1_000_000.0, 3);
+    writeln!(
+        out,
+        "  {}",
+        tr!("Phases ({threads} threads):", threads = timings.threads)
+    )?;
+    writeln!(out, "    split:  {} ms", ms(timings.split_ns))?;
+    writeln!(out, "    count:  {} ms", ms(timings.count_ns))?;
+    writeln!(
+        out,
+        "    merge:  {} ms ({})",
+        ms(timings.merge_ns),
+        tr!("{rounds} rounds", rounds = timings.merge_rounds)
+    )?;
+    writeln!(out, "    finish: {} ms\n", ms(timings.finish_ns))?;
+    Ok(())
+}
+
+pub fn print_timings(out: &mut impl Write, timings: &Timings) -> io::Result<()> {
+    let ms = |ns: u128| decimal(ns as f64 / 1_000_000.0, 3);
+    let rest = timings.total_ns.saturating_sub(
+        timings.tokenize_ns + timings.count_ns + timings.top_k_ns + timings.longest_ns,
+    );
+    writeln!(out, "  {}", tr!("Phases:"))?;
+    writeln!(out, "    tokenize: {} ms", ms(timings.tokenize_ns))?;
+    writeln!(out, "    count:    {} ms", ms(timings.count_ns))?;
+    writeln!(out, "    top-k:    {} ms", ms(timings.top_k_ns))?;
+    writeln!(out, "    longest:  {} ms", ms(timings.longest_ns))?;
+    writeln!(out, "    other:    {} ms\n", ms(rest))?;
+    Ok(())
+}
+
+pub fn print_against(
+    out: &mut impl Write,
+    title: &str,
+    report: &BenchReport,
+    baseline: &BenchReport,
+) -> io::Result<()> {
+    let diff = report.against(baseline);
+    for d in diff.environment_differences() {
+        warn!(
+            "{}",
+            tr!(
+                "baseline was recorded in a different environment: {difference}",
+                difference = d
+            )
+        );
+    }
+    writeln!(out, "\n{title}")?;
+    write!(out, "{diff}")?;
+    Ok(())
+}
+
+/// `seed 7, seed version 1`, for reports to echo the seed they ran with.
+pub fn seed_label(seed: u64) -> String {
+    tr!(
+        "seed {seed}, seed version {version}",
+        seed = seed,
+        version = td5::seed::SEED_VERSION
+    )
+}
+
+fn print_estimate(out: &mut impl Write, path: &str, estimate: &Estimate) -> io::Result<()> {
+    writeln!(
+        out,
+        "{}",
+        tr!(
+            "{path}: estimated from a {share}% sample ({read} of {total} bytes read, {seed})",
+            path = path,
+            share = decimal(estimate.fraction * 100.0, 1),
+            read = number(estimate.bytes_read),
+            total = number(estimate.bytes_total),
+            seed = seed_label(estimate.seed)
+        )
+    )?;
+    writeln!(
+        out,
+        "  {}\n",
+        tr!(
+            "counts scaled by {scale}; unique words are those seen in the sample",
+            scale = decimal(estimate.scale(), 2)
+        )
+    )?;
+    Ok(())
+}
+
+/// The parts of `extras` found, after the stats of `label`.
+pub fn print_extras(out: &mut impl Write, label: &str, extras: &Extras) -> io::Result<()> {
+    if let Some(estimate) = &extras.estimate {
+        print_estimate(out, label, estimate)?;
+    }
+    if let Some(pii) = &extras.pii {
+        print_pii(out, label, pii)?;
+    }
+    if let Some(social) = &extras.social {
+        print_social(out, label, social)?;
+    }
+    if let Some(distribution) = &extras.distribution {
+        print_distribution(out, label, distribution)?;
+    }
+    if let Some(dispersion) = &extras.dispersion {
+        print_dispersion(out, label, dispersion)?;
+    }
+    if let Some(categories) = &extras.categories {
+        print_categories(out, label, categories)?;
+    }
+    if let Some(dialogue) = &extras.dialogue {
+        print_dialogue(out, label, dialogue)?;
+    }
+    if let Some(acronyms) = &extras.acronyms {
+        print_acronyms(out, label, acronyms)?;
+    }
+    if let Some(code) = &extras.code {
+        print_code(out, label, code)?;
+    }
+    if let Some(quantities) = &extras.quantities {
+        print_quantities(out, label, quantities)?;
+    }
+    if let Some(paths) = &extras.paths {
+        print_paths(out, label, paths)?;
+    }
+    if let Some(phases) = &extras.phases {
+        print_timings(out, phases)?;
+    }
+    if let Some(buffer) = &extras.buffer {
+        print_buffer(out, label, buffer)?;
+    }
+    if !extras.plugins.is_empty() {
+        print_plugin_metrics(out, label, &extras.plugins)?;
+    }
+    if let Some(speech) = &extras.speech {
+        print_speech(out, label, speech)?;
+    }
+    if let Some(speakers) = &extras.speakers {
+        print_speakers(out, label, speakers, extras.mail)?;
+    }
+    Ok(())
+}
+
+/// One row per section; the unique/total ratio falls as sections get longer,
+/// so compare sections of similar length.
+pub fn print_sections(
+    out: &mut impl Write,
+    label: &str,
+    sections: &[(Section, TextStats)],
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "{}",
+        tr!(
+            "{path}: {count} sections",
+            path = label,
+            count = sections.len()
+        )
+    )?;
+    writeln!(
+        out,
+        "  {:>6} {:>8} {:>8} {:>6}  {}",
+        tr!("line"),
+        tr!("words"),
+        tr!("unique"),
+        tr!("ratio"),
+        tr!("section")
+    )?;
+    for (section, stats) in sections {
+        let ratio = stats.word_count as f64 / stats.total_words.max(1) as f64;
+        writeln!(
+            out,
+            "  {:>6} {:>8} {:>8} {:>6}  {}",
+            number(section.line),
+            number(stats.total_words),
+            number(stats.word_count),
+            decimal(ratio, 3),
+            section
+                .title
+                .as_deref()
+                .unwrap_or(tr!("(before the first section)"))
+        )?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+fn print_pii(out: &mut impl Write, label: &str, pii: &PiiStats) -> io::Result<()> {
+    writeln!(out, "{}", tr!("{path}: personal data", path = label))?;
+    for kind in PiiKind::ALL {
+        let samples: Vec<&str> = pii
+            .samples
+            .iter()
+            .filter(|(k, _)| *k == kind)
+            .map(|(_, s)| s.as_str())
+            .collect();
+        writeln!(
+            out,
+            "  {:<7} {:>6}  {}",
+            kind.name(),
+            number(pii.count(kind)),
+            samples.join(", ")
+        )?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+fn print_social(out: &mut impl Write, label: &str, social: &SocialStats) -> io::Result<()> {
+    writeln!(
+        out,
+        "{}",
+        tr!("{path}: hashtags, mentions and URLs", path = label)
+    )?;
+    for (name, total, top) in [
+        (tr!("Hashtags:"), social.total_hashtags, &social.hashtags),
+        (tr!("Mentions:"), social.total_mentions, &social.mentions),
+        (tr!("URLs:"), social.total_urls, &social.urls),
+    ] {
+        writeln!(out, "  {name} {} {top:?}", number(total))?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+fn print_distribution(
+    out: &mut impl Write,
+    label: &str,
+    distribution: &Distribution,
+) -> io::Result<()> {
+    writeln!(out, "{}", tr!("{path}: count distribution", path = label))?;
+    for band in &distribution.bands {
+        let range = match band.max {
+            Some(max) if max == band.min => number(max),
+            Some(max) => format!("{}-{}", number(band.min), number(max)),
+            None => format!("{}+", number(band.min)),
+        };
+        writeln!(
+            out,
+            "  {range:>9} {:>8} {}  {:>9} {}",
+            number(band.words),
+            tr!("words"),
+            number(band.tokens),
+            tr!("tokens")
+        )?;
+    }
+    let quantiles: Vec<String> = distribution
+        .quantiles
+        .iter()
+        .map(|&(q, count)| format!("p{}={}", (q * 100.0).round(), number(count)))
+        .collect();
+    writeln!(out, "  {} {}\n", tr!("Quantiles:"), quantiles.join(" "))?;
+    Ok(())
+}
+
+fn print_categories(out: &mut impl Write, label: &str, categories: &Categories) -> io::Result<()> {
+    writeln!(
+        out,
+        "{}",
+        tr!(
+            "{path}: probable nouns, verbs and adjectives (from suffixes)",
+            path = label
+        )
+    )?;
+    for category in Category::ALL {
+        let label = match category {
+            Category::Noun => tr!("Nouns:"),
+            Category::Verb => tr!("Verbs:"),
+            Category::Adjective => tr!("Adjectives:"),
+        };
+        writeln!(out, "  {label:<12} {:?}", categories.list(category))?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+fn print_acronyms(out: &mut impl Write, label: &str, acronyms: &Acronyms) -> io::Result<()> {
+    writeln!(
+        out,
+        "{}",
+        tr!(
+            "{path}: {total} acronyms and all-caps terms, {distinct} distinct",
+            path = label,
+            total = number(acronyms.total),
+            distinct = number(acronyms.terms.len())
+        )
+    )?;
+    for (term, count) in &acronyms.terms {
+        writeln!(out, "  {:>8}  {term}", number(count))?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+fn print_buffer(out: &mut impl Write, label: &str, buffer: &BufferReport) -> io::Result<()> {
+    writeln!(out, "{}", tr!("{path}: read buffer", path = label))?;
+    writeln!(
+        out,
+        "  {}",
+        tr!(
+            "{bytes} bytes in {reads} reads",
+            bytes = number(buffer.bytes),
+            reads = number(buffer.reads)
+        )
+    )?;
+    writeln!(
+        out,
+        "  {}",
+        tr!(
+            "{first} bytes at first, {last} at the end ({smallest} to {largest}, {changes} changes)",
+            first = number(buffer.first),
+            last = number(buffer.last),
+            smallest = number(buffer.smallest),
+            largest = number(buffer.largest),
+            changes = number(buffer.changes)
+        )
+    )?;
+    writeln!(out)?;
+    Ok(())
+}
+
+fn print_paths(out: &mut impl Write, label: &str, paths: &PathStats) -> io::Result<()> {
+    writeln!(
+        out,
+        "{}",
+        tr!("{path}: paths of the fast analyzer", path = label)
+    )?;
+    writeln!(out, "  simple-lower: {}", number(paths.simple_lower))?;
+    writeln!(out, "  ascii:        {}", number(paths.ascii))?;
+    writeln!(out, "  unicode:      {}", number(paths.unicode))?;
+    writeln!(out, "  other:        {}", number(paths.other))?;
+    if paths.simple_fallbacks > 0 {
+        writeln!(
+            out,
+            "  {}",
+            tr!(
+                "{count} simple-path passes counted again as ASCII",
+                count = number(paths.simple_fallbacks)
+            )
+        )?;
+    }
+    writeln!(
+        out,
+        "  {}",
+        tr!(
+            "{hot} tokens in the hot array ({share}%), {map} in the hash map",
+            hot = number(paths.hot_tokens),
+            share = decimal(100.0 * paths.hot_share(), 1),
+            map = number(paths.map_tokens)
+        )
+    )?;
+    if paths.hot_only {
+        writeln!(
+            out,
+            "  {}",
+            tr!("finished from the hot array alone, without a sort")
+        )?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+fn print_code(out: &mut impl Write, label: &str, code: &CodeStats) -> io::Result<()> {
+    writeln!(
+        out,
+        "{}",
+        tr!(
+            "{path}: {keywords} {language} keywords ({share}% of {tokens} tokens), {distinct} distinct identifiers",
+            path = label,
+            keywords = number(code.keyword_total),
+            language = code.language.name(),
+            share = decimal(100.0 * code.keyword_share(), 1),
+            tokens = number(code.keyword_total + code.identifier_total),
+            distinct = number(code.identifiers.len())
+        )
+    )?;
+    for (keyword, count) in &code.keywords {
+        writeln!(out, "  {:>8}  {keyword}", number(count))?;
+    }
+    if !code.identifiers.is_empty() {
+        let top: Vec<String> = code
+            .identifiers
+            .iter()
+            .take(10)
+            .map(|(w, n)| format!("{w} {n}"))
+            .collect();
+        writeln!(out, "  {} {}", tr!("Top identifiers:"), top.join(", "))?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+fn print_plugin_metrics(
+    out: &mut impl Write,
+    label: &str,
+    metrics: &[(String, f64)],
+) -> io::Result<()> {
+    writeln!(out, "{}", tr!("{path}: plugin metrics", path = label))?;
+    for (name, value) in metrics {
+        writeln!(out, "  {name:<32} {}", decimal(*value, 3))?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+fn print_quantities(out: &mut impl Write, label: &str, quantities: &Quantities) -> io::Result<()> {
+    writeln!(
+        out,
+        "{}",
+        tr!(
+            "{path}: numbers with units ({bare} without)",
+            path = label,
+            bare = number(quantities.bare)
+        )
+    )?;
+    writeln!(
+        out,
+        "  {:<8} {:>8} {:>12} {:>12} {:>12}",
+        tr!("Unit"),
+        tr!("Count"),
+        "min",
+        tr!("mean"),
+        "max"
+    )?;
+    for (unit, stats) in &quantities.units {
+        writeln!(
+            out,
+            "  {unit:<8} {:>8} {:>12} {:>12} {:>12}",
+            number(stats.count),
+            decimal(stats.min, 2),
+            decimal(stats.mean(), 2),
+            decimal(stats.max, 2)
+        )?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+fn print_dialogue(out: &mut impl Write, label: &str, dialogue: &DialogueStats) -> io::Result<()> {
+    writeln!(
+        out,
+        "{}",
+        tr!(
+            "{path}: {share}% of the text in {quotes} quotes",
+            path = label,
+            share = decimal(100.0 * dialogue.quoted_share(), 1),
+            quotes = number(dialogue.quotes)
+        )
+    )?;
+    for (name, words, top) in [
+        (
+            tr!("Dialogue:"),
+            dialogue.dialogue_words,
+            &dialogue.dialogue_top,
+        ),
+        (
+            tr!("Narration:"),
+            dialogue.narration_words,
+            &dialogue.narration_top,
+        ),
+    ] {
+        let top: Vec<String> = top.iter().map(|(w, n)| format!("{w} {n}")).collect();
+        writeln!(
+            out,
+            "  {name} {} {}, {}",
+            number(words),
+            tr!("words"),
+            top.join(", ")
+        )?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+fn print_dispersion(out: &mut impl Write, label: &str, dispersion: &Dispersion) -> io::Result<()> {
+    writeln!(
+        out,
+        "{}",
+        tr!(
+            "{path}: dispersion over {parts} parts of {tokens} tokens",
+            path = label,
+            parts = td5::dispersion::PARTS,
+            tokens = number(dispersion.tokens)
+        )
+    )?;
+    writeln!(
+        out,
+        "  {:<20} {:>8} {:>6} {:>6}  {}",
+        tr!("Word"),
+        tr!("Count"),
+        "D",
+        tr!("Burst"),
+        tr!("Busiest part")
+    )?;
+    for w in &dispersion.words {
+        writeln!(
+            out,
+            "  {:<20} {:>8} {:>6} {:>6}  {} ({}%)",
+            w.word,
+            number(w.count),
+            decimal(w.dispersion, 2),
+            decimal(w.burstiness, 2),
+            w.peak_part + 1,
+            decimal(100.0 * w.peak, 0)
+        )?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+fn print_speech(out: &mut impl Write, label: &str, speech: &SpeechRate) -> io::Result<()> {
+    let minutes = |ms: u64| decimal(ms as f64 / 60_000.0, 1);
+    writeln!(out, "{}", tr!("{path}: speech rate", path = label))?;
+    writeln!(
+        out,
+        "  {}",
+        tr!(
+            "{wpm} words per minute over {minutes} min",
+            wpm = decimal(speech.overall_wpm, 0),
+            minutes = minutes(speech.span_ms)
+        )
+    )?;
+    writeln!(
+        out,
+        "  {}",
+        tr!(
+            "{wpm} words per minute while speaking ({minutes} min)",
+            wpm = decimal(speech.speaking_wpm, 0),
+            minutes = minutes(speech.speaking_ms)
+        )
+    )?;
+    writeln!(
+        out,
+        "  {}",
+        tr!(
+            "{wpm} words in the busiest minute",
+            wpm = number(speech.peak_wpm)
+        )
+    )?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// `mail` names the speakers senders, as of a mailbox.
+fn print_speakers(
+    out: &mut impl Write,
+    label: &str,
+    speakers: &[SpeakerStats],
+    mail: bool,
+) -> io::Result<()> {
+    let messages = number(speakers.iter().map(|s| s.messages).sum::<usize>());
+    let count = number(speakers.len());
+    if mail {
+        writeln!(
+            out,
+            "{}",
+            tr!(
+                "{path}: {messages} messages from {senders} senders",
+                path = label,
+                messages = messages,
+                senders = count
+            )
+        )?;
+    } else {
+        writeln!(
+            out,
+            "{}",
+            tr!(
+                "{path}: {messages} messages from {speakers} speakers",
+                path = label,
+                messages = messages,
+                speakers = count
+            )
+        )?;
+    }
+    let width = speakers
+        .iter()
+        .map(|s| s.speaker.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(7);
+    writeln!(
+        out,
+        "  {:<width$} {:>8} {:>9} {:>9} {:>9}  {}",
+        if mail { tr!("Sender") } else { tr!("Speaker") },
+        tr!("Messages"),
+        tr!("Words"),
+        tr!("Words/msg"),
+        tr!("Longest"),
+        tr!("Top words:").trim_end_matches([':', ' '])
+    )?;
+    for speaker in speakers {
+        let top: Vec<String> = speaker
+            .stats
+            .top_words
+            .iter()
+            .take(5)
+            .map(|(w, n)| format!("{w} {n}"))
+            .collect();
+        writeln!(
+            out,
+            "  {:<width$} {:>8} {:>9} {:>9} {:>9}  {}",
+            speaker.speaker,
+            number(speaker.messages),
+            number(speaker.stats.total_words),
+            decimal(speaker.lengths.mean, 1),
+            number(speaker.lengths.max),
+            top.join(", ")
+        )?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+pub fn print_approx(out: &mut impl Write, counter: &ApproxCounter, top: usize) -> io::Result<()> {
+    writeln!(
+        out,
+        "{}",
+        tr!(
+            "{total} words, in {memory} KiB:",
+            total = number(counter.total()),
+            memory = number(counter.memory_bytes() / 1024)
+        )
+    )?;
+    let words = counter.top(top);
+    let width = words
+        .iter()
+        .map(|w| w.word.chars().count())
+        .max()
+        .unwrap_or(0);
+    for word in &words {
+        writeln!(
+            out,
+            "  {:<width$} {:>12}  {}",
+            word.word,
+            number(word.count),
+            tr!("(at least {min})", min = number(word.min_count))
+        )?;
+    }
+    writeln!(
+        out,
+        "{}",
+        tr!(
+            "Counts are at most {bound} too high with probability {confidence}.",
+            bound = number(counter.error_bound()),
+            confidence = decimal(1.0 - counter.delta(), 2)
+        )
+    )?;
+    Ok(())
+}
+
+/// A `watch` delta on one line, plus one for the top words when they moved.
+pub fn print_delta(out: &mut impl Write, path: &str, delta: &Delta) -> io::Result<()> {
+    let change = |(a, b): (usize, usize)| {
+        let sign = if b >= a { "+" } else { "-" };
+        format!("{sign}{}", number(a.abs_diff(b)))
+    };
+    writeln!(
+        out,
+        "{path}: {}",
+        tr!(
+            "{total} words ({total_change}), {unique} distinct ({unique_change})",
+            total = number(delta.total_words.1),
+            total_change = change(delta.total_words),
+            unique = number(delta.word_count.1),
+            unique_change = change(delta.word_count)
+        )
+    )?;
+    let moves: Vec<String> = delta
+        .entered
+        .iter()
+        .map(|(word, n)| format!("+{word} {}", number(n)))
+        .chain(delta.left.iter().map(|word| format!("-{word}")))
+        .chain(
+            delta
+                .changed
+                .iter()
+                .map(|(word, a, b)| format!("{word} {} → {}", number(a), number(b))),
+        )
+        .collect();
+    if !moves.is_empty() {
+        writeln!(out, "  {} {}", tr!("Top words:"), moves.join(", "))?;
+    }
+    Ok(())
+}
+
+/// The agreement of the top-word lists of `a` and `b`, the analyzer specs
+/// compared, each sampled side first with its share and seed.
+pub fn print_stability(
+    out: &mut impl Write,
+    sides: [(&str, Option<&Estimate>); 2],
+    top: usize,
+    stability: &Stability,
+) -> io::Result<()> {
+    for (spec, estimate) in sides {
+        if let Some(estimate) = estimate {
+            writeln!(
+                out,
+                "{}",
+                tr!(
+                    "{spec}: {share}% sample, {seed}",
+                    spec = spec,
+                    share = decimal(estimate.fraction * 100.0, 1),
+                    seed = seed_label(estimate.seed)
+                )
+            )?;
+        }
+    }
+    let tau = stability
+        .tau
+        .map_or_else(|| "n/a".to_string(), |t| decimal(t, 3));
+    writeln!(
+        out,
+        "{}",
+        tr!(
+            "Kendall tau-b over the top {top}: {tau} ({shared} words in both lists)",
+            top = top,
+            tau = tau,
+            shared = stability.shared
+        )
+    )?;
+    let width = stability
+        .words
+        .iter()
+        .map(|w| w.word.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(4);
+    let [(a, _), (b, _)] = sides;
+    writeln!(out, "  {:<width$}  {:>12}  {:>12}", tr!("word"), a, b)?;
+    let rank = |r: Option<f64>| r.map_or_else(|| "-".to_string(), |r| r.to_string());
+    for w in &stability.words {
+        writeln!(
+            out,
+            "  {:<width$}  {:>12}  {:>12}",
+            w.word,
+            rank(w.rank_a),
+            rank(w.rank_b)
+        )?;
+    }
+    Ok(())
+}
+
+/// The vocabulary `a` and `b` share and the words only one of them uses.
+pub fn print_overlap(out: &mut impl Write, a: &str, b: &str, overlap: &Overlap) -> io::Result<()> {
+    writeln!(
+        out,
+        "{}",
+        tr!(
+            "Jaccard similarity: {jaccard} ({shared} shared of {a} and {b} distinct words)",
+            jaccard = decimal(overlap.jaccard, 3),
+            shared = number(overlap.shared),
+            a = number(overlap.vocabulary.0),
+            b = number(overlap.vocabulary.1)
+        )
+    )?;
+    let words = |list: &[(&str, usize)]| {
+        let words: Vec<String> = list.iter().map(|(w, n)| format!("{w} {n}")).collect();
+        words.join(", ")
+    };
+    writeln!(
+        out,
+        "\n{}\n  {}",
+        tr!("Only in {path}:", path = a),
+        words(&overlap.only_a)
+    )?;
+    writeln!(
+        out,
+        "\n{}\n  {}",
+        tr!("Only in {path}:", path = b),
+        words(&overlap.only_b)
+    )?;
+    writeln!(out, "\n{}", tr!("Shared top terms:"))?;
+    for (word, in_a, in_b) in &overlap.shared_top {
+        writeln!(out, "  {word:<24} {in_a:>8} {in_b:>8}")?;
+    }
+    Ok(())
+}
+
+/// How `b` differs from `a`: the metrics side by side with the time each
+/// took, then the words.
+pub fn print_diff(
+    out: &mut impl Write,
+    (a, time_a): (&str, u128),
+    (b, time_b): (&str, u128),
+    diff: &StatsDiff,
+) -> io::Result<()> {
+    writeln!(out, "  {:<18} {a:>12} {b:>12} {:>12}", "", tr!("change"))?;
+    for &(metric, in_a, in_b) in &diff.metrics {
+        // Counts as integers, ratios and lengths with decimals.
+        let value = |x: f64| match metric {
+            Metric::TypeTokenRatio
+            | Metric::Top1Share
+            | Metric::Top10Share
+            | Metric::MeanWordLength => decimal(x, 3),
+            _ => number(x),
+        };
+        let sign = if in_b > in_a { "+" } else { "" };
+        let change = format!("{sign}{}", value(in_b - in_a));
+        writeln!(
+            out,
+            "  {:<18} {:>12} {:>12} {change:>12}",
+            metric.name(),
+            value(in_a),
+            value(in_b)
+        )?;
+    }
+    let (time_a, time_b) = (time_a as f64 / 1e6, time_b as f64 / 1e6);
+    let sign = if time_b > time_a { "+" } else { "" };
+    writeln!(
+        out,
+        "  {:<18} {:>12} {:>12} {:>12}",
+        "time_ms",
+        decimal(time_a, 3),
+        decimal(time_b, 3),
+        format!("{sign}{}", decimal(time_b - time_a, 3))
+    )?;
+    writeln!(
+        out,
+        "\n{}",
+        tr!(
+            "Jaccard similarity: {jaccard} ({shared} shared of {a} and {b} distinct words)",
+            jaccard = decimal(diff.jaccard, 3),
+            shared = number(diff.shared),
+            a = number(diff.vocabulary.0),
+            b = number(diff.vocabulary.1)
+        )
+    )?;
+    let words = |list: &[(String, usize)]| {
+        let words: Vec<String> = list.iter().map(|(w, n)| format!("{w} {n}")).collect();
+        words.join(", ")
+    };
+    writeln!(
+        out,
+        "\n{}\n  {}",
+        tr!("Only in {path}:", path = a),
+        words(&diff.only_a)
+    )?;
+    writeln!(
+        out,
+        "\n{}\n  {}",
+        tr!("Only in {path}:", path = b),
+        words(&diff.only_b)
+    )?;
+    writeln!(out, "\n{}", tr!("Largest shifts (per thousand words):"))?;
+    for shift in &diff.shifts {
+        let sign = if shift.change > 0.0 { "+" } else { "" };
+        let change = format!("{sign}{}", decimal(shift.change, 2));
+        writeln!(
+            out,
+            "  {:<20} {change:>9}  {} → {}",
+            shift.word,
+            number(shift.a),
+            number(shift.b)
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,159 @@
+//! Protocol Buffers encoding of `TextStats`, following `proto/td5.proto`,
+//! and decoding of the requests of its `Analyzer` service.
+//!
+//! Hand-written since the messages are small and the crate has no protobuf
+//! dependency; `td5::grpc` returns these bytes as is, and so can any other
+//! gRPC front end built from the same file.
+
+use crate::TextStats;
+use crate::schema::SCHEMA_VERSION;
+
+const VARINT: u8 = 0;
+const FIXED64: u8 = 1;
+const LEN: u8 = 2;
+#[cfg(feature = "grpc")]
+const FIXED32: u8 = 5;
+
+fn varint(out: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        out.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+fn key(out: &mut Vec<u8>, field: u32, wire_type: u8) {
+    varint(out, u64::from(field) << 3 | u64::from(wire_type));
+}
+
+/// proto3 leaves zero scalars out.
+fn uint(out: &mut Vec<u8>, field: u32, n: u64) {
+    if n != 0 {
+        key(out, field, VARINT);
+        varint(out, n);
+    }
+}
+
+/// An `optional` scalar: written whenever known, zero included.
+fn optional_uint(out: &mut Vec<u8>, field: u32, n: Option<usize>) {
+    if let Some(n) = n {
+        key(out, field, VARINT);
+        varint(out, n as u64);
+    }
+}
+
+fn double(out: &mut Vec<u8>, field: u32, x: f64) {
+    if x != 0.0 {
+        key(out, field, FIXED64);
+        out.extend_from_slice(&x.to_le_bytes());
+    }
+}
+
+fn bytes(out: &mut Vec<u8>, field: u32, data: &[u8]) {
+    key(out, field, LEN);
+    varint(out, data.len() as u64);
+    out.extend_from_slice(data);
+}
+
+#[cfg(feature = "grpc")]
+fn read_varint(msg: &[u8], at: &mut usize) -> Result<u64, &'static str> {
+    let mut n = 0u64;
+    for shift in (0..64).step_by(7) {
+        let &byte = msg.get(*at).ok_or("truncated varint")?;
+        *at += 1;
+        n |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+    Err("varint too long")
+}
+
+/// The length-delimited fields of a message, by field number, the others
+/// skipped: `AnalyzeRequest` and `TextChunk` have only strings.
+#[cfg(feature = "grpc")]
+pub(crate) fn len_fields(msg: &[u8]) -> Result<Vec<(u32, &[u8])>, &'static str> {
+    let mut fields = Vec::new();
+    let mut at = 0;
+    while at < msg.len() {
+        let key = read_varint(msg, &mut at)?;
+        let field = u32::try_from(key >> 3).map_err(|_| "field number too large")?;
+        let skip = match key as u8 & 7 {
+            VARINT => {
+                read_varint(msg, &mut at)?;
+                0
+            }
+            FIXED64 => 8,
+            FIXED32 => 4,
+            LEN => {
+                let len = read_varint(msg, &mut at)? as usize;
+                let data = msg
+                    .get(at..at.saturating_add(len))
+                    .ok_or("truncated field")?;
+                fields.push((field, data));
+                len
+            }
+            _ => return Err("unsupported wire type"),
+        };
+        at = at.saturating_add(skip);
+        if at > msg.len() {
+            return Err("truncated field");
+        }
+    }
+    Ok(fields)
+}
+
+/// A `{ string = 1; uint64 = 2; }` message: `WordCount` and map entries.
+fn string_count(word: &str, count: usize) -> Vec<u8> {
+    let mut msg = Vec::new();
+    if !word.is_empty() {
+        bytes(&mut msg, 1, word.as_bytes());
+    }
+    uint(&mut msg, 2, count as u64);
+    msg
+}
+
+impl TextStats {
+    /// The `td5.v1.TextStats` message; `time_ns` is how long the analysis
+    /// took, as the caller measured it.
+    pub fn to_protobuf(&self, time_ns: u128) -> Vec<u8> {
+        let mut out = Vec::new();
+        uint(&mut out, 1, self.word_count as u64);
+        uint(&mut out, 2, self.total_words as u64);
+        uint(&mut out, 3, self.char_count as u64);
+        for (word, count) in &self.top_words {
+            bytes(&mut out, 4, &string_count(word, *count));
+        }
+        for word in &self.longest_words {
+            bytes(&mut out, 5, word.as_bytes());
+        }
+        for (name, n) in self.diagnostics.counters() {
+            bytes(&mut out, 6, &string_count(name, n));
+        }
+        uint(&mut out, 7, time_ns.min(u64::MAX as u128) as u64);
+        uint(&mut out, 8, SCHEMA_VERSION);
+        optional_uint(&mut out, 9, self.line_count);
+        optional_uint(&mut out, 10, self.sentence_count);
+        double(&mut out, 11, self.mean_word_length);
+        uint(&mut out, 12, self.median_word_length as u64);
+        if !self.word_lengths.is_empty() {
+            // Packed, as proto3 writes repeated scalars.
+            let mut packed = Vec::new();
+            for &n in &self.word_lengths {
+                varint(&mut packed, n as u64);
+            }
+            bytes(&mut out, 13, &packed);
+        }
+        out
+    }
+
+    /// `to_protobuf` prefixed with its length as a varint, so several
+    /// messages can be written back to back (`writeDelimitedTo` framing).
+    pub fn to_protobuf_delimited(&self, time_ns: u128) -> Vec<u8> {
+        let msg = self.to_protobuf(time_ns);
+        let mut out = Vec::with_capacity(msg.len() + 5);
+        varint(&mut out, msg.len() as u64);
+        out.extend(msg);
+        out
+    }
+}
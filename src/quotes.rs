@@ -0,0 +1,128 @@
+//! Dialogue and narration told apart by quotation marks, for fiction: the
+//! share of the text inside quotes and the top words of each side.
+//!
+//! A quote opens at any configured opening mark and runs to its closing mark.
+//! Marks inside a quote are part of it (no nesting), and a quote left open
+//! ends with its paragraph, at the next blank line, as in dialogue where each
+//! speech stays on one paragraph.
+
+use crate::error::{Result, Td5Error};
+use crate::freq::WordFrequency;
+use crate::json::Json;
+
+/// Entries kept in each top list.
+const TOP: usize = 10;
+
+/// Opening and closing marks; the same character for straight quotes.
+pub const DEFAULT_QUOTES: [(char, char); 3] = [('"', '"'), ('“', '”'), ('«', '»')];
+
+/// Parses `"“”,«»"`-style specs: comma-separated pairs of an opening and a
+/// closing mark, or one mark used for both.
+pub fn parse_quotes(spec: &str) -> Result<Vec<(char, char)>> {
+    spec.split(',')
+        .map(|pair| {
+            let chars: Vec<char> = pair.trim().chars().collect();
+            match chars[..] {
+                [mark] => Ok((mark, mark)),
+                [open, close] => Ok((open, close)),
+                _ => Err(Td5Error::config(format!(
+                    "invalid quote pair `{pair}` (expected one or two characters, e.g. `«»`)"
+                ))),
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DialogueStats {
+    /// Characters between quotation marks, the marks excluded.
+    pub quoted_chars: usize,
+    /// Characters of the text, whitespace excluded in both counts.
+    pub total_chars: usize,
+    /// Quoted spans found.
+    pub quotes: usize,
+    pub dialogue_words: usize,
+    pub narration_words: usize,
+    /// Most frequent first, at most ten each.
+    pub dialogue_top: Vec<(String, usize)>,
+    pub narration_top: Vec<(String, usize)>,
+}
+
+fn top(freq: &WordFrequency) -> Vec<(String, usize)> {
+    freq.by_count()
+        .take(TOP)
+        .map(|(w, n)| (w.to_string(), n))
+        .collect()
+}
+
+impl DialogueStats {
+    pub fn of(text: &str, quotes: &[(char, char)]) -> DialogueStats {
+        let (mut dialogue, mut narration) = (String::new(), String::new());
+        let mut stats = DialogueStats::default();
+        let mut closing: Option<char> = None;
+        let mut newlines = 0;
+        for c in text.chars() {
+            if c == '\n' {
+                newlines += 1;
+                if newlines >= 2 {
+                    closing = None;
+                }
+            } else if !c.is_whitespace() {
+                newlines = 0;
+            }
+            match closing {
+                Some(close) if c == close => {
+                    closing = None;
+                    // Keeps the last quoted word apart from the next one.
+                    dialogue.push(' ');
+                }
+                Some(_) => {
+                    dialogue.push(c);
+                    stats.quoted_chars += usize::from(!c.is_whitespace());
+                }
+                None => match quotes.iter().find(|&&(open, _)| open == c) {
+                    Some(&(_, close)) => {
+                        closing = Some(close);
+                        stats.quotes += 1;
+                        narration.push(' ');
+                    }
+                    None => narration.push(c),
+                },
+            }
+            stats.total_chars += usize::from(!c.is_whitespace());
+        }
+        let dialogue = WordFrequency::from_text(&dialogue);
+        let narration = WordFrequency::from_text(&narration);
+        stats.dialogue_words = dialogue.total();
+        stats.narration_words = narration.total();
+        stats.dialogue_top = top(&dialogue);
+        stats.narration_top = top(&narration);
+        stats
+    }
+
+    /// Quoted characters over all characters, 0 to 1.
+    pub fn quoted_share(&self) -> f64 {
+        self.quoted_chars as f64 / self.total_chars.max(1) as f64
+    }
+
+    pub fn to_json_value(&self) -> Json {
+        let list = |entries: &[(String, usize)]| {
+            Json::Array(
+                entries
+                    .iter()
+                    .map(|(e, c)| Json::Array(vec![e.as_str().into(), (*c).into()]))
+                    .collect(),
+            )
+        };
+        Json::Object(vec![
+            ("quotes".into(), self.quotes.into()),
+            ("quoted_chars".into(), self.quoted_chars.into()),
+            ("total_chars".into(), self.total_chars.into()),
+            ("quoted_share".into(), self.quoted_share().into()),
+            ("dialogue_words".into(), self.dialogue_words.into()),
+            ("narration_words".into(), self.narration_words.into()),
+            ("dialogue_top".into(), list(&self.dialogue_top)),
+            ("narration_top".into(), list(&self.narration_top)),
+        ])
+    }
+}
@@ -0,0 +1,132 @@
+//! Energy estimates from the RAPL counters Linux exposes under
+//! `/sys/class/powercap` (Intel, and AMD since Zen), for `td5 bench
+//! --energy`.
+//!
+//! Each CPU package has a counter of the microjoules it consumed, which
+//! `Rapl` reads before and after a run. The counters cover the whole package,
+//! other processes and idle cores included, so the figure is an estimate:
+//! compare analyzers within one run on a quiet machine rather than reading
+//! it as the cost of the code alone. Since 2020 most distributions make
+//! `energy_uj` readable by root only.
+
+use crate::error::{Result, Td5Error};
+use std::path::{Path, PathBuf};
+
+/// Where the kernel exposes the counters.
+pub const POWERCAP: &str = "/sys/class/powercap";
+
+/// The energy counters of every CPU package.
+///
+/// ```
+/// use td5::rapl::Rapl;
+///
+/// let root = std::env::temp_dir().join("td5-powercap");
+/// let package = root.join("intel-rapl:0");
+/// // A sub-domain (the cores), already counted in its package.
+/// std::fs::create_dir_all(root.join("intel-rapl:0:0")).unwrap();
+/// std::fs::create_dir_all(&package).unwrap();
+/// std::fs::write(package.join("max_energy_range_uj"), "1000000\n").unwrap();
+/// std::fs::write(package.join("energy_uj"), "999000\n").unwrap();
+///
+/// let rapl = Rapl::open_at(&root).unwrap();
+/// assert_eq!(rapl.packages(), 1);
+/// let ((), used) = rapl
+///     .measure(|| std::fs::write(package.join("energy_uj"), "4000\n").unwrap())
+///     .unwrap();
+/// // The counter wrapped around its range.
+/// assert_eq!(used, 5000);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Rapl {
+    /// Each package's `energy_uj` file and the value it wraps at.
+    packages: Vec<(PathBuf, u64)>,
+}
+
+impl Rapl {
+    /// The counters of this machine, under `POWERCAP`.
+    pub fn open() -> Result<Rapl> {
+        Self::open_at(Path::new(POWERCAP))
+    }
+
+    /// The counters of the package domains (`intel-rapl:N`) under `root`.
+    /// Fails if there are none or they cannot be read.
+    pub fn open_at(root: &Path) -> Result<Rapl> {
+        let io = |path: &Path| {
+            let path = path.to_path_buf();
+            move |source| Td5Error::Io {
+                path: Some(path),
+                source,
+            }
+        };
+        let mut packages = Vec::new();
+        for entry in std::fs::read_dir(root).map_err(io(root))? {
+            let dir = entry.map_err(io(root))?.path();
+            let is_package = dir
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix("intel-rapl:"))
+                .is_some_and(|index| index.bytes().all(|b| b.is_ascii_digit()));
+            if !is_package {
+                continue;
+            }
+            let range = dir.join("max_energy_range_uj");
+            let range = read_counter(&range).map_err(io(&range))?;
+            let counter = dir.join("energy_uj");
+            read_counter(&counter).map_err(io(&counter))?;
+            packages.push((counter, range));
+        }
+        if packages.is_empty() {
+            return Err(Td5Error::config(format!(
+                "no RAPL energy counters under {}",
+                root.display()
+            )));
+        }
+        packages.sort();
+        Ok(Rapl { packages })
+    }
+
+    pub fn packages(&self) -> usize {
+        self.packages.len()
+    }
+
+    /// Every package's counter, in microjoules.
+    pub fn read(&self) -> Result<Vec<u64>> {
+        self.packages
+            .iter()
+            .map(|(path, _)| {
+                read_counter(path).map_err(|source| Td5Error::Io {
+                    path: Some(path.clone()),
+                    source,
+                })
+            })
+            .collect()
+    }
+
+    /// Microjoules consumed between two `read`s, across all packages. A
+    /// counter may wrap around once in between.
+    pub fn used(&self, before: &[u64], after: &[u64]) -> u64 {
+        self.packages
+            .iter()
+            .zip(before.iter().zip(after))
+            .map(|(&(_, range), (&before, &after))| match after >= before {
+                true => after - before,
+                false => range - before + after,
+            })
+            .sum()
+    }
+
+    /// Runs `f` and returns its result with the microjoules used meanwhile.
+    pub fn measure<T>(&self, f: impl FnOnce() -> T) -> Result<(T, u64)> {
+        let before = self.read()?;
+        let value = f();
+        let after = self.read()?;
+        Ok((value, self.used(&before, &after)))
+    }
+}
+
+fn read_counter(path: &Path) -> std::io::Result<u64> {
+    std::fs::read_to_string(path)?
+        .trim()
+        .parse()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
@@ -0,0 +1,207 @@
+//! Readability along a document (`td5 readability`): Flesch reading ease,
+//! Flesch–Kincaid grade and average sentence length over a window of words
+//! moved through the text, so the hardest passages stand out rather than
+//! averaging away.
+//!
+//! Words and sentences come from the prose linter's tokenizer. Syllables are
+//! estimated as groups of vowels, a final silent `e` dropped, which is close
+//! for English and rough for other languages.
+
+use crate::json::Json;
+use crate::lint::{Word, words};
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Readability {
+    pub words: usize,
+    pub sentences: usize,
+    pub syllables: usize,
+}
+
+impl Readability {
+    pub fn words_per_sentence(&self) -> f64 {
+        self.words as f64 / self.sentences.max(1) as f64
+    }
+
+    pub fn syllables_per_word(&self) -> f64 {
+        self.syllables as f64 / self.words.max(1) as f64
+    }
+
+    /// Flesch reading ease: higher is easier, 60–70 plain English.
+    pub fn reading_ease(&self) -> f64 {
+        206.835 - 1.015 * self.words_per_sentence() - 84.6 * self.syllables_per_word()
+    }
+
+    /// Flesch–Kincaid grade level: the US school grade the text suits.
+    pub fn grade(&self) -> f64 {
+        0.39 * self.words_per_sentence() + 11.8 * self.syllables_per_word() - 15.59
+    }
+
+    /// Counts of the sentences touched by `words`, a sentence cut by the
+    /// start of the slice counting as one.
+    fn of(words: &[Word]) -> Readability {
+        let starts = words.iter().filter(|w| w.starts_sentence).count();
+        let cut = words.first().is_some_and(|w| !w.starts_sentence);
+        Readability {
+            words: words.len(),
+            sentences: starts + usize::from(cut),
+            syllables: words.iter().map(|w| syllables(&w.lower)).sum(),
+        }
+    }
+
+    fn to_json_fields(self) -> Vec<(String, Json)> {
+        vec![
+            ("words".into(), self.words.into()),
+            ("sentences".into(), self.sentences.into()),
+            ("syllables".into(), self.syllables.into()),
+            (
+                "words_per_sentence".into(),
+                self.words_per_sentence().into(),
+            ),
+            ("reading_ease".into(), self.reading_ease().into()),
+            ("grade".into(), self.grade().into()),
+        ]
+    }
+}
+
+fn is_vowel(c: char) -> bool {
+    "aeiouyàâéèêëîïôöûùüÿœæ".contains(c)
+}
+
+/// Estimated syllables of a lowercase word, at least one.
+pub fn syllables(word: &str) -> usize {
+    let mut groups = 0;
+    let mut previous = false;
+    for c in word.chars() {
+        let vowel = is_vowel(c);
+        if vowel && !previous {
+            groups += 1;
+        }
+        previous = vowel;
+    }
+    let silent_e = word.ends_with('e') && !word.ends_with("le") && !word.ends_with("ee");
+    if silent_e && groups > 1 {
+        groups -= 1;
+    }
+    groups.max(1)
+}
+
+/// One window of the profile.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    /// Index of the window's first word.
+    pub start_word: usize,
+    /// Lines of its first and last word, 1-based.
+    pub lines: (usize, usize),
+    pub readability: Readability,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    /// The whole document.
+    pub overall: Readability,
+    /// Windows in reading order.
+    pub points: Vec<Point>,
+}
+
+impl Profile {
+    /// Readability of every `window` consecutive words, starting every
+    /// `step` words. A text shorter than `window` is a single window; the
+    /// last window ends with the text.
+    pub fn of(text: &str, window: usize, step: usize) -> Profile {
+        let words = words(text);
+        let window = window.clamp(1, words.len().max(1));
+        let step = step.max(1);
+        let mut starts: Vec<usize> = (0..=words.len().saturating_sub(window))
+            .step_by(step)
+            .collect();
+        if let Some(&last) = starts.last()
+            && last + window < words.len()
+        {
+            starts.push(words.len() - window);
+        }
+        let points = starts
+            .into_iter()
+            .filter(|_| !words.is_empty())
+            .map(|start| {
+                let slice = &words[start..start + window];
+                Point {
+                    start_word: start,
+                    lines: (slice[0].line, slice[slice.len() - 1].line),
+                    readability: Readability::of(slice),
+                }
+            })
+            .collect();
+        Profile {
+            overall: Readability::of(&words),
+            points,
+        }
+    }
+
+    /// The `n` windows with the highest grade, hardest first, skipping
+    /// windows overlapping one already picked.
+    pub fn hardest(&self, n: usize) -> Vec<&Point> {
+        let mut ranked: Vec<&Point> = self.points.iter().collect();
+        ranked.sort_by(|a, b| b.readability.grade().total_cmp(&a.readability.grade()));
+        let mut picked: Vec<&Point> = Vec::new();
+        for point in ranked {
+            if picked.len() == n {
+                break;
+            }
+            let overlaps = picked
+                .iter()
+                .any(|p| p.lines.0 <= point.lines.1 && point.lines.0 <= p.lines.1);
+            if !overlaps {
+                picked.push(point);
+            }
+        }
+        picked
+    }
+
+    /// One row per window, for spreadsheets and plotting.
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from(
+            "start_word,first_line,last_line,words,sentences,words_per_sentence,syllables_per_word,reading_ease,grade\n",
+        );
+        for p in &self.points {
+            let r = &p.readability;
+            let _ = writeln!(
+                out,
+                "{},{},{},{},{},{:.2},{:.3},{:.1},{:.2}",
+                p.start_word,
+                p.lines.0,
+                p.lines.1,
+                r.words,
+                r.sentences,
+                r.words_per_sentence(),
+                r.syllables_per_word(),
+                r.reading_ease(),
+                r.grade()
+            );
+        }
+        out
+    }
+
+    pub fn to_json_value(&self) -> Json {
+        let points = self
+            .points
+            .iter()
+            .map(|p| {
+                let mut fields = vec![
+                    ("start_word".into(), p.start_word.into()),
+                    ("first_line".into(), p.lines.0.into()),
+                    ("last_line".into(), p.lines.1.into()),
+                ];
+                fields.extend(p.readability.to_json_fields());
+                Json::Object(fields)
+            })
+            .collect();
+        Json::Object(vec![
+            (
+                "overall".into(),
+                Json::Object(self.overall.to_json_fields()),
+            ),
+            ("points".into(), Json::Array(points)),
+        ])
+    }
+}
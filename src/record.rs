@@ -0,0 +1,214 @@
+//! Record/replay bundles for performance reports.
+//!
+//! A bundle holds everything needed to rerun a benchmark elsewhere: the inputs
+//! (generator parameters, or the full text for files), the analyzers and
+//! iteration count, the environment and the report that was observed. Replaying
+//! it reruns the same plan and diffs the new report against the recorded one.
+
+use crate::bench::{BenchInput, BenchReport, BenchSuite};
+use crate::environment::Environment;
+use crate::error::Result;
+use crate::json::{self, Json};
+#[cfg(all(feature = "rapl", target_os = "linux"))]
+use crate::rapl::Rapl;
+use crate::schema::DecodeError;
+use crate::shapes::{InputCache, Shape};
+
+/// Bumped when the bundle layout changes incompatibly.
+pub const BUNDLE_VERSION: u64 = 1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordedInput {
+    /// Regenerated on replay, so the bundle stays small.
+    Generated {
+        shape: Shape,
+        words: usize,
+        seed: u64,
+    },
+    /// Stored verbatim.
+    Text { name: String, text: String },
+}
+
+impl RecordedInput {
+    pub fn name(&self) -> &str {
+        match self {
+            RecordedInput::Generated { shape, .. } => shape.name(),
+            RecordedInput::Text { name, .. } => name,
+        }
+    }
+
+    pub fn materialize(&self) -> BenchInput {
+        self.materialize_with(InputCache::default())
+    }
+
+    /// Generated inputs are read from the disk cache when `cache` applies.
+    pub fn materialize_with(&self, cache: InputCache) -> BenchInput {
+        match self {
+            RecordedInput::Generated { shape, words, seed } => {
+                BenchInput::new(shape.name(), shape.generate_cached(*words, *seed, cache))
+            }
+            RecordedInput::Text { name, text } => BenchInput::new(name.as_str(), text.as_str()),
+        }
+    }
+
+    fn to_json_value(&self) -> Json {
+        match self {
+            RecordedInput::Generated { shape, words, seed } => Json::Object(vec![
+                ("shape".into(), shape.name().into()),
+                ("words".into(), (*words).into()),
+                ("seed".into(), (*seed).into()),
+            ]),
+            RecordedInput::Text { name, text } => Json::Object(vec![
+                ("name".into(), name.as_str().into()),
+                ("text".into(), text.as_str().into()),
+            ]),
+        }
+    }
+
+    fn from_json_value(doc: &Json) -> std::result::Result<Self, DecodeError> {
+        if let Some(text) = doc.get("text").and_then(Json::as_str) {
+            let name = doc.get("name").and_then(Json::as_str).unwrap_or("input");
+            return Ok(RecordedInput::Text {
+                name: name.into(),
+                text: text.into(),
+            });
+        }
+        let shape = doc
+            .get("shape")
+            .and_then(Json::as_str)
+            .and_then(Shape::from_name)
+            .ok_or(DecodeError::InvalidField("shape"))?;
+        let number = |name: &'static str| {
+            doc.get(name)
+                .and_then(Json::as_u64)
+                .ok_or(DecodeError::InvalidField(name))
+        };
+        Ok(RecordedInput::Generated {
+            shape,
+            words: number("words")? as usize,
+            seed: number("seed")?,
+        })
+    }
+}
+
+/// What to run: the part of a bundle replay needs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plan {
+    pub inputs: Vec<RecordedInput>,
+    pub analyzers: Vec<String>,
+    pub iterations: usize,
+}
+
+impl Plan {
+    pub fn run(&self) -> Result<BenchReport> {
+        self.run_with(InputCache::default())
+    }
+
+    pub fn run_with(&self, cache: InputCache) -> Result<BenchReport> {
+        self.run_suite(cache, |suite| suite)
+    }
+
+    /// `run_with`, also measuring the energy of every run.
+    #[cfg(all(feature = "rapl", target_os = "linux"))]
+    pub fn run_with_energy(&self, cache: InputCache, rapl: Rapl) -> Result<BenchReport> {
+        self.run_suite(cache, |suite| suite.energy(rapl))
+    }
+
+    fn run_suite(
+        &self,
+        cache: InputCache,
+        configure: impl FnOnce(BenchSuite) -> BenchSuite,
+    ) -> Result<BenchReport> {
+        let names: Vec<&str> = self.analyzers.iter().map(String::as_str).collect();
+        let inputs: Vec<BenchInput> = self
+            .inputs
+            .iter()
+            .map(|input| input.materialize_with(cache))
+            .collect();
+        let suite = BenchSuite::with_builtins_named(&names)?.iterations(self.iterations);
+        let mut report = configure(suite).run(&inputs)?;
+        report.seed = self.inputs.iter().find_map(|input| match input {
+            RecordedInput::Generated { seed, .. } => Some(*seed),
+            RecordedInput::Text { .. } => None,
+        });
+        Ok(report)
+    }
+}
+
+#[derive(Debug)]
+pub struct Bundle {
+    pub plan: Plan,
+    pub report: BenchReport,
+}
+
+impl Bundle {
+    pub fn environment(&self) -> &Environment {
+        &self.report.environment
+    }
+
+    pub fn to_json(&self) -> String {
+        Json::Object(vec![
+            ("bundle_version".into(), BUNDLE_VERSION.into()),
+            (
+                "inputs".into(),
+                Json::Array(self.plan.inputs.iter().map(|i| i.to_json_value()).collect()),
+            ),
+            (
+                "analyzers".into(),
+                Json::Array(
+                    self.plan
+                        .analyzers
+                        .iter()
+                        .map(|a| a.as_str().into())
+                        .collect(),
+                ),
+            ),
+            ("iterations".into(), self.plan.iterations.into()),
+            ("report".into(), self.report.to_json_value()),
+        ])
+        .to_string()
+    }
+
+    pub fn from_json(input: &str) -> std::result::Result<Self, DecodeError> {
+        let doc = json::parse(input)?;
+        if !matches!(doc, Json::Object(_)) {
+            return Err(DecodeError::NotAnObject);
+        }
+        if doc.get("bundle_version").and_then(Json::as_u64) != Some(BUNDLE_VERSION) {
+            return Err(DecodeError::InvalidField("bundle_version"));
+        }
+        let inputs = doc
+            .get("inputs")
+            .and_then(Json::as_array)
+            .ok_or(DecodeError::InvalidField("inputs"))?
+            .iter()
+            .map(RecordedInput::from_json_value)
+            .collect::<std::result::Result<_, _>>()?;
+        let analyzers = doc
+            .get("analyzers")
+            .and_then(Json::as_array)
+            .ok_or(DecodeError::InvalidField("analyzers"))?
+            .iter()
+            .map(|a| {
+                a.as_str()
+                    .map(str::to_string)
+                    .ok_or(DecodeError::InvalidField("analyzers"))
+            })
+            .collect::<std::result::Result<_, _>>()?;
+        let iterations = doc
+            .get("iterations")
+            .and_then(Json::as_u64)
+            .ok_or(DecodeError::InvalidField("iterations"))? as usize;
+        let report = doc
+            .get("report")
+            .ok_or(DecodeError::InvalidField("report"))?;
+        Ok(Self {
+            plan: Plan {
+                inputs,
+                analyzers,
+                iterations,
+            },
+            report: BenchReport::from_json_value(report)?,
+        })
+    }
+}
@@ -0,0 +1,296 @@
+//! Interactive query loop over documents that are parsed once and kept in memory.
+
+use crate::concordance::Concordance;
+use crate::encoding::Encoding;
+use crate::error::Result;
+use crate::fast;
+use crate::freq::WordFrequency;
+use crate::input::read_transcoded;
+use crate::interner::Interner;
+use crate::overlap::Overlap;
+use crate::{AnalyzerConfig, TextStats};
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+const KWIC_CONTEXT: usize = 5;
+
+const HELP: &str = "\
+commands:
+  load <path>...        load and analyze documents
+  docs                  list loaded documents
+  use <doc>             make <doc> the active document
+  stats                 summary of the active document
+  top [n]               n most frequent words (default 10)
+  count <word>          occurrences of <word>
+  kwic <word>           every occurrence of <word> with surrounding words
+  compare <doc> <doc>   vocabulary overlap between two documents
+  help                  this message
+  quit                  leave the REPL";
+
+pub struct Document {
+    pub name: String,
+    pub text: String,
+    pub stats: TextStats,
+    pub freq: WordFrequency,
+}
+
+impl Document {
+    pub fn new(name: impl Into<String>, text: String) -> Self {
+        Self::with_config(name, text, &AnalyzerConfig::default())
+    }
+
+    /// Counts `text` once, for both the stats and the table.
+    pub fn with_config(name: impl Into<String>, text: String, config: &AnalyzerConfig) -> Self {
+        let counts = fast::count(&text, config);
+        Self {
+            name: name.into(),
+            stats: fast::finish(counts.clone(), config),
+            freq: WordFrequency::from_counts(counts),
+            text,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Repl {
+    docs: Vec<Document>,
+    active: usize,
+    /// Words of every loaded document's table, stored once.
+    words: Interner,
+    /// How loaded documents are counted and `kwic` finds words.
+    config: AnalyzerConfig,
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_config(config: AnalyzerConfig) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+
+    /// Loads a file in any supported encoding; returns the one detected.
+    pub fn load(&mut self, path: &str) -> Result<Encoding> {
+        let (text, encoding) = read_transcoded(path)?;
+        self.add(Document::with_config(path, text, &self.config));
+        Ok(encoding)
+    }
+
+    /// Adds a document and makes it the active one.
+    pub fn add(&mut self, mut doc: Document) {
+        doc.freq.intern(&self.words);
+        self.docs.push(doc);
+        self.active = self.docs.len() - 1;
+    }
+
+    pub fn docs(&self) -> &[Document] {
+        &self.docs
+    }
+
+    fn find(&self, name: &str) -> Option<usize> {
+        self.docs.iter().position(|d| d.name == name).or_else(|| {
+            self.docs
+                .iter()
+                .position(|d| Path::new(&d.name).file_name().is_some_and(|f| f == name))
+        })
+    }
+
+    fn active(&self) -> Option<&Document> {
+        self.docs.get(self.active)
+    }
+
+    /// Reads commands until EOF or `quit`.
+    pub fn run(&mut self, input: impl BufRead, mut out: impl Write) -> io::Result<()> {
+        write!(out, "td5> ")?;
+        out.flush()?;
+        for line in input.lines() {
+            if !self.execute(&line?, &mut out)? {
+                break;
+            }
+            write!(out, "td5> ")?;
+            out.flush()?;
+        }
+        writeln!(out)
+    }
+
+    /// Runs a single command line. Returns `false` once the user asked to quit.
+    ///
+    /// ```
+    /// use td5::repl::{Document, Repl};
+    ///
+    /// let mut repl = Repl::new();
+    /// repl.add(Document::new("pets", "The cat sat. A CAT! cats".to_string()));
+    /// let mut out = Vec::new();
+    /// repl.execute("kwic cat", &mut out).unwrap();
+    /// let out = String::from_utf8(out).unwrap();
+    /// assert_eq!(out.lines().count(), 2);
+    /// assert!(out.lines().nth(1).unwrap().contains("A [CAT]"));
+    /// ```
+    pub fn execute(&mut self, line: &str, out: &mut impl Write) -> io::Result<bool> {
+        let args = split_args(line);
+        let Some((cmd, rest)) = args.split_first() else {
+            return Ok(true);
+        };
+        match (cmd.as_str(), rest) {
+            ("quit" | "exit", _) => return Ok(false),
+            ("help", _) => writeln!(out, "{HELP}")?,
+            ("load", paths) if !paths.is_empty() => {
+                for path in paths {
+                    match self.load(path) {
+                        Ok(Encoding::Utf8) => writeln!(out, "loaded {path}")?,
+                        Ok(encoding) => writeln!(out, "loaded {path} (from {encoding})")?,
+                        Err(e) => writeln!(out, "error: {e}")?,
+                    }
+                }
+            }
+            ("docs", []) => {
+                for (i, doc) in self.docs.iter().enumerate() {
+                    let marker = if i == self.active { '*' } else { ' ' };
+                    writeln!(out, "{marker} {} ({} bytes)", doc.name, doc.text.len())?;
+                }
+                if !self.docs.is_empty() {
+                    writeln!(
+                        out,
+                        "  {} distinct words, {} bytes, shared by all documents",
+                        self.words.len(),
+                        self.words.text_bytes()
+                    )?;
+                }
+            }
+            ("use", [name]) => match self.find(name) {
+                Some(idx) => self.active = idx,
+                None => writeln!(out, "error: no document named {name}")?,
+            },
+            ("stats", []) => self.with_active(out, |doc, out| {
+                let s = &doc.stats;
+                writeln!(out, "Unique words: {}", s.word_count)?;
+                writeln!(out, "Total chars: {}", s.char_count)?;
+                writeln!(out, "Top 10 words: {:?}", s.top_words)?;
+                writeln!(out, "Longest words: {:?}", s.longest_words)
+            })?,
+            ("top", [] | [_]) => {
+                let n = match rest.first().map(|n| n.parse::<usize>()) {
+                    None => 10,
+                    Some(Ok(n)) => n,
+                    Some(Err(_)) => {
+                        writeln!(out, "error: top expects a number")?;
+                        return Ok(true);
+                    }
+                };
+                self.with_active(out, |doc, out| {
+                    for (rank, (word, count)) in doc.freq.top_k(n).into_iter().enumerate() {
+                        writeln!(out, "{:>4}. {word} {count}", rank + 1)?;
+                    }
+                    Ok(())
+                })?
+            }
+            ("count", [word]) => {
+                self.with_active(out, |doc, out| writeln!(out, "{}", doc.freq.count(word)))?
+            }
+            ("kwic", [word]) => {
+                self.with_active(out, |doc, out| kwic(doc, word, &self.config, out))?
+            }
+            ("compare", [a, b]) => match (self.find(a), self.find(b)) {
+                (Some(a), Some(b)) => compare(&self.docs[a], &self.docs[b], out)?,
+                _ => writeln!(out, "error: both documents must be loaded")?,
+            },
+            _ => writeln!(out, "error: unknown command `{line}` (try `help`)")?,
+        }
+        Ok(true)
+    }
+
+    fn with_active<W: Write>(
+        &self,
+        out: &mut W,
+        f: impl FnOnce(&Document, &mut W) -> io::Result<()>,
+    ) -> io::Result<()> {
+        match self.active() {
+            Some(doc) => f(doc, out),
+            None => writeln!(out, "error: no document loaded (use `load <path>`)"),
+        }
+    }
+}
+
+/// Splits on whitespace, keeping `"double quoted"` runs together.
+fn split_args(line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut started = false;
+    for ch in line.chars() {
+        match ch {
+            '"' => {
+                quoted = !quoted;
+                started = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if started {
+                    args.push(std::mem::take(&mut current));
+                    started = false;
+                }
+            }
+            c => {
+                current.push(c);
+                started = true;
+            }
+        }
+    }
+    if started {
+        args.push(current);
+    }
+    args
+}
+
+/// Every occurrence of `word` as the analyzers count it, `Cat.` and
+/// `cat's` included.
+fn kwic(
+    doc: &Document,
+    word: &str,
+    config: &AnalyzerConfig,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let lines = Concordance::of(&doc.text, word, config).lines(word, KWIC_CONTEXT);
+    for line in &lines {
+        writeln!(out, "{:>40} [{}] {}", line.left, line.word, line.right)?;
+    }
+    if lines.is_empty() {
+        writeln!(out, "no occurrences of {}", word.to_lowercase())?;
+    }
+    Ok(())
+}
+
+fn compare(a: &Document, b: &Document, out: &mut impl Write) -> io::Result<()> {
+    let overlap = Overlap::between(&a.freq, &b.freq, 0);
+    let (shared, jaccard) = (overlap.shared, overlap.jaccard);
+    writeln!(out, "{:<24} {:>10} {:>10}", "", a.name, b.name)?;
+    writeln!(
+        out,
+        "{:<24} {:>10} {:>10}",
+        "unique words",
+        a.freq.len(),
+        b.freq.len()
+    )?;
+    writeln!(
+        out,
+        "{:<24} {:>10} {:>10}",
+        "only here",
+        a.freq.len() - shared,
+        b.freq.len() - shared
+    )?;
+    writeln!(out, "shared words: {shared} (jaccard {jaccard:.3})")?;
+    let top_a = a.freq.top_k(10);
+    let top_b = b.freq.top_k(10);
+    for i in 0..top_a.len().max(top_b.len()) {
+        let cell = |top: &[(&str, usize)]| {
+            top.get(i)
+                .map(|(w, c)| format!("{w} {c}"))
+                .unwrap_or_default()
+        };
+        writeln!(out, "{:>4}. {:<28} {}", i + 1, cell(&top_a), cell(&top_b))?;
+    }
+    Ok(())
+}
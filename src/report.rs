@@ -0,0 +1,268 @@
+//! One analysis as a self-contained HTML page (`td5 analyze --report`,
+//! `td5 report`), for sharing results with people who will not run the CLI,
+//! or as Markdown for a write-up.
+//!
+//! The page is `report/report.html` rendered with the `template` engine; the
+//! stylesheet, the script drawing the charts and the data (as JSON) are
+//! inlined, so the file opens offline and can be mailed as is. Charts are
+//! plain SVG: the top words, a histogram of word lengths (distinct words or
+//! occurrences), the coverage curve, the share of the text made of the N
+//! most frequent words, and, when the report has timings, the time each
+//! analyzer took. The Markdown version draws the same charts but the
+//! coverage curve with block characters.
+
+use crate::TextStats;
+use crate::freq::WordFrequency;
+use crate::html;
+use crate::json::Json;
+use crate::template::Template;
+use std::fmt::Write;
+
+const PAGE: &str = include_str!("../report/report.html");
+const STYLE: &str = include_str!("../report/report.css");
+const SCRIPT: &str = include_str!("../report/report.js");
+
+/// Words in the top-words chart.
+pub const TOP_WORDS: usize = 25;
+
+/// Ranks drawn one by one on the coverage curve; beyond, each point is about
+/// 2% further than the last, so a million words take a few hundred points.
+const EXACT_RANKS: usize = 100;
+
+/// Words of one length, in characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LengthBin {
+    pub length: usize,
+    /// Distinct words.
+    pub words: usize,
+    /// Occurrences of those words.
+    pub tokens: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub title: String,
+    pub stats: TextStats,
+    /// Most frequent first, at most `TOP_WORDS`.
+    pub top_words: Vec<(String, usize)>,
+    /// By increasing length, lengths without words left out.
+    pub lengths: Vec<LengthBin>,
+    /// `(rank, share)`: the share of all occurrences made of the `rank` most
+    /// frequent words, by increasing rank, ending at every word.
+    pub coverage: Vec<(usize, f64)>,
+    /// `(analyzer, time_ns)` on the same text, the first being the baseline
+    /// of the speedups; empty unless `with_timings` was called.
+    pub timings: Vec<(String, u128)>,
+}
+
+impl Report {
+    /// `stats` as printed by `analyze`; `freq` is the full table of the same
+    /// text, for the charts.
+    pub fn new(title: &str, stats: &TextStats, freq: &WordFrequency) -> Report {
+        let ranked: Vec<(&str, usize)> = freq.by_count().collect();
+        let top_words = ranked
+            .iter()
+            .take(TOP_WORDS)
+            .map(|&(word, count)| (word.to_string(), count))
+            .collect();
+
+        let mut lengths: Vec<LengthBin> = Vec::new();
+        for &(word, count) in &ranked {
+            let length = word.chars().count();
+            if length == 0 {
+                continue;
+            }
+            if lengths.len() < length {
+                lengths.resize_with(length, || LengthBin {
+                    length: 0,
+                    words: 0,
+                    tokens: 0,
+                });
+            }
+            let bin = &mut lengths[length - 1];
+            bin.length = length;
+            bin.words += 1;
+            bin.tokens += count;
+        }
+        lengths.retain(|bin| bin.words > 0);
+
+        let total = freq.total().max(1) as f64;
+        let mut coverage = Vec::new();
+        let mut covered = 0;
+        let mut next = 1;
+        for (i, &(_, count)) in ranked.iter().enumerate() {
+            covered += count;
+            let rank = i + 1;
+            if rank == next || rank == ranked.len() {
+                coverage.push((rank, covered as f64 / total));
+                next = rank + if rank < EXACT_RANKS { 1 } else { rank / 50 };
+            }
+        }
+
+        Report {
+            title: title.to_string(),
+            stats: stats.clone(),
+            top_words,
+            lengths,
+            coverage,
+            timings: Vec::new(),
+        }
+    }
+
+    /// Adds the time each analyzer took on the text, baseline first.
+    pub fn with_timings(mut self, timings: Vec<(String, u128)>) -> Report {
+        self.timings = timings;
+        self
+    }
+
+    pub fn to_json_value(&self) -> Json {
+        let pair = |a: Json, b: Json| Json::Array(vec![a, b]);
+        Json::Object(vec![
+            ("title".into(), self.title.as_str().into()),
+            ("stats".into(), self.stats.to_json_value()),
+            (
+                "top_words".into(),
+                Json::Array(
+                    self.top_words
+                        .iter()
+                        .map(|(word, count)| pair(word.as_str().into(), (*count).into()))
+                        .collect(),
+                ),
+            ),
+            (
+                "lengths".into(),
+                Json::Array(
+                    self.lengths
+                        .iter()
+                        .map(|bin| {
+                            Json::Object(vec![
+                                ("length".into(), bin.length.into()),
+                                ("words".into(), bin.words.into()),
+                                ("tokens".into(), bin.tokens.into()),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "coverage".into(),
+                Json::Array(
+                    self.coverage
+                        .iter()
+                        .map(|&(rank, share)| pair(rank.into(), share.into()))
+                        .collect(),
+                ),
+            ),
+            (
+                "timings".into(),
+                Json::Array(
+                    self.timings
+                        .iter()
+                        .map(|(name, ns)| pair(name.as_str().into(), (*ns).into()))
+                        .collect(),
+                ),
+            ),
+        ])
+    }
+
+    /// The page, ready to write to a `.html` file.
+    pub fn to_html(&self) -> String {
+        let page = Template::parse(PAGE).expect("the report template is valid");
+        // `<` only occurs inside JSON strings, where `\u003c` means the same
+        // and cannot close the script element holding the data.
+        let data = self.to_json_value().to_string().replace('<', "\\u003c");
+        page.render(&Json::Object(vec![
+            ("title".into(), html::escape(&self.title).into()),
+            ("style".into(), STYLE.into()),
+            ("script".into(), SCRIPT.into()),
+            ("data".into(), data.into()),
+        ]))
+    }
+
+    /// The report as Markdown, charts drawn in code blocks.
+    ///
+    /// ```
+    /// use td5::WordFrequency;
+    /// use td5::report::Report;
+    ///
+    /// let text = "the cat and the hat";
+    /// let report = Report::new("hats", &td5::analyze(text), &WordFrequency::from_text(text))
+    ///     .with_timings(vec![("slow".into(), 4_000_000), ("fast".into(), 1_000_000)]);
+    /// let markdown = report.to_markdown();
+    /// assert!(markdown.starts_with("# hats – word statistics\n"));
+    /// assert!(markdown.contains("the  ████████████████████████████████████████ 2 (40.0%)"));
+    /// assert!(markdown.contains("| fast | 1.000 | 4.00× |"));
+    /// ```
+    pub fn to_markdown(&self) -> String {
+        const WIDTH: usize = 40;
+        let bar = |n: usize, most: usize| "█".repeat((n * WIDTH).div_ceil(most.max(1)));
+        let stats = &self.stats;
+        let mut out = format!("# {} – word statistics\n\n", self.title);
+        out.push_str("| Words | Distinct words | Letters | Longest word |\n");
+        out.push_str("|---:|---:|---:|---|\n");
+        let _ = writeln!(
+            out,
+            "| {} | {} | {} | {} |",
+            stats.total_words,
+            stats.word_count,
+            stats.char_count,
+            stats.longest_words.first().map_or("–", String::as_str)
+        );
+
+        out.push_str("\n## Top words\n\n");
+        if self.top_words.is_empty() {
+            out.push_str("No words in this text.\n");
+        } else {
+            let most = self.top_words[0].1;
+            let width = self.top_words.iter().map(|(w, _)| w.chars().count()).max();
+            let total = stats.total_words.max(1) as f64;
+            out.push_str("```text\n");
+            for (word, count) in &self.top_words {
+                let _ = writeln!(
+                    out,
+                    "{word:<width$}  {} {count} ({:.1}%)",
+                    bar(*count, most),
+                    100.0 * *count as f64 / total,
+                    width = width.unwrap_or(0)
+                );
+            }
+            out.push_str("```\n");
+        }
+
+        out.push_str("\n## Word lengths\n\n");
+        if self.lengths.is_empty() {
+            out.push_str("No words in this text.\n");
+        } else {
+            let most = self.lengths.iter().map(|bin| bin.tokens).max();
+            out.push_str("Occurrences of words of each length, in letters.\n\n```text\n");
+            for bin in &self.lengths {
+                let _ = writeln!(
+                    out,
+                    "{:>3}  {} {} ({} distinct)",
+                    bin.length,
+                    bar(bin.tokens, most.unwrap_or(1)),
+                    bin.tokens,
+                    bin.words
+                );
+            }
+            out.push_str("```\n");
+        }
+
+        if let Some((baseline, base_ns)) = self.timings.first() {
+            let _ = write!(
+                out,
+                "\n## Analyzer timings\n\nSpeedups are against `{baseline}`.\n\n"
+            );
+            out.push_str("| Analyzer | Time (ms) | Speedup |\n|---|---:|---:|\n");
+            for (name, ns) in &self.timings {
+                let _ = writeln!(
+                    out,
+                    "| {name} | {:.3} | {:.2}× |",
+                    *ns as f64 / 1e6,
+                    *base_ns as f64 / (*ns).max(1) as f64
+                );
+            }
+        }
+        out
+    }
+}
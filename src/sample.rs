@@ -0,0 +1,254 @@
+//! Quick previews of huge files (`--sample 10%`): a deterministic share of
+//! the file's blocks is read and analyzed, and the counts are scaled up to
+//! estimate the whole file's.
+//!
+//! The file is cut into `BLOCK_BYTES` blocks and blocks are picked evenly
+//! spread, from a starting offset chosen by the seed (`--seed`), so the same
+//! file, share and seed always read the same bytes. Each block
+//! is widened to whole lines the way `--range` widens its ends, so no word is
+//! cut or counted twice; in text with lines longer than a block, such as
+//! minified JSON, blocks are cut between words instead, or between
+//! characters if there are none. Only the picked blocks are read.
+//!
+//! Totals, counts and diagnostics scale by the ratio of file bytes to bytes
+//! read. Lines and sentences scale only by those the sample saw end: a line
+//! cut short by a block's end and a sentence unfinished at the end of the
+//! sample are counted once, so a file of one long line is still estimated to
+//! have one. Unique words cannot be extrapolated that way: the estimate keeps
+//! the number seen in the sample, a lower bound.
+
+use crate::TextStats;
+use crate::error::{Result, Td5Error};
+use crate::fast::is_sentence_end;
+use crate::json::Json;
+use crate::seed;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Size of the blocks a sample picks from.
+pub const BLOCK_BYTES: u64 = 64 << 10;
+
+/// How much of a file a sample read.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    /// Share of the blocks requested, in `(0, 1]`.
+    pub fraction: f64,
+    pub bytes_read: u64,
+    pub bytes_total: u64,
+    /// The seed that picked the blocks.
+    pub seed: u64,
+    /// Line breaks among the bytes read; the sample's other lines were cut
+    /// short by the end of a block.
+    pub line_breaks: u64,
+    /// Whether the sample ends in the middle of a sentence.
+    pub open_sentence: bool,
+}
+
+/// `10%` or `0.1`.
+pub fn parse_fraction(text: &str) -> Result<f64> {
+    let value = match text.trim().strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f64>().map(|p| p / 100.0),
+        None => text.trim().parse::<f64>(),
+    };
+    match value {
+        Ok(fraction) if fraction > 0.0 && fraction <= 1.0 => Ok(fraction),
+        _ => Err(Td5Error::config(format!(
+            "expected a share such as 10% or 0.1, got `{text}`"
+        ))),
+    }
+}
+
+/// The picked blocks of `path`, concatenated.
+///
+/// ```
+/// // One 300 KB line: blocks are cut between words.
+/// let path = std::env::temp_dir().join("td5-sample-one-line.txt");
+/// std::fs::write(&path, "word ".repeat(60_000)).unwrap();
+/// let (bytes, estimate) = td5::sample::read(&path, 0.5).unwrap();
+/// assert!(estimate.bytes_read > 0 && estimate.bytes_read < estimate.bytes_total);
+/// let mut stats = td5::analyze(std::str::from_utf8(&bytes).unwrap());
+/// assert_eq!(stats.top_words[0].0, "word");
+/// estimate.extrapolate(&mut stats);
+/// assert!(stats.total_words.abs_diff(60_000) < 100);
+/// ```
+pub fn read(path: &Path, fraction: f64) -> Result<(Vec<u8>, Estimate)> {
+    read_seeded(path, fraction, 0)
+}
+
+/// `read` with the blocks picked by `seed`; seed 0 picks those of `read`.
+pub fn read_seeded(path: &Path, fraction: f64, seed: u64) -> Result<(Vec<u8>, Estimate)> {
+    let io_error = |source| Td5Error::Io {
+        path: Some(path.to_path_buf()),
+        source,
+    };
+    let file = File::open(path).map_err(io_error)?;
+    let bytes_total = file.metadata().map_err(io_error)?.len();
+    let mut reader = BufReader::new(file);
+    let blocks = bytes_total.div_ceil(BLOCK_BYTES);
+    let phase = seed::unit(seed);
+    // Too small a share for the spread to pick anything: one block.
+    let single = (fraction * (blocks as f64) < 1.0).then_some((phase * blocks as f64) as u64);
+    let mut bytes = Vec::new();
+    let mut line_breaks = 0;
+    for block in 0..blocks {
+        // Picks block `i` when `i * fraction` crosses an integer, shifted by
+        // the seed's phase: an even spread.
+        let picked = match single {
+            Some(only) => block == only,
+            None => {
+                ((block + 1) as f64 * fraction + phase).floor()
+                    > (block as f64 * fraction + phase).floor()
+            }
+        };
+        if !picked {
+            continue;
+        }
+        let start = cut(&mut reader, block * BLOCK_BYTES, bytes_total).map_err(io_error)?;
+        let end = cut(&mut reader, (block + 1) * BLOCK_BYTES, bytes_total).map_err(io_error)?;
+        if end <= start {
+            continue;
+        }
+        reader.seek(SeekFrom::Start(start)).map_err(io_error)?;
+        let before = bytes.len();
+        (&mut reader)
+            .take(end - start)
+            .read_to_end(&mut bytes)
+            .map_err(io_error)?;
+        line_breaks += bytes[before..].iter().filter(|&&b| b == b'\n').count() as u64;
+        if bytes.last().is_some_and(|&b| b != b'\n') && bytes.len() > before {
+            bytes.push(b'\n');
+        }
+    }
+    if bytes.is_empty() && bytes_total > 0 {
+        return Err(Td5Error::config(format!(
+            "the {:.0}% sample of {} read nothing: its block holds only the end of a line; \
+             try a larger share or another seed",
+            fraction * 100.0,
+            path.display()
+        )));
+    }
+    let estimate = Estimate {
+        fraction,
+        bytes_read: bytes.len() as u64,
+        bytes_total,
+        seed,
+        line_breaks,
+        open_sentence: ends_open(&bytes),
+    };
+    Ok((bytes, estimate))
+}
+
+/// Whether words follow the last sentence end of `bytes`, as the analyzers
+/// find them: a token with a letter after the last token ending a sentence.
+fn ends_open(bytes: &[u8]) -> bool {
+    for token in bytes.rsplit(u8::is_ascii_whitespace) {
+        let token = String::from_utf8_lossy(token);
+        if token.ends_with(is_sentence_end) {
+            return false;
+        }
+        if token.chars().any(char::is_alphabetic) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Where a block boundary at `offset` falls: the first line start at or
+/// after it, or if no line ends within a block of it, the first word start,
+/// or failing that the first character boundary. Every block cuts at the
+/// same places, so neighbouring blocks never overlap.
+fn cut(reader: &mut (impl BufRead + Seek), offset: u64, bytes_total: u64) -> io::Result<u64> {
+    if offset == 0 || offset >= bytes_total {
+        return Ok(offset.min(bytes_total));
+    }
+    // From the byte before `offset`, so a boundary right at it is found.
+    reader.seek(SeekFrom::Start(offset - 1))?;
+    let mut ahead = Vec::new();
+    reader.take(BLOCK_BYTES + 1).read_to_end(&mut ahead)?;
+    let after = |i: usize| offset + i as u64;
+    if let Some(i) = ahead.iter().position(|&b| b == b'\n') {
+        return Ok(after(i));
+    }
+    if offset - 1 + ahead.len() as u64 == bytes_total {
+        // The line runs to the end of the file.
+        return Ok(bytes_total);
+    }
+    if let Some(i) = ahead.iter().position(u8::is_ascii_whitespace) {
+        return Ok(after(i));
+    }
+    // A continuation byte (`10xxxxxx`) is never the first of a character.
+    Ok(ahead[1..]
+        .iter()
+        .position(|&b| b & 0xc0 != 0x80)
+        .map_or(bytes_total, after))
+}
+
+impl Estimate {
+    /// File bytes per byte read.
+    pub fn scale(&self) -> f64 {
+        if self.bytes_read == 0 {
+            1.0
+        } else {
+            self.bytes_total as f64 / self.bytes_read as f64
+        }
+    }
+
+    /// `stats` of the sample scaled to the whole file. Only the lines and
+    /// sentences seen to end scale; those cut short count once.
+    ///
+    /// ```
+    /// // One 1 MB sentence on one line.
+    /// let path = std::env::temp_dir().join("td5-sample-one-sentence.txt");
+    /// std::fs::write(&path, "word ".repeat(200_000) + "end.").unwrap();
+    /// let (bytes, estimate) = td5::sample::read(&path, 0.1).unwrap();
+    /// let mut stats = td5::analyze(std::str::from_utf8(&bytes).unwrap());
+    /// estimate.extrapolate(&mut stats);
+    /// assert_eq!((stats.line_count, stats.sentence_count), (Some(1), Some(1)));
+    ///
+    /// let path = std::env::temp_dir().join("td5-sample-many-sentences.txt");
+    /// std::fs::write(&path, "Word word word word.\n".repeat(50_000)).unwrap();
+    /// let (bytes, estimate) = td5::sample::read(&path, 0.1).unwrap();
+    /// let mut stats = td5::analyze(std::str::from_utf8(&bytes).unwrap());
+    /// estimate.extrapolate(&mut stats);
+    /// assert!(stats.line_count.unwrap().abs_diff(50_000) < 100);
+    /// assert!(stats.sentence_count.unwrap().abs_diff(50_000) < 100);
+    /// ```
+    pub fn extrapolate(&self, stats: &mut TextStats) {
+        let scale = self.scale();
+        let up = |n: usize| (n as f64 * scale).round() as usize;
+        stats.total_words = up(stats.total_words);
+        stats.char_count = up(stats.char_count);
+        let breaks = self.line_breaks as usize;
+        stats.line_count = stats
+            .line_count
+            .map(|lines| up(breaks.min(lines)) + lines.saturating_sub(breaks));
+        let open = usize::from(self.open_sentence);
+        stats.sentence_count = stats
+            .sentence_count
+            .map(|sentences| up(sentences.saturating_sub(open)) + open.min(sentences));
+        // The mean and median hold; the histogram scales with the words.
+        for count in &mut stats.word_lengths {
+            *count = up(*count);
+        }
+        for (_, count) in &mut stats.top_words {
+            *count = up(*count);
+        }
+        let names: Vec<&str> = stats.diagnostics.counters().map(|(name, _)| name).collect();
+        for name in names {
+            if let Some(counter) = stats.diagnostics.counter_mut(name) {
+                *counter = up(*counter);
+            }
+        }
+    }
+
+    pub fn to_json_value(&self) -> Json {
+        Json::Object(vec![
+            ("fraction".into(), self.fraction.into()),
+            ("bytes_read".into(), self.bytes_read.into()),
+            ("bytes_total".into(), self.bytes_total.into()),
+            ("scale".into(), self.scale().into()),
+            ("seed".into(), seed::to_json_value(self.seed)),
+        ])
+    }
+}
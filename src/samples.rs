@@ -0,0 +1,49 @@
+//! Small multilingual texts embedded in the library (`samples` feature).
+//!
+//! Shared by the self-test corpus, `td5 bench --corpus sample:<name>` and docs
+//! examples. See `samples/README.md` for where they come from.
+//!
+//! ```
+//! let stats = td5::analyze_text_fast(td5::samples::french());
+//! assert!(stats.word_count > 100);
+//! ```
+
+pub fn english() -> &'static str {
+    include_str!("../samples/english.txt")
+}
+
+pub fn french() -> &'static str {
+    include_str!("../samples/french.txt")
+}
+
+pub fn german() -> &'static str {
+    include_str!("../samples/german.txt")
+}
+
+/// One line per script: Greek, Cyrillic, CJK, Hangul, accented Latin.
+pub fn mixed_scripts() -> &'static str {
+    include_str!("../samples/mixed_scripts.txt")
+}
+
+/// Logs, code, markup and punctuation runs: mostly not prose.
+pub fn ascii_noise() -> &'static str {
+    include_str!("../samples/ascii_noise.txt")
+}
+
+/// Every sample with its name.
+pub fn all() -> [(&'static str, &'static str); 5] {
+    [
+        ("english", english()),
+        ("french", french()),
+        ("german", german()),
+        ("mixed_scripts", mixed_scripts()),
+        ("ascii_noise", ascii_noise()),
+    ]
+}
+
+pub fn get(name: &str) -> Option<&'static str> {
+    all()
+        .into_iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, text)| text)
+}
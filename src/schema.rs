@@ -0,0 +1,348 @@
+//! Versioned JSON form of `TextStats`.
+//!
+//! Every document carries a `schema_version`. Readers ignore fields they do
+//! not know, fill in defaults for fields an older writer did not emit, and run
+//! older documents through `UPGRADES` before decoding, so stored baselines keep
+//! loading after the crate changes.
+
+use crate::json::{self, Json};
+use crate::{Diagnostics, TextStats};
+use std::fmt;
+
+pub const SCHEMA_VERSION: u64 = 4;
+
+/// Documents written before `schema_version` existed are treated as this version.
+const UNVERSIONED: u64 = 1;
+
+/// `UPGRADES[i]` rewrites a version `i + 1` document into version `i + 2`.
+const UPGRADES: &[fn(&mut Json)] = &[v1_to_v2, v2_to_v3, v3_to_v4];
+
+/// v2 added the `diagnostics` section; v1 writers never flagged anything.
+fn v1_to_v2(doc: &mut Json) {
+    if doc.get("diagnostics").is_none() {
+        doc.set(
+            "diagnostics",
+            Json::Object(vec![("long_tokens".into(), 0u64.into())]),
+        );
+    }
+}
+
+/// v3 added `total_words`. Older writers did not keep it, so it decodes as 0
+/// (unknown) rather than being guessed from `top_words`.
+fn v2_to_v3(doc: &mut Json) {
+    if doc.get("total_words").is_none() {
+        doc.set("total_words", Json::Null);
+    }
+}
+
+/// v4 added lines, sentences and word lengths. From older documents, lines
+/// and sentences decode as `None` and word lengths as 0 and empty (unknown).
+fn v3_to_v4(doc: &mut Json) {
+    for field in [
+        "line_count",
+        "sentence_count",
+        "mean_word_length",
+        "median_word_length",
+    ] {
+        if doc.get(field).is_none() {
+            doc.set(field, Json::Null);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum DecodeError {
+    Json(json::ParseError),
+    NotAnObject,
+    InvalidField(&'static str),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Json(e) => e.fmt(f),
+            DecodeError::NotAnObject => f.write_str("stats document is not a JSON object"),
+            DecodeError::InvalidField(name) => write!(f, "invalid value for field `{name}`"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl From<json::ParseError> for DecodeError {
+    fn from(e: json::ParseError) -> Self {
+        DecodeError::Json(e)
+    }
+}
+
+/// `[[text, count], ...]`, as `top_words` and the n-gram lists are written.
+fn counted(list: &[(String, usize)]) -> Json {
+    Json::Array(
+        list.iter()
+            .map(|(w, c)| Json::Array(vec![w.as_str().into(), (*c).into()]))
+            .collect(),
+    )
+}
+
+/// `null` for a count that is not known.
+fn known(n: Option<usize>) -> Json {
+    n.map_or(Json::Null, Json::from)
+}
+
+fn strings(list: &[String]) -> Json {
+    Json::Array(list.iter().map(|w| w.as_str().into()).collect())
+}
+
+/// The strings under `name`, empty when absent.
+fn strings_field(doc: &Json, name: &'static str) -> Result<Vec<String>, DecodeError> {
+    match doc.get(name) {
+        None | Some(Json::Null) => Ok(Vec::new()),
+        Some(v) => v
+            .as_array()
+            .ok_or(DecodeError::InvalidField(name))?
+            .iter()
+            .map(|w| w.as_str().map(str::to_string))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(DecodeError::InvalidField(name)),
+    }
+}
+
+/// The `[text, count]` pairs under `name`, empty when absent.
+fn counted_field(doc: &Json, name: &'static str) -> Result<Vec<(String, usize)>, DecodeError> {
+    match doc.get(name) {
+        None | Some(Json::Null) => Ok(Vec::new()),
+        Some(v) => v
+            .as_array()
+            .ok_or(DecodeError::InvalidField(name))?
+            .iter()
+            .map(|pair| match pair.as_array() {
+                Some([w, c, ..]) => Some((w.as_str()?.to_string(), c.as_u64()? as usize)),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or(DecodeError::InvalidField(name)),
+    }
+}
+
+impl TextStats {
+    /// N-gram lists are only written when counted, and read back as empty
+    /// when absent. The time an analysis took is not part of the stats:
+    /// writers that measure it add `time_ns` beside them, and readers ignore
+    /// it.
+    pub fn to_json_value(&self) -> Json {
+        let mut doc = Json::Object(vec![
+            ("schema_version".into(), SCHEMA_VERSION.into()),
+            ("word_count".into(), self.word_count.into()),
+            ("total_words".into(), self.total_words.into()),
+            ("char_count".into(), self.char_count.into()),
+            ("line_count".into(), known(self.line_count)),
+            ("sentence_count".into(), known(self.sentence_count)),
+            ("mean_word_length".into(), self.mean_word_length.into()),
+            ("median_word_length".into(), self.median_word_length.into()),
+            (
+                "word_lengths".into(),
+                Json::Array(self.word_lengths.iter().map(|&n| n.into()).collect()),
+            ),
+            ("top_words".into(), counted(&self.top_words)),
+            ("longest_words".into(), strings(&self.longest_words)),
+            (
+                "diagnostics".into(),
+                Json::Object(
+                    self.diagnostics
+                        .counters()
+                        .map(|(name, n)| (name.to_string(), n.into()))
+                        .collect(),
+                ),
+            ),
+        ]);
+        if !self.top_bigrams.is_empty() {
+            doc.set("top_bigrams", counted(&self.top_bigrams));
+        }
+        if !self.top_trigrams.is_empty() {
+            doc.set("top_trigrams", counted(&self.top_trigrams));
+        }
+        if !self.surface_forms.is_empty() {
+            doc.set("surface_forms", strings(&self.surface_forms));
+        }
+        if let Some(language) = &self.detected_language {
+            doc.set("detected_language", language.as_str().into());
+        }
+        doc
+    }
+
+    pub fn to_json(&self) -> String {
+        self.to_json_value().to_string()
+    }
+
+    pub fn from_json(input: &str) -> Result<Self, DecodeError> {
+        Self::from_json_value(json::parse(input)?)
+    }
+
+    pub fn from_json_value(mut doc: Json) -> Result<Self, DecodeError> {
+        if !matches!(doc, Json::Object(_)) {
+            return Err(DecodeError::NotAnObject);
+        }
+        let version = match doc.get("schema_version") {
+            None => UNVERSIONED,
+            Some(v) => v
+                .as_u64()
+                .ok_or(DecodeError::InvalidField("schema_version"))?,
+        };
+        for upgrade in UPGRADES.iter().skip(version.saturating_sub(1) as usize) {
+            upgrade(&mut doc);
+        }
+
+        let usize_field = |name: &'static str| match doc.get(name) {
+            None | Some(Json::Null) => Ok(0),
+            Some(v) => v
+                .as_u64()
+                .map(|n| n as usize)
+                .ok_or(DecodeError::InvalidField(name)),
+        };
+        let word_count = usize_field("word_count")?;
+        let total_words = usize_field("total_words")?;
+        let char_count = usize_field("char_count")?;
+        let known_field = |name: &'static str| match doc.get(name) {
+            None | Some(Json::Null) => Ok(None),
+            Some(_) => usize_field(name).map(Some),
+        };
+        let line_count = known_field("line_count")?;
+        let sentence_count = known_field("sentence_count")?;
+        let median_word_length = usize_field("median_word_length")?;
+        let mean_word_length = match doc.get("mean_word_length") {
+            None | Some(Json::Null) => 0.0,
+            Some(v) => v
+                .as_f64()
+                .ok_or(DecodeError::InvalidField("mean_word_length"))?,
+        };
+        let word_lengths = match doc.get("word_lengths") {
+            None | Some(Json::Null) => Vec::new(),
+            Some(v) => v
+                .as_array()
+                .ok_or(DecodeError::InvalidField("word_lengths"))?
+                .iter()
+                .map(|n| n.as_u64().map(|n| n as usize))
+                .collect::<Option<Vec<_>>>()
+                .ok_or(DecodeError::InvalidField("word_lengths"))?,
+        };
+
+        let top_words = counted_field(&doc, "top_words")?;
+        let top_bigrams = counted_field(&doc, "top_bigrams")?;
+        let top_trigrams = counted_field(&doc, "top_trigrams")?;
+        let longest_words = strings_field(&doc, "longest_words")?;
+        let surface_forms = strings_field(&doc, "surface_forms")?;
+        let detected_language = match doc.get("detected_language") {
+            None | Some(Json::Null) => None,
+            Some(v) => Some(
+                v.as_str()
+                    .ok_or(DecodeError::InvalidField("detected_language"))?
+                    .to_string(),
+            ),
+        };
+
+        let mut diagnostics = Diagnostics::default();
+        match doc.get("diagnostics") {
+            None | Some(Json::Null) => {}
+            Some(Json::Object(fields)) => {
+                for (name, value) in fields {
+                    if let (Some(counter), Some(n)) =
+                        (diagnostics.counter_mut(name), value.as_u64())
+                    {
+                        *counter = n as usize;
+                    }
+                }
+            }
+            Some(_) => return Err(DecodeError::InvalidField("diagnostics")),
+        }
+
+        Ok(TextStats {
+            word_count,
+            total_words,
+            char_count,
+            line_count,
+            sentence_count,
+            mean_word_length,
+            median_word_length,
+            word_lengths,
+            top_words,
+            surface_forms,
+            detected_language,
+            longest_words,
+            top_bigrams,
+            top_trigrams,
+            diagnostics,
+        })
+    }
+}
+
+/// What serialized output keeps of measured times: `time_ns`, any other
+/// `*_ns` field and `timings`, at any depth. Everything else in the stats is
+/// a function of the text and the config, so with `Bucket` or `Omit` golden
+/// files and cached results only change when the counts do.
+///
+/// ```
+/// use td5::{json, schema::TimePolicy};
+///
+/// let mut doc = json::parse(r#"{"word_count": 3, "time_ns": 1234567, "phases": {"count_ns": 480000}}"#).unwrap();
+/// TimePolicy::Bucket(1_000_000).apply(&mut doc);
+/// assert_eq!(doc.to_string(), r#"{"word_count":3,"time_ns":1000000,"phases":{"count_ns":0}}"#);
+/// TimePolicy::Omit.apply(&mut doc);
+/// assert_eq!(doc.to_string(), r#"{"word_count":3,"phases":{}}"#);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimePolicy {
+    /// Times as measured.
+    #[default]
+    Exact,
+    /// Times rounded to the nearest multiple of this many nanoseconds.
+    Bucket(u128),
+    /// No times at all. Readers take a missing `time_ns` as 0.
+    Omit,
+}
+
+impl TimePolicy {
+    /// `ns` as written under this policy, `None` when omitted.
+    pub fn ns(self, ns: u128) -> Option<u128> {
+        match self {
+            TimePolicy::Exact => Some(ns),
+            TimePolicy::Bucket(0) => Some(ns),
+            TimePolicy::Bucket(width) => Some((ns + width / 2) / width * width),
+            TimePolicy::Omit => None,
+        }
+    }
+
+    /// Rewrites the time fields of `doc` and of every document nested in it.
+    pub fn apply(self, doc: &mut Json) {
+        match doc {
+            Json::Object(fields) => {
+                if self == TimePolicy::Omit {
+                    fields.retain(|(key, _)| !is_time(key));
+                }
+                for (key, value) in fields {
+                    match self {
+                        TimePolicy::Bucket(width) if width > 0 && is_time(key) => {
+                            round(value, width as f64)
+                        }
+                        _ => self.apply(value),
+                    }
+                }
+            }
+            Json::Array(items) => items.iter_mut().for_each(|item| self.apply(item)),
+            _ => {}
+        }
+    }
+}
+
+fn is_time(key: &str) -> bool {
+    key.ends_with("_ns") || key == "timings"
+}
+
+/// Every number in `value` rounded to a multiple of `width`.
+fn round(value: &mut Json, width: f64) {
+    match value {
+        Json::Number(n) => *n = (*n / width).round() * width,
+        Json::Array(items) => items.iter_mut().for_each(|item| round(item, width)),
+        Json::Object(fields) => fields.iter_mut().for_each(|(_, v)| round(v, width)),
+        _ => {}
+    }
+}
@@ -0,0 +1,439 @@
+//! Cutting a document into sections so each can be analyzed on its own.
+//!
+//! A section starts at a boundary line and runs to the next one: a Markdown
+//! heading (`# ...` to `###### ...`, outside fenced code blocks), a chapter
+//! line (`Chapter 12`, `CHAPTER XII.`) or any line matching a pattern. Text
+//! before the first boundary forms an untitled section, left out when it has
+//! no words.
+//!
+//! Patterns are a small regex subset matched against each line: literals,
+//! `.`, `[a-z]`/`[^0-9]` classes, `\d \w \s` (and `\D \W \S`), the `*`, `+`
+//! and `?` quantifiers and the `^`/`$` anchors. There are no groups or
+//! alternation. `tokenizer::Pattern` uses the same subset to find words.
+
+use crate::error::{Result, Td5Error};
+use std::ops::Range;
+
+#[derive(Debug, Clone)]
+pub enum Splitter {
+    Heading,
+    Chapter,
+    Pattern(LinePattern),
+}
+
+impl Splitter {
+    /// `heading`, `chapter` or `regex:PATTERN`.
+    pub fn parse(spec: &str) -> Result<Splitter> {
+        match spec {
+            "heading" => Ok(Splitter::Heading),
+            "chapter" => Ok(Splitter::Chapter),
+            _ => match spec.strip_prefix("regex:") {
+                Some(pattern) => LinePattern::parse(pattern).map(Splitter::Pattern),
+                None => Err(Td5Error::config(format!(
+                    "unknown split `{spec}` (expected heading, chapter or regex:PATTERN)"
+                ))),
+            },
+        }
+    }
+
+    /// The sections of `text`, in order.
+    pub fn split<'a>(&self, text: &'a str) -> Vec<Section<'a>> {
+        let mut sections = Vec::new();
+        let mut current = Section {
+            title: None,
+            line: 1,
+            text: "",
+        };
+        let mut start = 0;
+        let mut fenced = false;
+        let mut offset = 0;
+        for (index, line) in text.split_inclusive('\n').enumerate() {
+            let content = line.trim_end_matches(['\n', '\r']);
+            let title = match self {
+                Splitter::Heading => {
+                    if content.trim_start().starts_with("```") {
+                        fenced = !fenced;
+                    }
+                    if fenced { None } else { heading(content) }
+                }
+                Splitter::Chapter => chapter(content),
+                Splitter::Pattern(pattern) => pattern
+                    .is_match(content)
+                    .then(|| content.trim().to_string()),
+            };
+            if let Some(title) = title {
+                current.text = &text[start..offset];
+                if current.title.is_some() || current.text.split_whitespace().next().is_some() {
+                    sections.push(current);
+                }
+                current = Section {
+                    title: Some(title),
+                    line: index + 1,
+                    text: "",
+                };
+                start = offset;
+            }
+            offset += line.len();
+        }
+        current.text = &text[start..];
+        if current.title.is_some() || current.text.split_whitespace().next().is_some() {
+            sections.push(current);
+        }
+        sections
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section<'a> {
+    /// The heading or boundary line; `None` for text before the first one.
+    pub title: Option<String>,
+    /// 1-based line on which the section starts.
+    pub line: usize,
+    /// The section's text, boundary line included.
+    pub text: &'a str,
+}
+
+fn heading(line: &str) -> Option<String> {
+    let hashes = line.bytes().take_while(|&b| b == b'#').count();
+    let rest = &line[hashes..];
+    if !(1..=6).contains(&hashes) || !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return None;
+    }
+    Some(rest.trim().trim_end_matches('#').trim_end().to_string())
+}
+
+fn chapter(line: &str) -> Option<String> {
+    let trimmed = line.trim();
+    let word = trimmed.get(..7)?;
+    let rest = &trimmed[7..];
+    if !word.eq_ignore_ascii_case("chapter") || !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+    let number = rest.split_whitespace().next()?.trim_end_matches(['.', ':']);
+    let numeral = number.chars().all(|c| c.is_ascii_digit())
+        || number.chars().all(|c| "IVXLCDMivxlcdm".contains(c))
+        || NUMBERS
+            .split_whitespace()
+            .any(|n| number.eq_ignore_ascii_case(n));
+    (!number.is_empty() && numeral).then(|| trimmed.to_string())
+}
+
+const NUMBERS: &str = "one two three four five six seven eight nine ten eleven twelve \
+    thirteen fourteen fifteen sixteen seventeen eighteen nineteen twenty";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Atom {
+    Char(char),
+    Any,
+    /// Ranges, negated.
+    Class(Vec<(char, char)>, bool),
+}
+
+impl Atom {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Atom::Char(x) => *x == c,
+            Atom::Any => true,
+            Atom::Class(ranges, negated) => {
+                ranges.iter().any(|&(lo, hi)| (lo..=hi).contains(&c)) != *negated
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Repeat {
+    One,
+    Optional,
+    Star,
+    Plus,
+}
+
+/// An instruction of a compiled pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Inst {
+    /// Takes one character the atom matches.
+    Atom(Atom),
+    /// Goes on at both, the first preferred.
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+/// A line pattern in the regex subset described in the module docs.
+///
+/// Patterns are compiled to a small NFA run as a Pike VM: every way the
+/// pattern can go is followed at once, in order of preference, so a match
+/// takes time linear in the text, whatever the pattern. Preferring the
+/// ways in that order gives the match a backtracking matcher would find.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinePattern {
+    pub source: String,
+    program: Vec<Inst>,
+    anchored_start: bool,
+    anchored_end: bool,
+}
+
+impl LinePattern {
+    pub fn parse(source: &str) -> Result<LinePattern> {
+        let invalid = |what: &str| Td5Error::config(format!("pattern `{source}`: {what}"));
+        let mut chars = source.chars().peekable();
+        let anchored_start = chars.next_if_eq(&'^').is_some();
+        let mut items: Vec<(Atom, Repeat)> = Vec::new();
+        let mut anchored_end = false;
+        while let Some(c) = chars.next() {
+            let atom = match c {
+                '$' if chars.peek().is_none() => {
+                    anchored_end = true;
+                    break;
+                }
+                '.' => Atom::Any,
+                '\\' => escape(chars.next().ok_or_else(|| invalid("trailing `\\`"))?),
+                '[' => {
+                    let negated = chars.next_if_eq(&'^').is_some();
+                    let mut ranges = Vec::new();
+                    loop {
+                        let c = match chars.next() {
+                            None => return Err(invalid("unclosed `[`")),
+                            Some(']') if !ranges.is_empty() => break,
+                            Some('\\') => {
+                                let escaped =
+                                    chars.next().ok_or_else(|| invalid("trailing `\\`"))?;
+                                match escape(escaped) {
+                                    Atom::Char(c) => c,
+                                    Atom::Class(more, false) => {
+                                        ranges.extend(more);
+                                        continue;
+                                    }
+                                    _ => return Err(invalid("negated escape inside `[...]`")),
+                                }
+                            }
+                            Some(c) => c,
+                        };
+                        let end = if chars.peek() == Some(&'-') {
+                            chars.next();
+                            match chars.next() {
+                                Some(']') => {
+                                    ranges.extend([(c, c), ('-', '-')]);
+                                    break;
+                                }
+                                Some(end) => end,
+                                None => return Err(invalid("unclosed `[`")),
+                            }
+                        } else {
+                            c
+                        };
+                        ranges.push((c, end));
+                    }
+                    Atom::Class(ranges, negated)
+                }
+                '*' | '+' | '?' => return Err(invalid(&format!("nothing to repeat before `{c}`"))),
+                '(' | ')' | '|' | '{' | '}' => {
+                    return Err(invalid(&format!(
+                        "`{c}` is not supported; escape it as `\\{c}`"
+                    )));
+                }
+                c => Atom::Char(c),
+            };
+            let repeat = match chars.next_if(|c| matches!(c, '*' | '+' | '?')) {
+                Some('*') => Repeat::Star,
+                Some('+') => Repeat::Plus,
+                Some('?') => Repeat::Optional,
+                _ => Repeat::One,
+            };
+            items.push((atom, repeat));
+        }
+        Ok(LinePattern {
+            source: source.to_string(),
+            program: compile(&items),
+            anchored_start,
+            anchored_end,
+        })
+    }
+
+    /// Whether the pattern matches somewhere in `line`, in time linear in
+    /// its length.
+    ///
+    /// ```
+    /// use td5::sections::LinePattern;
+    ///
+    /// let pattern = LinePattern::parse(r"^Chapter \d+$").unwrap();
+    /// assert!(pattern.is_match("Chapter 12"));
+    /// assert!(!pattern.is_match("Chapter 12, continued"));
+    ///
+    /// let pattern = LinePattern::parse(r"\w*\w*\w*\w*\w*:$").unwrap();
+    /// assert!(!pattern.is_match(&"a".repeat(5000)));
+    /// ```
+    pub fn is_match(&self, line: &str) -> bool {
+        let chars: Vec<char> = line.chars().collect();
+        self.find(&chars, 0, true).is_some()
+    }
+
+    /// The byte ranges of the non-empty matches in `text` that do not
+    /// overlap, leftmost first, each quantifier taking as many characters as
+    /// it can.
+    ///
+    /// ```
+    /// use td5::sections::LinePattern;
+    ///
+    /// let pattern = LinePattern::parse(r"#?\w+").unwrap();
+    /// let text = "#rust, rust!";
+    /// let found: Vec<&str> = pattern.find_all(text).into_iter().map(|r| &text[r]).collect();
+    /// assert_eq!(found, ["#rust", "rust"]);
+    ///
+    /// // Linear in the text, even where a backtracker would take forever.
+    /// let pattern = LinePattern::parse("a*a*a*a*a*c").unwrap();
+    /// assert!(pattern.find_all(&"a".repeat(5000)).is_empty());
+    /// ```
+    pub fn find_all(&self, text: &str) -> Vec<Range<usize>> {
+        let chars: Vec<char> = text.chars().collect();
+        let offsets: Vec<usize> = text
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain([text.len()])
+            .collect();
+        let mut found = Vec::new();
+        let mut at = 0;
+        while let Some((start, end)) = self.find(&chars, at, false) {
+            found.push(offsets[start]..offsets[end]);
+            at = end;
+            if self.anchored_start {
+                break;
+            }
+        }
+        found
+    }
+
+    /// The leftmost match starting at char `from` or later (only at `from`
+    /// with `^`), as start and end chars, empty ones only if
+    /// `allow_empty`. The highest-preference thread that matches wins:
+    /// earlier starts come first, then greedier quantifiers.
+    fn find(&self, text: &[char], from: usize, allow_empty: bool) -> Option<(usize, usize)> {
+        let len = self.program.len();
+        let mut current = Threads::new(len);
+        let mut next = Threads::new(len);
+        let mut matched = None;
+        for pos in from..=text.len() {
+            if matched.is_none() && (!self.anchored_start || pos == from) {
+                self.add(&mut current, 0, pos, pos, text.len(), allow_empty);
+            }
+            if current.list.is_empty() {
+                if matched.is_some() || self.anchored_start {
+                    break;
+                }
+                current.clear();
+                continue;
+            }
+            next.clear();
+            for &(pc, start) in &current.list {
+                match &self.program[pc] {
+                    Inst::Match => {
+                        // Threads after this one are less preferred.
+                        matched = Some((start, pos));
+                        break;
+                    }
+                    Inst::Atom(atom) => {
+                        if text.get(pos).is_some_and(|&c| atom.matches(c)) {
+                            self.add(&mut next, pc + 1, start, pos + 1, text.len(), allow_empty);
+                        }
+                    }
+                    Inst::Split(..) | Inst::Jump(_) => unreachable!("`add` follows them"),
+                }
+            }
+            std::mem::swap(&mut current, &mut next);
+        }
+        matched
+    }
+
+    /// Adds the thread at `pc`, following splits and jumps, in order of
+    /// preference. A match that is empty when not allowed, or before the
+    /// end with `$`, is dropped.
+    fn add(
+        &self,
+        threads: &mut Threads,
+        pc: usize,
+        start: usize,
+        pos: usize,
+        len: usize,
+        allow_empty: bool,
+    ) {
+        let mut stack = vec![pc];
+        while let Some(pc) = stack.pop() {
+            if std::mem::replace(&mut threads.seen[pc], true) {
+                continue;
+            }
+            match self.program[pc] {
+                Inst::Split(first, second) => stack.extend([second, first]),
+                Inst::Jump(to) => stack.push(to),
+                Inst::Match
+                    if (self.anchored_end && pos != len) || (!allow_empty && pos == start) => {}
+                _ => threads.list.push((pc, start)),
+            }
+        }
+    }
+}
+
+/// The threads of a Pike VM step: instruction and start, most preferred
+/// first, each instruction at most once.
+struct Threads {
+    list: Vec<(usize, usize)>,
+    seen: Vec<bool>,
+}
+
+impl Threads {
+    fn new(len: usize) -> Self {
+        Threads {
+            list: Vec::new(),
+            seen: vec![false; len],
+        }
+    }
+
+    fn clear(&mut self) {
+        self.list.clear();
+        self.seen.fill(false);
+    }
+}
+
+/// The program of `items`: `a?` is a split around `a`, `a*` a split
+/// looping back over it, `a+` an `a` then a split back to it.
+fn compile(items: &[(Atom, Repeat)]) -> Vec<Inst> {
+    let mut program = Vec::new();
+    for (atom, repeat) in items {
+        let at = program.len();
+        match repeat {
+            Repeat::One => program.push(Inst::Atom(atom.clone())),
+            Repeat::Optional => {
+                program.push(Inst::Split(at + 1, at + 2));
+                program.push(Inst::Atom(atom.clone()));
+            }
+            Repeat::Star => {
+                program.push(Inst::Split(at + 1, at + 3));
+                program.push(Inst::Atom(atom.clone()));
+                program.push(Inst::Jump(at));
+            }
+            Repeat::Plus => {
+                program.push(Inst::Atom(atom.clone()));
+                program.push(Inst::Split(at, at + 2));
+            }
+        }
+    }
+    program.push(Inst::Match);
+    program
+}
+
+fn escape(c: char) -> Atom {
+    let class = |ranges: &[(char, char)], negated| Atom::Class(ranges.to_vec(), negated);
+    const DIGIT: &[(char, char)] = &[('0', '9')];
+    const WORD: &[(char, char)] = &[('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')];
+    const SPACE: &[(char, char)] = &[('\t', '\r'), (' ', ' ')];
+    match c {
+        'd' => class(DIGIT, false),
+        'D' => class(DIGIT, true),
+        'w' => class(WORD, false),
+        'W' => class(WORD, true),
+        's' => class(SPACE, false),
+        'S' => class(SPACE, true),
+        't' => Atom::Char('\t'),
+        c => Atom::Char(c),
+    }
+}
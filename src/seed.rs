@@ -0,0 +1,39 @@
+//! Seeds of td5's randomized features (`--seed N`): the generated inputs of
+//! `demo`, `bench` and `tune`, the texts of `selftest --differential`, the blocks
+//! `--sample` reads and the MinHash functions of `dedup`.
+//!
+//! Seed 0 is the default everywhere and gives the output td5 gave before
+//! seeds could be chosen. Reports echo the seed with `SEED_VERSION`, so a
+//! run can be reproduced exactly, or known not to be after an upgrade.
+
+use crate::json::Json;
+
+/// Bumped whenever a seed stops reproducing the output of an earlier td5:
+/// a generator (see `shapes::GENERATOR_VERSION`), the sample picker or the
+/// MinHash functions changed.
+pub const SEED_VERSION: u32 = 1;
+
+/// `{"value": seed, "version": SEED_VERSION}`, the `seed` of JSON reports.
+///
+/// ```
+/// let seed = td5::seed::to_json_value(7);
+/// assert_eq!(seed.to_string(), r#"{"value":7,"version":1}"#);
+/// ```
+pub fn to_json_value(seed: u64) -> Json {
+    Json::Object(vec![
+        ("value".into(), seed.into()),
+        ("version".into(), SEED_VERSION.into()),
+    ])
+}
+
+/// splitmix64's finalizer: 0 for 0, well spread for anything else.
+pub(crate) const fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// A number in `[0, 1)` picked by `seed`; 0 for seed 0.
+pub(crate) fn unit(seed: u64) -> f64 {
+    (mix(seed) >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -0,0 +1,218 @@
+//! Word boundaries after Unicode's UAX #29 (`Segmentation::Words`), for text
+//! the whitespace splitter gets wrong: CJK, which has no spaces, words with
+//! combining marks, and contractions.
+//!
+//! The rules are the standard's word-boundary rules WB3 to WB13b:
+//! combining marks and format characters stay with the character before them,
+//! letters and digits join, `'`, `.`, `:` and `·` between letters and `,`,
+//! `;`, `.` between digits stay inside the word, katakana runs join and
+//! `_` links anything. Han ideographs and hiragana are words of one
+//! character each. td5 carries no Unicode tables, so classes come from
+//! `char::is_alphabetic` and `char::is_numeric` plus the code point ranges
+//! below, which cover the marks of the common scripts rather than every
+//! `Extend` character. Thai, Lao, Khmer and Myanmar, which UAX #29 leaves
+//! to dictionary-based segmentation, are joined like other letters: they
+//! split only where the text has a space or punctuation.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Newline,
+    /// Combining marks, joiners and format characters (WB4).
+    Extend,
+    Letter,
+    Katakana,
+    Numeric,
+    /// Joins letters only.
+    MidLetter,
+    /// Joins digits only.
+    MidNum,
+    /// Joins letters or digits.
+    MidNumLet,
+    ExtendNumLet,
+    Space,
+    Other,
+}
+
+fn class(ch: char) -> Class {
+    match ch {
+        '\n' | '\r' | '\u{0B}' | '\u{0C}' | '\u{85}' | '\u{2028}' | '\u{2029}' => Class::Newline,
+        '\u{00AD}'
+        | '\u{0300}'..='\u{036F}'
+        | '\u{0483}'..='\u{0489}'
+        | '\u{0591}'..='\u{05BD}'
+        | '\u{05BF}'
+        | '\u{05C1}'..='\u{05C2}'
+        | '\u{05C4}'..='\u{05C5}'
+        | '\u{05C7}'
+        | '\u{0600}'..='\u{0605}'
+        | '\u{0610}'..='\u{061A}'
+        | '\u{061C}'
+        | '\u{064B}'..='\u{065F}'
+        | '\u{0670}'
+        | '\u{06D6}'..='\u{06DD}'
+        | '\u{06DF}'..='\u{06E4}'
+        | '\u{06E7}'..='\u{06E8}'
+        | '\u{06EA}'..='\u{06ED}'
+        | '\u{1AB0}'..='\u{1AFF}'
+        | '\u{1DC0}'..='\u{1DFF}'
+        | '\u{200C}'..='\u{200F}'
+        | '\u{202A}'..='\u{202E}'
+        | '\u{2060}'..='\u{2064}'
+        | '\u{2066}'..='\u{206F}'
+        | '\u{20D0}'..='\u{20FF}'
+        | '\u{302A}'..='\u{302F}'
+        | '\u{3099}'..='\u{309A}'
+        | '\u{FE00}'..='\u{FE0F}'
+        | '\u{FE20}'..='\u{FE2F}'
+        | '\u{FEFF}'
+        | '\u{FFF9}'..='\u{FFFB}'
+        | '\u{1F3FB}'..='\u{1F3FF}'
+        | '\u{E0020}'..='\u{E007F}'
+        | '\u{E0100}'..='\u{E01EF}' => Class::Extend,
+        '\u{3031}'..='\u{3035}'
+        | '\u{309B}'..='\u{309C}'
+        | '\u{30A0}'..='\u{30FA}'
+        | '\u{30FC}'..='\u{30FF}'
+        | '\u{31F0}'..='\u{31FF}'
+        | '\u{32D0}'..='\u{32FE}'
+        | '\u{3300}'..='\u{3357}'
+        | '\u{FF66}'..='\u{FF9D}' => Class::Katakana,
+        // Han and hiragana: alphabetic, but every character is a word.
+        '\u{3040}'..='\u{309F}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{20000}'..='\u{3FFFF}' => Class::Other,
+        ':' | '\u{00B7}' | '\u{0387}' | '\u{055F}' | '\u{05F4}' | '\u{2027}' | '\u{FE13}'
+        | '\u{FE55}' | '\u{FF1A}' => Class::MidLetter,
+        ',' | ';' | '\u{037E}' | '\u{0589}' | '\u{060C}' | '\u{060D}' | '\u{066C}' | '\u{07F8}'
+        | '\u{2044}' | '\u{FE10}' | '\u{FE14}' | '\u{FE50}' | '\u{FE54}' | '\u{FF0C}'
+        | '\u{FF1B}' => Class::MidNum,
+        '.' | '\'' | '\u{2018}' | '\u{2019}' | '\u{2024}' | '\u{FE52}' | '\u{FF07}'
+        | '\u{FF0E}' => Class::MidNumLet,
+        '_'
+        | '\u{202F}'
+        | '\u{203F}'
+        | '\u{2040}'
+        | '\u{2054}'
+        | '\u{FE33}'
+        | '\u{FE34}'
+        | '\u{FE4D}'..='\u{FE4F}'
+        | '\u{FF3F}' => Class::ExtendNumLet,
+        ' '
+        | '\u{1680}'
+        | '\u{2000}'..='\u{2006}'
+        | '\u{2008}'..='\u{200A}'
+        | '\u{205F}'
+        | '\u{3000}' => Class::Space,
+        _ if ch.is_alphabetic() => Class::Letter,
+        _ if ch.is_numeric() => Class::Numeric,
+        _ => Class::Other,
+    }
+}
+
+/// Every segment of a text between two word boundaries, words and what lies
+/// between them alike: joined, the segments give back the text.
+///
+/// ```
+/// use td5::segment::WordBounds;
+///
+/// let segments: Vec<&str> = WordBounds::new("Don't pay 3.50, 東京!").collect();
+/// assert_eq!(segments, ["Don't", " ", "pay", " ", "3.50", ",", " ", "東", "京", "!"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct WordBounds<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> WordBounds<'a> {
+    pub fn new(text: &'a str) -> Self {
+        WordBounds { text, pos: 0 }
+    }
+}
+
+impl<'a> Iterator for WordBounds<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let rest = &self.text[self.pos..];
+        let mut chars = rest.char_indices();
+        let (_, first) = chars.next()?;
+        let mut end = first.len_utf8();
+        let mut prev = class(first);
+        if prev == Class::Newline {
+            // WB3: CR LF is one segment; WB3a, WB3b: break around newlines.
+            if first == '\r' && rest[end..].starts_with('\n') {
+                end += 1;
+            }
+            self.pos += end;
+            return Some(&rest[..end]);
+        }
+        while let Some((at, ch)) = chars.next() {
+            let current = class(ch);
+            let joins = match (prev, current) {
+                (_, Class::Newline) => false,
+                // WB4: marks never start a segment of their own.
+                (_, Class::Extend) => true,
+                (Class::Space, Class::Space)
+                | (Class::Letter | Class::Numeric, Class::Letter | Class::Numeric)
+                | (Class::Katakana, Class::Katakana)
+                | (
+                    Class::Letter | Class::Numeric | Class::Katakana | Class::ExtendNumLet,
+                    Class::ExtendNumLet,
+                )
+                | (Class::ExtendNumLet, Class::Letter | Class::Numeric | Class::Katakana) => true,
+                // WB6, WB7, WB11, WB12: punctuation inside a word or number
+                // joins only when the same kind of character follows.
+                (Class::Letter, Class::MidLetter | Class::MidNumLet)
+                | (Class::Numeric, Class::MidNum | Class::MidNumLet) => {
+                    if next_significant(chars.clone()) == Some(prev) {
+                        // `prev` is left as is, so the letter or digit after
+                        // the punctuation joins as if it were not there.
+                        end = at + ch.len_utf8();
+                        continue;
+                    }
+                    false
+                }
+                _ => false,
+            };
+            if !joins {
+                break;
+            }
+            end = at + ch.len_utf8();
+            if current != Class::Extend {
+                prev = current;
+            }
+        }
+        self.pos += end;
+        Some(&rest[..end])
+    }
+}
+
+/// The class of the first character of `chars` that is not a mark.
+fn next_significant(chars: std::str::CharIndices) -> Option<Class> {
+    chars
+        .map(|(_, ch)| class(ch))
+        .find(|&class| class != Class::Extend)
+}
+
+/// The segments of `text` holding a letter or a digit, with their byte
+/// offsets: what `Segmentation::Words` counts as words, before the config's
+/// filters.
+///
+/// ```
+/// let words: Vec<&str> = td5::segment::words("l'été — naïve").map(|(_, w)| w).collect();
+/// assert_eq!(words, ["l'été", "naïve"]);
+/// ```
+pub fn words(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut offset = 0;
+    WordBounds::new(text).filter_map(move |segment| {
+        let start = offset;
+        offset += segment.len();
+        segment
+            .chars()
+            .any(|ch| ch.is_alphanumeric())
+            .then_some((start, segment))
+    })
+}
@@ -0,0 +1,384 @@
+//! Cross-checks every registered analyzer against the reference on a corpus
+//! of edge cases. This is what `td5 selftest` runs.
+
+use crate::TextStats;
+use crate::analyzer::{Registered, registry};
+use crate::shapes::Shape;
+
+/// Named inputs that have tripped up one implementation or another.
+pub fn corpus() -> Vec<(&'static str, String)> {
+    #[allow(unused_mut)]
+    let mut cases = vec![
+        ("empty", String::new()),
+        ("whitespace only", " \t\n  \r\n".into()),
+        ("single word", "rust".into()),
+        ("only punctuation", "... !!! ,,, -- ?? ;:".into()),
+        ("only digits", "42 1984 3.14 -7".into()),
+        ("hot words only", crate::hot_words_text(1_000)),
+        ("mixed case", "Rust RUST rust rUsT Memory memory".into()),
+        ("digits and words", "abc123 123 4ever x86 64bit".into()),
+        ("32-byte word", "a".repeat(32)),
+        ("33-byte word", "b".repeat(33)),
+        (
+            "33-byte word among lowercase",
+            format!("rust {} speed", "c".repeat(33)),
+        ),
+        (
+            "unicode",
+            "Été été ÉTÉ naïve cœur Straße straße ελληνικά Москва 日本語".into(),
+        ),
+        ("unicode case folding", "İstanbul ǅemal ΣΊΣΥΦΟΣ ﬁn".into()),
+        ("apostrophes", "l'été aujourd'hui don't it's".into()),
+        ("newlines and tabs", "one\ttwo\nthree\r\nfour  five".into()),
+        ("ties", "b a d c b a d c e".into()),
+        ("punctuated", Shape::Punctuated.generate(2_000, 1)),
+        ("zipf", Shape::Zipf.generate(5_000, 1)),
+    ];
+    #[cfg(feature = "samples")]
+    cases.extend(
+        crate::samples::all()
+            .into_iter()
+            .map(|(name, text)| (name, text.to_string())),
+    );
+    cases
+}
+
+/// One analyzer disagreeing with the reference on one case.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub case: &'static str,
+    pub analyzer: &'static str,
+    pub field: &'static str,
+    pub expected: String,
+    pub actual: String,
+}
+
+/// Fields of two stats that differ, ignoring timing. Every backend breaks
+/// ties alphabetically, so the word lists are compared as they are.
+pub fn compare(expected: &TextStats, actual: &TextStats) -> Vec<(&'static str, String, String)> {
+    let mut out = Vec::new();
+    let mut check = |field, a: String, b: String| {
+        if a != b {
+            out.push((field, a, b));
+        }
+    };
+    check(
+        "word_count",
+        expected.word_count.to_string(),
+        actual.word_count.to_string(),
+    );
+    check(
+        "total_words",
+        expected.total_words.to_string(),
+        actual.total_words.to_string(),
+    );
+    check(
+        "char_count",
+        expected.char_count.to_string(),
+        actual.char_count.to_string(),
+    );
+    check(
+        "top_words",
+        format!("{:?}", expected.top_words),
+        format!("{:?}", actual.top_words),
+    );
+    check(
+        "longest_words",
+        format!("{:?}", expected.longest_words),
+        format!("{:?}", actual.longest_words),
+    );
+    check(
+        "diagnostics",
+        format!("{:?}", expected.diagnostics),
+        format!("{:?}", actual.diagnostics),
+    );
+    out
+}
+
+/// Runs every analyzer over every case; the first registered one is the
+/// reference. An empty result means all backends agree.
+pub fn run() -> Vec<Divergence> {
+    run_with(&registry(), &corpus())
+}
+
+pub fn run_with(analyzers: &[Registered], cases: &[(&'static str, String)]) -> Vec<Divergence> {
+    let Some((reference, others)) = analyzers.split_first() else {
+        return Vec::new();
+    };
+    let mut divergences = Vec::new();
+    for (case, text) in cases {
+        let expected = reference.analyzer.analyze(text);
+        for other in others {
+            let actual = other.analyzer.analyze(text);
+            for (field, expected, actual) in compare(&expected, &actual) {
+                divergences.push(Divergence {
+                    case,
+                    analyzer: other.name,
+                    field,
+                    expected,
+                    actual,
+                });
+            }
+        }
+    }
+    divergences
+}
+
+/// A hot-only input for the allocation check: every word is in the hot
+/// vocabulary, so the fast path never needs its word map.
+pub struct HotPathCase {
+    pub name: &'static str,
+    /// Allocations the counting pass may make before its loop, whatever the
+    /// input size: the generic ASCII scanner's word buffer.
+    pub setup: usize,
+    pub text: fn(usize) -> String,
+}
+
+pub const HOT_PATH_CASES: [HotPathCase; 2] = [
+    HotPathCase {
+        name: "lowercase hot words",
+        setup: 0,
+        text: crate::hot_words_text,
+    },
+    HotPathCase {
+        name: "punctuated hot words",
+        setup: 1,
+        text: |words| {
+            crate::hot_words_text(words)
+                .split(' ')
+                .enumerate()
+                .map(|(i, word)| match i % 12 {
+                    0 => word[..1].to_uppercase() + &word[1..],
+                    5 => format!("{word},"),
+                    11 => format!("{word}.\n"),
+                    _ => word.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        },
+    },
+];
+
+/// Input sizes, in words, the allocation check compares.
+pub const HOT_PATH_SIZES: [usize; 2] = [1_000, 100_000];
+
+/// Heap allocations of the fast path on one hot-only input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AllocationRun {
+    pub case: &'static str,
+    pub words: usize,
+    /// Made by the counting pass alone.
+    pub counting: usize,
+    /// Made by a whole `analyze_text_fast` call, stats included.
+    pub analyze: usize,
+}
+
+/// Measures every `HOT_PATH_CASES` input at every `HOT_PATH_SIZES` size.
+/// `allocations` runs its argument and returns the heap allocations it
+/// made; the library has no allocator of its own, the `td5` binary passes
+/// its counting one.
+pub fn hot_path_allocations(allocations: &dyn Fn(&mut dyn FnMut()) -> usize) -> Vec<AllocationRun> {
+    let config = crate::AnalyzerConfig::default();
+    let mut runs = Vec::new();
+    for case in &HOT_PATH_CASES {
+        for words in HOT_PATH_SIZES {
+            let text = (case.text)(words);
+            // Anything initialized lazily on first use is setup too.
+            std::hint::black_box(crate::analyze_text_fast(&text));
+            let counting = allocations(&mut || {
+                std::hint::black_box(crate::fast::count(&text, &config));
+            });
+            let analyze = allocations(&mut || {
+                std::hint::black_box(crate::analyze_text_fast(&text));
+            });
+            runs.push(AllocationRun {
+                case: case.name,
+                words,
+                counting,
+                analyze,
+            });
+        }
+    }
+    runs
+}
+
+/// What breaks the hot path's guarantee in `runs`: a counting pass
+/// allocating beyond its setup, or a call allocating more on a larger input.
+pub fn allocation_failures(runs: &[AllocationRun]) -> Vec<String> {
+    let mut failures = Vec::new();
+    for case in &HOT_PATH_CASES {
+        let runs: Vec<&AllocationRun> = runs.iter().filter(|r| r.case == case.name).collect();
+        for run in &runs {
+            if run.counting > case.setup {
+                failures.push(format!(
+                    "{}: counting {} words made {} allocations, expected at most {}",
+                    case.name, run.words, run.counting, case.setup
+                ));
+            }
+        }
+        if let (Some(first), Some(last)) = (runs.first(), runs.last())
+            && last.analyze != first.analyze
+        {
+            failures.push(format!(
+                "{}: analyze made {} allocations on {} words but {} on {}",
+                case.name, first.analyze, first.words, last.analyze, last.words
+            ));
+        }
+    }
+    failures
+}
+
+/// Letters the differential check builds words from: ASCII twice over, so
+/// most inputs stay on the ASCII scanners, then accented Latin, Greek,
+/// Cyrillic and CJK, with the letters whose lowercase depends on context or
+/// is longer than one character (`İ`, `Σ`, `ﬁ`).
+const LETTERS: &[&str] = &[
+    "abcdefghijklmnopqrstuvwxyz",
+    "ABCDEFGHIJKLMNOPQRSTUVWXYZ",
+    "éèêàçüöñßÉÀÇÜÖÑİﬁ",
+    "αβγδεζηθλμπρτφωΑΒΓΔΩΣς",
+    "абвгдежзиклмнопДЖЗИЯ",
+    "日本語文字東京",
+];
+
+/// What goes around words and inside them; ASCII texts only get the ASCII
+/// part.
+const PUNCTUATION: &str = ".,;:!?'\"()-…«»0123456789";
+
+const SEPARATORS: &[&str] = &[" ", " ", " ", "  ", "\t", "\n", "\r\n", " \n\n"];
+
+/// A random text for the differential check: up to `max_words` tokens from
+/// a small vocabulary, so words repeat and tie, with punctuation, digits and
+/// mixed whitespace. About half the texts are ASCII only.
+pub fn random_text(seed: u64, max_words: usize) -> String {
+    let mut rng = crate::shapes::Rng(seed);
+    let ascii = rng.below(2) == 0;
+    let scripts = if ascii { 2 } else { LETTERS.len() };
+    let pick = |rng: &mut crate::shapes::Rng, set: &str| {
+        let chars: Vec<char> = set.chars().collect();
+        chars[rng.below(chars.len())]
+    };
+    let punctuation = |rng: &mut crate::shapes::Rng| loop {
+        let ch = pick(rng, PUNCTUATION);
+        if !ascii || ch.is_ascii() {
+            return ch;
+        }
+    };
+    let vocabulary: Vec<String> = (0..1 + rng.below(12))
+        .map(|_| {
+            let script = LETTERS[rng.below(scripts)];
+            let len = 1 + rng.below(9);
+            let mut word = String::new();
+            for _ in 0..len {
+                // Mostly one script, the odd letter from another.
+                let set = if rng.below(8) == 0 {
+                    LETTERS[rng.below(scripts)]
+                } else {
+                    script
+                };
+                word.push(pick(&mut rng, set));
+            }
+            if rng.below(3) == 0 {
+                let at = word
+                    .char_indices()
+                    .nth(rng.below(len))
+                    .map_or(0, |(at, _)| at);
+                word.insert(at, punctuation(&mut rng));
+            }
+            word
+        })
+        .collect();
+    let mut text = String::new();
+    for _ in 0..rng.below(max_words + 1) {
+        match rng.below(10) {
+            // A token without a letter.
+            0 => {
+                for _ in 0..1 + rng.below(3) {
+                    text.push(punctuation(&mut rng));
+                }
+            }
+            _ => {
+                if rng.below(6) == 0 {
+                    text.push(punctuation(&mut rng));
+                }
+                text.push_str(&vocabulary[rng.below(vocabulary.len())]);
+                if rng.below(4) == 0 {
+                    text.push(punctuation(&mut rng));
+                }
+            }
+        }
+        text.push_str(SEPARATORS[rng.below(SEPARATORS.len())]);
+    }
+    text
+}
+
+/// A random input on which `analyze_text_slow` and `analyze_text_fast`
+/// disagree, shrunk to as few tokens and characters as still disagree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Counterexample {
+    pub seed: u64,
+    pub text: String,
+    /// Field, reference value, fast value.
+    pub differences: Vec<(&'static str, String, String)>,
+}
+
+/// Checks the fast path against the reference on `cases` random texts, the
+/// `i`th generated from seed `seed + i`, and returns the first disagreement,
+/// shrunk. Fields are compared as `compare` does.
+///
+/// ```
+/// assert_eq!(td5::selftest::differential(300, 0), None);
+/// ```
+pub fn differential(cases: u64, seed: u64) -> Option<Counterexample> {
+    let differences = |text: &str| {
+        compare(
+            &crate::analyze_text_slow(text),
+            &crate::analyze_text_fast(text),
+        )
+    };
+    (0..cases).find_map(|i| {
+        let seed = seed.wrapping_add(i);
+        let text = random_text(seed, 60);
+        if differences(&text).is_empty() {
+            return None;
+        }
+        let text = shrink(text, |t| !differences(t).is_empty());
+        Some(Counterexample {
+            seed,
+            differences: differences(&text),
+            text,
+        })
+    })
+}
+
+/// Drops whitespace-separated tokens, then characters other than
+/// whitespace, from `text` as long as `fails` still holds. Tokens are never
+/// merged, so the result stays close to what `random_text` makes.
+fn shrink(mut text: String, fails: impl Fn(&str) -> bool) -> String {
+    let mut i = 0;
+    loop {
+        let tokens: Vec<&str> = text.split_whitespace().collect();
+        if i >= tokens.len() {
+            break;
+        }
+        let mut fewer = tokens.clone();
+        fewer.remove(i);
+        let candidate = fewer.join(" ");
+        if fails(&candidate) {
+            text = candidate;
+        } else {
+            i += 1;
+        }
+    }
+    let mut i = 0;
+    while let Some((at, ch)) = text.char_indices().nth(i) {
+        let mut candidate = text.clone();
+        candidate.remove(at);
+        if !ch.is_whitespace() && fails(&candidate) {
+            text = candidate;
+        } else {
+            i += 1;
+        }
+    }
+    text
+}
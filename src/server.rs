@@ -0,0 +1,361 @@
+//! A Language Server Protocol subset over standard input and output
+//! (`td5 serve`), so an editor can show live word stats and prose-lint
+//! diagnostics.
+//!
+//! Messages are JSON-RPC 2.0 framed by `Content-Length` headers. The server
+//! handles `initialize`, `shutdown`, `exit` and the `textDocument/didOpen`,
+//! `didChange` (full or incremental) and `didClose` notifications. After
+//! every change it sends `textDocument/publishDiagnostics` with the lint
+//! findings and a `td5/stats` notification with the document's `TextStats`
+//! (the versioned JSON of `td5::schema`); a `td5/stats` request returns the
+//! same on demand. Positions are LSP ones: 0-based lines and UTF-16 columns.
+//! A message longer than `Server::max_message` is skipped unread and
+//! answered with an error.
+
+use crate::incremental::AnalyzedText;
+use crate::json::{self, Json};
+use crate::lint::{Finding, LintConfig, lint};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+const PARSE_ERROR: i32 = -32700;
+const INVALID_REQUEST: i32 = -32600;
+const INVALID_PARAMS: i32 = -32602;
+const METHOD_NOT_FOUND: i32 = -32601;
+/// LSP's `DiagnosticSeverity.Information`: findings are style advice.
+const SEVERITY: u64 = 3;
+/// LSP's `TextDocumentSyncKind.Incremental`.
+const SYNC_INCREMENTAL: u64 = 2;
+/// The default `Server::max_message`.
+const MAX_MESSAGE: usize = 64 << 20;
+
+pub struct Server {
+    docs: HashMap<String, AnalyzedText>,
+    lint: LintConfig,
+    max_message: usize,
+}
+
+/// What `read_message` found.
+enum Message {
+    Body(String),
+    /// A body over the limit, skipped: its length.
+    TooLarge(usize),
+}
+
+fn object(fields: Vec<(&str, Json)>) -> Json {
+    Json::Object(fields.into_iter().map(|(k, v)| (k.into(), v)).collect())
+}
+
+fn notification(method: &str, params: Json) -> Json {
+    object(vec![
+        ("jsonrpc", "2.0".into()),
+        ("method", method.into()),
+        ("params", params),
+    ])
+}
+
+fn response(id: Json, result: Json) -> Json {
+    object(vec![
+        ("jsonrpc", "2.0".into()),
+        ("id", id),
+        ("result", result),
+    ])
+}
+
+fn error(id: Json, code: i32, message: &str) -> Json {
+    let error = object(vec![("code", code.into()), ("message", message.into())]);
+    object(vec![
+        ("jsonrpc", "2.0".into()),
+        ("id", id),
+        ("error", error),
+    ])
+}
+
+/// The byte offset of an LSP position; positions past the end of a line or
+/// of the text are clamped to it, as the protocol asks.
+fn offset(text: &str, position: &Json) -> Option<usize> {
+    let line = position.get("line")?.as_u64()? as usize;
+    let character = position.get("character")?.as_u64()? as usize;
+    let mut start = 0;
+    for _ in 0..line {
+        match text[start..].find('\n') {
+            Some(i) => start += i + 1,
+            None => return Some(text.len()),
+        }
+    }
+    let mut units = 0;
+    for (i, c) in text[start..].char_indices() {
+        if units >= character || c == '\n' {
+            return Some(start + i);
+        }
+        units += c.len_utf16();
+    }
+    Some(text.len())
+}
+
+/// The LSP range of a finding: from its 1-based line and character column to
+/// the end of the word there.
+fn range(text: &str, finding: &Finding) -> Json {
+    let line = text.split('\n').nth(finding.line - 1).unwrap_or("");
+    let mut chars = line.chars();
+    let start: usize = chars
+        .by_ref()
+        .take(finding.column - 1)
+        .map(char::len_utf16)
+        .sum();
+    let word: usize = chars
+        .take_while(|&c| c.is_alphabetic() || matches!(c, '\'' | '’'))
+        .map(char::len_utf16)
+        .sum();
+    let position = |character: usize| {
+        object(vec![
+            ("line", (finding.line - 1).into()),
+            ("character", character.into()),
+        ])
+    };
+    object(vec![
+        ("start", position(start)),
+        ("end", position(start + word)),
+    ])
+}
+
+impl Server {
+    pub fn new(lint: LintConfig) -> Self {
+        Self {
+            docs: HashMap::new(),
+            lint,
+            max_message: MAX_MESSAGE,
+        }
+    }
+
+    /// Largest message body read, in bytes (default 64 MiB).
+    ///
+    /// ```
+    /// use td5::server::Server;
+    ///
+    /// let mut server = Server::new(Default::default()).max_message(16);
+    /// let input = "Content-Length: 52\r\n\r\n{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"shutdown\"}";
+    /// let mut out = Vec::new();
+    /// server.run(input.as_bytes(), &mut out).unwrap();
+    /// let out = String::from_utf8(out).unwrap();
+    /// assert!(out.contains("message of 52 bytes over the 16-byte limit"));
+    /// ```
+    pub fn max_message(mut self, bytes: usize) -> Self {
+        self.max_message = bytes;
+        self
+    }
+
+    /// Serves framed messages until `exit` or the end of `input`.
+    pub fn run(&mut self, mut input: impl BufRead, mut out: impl Write) -> io::Result<()> {
+        let mut replies = Vec::new();
+        while let Some(message) = read_message(&mut input, self.max_message)? {
+            let body = match message {
+                Message::Body(body) => body,
+                Message::TooLarge(len) => {
+                    let message = format!(
+                        "message of {len} bytes over the {}-byte limit",
+                        self.max_message
+                    );
+                    replies.push(error(Json::Null, INVALID_REQUEST, &message));
+                    String::new()
+                }
+            };
+            let keep_going = match json::parse(&body) {
+                _ if body.is_empty() => true,
+                Ok(message) => self.handle(&message, &mut replies),
+                Err(e) => {
+                    replies.push(error(Json::Null, PARSE_ERROR, &e.to_string()));
+                    true
+                }
+            };
+            for reply in replies.drain(..) {
+                let body = reply.to_string();
+                write!(out, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+            }
+            out.flush()?;
+            if !keep_going {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles one message, pushing what to send back onto `replies`.
+    /// Returns `false` once the client sent `exit`.
+    pub fn handle(&mut self, message: &Json, replies: &mut Vec<Json>) -> bool {
+        let method = message.get("method").and_then(Json::as_str).unwrap_or("");
+        let params = message.get("params").unwrap_or(&Json::Null);
+        let uri = params
+            .get("textDocument")
+            .and_then(|d| d.get("uri"))
+            .and_then(Json::as_str)
+            .map(str::to_string);
+        let Some(id) = message.get("id").cloned() else {
+            // A notification: no reply, errors included.
+            match (method, uri) {
+                ("exit", _) => return false,
+                ("textDocument/didOpen", Some(uri)) => {
+                    let text = params
+                        .get("textDocument")
+                        .and_then(|d| d.get("text"))
+                        .and_then(Json::as_str)
+                        .unwrap_or("");
+                    self.docs.insert(uri.clone(), AnalyzedText::new(text));
+                    self.publish(&uri, replies);
+                }
+                ("textDocument/didChange", Some(uri)) => {
+                    let changes = params.get("contentChanges").and_then(Json::as_array);
+                    if let Some(doc) = self.docs.get_mut(&uri) {
+                        for change in changes.unwrap_or(&[]) {
+                            apply_change(doc, change, replies);
+                        }
+                        self.publish(&uri, replies);
+                    }
+                }
+                ("textDocument/didClose", Some(uri)) => {
+                    self.docs.remove(&uri);
+                    replies.push(notification(
+                        "textDocument/publishDiagnostics",
+                        object(vec![
+                            ("uri", uri.as_str().into()),
+                            ("diagnostics", Json::Array(Vec::new())),
+                        ]),
+                    ));
+                }
+                _ => {}
+            }
+            return true;
+        };
+        replies.push(match (method, uri) {
+            ("initialize", _) => response(
+                id,
+                object(vec![
+                    (
+                        "capabilities",
+                        object(vec![(
+                            "textDocumentSync",
+                            object(vec![
+                                ("openClose", true.into()),
+                                ("change", SYNC_INCREMENTAL.into()),
+                            ]),
+                        )]),
+                    ),
+                    (
+                        "serverInfo",
+                        object(vec![
+                            ("name", "td5".into()),
+                            ("version", env!("CARGO_PKG_VERSION").into()),
+                        ]),
+                    ),
+                ]),
+            ),
+            ("shutdown", _) => response(id, Json::Null),
+            ("td5/stats", Some(uri)) => match self.docs.get(&uri) {
+                Some(doc) => response(id, doc.stats().to_json_value()),
+                None => error(id, INVALID_PARAMS, &format!("{uri} is not open")),
+            },
+            _ => error(
+                id,
+                METHOD_NOT_FOUND,
+                &format!("unsupported method `{method}`"),
+            ),
+        });
+        true
+    }
+
+    fn publish(&self, uri: &str, replies: &mut Vec<Json>) {
+        let Some(doc) = self.docs.get(uri) else {
+            return;
+        };
+        let diagnostics = lint(doc.text(), &self.lint)
+            .findings
+            .iter()
+            .map(|finding| {
+                object(vec![
+                    ("range", range(doc.text(), finding)),
+                    ("severity", SEVERITY.into()),
+                    ("source", "td5".into()),
+                    ("code", finding.rule.name().into()),
+                    ("message", finding.message.as_str().into()),
+                ])
+            })
+            .collect();
+        replies.push(notification(
+            "textDocument/publishDiagnostics",
+            object(vec![
+                ("uri", uri.into()),
+                ("diagnostics", Json::Array(diagnostics)),
+            ]),
+        ));
+        replies.push(notification(
+            "td5/stats",
+            object(vec![
+                ("uri", uri.into()),
+                ("stats", doc.stats().to_json_value()),
+            ]),
+        ));
+    }
+}
+
+/// Applies one `TextDocumentContentChangeEvent`: the whole text without a
+/// `range`, an edit with one.
+fn apply_change(doc: &mut AnalyzedText, change: &Json, replies: &mut Vec<Json>) {
+    let text = change.get("text").and_then(Json::as_str).unwrap_or("");
+    let Some(range) = change.get("range") else {
+        *doc = AnalyzedText::new(text);
+        return;
+    };
+    let edit = range
+        .get("start")
+        .and_then(|p| offset(doc.text(), p))
+        .zip(range.get("end").and_then(|p| offset(doc.text(), p)));
+    let result = match edit {
+        Some((start, end)) => doc.apply_edit(start..end, text).map_err(|e| e.to_string()),
+        None => Err("change without a valid range".to_string()),
+    };
+    if let Err(message) = result {
+        // `window/logMessage` type 1 is an error.
+        replies.push(notification(
+            "window/logMessage",
+            object(vec![("type", 1u64.into()), ("message", message.into())]),
+        ));
+    }
+}
+
+/// The next message, `None` at the end of the input. A body over `max`
+/// bytes is read past without being kept.
+fn read_message(input: &mut impl BufRead, max: usize) -> io::Result<Option<Message>> {
+    let mut length = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            if length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("content-length")
+        {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+    let length = length.unwrap_or(0);
+    if length > max {
+        io::copy(
+            &mut io::Read::take(&mut *input, length as u64),
+            &mut io::sink(),
+        )?;
+        return Ok(Some(Message::TooLarge(length)));
+    }
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    String::from_utf8(body)
+        .map(|body| Some(Message::Body(body)))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
@@ -0,0 +1,459 @@
+//! Deterministic text generators covering the different fast-path modes.
+//!
+//! `hot_words_text` only repeats the ten hot words, so it never leaves the
+//! hot-only shortcut. Each `Shape` here targets another scanner or a larger
+//! vocabulary; the same `(shape, words, seed)` always yields the same text.
+//! `TextGenerator`, behind `generate_test_text`, mixes them into something
+//! closer to prose.
+
+use std::fmt;
+use std::path::PathBuf;
+
+/// Bumped whenever a generator's output changes, so texts cached on disk by
+/// an older td5 are not reused.
+pub const GENERATOR_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shape {
+    /// Lowercase ASCII words and single spaces (simple-lower scanner).
+    LowerAscii,
+    /// Capitalized and upper-case ASCII words (general ASCII scanner).
+    MixedAscii,
+    /// ASCII words wrapped in punctuation, digits and newlines.
+    Punctuated,
+    /// Accented and non-Latin words (Unicode scanner).
+    Unicode,
+    /// Large lowercase vocabulary with Zipf-distributed frequencies.
+    Zipf,
+    /// Lowercase words that never repeat, in random order: the worst case
+    /// for the table of non-hot words, which grows with every word.
+    Distinct,
+}
+
+impl Shape {
+    pub const ALL: [Shape; 6] = [
+        Shape::LowerAscii,
+        Shape::MixedAscii,
+        Shape::Punctuated,
+        Shape::Unicode,
+        Shape::Zipf,
+        Shape::Distinct,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Shape::LowerAscii => "lower-ascii",
+            Shape::MixedAscii => "mixed-ascii",
+            Shape::Punctuated => "punctuated",
+            Shape::Unicode => "unicode",
+            Shape::Zipf => "zipf",
+            Shape::Distinct => "distinct",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Shape> {
+        Shape::ALL.into_iter().find(|s| s.name() == name)
+    }
+
+    /// Where `generate(words, seed)` is cached: `generated/` in the corpus
+    /// cache directory.
+    pub fn cache_path(self, words: usize, seed: u64) -> PathBuf {
+        crate::corpus::cache_dir().join("generated").join(format!(
+            "{}-{words}-{seed}-v{GENERATOR_VERSION}.txt",
+            self.name()
+        ))
+    }
+
+    /// `generate`, read from the disk cache when `cache` applies and the text
+    /// is there, else generated and stored for the next run. Caching is best
+    /// effort: a cache that cannot be written only costs the regeneration.
+    pub fn generate_cached(self, words: usize, seed: u64, cache: InputCache) -> String {
+        if !cache.applies(words) {
+            return self.generate(words, seed);
+        }
+        let path = self.cache_path(words, seed);
+        if let Ok(text) = std::fs::read_to_string(&path) {
+            return text;
+        }
+        let text = self.generate(words, seed);
+        if let Some(dir) = path.parent()
+            && std::fs::create_dir_all(dir).is_ok()
+        {
+            // Write next to the target and rename, so an interrupted run or a
+            // concurrent one never leaves a truncated text in the cache.
+            let partial = path.with_extension(format!("{}.part", std::process::id()));
+            if std::fs::write(&partial, &text).is_err() || std::fs::rename(&partial, &path).is_err()
+            {
+                let _ = std::fs::remove_file(&partial);
+            }
+        }
+        text
+    }
+
+    /// A text of `words` tokens separated by whitespace.
+    pub fn generate(self, words: usize, seed: u64) -> String {
+        let mut rng = Rng(seed ^ 0x9E37_79B9_7F4A_7C15);
+        let mut out = String::with_capacity(words * 8);
+        match self {
+            Shape::LowerAscii => {
+                let vocab = vocabulary(1_000);
+                fill(&mut out, words, |out| {
+                    out.push_str(&vocab[rng.below(vocab.len())])
+                });
+            }
+            Shape::MixedAscii => {
+                let vocab = vocabulary(1_000);
+                fill(&mut out, words, |out| {
+                    let word = &vocab[rng.below(vocab.len())];
+                    match rng.below(4) {
+                        0 => out.push_str(&word.to_ascii_uppercase()),
+                        1 => {
+                            out.push(word.as_bytes()[0].to_ascii_uppercase() as char);
+                            out.push_str(&word[1..]);
+                        }
+                        _ => out.push_str(word),
+                    }
+                });
+            }
+            Shape::Punctuated => {
+                const BEFORE: &[&str] = &["", "", "\"", "(", "'", "--"];
+                const AFTER: &[&str] = &["", ",", ".", ";", "!", "?)", "'s", "42", ":\n"];
+                let vocab = vocabulary(1_000);
+                fill(&mut out, words, |out| {
+                    out.push_str(BEFORE[rng.below(BEFORE.len())]);
+                    out.push_str(&vocab[rng.below(vocab.len())]);
+                    out.push_str(AFTER[rng.below(AFTER.len())]);
+                });
+            }
+            Shape::Unicode => fill(&mut out, words, |out| {
+                out.push_str(UNICODE_WORDS[rng.below(UNICODE_WORDS.len())])
+            }),
+            Shape::Zipf => {
+                let vocab = vocabulary(50_000);
+                let zipf = Zipf::new(vocab.len());
+                fill(&mut out, words, |out| {
+                    out.push_str(&vocab[zipf.sample(&mut rng)])
+                });
+            }
+            Shape::Distinct => {
+                let mut vocab = vocabulary(words);
+                for i in (1..vocab.len()).rev() {
+                    vocab.swap(i, rng.below(i + 1));
+                }
+                let mut vocab = vocab.into_iter();
+                fill(&mut out, words, |out| {
+                    out.push_str(&vocab.next().unwrap_or_default())
+                });
+            }
+        }
+        out
+    }
+}
+
+/// Prose-like text: Zipf-distributed words from a vocabulary whose most
+/// frequent entries are the ten hot words, so the fast path's shortcut and
+/// its hash map both get their share, cut into sentences and paragraphs.
+///
+/// ```
+/// use td5::shapes::TextGenerator;
+///
+/// let text = TextGenerator::new().seed(7).vocabulary(500).generate(1_000);
+/// let stats = td5::analyze(&text);
+/// assert_eq!(stats.total_words, 1_000);
+/// assert!(stats.sentence_count > Some(20) && stats.word_count > 100);
+/// assert_eq!(text, TextGenerator::new().seed(7).vocabulary(500).generate(1_000));
+///
+/// let plain = TextGenerator::new().punctuation(false).capitalization(false).generate(50);
+/// assert!(plain.bytes().all(|b| b.is_ascii_lowercase() || b == b' '));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextGenerator {
+    seed: u64,
+    vocabulary: usize,
+    punctuation: bool,
+    capitalization: bool,
+    unicode: bool,
+}
+
+impl Default for TextGenerator {
+    fn default() -> Self {
+        TextGenerator {
+            seed: 0,
+            vocabulary: TextGenerator::DEFAULT_VOCABULARY,
+            punctuation: true,
+            capitalization: true,
+            unicode: false,
+        }
+    }
+}
+
+impl TextGenerator {
+    pub const DEFAULT_VOCABULARY: usize = 10_000;
+
+    /// Seed 0, `DEFAULT_VOCABULARY` words, punctuation and capitals, ASCII
+    /// only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Distinct words drawn from, hot words included; at least 1.
+    pub fn vocabulary(mut self, words: usize) -> Self {
+        self.vocabulary = words.max(1);
+        self
+    }
+
+    /// Commas, sentence ends and a blank line between paragraphs.
+    pub fn punctuation(mut self, on: bool) -> Self {
+        self.punctuation = on;
+        self
+    }
+
+    /// Sentence starts in title case and one word in fifty in capitals.
+    pub fn capitalization(mut self, on: bool) -> Self {
+        self.capitalization = on;
+        self
+    }
+
+    /// One word in twenty taken from accented and non-Latin words.
+    pub fn unicode(mut self, on: bool) -> Self {
+        self.unicode = on;
+        self
+    }
+
+    /// A text of `words` words.
+    pub fn generate(&self, words: usize) -> String {
+        let mut rng = Rng(self.seed ^ 0x9E37_79B9_7F4A_7C15);
+        let vocab: Vec<String> = HOT_WORDS
+            .iter()
+            .map(|word| word.to_string())
+            .chain(vocabulary(self.vocabulary.saturating_sub(HOT_WORDS.len())))
+            .take(self.vocabulary)
+            .collect();
+        let zipf = Zipf::new(vocab.len());
+        let mut out = String::with_capacity(words * 8);
+        // Words left in the sentence and sentences left in the paragraph.
+        let (mut sentence, mut paragraph) = (0, 0);
+        for i in 0..words {
+            let start = sentence == 0;
+            let new_paragraph = start && paragraph == 0;
+            if start {
+                sentence = 4 + rng.below(16);
+            }
+            if new_paragraph {
+                paragraph = 2 + rng.below(5);
+            }
+            if i > 0 {
+                out.push_str(if self.punctuation && new_paragraph {
+                    "\n\n"
+                } else {
+                    " "
+                });
+            }
+            let word = if self.unicode && rng.below(20) == 0 {
+                UNICODE_WORDS[rng.below(UNICODE_WORDS.len())]
+            } else {
+                vocab[zipf.sample(&mut rng)].as_str()
+            };
+            if self.capitalization && rng.below(50) == 0 {
+                out.push_str(&word.to_uppercase());
+            } else if self.capitalization && start {
+                let mut chars = word.chars();
+                out.extend(chars.next().into_iter().flat_map(char::to_uppercase));
+                out.push_str(chars.as_str());
+            } else {
+                out.push_str(word);
+            }
+            sentence -= 1;
+            if !self.punctuation {
+                continue;
+            }
+            if sentence == 0 || i + 1 == words {
+                out.push(['.', '.', '.', '.', '?', '!'][rng.below(6)]);
+                paragraph -= 1;
+            } else if rng.below(10) == 0 {
+                out.push(',');
+            }
+        }
+        out
+    }
+}
+
+/// When generated inputs go through the disk cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InputCache {
+    /// Inputs of `InputCache::AUTO_WORDS` words or more, whose generation
+    /// takes longer than reading them back.
+    #[default]
+    Auto,
+    Always,
+    Off,
+}
+
+impl InputCache {
+    pub const AUTO_WORDS: usize = 1_000_000;
+
+    pub fn from_name(name: &str) -> Option<InputCache> {
+        match name {
+            "auto" => Some(InputCache::Auto),
+            "always" => Some(InputCache::Always),
+            "off" => Some(InputCache::Off),
+            _ => None,
+        }
+    }
+
+    pub fn applies(self, words: usize) -> bool {
+        match self {
+            InputCache::Auto => words >= InputCache::AUTO_WORDS,
+            InputCache::Always => true,
+            InputCache::Off => false,
+        }
+    }
+}
+
+/// `words` tokens drawn uniformly from `vocabulary`, so every word is as
+/// likely to be looked up and the counting table's working set is the whole
+/// vocabulary.
+pub(crate) fn uniform(vocabulary: &[String], words: usize, seed: u64) -> String {
+    let mut rng = Rng(seed ^ 0x9E37_79B9_7F4A_7C15);
+    let mut out = String::with_capacity(words * 8);
+    fill(&mut out, words, |out| {
+        out.push_str(&vocabulary[rng.below(vocabulary.len())])
+    });
+    out
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+fn fill(out: &mut String, words: usize, mut push_word: impl FnMut(&mut String)) {
+    for i in 0..words {
+        if i > 0 {
+            out.push(' ');
+        }
+        push_word(out);
+    }
+}
+
+/// The words of `hot_words_text`, the fast path's default hot words.
+pub(crate) const HOT_WORDS: [&str; 10] = [
+    "rust",
+    "performance",
+    "optimization",
+    "memory",
+    "speed",
+    "efficiency",
+    "benchmark",
+    "algorithm",
+    "data",
+    "structure",
+];
+
+const UNICODE_WORDS: &[&str] = &[
+    "été",
+    "Élève",
+    "naïve",
+    "façade",
+    "cœur",
+    "Noël",
+    "déjà",
+    "où",
+    "français",
+    "über",
+    "Straße",
+    "größe",
+    "mañana",
+    "niño",
+    "ação",
+    "ÇA",
+    "zürich",
+    "ελληνικά",
+    "λόγος",
+    "Москва",
+    "слово",
+    "日本語",
+    "東京",
+    "한국어",
+    "ﬁn",
+    "rust",
+    "mémoire",
+    "rapidité",
+    "données",
+    "algorithme",
+];
+
+const SYLLABLES: &[&str] = &[
+    "ba", "ce", "di", "fo", "gu", "ka", "le", "mi", "no", "pu", "ra", "se", "ti", "vo", "zu", "an",
+    "er", "is", "ol", "ur", "str", "th", "pl", "gr",
+];
+
+/// `n` distinct lowercase ASCII words built from syllables.
+pub(crate) fn vocabulary(n: usize) -> Vec<String> {
+    (0..n)
+        .map(|mut i| {
+            // Bijective base-|SYLLABLES| numbering, so every index is unique.
+            let mut word = String::new();
+            loop {
+                word.push_str(SYLLABLES[i % SYLLABLES.len()]);
+                i /= SYLLABLES.len();
+                if i == 0 {
+                    break word;
+                }
+                i -= 1;
+            }
+        })
+        .collect()
+}
+
+/// SplitMix64; good enough for test data and needs no dependency.
+pub(crate) struct Rng(pub(crate) u64);
+
+impl Rng {
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Rank `k` (0-based) is drawn with probability proportional to `1 / (k + 1)`.
+struct Zipf {
+    cumulative: Vec<f64>,
+}
+
+impl Zipf {
+    fn new(n: usize) -> Self {
+        let mut total = 0.0;
+        let cumulative = (1..=n)
+            .map(|k| {
+                total += 1.0 / k as f64;
+                total
+            })
+            .collect();
+        Self { cumulative }
+    }
+
+    fn sample(&self, rng: &mut Rng) -> usize {
+        let target = rng.unit() * self.cumulative.last().copied().unwrap_or(0.0);
+        self.cumulative
+            .partition_point(|&c| c < target)
+            .min(self.cumulative.len() - 1)
+    }
+}
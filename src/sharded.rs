@@ -0,0 +1,242 @@
+//! Shared-map parallel backend, kept as a foil for `parallel`.
+//!
+//! All threads count into one map split into mutex-protected shards. There is
+//! no merge step, but every word takes a lock, and on skewed (Zipf-like)
+//! vocabularies the few very frequent words keep hitting the same shards, so
+//! threads queue on each other. Benchmark it against `parallel` to see the
+//! contention cost.
+//!
+//! With a progress callback, each thread scans its chunk in pieces of
+//! `AnalyzerConfig::progress_every` bytes and reports after each.
+
+use crate::clock::Stopwatch;
+use crate::error::Result;
+use crate::fast::{self, Counts, Limited, Shape, Sink};
+use crate::ngrams::NGrams;
+use crate::parallel::{PhaseTimings, effective_threads, split_chunks};
+use crate::wordmap::{self, WordMap};
+use crate::{Analyzer, AnalyzerConfig, Diagnostics, TextStats};
+use std::ops::Range;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/// Sharded parallel analyzer; `threads == 0` uses all cores, `shards == 0`
+/// uses four shards per thread.
+#[derive(Debug, Clone, Default)]
+pub struct Sharded {
+    pub threads: usize,
+    pub shards: usize,
+    pub config: AnalyzerConfig,
+}
+
+impl Analyzer for Sharded {
+    fn analyze(&self, text: &str) -> TextStats {
+        analyze_text_sharded_timed(text, self.threads, self.shards, &self.config).0
+    }
+}
+
+struct ShardedMap {
+    shards: Vec<Mutex<WordMap>>,
+}
+
+impl ShardedMap {
+    fn new(n: usize) -> Self {
+        Self {
+            shards: (0..n.max(1)).map(|_| Mutex::default()).collect(),
+        }
+    }
+
+    fn add(&self, word: &str) {
+        let hash = wordmap::hash(word);
+        let idx = hash as usize % self.shards.len();
+        self.shards[idx]
+            .lock()
+            .expect("shard lock poisoned")
+            .add_hashed(word, hash, 1);
+    }
+
+    fn into_words(self) -> WordMap {
+        let mut shards = self
+            .shards
+            .into_iter()
+            .map(|m| m.into_inner().expect("shard lock poisoned"));
+        let mut all = shards.next().unwrap_or_default();
+        for shard in shards {
+            all.merge(&shard);
+        }
+        all
+    }
+}
+
+struct SharedSink<'a> {
+    map: &'a ShardedMap,
+    chars: usize,
+    /// N-grams and the shape stay per chunk, to be merged in text order.
+    ngrams: Option<NGrams>,
+    shape: Shape,
+}
+
+impl Sink for SharedSink<'_> {
+    fn ascii_word(&mut self, word: &[u8], span: Range<usize>) {
+        self.shape.word(word.len(), span.end);
+        // SAFETY: scanners only hand out lowercase ASCII here
+        let word = unsafe { std::str::from_utf8_unchecked(word) };
+        self.map.add(word);
+        if let Some(ngrams) = &mut self.ngrams {
+            ngrams.push(word);
+        }
+    }
+
+    fn unicode_word(&mut self, word: &str, span: Range<usize>) {
+        self.shape.word(word.chars().count(), span.end);
+        self.map.add(word);
+        if let Some(ngrams) = &mut self.ngrams {
+            ngrams.push(word);
+        }
+    }
+
+    fn add_chars(&mut self, n: usize) {
+        self.chars += n;
+    }
+
+    fn newlines(&mut self, n: usize) {
+        self.shape.newlines(n);
+    }
+
+    fn sentence_ends(&mut self, n: usize, last: usize) {
+        self.shape.sentence_ends(n, last);
+    }
+}
+
+/// What a thread counted besides the shared map.
+struct Counted {
+    chars: usize,
+    diagnostics: Diagnostics,
+    ngrams: Option<NGrams>,
+    shape: Shape,
+}
+
+fn count_shared(
+    chunks: &[&str],
+    mode: fast::Mode,
+    shards: usize,
+    config: &AnalyzerConfig,
+) -> Result<(ShardedMap, Counted)> {
+    let map = ShardedMap::new(shards);
+    let (processed, tokens) = (AtomicUsize::new(0), AtomicUsize::new(0));
+    let per_thread = thread::scope(|s| {
+        let handles: Vec<_> = chunks
+            .iter()
+            .map(|chunk| {
+                let (map, processed, tokens) = (&map, &processed, &tokens);
+                s.spawn(move || {
+                    let mut sink = SharedSink {
+                        map,
+                        chars: 0,
+                        ngrams: NGrams::new(config.ngrams),
+                        shape: Shape::default(),
+                    };
+                    let pieces = match config.progress {
+                        Some(_) => split_chunks(chunk, chunk.len().div_ceil(config.progress_bytes)),
+                        None => vec![*chunk],
+                    };
+                    let mut diagnostics = Diagnostics::default();
+                    let mut shape = Shape::default();
+                    for piece in pieces {
+                        let mut limited = Limited::new(&mut sink, config);
+                        fast::scan(piece, mode, config, &mut limited)?;
+                        diagnostics.merge(&limited.diagnostics);
+                        // Word and sentence offsets are the piece's own.
+                        sink.shape.seal(piece);
+                        let words = sink.shape.words();
+                        shape.merge(&std::mem::take(&mut sink.shape));
+                        if let Some(progress) = &config.progress {
+                            let done =
+                                processed.fetch_add(piece.len(), Ordering::Relaxed) + piece.len();
+                            let counted = tokens.fetch_add(words, Ordering::Relaxed) + words;
+                            progress.report(done, counted);
+                        }
+                    }
+                    Ok(Counted {
+                        chars: sink.chars,
+                        diagnostics,
+                        ngrams: sink.ngrams,
+                        shape,
+                    })
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().expect("counting thread panicked"))
+            .collect::<Result<Vec<Counted>>>()
+    })?;
+    let mut all = Counted {
+        chars: 0,
+        diagnostics: Diagnostics::default(),
+        ngrams: None,
+        shape: Shape::default(),
+    };
+    for counted in per_thread {
+        all.chars += counted.chars;
+        all.diagnostics.merge(&counted.diagnostics);
+        all.shape.merge(&counted.shape);
+        match (&mut all.ngrams, counted.ngrams) {
+            (Some(ours), Some(theirs)) => ours.merge(theirs),
+            (None, theirs) => all.ngrams = theirs,
+            (Some(_), None) => {}
+        }
+    }
+    Ok((map, all))
+}
+
+pub fn analyze_text_sharded(text: &str, threads: usize) -> TextStats {
+    analyze_text_sharded_timed(text, threads, 0, &AnalyzerConfig::default()).0
+}
+
+/// Same phases as `analyze_text_parallel_timed`; `merge_ns` is the time spent
+/// draining the shards into one table.
+pub fn analyze_text_sharded_timed(
+    text: &str,
+    threads: usize,
+    shards: usize,
+    config: &AnalyzerConfig,
+) -> (TextStats, PhaseTimings) {
+    let mut phase = Stopwatch::start();
+    let threads = effective_threads(threads);
+    let shards = if shards == 0 { threads * 4 } else { shards };
+
+    let (text, controls) = config.control_text(text);
+    let mode = fast::detect(&text);
+    let chunks = split_chunks(&text, threads);
+    let split_ns = phase.elapsed_ns();
+    phase = Stopwatch::start();
+
+    // The shared map cannot be rolled back per chunk, so if the simple path
+    // hits an over-long word the whole text is recounted with the ASCII scanner.
+    let (map, counted) = count_shared(&chunks, mode, shards, config)
+        .or_else(|_| count_shared(&chunks, fast::Mode::Ascii, shards, config))
+        .expect("the ASCII scanner has no length limit");
+    let count_ns = phase.elapsed_ns();
+    phase = Stopwatch::start();
+
+    let mut counts = Counts::from_words(map.into_words(), counted.chars);
+    counts.diagnostics = counted.diagnostics;
+    counts.diagnostics.control_chars = controls;
+    counts.ngrams = counted.ngrams;
+    counts.shape = counted.shape;
+    let merge_ns = phase.elapsed_ns();
+    phase = Stopwatch::start();
+
+    let stats = fast::finish(counts, config);
+    let timings = PhaseTimings {
+        threads: chunks.len(),
+        split_ns,
+        count_ns,
+        merge_ns,
+        merge_rounds: 0,
+        finish_ns: phase.elapsed_ns(),
+    };
+    (stats, timings)
+}
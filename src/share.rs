@@ -0,0 +1,164 @@
+//! The top words of an analysis packed into a string that can sit in a URL
+//! (`td5 share`), for links to a web viewer that redraws the distribution
+//! without a server.
+//!
+//! The payload is `1.TOTAL.WORD_COUNT.WORD_COUNT...`: the format version, the
+//! total word count, then the words most frequent first. Numbers are base 36.
+//! ASCII letters and digits are kept as they are and every other byte of a
+//! word is percent-encoded (`%C3%A9` for `é`), so `_` and `.` only ever
+//! appear as separators and the whole payload uses URL-safe characters;
+//! `decodeURIComponent` reads a word back in a browser.
+
+use crate::TextStats;
+use crate::error::{Result, Td5Error};
+use crate::freq::WordFrequency;
+use crate::json::Json;
+
+const VERSION: &str = "1";
+
+/// The head of a word distribution, as carried by a share payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopWords {
+    /// Counted words in the whole text, so a viewer can show shares.
+    pub total_words: usize,
+    /// Most frequent first.
+    pub words: Vec<(String, usize)>,
+}
+
+impl TopWords {
+    /// The top ten of `stats`.
+    pub fn from_stats(stats: &TextStats) -> TopWords {
+        TopWords {
+            total_words: stats.total_words,
+            words: stats.top_words.clone(),
+        }
+    }
+
+    /// The `top` most frequent words of `freq`.
+    pub fn from_freq(freq: &WordFrequency, top: usize) -> TopWords {
+        TopWords {
+            total_words: freq.total(),
+            words: freq
+                .top_k(top)
+                .into_iter()
+                .map(|(word, count)| (word.to_string(), count))
+                .collect(),
+        }
+    }
+
+    /// The URL-safe payload.
+    ///
+    /// ```
+    /// use td5::share::TopWords;
+    ///
+    /// let top = TopWords {
+    ///     total_words: 1000,
+    ///     words: vec![("été".into(), 40), ("a_b.c%".into(), 3), ("<long>".into(), 1)],
+    /// };
+    /// let payload = top.encode();
+    /// assert_eq!(payload, "1.rs.%C3%A9t%C3%A9_14.a%5Fb%2Ec%25_3.%3Clong%3E_1");
+    /// assert_eq!(TopWords::decode(&payload).unwrap(), top);
+    /// ```
+    pub fn encode(&self) -> String {
+        let mut out = format!("{VERSION}.{}", base36(self.total_words));
+        for (word, count) in &self.words {
+            out.push('.');
+            for &byte in word.as_bytes() {
+                if byte.is_ascii_alphanumeric() {
+                    out.push(byte as char);
+                } else {
+                    out.push_str(&format!("%{byte:02X}"));
+                }
+            }
+            out.push('_');
+            out.push_str(&base36(*count));
+        }
+        out
+    }
+
+    /// Reads a payload back; anything before a `#` is ignored, so a whole
+    /// share link is accepted too.
+    pub fn decode(payload: &str) -> Result<TopWords> {
+        let invalid = |what: String| Td5Error::config(format!("share payload: {what}"));
+        let payload = payload.rsplit_once('#').map_or(payload, |(_, p)| p).trim();
+        let mut parts = payload.split('.');
+        match parts.next() {
+            Some(VERSION) => {}
+            Some(version) => return Err(invalid(format!("unsupported version `{version}`"))),
+            None => return Err(invalid("empty".into())),
+        }
+        let total = parts
+            .next()
+            .ok_or_else(|| invalid("missing the total word count".into()))?;
+        let total_words =
+            number(total).ok_or_else(|| invalid(format!("invalid total `{total}`")))?;
+        let mut words = Vec::new();
+        for entry in parts {
+            let (word, count) = entry
+                .rsplit_once('_')
+                .ok_or_else(|| invalid(format!("entry `{entry}` has no count")))?;
+            let count = number(count).ok_or_else(|| invalid(format!("invalid count `{count}`")))?;
+            let word = unescape(word).ok_or_else(|| invalid(format!("invalid word `{word}`")))?;
+            words.push((word, count));
+        }
+        Ok(TopWords { total_words, words })
+    }
+
+    pub fn to_json_value(&self) -> Json {
+        let words = self
+            .words
+            .iter()
+            .map(|(word, count)| Json::Array(vec![word.as_str().into(), (*count).into()]))
+            .collect();
+        Json::Object(vec![
+            ("total_words".into(), self.total_words.into()),
+            ("top_words".into(), Json::Array(words)),
+        ])
+    }
+}
+
+fn base36(mut n: usize) -> String {
+    const DIGITS: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+    let mut digits = Vec::new();
+    loop {
+        digits.push(DIGITS[n % 36]);
+        n /= 36;
+        if n == 0 {
+            break;
+        }
+    }
+    digits.reverse();
+    String::from_utf8(digits).expect("base 36 digits are ASCII")
+}
+
+fn number(digits: &str) -> Option<usize> {
+    // `from_str_radix` takes a leading `+`, which `encode` never writes.
+    if digits.starts_with('+') {
+        return None;
+    }
+    usize::from_str_radix(digits, 36).ok()
+}
+
+/// Undoes the percent-encoding of a word; `None` on a malformed escape, a
+/// character `encode` would have escaped, or bytes that are not UTF-8.
+fn unescape(word: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(word.len());
+    let mut rest = word.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte.is_ascii_alphanumeric() {
+            bytes.push(byte);
+            rest = tail;
+        } else if let [b'%', high, low, ..] = rest
+            && high.is_ascii_hexdigit()
+            && low.is_ascii_hexdigit()
+        {
+            let hex = [*high, *low];
+            let hex = std::str::from_utf8(&hex).expect("hex digits are ASCII");
+            bytes.push(u8::from_str_radix(hex, 16).expect("checked hex digits"));
+            rest = &rest[3..];
+        } else {
+            return None;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
@@ -0,0 +1,421 @@
+//! Vectorized ASCII tokenizer (the `simd` feature).
+//!
+//! The scalar scanner looks at one byte at a time and branches on it. This
+//! one classifies 64 bytes at once into bitmasks (word characters, spaces,
+//! upper case) with SSE2, or AVX2 when the CPU has it (checked at run time),
+//! and walks the masks instead: `trailing_zeros` finds where each word
+//! starts and ends, `count_ones` counts letters, and words already in lower
+//! case reach the sink as slices of the input, never copied. Runs of word
+//! characters with only punctuation between them are one word (`don't`
+//! counts as `dont`), copied without the punctuation. Targets other
+//! than x86-64 build the masks with a plain loop and keep the rest.
+//!
+//! Lines and sentence ends come from two more masks: `\n` bytes, and `.`,
+//! `!` and `?` bytes followed by a space.
+//!
+//! Tokens without a letter (`--`, `...`) are counted with the carry of an
+//! addition: adding the first byte of every such run that follows a space
+//! to the mask of punctuation bytes ripples a carry to the byte after the
+//! run, and the run was a whole token when that byte is a space.
+//!
+//! The output is the scalar scanner's, byte for byte.
+
+use crate::fast::Sink;
+
+/// Classes of 64 consecutive bytes, one bit per byte, lowest bit first.
+#[derive(Debug, Clone, Copy, Default)]
+struct Masks {
+    /// Letters, and digits when they count.
+    word: u64,
+    /// The ASCII characters `char::is_whitespace` accepts.
+    space: u64,
+    upper: u64,
+    newline: u64,
+    /// `.`, `!` and `?`.
+    stop: u64,
+}
+
+impl Masks {
+    /// `self` with the masks of `part` put at bit `at`.
+    #[inline(always)]
+    fn with(self, part: Masks, at: usize) -> Masks {
+        Masks {
+            word: self.word | part.word << at,
+            space: self.space | part.space << at,
+            upper: self.upper | part.upper << at,
+            newline: self.newline | part.newline << at,
+            stop: self.stop | part.stop << at,
+        }
+    }
+}
+
+trait Classify {
+    /// # Safety
+    ///
+    /// The CPU must support the instructions the implementation uses.
+    unsafe fn classify<const DIGITS: bool>(block: &[u8; 64]) -> Masks;
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+struct Scalar;
+
+#[cfg(not(target_arch = "x86_64"))]
+impl Classify for Scalar {
+    #[inline(always)]
+    unsafe fn classify<const DIGITS: bool>(block: &[u8; 64]) -> Masks {
+        let mut masks = Masks::default();
+        for (i, &b) in block.iter().enumerate() {
+            let bit = 1u64 << i;
+            if b.is_ascii_alphabetic() || (DIGITS && b.is_ascii_digit()) {
+                masks.word |= bit;
+            }
+            if b.is_ascii_uppercase() {
+                masks.upper |= bit;
+            }
+            if matches!(b, b' ' | b'\t' | b'\n' | b'\r' | 0x0b | 0x0c) {
+                masks.space |= bit;
+            }
+            if b == b'\n' {
+                masks.newline |= bit;
+            }
+            if matches!(b, b'.' | b'!' | b'?') {
+                masks.stop |= bit;
+            }
+        }
+        masks
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use super::{Classify, Masks};
+    use std::arch::x86_64::*;
+
+    /// Bytes of `v` in `lo..=hi`, as 0xff lanes: `v - lo <= hi - lo`
+    /// unsigned, tested as `min(v - lo, hi - lo) == v - lo`.
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    fn in_range_128(v: __m128i, lo: u8, hi: u8) -> __m128i {
+        let shifted = _mm_sub_epi8(v, _mm_set1_epi8(lo as i8));
+        _mm_cmpeq_epi8(
+            _mm_min_epu8(shifted, _mm_set1_epi8((hi - lo) as i8)),
+            shifted,
+        )
+    }
+
+    /// 16 bytes at `block[at..]`, as 16-bit masks.
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    fn classify_16<const DIGITS: bool>(block: &[u8; 64], at: usize) -> Masks {
+        // SAFETY: `at + 16 <= 64`; the load is unaligned.
+        let v = unsafe { _mm_loadu_si128(block.as_ptr().add(at).cast()) };
+        // `| 0x20` lowercases letters and maps no other byte into a..=z.
+        let letters = in_range_128(_mm_or_si128(v, _mm_set1_epi8(0x20)), b'a', b'z');
+        let word = if DIGITS {
+            _mm_or_si128(letters, in_range_128(v, b'0', b'9'))
+        } else {
+            letters
+        };
+        let space = _mm_or_si128(
+            in_range_128(v, b'\t', b'\r'),
+            _mm_cmpeq_epi8(v, _mm_set1_epi8(b' ' as i8)),
+        );
+        let upper = in_range_128(v, b'A', b'Z');
+        let is = |b: u8| _mm_cmpeq_epi8(v, _mm_set1_epi8(b as i8));
+        let stop = _mm_or_si128(_mm_or_si128(is(b'.'), is(b'!')), is(b'?'));
+        let bits = |lanes| u64::from(_mm_movemask_epi8(lanes) as u16);
+        Masks {
+            word: bits(word),
+            space: bits(space),
+            upper: bits(upper),
+            newline: bits(is(b'\n')),
+            stop: bits(stop),
+        }
+    }
+
+    /// SSE2, which every x86-64 CPU has.
+    pub(super) struct Sse2;
+
+    impl Classify for Sse2 {
+        #[inline(always)]
+        unsafe fn classify<const DIGITS: bool>(block: &[u8; 64]) -> Masks {
+            let mut masks = Masks::default();
+            for lane in 0..4 {
+                // SAFETY: SSE2 is part of x86-64.
+                let part = unsafe { classify_16::<DIGITS>(block, lane * 16) };
+                masks = masks.with(part, lane * 16);
+            }
+            masks
+        }
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    fn in_range_256(v: __m256i, lo: u8, hi: u8) -> __m256i {
+        let shifted = _mm256_sub_epi8(v, _mm256_set1_epi8(lo as i8));
+        _mm256_cmpeq_epi8(
+            _mm256_min_epu8(shifted, _mm256_set1_epi8((hi - lo) as i8)),
+            shifted,
+        )
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    fn classify_32<const DIGITS: bool>(block: &[u8; 64], at: usize) -> Masks {
+        // SAFETY: `at + 32 <= 64`; the load is unaligned.
+        let v = unsafe { _mm256_loadu_si256(block.as_ptr().add(at).cast()) };
+        let letters = in_range_256(_mm256_or_si256(v, _mm256_set1_epi8(0x20)), b'a', b'z');
+        let word = if DIGITS {
+            _mm256_or_si256(letters, in_range_256(v, b'0', b'9'))
+        } else {
+            letters
+        };
+        let space = _mm256_or_si256(
+            in_range_256(v, b'\t', b'\r'),
+            _mm256_cmpeq_epi8(v, _mm256_set1_epi8(b' ' as i8)),
+        );
+        let upper = in_range_256(v, b'A', b'Z');
+        let is = |b: u8| _mm256_cmpeq_epi8(v, _mm256_set1_epi8(b as i8));
+        let stop = _mm256_or_si256(_mm256_or_si256(is(b'.'), is(b'!')), is(b'?'));
+        let bits = |lanes| u64::from(_mm256_movemask_epi8(lanes) as u32);
+        Masks {
+            word: bits(word),
+            space: bits(space),
+            upper: bits(upper),
+            newline: bits(is(b'\n')),
+            stop: bits(stop),
+        }
+    }
+
+    pub(super) struct Avx2;
+
+    impl Classify for Avx2 {
+        #[inline(always)]
+        unsafe fn classify<const DIGITS: bool>(block: &[u8; 64]) -> Masks {
+            // SAFETY: only called under `scan_avx2`, once AVX2 is detected.
+            let (low, high) = unsafe {
+                (
+                    classify_32::<DIGITS>(block, 0),
+                    classify_32::<DIGITS>(block, 32),
+                )
+            };
+            low.with(high, 32)
+        }
+    }
+
+    /// `scan_blocks` compiled with AVX2 enabled, so the classification
+    /// inlines into the loop.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn scan_avx2<S: crate::fast::Sink, const FOLD: bool, const DIGITS: bool>(
+        text: &str,
+        sink: &mut S,
+    ) {
+        super::scan_blocks::<S, Avx2, FOLD, DIGITS>(text, sink);
+    }
+}
+
+/// The scalar `scan_ascii`'s output for `text`, computed a block at a time.
+/// Uses AVX2 when the CPU has it, else SSE2 on x86-64, else plain loops.
+pub(crate) fn scan_ascii<S: Sink, const FOLD: bool, const DIGITS: bool>(text: &str, sink: &mut S) {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if std::is_x86_feature_detected!("avx2") {
+            // SAFETY: AVX2 was just detected.
+            return unsafe { x86::scan_avx2::<S, FOLD, DIGITS>(text, sink) };
+        }
+        scan_blocks::<S, x86::Sse2, FOLD, DIGITS>(text, sink)
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    scan_blocks::<S, Scalar, FOLD, DIGITS>(text, sink)
+}
+
+/// What a block leaves for the next one.
+struct State {
+    /// From the first to the last word byte of the current token so far,
+    /// until a space ends the token.
+    word: Option<std::ops::Range<usize>>,
+    /// Whether that word has an upper-case letter so far.
+    word_upper: bool,
+    /// Whether punctuation splits its word bytes.
+    word_split: bool,
+    /// Whether the last block ended with a space (true at the start: the
+    /// text begins a token).
+    after_space: bool,
+    /// Whether the last block ended inside a token of punctuation that
+    /// began after a space.
+    punctuation_carry: bool,
+    /// Whether the last block ended with a `.`, `!` or `?`.
+    stop_carry: bool,
+    char_count: usize,
+    non_alphabetic: usize,
+    newlines: usize,
+    sentence_ends: usize,
+    last_sentence_end: usize,
+    /// Upper-case words folded to lower case.
+    buf: Vec<u8>,
+}
+
+#[inline(always)]
+fn scan_blocks<S: Sink, C: Classify, const FOLD: bool, const DIGITS: bool>(
+    text: &str,
+    sink: &mut S,
+) {
+    let bytes = text.as_bytes();
+    let mut state = State {
+        word: None,
+        word_upper: false,
+        word_split: false,
+        after_space: true,
+        punctuation_carry: false,
+        stop_carry: false,
+        char_count: 0,
+        non_alphabetic: 0,
+        newlines: 0,
+        sentence_ends: 0,
+        last_sentence_end: 0,
+        buf: Vec::with_capacity(32),
+    };
+    let mut blocks = bytes.chunks_exact(64);
+    let mut base = 0;
+    for block in &mut blocks {
+        let block = block.try_into().expect("chunks of 64");
+        // SAFETY: `scan_ascii` picks `C` for the running CPU.
+        let masks = unsafe { C::classify::<DIGITS>(block) };
+        state.block::<S, FOLD, DIGITS>(bytes, base, masks, sink);
+        base += 64;
+    }
+    let rest = blocks.remainder();
+    if !rest.is_empty() {
+        // Spaces past the end close the last word and token.
+        let mut padded = [b' '; 64];
+        padded[..rest.len()].copy_from_slice(rest);
+        // SAFETY: as above.
+        let masks = unsafe { C::classify::<DIGITS>(&padded) };
+        state.block::<S, FOLD, DIGITS>(bytes, base, masks, sink);
+    }
+    state.flush::<S, FOLD, DIGITS>(bytes, sink);
+    state.non_alphabetic += usize::from(state.punctuation_carry);
+    if state.stop_carry {
+        state.sentence_ends += 1;
+        state.last_sentence_end = bytes.len();
+    }
+    sink.add_chars(state.char_count);
+    sink.non_alphabetic(state.non_alphabetic);
+    sink.newlines(state.newlines);
+    sink.sentence_ends(state.sentence_ends, state.last_sentence_end);
+}
+
+/// Bits `0..n`, for `n < 64`.
+#[inline(always)]
+fn below(n: u32) -> u64 {
+    (1u64 << n) - 1
+}
+
+impl State {
+    #[inline(always)]
+    fn block<S: Sink, const FOLD: bool, const DIGITS: bool>(
+        &mut self,
+        bytes: &[u8],
+        base: usize,
+        masks: Masks,
+        sink: &mut S,
+    ) {
+        self.char_count += masks.word.count_ones() as usize;
+        self.newlines += masks.newline.count_ones() as usize;
+        if self.stop_carry && masks.space & 1 != 0 {
+            self.sentence_ends += 1;
+            self.last_sentence_end = base;
+        }
+        // Bit 63 is followed by the next block's first byte.
+        let ends = masks.stop & masks.space >> 1;
+        if ends != 0 {
+            self.sentence_ends += ends.count_ones() as usize;
+            self.last_sentence_end = base + 64 - ends.leading_zeros() as usize;
+        }
+        self.stop_carry = masks.stop >> 63 != 0;
+
+        let punctuation = !(masks.word | masks.space);
+        let starts = punctuation & (masks.space << 1 | u64::from(self.after_space));
+        let (sum, carry) = punctuation.overflowing_add(starts);
+        let (sum, carry_in) = sum.overflowing_add(u64::from(self.punctuation_carry));
+        // The byte after each run of punctuation that began a token.
+        let after = sum & !punctuation;
+        self.non_alphabetic += (after & masks.space).count_ones() as usize;
+        self.punctuation_carry = carry || carry_in;
+        self.after_space = masks.space >> 63 != 0;
+
+        let mut words = masks.word;
+        while words != 0 {
+            let start = words.trailing_zeros();
+            let past = !words & (u64::MAX << start);
+            let end = if past == 0 { 64 } else { past.trailing_zeros() };
+            let run = match end {
+                64 => u64::MAX << start,
+                _ => below(end) & !below(start),
+            };
+            let upper = masks.upper & run != 0;
+            let span = base + start as usize..base + end as usize;
+            match &mut self.word {
+                // The same run, carried over from the last block.
+                Some(word) if word.end == span.start => word.end = span.end,
+                // Only punctuation since the word's last byte.
+                Some(word)
+                    if masks.space
+                        & below(start)
+                        & !below(word.end.saturating_sub(base) as u32)
+                        == 0 =>
+                {
+                    word.end = span.end;
+                    self.word_split = true;
+                }
+                _ => {
+                    self.flush::<S, FOLD, DIGITS>(bytes, sink);
+                    self.word = Some(span.start..span.end);
+                    self.word_split = false;
+                }
+            }
+            self.word_upper |= upper;
+            if end == 64 {
+                break;
+            }
+            words &= !below(end);
+        }
+        // A space after the word's last byte ends its token.
+        let ended = self.word.as_ref().is_some_and(|word| {
+            let after = word.end.saturating_sub(base) as u32;
+            after < 64 && masks.space & (u64::MAX << after) != 0
+        });
+        if ended {
+            self.flush::<S, FOLD, DIGITS>(bytes, sink);
+        }
+    }
+
+    /// Hands the current token's word to the sink, if it has one.
+    #[inline(always)]
+    fn flush<S: Sink, const FOLD: bool, const DIGITS: bool>(&mut self, bytes: &[u8], sink: &mut S) {
+        let Some(span) = self.word.take() else {
+            return;
+        };
+        let upper = std::mem::take(&mut self.word_upper);
+        let word = &bytes[span.clone()];
+        if self.word_split {
+            self.buf.clear();
+            self.buf.extend(
+                word.iter()
+                    .filter(|b| b.is_ascii_alphabetic() || (DIGITS && b.is_ascii_digit()))
+                    .map(|&b| if FOLD { b | 0b0010_0000 } else { b }),
+            );
+            sink.ascii_word(&self.buf, span);
+        } else if FOLD && upper {
+            self.buf.clear();
+            self.buf.extend(word.iter().map(|b| b | 0b0010_0000));
+            sink.ascii_word(&self.buf, span);
+        } else {
+            sink.ascii_word(word, span);
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! Rules for leaving inputs out of a multi-file run (`td5 analyze docs/`):
+//! images, archives and other binary files, files outside a size range and
+//! documents too short to say anything.
+//!
+//! The size is checked before a file is read, its content once it is, by
+//! `sniff`: binary formats, NUL bytes and too many control characters are
+//! skipped, and so are compressed inputs td5 cannot decompress. The word
+//! count is checked after the analysis.
+
+use crate::TextStats;
+use crate::error::{Result, Td5Error};
+use crate::sniff::{self, Compression, Kind, MAX_NON_TEXT, SAMPLE_BYTES};
+use std::fmt;
+use std::io::Read;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkipRules {
+    pub min_bytes: u64,
+    pub max_bytes: Option<u64>,
+    /// Whether binary and unreadable compressed files are skipped.
+    pub binary: bool,
+    /// Largest share of control bytes in a text file.
+    pub max_non_text: f64,
+    pub min_words: usize,
+}
+
+impl Default for SkipRules {
+    fn default() -> Self {
+        Self {
+            min_bytes: 0,
+            max_bytes: None,
+            binary: true,
+            max_non_text: MAX_NON_TEXT,
+            min_words: 0,
+        }
+    }
+}
+
+/// Why an input was skipped.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Skip {
+    TooSmall {
+        bytes: u64,
+        min: u64,
+    },
+    TooLarge {
+        bytes: u64,
+        max: u64,
+    },
+    /// The format `sniff` found, `"data"` for NUL bytes without a known
+    /// signature.
+    Binary {
+        format: &'static str,
+    },
+    /// Compressed in a format td5 does not read.
+    Compressed(Compression),
+    /// `share` of the sample is control bytes.
+    NonText {
+        share: f64,
+    },
+    TooShort {
+        words: usize,
+        min: usize,
+    },
+}
+
+impl Skip {
+    /// A stable name for scripts: `too_small`, `too_large`, `binary`,
+    /// `compressed`, `non_text` or `too_short`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Skip::TooSmall { .. } => "too_small",
+            Skip::TooLarge { .. } => "too_large",
+            Skip::Binary { .. } => "binary",
+            Skip::Compressed(_) => "compressed",
+            Skip::NonText { .. } => "non_text",
+            Skip::TooShort { .. } => "too_short",
+        }
+    }
+}
+
+impl fmt::Display for Skip {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Skip::TooSmall { bytes, min } => write!(f, "under {min} bytes ({bytes})"),
+            Skip::TooLarge { bytes, max } => write!(f, "over {max} bytes ({bytes})"),
+            Skip::Binary { format } => write!(f, "binary: {format}"),
+            Skip::Compressed(compression) => write!(f, "{compression} compressed"),
+            Skip::NonText { share } => {
+                write!(f, "{:.0}% non-text bytes", share * 100.0)
+            }
+            Skip::TooShort { words, min } => write!(f, "under {min} words ({words})"),
+        }
+    }
+}
+
+impl SkipRules {
+    pub fn check_size(&self, bytes: u64) -> Option<Skip> {
+        if bytes < self.min_bytes {
+            return Some(Skip::TooSmall {
+                bytes,
+                min: self.min_bytes,
+            });
+        }
+        self.max_bytes
+            .filter(|&max| bytes > max)
+            .map(|max| Skip::TooLarge { bytes, max })
+    }
+
+    /// The content rules on the start of an input.
+    ///
+    /// ```
+    /// use td5::skip::{Skip, SkipRules};
+    ///
+    /// let rules = SkipRules::default();
+    /// assert_eq!(rules.check_content(b"Plain text,\r\n\tindented."), None);
+    /// let png = rules.check_content(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR");
+    /// assert_eq!(png, Some(Skip::Binary { format: "PNG image" }));
+    /// assert_eq!(rules.check_content(&[0x02, 0x03, b'a', b'b']), Some(Skip::NonText { share: 0.5 }));
+    /// // Read through `sniff::decompress`.
+    /// assert_eq!(rules.check_content(b"\x1f\x8b\x08\0\0\0\0\0\0\x03"), None);
+    /// ```
+    pub fn check_content(&self, bytes: &[u8]) -> Option<Skip> {
+        if !self.binary {
+            return None;
+        }
+        match sniff::sniff_with(bytes, self.max_non_text) {
+            Kind::Text | Kind::Epub => None,
+            Kind::Compressed(compression) if compression.is_supported() => None,
+            Kind::Compressed(compression) => Some(Skip::Compressed(compression)),
+            Kind::Binary(format) => Some(Skip::Binary { format }),
+            Kind::Control(share) => Some(Skip::NonText { share }),
+        }
+    }
+
+    /// The size and content rules on the file at `path`, reading only its
+    /// first `SAMPLE_BYTES`.
+    pub fn check_file(&self, path: &Path) -> Result<Option<Skip>> {
+        let io_error = |source| Td5Error::Io {
+            path: Some(path.to_path_buf()),
+            source,
+        };
+        let size = std::fs::metadata(path).map_err(io_error)?.len();
+        if let Some(skip) = self.check_size(size) {
+            return Ok(Some(skip));
+        }
+        if !self.binary {
+            return Ok(None);
+        }
+        let mut sample = Vec::with_capacity(SAMPLE_BYTES);
+        std::fs::File::open(path)
+            .and_then(|file| file.take(SAMPLE_BYTES as u64).read_to_end(&mut sample))
+            .map_err(io_error)?;
+        Ok(self.check_content(&sample))
+    }
+
+    pub fn check_words(&self, stats: &TextStats) -> Option<Skip> {
+        (stats.total_words < self.min_words).then_some(Skip::TooShort {
+            words: stats.total_words,
+            min: self.min_words,
+        })
+    }
+}
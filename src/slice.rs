@@ -0,0 +1,180 @@
+//! Analyzing one region of a huge file: `--range 1GB..2GB` by byte offset,
+//! `--lines 1000..2000` by line number.
+//!
+//! A byte range is widened to whole lines, the same way on both ends: a line
+//! is in the range when its first byte is, so two adjacent ranges never cut a
+//! word in two or count it twice. Only the region is read, after a seek for
+//! byte ranges; a line range reads up to its last line and stops there.
+
+use crate::error::{Result, Td5Error};
+use crate::json::Json;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// A region of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Range {
+    /// Byte offsets, `end` excluded; `None` runs to the end of the file.
+    Bytes { start: u64, end: Option<u64> },
+    /// Line numbers from 1, both included.
+    Lines { first: u64, last: Option<u64> },
+}
+
+/// What a range read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Slice {
+    /// Byte offsets of the lines read, `end` excluded.
+    pub start: u64,
+    pub end: u64,
+    pub file_bytes: u64,
+}
+
+/// A byte count with an optional `K`, `M` or `G` suffix, powers of 1024,
+/// optionally followed by `B` or `iB`: `500000`, `64K`, `50MB`, `1GiB`.
+pub fn parse_size(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let text = text
+        .strip_suffix("iB")
+        .or_else(|| text.strip_suffix(['B', 'b']))
+        .unwrap_or(text);
+    let (digits, unit) = match text.char_indices().last()? {
+        (i, 'k' | 'K') => (&text[..i], 1 << 10),
+        (i, 'm' | 'M') => (&text[..i], 1 << 20),
+        (i, 'g' | 'G') => (&text[..i], 1 << 30),
+        (i, 't' | 'T') => (&text[..i], 1 << 40),
+        _ => (text, 1),
+    };
+    digits.trim().parse::<u64>().ok()?.checked_mul(unit)
+}
+
+/// `bytes` the way `parse_size` reads them, rounded: `512`, `48K`, `2.5M`.
+pub fn format_size(bytes: u64) -> String {
+    let units = [
+        (1u64 << 40, 'T'),
+        (1 << 30, 'G'),
+        (1 << 20, 'M'),
+        (1 << 10, 'K'),
+    ];
+    match units.into_iter().find(|&(unit, _)| bytes >= unit) {
+        Some((unit, suffix)) if bytes.is_multiple_of(unit) => format!("{}{suffix}", bytes / unit),
+        Some((unit, suffix)) => format!("{:.1}{suffix}", bytes as f64 / unit as f64),
+        None => bytes.to_string(),
+    }
+}
+
+/// `START..END`, either side optional.
+fn bounds<T>(spec: &str, parse: impl Fn(&str) -> Option<T>) -> Option<(Option<T>, Option<T>)> {
+    let (start, end) = spec.split_once("..")?;
+    let bound = |s: &str| match s.trim() {
+        "" => Some(None),
+        s => parse(s).map(Some),
+    };
+    Some((bound(start)?, bound(end)?))
+}
+
+impl Range {
+    /// `1GB..2GB`, `..500M` or `100M..`.
+    pub fn parse_bytes(spec: &str) -> Result<Range> {
+        match bounds(spec, parse_size) {
+            Some((start, end)) if end.is_none_or(|end| end > start.unwrap_or(0)) => {
+                Ok(Range::Bytes {
+                    start: start.unwrap_or(0),
+                    end,
+                })
+            }
+            _ => Err(Td5Error::config(format!(
+                "expected a byte range such as 1GB..2GB, ..500M or 100M.., got `{spec}`"
+            ))),
+        }
+    }
+
+    /// `1000..2000`, `..500` or `100..`, counting from line 1.
+    pub fn parse_lines(spec: &str) -> Result<Range> {
+        let line = |s: &str| s.parse::<u64>().ok().filter(|&n| n >= 1);
+        match bounds(spec, line) {
+            Some((first, last)) if last.is_none_or(|last| last >= first.unwrap_or(1)) => {
+                Ok(Range::Lines {
+                    first: first.unwrap_or(1),
+                    last,
+                })
+            }
+            _ => Err(Td5Error::config(format!(
+                "expected a line range such as 1000..2000, ..500 or 100.., got `{spec}`"
+            ))),
+        }
+    }
+
+    /// The lines of `path` in the range.
+    pub fn read(&self, path: &Path) -> Result<(Vec<u8>, Slice)> {
+        let io_error = |source| Td5Error::Io {
+            path: Some(path.to_path_buf()),
+            source,
+        };
+        let file = File::open(path).map_err(io_error)?;
+        let file_bytes = file.metadata().map_err(io_error)?.len();
+        let mut reader = BufReader::new(file);
+        let mut bytes = Vec::new();
+        let (start, end) = match *self {
+            Range::Bytes { start, end } => {
+                let start = line_start(&mut reader, start.min(file_bytes)).map_err(io_error)?;
+                let end = line_start(&mut reader, end.unwrap_or(file_bytes).min(file_bytes))
+                    .map_err(io_error)?;
+                reader.seek(SeekFrom::Start(start)).map_err(io_error)?;
+                (&mut reader)
+                    .take(end.saturating_sub(start))
+                    .read_to_end(&mut bytes)
+                    .map_err(io_error)?;
+                (start, end.max(start))
+            }
+            Range::Lines { first, last } => {
+                let mut offset = 0;
+                let mut start = None;
+                let mut line = Vec::new();
+                for number in 1.. {
+                    if last.is_some_and(|last| number > last) {
+                        break;
+                    }
+                    line.clear();
+                    let n = reader.read_until(b'\n', &mut line).map_err(io_error)?;
+                    if n == 0 {
+                        break;
+                    }
+                    if number >= first {
+                        start.get_or_insert(offset);
+                        bytes.extend_from_slice(&line);
+                    }
+                    offset += n as u64;
+                }
+                (start.unwrap_or(offset), offset)
+            }
+        };
+        let slice = Slice {
+            start,
+            end,
+            file_bytes,
+        };
+        Ok((bytes, slice))
+    }
+}
+
+/// The first line start at or after `offset`.
+pub(crate) fn line_start(reader: &mut (impl BufRead + Seek), offset: u64) -> io::Result<u64> {
+    if offset == 0 {
+        return Ok(0);
+    }
+    reader.seek(SeekFrom::Start(offset - 1))?;
+    let mut skipped = Vec::new();
+    let n = reader.read_until(b'\n', &mut skipped)?;
+    Ok(offset - 1 + n as u64)
+}
+
+impl Slice {
+    pub fn to_json_value(&self) -> Json {
+        Json::Object(vec![
+            ("start".into(), self.start.into()),
+            ("end".into(), self.end.into()),
+            ("file_bytes".into(), self.file_bytes.into()),
+        ])
+    }
+}
@@ -0,0 +1,312 @@
+use crate::clock::Stopwatch;
+use crate::config::Segmentation;
+use crate::fast::{LENGTH_SLOTS, Timings, is_sentence_end};
+use crate::segment::WordBounds;
+use crate::{AnalyzerConfig, Diagnostics, TextStats};
+use std::collections::HashMap;
+
+// --------------------------- VERSION LENTE ---------------------------
+pub fn analyze_text_slow(text: &str) -> TextStats {
+    analyze_text_slow_with(text, &AnalyzerConfig::default())
+}
+
+pub fn analyze_text_slow_with(text: &str, config: &AnalyzerConfig) -> TextStats {
+    analyze_text_slow_timed(text, config).0
+}
+
+/// `analyze_text_slow_with`, with the time of each phase. Unlike the fast
+/// path, this one tokenizes the text a second time, for word lengths,
+/// n-grams and longest words.
+///
+/// ```
+/// use td5::{AnalyzerConfig, analyze_text_slow_timed};
+///
+/// let (stats, timings) = analyze_text_slow_timed("the cat sat", &AnalyzerConfig::new());
+/// assert_eq!(stats.total_words, 3);
+/// assert!(timings.tokenize_ns > 0 && timings.count_ns > 0);
+/// ```
+pub fn analyze_text_slow_timed(text: &str, config: &AnalyzerConfig) -> (TextStats, Timings) {
+    let start = Stopwatch::start();
+    let mut timings = Timings::default();
+
+    let mut diagnostics = Diagnostics::default();
+    let (text, controls) = config.control_text(text);
+    let text = &*text;
+    diagnostics.control_chars = controls;
+    let mut word_freq = HashMap::new();
+    let (mut bytes_done, mut tokens_done, mut reported) = (0, 0, 0);
+    for line in text.lines() {
+        if let Some(progress) = &config.progress
+            && bytes_done - reported >= config.progress_bytes
+        {
+            progress.report(bytes_done, tokens_done);
+            reported = bytes_done;
+        }
+        // The line and its line break.
+        bytes_done += line.len() + 1;
+        for clean_word in tokens(line, config)
+            .into_iter()
+            .flat_map(|token| words(token, config))
+        {
+            if clean_word.is_empty() {
+                diagnostics.non_alphabetic += 1;
+                continue;
+            }
+            if clean_word.chars().count() < config.min_word_length {
+                diagnostics.short_words += 1;
+                continue;
+            }
+            let (kept, long) = config.limit_word(&clean_word);
+            if long {
+                diagnostics.long_tokens += 1;
+            }
+            match kept {
+                Some(kept) => {
+                    *word_freq.entry(kept.to_string()).or_insert(0) += 1;
+                    tokens_done += 1;
+                }
+                None => diagnostics.long_tokens_skipped += 1,
+            }
+        }
+    }
+    if let Some(progress) = &config.progress
+        && reported < text.len()
+    {
+        progress.report(text.len(), tokens_done);
+    }
+    timings.count_ns = start.elapsed_ns();
+
+    let phase = Stopwatch::start();
+    let mut top_words = Vec::new();
+    let mut surface_forms = Vec::new();
+    let mut word_count = word_freq.len();
+    if let Some(stems) = config.top_stems(word_freq.iter().map(|(w, c)| (w.as_str(), *c))) {
+        (top_words, surface_forms, word_count) = stems;
+    } else {
+        for _ in 0..config.top_k {
+            let mut max_word = String::new();
+            let mut max_count = 0;
+
+            for (word, count) in &word_freq {
+                let mut found = false;
+                for (existing_word, _) in &top_words {
+                    if word == existing_word {
+                        found = true;
+                        break;
+                    }
+                }
+
+                // Ties go to the alphabetically first word, as in `by_count`.
+                let ahead = *count > max_count || (*count == max_count && *word < max_word);
+                if !found && ahead && !config.is_stopword(word) {
+                    max_word = word.clone();
+                    max_count = *count;
+                }
+            }
+
+            if max_count > 0 {
+                top_words.push((max_word, max_count));
+            }
+        }
+    }
+    timings.top_k_ns = phase.elapsed_ns();
+
+    let mut char_count = 0;
+    for line in text.lines() {
+        if config.tokenizer.is_some() {
+            char_count += tokens(line, config)
+                .iter()
+                .map(|token| config.normalized(token).chars().count())
+                .sum::<usize>();
+            continue;
+        }
+        if config.normalizes() {
+            char_count += tokens(line, config)
+                .iter()
+                .map(|token| {
+                    let token = config.normalized(token);
+                    token.chars().filter(|&ch| config.is_word_char(ch)).count()
+                })
+                .sum::<usize>();
+            continue;
+        }
+        for ch in line.chars() {
+            if config.is_word_char(ch) {
+                char_count += 1;
+            }
+        }
+    }
+
+    let phase = Stopwatch::start();
+    let mut all_words = Vec::new();
+    for line in text.lines() {
+        for clean in tokens(line, config)
+            .into_iter()
+            .flat_map(|token| words(token, config))
+        {
+            if clean.chars().count() >= config.min_word_length
+                && let (Some(kept), _) = config.limit_word(&clean)
+            {
+                all_words.push(kept.to_string());
+            }
+        }
+    }
+    timings.tokenize_ns = phase.elapsed_ns();
+
+    let mut lengths: Vec<usize> = all_words.iter().map(|w| w.chars().count()).collect();
+    lengths.sort();
+    let mut word_lengths = vec![0; lengths.last().map_or(0, |&n| n.min(LENGTH_SLOTS))];
+    for &length in &lengths {
+        word_lengths[length.min(LENGTH_SLOTS) - 1] += 1;
+    }
+    let mean_word_length = lengths.iter().sum::<usize>() as f64 / lengths.len().max(1) as f64;
+    let median_word_length = lengths
+        .get(lengths.len() / 2)
+        .map_or(0, |&n| n.min(LENGTH_SLOTS));
+
+    let mut sentence_count = 0;
+    let mut open_sentence = false;
+    for token in text.split_whitespace() {
+        if counted(token, config) {
+            open_sentence = true;
+        }
+        if token.ends_with(is_sentence_end) {
+            sentence_count += 1;
+            open_sentence = false;
+        }
+    }
+    if open_sentence {
+        sentence_count += 1;
+    }
+
+    let (top_bigrams, top_trigrams) = match config.ngrams {
+        0 => (Vec::new(), Vec::new()),
+        n => (
+            top_ngrams(&all_words, 2, config),
+            if n >= 3 {
+                top_ngrams(&all_words, 3, config)
+            } else {
+                Vec::new()
+            },
+        ),
+    };
+
+    let phase = Stopwatch::start();
+    all_words.sort_unstable_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+    all_words.dedup();
+    let longest_words: Vec<String> = all_words.iter().take(config.longest_n).cloned().collect();
+    timings.longest_ns = phase.elapsed_ns();
+
+    let stats = TextStats {
+        word_count,
+        total_words: word_freq.values().sum(),
+        char_count,
+        line_count: Some(text.lines().count()),
+        sentence_count: Some(sentence_count),
+        mean_word_length,
+        median_word_length,
+        word_lengths,
+        top_words,
+        surface_forms,
+        detected_language: config
+            .detected_language(word_freq.iter().map(|(w, c)| (w.as_str(), *c))),
+        longest_words,
+        top_bigrams,
+        top_trigrams,
+        diagnostics,
+    };
+    timings.total_ns = start.elapsed_ns();
+    (stats, timings)
+}
+
+/// Whether any word of the whitespace-separated `token` is counted.
+fn counted(token: &str, config: &AnalyzerConfig) -> bool {
+    tokens(token, config).into_iter().any(|word| {
+        words(word, config).into_iter().any(|clean| {
+            !clean.is_empty()
+                && clean.chars().count() >= config.min_word_length
+                && config.limit_word(&clean).0.is_some()
+        })
+    })
+}
+
+/// The `config.top_k` most frequent runs of `n` consecutive words, ties in
+/// alphabetical order, without those that start or end with a stopword.
+fn top_ngrams(words: &[String], n: usize, config: &AnalyzerConfig) -> Vec<(String, usize)> {
+    let mut freq: HashMap<String, usize> = HashMap::new();
+    for window in words.windows(n) {
+        *freq.entry(window.join(" ")).or_insert(0) += 1;
+    }
+    let mut grams: Vec<(String, usize)> = freq
+        .into_iter()
+        .filter(|(gram, _)| !config.is_stop_gram(gram))
+        .collect();
+    grams.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    grams.truncate(config.top_k);
+    grams
+}
+
+/// The tokens of `line`: whitespace-separated, or its non-blank word
+/// segments.
+fn tokens<'a>(line: &'a str, config: &AnalyzerConfig) -> Vec<&'a str> {
+    if let Some(tokenizer) = &config.tokenizer {
+        let mut found = Vec::new();
+        for token in line.split_whitespace() {
+            tokenizer.0.tokens(token, &mut |span| {
+                if !span.is_empty() {
+                    found.push(&token[span]);
+                }
+            });
+        }
+        return found;
+    }
+    match config.segmentation {
+        Segmentation::Whitespace => line.split_whitespace().collect(),
+        Segmentation::Words => WordBounds::new(line)
+            .filter(|segment| !segment.trim().is_empty())
+            .collect(),
+    }
+}
+
+/// The cleaned words of `token`: one, empty if it has no word characters,
+/// unless the punctuation policy splits it. A custom tokenizer's tokens are
+/// only cased. Every token is normalized first.
+fn words(token: &str, config: &AnalyzerConfig) -> Vec<String> {
+    let token = &*config.normalized(token);
+    if config.tokenizer.is_some() {
+        return vec![match config.case_sensitive {
+            true => token.to_string(),
+            false => token.to_lowercase(),
+        }];
+    }
+    if !config.splits_punctuation() {
+        return vec![clean(token, config)];
+    }
+    let mut words = Vec::new();
+    config.punctuated_words(token, &mut String::new(), |word| {
+        words.push(word.to_string())
+    });
+    if words.is_empty() {
+        words.push(String::new());
+    }
+    words
+}
+
+/// The word characters of `word`, cased as counted. A word segment is kept
+/// whole, lowercased as written, if it has any.
+fn clean(word: &str, config: &AnalyzerConfig) -> String {
+    match config.segmentation {
+        Segmentation::Whitespace => {
+            let mut clean: String = word.chars().filter(|&c| config.is_word_char(c)).collect();
+            config.cased(&mut clean);
+            clean
+        }
+        Segmentation::Words if word.chars().any(|c| config.is_word_char(c)) => {
+            match config.case_sensitive {
+                true => word.to_string(),
+                false => word.to_lowercase(),
+            }
+        }
+        Segmentation::Words => String::new(),
+    }
+}
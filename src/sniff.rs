@@ -0,0 +1,246 @@
+//! Content sniffing: what an input holds, from its first bytes, so that it
+//! goes to the right reader or is skipped (`td5::skip`).
+//!
+//! Known signatures come first: compressed streams and archives, then
+//! images, documents and executables. Without one, a NUL byte makes the
+//! input binary and so does a share of control characters above the limit
+//! (tabs, line breaks, form feeds and escapes are text); UTF-16, which is
+//! full of NULs, is recognized by `encoding::detect` before that. Only the
+//! first `SAMPLE_BYTES` are looked at.
+
+use crate::encoding::{self, Encoding};
+use crate::error::{Result, Td5Error};
+use crate::inflate::Inflater;
+use std::fmt;
+use std::io::{self, Read};
+
+/// Bytes looked at by `sniff`.
+pub const SAMPLE_BYTES: usize = 8192;
+
+/// Largest share of control bytes `sniff` takes for text.
+pub const MAX_NON_TEXT: f64 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Bzip2,
+    Xz,
+    Zstd,
+    /// A ZIP archive other than an EPUB book.
+    Zip,
+    SevenZip,
+}
+
+impl Compression {
+    /// Whether `decompress` and `decompressing` can read it: gzip, and
+    /// zstd with the `zstd` feature.
+    pub fn is_supported(self) -> bool {
+        self == Compression::Gzip || (self == Compression::Zstd && cfg!(feature = "zstd"))
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Compression::Gzip => "gzip",
+            Compression::Bzip2 => "bzip2",
+            Compression::Xz => "xz",
+            Compression::Zstd => "zstd",
+            Compression::Zip => "zip",
+            Compression::SevenZip => "7z",
+        })
+    }
+}
+
+/// What `sniff` found.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Text,
+    /// A ZIP archive whose first entry says it is an EPUB book.
+    Epub,
+    Compressed(Compression),
+    /// The format of a known signature (`"PNG image"`), or `"data"` for
+    /// NUL bytes without one.
+    Binary(&'static str),
+    /// No signature, but this share of control bytes.
+    Control(f64),
+}
+
+impl Kind {
+    pub fn is_text(self) -> bool {
+        self == Kind::Text
+    }
+}
+
+const COMPRESSED: &[(&[u8], Compression)] = &[
+    (b"\x1f\x8b", Compression::Gzip),
+    (b"\xfd7zXZ\0", Compression::Xz),
+    (b"\x28\xb5\x2f\xfd", Compression::Zstd),
+    (b"PK\x03\x04", Compression::Zip),
+    (b"PK\x05\x06", Compression::Zip),
+    (b"7z\xbc\xaf\x27\x1c", Compression::SevenZip),
+];
+
+const SIGNATURES: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "PNG image"),
+    (b"\xff\xd8\xff", "JPEG image"),
+    (b"GIF87a", "GIF image"),
+    (b"GIF89a", "GIF image"),
+    (b"%PDF-", "PDF document"),
+    (b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1", "Office document"),
+    (b"\x7fELF", "ELF executable"),
+    (b"\xcf\xfa\xed\xfe", "Mach-O executable"),
+    (b"\0asm", "WebAssembly module"),
+    (b"SQLite format 3\0", "SQLite database"),
+    (b"OggS", "Ogg media"),
+    (b"fLaC", "FLAC audio"),
+];
+
+/// What `bytes` hold, control bytes allowed up to `MAX_NON_TEXT`.
+///
+/// ```
+/// use td5::sniff::{Compression, Kind, sniff};
+///
+/// assert_eq!(sniff(b"Plain text,\r\n\tindented."), Kind::Text);
+/// assert_eq!(sniff(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), Kind::Binary("PNG image"));
+/// assert_eq!(sniff(b"\x1f\x8b\x08\0\0\0\0\0\0\x03"), Kind::Compressed(Compression::Gzip));
+/// assert_eq!(sniff(b"BZh91AY&SY"), Kind::Compressed(Compression::Bzip2));
+/// assert_eq!(sniff(b"BZh, as in text"), Kind::Text);
+/// assert_eq!(sniff(b"a\0b"), Kind::Binary("data"));
+/// assert_eq!(sniff(&[0x02, 0x03, b'a', b'b']), Kind::Control(0.5));
+/// // UTF-16 has a NUL in every other byte.
+/// assert_eq!(sniff(b"\xff\xfeh\0i\0"), Kind::Text);
+/// ```
+pub fn sniff(bytes: &[u8]) -> Kind {
+    sniff_with(bytes, MAX_NON_TEXT)
+}
+
+/// `sniff`, taking up to `max_non_text` of control bytes for text.
+pub fn sniff_with(bytes: &[u8], max_non_text: f64) -> Kind {
+    let sample = &bytes[..bytes.len().min(SAMPLE_BYTES)];
+    if sample.starts_with(b"PK\x03\x04")
+        && sample.get(30..58) == Some(b"mimetypeapplication/epub+zip")
+    {
+        return Kind::Epub;
+    }
+    // `BZh`, the block size, then the block magic (the digits of pi).
+    if sample.starts_with(b"BZh")
+        && sample.get(3).is_some_and(|b| (b'1'..=b'9').contains(b))
+        && sample.get(4..10) == Some(b"1AY&SY")
+    {
+        return Kind::Compressed(Compression::Bzip2);
+    }
+    if let Some(&(_, compression)) = COMPRESSED
+        .iter()
+        .find(|(magic, _)| sample.starts_with(magic))
+    {
+        return Kind::Compressed(compression);
+    }
+    if let Some(&(_, format)) = SIGNATURES
+        .iter()
+        .find(|(magic, _)| sample.starts_with(magic))
+    {
+        return Kind::Binary(format);
+    }
+    if matches!(
+        encoding::detect(sample).0,
+        Encoding::Utf16Le | Encoding::Utf16Be
+    ) {
+        return Kind::Text;
+    }
+    if sample.contains(&0) {
+        return Kind::Binary("data");
+    }
+    let share = non_text_share(sample);
+    if share > max_non_text {
+        Kind::Control(share)
+    } else {
+        Kind::Text
+    }
+}
+
+/// Share of `sample` that is control bytes other than whitespace and escape.
+pub fn non_text_share(sample: &[u8]) -> f64 {
+    let control = sample
+        .iter()
+        .filter(|&&b| (b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0c | 0x1b)) || b == 0x7f)
+        .count();
+    control as f64 / sample.len().max(1) as f64
+}
+
+/// The content of a compressed stream, for the formats where
+/// `Compression::is_supported`. Every member of a gzip file and every frame
+/// of a zstd one is read, and the checksums they carry checked.
+pub fn decompress(bytes: &[u8], compression: Compression) -> Result<Vec<u8>> {
+    match compression {
+        Compression::Gzip => {
+            crate::inflate::gunzip(bytes).map_err(|e| Td5Error::config(format!("gzip: {e}")))
+        }
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => {
+            let mut out = Vec::new();
+            crate::zstd::ZstdReader::new(bytes)
+                .read_to_end(&mut out)
+                .map_err(|e| Td5Error::config(format!("zstd: {e}")))?;
+            Ok(out)
+        }
+        other => Err(Td5Error::config(format!(
+            "{other} compressed input is not supported"
+        ))),
+    }
+}
+
+/// `reader`, decompressed as it is read if its first bytes are those of a
+/// supported compressed stream, whatever its name says; the compression
+/// found. Other inputs, compressed archives included, read as they are, for
+/// `sniff` to judge. Only the decoder's window is held in memory, so a
+/// large compressed corpus can go straight to `analyze_reader`:
+///
+/// ```no_run
+/// use std::fs::File;
+///
+/// let (reader, _) = td5::sniff::decompressing(File::open("corpus.txt.gz")?)?;
+/// let stats = td5::analyze_reader(reader)?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+///
+/// ```
+/// use std::io::Read;
+/// use td5::sniff::{Compression, decompressing};
+///
+/// // "hi\n", as `gzip -n` writes it.
+/// let gz = b"\x1f\x8b\x08\0\0\0\0\0\0\x03\xcb\xc8\xe4\x02\0\x7a\x7a\x6f\xed\x03\0\0\0";
+/// let (mut reader, compression) = decompressing(&gz[..]).unwrap();
+/// let mut text = String::new();
+/// reader.read_to_string(&mut text).unwrap();
+/// assert_eq!((text.as_str(), compression), ("hi\n", Some(Compression::Gzip)));
+///
+/// let (_, compression) = decompressing(&b"plain text"[..]).unwrap();
+/// assert_eq!(compression, None);
+/// ```
+pub fn decompressing<'a>(
+    mut reader: impl Read + Send + 'a,
+) -> io::Result<(Box<dyn Read + Send + 'a>, Option<Compression>)> {
+    let mut magic = [0u8; 4];
+    let mut read = 0;
+    while read < magic.len() {
+        match reader.read(&mut magic[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    let whole = io::Cursor::new(magic).take(read as u64).chain(reader);
+    match sniff(&magic[..read]) {
+        Kind::Compressed(Compression::Gzip) => {
+            Ok((Box::new(Inflater::gzip(whole)), Some(Compression::Gzip)))
+        }
+        #[cfg(feature = "zstd")]
+        Kind::Compressed(Compression::Zstd) => Ok((
+            Box::new(crate::zstd::ZstdReader::new(whole)),
+            Some(Compression::Zstd),
+        )),
+        _ => Ok((Box::new(whole), None)),
+    }
+}
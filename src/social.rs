@@ -0,0 +1,127 @@
+//! Hashtags, @mentions and URLs as token classes of their own, for exported
+//! tweet and chat archives.
+//!
+//! `SocialStats::extract` counts them and returns the text with them blanked
+//! out, so that word statistics computed on what is left do not count
+//! `#rustlang` as the word `rustlang` or a URL as `https`, `www` and `com`.
+//! Hashtags and mentions are compared case-insensitively; URLs (`http://`,
+//! `https://` or `www.`) as written, minus trailing punctuation.
+
+use crate::json::Json;
+use std::collections::HashMap;
+
+/// Entries kept in each top list.
+const TOP: usize = 10;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SocialStats {
+    /// Most frequent first, ties broken alphabetically, at most ten.
+    pub hashtags: Vec<(String, usize)>,
+    pub mentions: Vec<(String, usize)>,
+    pub urls: Vec<(String, usize)>,
+    /// Occurrences of each class, beyond the top lists.
+    pub total_hashtags: usize,
+    pub total_mentions: usize,
+    pub total_urls: usize,
+}
+
+#[derive(Clone, Copy)]
+enum Class {
+    Hashtag,
+    Mention,
+    Url,
+}
+
+fn classify(token: &str) -> Option<(Class, &str)> {
+    let token = token.trim_start_matches(['(', '[', '"', '\'']);
+    let prefixed = |prefix: &str| {
+        token
+            .as_bytes()
+            .get(..prefix.len())
+            .is_some_and(|p| p.eq_ignore_ascii_case(prefix.as_bytes()))
+    };
+    if prefixed("http://") || prefixed("https://") || prefixed("www.") {
+        let url = token.trim_end_matches(|c: char| ".,;:!?)]\"'".contains(c));
+        return Some((Class::Url, url));
+    }
+    let (class, name) = match token.as_bytes().first() {
+        Some(b'#') => (Class::Hashtag, &token[1..]),
+        Some(b'@') => (Class::Mention, &token[1..]),
+        _ => return None,
+    };
+    let len = name
+        .char_indices()
+        .find(|&(_, c)| !(c.is_alphanumeric() || c == '_'))
+        .map_or(name.len(), |(i, _)| i);
+    let name = &name[..len];
+    // `#1` is a number and `@` alone an "at", not a tag.
+    let valid = match class {
+        Class::Hashtag => name.chars().any(char::is_alphabetic),
+        _ => !name.is_empty(),
+    };
+    valid.then(|| (class, &token[..len + 1]))
+}
+
+impl SocialStats {
+    /// Counts the hashtags, mentions and URLs of `text` and returns it with
+    /// each of them replaced by spaces.
+    pub fn extract(text: &str) -> (SocialStats, String) {
+        let mut counts: [HashMap<String, usize>; 3] = Default::default();
+        let mut rest = String::with_capacity(text.len());
+        let mut copied = 0;
+        let base = text.as_ptr() as usize;
+        for token in text.split_whitespace() {
+            let Some((class, found)) = classify(token) else {
+                continue;
+            };
+            let key = match class {
+                Class::Url => found.to_string(),
+                _ => found.to_lowercase(),
+            };
+            *counts[class as usize].entry(key).or_insert(0) += 1;
+            let start = found.as_ptr() as usize - base;
+            rest.push_str(&text[copied..start]);
+            rest.extend(std::iter::repeat_n(' ', found.len()));
+            copied = start + found.len();
+        }
+        rest.push_str(&text[copied..]);
+        let [hashtags, mentions, urls] = counts;
+        let total = |m: &HashMap<String, usize>| m.values().sum();
+        let stats = SocialStats {
+            total_hashtags: total(&hashtags),
+            total_mentions: total(&mentions),
+            total_urls: total(&urls),
+            hashtags: top(hashtags),
+            mentions: top(mentions),
+            urls: top(urls),
+        };
+        (stats, rest)
+    }
+
+    /// Every class as `[entry, count]` pairs plus its total.
+    pub fn to_json_value(&self) -> Json {
+        let list = |entries: &[(String, usize)]| {
+            Json::Array(
+                entries
+                    .iter()
+                    .map(|(e, c)| Json::Array(vec![e.as_str().into(), (*c).into()]))
+                    .collect(),
+            )
+        };
+        Json::Object(vec![
+            ("hashtags".into(), list(&self.hashtags)),
+            ("mentions".into(), list(&self.mentions)),
+            ("urls".into(), list(&self.urls)),
+            ("total_hashtags".into(), self.total_hashtags.into()),
+            ("total_mentions".into(), self.total_mentions.into()),
+            ("total_urls".into(), self.total_urls.into()),
+        ])
+    }
+}
+
+fn top(counts: HashMap<String, usize>) -> Vec<(String, usize)> {
+    let mut all: Vec<(String, usize)> = counts.into_iter().collect();
+    all.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    all.truncate(TOP);
+    all
+}
@@ -0,0 +1,208 @@
+//! How much two top-word lists agree (`td5 stability`): the same text run
+//! through two backends, or one backend with two option sets such as a full
+//! read and a `--sample`, compared by rank correlation.
+//!
+//! Kendall's tau-b is computed over counts, so words tied in a list are tied
+//! in the comparison whatever order the backend listed them in. The words
+//! compared are those at or above the count of either list's Nth word, so a
+//! tie straddling the cut is taken whole rather than split by list position.
+//! A word missing from one list is ranked after all of that list's words,
+//! tied with the other missing ones, so dropping a word out of the top N
+//! counts against agreement without inventing a rank for it. 1 means the
+//! same words in the same order, 0 no relation, -1 the order reversed.
+
+use crate::json::Json;
+use crate::{AnalyzerConfig, TextStats, analyzer};
+use std::cmp::Ordering;
+
+/// A word of either list, its count in each (0 when missing) and its
+/// 1-based rank by count, tied words sharing the average of their positions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankedWord {
+    pub word: String,
+    pub count_a: usize,
+    pub count_b: usize,
+    pub rank_a: Option<f64>,
+    pub rank_b: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stability {
+    /// List length compared.
+    pub top: usize,
+    /// Kendall's tau-b; `None` when undefined (fewer than two words, or one
+    /// side entirely tied).
+    pub tau: Option<f64>,
+    /// Words in both lists.
+    pub shared: usize,
+    /// Every word compared, in `a`'s order, then `b`'s words missing from
+    /// `a`.
+    pub words: Vec<RankedWord>,
+}
+
+impl Stability {
+    /// Compares the top `top` words of two lists, most frequent first. Each
+    /// list must hold every word whose count reaches that of its `top`th
+    /// entry (see `complete_at`); words past the cut that tie with it are
+    /// compared too.
+    ///
+    /// ```
+    /// use td5::stability::Stability;
+    ///
+    /// let list = |words: &[&str]| -> Vec<(String, usize)> {
+    ///     words.iter().map(|w| (w.to_string(), 2)).collect()
+    /// };
+    /// let s = Stability::between(&list(&["a", "b", "c"]), &list(&["c", "b", "a"]), 2);
+    /// assert_eq!(s.tau, None);
+    /// assert_eq!((s.shared, s.words[0].rank_a), (3, Some(2.0)));
+    /// ```
+    pub fn between(a: &[(String, usize)], b: &[(String, usize)], top: usize) -> Stability {
+        let (a, b) = (cut(a, top), cut(b, top));
+        let count = |list: &[(String, usize)], word: &str| {
+            list.iter().find(|(w, _)| w == word).map_or(0, |&(_, c)| c)
+        };
+        let mut words: Vec<RankedWord> = a
+            .iter()
+            .map(|(word, count_a)| RankedWord {
+                word: word.clone(),
+                count_a: *count_a,
+                count_b: count(b, word),
+                rank_a: rank(a, *count_a),
+                rank_b: rank(b, count(b, word)),
+            })
+            .collect();
+        words.extend(
+            b.iter()
+                .filter(|(word, _)| count(a, word) == 0)
+                .map(|(word, count_b)| RankedWord {
+                    word: word.clone(),
+                    count_a: 0,
+                    count_b: *count_b,
+                    rank_a: None,
+                    rank_b: rank(b, *count_b),
+                }),
+        );
+        let shared = words
+            .iter()
+            .filter(|w| w.count_a > 0 && w.count_b > 0)
+            .count();
+        // Higher counts rank first on both sides, so comparing counts orders
+        // pairs as their ranks would.
+        let counts: Vec<(usize, usize)> = words.iter().map(|w| (w.count_a, w.count_b)).collect();
+        Stability {
+            top,
+            tau: tau_b(&counts),
+            shared,
+            words,
+        }
+    }
+
+    pub fn to_json_value(&self) -> Json {
+        let rank = |r: Option<f64>| r.map_or(Json::Null, Json::from);
+        let words = self
+            .words
+            .iter()
+            .map(|w| {
+                Json::Object(vec![
+                    ("word".into(), w.word.as_str().into()),
+                    ("count_a".into(), w.count_a.into()),
+                    ("count_b".into(), w.count_b.into()),
+                    ("rank_a".into(), rank(w.rank_a)),
+                    ("rank_b".into(), rank(w.rank_b)),
+                ])
+            })
+            .collect();
+        Json::Object(vec![
+            ("top".into(), self.top.into()),
+            (
+                "kendall_tau".into(),
+                self.tau.map_or(Json::Null, Json::from),
+            ),
+            ("shared".into(), self.shared.into()),
+            ("words".into(), Json::Array(words)),
+        ])
+    }
+}
+
+/// Whether `list`, as asked for with a length of `asked`, holds every word
+/// tied with its `top`th entry: it is shorter than asked, or its last entry
+/// counts less than the `top`th.
+pub fn complete_at(list: &[(String, usize)], asked: usize, top: usize) -> bool {
+    match (top.checked_sub(1).and_then(|i| list.get(i)), list.last()) {
+        (Some(&(_, nth)), Some(&(_, last))) => list.len() < asked || last < nth,
+        _ => true,
+    }
+}
+
+/// The stats of `text` by the analyzer `name`, asked for more top words
+/// until they hold every word tied with the `top`th. `None` for an unknown
+/// analyzer.
+///
+/// ```
+/// // "b" ties with "a" for first, so both are kept.
+/// let stats = td5::stability::analyze_top("fast", "a b c a b", 1).unwrap();
+/// let words: Vec<&str> = stats.top_words.iter().map(|(w, _)| w.as_str()).collect();
+/// assert_eq!(words, ["a", "b", "c"]);
+/// assert!(td5::stability::analyze_top("nope", "a", 1).is_none());
+/// ```
+pub fn analyze_top(name: &str, text: &str, top: usize) -> Option<TextStats> {
+    analyzer::find(name)?;
+    let mut asked = top.saturating_mul(2).max(1);
+    loop {
+        let config = AnalyzerConfig::default().top_words(asked);
+        let stats = analyzer::configured(name, 0, config)?
+            .analyzer
+            .analyze(text);
+        if complete_at(&stats.top_words, asked, top) {
+            return Some(stats);
+        }
+        asked = asked.saturating_mul(2);
+    }
+}
+
+/// The words of `list` counted at least as often as its `top`th entry.
+fn cut(list: &[(String, usize)], top: usize) -> &[(String, usize)] {
+    match top.checked_sub(1).and_then(|i| list.get(i)) {
+        Some(&(_, nth)) => {
+            let end = list.iter().take_while(|&&(_, c)| c >= nth).count();
+            &list[..end]
+        }
+        None if top == 0 => &[],
+        None => list,
+    }
+}
+
+/// The average 1-based position of the entries of `list` counted `count`
+/// times; `None` for 0, a word the list does not have.
+fn rank(list: &[(String, usize)], count: usize) -> Option<f64> {
+    if count == 0 {
+        return None;
+    }
+    let above = list.iter().filter(|&&(_, c)| c > count).count();
+    let tied = list.iter().filter(|&&(_, c)| c == count).count();
+    Some(above as f64 + (tied as f64 + 1.0) / 2.0)
+}
+
+/// Kendall's tau-b of paired ranks, ties allowed.
+pub fn tau_b(ranks: &[(usize, usize)]) -> Option<f64> {
+    let (mut concordant, mut discordant) = (0i64, 0i64);
+    let (mut tied_a, mut tied_b) = (0i64, 0i64);
+    for (i, &(xa, xb)) in ranks.iter().enumerate() {
+        for &(ya, yb) in &ranks[i + 1..] {
+            match (xa.cmp(&ya), xb.cmp(&yb)) {
+                (Ordering::Equal, Ordering::Equal) => {
+                    tied_a += 1;
+                    tied_b += 1;
+                }
+                (Ordering::Equal, _) => tied_a += 1,
+                (_, Ordering::Equal) => tied_b += 1,
+                (a, b) if a == b => concordant += 1,
+                _ => discordant += 1,
+            }
+        }
+    }
+    let n = ranks.len() as i64;
+    let pairs = n * (n - 1) / 2;
+    let denominator = (((pairs - tied_a) * (pairs - tied_b)) as f64).sqrt();
+    (denominator > 0.0).then(|| (concordant - discordant) as f64 / denominator)
+}
@@ -0,0 +1,202 @@
+//! Stack use of the analyzers (`td5 selftest --stack`), for callers that run
+//! them on threads with small stacks: worker pools in web servers, embedded
+//! targets.
+//!
+//! A stack overflow aborts the process, so each measurement is a child
+//! `td5 probe --stack-size N` that analyzes its input on a thread of `N`
+//! bytes and dies if that is too little. A search finds the smallest stack
+//! each analyzer needs on each input. No analysis path recurses or keeps an
+//! input-sized buffer on the stack (long words live on the heap, sorts
+//! recurse at most `log n` deep), so the need must not grow with the input:
+//! every case is measured at each of `STACK_SIZES` and checked against
+//! `STACK_BUDGET`, which depends on the build profile, and, within
+//! `STACK_SLACK`, the smaller size.
+//!
+//! The multi-threaded analyzers count on worker threads of their own, which
+//! get the standard library's default stack (`RUST_MIN_STACK`, 2 MiB); only
+//! the calling thread is measured.
+
+use crate::error::{Result, Td5Error};
+use crate::input::TempFile;
+use crate::shapes::Shape;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// The smallest stack a thread can be given on Linux (`PTHREAD_STACK_MIN`);
+/// smaller requests are rounded up, so the search starts here.
+pub const SMALLEST_STACK: usize = 16 * 1024;
+
+/// Most stack any analyzer may need on any input. Every one currently fits
+/// in `SMALLEST_STACK` in a release build. A debug build keeps every local
+/// and temporary of a function in its frame, so `sharded`, whose counting
+/// and finishing frames are 4 to 6 KiB each there, needs up to 34 KiB: its
+/// budget is twice as large.
+pub const STACK_BUDGET: usize = if cfg!(debug_assertions) {
+    64 * 1024
+} else {
+    32 * 1024
+};
+
+/// Growth allowed from the smallest to the largest input: a few more levels
+/// of sorting.
+pub const STACK_SLACK: usize = 4 * 1024;
+
+/// Granularity of the search.
+const RESOLUTION: usize = 1024;
+
+/// Where the search gives up.
+const LARGEST_STACK: usize = 8 * 1024 * 1024;
+
+/// Input sizes, in words (letters for `one long word`), each case is
+/// measured at.
+pub const STACK_SIZES: [usize; 2] = [1_000, 200_000];
+
+/// An input the stack check runs every analyzer on.
+pub struct StackCase {
+    pub name: &'static str,
+    pub text: fn(usize) -> String,
+}
+
+pub const STACK_CASES: [StackCase; 5] = [
+    StackCase {
+        name: "hot words",
+        text: crate::hot_words_text,
+    },
+    StackCase {
+        name: "punctuated",
+        text: |words| Shape::Punctuated.generate(words, 0),
+    },
+    StackCase {
+        name: "unicode",
+        text: |words| Shape::Unicode.generate(words, 0),
+    },
+    StackCase {
+        name: "zipf",
+        text: |words| Shape::Zipf.generate(words, 0),
+    },
+    StackCase {
+        name: "one long word",
+        text: |letters| "a".repeat(letters),
+    },
+];
+
+/// The stack one analyzer needed on one input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackRun {
+    pub analyzer: String,
+    pub case: &'static str,
+    pub size: usize,
+    /// Smallest stack, in bytes, the analysis ran on, within 1 KiB;
+    /// `SMALLEST_STACK` when it needs no more. `None` when even 8 MiB
+    /// overflowed.
+    pub needed: Option<usize>,
+}
+
+/// Measures every analyzer on every `STACK_CASES` input at every
+/// `STACK_SIZES` size, running `exe probe` for each try.
+pub fn run(exe: &Path, analyzers: &[String]) -> Result<Vec<StackRun>> {
+    let file = TempFile::new("stack");
+    let mut runs = Vec::with_capacity(analyzers.len() * STACK_CASES.len() * STACK_SIZES.len());
+    for case in &STACK_CASES {
+        for size in STACK_SIZES {
+            std::fs::write(file.path(), (case.text)(size)).map_err(|source| Td5Error::Io {
+                path: Some(file.path().to_path_buf()),
+                source,
+            })?;
+            for analyzer in analyzers {
+                runs.push(StackRun {
+                    analyzer: analyzer.clone(),
+                    case: case.name,
+                    size,
+                    needed: needed(exe, analyzer, file.path())?,
+                });
+            }
+        }
+    }
+    Ok(runs)
+}
+
+/// Doubles the stack from `SMALLEST_STACK` until the analysis fits, then
+/// bisects between the last failure and the first success.
+fn needed(exe: &Path, analyzer: &str, input: &Path) -> Result<Option<usize>> {
+    let mut fits = SMALLEST_STACK;
+    while !probe(exe, analyzer, input, fits)? {
+        if fits >= LARGEST_STACK {
+            return Ok(None);
+        }
+        fits *= 2;
+    }
+    if fits == SMALLEST_STACK {
+        return Ok(Some(fits));
+    }
+    let mut overflows = fits / 2;
+    while fits - overflows > RESOLUTION {
+        let middle = (overflows + fits) / 2 / RESOLUTION * RESOLUTION;
+        if probe(exe, analyzer, input, middle)? {
+            fits = middle;
+        } else {
+            overflows = middle;
+        }
+    }
+    Ok(Some(fits))
+}
+
+fn probe(exe: &Path, analyzer: &str, input: &Path, stack: usize) -> Result<bool> {
+    let status = Command::new(exe)
+        .args(["probe", "--analyzer", analyzer, "--stack-size"])
+        .arg(stack.to_string())
+        .arg(input)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| Td5Error::config(format!("cannot run {}: {e}", exe.display())))?;
+    Ok(status.success())
+}
+
+/// What breaks the bounded-stack guarantee in `runs`: an analyzer needing
+/// more than `STACK_BUDGET`, or more than `STACK_SLACK` more on a larger
+/// input than on a smaller one.
+pub fn failures(runs: &[StackRun]) -> Vec<String> {
+    let mut failures = Vec::new();
+    for run in runs {
+        match run.needed {
+            Some(needed) if needed <= STACK_BUDGET => {}
+            Some(needed) => failures.push(format!(
+                "{} on {} ({}): needs {} KiB of stack, more than the {} KiB budget",
+                run.analyzer,
+                run.case,
+                run.size,
+                needed / 1024,
+                STACK_BUDGET / 1024
+            )),
+            None => failures.push(format!(
+                "{} on {} ({}): overflows even {} MiB of stack",
+                run.analyzer,
+                run.case,
+                run.size,
+                LARGEST_STACK >> 20
+            )),
+        }
+    }
+    for (i, small) in runs.iter().enumerate() {
+        let larger = runs[i + 1..]
+            .iter()
+            .filter(|r| r.analyzer == small.analyzer && r.case == small.case);
+        for large in larger {
+            if let (Some(a), Some(b)) = (small.needed, large.needed)
+                && b > a + STACK_SLACK
+            {
+                failures.push(format!(
+                    "{} on {}: needs {} KiB of stack at {} but {} KiB at {}",
+                    small.analyzer,
+                    small.case,
+                    a / 1024,
+                    small.size,
+                    b / 1024,
+                    large.size
+                ));
+            }
+        }
+    }
+    failures
+}
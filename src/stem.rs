@@ -0,0 +1,371 @@
+//! Stemming for `AnalyzerConfig::stemming`: inflected forms of a word
+//! counted together under their stem, so `optimization`, `optimizations` and
+//! `optimizing` make one entry of `top_words` rather than three.
+//!
+//! English uses the Snowball English (Porter2) algorithm. Stems are not
+//! words (`optimization` → `optim`), so the stats also list the most frequent
+//! form counted under each one, in `TextStats::surface_forms`.
+//!
+//! Unlike the `stem` stage of a `transform::Pipeline`, which strips a few
+//! common suffixes, this follows the published algorithm and its exceptions.
+
+use std::borrow::Cow;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    English,
+}
+
+impl Language {
+    pub const ALL: [Language; 1] = [Language::English];
+
+    /// `en` or `english`.
+    pub fn from_name(name: &str) -> Option<Language> {
+        match name.to_ascii_lowercase().as_str() {
+            "en" | "english" => Some(Language::English),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::English => "en",
+        }
+    }
+
+    /// The stem of a lowercase word.
+    ///
+    /// ```
+    /// use td5::stem::Language;
+    ///
+    /// for word in ["optimization", "optimizations", "optimizing", "optimize"] {
+    ///     assert_eq!(Language::English.stem(word), "optim");
+    /// }
+    /// assert_eq!(Language::English.stem("running"), "run");
+    /// assert_eq!(Language::English.stem("skies"), "sky");
+    /// assert_eq!(Language::English.stem("news"), "news");
+    /// ```
+    pub fn stem(self, word: &str) -> Cow<'_, str> {
+        match self {
+            Language::English => english(word),
+        }
+    }
+}
+
+fn is_vowel(b: u8) -> bool {
+    matches!(b, b'a' | b'e' | b'i' | b'o' | b'u' | b'y')
+}
+
+fn is_double(w: &[u8]) -> bool {
+    matches!(
+        w,
+        [.., b'b', b'b']
+            | [.., b'd', b'd']
+            | [.., b'f', b'f']
+            | [.., b'g', b'g']
+            | [.., b'm', b'm']
+            | [.., b'n', b'n']
+            | [.., b'p', b'p']
+            | [.., b'r', b'r']
+            | [.., b't', b't']
+    )
+}
+
+/// Whether `w` ends in a short syllable: a vowel followed by a non-vowel
+/// other than `w`, `x` or `Y` and preceded by a non-vowel, or a vowel then
+/// a non-vowel at the start of the word.
+fn ends_short_syllable(w: &[u8]) -> bool {
+    match *w {
+        [a, b] => is_vowel(a) && !is_vowel(b),
+        [.., a, b, c] => {
+            !is_vowel(a) && is_vowel(b) && !is_vowel(c) && !matches!(c, b'w' | b'x' | b'Y')
+        }
+        _ => false,
+    }
+}
+
+/// Start of the region after the first non-vowel that follows a vowel.
+fn region(w: &[u8], from: usize) -> usize {
+    (from + 1..w.len())
+        .find(|&i| !is_vowel(w[i]) && is_vowel(w[i - 1]))
+        .map_or(w.len(), |i| i + 1)
+}
+
+/// Words whose stem the algorithm does not compute.
+const EXCEPTIONS: [(&str, &str); 18] = [
+    ("skis", "ski"),
+    ("skies", "sky"),
+    ("dying", "die"),
+    ("lying", "lie"),
+    ("tying", "tie"),
+    ("idly", "idl"),
+    ("gently", "gentl"),
+    ("ugly", "ugli"),
+    ("early", "earli"),
+    ("only", "onli"),
+    ("singly", "singl"),
+    ("sky", "sky"),
+    ("news", "news"),
+    ("howe", "howe"),
+    ("atlas", "atlas"),
+    ("cosmos", "cosmos"),
+    ("bias", "bias"),
+    ("andes", "andes"),
+];
+
+/// Left alone after step 1a.
+const INVARIANT_AFTER_1A: [&str; 8] = [
+    "inning", "outing", "canning", "herring", "earring", "proceed", "exceed", "succeed",
+];
+
+struct Word {
+    w: Vec<u8>,
+    r1: usize,
+    r2: usize,
+}
+
+impl Word {
+    fn ends(&self, suffix: &str) -> bool {
+        self.w.ends_with(suffix.as_bytes())
+    }
+
+    /// Start of `suffix`, which the word ends with.
+    fn at(&self, suffix: &str) -> usize {
+        self.w.len() - suffix.len()
+    }
+
+    fn replace(&mut self, suffix: &str, by: &str) {
+        let at = self.at(suffix);
+        self.w.truncate(at);
+        self.w.extend_from_slice(by.as_bytes());
+    }
+
+    /// The longest of `suffixes` the word ends with.
+    fn longest<'a>(&self, suffixes: &[(&'a str, &'a str)]) -> Option<(&'a str, &'a str)> {
+        suffixes
+            .iter()
+            .filter(|(suffix, _)| self.ends(suffix))
+            .max_by_key(|(suffix, _)| suffix.len())
+            .copied()
+    }
+
+    fn has_vowel_before(&self, at: usize) -> bool {
+        self.w[..at].iter().any(|&b| is_vowel(b))
+    }
+
+    fn is_short(&self) -> bool {
+        self.r1 >= self.w.len() && ends_short_syllable(&self.w)
+    }
+}
+
+fn english(original: &str) -> Cow<'_, str> {
+    if original.len() <= 2 {
+        return Cow::Borrowed(original);
+    }
+    if let Some(&(_, stem)) = EXCEPTIONS.iter().find(|(w, _)| *w == original) {
+        return if stem == original {
+            Cow::Borrowed(original)
+        } else {
+            Cow::Owned(stem.to_string())
+        };
+    }
+    let mut w = original
+        .strip_prefix('\'')
+        .unwrap_or(original)
+        .as_bytes()
+        .to_vec();
+    for i in 0..w.len() {
+        if w[i] == b'y' && (i == 0 || is_vowel(w[i - 1])) {
+            w[i] = b'Y';
+        }
+    }
+    let r1 = ["gener", "commun", "arsen"]
+        .iter()
+        .find(|prefix| w.starts_with(prefix.as_bytes()))
+        .map_or_else(|| region(&w, 0), |prefix| prefix.len());
+    let r2 = region(&w, r1);
+    let mut word = Word { w, r1, r2 };
+
+    // Step 0: possessives.
+    if let Some((suffix, _)) = word.longest(&[("'s'", ""), ("'s", ""), ("'", "")]) {
+        word.replace(suffix, "");
+    }
+
+    // Step 1a: plurals.
+    match word.longest(&[
+        ("sses", ""),
+        ("ied", ""),
+        ("ies", ""),
+        ("us", ""),
+        ("ss", ""),
+        ("s", ""),
+    ]) {
+        Some(("sses", _)) => word.replace("sses", "ss"),
+        Some((suffix @ ("ied" | "ies"), _)) => {
+            let by = if word.at(suffix) > 1 { "i" } else { "ie" };
+            word.replace(suffix, by);
+        }
+        Some(("s", _)) => {
+            let at = word.at("s");
+            if at >= 2 && word.has_vowel_before(at - 1) {
+                word.w.pop();
+            }
+        }
+        _ => {}
+    }
+    if INVARIANT_AFTER_1A
+        .iter()
+        .any(|w| w.as_bytes() == word.w.as_slice())
+    {
+        return finish(original, word.w);
+    }
+
+    // Step 1b: past tenses and gerunds.
+    let step_1b = [
+        ("eed", ""),
+        ("eedly", ""),
+        ("ed", ""),
+        ("edly", ""),
+        ("ing", ""),
+        ("ingly", ""),
+    ];
+    if let Some((suffix, _)) = word.longest(&step_1b) {
+        // Outside R1, `eed` is left alone rather than taken for `ed`.
+        if suffix.starts_with("eed") {
+            if word.at(suffix) >= word.r1 {
+                word.replace(suffix, "ee");
+            }
+        } else if word.has_vowel_before(word.at(suffix)) {
+            word.replace(suffix, "");
+            if word.ends("at") || word.ends("bl") || word.ends("iz") {
+                word.w.push(b'e');
+            } else if is_double(&word.w) {
+                word.w.pop();
+            } else if word.is_short() {
+                word.w.push(b'e');
+            }
+        }
+    }
+
+    // Step 1c: `y` after a consonant.
+    if let [.., before, b'y' | b'Y'] = *word.w
+        && word.w.len() > 2
+        && !is_vowel(before)
+    {
+        *word.w.last_mut().unwrap() = b'i';
+    }
+
+    // Step 2.
+    let step_2 = [
+        ("tional", "tion"),
+        ("enci", "ence"),
+        ("anci", "ance"),
+        ("abli", "able"),
+        ("entli", "ent"),
+        ("izer", "ize"),
+        ("ization", "ize"),
+        ("ational", "ate"),
+        ("ation", "ate"),
+        ("ator", "ate"),
+        ("alism", "al"),
+        ("aliti", "al"),
+        ("alli", "al"),
+        ("fulness", "ful"),
+        ("ousli", "ous"),
+        ("ousness", "ous"),
+        ("iveness", "ive"),
+        ("iviti", "ive"),
+        ("biliti", "ble"),
+        ("bli", "ble"),
+        ("ogi", "og"),
+        ("fulli", "ful"),
+        ("lessli", "less"),
+        ("li", ""),
+    ];
+    if let Some((suffix, by)) = word.longest(&step_2)
+        && word.at(suffix) >= word.r1
+    {
+        let before = word.w[..word.at(suffix)].last().copied();
+        match suffix {
+            "ogi" if before != Some(b'l') => {}
+            "li" if !matches!(
+                before,
+                Some(b'c' | b'd' | b'e' | b'g' | b'h' | b'k' | b'm' | b'n' | b'r' | b't')
+            ) => {}
+            _ => word.replace(suffix, by),
+        }
+    }
+
+    // Step 3.
+    let step_3 = [
+        ("tional", "tion"),
+        ("ational", "ate"),
+        ("alize", "al"),
+        ("icate", "ic"),
+        ("iciti", "ic"),
+        ("ical", "ic"),
+        ("ful", ""),
+        ("ness", ""),
+        ("ative", ""),
+    ];
+    if let Some((suffix, by)) = word.longest(&step_3)
+        && word.at(suffix) >= word.r1
+        && (suffix != "ative" || word.at(suffix) >= word.r2)
+    {
+        word.replace(suffix, by);
+    }
+
+    // Step 4.
+    let step_4 = [
+        ("al", ""),
+        ("ance", ""),
+        ("ence", ""),
+        ("er", ""),
+        ("ic", ""),
+        ("able", ""),
+        ("ible", ""),
+        ("ant", ""),
+        ("ement", ""),
+        ("ment", ""),
+        ("ent", ""),
+        ("ism", ""),
+        ("ate", ""),
+        ("iti", ""),
+        ("ous", ""),
+        ("ive", ""),
+        ("ize", ""),
+        ("ion", ""),
+    ];
+    if let Some((suffix, _)) = word.longest(&step_4)
+        && word.at(suffix) >= word.r2
+        && (suffix != "ion" || matches!(word.w[..word.at(suffix)].last(), Some(b's' | b't')))
+    {
+        word.replace(suffix, "");
+    }
+
+    // Step 5.
+    if word.ends("e") {
+        let at = word.at("e");
+        if at >= word.r2 || (at >= word.r1 && !ends_short_syllable(&word.w[..at])) {
+            word.w.pop();
+        }
+    } else if word.ends("ll") && word.at("l") >= word.r2 {
+        word.w.pop();
+    }
+
+    finish(original, word.w)
+}
+
+/// `w` with `Y` back to `y`, borrowing `original` if nothing changed.
+fn finish(original: &str, mut w: Vec<u8>) -> Cow<'_, str> {
+    for b in &mut w {
+        if *b == b'Y' {
+            *b = b'y';
+        }
+    }
+    if w == original.as_bytes() {
+        return Cow::Borrowed(original);
+    }
+    // Only ASCII suffixes were cut or added, so `w` is still UTF-8.
+    Cow::Owned(String::from_utf8(w).expect("cut at an ASCII suffix"))
+}
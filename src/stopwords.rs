@@ -0,0 +1,94 @@
+//! Built-in stopword lists: the function words a corpus report usually
+//! leaves out, lowercased as the analyzers count them. `StopwordList` holds
+//! one or several of them, or a list read from a file, for
+//! `AnalyzerConfig::stopwords`.
+
+use std::collections::HashSet;
+
+/// Languages with a built-in list.
+pub const LANGUAGES: &[&str] = &["en", "fr"];
+
+const ENGLISH: &[&str] = &[
+    "a", "about", "after", "all", "also", "an", "and", "any", "are", "as", "at", "be", "because",
+    "been", "but", "by", "can", "could", "did", "do", "does", "for", "from", "had", "has", "have",
+    "he", "her", "him", "his", "how", "i", "if", "in", "into", "is", "it", "its", "me", "my", "no",
+    "not", "of", "on", "one", "or", "our", "out", "she", "so", "some", "than", "that", "the",
+    "their", "them", "then", "there", "these", "they", "this", "those", "to", "up", "us", "was",
+    "we", "were", "what", "when", "which", "who", "will", "with", "would", "you", "your",
+];
+
+const FRENCH: &[&str] = &[
+    "à", "au", "aux", "avec", "ce", "ces", "cette", "dans", "de", "des", "du", "elle", "elles",
+    "en", "est", "et", "été", "être", "eux", "il", "ils", "je", "la", "le", "les", "leur", "leurs",
+    "lui", "ma", "mais", "me", "même", "mes", "moi", "mon", "ne", "nos", "notre", "nous", "on",
+    "ont", "ou", "où", "par", "pas", "pour", "qu", "que", "qui", "sa", "se", "ses", "son", "sont",
+    "sur", "ta", "te", "tes", "toi", "ton", "tu", "un", "une", "vos", "votre", "vous", "y",
+];
+
+/// The list for `language` (`en` or `fr`).
+pub fn builtin(language: &str) -> Option<&'static [&'static str]> {
+    match language {
+        "en" => Some(ENGLISH),
+        "fr" => Some(FRENCH),
+        _ => None,
+    }
+}
+
+/// A set of lowercase stopwords.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StopwordList {
+    words: HashSet<String>,
+}
+
+impl StopwordList {
+    /// The built-in list for `language`.
+    pub fn builtin(language: &str) -> Option<StopwordList> {
+        builtin(language).map(|words| words.iter().copied().collect())
+    }
+
+    /// One word per line; `#` starts a comment.
+    ///
+    /// ```
+    /// use td5::stopwords::StopwordList;
+    ///
+    /// let list = StopwordList::parse("# articles\nThe\na  # indefinite\n\nan\n");
+    /// assert_eq!(list.len(), 3);
+    /// assert!(list.contains("the"));
+    /// ```
+    pub fn parse(text: &str) -> StopwordList {
+        text.lines()
+            .map(|line| line.split('#').next().unwrap_or("").trim())
+            .filter(|word| !word.is_empty())
+            .collect()
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.words.contains(word)
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Adds the words of `other`.
+    pub fn extend(&mut self, other: StopwordList) {
+        self.words.extend(other.words);
+    }
+
+    /// The words, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.words.iter().map(String::as_str)
+    }
+}
+
+impl<'a> FromIterator<&'a str> for StopwordList {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(words: I) -> Self {
+        StopwordList {
+            words: words.into_iter().map(str::to_lowercase).collect(),
+        }
+    }
+}
@@ -0,0 +1,506 @@
+//! Incremental analysis of text that arrives in pieces (a socket, a pipe, an
+//! upload), with a snapshot of the stats so far after every piece.
+//!
+//! `StreamingAnalyzer` is the synchronous core, and `TextAnalyzer` the same
+//! with only the final stats. `StatsStream` wraps it around
+//! a pollable source of chunks and yields a `PartialStats` per chunk. Its
+//! `poll_next` has the signature of `futures::Stream::poll_next`, so
+//! `futures::stream::poll_fn(move |cx| Pin::new(&mut s).poll_next(cx))` turns
+//! it into a `Stream` without this crate depending on `futures`. The source is
+//! only polled when the consumer asks for the next item, so a slow consumer
+//! slows down reading instead of piling up snapshots.
+//!
+//! Chunks are tokenized like the reference analyzer (whitespace-separated
+//! tokens stripped of non-letters), whatever characters each chunk contains,
+//! so the result never depends on where the input was cut.
+//!
+//! `WordLimit` reads only the start or the end of a document, stopping as
+//! soon as it has enough words.
+
+use crate::TextStats;
+use crate::clock::{self, Clock, Stopwatch};
+use crate::config::AnalyzerConfig;
+use crate::error::{Result, Td5Error};
+use crate::fast::{self, Counts, Mode};
+use crate::input::{AdaptiveBuffer, BufferReport};
+use crate::tuning::Tuning;
+use std::fs::File;
+use std::future::Future;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// Stats over everything received so far.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialStats {
+    /// Bytes received so far.
+    pub bytes: usize,
+    /// Chunks received so far.
+    pub chunks: usize,
+    /// Stats over the input up to its last whitespace; a word cut by the end
+    /// of the chunk is counted once its end arrives.
+    pub stats: TextStats,
+    /// Time since the analyzer was created.
+    pub time_ns: u128,
+    /// The source is exhausted and `stats` covers the whole input.
+    pub done: bool,
+}
+
+pub struct StreamingAnalyzer {
+    counts: Counts,
+    /// Text after the last whitespace seen, possibly the start of a word.
+    carry: String,
+    config: AnalyzerConfig,
+    bytes: usize,
+    chunks: usize,
+    clock: Arc<dyn Clock>,
+    start: Duration,
+}
+
+impl Default for StreamingAnalyzer {
+    fn default() -> Self {
+        Self::new(AnalyzerConfig::default())
+    }
+}
+
+impl StreamingAnalyzer {
+    pub fn new(config: AnalyzerConfig) -> Self {
+        Self::with_clock(config, clock::system())
+    }
+
+    /// `time_ns` of the snapshots is measured on `clock`.
+    pub fn with_clock(config: AnalyzerConfig, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            counts: Counts::from_map(Default::default(), 0),
+            carry: String::new(),
+            config,
+            bytes: 0,
+            chunks: 0,
+            start: clock.now(),
+            clock,
+        }
+    }
+
+    pub fn push(&mut self, chunk: &str) {
+        self.bytes += chunk.len();
+        self.chunks += 1;
+        // The carry has no whitespace, so only the chunk needs searching: a
+        // long word arriving a byte at a time stays linear.
+        let carried = self.carry.len();
+        self.carry.push_str(chunk);
+        if let Some((at, ws)) = chunk.char_indices().rfind(|(_, c)| c.is_whitespace()) {
+            // The whitespace goes with the text before it: it may be a newline.
+            let end = carried + at + ws.len_utf8();
+            self.counts
+                .merge(Self::count(&self.carry[..end], &self.config));
+            self.carry.drain(..end);
+        }
+        // The words still carried are not counted yet.
+        if let Some(progress) = &self.config.progress {
+            progress.report(self.bytes, self.counts.tokens());
+        }
+    }
+
+    fn count(text: &str, config: &AnalyzerConfig) -> Counts {
+        fast::count_with(text, Mode::Unicode, config).expect("the Unicode scanner cannot fail")
+    }
+
+    pub fn snapshot(&self) -> PartialStats {
+        PartialStats {
+            bytes: self.bytes,
+            chunks: self.chunks,
+            stats: fast::finish(self.counts.clone(), &self.config),
+            time_ns: self.elapsed_ns(),
+            done: false,
+        }
+    }
+
+    fn elapsed_ns(&self) -> u128 {
+        self.clock.now().saturating_sub(self.start).as_nanos()
+    }
+
+    /// Counts the trailing word and returns the final stats.
+    pub fn finish(mut self) -> PartialStats {
+        let rest = Self::count(&self.carry, &self.config);
+        self.counts.merge(rest);
+        let counts = std::mem::replace(&mut self.counts, Counts::from_map(Default::default(), 0));
+        PartialStats {
+            bytes: self.bytes,
+            chunks: self.chunks,
+            stats: fast::finish(counts, &self.config),
+            time_ns: self.elapsed_ns(),
+            done: true,
+        }
+    }
+}
+
+/// `StreamingAnalyzer` for callers who only want the final stats: feed it
+/// chunks as they arrive, words cut at a boundary included, and `finish`.
+///
+/// ```
+/// let mut analyzer = td5::TextAnalyzer::default();
+/// for chunk in ["the ca", "t sat on th", "e mat"] {
+///     analyzer.feed(chunk);
+/// }
+/// let stats = analyzer.finish();
+/// assert_eq!(stats.total_words, 6);
+/// assert_eq!(stats.top_words[0], ("the".to_string(), 2));
+/// assert!(stats.top_words.contains(&("cat".to_string(), 1)));
+/// ```
+#[derive(Default)]
+pub struct TextAnalyzer(StreamingAnalyzer);
+
+impl TextAnalyzer {
+    pub fn new(config: AnalyzerConfig) -> Self {
+        Self(StreamingAnalyzer::new(config))
+    }
+
+    pub fn feed(&mut self, chunk: &str) {
+        self.0.push(chunk);
+    }
+
+    /// Stats over everything fed.
+    pub fn finish(self) -> TextStats {
+        self.0.finish().stats
+    }
+}
+
+/// Analyzes everything `reader` yields, through an `input::AdaptiveBuffer`,
+/// without holding more than a buffer and the word it ends in. Tokenized like
+/// `StreamingAnalyzer`; invalid UTF-8 is replaced and counted in
+/// `Diagnostics::invalid_utf8` as by `analyze_bytes`.
+///
+/// ```
+/// let stats = td5::analyze_reader(&b"one fish two fish"[..]).unwrap();
+/// assert_eq!(stats.top_words[0], ("fish".to_string(), 2));
+/// ```
+pub fn analyze_reader(reader: impl Read) -> io::Result<TextStats> {
+    analyze_reader_with(reader, AnalyzerConfig::default())
+}
+
+pub fn analyze_reader_with(reader: impl Read, config: AnalyzerConfig) -> io::Result<TextStats> {
+    analyze_reader_sized(reader, config).map(|(stats, _)| stats)
+}
+
+/// `analyze_reader_with`, with the read sizes it went through.
+///
+/// ```
+/// use td5::AnalyzerConfig;
+/// use td5::streaming::analyze_reader_sized;
+///
+/// let text = "one fish two fish ".repeat(100_000);
+/// let (stats, reads) = analyze_reader_sized(text.as_bytes(), AnalyzerConfig::new()).unwrap();
+/// assert_eq!(stats.total_words, 400_000);
+/// assert_eq!(reads.bytes, text.len() as u64);
+/// assert!(reads.largest > reads.first);
+/// ```
+pub fn analyze_reader_sized(
+    mut reader: impl Read,
+    config: AnalyzerConfig,
+) -> io::Result<(TextStats, BufferReport)> {
+    let mut analyzer = StreamingAnalyzer::new(config);
+    let mut buffer = AdaptiveBuffer::new();
+    // Bytes of a UTF-8 sequence cut by the end of the previous read.
+    let mut pending = Vec::new();
+    let mut chunk = String::new();
+    let mut invalid = 0;
+    loop {
+        let read = buffer.read(&mut reader)?;
+        if read.is_empty() {
+            break;
+        }
+        let work = Stopwatch::start();
+        pending.extend_from_slice(read);
+        let mut rest = &pending[..];
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(valid) => {
+                    chunk.push_str(valid);
+                    rest = &[];
+                    break;
+                }
+                Err(e) => {
+                    let (valid, after) = rest.split_at(e.valid_up_to());
+                    // SAFETY: `valid_up_to` bytes were just validated
+                    chunk.push_str(unsafe { std::str::from_utf8_unchecked(valid) });
+                    let Some(len) = e.error_len() else {
+                        // Possibly completed by the next read.
+                        rest = after;
+                        break;
+                    };
+                    chunk.push(char::REPLACEMENT_CHARACTER);
+                    invalid += 1;
+                    rest = &after[len..];
+                }
+            }
+        }
+        let kept = rest.len();
+        pending.drain(..pending.len() - kept);
+        analyzer.push(&chunk);
+        chunk.clear();
+        buffer.adapt(Duration::from_nanos(work.elapsed_ns() as u64));
+    }
+    if !pending.is_empty() {
+        analyzer.push(&char::REPLACEMENT_CHARACTER.to_string());
+        invalid += 1;
+    }
+    let mut stats = analyzer.finish().stats;
+    stats.diagnostics.invalid_utf8 += invalid;
+    Ok((stats, buffer.report()))
+}
+
+/// Yields a snapshot after each chunk of `source`, then a final one with
+/// `done` set, then `None`.
+pub struct StatsStream<F> {
+    source: F,
+    analyzer: Option<StreamingAnalyzer>,
+}
+
+impl<F> StatsStream<F>
+where
+    F: FnMut(&mut Context<'_>) -> Poll<Option<String>> + Unpin,
+{
+    /// `source` is polled for the next chunk like a `Stream<Item = String>`;
+    /// `Ready(None)` ends the input.
+    pub fn new(source: F, config: AnalyzerConfig) -> Self {
+        Self {
+            source,
+            analyzer: Some(StreamingAnalyzer::new(config)),
+        }
+    }
+
+    pub fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<PartialStats>> {
+        let this = self.get_mut();
+        let Some(analyzer) = &mut this.analyzer else {
+            return Poll::Ready(None);
+        };
+        match (this.source)(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Some(chunk)) => {
+                analyzer.push(&chunk);
+                Poll::Ready(Some(analyzer.snapshot()))
+            }
+            Poll::Ready(None) => Poll::Ready(this.analyzer.take().map(StreamingAnalyzer::finish)),
+        }
+    }
+
+    /// The next snapshot, for `while let Some(p) = stream.next_snapshot().await`.
+    pub fn next_snapshot(&mut self) -> Next<'_, F> {
+        Next(self)
+    }
+}
+
+/// Chunks from an iterator, e.g. lines of a file read on another thread.
+///
+/// ```
+/// use std::task::{Context, Poll, Waker};
+///
+/// let chunks = ["the qui", "ck fox ", "the end"].map(String::from);
+/// let mut stream = td5::streaming::from_chunks(chunks, Default::default());
+/// let mut cx = Context::from_waker(Waker::noop());
+/// let mut last = None;
+/// while let Poll::Ready(Some(partial)) = std::pin::Pin::new(&mut stream).poll_next(&mut cx) {
+///     last = Some(partial);
+/// }
+/// let last = last.unwrap();
+/// assert!(last.done);
+/// assert_eq!(last.stats.total_words, 5);
+/// ```
+pub fn from_chunks<I>(
+    chunks: I,
+    config: AnalyzerConfig,
+) -> StatsStream<impl FnMut(&mut Context<'_>) -> Poll<Option<String>> + Unpin>
+where
+    I: IntoIterator<Item = String>,
+    I::IntoIter: Unpin,
+{
+    let mut chunks = chunks.into_iter();
+    StatsStream::new(
+        move |_: &mut Context<'_>| Poll::Ready(chunks.next()),
+        config,
+    )
+}
+
+/// The start or the end of a document, in words: `--first-n-words` and
+/// `--last-n-words`.
+///
+/// A word is a whitespace-separated token with a letter in it
+/// (`char::is_alphabetic`), the tokens the analyzers count: `«`, `—` or `42`
+/// alone is not one. The text kept
+/// runs from the first byte of the input (or of the first word kept) to the
+/// whitespace after the last word kept (or the end of the input).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordLimit {
+    First(usize),
+    Last(usize),
+}
+
+impl WordLimit {
+    /// The kept text of `reader`. `First` stops reading once it has its
+    /// words; `Last` has to read everything, see `read_file`.
+    pub fn read(&self, mut reader: impl Read) -> io::Result<Vec<u8>> {
+        match *self {
+            WordLimit::First(n) => {
+                let mut bytes = Vec::new();
+                let mut buffer = vec![0; Tuning::current().buffer_bytes.max(1)];
+                let mut scan = WordScan::default();
+                loop {
+                    let read = match reader.read(&mut buffer) {
+                        Ok(0) => return Ok(bytes),
+                        Ok(read) => read,
+                        Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                        Err(e) => return Err(e),
+                    };
+                    if let Some(end) = scan.first(&buffer[..read], n) {
+                        bytes.extend_from_slice(&buffer[..end]);
+                        return Ok(bytes);
+                    }
+                    bytes.extend_from_slice(&buffer[..read]);
+                }
+            }
+            WordLimit::Last(n) => {
+                let mut bytes = Vec::new();
+                reader.read_to_end(&mut bytes)?;
+                let start = last_words_start(&bytes, n, true).unwrap_or(0);
+                bytes.drain(..start);
+                Ok(bytes)
+            }
+        }
+    }
+
+    /// The kept text of the file at `path`. `Last` reads backwards from the
+    /// end of the file, in growing blocks, until it has its words.
+    pub fn read_file(&self, path: &Path) -> Result<Vec<u8>> {
+        let io_error = |source| Td5Error::Io {
+            path: Some(path.to_path_buf()),
+            source,
+        };
+        let mut file = File::open(path).map_err(io_error)?;
+        let WordLimit::Last(n) = *self else {
+            return self.read(file).map_err(io_error);
+        };
+        let mut end = file.metadata().map_err(io_error)?.len();
+        let mut block = Tuning::current().buffer_bytes.max(1) as u64;
+        let mut bytes: Vec<u8> = Vec::new();
+        loop {
+            let start = end.saturating_sub(block);
+            let mut chunk = vec![0; (end - start) as usize];
+            file.seek(SeekFrom::Start(start)).map_err(io_error)?;
+            file.read_exact(&mut chunk).map_err(io_error)?;
+            chunk.extend_from_slice(&bytes);
+            bytes = chunk;
+            if let Some(kept) = last_words_start(&bytes, n, start == 0) {
+                bytes.drain(..kept);
+                return Ok(bytes);
+            }
+            end = start;
+            block *= 2;
+        }
+    }
+}
+
+/// Word boundaries of text arriving in pieces.
+#[derive(Default)]
+struct WordScan {
+    words: usize,
+    in_token: bool,
+    has_letter: bool,
+    /// The bytes of the current token's last non-ASCII character, which a
+    /// piece may cut.
+    pending: Vec<u8>,
+}
+
+impl WordScan {
+    /// Scans `chunk`; returns the length of it to keep once the `n`th word
+    /// has ended.
+    fn first(&mut self, chunk: &[u8], n: usize) -> Option<usize> {
+        if n == 0 {
+            return Some(0);
+        }
+        for (i, &b) in chunk.iter().enumerate() {
+            // A byte other than a continuation byte ends the character
+            // before it.
+            if b & 0xc0 != 0x80 && !self.pending.is_empty() {
+                self.has_letter |= has_letter(&self.pending);
+                self.pending.clear();
+            }
+            if b.is_ascii_whitespace() {
+                if self.in_token && self.has_letter {
+                    self.words += 1;
+                    if self.words == n {
+                        return Some(i);
+                    }
+                }
+                self.in_token = false;
+                self.has_letter = false;
+            } else {
+                self.in_token = true;
+                if b.is_ascii() {
+                    self.has_letter |= b.is_ascii_alphabetic();
+                } else {
+                    self.pending.push(b);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Where the last `n` words of `bytes` start, or `None` if `bytes` may not
+/// hold all of them yet; `complete` says nothing comes before `bytes`.
+fn last_words_start(bytes: &[u8], n: usize, complete: bool) -> Option<usize> {
+    if n == 0 {
+        return Some(bytes.len());
+    }
+    let mut words = 0;
+    let mut end = bytes.len();
+    while end > 0 {
+        while end > 0 && bytes[end - 1].is_ascii_whitespace() {
+            end -= 1;
+        }
+        let mut start = end;
+        while start > 0 && !bytes[start - 1].is_ascii_whitespace() {
+            start -= 1;
+        }
+        if start == end {
+            break;
+        }
+        // A token touching the start of `bytes` may go on before it.
+        if start == 0 && !complete {
+            return None;
+        }
+        if has_letter(&bytes[start..end]) {
+            words += 1;
+            if words == n {
+                return Some(start);
+            }
+        }
+        end = start;
+    }
+    complete.then_some(0)
+}
+
+/// Whether `token` holds a letter, as the tokenizer decides: decoded as
+/// UTF-8, invalid sequences replaced.
+fn has_letter(token: &[u8]) -> bool {
+    String::from_utf8_lossy(token)
+        .chars()
+        .any(char::is_alphabetic)
+}
+
+/// Future returned by `StatsStream::next_snapshot`.
+pub struct Next<'a, F>(&'a mut StatsStream<F>);
+
+impl<F> Future for Next<'_, F>
+where
+    F: FnMut(&mut Context<'_>) -> Poll<Option<String>> + Unpin,
+{
+    type Output = Option<PartialStats>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut *self.get_mut().0).poll_next(cx)
+    }
+}
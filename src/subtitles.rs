@@ -0,0 +1,203 @@
+//! SubRip (`.srt`) and WebVTT (`.vtt`) input: the dialogue without timings
+//! and markup, and how fast it is spoken.
+//!
+//! A cue is a block of lines containing a `start --> end` timing line; the
+//! lines after it are its text. Blocks without a timing line (SRT indices
+//! aside, the `WEBVTT` header, `NOTE`, `STYLE` and `REGION` blocks) are
+//! skipped. Markup is removed: HTML-like tags (`<i>`, `<v Alice>`,
+//! `<00:01.500>`), SSA overrides (`{\an8}`) and the common entities.
+
+use crate::error::{Result, Td5Error};
+use crate::json::Json;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cue {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    /// Plain text, lines joined by newlines.
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subtitles {
+    /// In file order.
+    pub cues: Vec<Cue>,
+}
+
+/// Words per minute, from the number of words in each cue.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpeechRate {
+    pub words: usize,
+    /// From the start of the first cue to the end of the last.
+    pub span_ms: u64,
+    /// Time with at least one cue on screen.
+    pub speaking_ms: u64,
+    /// Words over the whole span.
+    pub overall_wpm: f64,
+    /// Words over the time something is said.
+    pub speaking_wpm: f64,
+    /// Most words whose cue starts in the same minute of the timeline.
+    pub peak_wpm: usize,
+}
+
+/// Whether `path` or `text` looks like subtitles: an `.srt` or `.vtt`
+/// extension, or a `WEBVTT` header.
+pub fn detect(path: &str, text: &str) -> bool {
+    let lower = path.to_ascii_lowercase();
+    lower.ends_with(".srt")
+        || lower.ends_with(".vtt")
+        || text.trim_start_matches('\u{feff}').starts_with("WEBVTT")
+}
+
+impl Subtitles {
+    /// The cues of `source`. A timing that is not a time of day (`99:99:99,999`)
+    /// or that ends before it starts is an error naming the line.
+    pub fn parse(source: &str) -> Result<Subtitles> {
+        let mut cues = Vec::new();
+        let mut lines = source.lines().map(|l| l.trim_end_matches('\r')).peekable();
+        while lines.peek().is_some() {
+            let block: Vec<&str> = lines
+                .by_ref()
+                .take_while(|l| !l.trim().is_empty())
+                .collect();
+            let Some(timing) = block.iter().position(|l| l.contains("-->")) else {
+                continue;
+            };
+            let (start, end) = block[timing].split_once("-->").expect("checked above");
+            // VTT cue settings follow the end time.
+            let end = end.split_whitespace().next().unwrap_or("");
+            let (Some(start_ms), Some(end_ms)) = (time(start.trim()), time(end)) else {
+                return Err(Td5Error::config(format!(
+                    "invalid cue timing `{}`",
+                    block[timing]
+                )));
+            };
+            if end_ms < start_ms {
+                return Err(Td5Error::config(format!(
+                    "cue ends before it starts in `{}`",
+                    block[timing]
+                )));
+            }
+            let text: Vec<String> = block[timing + 1..]
+                .iter()
+                .map(|l| strip_markup(l))
+                .collect();
+            cues.push(Cue {
+                start_ms,
+                end_ms,
+                text: text.join("\n"),
+            });
+        }
+        if cues.is_empty() {
+            return Err(Td5Error::config("no subtitle cues found"));
+        }
+        Ok(Subtitles { cues })
+    }
+
+    /// The text of every cue, one per paragraph.
+    pub fn dialogue(&self) -> String {
+        let texts: Vec<&str> = self.cues.iter().map(|c| c.text.as_str()).collect();
+        texts.join("\n\n")
+    }
+
+    pub fn speech_rate(&self) -> SpeechRate {
+        let words = |text: &str| {
+            text.split_whitespace()
+                .filter(|t| t.chars().any(char::is_alphabetic))
+                .count()
+        };
+        let start = self.cues.iter().map(|c| c.start_ms).min().unwrap_or(0);
+        let end = self.cues.iter().map(|c| c.end_ms).max().unwrap_or(0);
+        let mut spans: Vec<(u64, u64)> = self.cues.iter().map(|c| (c.start_ms, c.end_ms)).collect();
+        spans.sort_unstable();
+        let (mut speaking_ms, mut covered) = (0, 0);
+        for (from, to) in spans {
+            let from = from.max(covered);
+            if to > from {
+                speaking_ms += to - from;
+            }
+            covered = covered.max(to);
+        }
+        let mut per_minute: Vec<usize> = Vec::new();
+        let mut total = 0;
+        for cue in &self.cues {
+            let n = words(&cue.text);
+            total += n;
+            let minute = ((cue.start_ms - start) / 60_000) as usize;
+            if per_minute.len() <= minute {
+                per_minute.resize(minute + 1, 0);
+            }
+            per_minute[minute] += n;
+        }
+        let per_minute_of = |ms: u64| total as f64 / (ms.max(1) as f64 / 60_000.0);
+        SpeechRate {
+            words: total,
+            span_ms: end - start,
+            speaking_ms,
+            overall_wpm: per_minute_of(end - start),
+            speaking_wpm: per_minute_of(speaking_ms),
+            peak_wpm: per_minute.into_iter().max().unwrap_or(0),
+        }
+    }
+}
+
+impl SpeechRate {
+    pub fn to_json_value(&self) -> Json {
+        Json::Object(vec![
+            ("words".into(), self.words.into()),
+            ("span_ms".into(), self.span_ms.into()),
+            ("speaking_ms".into(), self.speaking_ms.into()),
+            ("overall_wpm".into(), self.overall_wpm.into()),
+            ("speaking_wpm".into(), self.speaking_wpm.into()),
+            ("peak_wpm".into(), self.peak_wpm.into()),
+        ])
+    }
+}
+
+/// `[hh:]mm:ss[,.]mmm` in milliseconds: minutes and seconds are two digits
+/// below 60, hours any number of digits.
+fn time(s: &str) -> Option<u64> {
+    let (clock, millis) = s.split_once([',', '.'])?;
+    let parts: Vec<&str> = clock.split(':').collect();
+    if !(2..=3).contains(&parts.len()) {
+        return None;
+    }
+    let mut secs = 0;
+    for (i, part) in parts.iter().enumerate() {
+        if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let value = part.parse::<u64>().ok()?;
+        // Every part but the hours.
+        if i + 2 >= parts.len() && (part.len() != 2 || value >= 60) {
+            return None;
+        }
+        secs = secs * 60 + value;
+    }
+    if millis.len() != 3 || !millis.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some(secs * 1000 + millis.parse::<u64>().ok()?)
+}
+
+fn strip_markup(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '<' => {
+                chars.by_ref().find(|&c| c == '>');
+            }
+            '{' => {
+                chars.by_ref().find(|&c| c == '}');
+            }
+            c => out.push(c),
+        }
+    }
+    out.replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .trim()
+        .to_string()
+}
@@ -0,0 +1,208 @@
+//! A small Handlebars-style template language for user-defined reports.
+//!
+//! Templates are rendered against a `Json` context (`TextStats::to_json_value`
+//! or `BenchReport::template_context`). Supported tags:
+//!
+//! - `{{path.to.value}}` prints a value; numeric segments index arrays
+//!   (`top_words.0.1`). Strings print raw, arrays and objects as JSON, and
+//!   missing values as nothing. Output is not HTML-escaped.
+//! - `{{this}}` is the current `#each` item, `{{@index}}` its 0-based position.
+//! - `{{#each path}}…{{/each}}` repeats for every array item or object value.
+//! - `{{#if path}}…{{else}}…{{/if}}`; false, null, 0, "" and empty arrays
+//!   are falsy.
+//! - `{{! comment }}` prints nothing.
+//!
+//! Inside `#each`, a name not found on the item is looked up in the enclosing
+//! scopes, so `{{#each results}}{{environment.os}}{{/each}}` works.
+
+use crate::error::{Result, Td5Error};
+use crate::json::Json;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Value(String),
+    Each(String, Vec<Node>),
+    If(String, Vec<Node>, Vec<Node>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+/// An open block while parsing: its kind, path, the nodes of its `if`
+/// branch once `{{else}}` is seen, and the nodes collected so far.
+struct Open {
+    kind: &'static str,
+    path: String,
+    then: Option<Vec<Node>>,
+    nodes: Vec<Node>,
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Template> {
+        let invalid = |why: String| Td5Error::config(format!("template: {why}"));
+        let mut stack: Vec<Open> = Vec::new();
+        let mut nodes = Vec::new();
+        let mut rest = source;
+        while !rest.is_empty() {
+            let (text, tag) = match rest.find("{{") {
+                Some(at) => {
+                    let len = rest[at..]
+                        .find("}}")
+                        .ok_or_else(|| invalid("unclosed `{{`".into()))?;
+                    let tag = rest[at + 2..at + len].trim();
+                    let text = &rest[..at];
+                    rest = &rest[at + len + 2..];
+                    (text, Some(tag))
+                }
+                None => (std::mem::take(&mut rest), None),
+            };
+            let current = stack.last_mut().map_or(&mut nodes, |o| &mut o.nodes);
+            if !text.is_empty() {
+                current.push(Node::Text(text.to_string()));
+            }
+            let Some(tag) = tag else { break };
+            if tag.starts_with('!') {
+                continue;
+            }
+            if let Some(block) = tag.strip_prefix('#') {
+                let (kind, path) = block.split_once(char::is_whitespace).unwrap_or((block, ""));
+                let kind = match kind {
+                    "each" => "each",
+                    "if" => "if",
+                    other => return Err(invalid(format!("unknown block `#{other}`"))),
+                };
+                if path.trim().is_empty() {
+                    return Err(invalid(format!("`#{kind}` needs a path")));
+                }
+                stack.push(Open {
+                    kind,
+                    path: path.trim().to_string(),
+                    then: None,
+                    nodes: Vec::new(),
+                });
+            } else if tag == "else" {
+                match stack.last_mut() {
+                    Some(open) if open.kind == "if" && open.then.is_none() => {
+                        open.then = Some(std::mem::take(&mut open.nodes));
+                    }
+                    _ => return Err(invalid("`{{else}}` outside `#if`".into())),
+                }
+            } else if let Some(kind) = tag.strip_prefix('/') {
+                let open = stack
+                    .pop()
+                    .filter(|o| o.kind == kind)
+                    .ok_or_else(|| invalid(format!("unexpected `{{{{/{kind}}}}}`")))?;
+                let node = match (open.kind, open.then) {
+                    ("each", _) => Node::Each(open.path, open.nodes),
+                    (_, Some(then)) => Node::If(open.path, then, open.nodes),
+                    (_, None) => Node::If(open.path, open.nodes, Vec::new()),
+                };
+                stack
+                    .last_mut()
+                    .map_or(&mut nodes, |o| &mut o.nodes)
+                    .push(node);
+            } else {
+                current.push(Node::Value(tag.to_string()));
+            }
+        }
+        if let Some(open) = stack.last() {
+            return Err(invalid(format!(
+                "`#{} {}` is never closed",
+                open.kind, open.path
+            )));
+        }
+        Ok(Template { nodes })
+    }
+
+    pub fn render(&self, context: &Json) -> String {
+        let mut out = String::new();
+        render(
+            &self.nodes,
+            &mut vec![Scope {
+                value: context,
+                index: None,
+            }],
+            &mut out,
+        );
+        out
+    }
+}
+
+struct Scope<'a> {
+    value: &'a Json,
+    index: Option<usize>,
+}
+
+fn render<'a>(nodes: &'a [Node], scopes: &mut Vec<Scope<'a>>, out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Value(path) if path == "@index" => {
+                if let Some(index) = scopes.last().and_then(|s| s.index) {
+                    out.push_str(&index.to_string());
+                }
+            }
+            Node::Value(path) => match lookup(path, scopes) {
+                Some(Json::String(s)) => out.push_str(s),
+                Some(Json::Null) | None => {}
+                Some(other) => out.push_str(&other.to_string()),
+            },
+            Node::Each(path, body) => {
+                let items: Vec<&Json> = match lookup(path, scopes) {
+                    Some(Json::Array(items)) => items.iter().collect(),
+                    Some(Json::Object(fields)) => fields.iter().map(|(_, v)| v).collect(),
+                    _ => Vec::new(),
+                };
+                for (index, value) in items.into_iter().enumerate() {
+                    scopes.push(Scope {
+                        value,
+                        index: Some(index),
+                    });
+                    render(body, scopes, out);
+                    scopes.pop();
+                }
+            }
+            Node::If(path, then, otherwise) => {
+                let branch = if lookup(path, scopes).is_some_and(truthy) {
+                    then
+                } else {
+                    otherwise
+                };
+                render(branch, scopes, out);
+            }
+        }
+    }
+}
+
+fn lookup<'a>(path: &str, scopes: &[Scope<'a>]) -> Option<&'a Json> {
+    let current = scopes.last()?;
+    let mut segments = path.split('.');
+    let first = segments.next()?;
+    let start = if first == "this" {
+        current.value
+    } else {
+        scopes.iter().rev().find_map(|s| child(s.value, first))?
+    };
+    segments.try_fold(start, |value, segment| child(value, segment))
+}
+
+fn child<'a>(value: &'a Json, segment: &str) -> Option<&'a Json> {
+    match value {
+        Json::Array(items) => items.get(segment.parse::<usize>().ok()?),
+        _ => value.get(segment),
+    }
+}
+
+fn truthy(value: &Json) -> bool {
+    match value {
+        Json::Null => false,
+        Json::Bool(b) => *b,
+        Json::Number(n) => *n != 0.0,
+        Json::String(s) => !s.is_empty(),
+        Json::Array(items) => !items.is_empty(),
+        Json::Object(_) => true,
+    }
+}
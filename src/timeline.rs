@@ -0,0 +1,225 @@
+//! Word statistics per minute, hour or day of timestamped, log-style input,
+//! with the terms that are trending in each bucket.
+//!
+//! Lines are assigned to buckets by their leading timestamp (see
+//! `timestamp`); a line without one belongs to the previous timestamped line
+//! of the same input, and lines before the first timestamp are not counted.
+//! Buckets come out in time order whatever order the lines were in, so
+//! rotated logs can be fed in any order. Buckets without any line are left
+//! out of the series.
+
+use crate::error::{Result, Td5Error};
+use crate::json::Json;
+use crate::{AnalyzerConfig, TextStats, fast, timestamp};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+/// How many trending terms are reported per bucket.
+const TRENDING: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bucket {
+    Minute,
+    Hour,
+    Day,
+}
+
+impl Bucket {
+    pub const ALL: [Bucket; 3] = [Bucket::Minute, Bucket::Hour, Bucket::Day];
+
+    pub fn parse(name: &str) -> Result<Bucket> {
+        Self::ALL
+            .into_iter()
+            .find(|b| b.name() == name)
+            .ok_or_else(|| {
+                Td5Error::config(format!(
+                    "unknown bucket `{name}` (expected minute, hour or day)"
+                ))
+            })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Bucket::Minute => "minute",
+            Bucket::Hour => "hour",
+            Bucket::Day => "day",
+        }
+    }
+
+    pub fn seconds(self) -> i64 {
+        match self {
+            Bucket::Minute => 60,
+            Bucket::Hour => 3600,
+            Bucket::Day => 86_400,
+        }
+    }
+}
+
+/// A word used markedly more than in the previous bucket.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trend {
+    pub word: String,
+    pub count: usize,
+    /// Count in the previous non-empty bucket.
+    pub previous: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BucketStats {
+    /// Unix seconds at which the bucket starts.
+    pub start: i64,
+    pub stats: TextStats,
+    /// Words ranked by how far their count exceeds the previous bucket's,
+    /// scaled to this bucket's size. Empty for the first bucket.
+    pub trending: Vec<Trend>,
+}
+
+impl BucketStats {
+    /// The `TextStats` document plus `bucket` (ISO 8601), `start` and
+    /// `trending` as `[word, count, previous]` triples.
+    pub fn to_json_value(&self) -> Json {
+        let mut doc = self.stats.to_json_value();
+        doc.set("bucket", timestamp::format(self.start).into());
+        doc.set("start", self.start.into());
+        doc.set(
+            "trending",
+            Json::Array(
+                self.trending
+                    .iter()
+                    .map(|t| {
+                        Json::Array(vec![
+                            t.word.as_str().into(),
+                            t.count.into(),
+                            t.previous.into(),
+                        ])
+                    })
+                    .collect(),
+            ),
+        );
+        doc
+    }
+}
+
+pub struct Timeline {
+    bucket: Bucket,
+    /// Text of each bucket, keyed by its start.
+    texts: BTreeMap<i64, String>,
+    untimestamped: usize,
+}
+
+impl Timeline {
+    pub fn new(bucket: Bucket) -> Self {
+        Self {
+            bucket,
+            texts: BTreeMap::new(),
+            untimestamped: 0,
+        }
+    }
+
+    /// Adds the lines of one input.
+    pub fn push_lines(&mut self, text: &str) {
+        let size = self.bucket.seconds();
+        let mut current = None;
+        for line in text.lines() {
+            let rest = match timestamp::split(line) {
+                Some((at, rest)) => {
+                    current = Some(at.div_euclid(size) * size);
+                    rest
+                }
+                None => line,
+            };
+            match current {
+                Some(start) => {
+                    let text = self.texts.entry(start).or_default();
+                    text.push_str(rest);
+                    text.push('\n');
+                }
+                None if !line.trim().is_empty() => self.untimestamped += 1,
+                None => {}
+            }
+        }
+    }
+
+    /// Non-blank lines skipped because no timestamp preceded them.
+    pub fn untimestamped_lines(&self) -> usize {
+        self.untimestamped
+    }
+
+    /// Stats per bucket, oldest first.
+    pub fn finish(self) -> Vec<BucketStats> {
+        let config = AnalyzerConfig::default();
+        let mut series = Vec::with_capacity(self.texts.len());
+        let mut previous: Option<(fast::FastMap<String, usize>, usize)> = None;
+        for (start, text) in self.texts {
+            let counts = fast::count(&text, &config);
+            let map = counts.clone().into_map();
+            let stats = fast::finish(counts, &config);
+            let total = stats.total_words;
+            let trending = match &previous {
+                Some((before, before_total)) => trending(&map, total, before, *before_total),
+                None => Vec::new(),
+            };
+            series.push(BucketStats {
+                start,
+                stats,
+                trending,
+            });
+            previous = Some((map, total));
+        }
+        series
+    }
+}
+
+fn trending(
+    now: &fast::FastMap<String, usize>,
+    total: usize,
+    before: &fast::FastMap<String, usize>,
+    before_total: usize,
+) -> Vec<Trend> {
+    let scale = total as f64 / before_total.max(1) as f64;
+    let mut rising: Vec<(f64, Trend)> = now
+        .iter()
+        .map(|(word, &count)| {
+            let previous = before.get(word).copied().unwrap_or(0);
+            let excess = count as f64 - previous as f64 * scale;
+            let trend = Trend {
+                word: word.clone(),
+                count,
+                previous,
+            };
+            (excess, trend)
+        })
+        // A single use is noise, not a trend.
+        .filter(|(excess, t)| *excess > 0.0 && t.count > 1)
+        .collect();
+    rising.sort_unstable_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.word.cmp(&b.1.word)));
+    rising.into_iter().take(TRENDING).map(|(_, t)| t).collect()
+}
+
+/// One row per bucket: `bucket,start,total_words,unique_words,char_count,`
+/// `top_words,trending`, where the last two columns are space-separated
+/// `word:count` pairs.
+pub fn to_csv(series: &[BucketStats]) -> String {
+    let mut out =
+        String::from("bucket,start,total_words,unique_words,char_count,top_words,trending\n");
+    for b in series {
+        let pairs = |words: &mut dyn Iterator<Item = (&str, usize)>| {
+            words
+                .map(|(w, c)| format!("{w}:{c}"))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{},{},{}",
+            timestamp::format(b.start),
+            b.start,
+            b.stats.total_words,
+            b.stats.word_count,
+            b.stats.char_count,
+            pairs(&mut b.stats.top_words.iter().map(|(w, c)| (w.as_str(), *c))),
+            pairs(&mut b.trending.iter().map(|t| (t.word.as_str(), t.count))),
+        );
+    }
+    out
+}
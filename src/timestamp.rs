@@ -0,0 +1,123 @@
+//! Leading timestamps on log-style lines, as Unix seconds (UTC).
+//!
+//! Recognized at the start of a line, optionally inside `[...]`:
+//! `2024-05-01T12:30:00`, `2024-05-01 12:30:00` (fraction, `Z` and `+02:00`
+//! offsets allowed) and bare Unix seconds with 9 to 11 digits.
+
+/// The timestamp a line starts with and the rest of the line.
+pub fn split(line: &str) -> Option<(i64, &str)> {
+    let trimmed = line.trim_start();
+    let (inner, bracketed) = match trimmed.strip_prefix('[') {
+        Some(rest) => (rest, true),
+        None => (trimmed, false),
+    };
+    let (secs, len) = iso(inner).or_else(|| unix(inner))?;
+    let mut rest = &inner[len..];
+    if bracketed {
+        rest = rest.strip_prefix(']')?;
+    }
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some((secs, rest))
+}
+
+fn digits(s: &str, n: usize) -> Option<i64> {
+    let part = s.get(..n)?;
+    if !part.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    part.parse().ok()
+}
+
+/// `YYYY-MM-DD[T ]HH:MM:SS[.fff][Z|±HH:MM]`: seconds and bytes consumed.
+fn iso(s: &str) -> Option<(i64, usize)> {
+    let b = s.as_bytes();
+    let sep = |i: usize, c: &[u8]| b.get(i).is_some_and(|x| c.contains(x));
+    if !(sep(4, b"-") && sep(7, b"-") && sep(10, b"T ") && sep(13, b":") && sep(16, b":")) {
+        return None;
+    }
+    let (year, month, day) = (digits(s, 4)?, digits(&s[5..], 2)?, digits(&s[8..], 2)?);
+    let (hour, minute, second) = (
+        digits(&s[11..], 2)?,
+        digits(&s[14..], 2)?,
+        digits(&s[17..], 2)?,
+    );
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+    let mut len = 19;
+    if sep(len, b".,") {
+        len += 1;
+        while sep(len, b"0123456789") {
+            len += 1;
+        }
+    }
+    let mut offset = 0;
+    if sep(len, b"Z") {
+        len += 1;
+    } else if sep(len, b"+-") && sep(len + 3, b":") {
+        let sign = if b[len] == b'-' { -1 } else { 1 };
+        offset = sign * (digits(&s[len + 1..], 2)? * 3600 + digits(&s[len + 4..], 2)? * 60);
+        len += 6;
+    }
+    let days = days_from_civil(year, month, day);
+    Some((
+        days * 86_400 + hour * 3600 + minute * 60 + second - offset,
+        len,
+    ))
+}
+
+fn unix(s: &str) -> Option<(i64, usize)> {
+    let len = s.bytes().take_while(u8::is_ascii_digit).count();
+    if !(9..=11).contains(&len) {
+        return None;
+    }
+    let mut end = len;
+    if s[len..].starts_with('.') {
+        end += 1 + s[len + 1..].bytes().take_while(u8::is_ascii_digit).count();
+    }
+    Some((s[..len].parse().ok()?, end))
+}
+
+/// `secs` as `YYYY-MM-DDTHH:MM:SSZ`.
+pub fn format(secs: i64) -> String {
+    let (days, time) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date (Howard Hinnant's
+/// algorithm).
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Inverse of `days_from_civil`.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
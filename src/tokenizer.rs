@@ -0,0 +1,197 @@
+//! Where the words of a text are, behind one trait, so corpora with their
+//! own idea of a word (identifiers, hashtags, URLs) can be counted without
+//! touching the counting.
+//!
+//! A `Tokenizer` reports the byte ranges of the tokens of a text. Set with
+//! `AnalyzerConfig::tokenizer`, it replaces the scanners, the segmentation
+//! and the punctuation policy in every analyzer that takes a config: each
+//! token is counted as written, lowercased unless the config is
+//! case-sensitive, and the minimum length and long-token policy still apply.
+//! The analyzers hand it one whitespace-separated token at a time, so text
+//! cut at whitespace, into lines or into chunks for the parallel analyzers,
+//! yields the same tokens.
+//!
+//! | Tokenizer | Tokens |
+//! |---|---|
+//! | `AsciiLetters` | runs of ASCII letters |
+//! | `UnicodeTokens` | whitespace-separated tokens with a letter, those the Unicode scanner reads words from |
+//! | `Pattern` | matches of a pattern in the `sections` regex subset |
+//!
+//! `spec` builds one from a name such as `ascii` or `regex:#?\w+`.
+
+use crate::config::AnalyzerConfig;
+use crate::error::{Result, Td5Error};
+use crate::fast::{self, Mode, Sink};
+use crate::sections::LinePattern;
+use std::ops::Range;
+
+/// Finds tokens in text.
+///
+/// ```
+/// use std::ops::Range;
+/// use td5::{AnalyzerConfig, analyze_text_fast_with, analyze_text_slow_with};
+/// use td5::tokenizer::Tokenizer;
+///
+/// /// Splits identifiers at underscores.
+/// struct Snake;
+///
+/// impl Tokenizer for Snake {
+///     fn tokens(&self, text: &str, token: &mut dyn FnMut(Range<usize>)) {
+///         let mut start = 0;
+///         for part in text.split('_') {
+///             token(start..start + part.len());
+///             start += part.len() + 1;
+///         }
+///     }
+/// }
+///
+/// let config = AnalyzerConfig::new().tokenizer(Snake);
+/// for analyze in [analyze_text_slow_with, analyze_text_fast_with] {
+///     let stats = analyze("user_id user_name", &config);
+///     assert_eq!(stats.top_words[0], ("user".to_string(), 2));
+///     assert_eq!(stats.total_words, 4);
+/// }
+/// ```
+pub trait Tokenizer: Send + Sync {
+    /// Calls `token` with the byte range of every token of `text`, in order.
+    /// Ranges must lie on char boundaries and not overlap; empty ones are
+    /// ignored.
+    fn tokens(&self, text: &str, token: &mut dyn FnMut(Range<usize>));
+}
+
+impl Tokenizer for Box<dyn Tokenizer> {
+    fn tokens(&self, text: &str, token: &mut dyn FnMut(Range<usize>)) {
+        (**self).tokens(text, token)
+    }
+}
+
+/// Runs of ASCII letters: `don't` is `don` and `t`, where the scanners
+/// count `dont`, and anything else, digits and non-ASCII letters included,
+/// separates words.
+///
+/// ```
+/// use td5::tokenizer::{AsciiLetters, Tokenizer};
+///
+/// let text = "Don't stop-2-go";
+/// let mut words = Vec::new();
+/// AsciiLetters.tokens(text, &mut |span| words.push(&text[span]));
+/// assert_eq!(words, ["Don", "t", "stop", "go"]);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AsciiLetters;
+
+impl Tokenizer for AsciiLetters {
+    fn tokens(&self, text: &str, token: &mut dyn FnMut(Range<usize>)) {
+        let mut start = None;
+        for (i, b) in text.bytes().enumerate() {
+            match (b.is_ascii_alphabetic(), start) {
+                (true, None) => start = Some(i),
+                (false, Some(s)) => {
+                    token(s..i);
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(s) = start {
+            token(s..text.len());
+        }
+    }
+}
+
+/// Whitespace-separated tokens holding a letter, as the Unicode scanner
+/// reads them. Counted through `AnalyzerConfig::tokenizer`, a token keeps
+/// its punctuation: `l'été.` stays `l'été.` where the scanner counts `lété`.
+///
+/// ```
+/// use td5::tokenizer::{Tokenizer, UnicodeTokens};
+///
+/// let text = "l'été -- 2024 déjà.";
+/// let mut words = Vec::new();
+/// UnicodeTokens.tokens(text, &mut |span| words.push(&text[span]));
+/// assert_eq!(words, ["l'été", "déjà."]);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UnicodeTokens;
+
+impl Tokenizer for UnicodeTokens {
+    fn tokens(&self, text: &str, token: &mut dyn FnMut(Range<usize>)) {
+        let mut spans = Spans(token);
+        let _ = fast::scan(text, Mode::Unicode, &AnalyzerConfig::default(), &mut spans);
+    }
+}
+
+/// Hands the scanners' word spans to a tokenizer's callback.
+struct Spans<'a>(&'a mut dyn FnMut(Range<usize>));
+
+impl Sink for Spans<'_> {
+    fn ascii_word(&mut self, _word: &[u8], span: Range<usize>) {
+        (self.0)(span);
+    }
+
+    fn unicode_word(&mut self, _word: &str, span: Range<usize>) {
+        (self.0)(span);
+    }
+
+    fn add_chars(&mut self, _n: usize) {}
+}
+
+/// The matches of a pattern: literals, `.`, classes such as `[a-z_]`,
+/// `\d \w \s` and their negations, `*`, `+` and `?`, and `^`/`$` for the
+/// start and end of a whitespace-separated token. Each match is as long as
+/// the quantifiers can make it; `\w` is ASCII only.
+///
+/// ```
+/// use td5::AnalyzerConfig;
+/// use td5::tokenizer::Pattern;
+///
+/// let hashtags = Pattern::new(r"#\w+").unwrap();
+/// let config = AnalyzerConfig::new().tokenizer(hashtags);
+/// let stats = td5::analyze_text_fast_with("#Rust is fun. #rust #perf, not #", &config);
+/// assert_eq!(stats.top_words, [("#rust".to_string(), 2), ("#perf".to_string(), 1)]);
+/// assert!(Pattern::new("(a|b)").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern(LinePattern);
+
+impl Pattern {
+    /// Fails on syntax outside the subset.
+    pub fn new(source: &str) -> Result<Pattern> {
+        LinePattern::parse(source).map(Pattern)
+    }
+
+    pub fn source(&self) -> &str {
+        &self.0.source
+    }
+}
+
+impl Tokenizer for Pattern {
+    fn tokens(&self, text: &str, token: &mut dyn FnMut(Range<usize>)) {
+        for span in self.0.find_all(text) {
+            token(span);
+        }
+    }
+}
+
+/// The tokenizer a spec names: `ascii`, `unicode` or `regex:PATTERN`.
+///
+/// ```
+/// use td5::tokenizer;
+///
+/// let config = td5::AnalyzerConfig::new().tokenizer(tokenizer::spec(r"regex:[A-Za-z_]\w*").unwrap());
+/// let stats = td5::analyze_text_fast_with("let user_id = user_id + 1;", &config);
+/// assert_eq!(stats.top_words[0], ("user_id".to_string(), 2));
+/// assert!(tokenizer::spec("words").is_err());
+/// ```
+pub fn spec(spec: &str) -> Result<Box<dyn Tokenizer>> {
+    match spec {
+        "ascii" => Ok(Box::new(AsciiLetters)),
+        "unicode" => Ok(Box::new(UnicodeTokens)),
+        _ => match spec.strip_prefix("regex:") {
+            Some(pattern) => Ok(Box::new(Pattern::new(pattern)?)),
+            None => Err(Td5Error::config(format!(
+                "unknown tokenizer `{spec}` (expected ascii, unicode or regex:PATTERN)"
+            ))),
+        },
+    }
+}
@@ -0,0 +1,109 @@
+//! The fast analyzer's tokenizer on its own: `tokenize` runs the same
+//! scanners and long-token policy as `analyze_text_fast_with` and hands each
+//! word to a callback instead of counting it, for aggregations td5 does not
+//! do itself (per speaker, per channel, per hour...).
+//!
+//! Words come lowercased, in text order. The span is the bytes of the text a
+//! word was read from: its letters on ASCII input, the whole
+//! whitespace-separated token on Unicode input (where letters are picked out
+//! of the token, `l'été` giving `lété`). Tokens without a letter are skipped,
+//! as the analyzers skip them. Control characters go by
+//! `AnalyzerConfig::control_chars`, as for the analyzers: `a\0b` is one
+//! word, `ab`, by default.
+
+use crate::config::{self, AnalyzerConfig, ControlChars};
+use crate::fast::{self, Limited, Mode, SIMPLE_WORD_MAX, Sink};
+use std::ops::Range;
+
+/// Calls `visit` with every word of `text` and the byte range it came from.
+///
+/// ```
+/// let text = "The cat saw the other cat.";
+/// let mut cats = Vec::new();
+/// td5::tokenize(text, &td5::AnalyzerConfig::default(), |word, span| {
+///     if word == "cat" {
+///         cats.push(span.start);
+///     }
+/// });
+/// assert_eq!(cats, [4, 22]);
+///
+/// // A stripped control character stays inside the span.
+/// let mut words = Vec::new();
+/// td5::tokenize("x a\0b", &td5::AnalyzerConfig::default(), |word, span| {
+///     words.push((word.to_string(), span));
+/// });
+/// assert_eq!(words, [("x".to_string(), 0..1), ("ab".to_string(), 2..5)]);
+/// ```
+pub fn tokenize<F>(text: &str, config: &AnalyzerConfig, mut visit: F)
+where
+    F: FnMut(&str, Range<usize>),
+{
+    let (kept, _) = config.control_text(text);
+    let dropped = match config.control_chars {
+        // Replaced by spaces of the same length.
+        ControlChars::Separate => Vec::new(),
+        ControlChars::Strip | ControlChars::Error => dropped(text),
+    };
+    if dropped.is_empty() {
+        return scan(&kept, config, visit);
+    }
+    // Spans in the text as given: a dropped character before the span
+    // shifts it, one inside widens it.
+    let shift = |at: usize, inclusive: bool| {
+        at + dropped
+            .iter()
+            .take_while(|&&(from, _)| from < at || (inclusive && from == at))
+            .map(|&(_, len)| len)
+            .sum::<usize>()
+    };
+    scan(&kept, config, |word, span| {
+        visit(word, shift(span.start, true)..shift(span.end, false));
+    });
+}
+
+/// The control characters stripping drops from `text`: where each was in
+/// the stripped text, and its length.
+fn dropped(text: &str) -> Vec<(usize, usize)> {
+    let mut dropped = Vec::new();
+    let mut removed = 0;
+    for (at, ch) in text
+        .char_indices()
+        .filter(|&(_, ch)| config::is_control(ch))
+    {
+        dropped.push((at - removed, ch.len_utf8()));
+        removed += ch.len_utf8();
+    }
+    dropped
+}
+
+fn scan<F>(text: &str, config: &AnalyzerConfig, visit: F)
+where
+    F: FnMut(&str, Range<usize>),
+{
+    let mode = match fast::detect(text) {
+        // The simple scanner stops at its first over-long word; the ASCII
+        // one splits lowercase text the same way and has no limit.
+        Mode::SimpleLower if text.split(' ').any(|w| w.len() > SIMPLE_WORD_MAX) => Mode::Ascii,
+        mode => mode,
+    };
+    let mut visitor = Visitor(visit);
+    let mut sink = Limited::new(&mut visitor, config);
+    fast::scan(text, mode, config, &mut sink).expect("no word is too long for the chosen scanner");
+}
+
+struct Visitor<F>(F);
+
+impl<F: FnMut(&str, Range<usize>)> Sink for Visitor<F> {
+    #[inline]
+    fn ascii_word(&mut self, word: &[u8], span: Range<usize>) {
+        // SAFETY: scanners only hand out lowercase ASCII here
+        (self.0)(unsafe { std::str::from_utf8_unchecked(word) }, span);
+    }
+
+    #[inline]
+    fn unicode_word(&mut self, word: &str, span: Range<usize>) {
+        (self.0)(word, span);
+    }
+
+    fn add_chars(&mut self, _n: usize) {}
+}
@@ -0,0 +1,342 @@
+//! A pipeline of word transformations applied to the counted words before
+//! they are ranked, declared in a config file: accents stripped, case
+//! folded, suffixes stemmed, synonyms mapped, stopwords dropped, in whatever
+//! order the file lists them.
+//!
+//! The file has one stage per line; `#` starts a comment:
+//!
+//! ```text
+//! strip_accents
+//! lowercase
+//! stem
+//! synonyms color = colour, colours
+//! synonyms big = large, huge
+//! stopwords the, a, an
+//! ```
+//!
+//! Consecutive `synonyms` lines make one stage, and so do consecutive
+//! `stopwords` lines. Stages run once per distinct
+//! word of the frequency table, not once per token, and words that end up
+//! equal are counted together. Words reach the pipeline lowercased and
+//! letters-only by the analyzers, so `lowercase` only matters after a stage
+//! that can bring capitals back, such as a capitalized synonym target.
+//!
+//! Unless `--pipeline` names another file, `td5 analyze` and `td5 wordlist`
+//! read `pipeline.conf` from the directory of the tuning config, if it exists.
+//! A `--merge-map` of variant spellings becomes a first `synonyms` stage.
+
+use crate::error::{Result, Td5Error};
+use crate::fast;
+use crate::freq::WordFrequency;
+#[cfg(feature = "fs")]
+use crate::input;
+use crate::plugin::Filter;
+use crate::tuning::Tuning;
+use crate::{AnalyzerConfig, TextStats};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stage {
+    /// Latin letters with diacritics to their base letter, `œ` to `oe`.
+    StripAccents,
+    Lowercase,
+    /// Light English suffix stripping: `running` → `run`, `studies` →
+    /// `study`, `quickly` → `quick`.
+    Stem,
+    /// Each variant to its canonical word.
+    Synonyms(HashMap<String, String>),
+    /// Words left out of the counts.
+    Stopwords(HashSet<String>),
+    /// Words an extension does not keep left out of the counts.
+    Filter(Filter),
+}
+
+impl Stage {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::StripAccents => "strip_accents",
+            Stage::Lowercase => "lowercase",
+            Stage::Stem => "stem",
+            Stage::Synonyms(_) => "synonyms",
+            Stage::Stopwords(_) => "stopwords",
+            Stage::Filter(_) => "filter",
+        }
+    }
+
+    /// A `Synonyms` stage from a merge map: one `variant<TAB>canonical` pair
+    /// per line, `variant → canonical` and `variant -> canonical` accepted
+    /// too, `#` starting a comment. Chains (`a → b`, `b → c`) are resolved
+    /// here, so each word takes a single lookup.
+    pub fn merge_map(text: &str) -> Result<Stage> {
+        let mut map = HashMap::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let pair = ["\t", "→", "->"]
+                .iter()
+                .find_map(|sep| line.split_once(sep))
+                .map(|(variant, canonical)| (variant.trim(), canonical.trim()))
+                .filter(|(variant, canonical)| !variant.is_empty() && !canonical.is_empty());
+            let Some((variant, canonical)) = pair else {
+                return Err(Td5Error::config(format!(
+                    "line {}: expected `variant<TAB>canonical`",
+                    i + 1
+                )));
+            };
+            map.insert(variant.to_lowercase(), canonical.to_string());
+        }
+        let mut resolved = HashMap::with_capacity(map.len());
+        for (variant, canonical) in &map {
+            let mut target = canonical;
+            let mut steps = 0;
+            while let Some(next) = map.get(target.as_str()).filter(|&next| next != target) {
+                steps += 1;
+                if steps > map.len() {
+                    return Err(Td5Error::config(format!(
+                        "`{variant}` maps back to itself through `{target}`"
+                    )));
+                }
+                target = next;
+            }
+            resolved.insert(variant.clone(), target.clone());
+        }
+        Ok(Stage::Synonyms(resolved))
+    }
+
+    #[cfg(feature = "fs")]
+    pub fn load_merge_map(path: &Path) -> Result<Stage> {
+        let text = input::read_text(path)?;
+        Self::merge_map(&text).map_err(|e| match e {
+            Td5Error::Config(message) => Td5Error::Config(format!("{}: {message}", path.display())),
+            e => e,
+        })
+    }
+
+    fn apply<'a>(&self, word: Cow<'a, str>) -> Cow<'a, str> {
+        match self {
+            Stage::StripAccents if word.is_ascii() => word,
+            Stage::StripAccents => Cow::Owned(word.chars().flat_map(unaccent).collect()),
+            Stage::Lowercase if !word.chars().any(char::is_uppercase) => word,
+            Stage::Lowercase => Cow::Owned(word.to_lowercase()),
+            Stage::Stem => match stem(&word) {
+                Some(stemmed) => Cow::Owned(stemmed),
+                None => word,
+            },
+            Stage::Synonyms(map) => match map.get(word.as_ref()) {
+                Some(canonical) => Cow::Owned(canonical.clone()),
+                None => word,
+            },
+            Stage::Stopwords(words) if words.contains(word.as_ref()) => Cow::Borrowed(""),
+            Stage::Stopwords(_) => word,
+            Stage::Filter(filter) if !word.is_empty() && !filter.0.keep_word(&word) => {
+                Cow::Borrowed("")
+            }
+            Stage::Filter(_) => word,
+        }
+    }
+}
+
+/// `c` without its diacritics, as one or two letters.
+fn unaccent(c: char) -> impl Iterator<Item = char> {
+    let base: &str = match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' => "a",
+        'À' | 'Á' | 'Â' | 'Ã' | 'Ä' | 'Å' | 'Ā' => "A",
+        'ç' | 'ć' | 'č' => "c",
+        'Ç' | 'Ć' | 'Č' => "C",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ę' | 'ě' => "e",
+        'È' | 'É' | 'Ê' | 'Ë' | 'Ē' | 'Ę' | 'Ě' => "E",
+        'ì' | 'í' | 'î' | 'ï' | 'ī' => "i",
+        'Ì' | 'Í' | 'Î' | 'Ï' | 'Ī' => "I",
+        'ñ' | 'ń' | 'ň' => "n",
+        'Ñ' | 'Ń' | 'Ň' => "N",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' => "o",
+        'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ö' | 'Ø' | 'Ō' => "O",
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' => "u",
+        'Ù' | 'Ú' | 'Û' | 'Ü' | 'Ū' | 'Ů' => "U",
+        'ý' | 'ÿ' => "y",
+        'Ý' | 'Ÿ' => "Y",
+        'š' | 'ś' => "s",
+        'Š' | 'Ś' => "S",
+        'ž' | 'ź' | 'ż' => "z",
+        'Ž' | 'Ź' | 'Ż' => "Z",
+        'ł' => "l",
+        'Ł' => "L",
+        'œ' => "oe",
+        'Œ' => "OE",
+        'æ' => "ae",
+        'Æ' => "AE",
+        'ß' => "ss",
+        _ => "",
+    };
+    let mut chars = base.chars().peekable();
+    let keep = chars.peek().is_none().then_some(c);
+    keep.into_iter().chain(chars)
+}
+
+fn has_vowel(s: &str) -> bool {
+    s.chars().any(|c| "aeiouy".contains(c))
+}
+
+/// The stem of a lowercase English word, `None` if it has no suffix to
+/// strip.
+fn stem(word: &str) -> Option<String> {
+    if word.chars().count() <= 3 {
+        return None;
+    }
+    const SUFFIXES: [(&str, &str); 8] = [
+        ("sses", "ss"),
+        ("ies", "y"),
+        ("ingly", ""),
+        ("edly", ""),
+        ("ing", ""),
+        ("ed", ""),
+        ("ly", ""),
+        ("s", ""),
+    ];
+    for (suffix, replacement) in SUFFIXES {
+        let Some(base) = word.strip_suffix(suffix) else {
+            continue;
+        };
+        // `glass`, `bus` and `analysis` are not plurals.
+        if suffix == "s" && (base.ends_with('s') || base.ends_with('u') || base.ends_with('i')) {
+            return None;
+        }
+        // `family` and `really` keep their `ly`.
+        let shortest = if suffix == "ly" { 5 } else { 3 };
+        if base.chars().count() < shortest || !has_vowel(base) {
+            return None;
+        }
+        let mut stem = format!("{base}{replacement}");
+        // `running` → `runn` → `run`, but `falling` stays `fall`.
+        if matches!(suffix, "ing" | "ed" | "ingly" | "edly") {
+            let bytes = stem.as_bytes();
+            if let [.., a, b] = bytes
+                && a == b
+                && !b"aeiouylsz".contains(b)
+            {
+                stem.pop();
+            }
+        }
+        return Some(stem);
+    }
+    None
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pipeline {
+    pub stages: Vec<Stage>,
+}
+
+impl Pipeline {
+    pub fn parse(text: &str) -> Result<Pipeline> {
+        let mut stages: Vec<Stage> = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (name, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let stage = match name {
+                "strip_accents" => Stage::StripAccents,
+                "lowercase" => Stage::Lowercase,
+                "stem" => Stage::Stem,
+                "synonyms" => {
+                    let invalid = || {
+                        Td5Error::config(format!(
+                            "line {}: expected `synonyms word = variant, variant`",
+                            i + 1
+                        ))
+                    };
+                    let (canonical, variants) = rest.split_once('=').ok_or_else(invalid)?;
+                    let canonical = canonical.trim();
+                    if canonical.is_empty() {
+                        return Err(invalid());
+                    }
+                    if !matches!(stages.last(), Some(Stage::Synonyms(_))) {
+                        stages.push(Stage::Synonyms(HashMap::new()));
+                    }
+                    let Some(Stage::Synonyms(map)) = stages.last_mut() else {
+                        unreachable!()
+                    };
+                    for variant in variants.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+                        map.insert(variant.to_lowercase(), canonical.to_string());
+                    }
+                    continue;
+                }
+                "stopwords" => {
+                    if !matches!(stages.last(), Some(Stage::Stopwords(_))) {
+                        stages.push(Stage::Stopwords(HashSet::new()));
+                    }
+                    let Some(Stage::Stopwords(words)) = stages.last_mut() else {
+                        unreachable!()
+                    };
+                    words.extend(
+                        rest.split(',')
+                            .map(str::trim)
+                            .filter(|w| !w.is_empty())
+                            .map(str::to_lowercase),
+                    );
+                    continue;
+                }
+                name => {
+                    return Err(Td5Error::config(format!(
+                        "line {}: unknown stage `{name}` (expected strip_accents, lowercase, stem, synonyms or stopwords)",
+                        i + 1
+                    )));
+                }
+            };
+            stages.push(stage);
+        }
+        Ok(Pipeline { stages })
+    }
+
+    #[cfg(feature = "fs")]
+    pub fn load(path: &Path) -> Result<Pipeline> {
+        let text = input::read_text(path)?;
+        Self::parse(&text).map_err(|e| match e {
+            Td5Error::Config(message) => Td5Error::Config(format!("{}: {message}", path.display())),
+            e => e,
+        })
+    }
+
+    /// `pipeline.conf` next to the tuning config.
+    pub fn path() -> Option<PathBuf> {
+        Some(Tuning::path()?.parent()?.join("pipeline.conf"))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// `word` through every stage in order.
+    pub fn apply<'a>(&self, word: &'a str) -> Cow<'a, str> {
+        self.stages
+            .iter()
+            .fold(Cow::Borrowed(word), |word, stage| stage.apply(word))
+    }
+
+    /// `freq` with every word transformed; words that become equal are
+    /// merged and words that become empty dropped.
+    pub fn transform(&self, freq: &WordFrequency) -> WordFrequency {
+        freq.map_words(|word| self.apply(word))
+    }
+
+    /// The fast analyzer's stats of `text` with the pipeline applied to its
+    /// words; `char_count`, `diagnostics`, lines and sentences are the
+    /// untransformed text's, word lengths the transformed words'.
+    pub fn analyze(&self, text: &str) -> TextStats {
+        let counts = fast::count(text, &AnalyzerConfig::default());
+        let (diagnostics, shape) = (counts.diagnostics.clone(), counts.shape.clone());
+        let mut stats = self
+            .transform(&WordFrequency::from_counts(counts))
+            .to_stats_with(Some(&shape));
+        stats.diagnostics = diagnostics;
+        stats
+    }
+}
@@ -0,0 +1,148 @@
+//! Word frequency trends across dated corpus snapshots (`td5 trend`): which
+//! words rose or fell the most, relative to each snapshot's size.
+//!
+//! Frequencies are per million words so snapshots of different sizes compare.
+//! A word's change is the log2 ratio of its last to its first frequency, both
+//! smoothed by one occurrence so words appearing or vanishing get a finite,
+//! count-weighted score instead of an infinite one.
+
+use crate::freq::WordFrequency;
+use crate::json::Json;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WordTrend {
+    pub word: String,
+    /// Occurrences in each snapshot.
+    pub counts: Vec<usize>,
+    /// Occurrences per million words in each snapshot.
+    pub per_million: Vec<f64>,
+    /// log2 of the smoothed last over first frequency.
+    pub change: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trend {
+    pub labels: Vec<String>,
+    /// Words in each snapshot.
+    pub totals: Vec<usize>,
+    /// Every word seen at least `min_count` times over all snapshots, by
+    /// decreasing change, ties alphabetically.
+    pub words: Vec<WordTrend>,
+}
+
+impl Trend {
+    /// Compares `snapshots`, oldest first, each with its label.
+    pub fn of(snapshots: &[(String, WordFrequency)], min_count: usize) -> Trend {
+        let totals: Vec<usize> = snapshots.iter().map(|(_, f)| f.total()).collect();
+        let vocabulary: BTreeSet<&str> = snapshots
+            .iter()
+            .flat_map(|(_, f)| f.iter().map(|(w, _)| w))
+            .collect();
+        let per_million = |count: usize, total: usize| 1e6 * count as f64 / total.max(1) as f64;
+        let mut words: Vec<WordTrend> = vocabulary
+            .into_iter()
+            .filter_map(|word| {
+                let counts: Vec<usize> = snapshots.iter().map(|(_, f)| f.count(word)).collect();
+                if counts.iter().sum::<usize>() < min_count.max(1) {
+                    return None;
+                }
+                let smoothed = |i: usize| per_million(counts[i] + 1, totals[i] + 1);
+                let change = match counts.len() {
+                    0 | 1 => 0.0,
+                    n => (smoothed(n - 1) / smoothed(0)).log2(),
+                };
+                Some(WordTrend {
+                    word: word.to_string(),
+                    per_million: counts
+                        .iter()
+                        .zip(&totals)
+                        .map(|(&c, &t)| per_million(c, t))
+                        .collect(),
+                    counts,
+                    change,
+                })
+            })
+            .collect();
+        words.sort_by(|a, b| {
+            b.change
+                .total_cmp(&a.change)
+                .then_with(|| a.word.cmp(&b.word))
+        });
+        Trend {
+            labels: snapshots.iter().map(|(l, _)| l.clone()).collect(),
+            totals,
+            words,
+        }
+    }
+
+    /// The `n` largest rises, biggest first.
+    pub fn rising(&self, n: usize) -> impl Iterator<Item = &WordTrend> {
+        self.words.iter().take_while(|w| w.change > 0.0).take(n)
+    }
+
+    /// The `n` largest falls, biggest first.
+    pub fn falling(&self, n: usize) -> impl Iterator<Item = &WordTrend> {
+        self.words
+            .iter()
+            .rev()
+            .take_while(|w| w.change < 0.0)
+            .take(n)
+    }
+
+    /// The whole table: the word, its count and frequency per million in
+    /// each snapshot, then its change.
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("word");
+        for label in &self.labels {
+            let label = label.replace(['"', ','], "_");
+            let _ = write!(out, ",{label} count,{label} per million");
+        }
+        out.push_str(",log2 change\n");
+        for w in &self.words {
+            out.push_str(&w.word);
+            for (count, pm) in w.counts.iter().zip(&w.per_million) {
+                let _ = write!(out, ",{count},{pm:.2}");
+            }
+            let _ = writeln!(out, ",{:.4}", w.change);
+        }
+        out
+    }
+
+    pub fn to_json_value(&self, top: usize) -> Json {
+        let list = |words: Vec<&WordTrend>| {
+            Json::Array(
+                words
+                    .into_iter()
+                    .map(|w| {
+                        Json::Object(vec![
+                            ("word".into(), w.word.as_str().into()),
+                            (
+                                "counts".into(),
+                                Json::Array(w.counts.iter().map(|&c| c.into()).collect()),
+                            ),
+                            (
+                                "per_million".into(),
+                                Json::Array(w.per_million.iter().map(|&f| f.into()).collect()),
+                            ),
+                            ("change".into(), w.change.into()),
+                        ])
+                    })
+                    .collect(),
+            )
+        };
+        Json::Object(vec![
+            (
+                "snapshots".into(),
+                Json::Array(self.labels.iter().map(|l| l.as_str().into()).collect()),
+            ),
+            (
+                "totals".into(),
+                Json::Array(self.totals.iter().map(|&t| t.into()).collect()),
+            ),
+            ("rising".into(), list(self.rising(top).collect())),
+            ("falling".into(), list(self.falling(top).collect())),
+        ])
+    }
+}
@@ -0,0 +1,281 @@
+//! Machine-specific performance knobs, measured by `td5 tune` and read back
+//! from a config file on every run.
+//!
+//! The values are process-wide: `Tuning::apply` makes them the ones the fast
+//! and parallel paths and the standard-input reader use. They only change
+//! timings, never results.
+//!
+//! The file holds `key = value` lines; `#` starts a comment and missing keys
+//! keep their default. It lives at `$TD5_CONFIG`, else
+//! `$XDG_CONFIG_HOME/td5/tuning.conf`, else `~/.config/td5/tuning.conf`.
+
+#[cfg(feature = "fs")]
+use crate::bench::Summary;
+#[cfg(feature = "fs")]
+use crate::clock::Stopwatch;
+use crate::error::{Result, Td5Error};
+#[cfg(feature = "fs")]
+use crate::input::{self, TempFile};
+#[cfg(feature = "fs")]
+use crate::{analyze_text_fast, analyze_text_parallel};
+#[cfg(feature = "fs")]
+use std::fs::File;
+#[cfg(feature = "fs")]
+use std::hint::black_box;
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tuning {
+    /// Bytes per unit of parallel work; 0 cuts one chunk per thread.
+    pub chunk_bytes: usize,
+    /// Input bytes per pre-allocated word-map slot; 0 disables
+    /// pre-allocation.
+    pub bytes_per_slot: usize,
+    /// First read size for standard input and `analyze_reader`, which then
+    /// adapt it to the source (see `input::AdaptiveBuffer`).
+    pub buffer_bytes: usize,
+}
+
+static CHUNK_BYTES: AtomicUsize = AtomicUsize::new(Tuning::DEFAULT.chunk_bytes);
+static BYTES_PER_SLOT: AtomicUsize = AtomicUsize::new(Tuning::DEFAULT.bytes_per_slot);
+static BUFFER_BYTES: AtomicUsize = AtomicUsize::new(Tuning::DEFAULT.buffer_bytes);
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl Tuning {
+    /// The values used before `td5 tune` existed.
+    pub const DEFAULT: Tuning = Tuning {
+        chunk_bytes: 0,
+        bytes_per_slot: 64,
+        buffer_bytes: 64 * 1024,
+    };
+
+    /// The values in effect.
+    pub fn current() -> Tuning {
+        Tuning {
+            chunk_bytes: CHUNK_BYTES.load(Ordering::Relaxed),
+            bytes_per_slot: BYTES_PER_SLOT.load(Ordering::Relaxed),
+            buffer_bytes: BUFFER_BYTES.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Makes these values the ones in effect for the whole process.
+    pub fn apply(self) {
+        CHUNK_BYTES.store(self.chunk_bytes, Ordering::Relaxed);
+        BYTES_PER_SLOT.store(self.bytes_per_slot, Ordering::Relaxed);
+        BUFFER_BYTES.store(self.buffer_bytes.max(1), Ordering::Relaxed);
+    }
+
+    pub fn parse(text: &str) -> Result<Tuning> {
+        let mut tuning = Tuning::DEFAULT;
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = || Td5Error::config(format!("line {}: expected `key = number`", i + 1));
+            let (key, value) = line.split_once('=').ok_or_else(invalid)?;
+            let value: usize = value.trim().parse().map_err(|_| invalid())?;
+            match key.trim() {
+                "chunk_bytes" => tuning.chunk_bytes = value,
+                "bytes_per_slot" => tuning.bytes_per_slot = value,
+                "buffer_bytes" => tuning.buffer_bytes = value,
+                key => {
+                    return Err(Td5Error::config(format!(
+                        "line {}: unknown key `{key}`",
+                        i + 1
+                    )));
+                }
+            }
+        }
+        Ok(tuning)
+    }
+
+    pub fn to_config(&self) -> String {
+        format!(
+            "chunk_bytes = {}\nbytes_per_slot = {}\nbuffer_bytes = {}\n",
+            self.chunk_bytes, self.bytes_per_slot, self.buffer_bytes
+        )
+    }
+
+    /// Where the config file is read from and `td5 tune` writes to.
+    pub fn path() -> Option<PathBuf> {
+        let env = |name| std::env::var_os(name).filter(|v| !v.is_empty());
+        if let Some(path) = env("TD5_CONFIG") {
+            return Some(path.into());
+        }
+        let dir = env("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(dir.join("td5").join("tuning.conf"))
+    }
+
+    /// The saved tuning, if there is a config file.
+    #[cfg(feature = "fs")]
+    pub fn load() -> Result<Option<Tuning>> {
+        let Some(path) = Self::path().filter(|p| p.exists()) else {
+            return Ok(None);
+        };
+        let text = input::read_text(&path)?;
+        Self::parse(&text).map(Some).map_err(|e| match e {
+            Td5Error::Config(message) => Td5Error::Config(format!("{}: {message}", path.display())),
+            e => e,
+        })
+    }
+
+    /// Writes the config file, `header` first as comment lines.
+    #[cfg(feature = "fs")]
+    pub fn save(&self, path: &Path, header: &str) -> Result<()> {
+        let io_error = |source| Td5Error::Io {
+            path: Some(path.to_path_buf()),
+            source,
+        };
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(io_error)?;
+        }
+        let mut text: String = header.lines().map(|l| format!("# {l}\n")).collect();
+        text.push_str(&self.to_config());
+        std::fs::write(path, text).map_err(io_error)
+    }
+}
+
+/// One knob's candidate values with the median time measured for each.
+#[cfg(feature = "fs")]
+#[derive(Debug, Clone)]
+pub struct Sweep {
+    pub knob: &'static str,
+    pub timings: Vec<(usize, u128)>,
+}
+
+#[cfg(feature = "fs")]
+impl Sweep {
+    /// The fastest candidate.
+    pub fn best(&self) -> usize {
+        self.timings
+            .iter()
+            .min_by_key(|&&(_, ns)| ns)
+            .map_or(0, |&(value, _)| value)
+    }
+}
+
+#[cfg(feature = "fs")]
+fn sweep(
+    knob: &'static str,
+    candidates: &[usize],
+    iterations: usize,
+    mut set: impl FnMut(usize),
+    mut run: impl FnMut() -> Result<()>,
+) -> Result<Sweep> {
+    let mut timings = Vec::with_capacity(candidates.len());
+    for &value in candidates {
+        set(value);
+        run()?;
+        let samples = (0..iterations.max(1))
+            .map(|_| {
+                let start = Stopwatch::start();
+                run().map(|()| start.elapsed_ns())
+            })
+            .collect::<Result<Vec<u128>>>()?;
+        timings.push((value, Summary::from_samples(&samples).median_ns));
+    }
+    Ok(Sweep { knob, timings })
+}
+
+/// Measures every knob on `text`, one after the other with the best values
+/// found so far, and returns the fastest combination. The tuning in effect
+/// is restored afterwards.
+#[cfg(feature = "fs")]
+pub fn tune(text: &str, iterations: usize) -> Result<(Tuning, Vec<Sweep>)> {
+    let previous = Tuning::current();
+    let result = tune_from_default(text, iterations);
+    previous.apply();
+    result
+}
+
+#[cfg(feature = "fs")]
+fn tune_from_default(text: &str, iterations: usize) -> Result<(Tuning, Vec<Sweep>)> {
+    let mut best = Tuning::DEFAULT;
+    best.apply();
+    let mut sweeps = Vec::new();
+
+    let slots = sweep(
+        "bytes_per_slot",
+        &[0, 16, 32, 64, 128, 256],
+        iterations,
+        |v| {
+            Tuning {
+                bytes_per_slot: v,
+                ..best
+            }
+            .apply()
+        },
+        || {
+            black_box(analyze_text_fast(black_box(text)));
+            Ok(())
+        },
+    )?;
+    best.bytes_per_slot = slots.best();
+    sweeps.push(slots);
+
+    // Chunks larger than the input are the same as one per thread.
+    let chunk_sizes: Vec<usize> = [0, 64 << 10, 256 << 10, 1 << 20, 4 << 20]
+        .into_iter()
+        .filter(|&size| size < text.len())
+        .collect();
+    let chunks = sweep(
+        "chunk_bytes",
+        &chunk_sizes,
+        iterations,
+        |v| {
+            Tuning {
+                chunk_bytes: v,
+                ..best
+            }
+            .apply()
+        },
+        || {
+            black_box(analyze_text_parallel(black_box(text), 0));
+            Ok(())
+        },
+    )?;
+    best.chunk_bytes = chunks.best();
+    sweeps.push(chunks);
+
+    let file = TempFile::new("tune");
+    std::fs::write(file.path(), text).map_err(|source| Td5Error::Io {
+        path: Some(file.path().to_path_buf()),
+        source,
+    })?;
+    let buffers = sweep(
+        "buffer_bytes",
+        &[8 << 10, 64 << 10, 256 << 10, 1 << 20],
+        iterations,
+        |v| {
+            Tuning {
+                buffer_bytes: v,
+                ..best
+            }
+            .apply()
+        },
+        || {
+            let io_error = |source| Td5Error::Io {
+                path: Some(file.path().to_path_buf()),
+                source,
+            };
+            let reader = File::open(file.path()).map_err(io_error)?;
+            black_box(input::read_all(reader).map_err(io_error)?);
+            Ok(())
+        },
+    )?;
+    best.buffer_bytes = buffers.best();
+    sweeps.push(buffers);
+
+    Ok((best, sweeps))
+}
@@ -0,0 +1,178 @@
+//! Numbers with units in benchmark logs and technical reports: `15 ms`,
+//! `3.2GB`, `99.9%`, `1,200 req/s`, counted and summarized per unit.
+//!
+//! A number is digits with an optional sign, `,` or `_` digit grouping and a
+//! decimal point. Its unit is either attached (`15ms`) or the next token
+//! (`15 ms`), and must be one of `UNITS`, so `15 apples` is a bare number.
+//! Spellings of the same unit (`sec`, `secs`, `s`) are counted together under
+//! the first one listed.
+
+use crate::json::Json;
+use std::collections::HashMap;
+
+/// Recognized units: the name reported, then every spelling of it.
+pub const UNITS: &[(&str, &[&str])] = &[
+    ("ns", &["ns", "nanoseconds", "nanosecond"]),
+    ("µs", &["µs", "μs", "us", "microseconds", "microsecond"]),
+    ("ms", &["ms", "milliseconds", "millisecond", "msec"]),
+    ("s", &["s", "sec", "secs", "seconds", "second"]),
+    ("min", &["min", "mins", "minutes", "minute"]),
+    ("h", &["h", "hr", "hrs", "hours", "hour"]),
+    ("B", &["B", "bytes", "byte"]),
+    ("KB", &["KB", "kB", "kb"]),
+    ("MB", &["MB", "mb"]),
+    ("GB", &["GB", "gb"]),
+    ("TB", &["TB", "tb"]),
+    ("KiB", &["KiB"]),
+    ("MiB", &["MiB"]),
+    ("GiB", &["GiB"]),
+    ("TiB", &["TiB"]),
+    ("MB/s", &["MB/s", "MBps"]),
+    ("GB/s", &["GB/s", "GBps"]),
+    ("Mbit/s", &["Mbit/s", "Mbps"]),
+    ("Gbit/s", &["Gbit/s", "Gbps"]),
+    ("ops/s", &["ops/s", "op/s", "ops", "iops", "IOPS"]),
+    ("req/s", &["req/s", "rps", "RPS", "qps", "QPS"]),
+    ("fps", &["fps", "FPS"]),
+    ("Hz", &["Hz"]),
+    ("kHz", &["kHz"]),
+    ("MHz", &["MHz"]),
+    ("GHz", &["GHz"]),
+    ("%", &["%", "percent"]),
+    ("x", &["x", "×"]),
+    ("°C", &["°C"]),
+    ("W", &["W", "watts"]),
+    ("mm", &["mm"]),
+    ("cm", &["cm"]),
+    ("m", &["m", "meters", "metres"]),
+    ("km", &["km"]),
+    ("g", &["g", "grams"]),
+    ("kg", &["kg"]),
+];
+
+fn unit(spelling: &str) -> Option<&'static str> {
+    UNITS
+        .iter()
+        .find(|(_, spellings)| spellings.contains(&spelling))
+        .map(|&(name, _)| name)
+}
+
+/// Values seen with one unit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UnitStats {
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub sum: f64,
+}
+
+impl UnitStats {
+    pub fn mean(&self) -> f64 {
+        self.sum / self.count as f64
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Quantities {
+    /// Most frequent unit first, ties by name.
+    pub units: Vec<(&'static str, UnitStats)>,
+    /// Numbers without a recognized unit.
+    pub bare: usize,
+}
+
+/// Splits `token` into a leading number and what follows it.
+fn number(token: &str) -> Option<(f64, &str)> {
+    let bytes = token.as_bytes();
+    let mut end = usize::from(matches!(bytes.first(), Some(b'-' | b'+')));
+    let digits_start = end;
+    let mut seen_point = false;
+    while let Some(&b) = bytes.get(end) {
+        let next_is_digit = bytes.get(end + 1).is_some_and(u8::is_ascii_digit);
+        match b {
+            b'0'..=b'9' => {}
+            // Grouping and the decimal point only between digits.
+            b',' | b'_' if end > digits_start && next_is_digit && !seen_point => {}
+            b'.' if end > digits_start && next_is_digit && !seen_point => seen_point = true,
+            _ => break,
+        }
+        end += 1;
+    }
+    if end == digits_start {
+        return None;
+    }
+    let digits: String = token[..end]
+        .chars()
+        .filter(|&c| c != ',' && c != '_')
+        .collect();
+    Some((digits.parse().ok()?, &token[end..]))
+}
+
+impl Quantities {
+    pub fn extract(text: &str) -> Quantities {
+        let tokens: Vec<&str> = text
+            .split_whitespace()
+            .map(|t| {
+                t.trim_start_matches(['(', '[', '~', '≈'])
+                    .trim_end_matches(|c: char| ".,;:!?)]".contains(c))
+            })
+            .collect();
+        let mut units: HashMap<&'static str, UnitStats> = HashMap::new();
+        let mut bare = 0;
+        let mut i = 0;
+        while i < tokens.len() {
+            let Some((value, rest)) = number(tokens[i]) else {
+                i += 1;
+                continue;
+            };
+            let found = if rest.is_empty() {
+                let next = tokens.get(i + 1).and_then(|t| unit(t));
+                i += usize::from(next.is_some());
+                next
+            } else {
+                unit(rest)
+            };
+            i += 1;
+            match found {
+                Some(name) => {
+                    let stats = units.entry(name).or_insert(UnitStats {
+                        count: 0,
+                        min: value,
+                        max: value,
+                        sum: 0.0,
+                    });
+                    stats.count += 1;
+                    stats.min = stats.min.min(value);
+                    stats.max = stats.max.max(value);
+                    stats.sum += value;
+                }
+                // `3rd` or `10k` have no known unit and are not bare numbers
+                // either.
+                None if rest.is_empty() => bare += 1,
+                None => {}
+            }
+        }
+        let mut units: Vec<(&str, UnitStats)> = units.into_iter().collect();
+        units.sort_unstable_by(|a, b| b.1.count.cmp(&a.1.count).then_with(|| a.0.cmp(b.0)));
+        Quantities { units, bare }
+    }
+
+    pub fn to_json_value(&self) -> Json {
+        let units = self
+            .units
+            .iter()
+            .map(|(name, s)| {
+                Json::Object(vec![
+                    ("unit".into(), (*name).into()),
+                    ("count".into(), s.count.into()),
+                    ("min".into(), s.min.into()),
+                    ("mean".into(), s.mean().into()),
+                    ("max".into(), s.max.into()),
+                ])
+            })
+            .collect();
+        Json::Object(vec![
+            ("units".into(), Json::Array(units)),
+            ("bare".into(), self.bare.into()),
+        ])
+    }
+}
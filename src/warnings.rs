@@ -0,0 +1,173 @@
+//! Ways an analysis departed from the plain text, returned next to its
+//! `TextStats` so a caller can tell its own users instead of the issue being
+//! silently absorbed into the counts.
+//!
+//! `Diagnostics` counts what happened; a `Warning` says it in terms of what
+//! the caller asked for (the long-token policy, the sampled share) and only
+//! exists when something did.
+
+use crate::config::{AnalyzerConfig, LongTokenPolicy};
+use crate::json::Json;
+use crate::sample::Estimate;
+use crate::{TextStats, analyze_text_fast_with, bytes};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Warning {
+    /// Tokens longer than the configured limit, handled by `policy`.
+    LongTokens {
+        count: usize,
+        limit: usize,
+        policy: LongTokenPolicy,
+    },
+    /// Invalid UTF-8 sequences replaced by U+FFFD.
+    InvalidUtf8 { sequences: usize },
+    /// The stats were extrapolated from a sample of the input.
+    Approximate { fraction: f64 },
+}
+
+impl Warning {
+    /// Stable identifier, for machine-readable output.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Warning::LongTokens { .. } => "long_tokens",
+            Warning::InvalidUtf8 { .. } => "invalid_utf8",
+            Warning::Approximate { .. } => "approximate",
+        }
+    }
+
+    pub fn to_json_value(&self) -> Json {
+        let mut fields = vec![("kind".into(), self.kind().into())];
+        match *self {
+            Warning::LongTokens {
+                count,
+                limit,
+                policy,
+            } => {
+                fields.push(("count".into(), count.into()));
+                fields.push(("limit".into(), limit.into()));
+                fields.push(("policy".into(), policy_name(policy).into()));
+            }
+            Warning::InvalidUtf8 { sequences } => {
+                fields.push(("sequences".into(), sequences.into()));
+            }
+            Warning::Approximate { fraction } => {
+                fields.push(("fraction".into(), fraction.into()));
+            }
+        }
+        Json::Object(fields)
+    }
+}
+
+fn policy_name(policy: LongTokenPolicy) -> &'static str {
+    match policy {
+        LongTokenPolicy::Keep => "keep",
+        LongTokenPolicy::Skip => "skip",
+        LongTokenPolicy::Truncate => "truncate",
+        LongTokenPolicy::Sentinel => "sentinel",
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Warning::LongTokens {
+                count,
+                limit,
+                policy,
+            } => {
+                let handled = match policy {
+                    LongTokenPolicy::Keep => "counted as they are",
+                    LongTokenPolicy::Skip => "skipped",
+                    LongTokenPolicy::Truncate => "truncated",
+                    LongTokenPolicy::Sentinel => "counted as the sentinel word",
+                };
+                write!(f, "{count} tokens longer than {limit} bytes, {handled}")
+            }
+            Warning::InvalidUtf8 { sequences } => {
+                write!(f, "{sequences} invalid UTF-8 sequences replaced")
+            }
+            Warning::Approximate { fraction } => write!(
+                f,
+                "estimated from a {:.1}% sample, counts are approximate",
+                fraction * 100.0
+            ),
+        }
+    }
+}
+
+/// The warnings of one analysis, in the order above.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Warnings(Vec<Warning>);
+
+impl Warnings {
+    /// What `stats`, produced under `config`, has to report.
+    pub fn from_stats(stats: &TextStats, config: &AnalyzerConfig) -> Warnings {
+        let mut warnings = Warnings::default();
+        if stats.diagnostics.long_tokens > 0 {
+            warnings.push(Warning::LongTokens {
+                count: stats.diagnostics.long_tokens,
+                limit: config.long_token_limit(),
+                policy: config.long_token_policy(),
+            });
+        }
+        if stats.diagnostics.invalid_utf8 > 0 {
+            warnings.push(Warning::InvalidUtf8 {
+                sequences: stats.diagnostics.invalid_utf8,
+            });
+        }
+        warnings
+    }
+
+    /// Adds the warning of a sampled read.
+    pub fn sampled(&mut self, estimate: &Estimate) {
+        if estimate.bytes_read < estimate.bytes_total {
+            self.push(Warning::Approximate {
+                fraction: estimate.fraction,
+            });
+        }
+    }
+
+    pub fn push(&mut self, warning: Warning) {
+        self.0.push(warning);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Warning> {
+        self.0.iter()
+    }
+
+    pub fn to_json_value(&self) -> Json {
+        Json::Array(self.0.iter().map(Warning::to_json_value).collect())
+    }
+}
+
+impl<'a> IntoIterator for &'a Warnings {
+    type Item = &'a Warning;
+    type IntoIter = std::slice::Iter<'a, Warning>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// `analyze_text_fast_with`, and what the caller should hear about.
+pub fn analyze_text(text: &str, config: &AnalyzerConfig) -> (TextStats, Warnings) {
+    let stats = analyze_text_fast_with(text, config);
+    let warnings = Warnings::from_stats(&stats, config);
+    (stats, warnings)
+}
+
+/// `analyze_bytes_with`, and what the caller should hear about.
+pub fn analyze_bytes(input: &[u8], config: &AnalyzerConfig) -> (TextStats, Warnings) {
+    let stats = bytes::analyze_bytes_with(input, config);
+    let warnings = Warnings::from_stats(&stats, config);
+    (stats, warnings)
+}
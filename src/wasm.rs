@@ -0,0 +1,26 @@
+//! The browser API, for a page that loads td5 built with
+//! `--target wasm32-unknown-unknown --no-default-features --features wasm`
+//! and bound with `wasm-bindgen` (or `wasm-pack`).
+//!
+//! ```js
+//! import init, { analyze } from "./pkg/td5.js";
+//!
+//! await init();
+//! const stats = analyze("the cat sat on the mat");
+//! console.log(stats.total_words, stats.top_words);
+//! ```
+
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = JSON)]
+    fn parse(text: &str) -> JsValue;
+}
+
+/// The fast analyzer's stats of `text` with the default config, as an
+/// object with the fields of `td5 analyze --format json`.
+#[wasm_bindgen]
+pub fn analyze(text: &str) -> JsValue {
+    parse(&crate::analyze_text_fast(text).to_json())
+}
@@ -0,0 +1,181 @@
+//! Live stats for a file being edited (`td5 watch`): the file is analyzed
+//! again each time it changes, and only what moved is reported.
+//!
+//! `Watcher` polls the file's modification time and length rather than
+//! subscribing to change notifications. A poll costs one `stat`, works the
+//! same on every platform and on network filesystems, and sees an editor
+//! that saves by renaming a new file over the old one like any other write.
+
+use crate::TextStats;
+use crate::error::{Result, Td5Error};
+use crate::json::Json;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Notices changes to one file by polling it.
+///
+/// ```
+/// use td5::watch::Watcher;
+///
+/// let path = std::env::temp_dir().join(format!("td5-watch-doc-{}.txt", std::process::id()));
+/// std::fs::write(&path, "a first draft").unwrap();
+/// let mut watcher = Watcher::new(&path);
+/// assert!(watcher.changed().unwrap());
+/// assert!(!watcher.changed().unwrap());
+/// std::fs::write(&path, "a second, longer draft").unwrap();
+/// assert!(watcher.changed().unwrap());
+/// std::fs::remove_file(&path).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Watcher {
+    path: PathBuf,
+    /// Modification time and length at the last change seen.
+    seen: Option<(Option<SystemTime>, u64)>,
+}
+
+impl Watcher {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Watcher {
+            path: path.into(),
+            seen: None,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the file changed since the last call; true on the first. A
+    /// file missing for a moment, as while an editor replaces it, has not
+    /// changed yet.
+    pub fn changed(&mut self) -> Result<bool> {
+        let metadata = match std::fs::metadata(&self.path) {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && self.seen.is_some() => {
+                return Ok(false);
+            }
+            Err(source) => {
+                return Err(Td5Error::Io {
+                    path: Some(self.path.clone()),
+                    source,
+                });
+            }
+        };
+        let stamp = (metadata.modified().ok(), metadata.len());
+        let changed = self.seen != Some(stamp);
+        self.seen = Some(stamp);
+        Ok(changed)
+    }
+
+    /// Blocks until the file changes, polling every `interval`.
+    pub fn wait(&mut self, interval: Duration) -> Result<()> {
+        while !self.changed()? {
+            std::thread::sleep(interval);
+        }
+        Ok(())
+    }
+}
+
+/// What changed between two analyses of a text: its counts and its top
+/// words.
+///
+/// ```
+/// use td5::watch::Delta;
+///
+/// let before = td5::analyze("the cat sat on the mat");
+/// let after = td5::analyze("the cat sat on the mat and the dog sat too");
+/// let delta = Delta::between(&before, &after);
+/// assert_eq!(delta.total_words, (6, 11));
+/// assert_eq!(delta.entered[0], ("and".to_string(), 1));
+/// assert_eq!(delta.changed[0], ("the".to_string(), 2, 3));
+/// assert!(Delta::between(&after, &after).is_empty());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delta {
+    /// `total_words` before and after.
+    pub total_words: (usize, usize),
+    /// `word_count`, the distinct words, before and after.
+    pub word_count: (usize, usize),
+    /// Words new to `top_words`, with their count, in rank order.
+    pub entered: Vec<(String, usize)>,
+    /// Words no longer in `top_words`.
+    pub left: Vec<String>,
+    /// Words in both top lists whose count changed, with the count before
+    /// and after, in rank order.
+    pub changed: Vec<(String, usize, usize)>,
+}
+
+impl Delta {
+    pub fn between(before: &TextStats, after: &TextStats) -> Delta {
+        let count = |stats: &TextStats, word: &str| {
+            stats
+                .top_words
+                .iter()
+                .find(|(w, _)| w == word)
+                .map(|&(_, n)| n)
+        };
+        let mut entered = Vec::new();
+        let mut changed = Vec::new();
+        for (word, n) in &after.top_words {
+            match count(before, word) {
+                None => entered.push((word.clone(), *n)),
+                Some(was) if was != *n => changed.push((word.clone(), was, *n)),
+                Some(_) => {}
+            }
+        }
+        let left = before
+            .top_words
+            .iter()
+            .filter(|(word, _)| count(after, word).is_none())
+            .map(|(word, _)| word.clone())
+            .collect();
+        Delta {
+            total_words: (before.total_words, after.total_words),
+            word_count: (before.word_count, after.word_count),
+            entered,
+            left,
+            changed,
+        }
+    }
+
+    /// Whether neither the counts nor the top words moved.
+    pub fn is_empty(&self) -> bool {
+        self.total_words.0 == self.total_words.1
+            && self.word_count.0 == self.word_count.1
+            && self.entered.is_empty()
+            && self.left.is_empty()
+            && self.changed.is_empty()
+    }
+
+    pub fn to_json_value(&self) -> Json {
+        let pair = |(a, b): (usize, usize)| Json::Array(vec![a.into(), b.into()]);
+        Json::Object(vec![
+            ("total_words".into(), pair(self.total_words)),
+            ("word_count".into(), pair(self.word_count)),
+            (
+                "entered".into(),
+                Json::Array(
+                    self.entered
+                        .iter()
+                        .map(|(w, n)| Json::Array(vec![w.as_str().into(), (*n).into()]))
+                        .collect(),
+                ),
+            ),
+            (
+                "left".into(),
+                Json::Array(self.left.iter().map(|w| w.as_str().into()).collect()),
+            ),
+            (
+                "changed".into(),
+                Json::Array(
+                    self.changed
+                        .iter()
+                        .map(|(w, a, b)| {
+                            Json::Array(vec![w.as_str().into(), (*a).into(), (*b).into()])
+                        })
+                        .collect(),
+                ),
+            ),
+        ])
+    }
+}
@@ -0,0 +1,464 @@
+//! MediaWiki XML dumps (`td5 wiki`): the article text of each page, read as
+//! a stream so that multi-gigabyte dumps go through in bounded memory.
+//!
+//! `Pages` pulls one `<page>` at a time out of the XML, keeping only its
+//! title, namespace, whether it is a redirect and the wikitext of its last
+//! revision. `strip_markup` turns wikitext into prose: templates, tables,
+//! references, comments and file or category links are dropped, links are
+//! replaced by their label and headings, lists and emphasis lose their
+//! markup. `analyze_dump` runs the parallel pipeline over the pages: the
+//! reading thread hands them to `threads` workers through a bounded queue,
+//! each worker strips and counts its pages into its own map, and the maps
+//! are merged pairwise at the end, as `parallel` does with the chunks of one
+//! text. At most two pages per worker wait in the queue, so memory is that
+//! of a few pages plus the vocabulary, whatever the size of the dump.
+//!
+//! Compressed dumps are read from a pipe: `bzcat dump.xml.bz2 | td5 wiki -`.
+
+use crate::clock::Stopwatch;
+use crate::error::{Result, Td5Error};
+use crate::fast::{self, Counts};
+use crate::html::{self, decode_entities};
+use crate::parallel::{effective_threads, tree_merge};
+use crate::{AnalyzerConfig, TextStats};
+use std::io::BufRead;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// One `<page>` of a dump.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Page {
+    pub title: String,
+    /// 0 for articles; see the dump's `<namespaces>` for the others.
+    pub namespace: i64,
+    pub redirect: bool,
+    /// The wikitext of the last revision in the dump.
+    pub text: String,
+}
+
+/// The pages of a dump, read as they are needed.
+pub struct Pages<R> {
+    reader: R,
+    buf: Vec<u8>,
+    bytes: u64,
+}
+
+impl<R: BufRead> Pages<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: Vec::new(),
+            bytes: 0,
+        }
+    }
+
+    /// Bytes of XML read so far.
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Field {
+    Title,
+    Namespace,
+    Text,
+}
+
+impl<R: BufRead> Iterator for Pages<R> {
+    type Item = Result<Page>;
+
+    /// ```
+    /// use td5::wiki::Pages;
+    ///
+    /// let xml = r#"<mediawiki><siteinfo><sitename>Wiki</sitename></siteinfo>
+    ///   <page><title>Ada Lovelace</title><ns>0</ns>
+    ///     <revision><text xml:space="preserve">'''Ada''' wrote the first program &amp; more.</text></revision>
+    ///   </page>
+    ///   <page><title>Lovelace</title><ns>0</ns><redirect title="Ada Lovelace" />
+    ///     <revision><text>#REDIRECT [[Ada Lovelace]]</text></revision>
+    ///   </page>
+    /// </mediawiki>"#;
+    /// let pages: Vec<_> = Pages::new(xml.as_bytes()).collect::<Result<_, _>>().unwrap();
+    /// assert_eq!(pages.len(), 2);
+    /// assert_eq!(pages[0].title, "Ada Lovelace");
+    /// assert_eq!(pages[0].text, "'''Ada''' wrote the first program & more.");
+    /// assert!(pages[1].redirect);
+    /// ```
+    fn next(&mut self) -> Option<Result<Page>> {
+        let mut page: Option<Page> = None;
+        let mut field: Option<Field> = None;
+        let mut content: Vec<u8> = Vec::new();
+        loop {
+            // Each read ends at a `>`: the text before a tag, then the tag.
+            self.buf.clear();
+            let n = match self.reader.read_until(b'>', &mut self.buf) {
+                Ok(n) => n,
+                Err(e) => return Some(Err(e.into())),
+            };
+            if n == 0 {
+                return page.map(|_| Err(Td5Error::config("the dump ends inside a page")));
+            }
+            self.bytes += n as u64;
+            let (text, tag) = match self.buf.iter().position(|&b| b == b'<') {
+                Some(lt) if self.buf.ends_with(b">") => self.buf.split_at(lt),
+                // A `>` in text, or the end of the file.
+                _ => (&self.buf[..], &b""[..]),
+            };
+            if field.is_some() {
+                content.extend_from_slice(text);
+            }
+            let Some(inside) = tag.get(1..tag.len().saturating_sub(1)) else {
+                continue;
+            };
+            let inside = String::from_utf8_lossy(inside);
+            let (name, closing) = html::tag_name(&inside);
+            let self_closing = inside.ends_with('/');
+            let Some(current) = &mut page else {
+                if name == "page" && !closing {
+                    page = Some(Page::default());
+                }
+                continue;
+            };
+            match (name.as_str(), closing) {
+                ("page", true) => {
+                    // Old dumps have no `<redirect>` element.
+                    let start = current.text.trim_start().get(..9).unwrap_or("");
+                    current.redirect |= start.eq_ignore_ascii_case("#redirect");
+                    return page.map(Ok);
+                }
+                ("redirect", false) => current.redirect = true,
+                (_, false) if !self_closing => {
+                    field = match name.as_str() {
+                        "title" => Some(Field::Title),
+                        "ns" => Some(Field::Namespace),
+                        "text" => Some(Field::Text),
+                        _ => None,
+                    };
+                    content.clear();
+                }
+                ("text", false) => current.text.clear(),
+                (_, true) => {
+                    let value = decode_entities(&String::from_utf8_lossy(&content));
+                    match field.take() {
+                        Some(Field::Title) => current.title = value,
+                        Some(Field::Namespace) => {
+                            current.namespace = value.trim().parse().unwrap_or(0)
+                        }
+                        Some(Field::Text) => current.text = value,
+                        None => {}
+                    }
+                    content.clear();
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// The prose of a page's wikitext.
+///
+/// ```
+/// use td5::wiki::strip_markup;
+///
+/// let wikitext = "{{Infobox person|name=Ada}}\n\
+///                 '''Ada Lovelace''' was an [[England|English]] [[mathematician]].<ref>{{cite book}}</ref>\n\
+///                 == Life ==\n\
+///                 * She met [[Charles Babbage]] in 1833.[[File:Ada.jpg|thumb|A [[portrait]]]]\n\
+///                 [[Category:Mathematicians]]";
+/// assert_eq!(
+///     strip_markup(wikitext),
+///     "Ada Lovelace was an English mathematician.\nLife\nShe met Charles Babbage in 1833."
+/// );
+/// ```
+pub fn strip_markup(wikitext: &str) -> String {
+    let inline = strip_inline(&drop_elements(wikitext));
+    let mut lines: Vec<&str> = Vec::new();
+    for line in inline.lines() {
+        let line = line.trim();
+        // Leftovers of tables and horizontal rules.
+        if line.starts_with(['|', '!']) || line.starts_with("----") {
+            continue;
+        }
+        let line = line.trim_matches('=').trim();
+        let line = line.trim_start_matches(['*', '#', ':', ';']).trim_start();
+        if !line.is_empty() {
+            lines.push(line);
+        }
+    }
+    html::to_text(&lines.join("\n"))
+}
+
+/// `wikitext` without comments and `<ref>` elements.
+fn drop_elements(wikitext: &str) -> String {
+    let mut out = String::with_capacity(wikitext.len());
+    let mut rest = wikitext;
+    while let Some(open) = rest.find('<') {
+        out.push_str(&rest[..open]);
+        rest = &rest[open..];
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let close = rest.find('>');
+        let (name, closing) = html::tag_name(&rest[1..close.unwrap_or(1)]);
+        match close {
+            Some(close) if name == "ref" && !closing => {
+                if rest[..close].ends_with('/') {
+                    rest = &rest[close + 1..];
+                } else {
+                    rest = rest
+                        .find("</ref>")
+                        .map_or(&rest[close + 1..], |end| &rest[end + 6..]);
+                }
+            }
+            _ => {
+                out.push('<');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// `text` without templates, tables and emphasis, with links replaced by
+/// their label.
+fn strip_inline(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find(['{', '[', '\'', '_']) {
+        out.push_str(&rest[..at]);
+        rest = &rest[at..];
+        if rest.starts_with("{{") || rest.starts_with("{|") {
+            rest = &rest[balanced(rest, &["{{", "{|"], &["}}", "|}"])..];
+        } else if rest.starts_with("[[") {
+            let end = balanced(rest, &["[["], &["]]"]);
+            let inner = rest[2..end].strip_suffix("]]").unwrap_or(&rest[2..end]);
+            out.push_str(&link_label(inner));
+            rest = &rest[end..];
+        } else if rest.starts_with("[http") || rest.starts_with("[//") {
+            let end = rest.find(']').map_or(rest.len(), |end| end + 1);
+            let inner = rest[1..end].trim_end_matches(']');
+            if let Some((_, label)) = inner.split_once(' ') {
+                out.push_str(&strip_inline(label));
+            }
+            rest = &rest[end..];
+        } else if rest.starts_with("''") {
+            rest = rest.trim_start_matches('\'');
+        } else if let Some(word) = magic_word(rest) {
+            rest = &rest[word..];
+        } else {
+            let ch = rest.chars().next().expect("a match");
+            out.push(ch);
+            rest = &rest[ch.len_utf8()..];
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The length of the construct `text` starts with, up to its matching
+/// close or the end of the text.
+fn balanced(text: &str, opens: &[&str], closes: &[&str]) -> usize {
+    let mut depth = 0;
+    let mut i = 0;
+    while i < text.len() {
+        let rest = &text[i..];
+        if opens.iter().any(|open| rest.starts_with(open)) {
+            depth += 1;
+            i += 2;
+        } else if closes.iter().any(|close| rest.starts_with(close)) {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    text.len()
+}
+
+/// What the reader sees of `[[inner]]`: the label, else the target, and
+/// nothing for files, categories and other namespaced targets without a
+/// label (interlanguage links among them).
+fn link_label(inner: &str) -> String {
+    let (target, label) = match inner.find('|') {
+        Some(bar) => (&inner[..bar], Some(&inner[bar + 1..])),
+        None => (inner, None),
+    };
+    let namespace = target
+        .split_once(':')
+        .map(|(ns, _)| ns.trim().to_ascii_lowercase());
+    match (namespace.as_deref(), label) {
+        (Some("file" | "image" | "category" | "media"), _) | (Some(_), None) => String::new(),
+        (_, Some(label)) => strip_inline(label),
+        (None, None) => target.to_string(),
+    }
+}
+
+/// The length of the `__TOC__`-style behaviour switch `text` starts with.
+fn magic_word(text: &str) -> Option<usize> {
+    let name = text.strip_prefix("__")?;
+    let end = name.find("__")?;
+    (end > 0 && name[..end].bytes().all(|b| b.is_ascii_uppercase())).then_some(end + 4)
+}
+
+/// Which pages of a dump are analyzed.
+#[derive(Debug, Clone)]
+pub struct DumpOptions {
+    /// Worker threads; 0 uses all available cores.
+    pub threads: usize,
+    /// Namespaces read; empty for all. Articles only by default.
+    pub namespaces: Vec<i64>,
+    pub redirects: bool,
+    /// Stop after this many pages analyzed.
+    pub limit: Option<usize>,
+}
+
+impl Default for DumpOptions {
+    fn default() -> Self {
+        Self {
+            threads: 0,
+            namespaces: vec![0],
+            redirects: false,
+            limit: None,
+        }
+    }
+}
+
+impl DumpOptions {
+    fn wanted(&self, page: &Page) -> bool {
+        (self.redirects || !page.redirect)
+            && (self.namespaces.is_empty() || self.namespaces.contains(&page.namespace))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DumpStats {
+    pub pages: usize,
+    /// Redirects and pages of other namespaces.
+    pub skipped: usize,
+    pub bytes: u64,
+    /// Of every page analyzed, each counted as a document of its own.
+    pub stats: TextStats,
+    /// Time the whole dump took, reading included.
+    pub time_ns: u128,
+}
+
+/// Stats of the pages of a dump.
+///
+/// ```
+/// use td5::wiki::{DumpOptions, analyze_dump};
+///
+/// let xml = "<mediawiki>\
+///     <page><title>A</title><ns>0</ns><revision><text>One [[fish]], two fish.</text></revision></page>\
+///     <page><title>Talk:A</title><ns>1</ns><revision><text>Why fish?</text></revision></page>\
+///     <page><title>B</title><ns>0</ns><revision><text>{{stub}}Red fish</text></revision></page>\
+///     </mediawiki>";
+/// let dump = analyze_dump(xml.as_bytes(), &td5::AnalyzerConfig::default(), &DumpOptions::default()).unwrap();
+/// assert_eq!((dump.pages, dump.skipped), (2, 1));
+/// assert_eq!(dump.stats.top_words[0], ("fish".to_string(), 3));
+/// assert_eq!((dump.stats.total_words, dump.stats.sentence_count), (6, Some(2)));
+/// ```
+pub fn analyze_dump(
+    reader: impl BufRead,
+    config: &AnalyzerConfig,
+    options: &DumpOptions,
+) -> Result<DumpStats> {
+    run(reader, config, options, None)
+}
+
+/// `analyze_dump`, also calling `on_page` with the title and stats of each
+/// page and the nanoseconds they took, from the worker that counted it: in
+/// file order with one thread, as pages finish with more.
+pub fn analyze_dump_pages(
+    reader: impl BufRead,
+    config: &AnalyzerConfig,
+    options: &DumpOptions,
+    on_page: impl Fn(&str, &TextStats, u128) + Sync,
+) -> Result<DumpStats> {
+    run(reader, config, options, Some(&on_page))
+}
+
+type OnPage<'a> = &'a (dyn Fn(&str, &TextStats, u128) + Sync);
+
+fn run(
+    reader: impl BufRead,
+    config: &AnalyzerConfig,
+    options: &DumpOptions,
+    on_page: Option<OnPage<'_>>,
+) -> Result<DumpStats> {
+    let start = Stopwatch::start();
+    let workers = effective_threads(options.threads);
+    let (sender, receiver) = mpsc::sync_channel::<Page>(workers * 2);
+    let receiver = Arc::new(Mutex::new(receiver));
+    let mut pages = Pages::new(reader);
+    let (mut analyzed, mut skipped) = (0, 0);
+    let (read, parts) = thread::scope(|s| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                let receiver = Arc::clone(&receiver);
+                s.spawn(move || {
+                    let mut total: Option<Counts> = None;
+                    loop {
+                        let next = receiver.lock().expect("queue lock").recv();
+                        let Ok(page) = next else { break };
+                        let text = strip_markup(&page.text);
+                        let page_start = Stopwatch::start();
+                        let mut counts = fast::count_lenient(&text, fast::detect(&text), config);
+                        counts.close();
+                        if let Some(on_page) = on_page {
+                            let stats = fast::finish(counts.clone(), config);
+                            on_page(&page.title, &stats, page_start.elapsed_ns());
+                        }
+                        match &mut total {
+                            Some(total) => total.merge(counts),
+                            None => total = Some(counts),
+                        }
+                    }
+                    total
+                })
+            })
+            .collect();
+        let read: Result<()> = (|| {
+            for page in pages.by_ref() {
+                if options.limit.is_some_and(|limit| analyzed >= limit) {
+                    break;
+                }
+                let page = page?;
+                if !options.wanted(&page) {
+                    skipped += 1;
+                    continue;
+                }
+                analyzed += 1;
+                if sender.send(page).is_err() {
+                    break;
+                }
+            }
+            Ok(())
+        })();
+        drop(sender);
+        let parts: Vec<Counts> = handles
+            .into_iter()
+            .filter_map(|h| h.join().expect("counting thread panicked"))
+            .collect();
+        (read, parts)
+    });
+    read?;
+    let counts = if parts.is_empty() {
+        fast::count_lenient("", fast::detect(""), config)
+    } else {
+        tree_merge(parts).0
+    };
+    Ok(DumpStats {
+        pages: analyzed,
+        skipped,
+        bytes: pages.bytes_read(),
+        stats: fast::finish(counts, config),
+        time_ns: start.elapsed_ns(),
+    })
+}
@@ -0,0 +1,250 @@
+//! Stats over only the most recent part of a stream: the last N tokens, or
+//! the tokens of the last N seconds of timestamped input.
+//!
+//! Unlike the one-shot analyzers, counts here go down as well as up: every
+//! token is remembered in arrival order and uncounted when it leaves the
+//! window. Tokenization follows the reference analyzer.
+
+use crate::clock::{self, Clock};
+use crate::error::{Result, Td5Error};
+use crate::fast::{Shape, is_sentence_end};
+use crate::freq::{by_count, select_top};
+use crate::{Diagnostics, TextStats, timestamp};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowSize {
+    Tokens(usize),
+    Seconds(i64),
+}
+
+impl WindowSize {
+    /// `10000`, `10000 tokens`, or a duration: `90s`, `5m`, `2h`, `1d`.
+    pub fn parse(spec: &str) -> Result<WindowSize> {
+        let invalid = || {
+            Td5Error::config(format!(
+                "invalid window `{spec}` (expected e.g. `10000 tokens` or `5m`)"
+            ))
+        };
+        let spec = spec.trim();
+        let tokens = spec
+            .strip_suffix("tokens")
+            .or_else(|| spec.strip_suffix("token"))
+            .unwrap_or(spec)
+            .trim();
+        if let Ok(n) = tokens.parse::<usize>() {
+            return (n > 0).then_some(WindowSize::Tokens(n)).ok_or_else(invalid);
+        }
+        let unit = match spec.chars().last() {
+            Some('s') => 1,
+            Some('m') => 60,
+            Some('h') => 3600,
+            Some('d') => 86_400,
+            _ => return Err(invalid()),
+        };
+        let n: i64 = spec[..spec.len() - 1]
+            .trim()
+            .parse()
+            .map_err(|_| invalid())?;
+        (n > 0)
+            .then_some(WindowSize::Seconds(n * unit))
+            .ok_or_else(invalid)
+    }
+}
+
+/// A counted token, with where it sits in the text.
+struct Token {
+    word: String,
+    at: Option<i64>,
+    /// The first counted token of its line.
+    line_start: bool,
+    /// The last counted token of its sentence (see `fast::is_sentence_end`).
+    sentence_end: bool,
+}
+
+pub struct SlidingWindow {
+    size: WindowSize,
+    /// Tokens in the window, oldest first.
+    tokens: VecDeque<Token>,
+    counts: HashMap<String, usize>,
+    chars: usize,
+    /// Timestamp inherited by lines that have none.
+    current: Option<i64>,
+    non_alphabetic: usize,
+    clock: Arc<dyn Clock>,
+    start: Duration,
+}
+
+impl SlidingWindow {
+    pub fn new(size: WindowSize) -> Self {
+        Self::with_clock(size, clock::system())
+    }
+
+    /// `elapsed_ns` is measured on `clock`. Seconds windows go by the
+    /// input's timestamps, not by the clock.
+    pub fn with_clock(size: WindowSize, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            size,
+            tokens: VecDeque::new(),
+            counts: HashMap::new(),
+            chars: 0,
+            current: None,
+            non_alphabetic: 0,
+            start: clock.now(),
+            clock,
+        }
+    }
+
+    /// Adds the words of `text`, which starts a line, stamped with `at`,
+    /// then evicts whatever fell out of the window.
+    pub fn push_text(&mut self, text: &str, at: Option<i64>) {
+        for line in text.lines() {
+            let mut line_start = true;
+            for token in line.split_whitespace() {
+                let ends_sentence = token.ends_with(is_sentence_end);
+                let clean: String = token
+                    .to_lowercase()
+                    .chars()
+                    .filter(|c| c.is_alphabetic())
+                    .collect();
+                if clean.is_empty() {
+                    self.non_alphabetic += 1;
+                    // A lone `.` or `!` still ends the sentence before it.
+                    if let Some(last) = self.tokens.back_mut() {
+                        last.sentence_end |= ends_sentence;
+                    }
+                    continue;
+                }
+                self.chars += clean.chars().count();
+                *self.counts.entry(clean.clone()).or_insert(0) += 1;
+                self.tokens.push_back(Token {
+                    word: clean,
+                    at,
+                    line_start,
+                    sentence_end: ends_sentence,
+                });
+                line_start = false;
+            }
+        }
+        self.evict(at);
+    }
+
+    /// Adds log-style lines. A line starting with a timestamp moves the clock
+    /// to it; lines without one belong to the previous timestamp. Input is
+    /// expected in time order: a token is only evicted once everything before
+    /// it has been.
+    pub fn push_lines(&mut self, text: &str) {
+        for line in text.lines() {
+            let rest = match timestamp::split(line) {
+                Some((at, rest)) => {
+                    self.current = Some(at);
+                    rest
+                }
+                None => line,
+            };
+            self.push_text(rest, self.current);
+        }
+    }
+
+    fn evict(&mut self, now: Option<i64>) {
+        let keep = |front: &Token, len: usize| match self.size {
+            WindowSize::Tokens(n) => len <= n,
+            // Untimestamped tokens are older than any timestamp.
+            WindowSize::Seconds(secs) => match (front.at, now) {
+                (Some(at), Some(now)) => at > now - secs,
+                (None, Some(_)) => false,
+                (_, None) => true,
+            },
+        };
+        while let Some(front) = self.tokens.front() {
+            if keep(front, self.tokens.len()) {
+                break;
+            }
+            let Token { word, .. } = self.tokens.pop_front().expect("front exists");
+            self.chars -= word.chars().count();
+            match self.counts.get_mut(&word) {
+                Some(1) => {
+                    self.counts.remove(&word);
+                }
+                Some(count) => *count -= 1,
+                None => unreachable!("every windowed token is counted"),
+            }
+        }
+    }
+
+    /// Tokens currently in the window.
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Time since creation.
+    pub fn elapsed_ns(&self) -> u128 {
+        self.clock.now().saturating_sub(self.start).as_nanos()
+    }
+
+    /// Stats over the tokens in the window. `non_alphabetic` counts every
+    /// token skipped since the start.
+    /// Lines and sentences are those the window's words are on, the first
+    /// and last counted whole even if partly evicted or unfinished.
+    ///
+    /// ```
+    /// use td5::window::{SlidingWindow, WindowSize};
+    ///
+    /// let mut window = SlidingWindow::new(WindowSize::Tokens(4));
+    /// window.push_lines("One two. Three\nfour five. Six\n");
+    /// let stats = window.stats();
+    /// // "three four five." and "six", on two lines.
+    /// assert_eq!((stats.line_count, stats.sentence_count), (Some(2), Some(2)));
+    /// ```
+    pub fn stats(&self) -> TextStats {
+        let mut freq: Vec<(&str, usize)> =
+            self.counts.iter().map(|(w, &c)| (w.as_str(), c)).collect();
+        let top_words = select_top(&mut freq, 10, by_count)
+            .iter()
+            .map(|&(w, c)| (w.to_string(), c))
+            .collect();
+        let mut longest: Vec<&String> = self.counts.keys().collect();
+        let longest = select_top(&mut longest, 5, |a, b| {
+            b.len().cmp(&a.len()).then_with(|| a.cmp(b))
+        });
+        let mut shape = Shape::default();
+        for token in &self.tokens {
+            shape.word(token.word.chars().count(), 0);
+        }
+        let (lines, sentences) = match (self.tokens.front(), self.tokens.back()) {
+            (Some(first), Some(last)) => (
+                self.tokens.iter().filter(|t| t.line_start).count()
+                    + usize::from(!first.line_start),
+                self.tokens.iter().filter(|t| t.sentence_end).count()
+                    + usize::from(!last.sentence_end),
+            ),
+            _ => (0, 0),
+        };
+        TextStats {
+            word_count: self.counts.len(),
+            total_words: self.tokens.len(),
+            char_count: self.chars,
+            line_count: Some(lines),
+            sentence_count: Some(sentences),
+            mean_word_length: shape.mean_length(),
+            median_word_length: shape.median_length(),
+            word_lengths: shape.histogram(),
+            top_words,
+            surface_forms: Vec::new(),
+            detected_language: None,
+            longest_words: longest.iter().map(|w| w.to_string()).collect(),
+            top_bigrams: Vec::new(),
+            top_trigrams: Vec::new(),
+            diagnostics: Diagnostics {
+                non_alphabetic: self.non_alphabetic,
+                ..Diagnostics::default()
+            },
+        }
+    }
+}
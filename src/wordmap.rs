@@ -0,0 +1,123 @@
+//! The fast path's table of non-hot words.
+//!
+//! A `HashMap<String, usize>` needs an owned key for every lookup through
+//! `entry`, so counting allocated a `String` per word read, and one per
+//! distinct word was kept. `WordMap` stores the words' bytes end to end in a
+//! single pool and its open-addressing index refers to them by offset, so a
+//! word already seen costs a hash and a compare, a new one an append to the
+//! pool. Counting allocates only when the pool or the index grows: a few
+//! dozen times for a million distinct words. `String`s are made only for the
+//! words that make it into the stats.
+
+use rustc_hash::FxHasher;
+use std::hash::Hasher;
+
+const EMPTY: u32 = u32::MAX;
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    hash: u64,
+    start: usize,
+    len: usize,
+    count: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct WordMap {
+    pool: String,
+    entries: Vec<Entry>,
+    /// Indexes into `entries`, `EMPTY` for a free slot; a power of two long,
+    /// at most half full.
+    slots: Vec<u32>,
+}
+
+pub(crate) fn hash(word: &str) -> u64 {
+    let mut hasher = FxHasher::default();
+    hasher.write(word.as_bytes());
+    hasher.finish()
+}
+
+impl WordMap {
+    /// Room for `words` distinct words before the index grows.
+    pub(crate) fn with_capacity(words: usize) -> Self {
+        let mut map = Self::default();
+        if words > 0 {
+            map.entries.reserve(words);
+            map.slots = vec![EMPTY; (words * 2).next_power_of_two()];
+        }
+        map
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Adds `count` to `word`.
+    #[inline]
+    pub(crate) fn add(&mut self, word: &str, count: usize) {
+        self.add_hashed(word, hash(word), count);
+    }
+
+    /// `add` with `word`'s `hash` already known.
+    pub(crate) fn add_hashed(&mut self, word: &str, hash: u64, count: usize) {
+        if (self.entries.len() + 1) * 2 > self.slots.len() {
+            self.grow();
+        }
+        // The high bits of an Fx hash are the well-mixed ones.
+        let shift = 64 - self.slots.len().trailing_zeros();
+        let mask = self.slots.len() - 1;
+        let mut slot = (hash >> shift) as usize;
+        loop {
+            let index = self.slots[slot];
+            if index == EMPTY {
+                break;
+            }
+            let entry = &mut self.entries[index as usize];
+            if entry.hash == hash && &self.pool[entry.start..entry.start + entry.len] == word {
+                entry.count += count;
+                return;
+            }
+            slot = (slot + 1) & mask;
+        }
+        assert!(
+            self.entries.len() < EMPTY as usize,
+            "too many distinct words"
+        );
+        self.slots[slot] = self.entries.len() as u32;
+        self.entries.push(Entry {
+            hash,
+            start: self.pool.len(),
+            len: word.len(),
+            count,
+        });
+        self.pool.push_str(word);
+    }
+
+    fn grow(&mut self) {
+        let len = (self.slots.len() * 2).max(16);
+        let shift = 64 - len.trailing_zeros();
+        self.slots = vec![EMPTY; len];
+        for (index, entry) in self.entries.iter().enumerate() {
+            let mut slot = (entry.hash >> shift) as usize;
+            while self.slots[slot] != EMPTY {
+                slot = (slot + 1) & (len - 1);
+            }
+            self.slots[slot] = index as u32;
+        }
+    }
+
+    /// Every word with its count, in first-seen order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.entries
+            .iter()
+            .map(|e| (&self.pool[e.start..e.start + e.len], e.count))
+    }
+
+    /// Folds `other` in, reusing its hashes.
+    pub(crate) fn merge(&mut self, other: &WordMap) {
+        for entry in &other.entries {
+            let word = &other.pool[entry.start..entry.start + entry.len];
+            self.add_hashed(word, entry.hash, entry.count);
+        }
+    }
+}
@@ -0,0 +1,155 @@
+//! Cache-behavior sweep (`td5 bench --cache-sweep`): words drawn uniformly
+//! from ever larger vocabularies, timed per word.
+//!
+//! Counting is a hash-table lookup per word. While the table fits in L1 or
+//! L2 a lookup is cheap; once the working set outgrows a cache level the
+//! time per word steps up. Each step reports an estimate of
+//! the table's size next to the cache it fits in, read from
+//! `/sys/devices/system/cpu/cpu0/cache` where the system exposes it.
+//!
+//! Larger vocabularies need longer words to stay distinct, so part of the
+//! rise is the tokenizer's; the cliffs are the table's.
+
+use crate::bench::{BenchInput, BenchSuite};
+use crate::error::Result;
+use crate::json::Json;
+use crate::shapes;
+use std::fs;
+
+/// Vocabulary sizes swept when none are given: 1K to 1M entries.
+pub const DEFAULT_VOCABULARIES: [usize; 6] =
+    [1 << 10, 4 << 10, 16 << 10, 64 << 10, 256 << 10, 1 << 20];
+
+/// Fewest words generated per vocabulary entry, so the table is looked up
+/// more than it is filled even when the vocabulary outgrows `--words`.
+pub const MIN_WORDS_PER_ENTRY: usize = 4;
+
+/// A data or unified cache of cpu0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheLevel {
+    /// `L1d`, `L2`, `L3`...
+    pub name: String,
+    pub bytes: u64,
+}
+
+/// The data and unified caches of cpu0, smallest first; empty where sysfs
+/// does not describe them.
+pub fn cache_levels() -> Vec<CacheLevel> {
+    let mut levels = Vec::new();
+    for index in 0.. {
+        let dir = format!("/sys/devices/system/cpu/cpu0/cache/index{index}");
+        let read = |file: &str| fs::read_to_string(format!("{dir}/{file}")).ok();
+        let (Some(level), Some(kind), Some(size)) = (read("level"), read("type"), read("size"))
+        else {
+            break;
+        };
+        let name = match kind.trim() {
+            "Data" => format!("L{}d", level.trim()),
+            "Unified" => format!("L{}", level.trim()),
+            _ => continue,
+        };
+        if let Some(bytes) = crate::slice::parse_size(&size) {
+            levels.push(CacheLevel { name, bytes });
+        }
+    }
+    levels.sort_by_key(|l| l.bytes);
+    levels
+}
+
+/// The smallest cache holding `bytes`, `None` past the last level.
+pub fn fits_in(levels: &[CacheLevel], bytes: u64) -> Option<&CacheLevel> {
+    levels.iter().find(|l| l.bytes >= bytes)
+}
+
+/// Estimated bytes of a `String -> usize` table holding `vocabulary`: the
+/// buckets at hashbrown's 7/8 load factor, a control byte each, and one heap
+/// block per key (allocator chunks of 16 bytes, at least 32).
+pub fn table_bytes(vocabulary: &[String]) -> u64 {
+    let buckets = (vocabulary.len() * 8 / 7).next_power_of_two().max(4);
+    let slot = std::mem::size_of::<(String, usize)>() + 1;
+    let keys: usize = vocabulary
+        .iter()
+        .map(|w| (w.len() + 8).next_multiple_of(16).max(32))
+        .sum();
+    (buckets * slot + keys) as u64
+}
+
+/// One vocabulary size, timed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub vocabulary: usize,
+    pub words: usize,
+    pub table_bytes: u64,
+    /// Median nanoseconds per word, per analyzer.
+    pub ns_per_word: Vec<(String, f64)>,
+}
+
+/// Times `analyzers` on a uniform text of at least `words` words for each
+/// vocabulary size.
+pub fn sweep(
+    analyzers: &[&str],
+    vocabularies: &[usize],
+    words: usize,
+    iterations: usize,
+    seed: u64,
+) -> Result<Vec<Step>> {
+    let suite = BenchSuite::with_builtins_named(analyzers)?.iterations(iterations);
+    let mut steps = Vec::with_capacity(vocabularies.len());
+    for &size in vocabularies {
+        let vocabulary = shapes::vocabulary(size.max(1));
+        let words = words.max(vocabulary.len() * MIN_WORDS_PER_ENTRY);
+        let input = BenchInput::new(size.to_string(), shapes::uniform(&vocabulary, words, seed));
+        let report = suite.run(std::slice::from_ref(&input))?;
+        steps.push(Step {
+            vocabulary: vocabulary.len(),
+            words,
+            table_bytes: table_bytes(&vocabulary),
+            ns_per_word: report
+                .results
+                .iter()
+                .map(|r| {
+                    (
+                        r.analyzer.clone(),
+                        r.summary.median_ns as f64 / words as f64,
+                    )
+                })
+                .collect(),
+        });
+    }
+    Ok(steps)
+}
+
+pub fn to_json_value(levels: &[CacheLevel], steps: &[Step]) -> Json {
+    let caches = levels
+        .iter()
+        .map(|l| {
+            Json::Object(vec![
+                ("name".into(), l.name.as_str().into()),
+                ("bytes".into(), l.bytes.into()),
+            ])
+        })
+        .collect();
+    let steps = steps
+        .iter()
+        .map(|s| {
+            let fits =
+                fits_in(levels, s.table_bytes).map_or(Json::Null, |l| l.name.as_str().into());
+            let times = s
+                .ns_per_word
+                .iter()
+                .map(|(name, ns)| (name.clone(), (*ns).into()))
+                .collect();
+            Json::Object(vec![
+                ("vocabulary".into(), s.vocabulary.into()),
+                ("words".into(), s.words.into()),
+                ("table_bytes".into(), s.table_bytes.into()),
+                ("fits_in".into(), fits),
+                ("ns_per_word".into(), Json::Object(times)),
+            ])
+        })
+        .collect();
+    Json::Object(vec![
+        ("caches".into(), Json::Array(caches)),
+        ("steps".into(), Json::Array(steps)),
+    ])
+}
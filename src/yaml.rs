@@ -0,0 +1,109 @@
+//! YAML output of the JSON documents (`--format yaml`), for dashboards and
+//! config-style tooling that read YAML rather than JSON.
+//!
+//! Each document starts with `---`, so several form a YAML stream. Objects
+//! are written in block style, arrays of plain values (a `[word, count]`
+//! pair) in flow style. Strings are double-quoted with JSON's escapes, which
+//! YAML shares, so no word can be read back as a number, a boolean or null.
+
+use crate::TextStats;
+use crate::json::Json;
+use std::fmt::Write;
+
+pub fn encode(value: &Json) -> String {
+    let mut out = String::from("---");
+    match value {
+        Json::Object(fields) if !fields.is_empty() => {
+            out.push('\n');
+            object(&mut out, fields, 0);
+        }
+        Json::Array(items) if !is_flow(items) => {
+            out.push('\n');
+            array(&mut out, items, 0);
+        }
+        _ => {
+            out.push(' ');
+            scalar(&mut out, value);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Arrays written on one line: empty, or only scalars.
+fn is_flow(items: &[Json]) -> bool {
+    items
+        .iter()
+        .all(|item| !matches!(item, Json::Array(_) | Json::Object(_)))
+}
+
+fn object(out: &mut String, fields: &[(String, Json)], indent: usize) {
+    for (key, value) in fields {
+        let _ = write!(out, "{:indent$}", "");
+        if key.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_') && !key.is_empty() {
+            out.push_str(key);
+        } else {
+            scalar(out, &Json::String(key.clone()));
+        }
+        out.push(':');
+        nested(out, value, indent);
+    }
+}
+
+fn array(out: &mut String, items: &[Json], indent: usize) {
+    for item in items {
+        let _ = write!(out, "{:indent$}-", "");
+        nested(out, item, indent);
+    }
+}
+
+/// `value` after a key or a dash: inline when it fits on the line, else
+/// indented on the lines below.
+fn nested(out: &mut String, value: &Json, indent: usize) {
+    match value {
+        Json::Object(fields) if !fields.is_empty() => {
+            out.push('\n');
+            object(out, fields, indent + 2);
+        }
+        Json::Array(items) if !is_flow(items) => {
+            out.push('\n');
+            array(out, items, indent + 2);
+        }
+        _ => {
+            out.push(' ');
+            scalar(out, value);
+            out.push('\n');
+        }
+    }
+}
+
+fn scalar(out: &mut String, value: &Json) {
+    match value {
+        Json::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                scalar(out, item);
+            }
+            out.push(']');
+        }
+        Json::Object(_) => out.push_str("{}"),
+        // JSON's number, boolean, null and string forms are all valid YAML.
+        _ => {
+            let _ = write!(out, "{value}");
+        }
+    }
+}
+
+impl TextStats {
+    /// ```
+    /// let yaml = td5::analyze("the cat and the hat").to_yaml();
+    /// assert!(yaml.starts_with("---\nschema_version: 4\n"));
+    /// assert!(yaml.contains("top_words:\n  - [\"the\", 2]\n"));
+    /// ```
+    pub fn to_yaml(&self) -> String {
+        encode(&self.to_json_value())
+    }
+}
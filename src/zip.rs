@@ -0,0 +1,104 @@
+//! Just enough of the ZIP format to read EPUB archives: the central
+//! directory, and stored or deflated entries. No ZIP64, encryption or
+//! multi-disk archives.
+
+use crate::error::{Result, Td5Error};
+use crate::inflate::inflate;
+
+pub(crate) struct Archive<'a> {
+    data: &'a [u8],
+    entries: Vec<Entry>,
+}
+
+struct Entry {
+    name: String,
+    method: u16,
+    compressed: usize,
+    header_offset: usize,
+}
+
+fn u16_at(data: &[u8], at: usize) -> Result<u16> {
+    data.get(at..at + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| corrupt("truncated archive"))
+}
+
+fn u32_at(data: &[u8], at: usize) -> Result<usize> {
+    data.get(at..at + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+        .ok_or_else(|| corrupt("truncated archive"))
+}
+
+fn corrupt(message: &str) -> Td5Error {
+    Td5Error::config(format!("ZIP archive: {message}"))
+}
+
+impl<'a> Archive<'a> {
+    pub(crate) fn parse(data: &'a [u8]) -> Result<Archive<'a>> {
+        // The end-of-central-directory record is followed by a comment of
+        // at most 64 KiB.
+        let lowest = data.len().saturating_sub(22 + 0xFFFF);
+        let end = (lowest..data.len().saturating_sub(21))
+            .rev()
+            .find(|&i| data[i..].starts_with(b"PK\x05\x06"))
+            .ok_or_else(|| corrupt("no central directory"))?;
+        let count = usize::from(u16_at(data, end + 10)?);
+        let mut at = u32_at(data, end + 16)?;
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            if !data.get(at..).is_some_and(|d| d.starts_with(b"PK\x01\x02")) {
+                return Err(corrupt("bad central directory entry"));
+            }
+            let name_len = usize::from(u16_at(data, at + 28)?);
+            let extra_len = usize::from(u16_at(data, at + 30)?);
+            let comment_len = usize::from(u16_at(data, at + 32)?);
+            let name = data
+                .get(at + 46..at + 46 + name_len)
+                .ok_or_else(|| corrupt("truncated archive"))?;
+            entries.push(Entry {
+                name: String::from_utf8_lossy(name).into_owned(),
+                method: u16_at(data, at + 10)?,
+                compressed: u32_at(data, at + 20)?,
+                header_offset: u32_at(data, at + 42)?,
+            });
+            at += 46 + name_len + extra_len + comment_len;
+        }
+        Ok(Archive { data, entries })
+    }
+
+    pub(crate) fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|e| e.name.as_str())
+    }
+
+    /// The uncompressed content of `name`.
+    pub(crate) fn read(&self, name: &str) -> Result<Vec<u8>> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|e| e.name == name)
+            .ok_or_else(|| corrupt(&format!("no entry `{name}`")))?;
+        let at = entry.header_offset;
+        if !self
+            .data
+            .get(at..)
+            .is_some_and(|d| d.starts_with(b"PK\x03\x04"))
+        {
+            return Err(corrupt(&format!("bad local header for `{name}`")));
+        }
+        let start = at
+            + 30
+            + usize::from(u16_at(self.data, at + 26)?)
+            + usize::from(u16_at(self.data, at + 28)?);
+        let body = self
+            .data
+            .get(start..start + entry.compressed)
+            .ok_or_else(|| corrupt("truncated archive"))?;
+        match entry.method {
+            0 => Ok(body.to_vec()),
+            8 => inflate(body).map_err(|e| corrupt(&format!("`{name}`: {e}"))),
+            method => Err(corrupt(&format!(
+                "`{name}` uses unsupported compression method {method}"
+            ))),
+        }
+    }
+}
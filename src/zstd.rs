@@ -0,0 +1,876 @@
+//! Zstandard decompression (RFC 8878), without a compression crate: frames
+//! of raw, RLE and compressed blocks, Huffman-coded literals and FSE-coded
+//! sequences. Like `inflate`, correct rather than fast. Dictionaries are not
+//! supported; content checksums are checked at the end of each frame.
+//!
+//! `ZstdReader` decodes a block at a time as it is read, keeping the frame's
+//! window (up to `MAX_WINDOW`) for the matches that refer back into it.
+
+use std::io::{self, Read};
+
+/// Largest window accepted, as the reference decoder's default limit: a
+/// frame that needs more is refused instead of allocated.
+pub(crate) const MAX_WINDOW: usize = 1 << 27;
+
+/// Largest block, decoded or not.
+const MAX_BLOCK: usize = 128 << 10;
+
+const MAGIC: u32 = 0xFD2F_B528;
+
+fn corrupt(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+type Result<T> = io::Result<T>;
+
+/// Literals lengths: baseline and extra bits of each code.
+const LL_BASE: [u32; 36] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 18, 20, 22, 24, 28, 32, 40, 48, 64,
+    128, 256, 512, 1024, 2048, 4096, 8192, 16384, 32768, 65536,
+];
+const LL_BITS: [u8; 36] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 3, 3, 4, 6, 7, 8, 9, 10, 11,
+    12, 13, 14, 15, 16,
+];
+/// Match lengths: baseline and extra bits of each code.
+const ML_BASE: [u32; 53] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27,
+    28, 29, 30, 31, 32, 33, 34, 35, 37, 39, 41, 43, 47, 51, 59, 67, 83, 99, 131, 259, 515, 1027,
+    2051, 4099, 8195, 16387, 32771, 65539,
+];
+const ML_BITS: [u8; 53] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    1, 1, 1, 1, 2, 2, 3, 3, 4, 4, 5, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16,
+];
+
+/// Default distributions of the `Predefined` mode, -1 for "less than 1".
+const LL_DEFAULT: [i16; 36] = [
+    4, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 3, 2, 1, 1, 1, 1, 1,
+    -1, -1, -1, -1,
+];
+const ML_DEFAULT: [i16; 53] = [
+    1, 4, 3, 2, 2, 2, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, -1, -1, -1, -1, -1, -1, -1,
+];
+const OF_DEFAULT: [i16; 29] = [
+    1, 1, 1, 1, 1, 1, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, -1, -1, -1, -1, -1,
+];
+
+/// Bits read forwards, lowest first: FSE table descriptions.
+struct Forward<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Forward<'_> {
+    fn peek(&self, n: u32) -> u32 {
+        let word = load_le(self.data, self.pos / 8);
+        ((word >> (self.pos % 8)) & mask(n)) as u32
+    }
+
+    fn skip(&mut self, n: u32) {
+        self.pos += n as usize;
+    }
+
+    /// Whole bytes touched so far.
+    fn bytes(&self) -> usize {
+        self.pos.div_ceil(8)
+    }
+}
+
+/// Bits read backwards from the end, first-read bit highest: the Huffman
+/// and sequence streams. Reading past the start yields zeros, so the
+/// decoders can check for overflow after the fact.
+struct Backward<'a> {
+    data: &'a [u8],
+    /// Bits left.
+    pos: isize,
+}
+
+impl<'a> Backward<'a> {
+    fn new(data: &'a [u8]) -> Result<Self> {
+        let last = *data.last().ok_or_else(|| corrupt("empty bitstream"))?;
+        if last == 0 {
+            return Err(corrupt("bitstream without an end mark"));
+        }
+        let padding = last.leading_zeros() as isize + 1;
+        Ok(Backward {
+            data,
+            pos: data.len() as isize * 8 - padding,
+        })
+    }
+
+    fn peek(&self, n: u32) -> u64 {
+        if n == 0 {
+            return 0;
+        }
+        let start = self.pos - n as isize;
+        if self.pos <= 0 {
+            0
+        } else if start >= 0 {
+            let word = load_le(self.data, start as usize / 8);
+            (word >> (start % 8)) & mask(n)
+        } else {
+            let available = self.pos.max(0) as u32;
+            ((load_le(self.data, 0) & mask(available)) << -start) & mask(n)
+        }
+    }
+
+    fn read(&mut self, n: u32) -> u64 {
+        let value = self.peek(n);
+        self.pos -= n as isize;
+        value
+    }
+
+    fn overflowed(&self) -> bool {
+        self.pos < 0
+    }
+}
+
+/// Up to eight bytes of `data` from `at`, little-endian, zeros past the end.
+fn load_le(data: &[u8], at: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    if at < data.len() {
+        let n = (data.len() - at).min(8);
+        bytes[..n].copy_from_slice(&data[at..at + n]);
+    }
+    u64::from_le_bytes(bytes)
+}
+
+fn mask(n: u32) -> u64 {
+    if n >= 64 { !0 } else { (1 << n) - 1 }
+}
+
+fn highbit(n: u32) -> u32 {
+    31 - n.leading_zeros()
+}
+
+#[derive(Clone, Copy, Default)]
+struct FseEntry {
+    symbol: u8,
+    bits: u8,
+    base: u16,
+}
+
+/// An FSE decoding table.
+#[derive(Clone)]
+struct Fse {
+    log: u32,
+    entries: Vec<FseEntry>,
+}
+
+impl Fse {
+    /// The table of a normalized distribution.
+    fn build(norm: &[i16], log: u32) -> Result<Fse> {
+        let size = 1usize << log;
+        let mut entries = vec![FseEntry::default(); size];
+        let mut next = vec![0u32; norm.len()];
+        let mut high = size - 1;
+        for (symbol, &count) in norm.iter().enumerate() {
+            if count == -1 {
+                entries[high].symbol = symbol as u8;
+                high = high.wrapping_sub(1);
+                next[symbol] = 1;
+            } else {
+                next[symbol] = count.max(0) as u32;
+            }
+        }
+        let step = (size >> 1) + (size >> 3) + 3;
+        let mut position = 0;
+        for (symbol, &count) in norm.iter().enumerate() {
+            for _ in 0..count.max(0) {
+                entries[position].symbol = symbol as u8;
+                position = (position + step) & (size - 1);
+                while position > high {
+                    position = (position + step) & (size - 1);
+                }
+            }
+        }
+        if position != 0 {
+            return Err(corrupt("invalid FSE distribution"));
+        }
+        for entry in &mut entries {
+            let state = next[usize::from(entry.symbol)];
+            next[usize::from(entry.symbol)] += 1;
+            let bits = log - highbit(state);
+            entry.bits = bits as u8;
+            entry.base = ((state << bits) as usize - size) as u16;
+        }
+        Ok(Fse { log, entries })
+    }
+
+    /// A table that always yields `symbol`, for the `RLE` mode.
+    fn rle(symbol: u8) -> Fse {
+        Fse {
+            log: 0,
+            entries: vec![FseEntry {
+                symbol,
+                ..FseEntry::default()
+            }],
+        }
+    }
+
+    /// Reads a table description at the start of `data`, symbols up to
+    /// `max_symbol` and accuracy up to `max_log`; the table and the bytes
+    /// read.
+    fn read(data: &[u8], max_symbol: usize, max_log: u32) -> Result<(Fse, usize)> {
+        let mut bits = Forward { data, pos: 0 };
+        let log = bits.peek(4) + 5;
+        bits.skip(4);
+        if log > max_log {
+            return Err(corrupt("FSE accuracy too high"));
+        }
+        let mut remaining = (1i32 << log) + 1;
+        let mut threshold = 1i32 << log;
+        let mut width = log + 1;
+        let mut norm = Vec::with_capacity(max_symbol + 1);
+        let mut previous_zero = false;
+        while remaining > 1 && norm.len() <= max_symbol {
+            if previous_zero {
+                loop {
+                    let repeat = bits.peek(2);
+                    bits.skip(2);
+                    norm.extend(std::iter::repeat_n(0, repeat as usize));
+                    if repeat != 3 {
+                        break;
+                    }
+                }
+                if norm.len() > max_symbol {
+                    break;
+                }
+            }
+            let max = 2 * threshold - 1 - remaining;
+            let low = bits.peek(width - 1) as i32;
+            let mut count = if low < max {
+                bits.skip(width - 1);
+                low
+            } else {
+                let mut count = bits.peek(width) as i32;
+                if count >= threshold {
+                    count -= max;
+                }
+                bits.skip(width);
+                count
+            };
+            count -= 1;
+            remaining -= count.abs();
+            norm.push(count as i16);
+            previous_zero = count == 0;
+            while remaining < threshold {
+                width -= 1;
+                threshold >>= 1;
+            }
+        }
+        if remaining != 1 || norm.len() > max_symbol + 1 || bits.bytes() > data.len() {
+            return Err(corrupt("invalid FSE table description"));
+        }
+        Ok((Fse::build(&norm, log)?, bits.bytes()))
+    }
+
+    fn init(&self, bits: &mut Backward) -> usize {
+        bits.read(self.log) as usize
+    }
+
+    fn symbol(&self, state: usize) -> u8 {
+        self.entries[state].symbol
+    }
+
+    fn update(&self, state: usize, bits: &mut Backward) -> usize {
+        let entry = self.entries[state];
+        usize::from(entry.base) + bits.read(u32::from(entry.bits)) as usize
+    }
+}
+
+/// A Huffman decoding table for literals.
+#[derive(Clone)]
+struct Huffman {
+    max_bits: u32,
+    /// Symbol and code length of each `max_bits` prefix.
+    entries: Vec<(u8, u8)>,
+}
+
+impl Huffman {
+    /// Reads a tree description at the start of `data`; the table and the
+    /// bytes read.
+    fn read(data: &[u8]) -> Result<(Huffman, usize)> {
+        let header = usize::from(*data.first().ok_or_else(|| corrupt("truncated literals"))?);
+        let (mut weights, read) = if header < 128 {
+            let compressed = data
+                .get(1..1 + header)
+                .ok_or_else(|| corrupt("truncated Huffman weights"))?;
+            (fse_weights(compressed)?, 1 + header)
+        } else {
+            let count = header - 127;
+            let packed = data
+                .get(1..1 + count.div_ceil(2))
+                .ok_or_else(|| corrupt("truncated Huffman weights"))?;
+            let weights = (0..count)
+                .map(|i| {
+                    let byte = packed[i / 2];
+                    if i % 2 == 0 { byte >> 4 } else { byte & 0xF }
+                })
+                .collect();
+            (weights, 1 + count.div_ceil(2))
+        };
+        if weights.len() > 255 || weights.iter().any(|&w| w > 11) {
+            return Err(corrupt("invalid Huffman weights"));
+        }
+        let total: u32 = weights
+            .iter()
+            .filter(|&&w| w > 0)
+            .map(|&w| 1 << (w - 1))
+            .sum();
+        if total == 0 {
+            return Err(corrupt("invalid Huffman weights"));
+        }
+        let max_bits = highbit(total) + 1;
+        let rest = (1 << max_bits) - total;
+        if !rest.is_power_of_two() || max_bits > 11 {
+            return Err(corrupt("invalid Huffman weights"));
+        }
+        // The last symbol's weight is implied: the one that fills the tree.
+        weights.push(highbit(rest) as u8 + 1);
+        let mut entries = Vec::with_capacity(1 << max_bits);
+        for weight in 1..=max_bits as u8 {
+            for (symbol, _) in weights.iter().enumerate().filter(|&(_, &w)| w == weight) {
+                let length = (max_bits + 1 - u32::from(weight)) as u8;
+                entries.extend(std::iter::repeat_n(
+                    (symbol as u8, length),
+                    1 << (weight - 1),
+                ));
+            }
+        }
+        Ok((Huffman { max_bits, entries }, read))
+    }
+
+    /// Decodes one stream into `out`, filling it exactly.
+    fn decode(&self, stream: &[u8], out: &mut [u8]) -> Result<()> {
+        let mut bits = Backward::new(stream)?;
+        for byte in out {
+            let (symbol, length) = self.entries[bits.peek(self.max_bits) as usize];
+            bits.read(u32::from(length));
+            *byte = symbol;
+        }
+        if bits.pos != 0 {
+            return Err(corrupt("Huffman stream not consumed exactly"));
+        }
+        Ok(())
+    }
+}
+
+/// Huffman weights compressed with FSE: two states over one stream.
+fn fse_weights(data: &[u8]) -> Result<Vec<u8>> {
+    let (table, read) = Fse::read(data, 255, 6)?;
+    let mut bits = Backward::new(&data[read..])?;
+    let mut states = [table.init(&mut bits), table.init(&mut bits)];
+    let mut weights = Vec::new();
+    'decode: loop {
+        for i in [0, 1] {
+            weights.push(table.symbol(states[i]));
+            states[i] = table.update(states[i], &mut bits);
+            if bits.overflowed() {
+                weights.push(table.symbol(states[1 - i]));
+                break 'decode;
+            }
+            if weights.len() > 255 {
+                return Err(corrupt("too many Huffman weights"));
+            }
+        }
+    }
+    Ok(weights)
+}
+
+/// What a frame's blocks share.
+struct Frame {
+    window: usize,
+    /// The content so far, when the frame ends with its checksum.
+    checksum: Option<Xxh64>,
+    /// Output so far, which matches may not reach before.
+    produced: usize,
+    huffman: Option<Huffman>,
+    /// Literals lengths, offsets and match lengths, for the `Repeat` mode.
+    tables: [Option<Fse>; 3],
+    offsets: [usize; 3],
+}
+
+/// A Zstandard decoder over any reader, every frame one after the other;
+/// skippable frames are skipped. It decodes a block at a time as it is read.
+pub(crate) struct ZstdReader<R> {
+    inner: R,
+    frame: Option<Frame>,
+    done: bool,
+    /// Recent output: the window, then what `read` has not returned yet.
+    out: Vec<u8>,
+    /// Start of the output `read` has not returned.
+    served: usize,
+    block: Vec<u8>,
+}
+
+impl<R: Read> ZstdReader<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        ZstdReader {
+            inner,
+            frame: None,
+            done: false,
+            out: Vec::new(),
+            served: 0,
+            block: Vec::new(),
+        }
+    }
+
+    /// Decodes the next piece: a frame header or a block.
+    fn step(&mut self) -> Result<()> {
+        match self.frame.take() {
+            None => self.frame_header(),
+            Some(mut frame) => {
+                let last = self.block(&mut frame)?;
+                if !last {
+                    self.frame = Some(frame);
+                } else if let Some(content) = frame.checksum {
+                    let mut checksum = [0u8; 4];
+                    self.inner.read_exact(&mut checksum)?;
+                    if u32::from_le_bytes(checksum) != content.finish() as u32 {
+                        return Err(corrupt("zstd content checksum mismatch"));
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn frame_header(&mut self) -> Result<()> {
+        let mut magic = [0u8; 4];
+        let read = read_full(&mut self.inner, &mut magic)?;
+        if read == 0 {
+            self.done = true;
+            return Ok(());
+        }
+        if read < 4 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let magic = u32::from_le_bytes(magic);
+        if magic & 0xFFFF_FFF0 == 0x184D_2A50 {
+            let mut size = [0u8; 4];
+            self.inner.read_exact(&mut size)?;
+            let size = u64::from(u32::from_le_bytes(size));
+            if io::copy(&mut (&mut self.inner).take(size), &mut io::sink())? < size {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            return Ok(());
+        }
+        if magic != MAGIC {
+            return Err(corrupt("not a zstd frame"));
+        }
+        let mut descriptor = [0u8];
+        self.inner.read_exact(&mut descriptor)?;
+        let descriptor = descriptor[0];
+        let single_segment = descriptor & 0x20 != 0;
+        if descriptor & 0x08 != 0 {
+            return Err(corrupt("reserved frame header bit set"));
+        }
+        let window = if single_segment {
+            None
+        } else {
+            let mut byte = [0u8];
+            self.inner.read_exact(&mut byte)?;
+            let log = 10 + u32::from(byte[0] >> 3);
+            let base = 1u64 << log;
+            Some(base + (base / 8) * u64::from(byte[0] & 7))
+        };
+        let dictionary = [0, 1, 2, 4][usize::from(descriptor & 3)];
+        let mut id = [0u8; 4];
+        self.inner.read_exact(&mut id[..dictionary])?;
+        if u32::from_le_bytes(id) != 0 {
+            return Err(corrupt("zstd dictionaries are not supported"));
+        }
+        let content_bytes = match (descriptor >> 6, single_segment) {
+            (0, false) => 0,
+            (0, true) => 1,
+            (1, _) => 2,
+            (2, _) => 4,
+            _ => 8,
+        };
+        let mut content = [0u8; 8];
+        self.inner.read_exact(&mut content[..content_bytes])?;
+        let content = u64::from_le_bytes(content) + if content_bytes == 2 { 256 } else { 0 };
+        let window = window.unwrap_or(content);
+        if window > MAX_WINDOW as u64 {
+            return Err(corrupt("zstd window too large"));
+        }
+        self.frame = Some(Frame {
+            window: window as usize,
+            checksum: (descriptor & 0x04 != 0).then(Xxh64::new),
+            produced: 0,
+            huffman: None,
+            tables: [None, None, None],
+            offsets: [1, 4, 8],
+        });
+        Ok(())
+    }
+
+    /// Decodes one block into `out`; whether it was the last of its frame.
+    fn block(&mut self, frame: &mut Frame) -> Result<bool> {
+        let mut header = [0u8; 4];
+        self.inner.read_exact(&mut header[..3])?;
+        let header = u32::from_le_bytes(header);
+        let size = (header >> 3) as usize;
+        if size > MAX_BLOCK {
+            return Err(corrupt("zstd block too large"));
+        }
+        let start = self.out.len();
+        match (header >> 1) & 3 {
+            0 => {
+                self.out.resize(start + size, 0);
+                self.inner.read_exact(&mut self.out[start..])?;
+            }
+            1 => {
+                let mut byte = [0u8];
+                self.inner.read_exact(&mut byte)?;
+                self.out.resize(start + size, byte[0]);
+            }
+            2 => {
+                let mut block = std::mem::take(&mut self.block);
+                block.resize(size, 0);
+                self.inner.read_exact(&mut block)?;
+                let decoded = compressed_block(&block, frame, &mut self.out);
+                self.block = block;
+                decoded?;
+            }
+            _ => return Err(corrupt("reserved block type")),
+        }
+        frame.produced += self.out.len() - start;
+        if let Some(content) = &mut frame.checksum {
+            content.update(&self.out[start..]);
+        }
+        Ok(header & 1 == 1)
+    }
+}
+
+impl<R: Read> Read for ZstdReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.served == self.out.len() && !self.done {
+            // Everything returned: keep only what the window can reach.
+            let keep = self.frame.as_ref().map_or(0, |frame| frame.window);
+            if self.out.len() > 2 * keep.max(MAX_BLOCK) {
+                self.out.drain(..self.out.len() - keep);
+                self.served = keep;
+            }
+            self.step()?;
+        }
+        let n = buf.len().min(self.out.len() - self.served);
+        buf[..n].copy_from_slice(&self.out[self.served..self.served + n]);
+        self.served += n;
+        Ok(n)
+    }
+}
+
+/// Fills `buf` unless the input ends first; the bytes read.
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+fn compressed_block(block: &[u8], frame: &mut Frame, out: &mut Vec<u8>) -> Result<()> {
+    let (literals, read) = literals(block, frame)?;
+    sequences(&block[read..], &literals, frame, out)
+}
+
+/// The literals section at the start of `block`; the literals and the bytes
+/// read.
+fn literals(block: &[u8], frame: &mut Frame) -> Result<(Vec<u8>, usize)> {
+    let truncated = || corrupt("truncated literals");
+    let first = *block.first().ok_or_else(truncated)?;
+    let byte = |i: usize| block.get(i).map(|&b| usize::from(b)).ok_or_else(truncated);
+    let kind = first & 3;
+    let format = (first >> 2) & 3;
+    if kind < 2 {
+        let (size, header) = match format {
+            0 | 2 => (usize::from(first >> 3), 1),
+            1 => (usize::from(first >> 4) | byte(1)? << 4, 2),
+            _ => (usize::from(first >> 4) | byte(1)? << 4 | byte(2)? << 12, 3),
+        };
+        if kind == 0 {
+            let raw = block.get(header..header + size).ok_or_else(truncated)?;
+            return Ok((raw.to_vec(), header + size));
+        }
+        return Ok((vec![byte(header)? as u8; size], header + 1));
+    }
+    let (header, width, streams) = match format {
+        0 => (3, 10, 1),
+        1 => (3, 10, 4),
+        2 => (4, 14, 4),
+        _ => (5, 18, 4),
+    };
+    let sizes = load_le(block.get(..header).ok_or_else(truncated)?, 0);
+    let size = ((sizes >> 4) & mask(width)) as usize;
+    let compressed = ((sizes >> (4 + width)) & mask(width)) as usize;
+    if size > MAX_BLOCK {
+        return Err(corrupt("too many literals"));
+    }
+    let mut data = block
+        .get(header..header + compressed)
+        .ok_or_else(truncated)?;
+    if kind == 2 {
+        let (table, read) = Huffman::read(data)?;
+        frame.huffman = Some(table);
+        data = &data[read..];
+    }
+    let table = frame
+        .huffman
+        .as_ref()
+        .ok_or_else(|| corrupt("repeated Huffman table without a previous one"))?;
+    let mut literals = vec![0u8; size];
+    if streams == 1 {
+        table.decode(data, &mut literals)?;
+    } else {
+        let jump = data.get(..6).ok_or_else(truncated)?;
+        let [first, second, third] =
+            [0, 2, 4].map(|i| usize::from(u16::from_le_bytes([jump[i], jump[i + 1]])));
+        let fourth = (data.len() - 6)
+            .checked_sub(first + second + third)
+            .ok_or_else(truncated)?;
+        let per_stream = size.div_ceil(4);
+        let mut rest = &data[6..];
+        let mut out = &mut literals[..];
+        for (i, length) in [first, second, third, fourth].into_iter().enumerate() {
+            let stream = &rest[..length];
+            let n = if i < 3 {
+                per_stream.min(out.len())
+            } else {
+                out.len()
+            };
+            let (this, next) = out.split_at_mut(n);
+            table.decode(stream, this)?;
+            (rest, out) = (&rest[length..], next);
+        }
+    }
+    Ok((literals, header + compressed))
+}
+
+/// Decodes the sequences section `data` and executes its sequences.
+fn sequences(data: &[u8], literals: &[u8], frame: &mut Frame, out: &mut Vec<u8>) -> Result<()> {
+    let truncated = || corrupt("truncated sequences");
+    let byte = |i: usize| data.get(i).map(|&b| usize::from(b)).ok_or_else(truncated);
+    let (count, mut read) = match byte(0)? {
+        0 => {
+            out.extend_from_slice(literals);
+            return Ok(());
+        }
+        n @ 1..128 => (n, 1),
+        n @ 128..255 => (((n - 128) << 8) + byte(1)?, 2),
+        _ => (byte(1)? + (byte(2)? << 8) + 0x7F00, 3),
+    };
+    let modes = byte(read)?;
+    read += 1;
+    if modes & 3 != 0 {
+        return Err(corrupt("reserved sequence mode bits set"));
+    }
+    let specs: [(usize, &[i16], u32, usize); 3] = [
+        (6, &LL_DEFAULT, 6, 35),
+        (4, &OF_DEFAULT, 5, 31),
+        (2, &ML_DEFAULT, 6, 52),
+    ];
+    for (i, (shift, default, default_log, max_symbol)) in specs.into_iter().enumerate() {
+        let table = match (modes >> shift) & 3 {
+            0 => Fse::build(default, default_log)?,
+            1 => {
+                let symbol = byte(read)?;
+                read += 1;
+                if symbol > max_symbol {
+                    return Err(corrupt("invalid RLE sequence symbol"));
+                }
+                Fse::rle(symbol as u8)
+            }
+            2 => {
+                let max_log = if i == 1 { 8 } else { 9 };
+                let (table, n) =
+                    Fse::read(data.get(read..).ok_or_else(truncated)?, max_symbol, max_log)?;
+                read += n;
+                table
+            }
+            _ => frame.tables[i]
+                .clone()
+                .ok_or_else(|| corrupt("repeated sequence table without a previous one"))?,
+        };
+        frame.tables[i] = Some(table);
+    }
+    let [Some(ll), Some(of), Some(ml)] = &frame.tables else {
+        unreachable!("every table was just set");
+    };
+    let mut bits = Backward::new(data.get(read..).ok_or_else(truncated)?)?;
+    let mut states = [ll.init(&mut bits), of.init(&mut bits), ml.init(&mut bits)];
+    let mut literals = literals;
+    let offsets = &mut frame.offsets;
+    let start = out.len();
+    for i in 0..count {
+        let ll_code = usize::from(ll.symbol(states[0]));
+        let of_code = u32::from(of.symbol(states[1]));
+        let ml_code = usize::from(ml.symbol(states[2]));
+        if ll_code >= LL_BASE.len() || ml_code >= ML_BASE.len() || of_code > 31 {
+            return Err(corrupt("invalid sequence code"));
+        }
+        let value = (1usize << of_code) + bits.read(of_code) as usize;
+        let length = (ML_BASE[ml_code] + bits.read(u32::from(ML_BITS[ml_code])) as u32) as usize;
+        let run = (LL_BASE[ll_code] + bits.read(u32::from(LL_BITS[ll_code])) as u32) as usize;
+        let offset = if value > 3 {
+            *offsets = [value - 3, offsets[0], offsets[1]];
+            offsets[0]
+        } else {
+            // Repeat offsets, shifted by one after an empty literals run.
+            match value - 1 + usize::from(run == 0) {
+                0 => offsets[0],
+                1 => {
+                    offsets.swap(0, 1);
+                    offsets[0]
+                }
+                2 => {
+                    *offsets = [offsets[2], offsets[0], offsets[1]];
+                    offsets[0]
+                }
+                _ => {
+                    *offsets = [offsets[0].wrapping_sub(1), offsets[0], offsets[1]];
+                    offsets[0]
+                }
+            }
+        };
+        let run_literals = literals
+            .get(..run)
+            .ok_or_else(|| corrupt("literals overrun"))?;
+        out.extend_from_slice(run_literals);
+        literals = &literals[run..];
+        let reach = frame.produced + (out.len() - start);
+        if offset == 0 || offset > reach || offset > out.len() {
+            return Err(corrupt("match before the start of the output"));
+        }
+        let from = out.len() - offset;
+        if offset >= length {
+            out.extend_from_within(from..from + length);
+        } else {
+            for k in 0..length {
+                out.push(out[from + k]);
+            }
+        }
+        if i + 1 < count {
+            states[0] = ll.update(states[0], &mut bits);
+            states[2] = ml.update(states[2], &mut bits);
+            states[1] = of.update(states[1], &mut bits);
+        }
+    }
+    if bits.pos != 0 {
+        return Err(corrupt("sequence stream not consumed exactly"));
+    }
+    out.extend_from_slice(literals);
+    if out.len() - start > MAX_BLOCK {
+        return Err(corrupt("zstd block too large"));
+    }
+    Ok(())
+}
+
+const P1: u64 = 0x9E37_79B1_85EB_CA87;
+const P2: u64 = 0xC2B2_AE3D_27D4_EB4F;
+const P3: u64 = 0x1656_67B1_9E37_79F9;
+const P4: u64 = 0x85EB_CA77_C2B2_AE63;
+const P5: u64 = 0x27D4_EB2F_1656_67C5;
+
+/// XXH64 with seed 0, fed in pieces: zstd's content checksum is the low 32
+/// bits of its `finish`.
+pub(crate) struct Xxh64 {
+    lanes: [u64; 4],
+    /// Input not yet folded into `lanes`, fewer than 32 bytes.
+    pending: [u8; 32],
+    held: usize,
+    total: u64,
+}
+
+impl Xxh64 {
+    pub(crate) fn new() -> Self {
+        Xxh64 {
+            lanes: [P1.wrapping_add(P2), P2, 0, P1.wrapping_neg()],
+            pending: [0; 32],
+            held: 0,
+            total: 0,
+        }
+    }
+
+    pub(crate) fn update(&mut self, mut data: &[u8]) {
+        self.total += data.len() as u64;
+        if self.held > 0 {
+            let n = data.len().min(32 - self.held);
+            self.pending[self.held..self.held + n].copy_from_slice(&data[..n]);
+            self.held += n;
+            data = &data[n..];
+            if self.held < 32 {
+                return;
+            }
+            let pending = self.pending;
+            self.stripe(&pending);
+            self.held = 0;
+        }
+        let mut stripes = data.chunks_exact(32);
+        for stripe in &mut stripes {
+            self.stripe(stripe);
+        }
+        let rest = stripes.remainder();
+        self.pending[..rest.len()].copy_from_slice(rest);
+        self.held = rest.len();
+    }
+
+    fn stripe(&mut self, stripe: &[u8]) {
+        for (lane, word) in self.lanes.iter_mut().zip(stripe.chunks_exact(8)) {
+            *lane = round(*lane, load_le(word, 0));
+        }
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        let [v1, v2, v3, v4] = self.lanes;
+        let mut hash = if self.total >= 32 {
+            let mut hash = v1
+                .rotate_left(1)
+                .wrapping_add(v2.rotate_left(7))
+                .wrapping_add(v3.rotate_left(12))
+                .wrapping_add(v4.rotate_left(18));
+            for lane in self.lanes {
+                hash = (hash ^ round(0, lane)).wrapping_mul(P1).wrapping_add(P4);
+            }
+            hash
+        } else {
+            P5
+        };
+        hash = hash.wrapping_add(self.total);
+        let mut rest = &self.pending[..self.held];
+        while rest.len() >= 8 {
+            hash ^= round(0, load_le(rest, 0));
+            hash = hash.rotate_left(27).wrapping_mul(P1).wrapping_add(P4);
+            rest = &rest[8..];
+        }
+        if rest.len() >= 4 {
+            let word = u32::from_le_bytes(rest[..4].try_into().unwrap());
+            hash ^= u64::from(word).wrapping_mul(P1);
+            hash = hash.rotate_left(23).wrapping_mul(P2).wrapping_add(P3);
+            rest = &rest[4..];
+        }
+        for &byte in rest {
+            hash ^= u64::from(byte).wrapping_mul(P5);
+            hash = hash.rotate_left(11).wrapping_mul(P1);
+        }
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(P2);
+        hash ^= hash >> 29;
+        hash = hash.wrapping_mul(P3);
+        hash ^ (hash >> 32)
+    }
+}
+
+fn round(lane: u64, word: u64) -> u64 {
+    lane.wrapping_add(word.wrapping_mul(P2))
+        .rotate_left(31)
+        .wrapping_mul(P1)
+}
@@ -0,0 +1,104 @@
+//! The hot-only fast path allocates nothing per word: `selftest
+//! --allocations` as a test, with a counting allocator of its own.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use td5::selftest::{self, HOT_PATH_CASES};
+
+thread_local! {
+    // Per thread, as tests run side by side: other tests' allocations must
+    // not count.
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+struct Counting;
+
+fn count() {
+    // Fails only while the thread is torn down, when nothing is measured.
+    let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+}
+
+// SAFETY: every call is forwarded unchanged to `System`.
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count();
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count();
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count();
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn allocations(f: &mut dyn FnMut()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+#[test]
+fn the_counter_sees_allocations() {
+    let mut kept = Vec::new();
+    let n = allocations(&mut || kept.push(Box::new(1)));
+    assert_eq!(n, 2);
+}
+
+#[test]
+fn counting_hot_words_allocates_only_its_setup() {
+    let runs = selftest::hot_path_allocations(&allocations);
+    assert_eq!(
+        runs.len(),
+        HOT_PATH_CASES.len() * selftest::HOT_PATH_SIZES.len()
+    );
+    for run in &runs {
+        let case = HOT_PATH_CASES.iter().find(|c| c.name == run.case).unwrap();
+        assert!(run.counting <= case.setup, "{run:?}");
+    }
+    assert_eq!(selftest::allocation_failures(&runs), Vec::<String>::new());
+}
+
+#[test]
+fn analyze_allocates_the_same_whatever_the_length() {
+    let runs = selftest::hot_path_allocations(&allocations);
+    for case in &HOT_PATH_CASES {
+        let counts: Vec<usize> = runs
+            .iter()
+            .filter(|r| r.case == case.name)
+            .map(|r| r.analyze)
+            .collect();
+        assert!(
+            counts.windows(2).all(|w| w[0] == w[1]),
+            "{}: {counts:?}",
+            case.name
+        );
+    }
+}
+
+#[test]
+fn an_allocation_per_word_is_caught() {
+    // A run as a per-word allocation would leave it.
+    let mut runs = selftest::hot_path_allocations(&allocations);
+    for run in &mut runs {
+        run.counting += run.words;
+        run.analyze += run.words;
+    }
+    let failures = selftest::allocation_failures(&runs);
+    assert_eq!(
+        failures.len(),
+        runs.len() + HOT_PATH_CASES.len(),
+        "{failures:#?}"
+    );
+}
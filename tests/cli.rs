@@ -0,0 +1,429 @@
+//! End-to-end tests of the `td5` binary: each runs the real executable on a
+//! small fixture and checks its output and exit code.
+
+#![cfg(feature = "fs")]
+
+use std::io::Write;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use td5::json::{self, Json};
+
+const FIXTURE: &str = "The cat sat on the mat. The dog sat too.\n";
+
+/// Runs `td5 args`, feeding it `stdin`, with English messages whatever the
+/// locale.
+fn td5(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_td5"))
+        .args(args)
+        .env_remove("LC_ALL")
+        .env_remove("LC_MESSAGES")
+        .env_remove("LC_NUMERIC")
+        .env("LANG", "C")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("td5 runs");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+/// A file holding `text` in a directory of its own test, removed with the
+/// directory when the fixture is dropped.
+struct Fixture {
+    dir: PathBuf,
+    path: PathBuf,
+}
+
+impl Deref for Fixture {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn fixture(test: &str, text: &str) -> Fixture {
+    let dir = std::env::temp_dir().join(format!("td5-cli-{}-{test}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("fixture.txt");
+    std::fs::write(&path, text).unwrap();
+    Fixture { dir, path }
+}
+
+fn parse(output: &Output) -> Json {
+    json::parse(&stdout(output)).expect("valid JSON")
+}
+
+#[test]
+fn analyze_file_as_text() {
+    let path = fixture("text", FIXTURE);
+    let output = td5(&["analyze", path.to_str().unwrap()], "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    let out = stdout(&output);
+    assert!(out.contains("Unique words: 7"), "{out}");
+    assert!(out.contains("Total words: 10"), "{out}");
+    assert!(out.contains(r#"("the", 3), ("sat", 2)"#), "{out}");
+}
+
+#[test]
+fn analyze_stdin() {
+    let output = td5(&["analyze", "--format", "json", "-"], FIXTURE);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let doc = parse(&output);
+    assert_eq!(doc.get("total_words").and_then(Json::as_u64), Some(10));
+    assert_eq!(doc.get("source").and_then(Json::as_str), Some("-"));
+}
+
+#[test]
+fn analyze_formats() {
+    let path = fixture("formats", FIXTURE);
+    let path = path.to_str().unwrap();
+
+    let doc = parse(&td5(&["analyze", "--format", "json", path], ""));
+    assert_eq!(doc.get("word_count").and_then(Json::as_u64), Some(7));
+    let top = doc.get("top_words").and_then(Json::as_array).unwrap();
+    assert_eq!(top[0], Json::Array(vec!["the".into(), 3u64.into()]));
+
+    let csv = stdout(&td5(&["analyze", "--format", "csv", path], ""));
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some(td5::csv::HEADER));
+    assert!(
+        lines
+            .next()
+            .unwrap()
+            .starts_with(&format!("{path},7,10,29,1,2,2.9,3,the:3 sat:2"))
+    );
+
+    let yaml = stdout(&td5(&["analyze", "--format", "yaml", path], ""));
+    assert!(yaml.starts_with("---\n"), "{yaml}");
+    assert!(yaml.contains("\ntotal_words: 10\n"), "{yaml}");
+
+    let output = td5(&["analyze", "--format", "xml", path], "");
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("unknown format `xml`"));
+}
+
+#[test]
+fn analyze_without_time() {
+    let path = fixture("time", FIXTURE);
+    let output = td5(
+        &[
+            "analyze",
+            "--format",
+            "json",
+            "--time",
+            "omit",
+            path.to_str().unwrap(),
+        ],
+        "",
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(parse(&output).get("time_ns"), None);
+}
+
+#[test]
+fn word_limits_count_the_words_the_analyzers_count() {
+    let text = "« un » — deux, trois quatre « cinq » —\n";
+    let path = fixture("word-limits", text);
+    for (flag, source) in [
+        ("--first-n-words", "-"),
+        ("--last-n-words", path.to_str().unwrap()),
+    ] {
+        let output = td5(&["analyze", "--format", "json", flag, "3", source], text);
+        assert!(output.status.success(), "{}", stderr(&output));
+        assert_eq!(
+            parse(&output).get("total_words").and_then(Json::as_u64),
+            Some(3),
+            "{flag}"
+        );
+    }
+}
+
+#[test]
+fn analyze_assert_fails_with_code_3() {
+    let path = fixture("assert", FIXTURE);
+    let path = path.to_str().unwrap();
+    assert!(
+        td5(&["analyze", "--assert", "total_words>5", path], "")
+            .status
+            .success()
+    );
+    let output = td5(&["analyze", "--assert", "total_words>100", path], "");
+    assert_eq!(output.status.code(), Some(3));
+    assert!(stderr(&output).contains("total_words>100 (actual 10)"));
+}
+
+#[test]
+fn bench_reports_every_cell() {
+    let output = td5(
+        &[
+            "bench",
+            "--words",
+            "500",
+            "--iterations",
+            "2",
+            "--shapes",
+            "zipf",
+            "--analyzers",
+            "slow,fast",
+            "--format",
+            "json",
+        ],
+        "",
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    let doc = parse(&output);
+    let results = doc.get("results").and_then(Json::as_array).unwrap();
+    assert_eq!(results.len(), 2);
+    for result in results {
+        assert_eq!(result.get("input").and_then(Json::as_str), Some("zipf"));
+        let samples = result.get("samples_ns").and_then(Json::as_array).unwrap();
+        assert_eq!(samples.len(), 2);
+    }
+}
+
+#[test]
+fn bench_fails_on_regression() {
+    let baseline = fixture(
+        "regression",
+        r#"{"results": [{"analyzer": "fast", "input": "zipf", "samples_ns": [1, 1, 1, 1, 1]}]}"#,
+    );
+    let run = |percent: &str| {
+        td5(
+            &[
+                "bench",
+                "--words",
+                "500",
+                "--iterations",
+                "5",
+                "--shapes",
+                "zipf",
+                "--analyzers",
+                "fast",
+                "--baseline",
+                baseline.to_str().unwrap(),
+                "--max-regression",
+                percent,
+            ],
+            "",
+        )
+    };
+    let output = run("10");
+    assert_eq!(output.status.code(), Some(3), "{}", stderr(&output));
+    assert!(stderr(&output).contains("regression: zipf / fast"));
+    assert!(run("1e12").status.success());
+}
+
+#[test]
+fn selftest_runs_every_case() {
+    let output = td5(&["selftest"], "");
+    let out = stdout(&output);
+    assert!(output.status.success(), "{out}");
+    assert!(out.starts_with("ok    empty\n"), "{out}");
+    // Features add cases, so the number of cases varies.
+    let summary = out.lines().last().unwrap();
+    assert!(
+        summary.starts_with("0 of ") && summary.ends_with(" cases diverge"),
+        "{out}"
+    );
+    let output = td5(&["selftest", "--allocations"], "");
+    assert!(output.status.success(), "{}", stderr(&output));
+}
+
+#[test]
+fn selftest_stack_fits_the_budget() {
+    let output = td5(&["selftest", "--stack"], "");
+    assert!(output.status.success(), "{}", stdout(&output));
+    assert!(!stdout(&output).contains("FAIL"));
+}
+
+#[test]
+fn concordance_and_approx() {
+    let path = fixture("words", FIXTURE);
+    let path = path.to_str().unwrap();
+
+    let doc = parse(&td5(&["concordance", "--format", "json", "cat", path], ""));
+    let lines = doc.get("lines").and_then(Json::as_array).unwrap();
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0].get("left").and_then(Json::as_str), Some("The"));
+
+    let doc = parse(&td5(&["approx", "--format", "json", path], ""));
+    assert_eq!(doc.get("total_words").and_then(Json::as_u64), Some(10));
+    let top = doc.get("top_words").and_then(Json::as_array).unwrap();
+    assert_eq!(
+        top[0],
+        Json::Array(vec!["the".into(), 3u64.into(), 3u64.into()])
+    );
+
+    // A sketch too large to allocate is refused, not attempted.
+    let output = td5(&["approx", "--epsilon", "1e-15", path], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("raise epsilon or delta"));
+}
+
+#[test]
+fn usage_errors() {
+    let output = td5(&["frobnicate"], "");
+    assert_eq!(output.status.code(), Some(2));
+    let output = td5(&["analyze", "--no-such-flag", "-"], "");
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("--no-such-flag"));
+}
+
+#[test]
+fn help_lists_commands() {
+    let output = td5(&["--help"], "");
+    assert!(output.status.success());
+    let out = stdout(&output);
+    for command in ["analyze", "bench", "selftest", "concordance", "approx"] {
+        assert!(out.contains(&format!("\n  {command} ")), "{command}");
+    }
+}
+
+/// The words of a line of a completion script, unquoted the way zsh
+/// (`fish` false) or fish reads them; `None` when a quote is left open.
+fn shell_words(line: &str, fish: bool) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = line.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            ' ' | '\t' => words.extend(word.take()),
+            '\'' => loop {
+                let w = word.get_or_insert_with(String::new);
+                match chars.next()? {
+                    '\'' => break,
+                    '\\' if fish => match chars.next()? {
+                        ch @ ('\'' | '\\') => w.push(ch),
+                        ch => w.extend(['\\', ch]),
+                    },
+                    ch => w.push(ch),
+                }
+            },
+            '"' => loop {
+                let w = word.get_or_insert_with(String::new);
+                match chars.next()? {
+                    '"' => break,
+                    '\\' => w.push(chars.next()?),
+                    ch => w.push(ch),
+                }
+            },
+            '\\' => word.get_or_insert_with(String::new).push(chars.next()?),
+            ch => word.get_or_insert_with(String::new).push(ch),
+        }
+    }
+    words.extend(word);
+    Some(words)
+}
+
+#[test]
+fn completion_scripts_quote_help_texts() {
+    let help = "Detect each input's language and use its stopwords and stemmer";
+    for (shell, fish) in [("zsh", false), ("fish", true)] {
+        let script = stdout(&td5(&["completions", shell], ""));
+        let mut found = false;
+        for line in script.lines() {
+            let words = shell_words(line, fish)
+                .unwrap_or_else(|| panic!("{shell}: open quote in {line:?}"));
+            found |= words.iter().any(|w| w.contains(help));
+        }
+        assert!(found, "{shell}: no help text with an apostrophe");
+    }
+    let zsh = stdout(&td5(&["completions", "zsh"], ""));
+    assert!(zsh.contains(r"Phrase lists (\[name\] headers"));
+
+    // With the shell itself, where it is installed.
+    for shell in ["bash", "zsh", "fish"] {
+        let Ok(mut child) = Command::new(shell)
+            .arg("-n")
+            .stdin(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+        else {
+            continue;
+        };
+        let script = stdout(&td5(&["completions", shell], ""));
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(script.as_bytes())
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert!(output.status.success(), "{shell} -n: {}", stderr(&output));
+    }
+}
+
+#[test]
+fn a_closed_stdout_ends_analyze_quietly() {
+    for format in ["text", "json", "csv"] {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_td5"))
+            .args(["analyze", "--format", format, "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        // As `td5 analyze | head -0` would: gone before anything is written.
+        drop(child.stdout.take());
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(FIXTURE.as_bytes())
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert!(output.status.success(), "{format}: {}", stderr(&output));
+        assert_eq!(stderr(&output), "", "{format}");
+    }
+}
+
+#[test]
+fn a_closed_stdout_ends_every_command_quietly() {
+    let path = fixture("closed", FIXTURE);
+    let path = path.to_str().unwrap();
+    for args in [
+        &["lint", path][..],
+        &["stability", path],
+        &["overlap", path, path],
+        &["compare", path, path],
+        &["approx", path],
+        &["readability", path],
+        &["concordance", "sat", path],
+        &["help"],
+    ] {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_td5"))
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        drop(child.stdout.take());
+        let output = child.wait_with_output().unwrap();
+        let stderr = stderr(&output);
+        assert!(output.status.success(), "{args:?}: {stderr}");
+        assert!(!stderr.contains("panicked"), "{args:?}: {stderr}");
+    }
+}
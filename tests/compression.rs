@@ -0,0 +1,59 @@
+//! Decompressing inputs, and refusing the corrupt ones by their checksums.
+
+use td5::sniff::{Compression, decompress};
+
+const TEXT: &str =
+    "The quick brown fox jumps over the lazy dog, and the dog sleeps on; the fox runs off.\n";
+
+/// `TEXT` as `gzip -n` writes it.
+const GZIP: &[u8] = b"\
+    \x1f\x8b\x08\x00\x00\x00\x00\x00\x00\x03\x0b\xc9\x48\x55\x28\x2c\xcd\x4c\xce\x56\
+    \x48\x2a\xca\x2f\xcf\x53\x48\xcb\xaf\x50\xc8\x2a\xcd\x2d\x28\x56\xc8\x2f\x4b\x2d\
+    \x52\x28\x01\x4a\xe7\x24\x56\x55\x2a\xa4\xe4\xa7\xeb\x28\x24\xe6\xa5\x80\x45\x80\
+    \x1c\x85\xe2\x9c\xd4\x54\x90\xaa\x3c\x6b\xb0\x10\x48\x5f\x51\x69\x1e\x50\x20\x2d\
+    \x4d\x8f\x0b\x00\x9d\x6f\x28\x07\x56\x00\x00\x00";
+
+/// `TEXT` as `zstd --check` writes it.
+#[cfg(feature = "zstd")]
+const ZSTD: &[u8] = b"\
+    \x28\xb5\x2f\xfd\x24\x56\x85\x02\x00\x42\x04\x10\x17\x80\xd5\x39\x81\x75\x7b\xfc\
+    \x28\xd2\xf5\x7b\x13\x29\x99\x62\xc8\x18\xd3\x95\xb3\xb0\x89\x9e\xc1\xa0\x7a\xbb\
+    \x7d\x8d\xd8\x4c\xe6\xac\x8e\xb8\xc9\x82\x3a\x1a\x10\x48\xcc\xb9\x1c\xb7\x7c\x6e\
+    \xc7\x2c\x2f\x71\x74\xf5\x42\x6f\x86\x95\x03\x79\x8d\x73\x15\x01\x04\x00\x5b\x05\
+    \x40\xea\x70\x52\xc6\xa1\xda\x09\x05\xa0\x66\xec\x0f";
+
+/// `bytes` with the byte `from_end` bytes before its end flipped.
+fn flipped(bytes: &[u8], from_end: usize) -> Vec<u8> {
+    let mut bytes = bytes.to_vec();
+    let at = bytes.len() - from_end;
+    bytes[at] ^= 1;
+    bytes
+}
+
+#[test]
+fn gzip_trailers_are_checked() {
+    let text = decompress(GZIP, Compression::Gzip).unwrap();
+    assert_eq!(text, TEXT.as_bytes());
+    // The CRC-32, then the length.
+    let error = decompress(&flipped(GZIP, 8), Compression::Gzip).unwrap_err();
+    assert!(error.to_string().contains("CRC-32 mismatch"), "{error}");
+    let error = decompress(&flipped(GZIP, 4), Compression::Gzip).unwrap_err();
+    assert!(error.to_string().contains("length mismatch"), "{error}");
+    // Every member is checked, not only the first.
+    let twice = [GZIP, &flipped(GZIP, 8)].concat();
+    assert!(decompress(&twice, Compression::Gzip).is_err());
+    let twice = [GZIP, GZIP].concat();
+    assert_eq!(
+        decompress(&twice, Compression::Gzip).unwrap(),
+        TEXT.repeat(2).as_bytes()
+    );
+}
+
+#[test]
+#[cfg(feature = "zstd")]
+fn zstd_content_checksums_are_checked() {
+    let text = decompress(ZSTD, Compression::Zstd).unwrap();
+    assert_eq!(text, TEXT.as_bytes());
+    let error = decompress(&flipped(ZSTD, 1), Compression::Zstd).unwrap_err();
+    assert!(error.to_string().contains("checksum mismatch"), "{error}");
+}
@@ -0,0 +1,52 @@
+//! The fast path against the reference on random text, as
+//! `td5 selftest --differential` checks it, over the whole space the
+//! generator covers.
+
+use td5::selftest::{compare, differential, random_text};
+use td5::{analyze_text_fast, analyze_text_slow};
+
+#[test]
+fn the_fast_path_agrees_with_the_reference_on_random_text() {
+    for seed in [0, 1 << 20, u64::MAX - 5_000] {
+        assert_eq!(differential(5_000, seed), None, "from seed {seed}");
+    }
+}
+
+#[test]
+fn the_generator_covers_the_former_divergences() {
+    let texts: Vec<String> = (0..5_000).map(|seed| random_text(seed, 60)).collect();
+    let has = |what: &str, found: &dyn Fn(&str) -> bool| {
+        assert!(texts.iter().any(|t| found(t)), "no text with {what}");
+    };
+    has("`İ`", &|t| t.contains('İ'));
+    has("`Σ`", &|t| t.contains('Σ'));
+    has("`ﬁ`", &|t| t.contains('ﬁ'));
+    has("an apostrophe inside an ASCII word", &|t| {
+        t.is_ascii()
+            && t.split_whitespace().any(|token| {
+                let inner = token.trim_matches(|c: char| !c.is_ascii_alphabetic());
+                inner.contains('\'')
+            })
+    });
+    has("a longest word repeated", &|t| {
+        let longest = t.split_whitespace().map(str::len).max().unwrap_or(0);
+        t.split_whitespace().filter(|w| w.len() == longest).count() > 1
+    });
+}
+
+#[test]
+fn known_hard_inputs_agree() {
+    for text in [
+        "it's don't l'été",
+        "İstanbul ΣΊΣΥΦΟΣ ﬁn ǅemal",
+        "abc, abc; abc. de",
+        "x'y'z x-y-z x…y",
+    ] {
+        let expected = analyze_text_slow(text);
+        let actual = analyze_text_fast(text);
+        assert_eq!(compare(&expected, &actual), Vec::new(), "on {text:?}");
+        let mut distinct = expected.longest_words.clone();
+        distinct.dedup();
+        assert_eq!(distinct, expected.longest_words, "repeats on {text:?}");
+    }
+}
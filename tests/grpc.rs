@@ -0,0 +1,315 @@
+//! Calls to `td5::grpc::Server` from a bare HTTP/2 client: each test starts
+//! a server on a free port and checks the response frames of one call.
+#![cfg(feature = "grpc")]
+
+use std::io::{ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::time::Duration;
+use td5::clock::ManualClock;
+use td5::grpc::{Limits, Server};
+
+/// A response: its headers and trailers, and its gRPC messages.
+struct Response {
+    headers: Vec<(String, String)>,
+    messages: Vec<Vec<u8>>,
+}
+
+impl Response {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+fn frame(out: &mut Vec<u8>, kind: u8, flags: u8, payload: &[u8]) {
+    out.extend_from_slice(&(payload.len() as u32).to_be_bytes()[1..]);
+    out.extend_from_slice(&[kind, flags]);
+    out.extend_from_slice(&1u32.to_be_bytes());
+    out.extend_from_slice(payload);
+}
+
+/// A protobuf message of string fields, lengths under 128 bytes.
+fn strings(fields: &[(u8, &str)]) -> Vec<u8> {
+    let mut msg = Vec::new();
+    for (field, text) in fields {
+        msg.extend_from_slice(&[field << 3 | 2, text.len() as u8]);
+        msg.extend_from_slice(text.as_bytes());
+    }
+    msg
+}
+
+/// The client preface, empty settings, and the headers of a call to `path`
+/// on stream 1.
+fn open(path: &str) -> Vec<u8> {
+    let mut request = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n".to_vec();
+    request.extend_from_slice(&[0, 0, 0, 4, 0, 0, 0, 0, 0]);
+    // Literal header fields, neither indexed nor Huffman-coded.
+    let mut block = Vec::new();
+    for (name, value) in [
+        (":method", "POST"),
+        (":scheme", "http"),
+        (":path", path),
+        (":authority", "localhost"),
+        ("content-type", "application/grpc"),
+        ("te", "trailers"),
+    ] {
+        block.extend_from_slice(&[0, name.len() as u8]);
+        block.extend_from_slice(name.as_bytes());
+        block.push(value.len() as u8);
+        block.extend_from_slice(value.as_bytes());
+    }
+    frame(&mut request, 1, 0x4, &block);
+    request
+}
+
+/// Adds the fields of a header block to `headers`.
+fn decode(block: &[u8], headers: &mut Vec<(String, String)>) {
+    // The server writes literals alone, with short lengths.
+    let mut at = 0;
+    while at < block.len() {
+        let name_len = usize::from(block[at + 1]);
+        let name = &block[at + 2..at + 2 + name_len];
+        at += 2 + name_len;
+        let value_len = usize::from(block[at]);
+        let value = &block[at + 1..at + 1 + value_len];
+        at += 1 + value_len;
+        headers.push((
+            String::from_utf8(name.to_vec()).unwrap(),
+            String::from_utf8(value.to_vec()).unwrap(),
+        ));
+    }
+}
+
+/// Calls `path` on a fresh server with `messages` on stream 1.
+fn call(path: &str, messages: &[Vec<u8>]) -> Response {
+    call_with(Limits::default(), path, messages)
+}
+
+/// `call` on a server with `limits`.
+fn call_with(limits: Limits, path: &str, messages: &[Vec<u8>]) -> Response {
+    let server = Server::bind("127.0.0.1:0", Default::default())
+        .unwrap()
+        .limits(limits);
+    let address = server.local_addr().unwrap();
+    std::thread::spawn(move || server.run());
+
+    let mut request = open(path);
+    let mut body = Vec::new();
+    for message in messages {
+        body.push(0);
+        body.extend_from_slice(&(message.len() as u32).to_be_bytes());
+        body.extend_from_slice(message);
+    }
+    frame(&mut request, 0, 0x1, &body);
+    let mut socket = TcpStream::connect(address).unwrap();
+    socket.write_all(&request).unwrap();
+
+    let mut response = Response {
+        headers: Vec::new(),
+        messages: Vec::new(),
+    };
+    let mut data = Vec::new();
+    loop {
+        let mut header = [0; 9];
+        socket.read_exact(&mut header).unwrap();
+        let len = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+        let mut payload = vec![0; len];
+        socket.read_exact(&mut payload).unwrap();
+        let (kind, flags, stream) = (header[3], header[4], header[8]);
+        assert_ne!(kind, 7, "GOAWAY: {}", String::from_utf8_lossy(&payload));
+        if stream != 1 {
+            continue;
+        }
+        match kind {
+            0 => data.extend(payload),
+            1 => {
+                decode(&payload, &mut response.headers);
+                if flags & 0x1 != 0 {
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    while !data.is_empty() {
+        let len = u32::from_be_bytes([data[1], data[2], data[3], data[4]]) as usize;
+        response.messages.push(data[5..5 + len].to_vec());
+        data.drain(..5 + len);
+    }
+    response
+}
+
+/// The value of a varint field of a protobuf message.
+fn uint(msg: &[u8], wanted: u8) -> Option<u64> {
+    let mut at = 0;
+    while at < msg.len() {
+        let key = msg[at];
+        at += 1;
+        let mut n = 0u64;
+        let mut shift = 0;
+        if key & 7 == 0 || key & 7 == 2 {
+            loop {
+                let byte = msg[at];
+                at += 1;
+                n |= u64::from(byte & 0x7f) << shift;
+                shift += 7;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+        }
+        match key & 7 {
+            0 if key >> 3 == wanted => return Some(n),
+            0 => {}
+            1 => at += 8,
+            _ => at += n as usize,
+        }
+    }
+    None
+}
+
+#[test]
+fn analyze_returns_the_stats_of_the_text() {
+    let request = strings(&[(1, "the cat the"), (2, "slow")]);
+    let response = call("/td5.v1.Analyzer/Analyze", &[request]);
+    assert_eq!(response.header(":status"), Some("200"));
+    assert_eq!(response.header("grpc-status"), Some("0"));
+    assert_eq!(response.messages.len(), 1);
+    // `unique_words` and `total_words`.
+    assert_eq!(uint(&response.messages[0], 1), Some(2));
+    assert_eq!(uint(&response.messages[0], 2), Some(3));
+}
+
+#[test]
+fn analyze_stream_joins_words_cut_between_chunks() {
+    let chunks: Vec<Vec<u8>> = ["the ca", "t sat on th", "e mat"]
+        .iter()
+        .map(|chunk| strings(&[(1, chunk)]))
+        .collect();
+    let response = call("/td5.v1.Analyzer/AnalyzeStream", &chunks);
+    assert_eq!(response.header("grpc-status"), Some("0"));
+    assert_eq!(uint(&response.messages[0], 1), Some(5));
+    assert_eq!(uint(&response.messages[0], 2), Some(6));
+}
+
+#[test]
+fn unknown_analyzers_and_methods_are_errors() {
+    let request = strings(&[(1, "text"), (2, "nope")]);
+    let response = call("/td5.v1.Analyzer/Analyze", &[request]);
+    assert_eq!(response.header("grpc-status"), Some("3"));
+    assert_eq!(
+        response.header("grpc-message"),
+        Some("unknown analyzer `nope`")
+    );
+    assert!(response.messages.is_empty());
+
+    let response = call("/td5.v1.Analyzer/Translate", &[]);
+    assert_eq!(response.header("grpc-status"), Some("12"));
+}
+
+#[test]
+fn messages_over_the_limit_are_refused() {
+    let limits = Limits {
+        max_message: 8,
+        ..Limits::default()
+    };
+    let request = strings(&[(1, "the cat sat on the mat")]);
+    let response = call_with(limits, "/td5.v1.Analyzer/Analyze", &[request]);
+    assert_eq!(response.header("grpc-status"), Some("8"));
+    assert!(response.messages.is_empty());
+}
+
+#[test]
+fn shutdown_stops_the_server() {
+    let server = Server::bind("127.0.0.1:0", Default::default()).unwrap();
+    let shutdown = server.shutdown_handle().unwrap();
+    let running = std::thread::spawn(move || server.run());
+    std::thread::sleep(Duration::from_millis(50));
+    shutdown.shutdown();
+    for _ in 0..100 {
+        if running.is_finished() {
+            return running.join().unwrap().unwrap();
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    panic!("the server still runs 5 s after shutdown");
+}
+
+/// Frames read off `socket` until one of `kind` arrives, `None` if the
+/// server stays silent for `wait`.
+fn next(socket: &mut TcpStream, kind: u8, wait: Duration) -> Option<(u8, u32, Vec<u8>)> {
+    socket.set_read_timeout(Some(wait)).unwrap();
+    loop {
+        let mut header = [0; 9];
+        match socket.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                return None;
+            }
+            Err(e) => panic!("{e}"),
+        }
+        let len = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+        let mut payload = vec![0; len];
+        socket.set_read_timeout(None).unwrap();
+        socket.read_exact(&mut payload).unwrap();
+        socket.set_read_timeout(Some(wait)).unwrap();
+        if header[3] == kind {
+            let stream = u32::from_be_bytes([header[5], header[6], header[7], header[8]]);
+            return Some((header[4], stream, payload));
+        }
+    }
+}
+
+#[test]
+fn deadlines_and_idle_time_follow_the_server_clock() {
+    let clock = Arc::new(ManualClock::new());
+    let server = Server::bind("127.0.0.1:0", Default::default())
+        .unwrap()
+        .limits(Limits {
+            timeout: Duration::from_secs(10),
+            idle_timeout: Duration::from_secs(20),
+            ..Limits::default()
+        })
+        .with_clock(clock.clone());
+    let address = server.local_addr().unwrap();
+    std::thread::spawn(move || server.run());
+
+    // A call whose request never comes.
+    let mut socket = TcpStream::connect(address).unwrap();
+    socket.write_all(&open("/td5.v1.Analyzer/Analyze")).unwrap();
+    // Real time passing is not enough.
+    assert!(next(&mut socket, 1, Duration::from_millis(500)).is_none());
+
+    let mut headers = Vec::new();
+    let mut waited = Duration::ZERO;
+    while !headers.iter().any(|(name, _)| name == "grpc-status") {
+        assert!(
+            waited <= Duration::from_secs(15),
+            "no deadline after {waited:?}"
+        );
+        clock.advance(Duration::from_secs(1));
+        waited += Duration::from_secs(1);
+        if let Some((_, 1, block)) = next(&mut socket, 1, Duration::from_millis(250)) {
+            decode(&block, &mut headers);
+        }
+    }
+    assert!(
+        waited >= Duration::from_secs(10),
+        "deadline after {waited:?}"
+    );
+    assert!(headers.contains(&("grpc-status".to_string(), "4".to_string())));
+
+    // With no call left, the connection is closed once idle long enough.
+    assert!(next(&mut socket, 7, Duration::from_millis(500)).is_none());
+    clock.advance(Duration::from_secs(20));
+    let (_, _, goaway) = next(&mut socket, 7, Duration::from_secs(5)).unwrap();
+    assert!(goaway.ends_with(b"idle"));
+    let mut rest = Vec::new();
+    socket
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    assert_eq!(socket.read_to_end(&mut rest).unwrap(), 0);
+}
@@ -0,0 +1,46 @@
+//! Walking directories and globs on disk.
+
+#![cfg(all(unix, feature = "fs"))]
+
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+
+/// `corpus/a.txt`, `corpus/sub/b.txt` and `corpus/sub/up`, a link back to
+/// `corpus`.
+fn looped(test: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("td5-input-{}-{test}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(root.join("sub")).unwrap();
+    std::fs::write(root.join("a.txt"), "a").unwrap();
+    std::fs::write(root.join("sub/b.txt"), "b").unwrap();
+    symlink(&root, root.join("sub/up")).unwrap();
+    root
+}
+
+fn names(root: &Path, files: &[PathBuf]) -> Vec<String> {
+    let mut names: Vec<String> = files
+        .iter()
+        .map(|f| f.strip_prefix(root).unwrap().display().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn a_directory_link_loop_is_walked_once() {
+    let root = looped("files");
+    let files = td5::input::files(&root).unwrap();
+    assert_eq!(names(&root, &files), ["a.txt", "sub/b.txt"]);
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn a_glob_through_a_link_loop_ends() {
+    let root = looped("glob");
+    let files = td5::input::glob(&format!("{}/**/*.txt", root.display())).unwrap();
+    assert_eq!(names(&root, &files), ["a.txt", "sub/b.txt"]);
+    // A pattern that is not a path walks the same way.
+    let files = td5::input::files(root.join("**")).unwrap();
+    assert_eq!(names(&root, &files), ["a.txt", "sub/b.txt"]);
+    std::fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,243 @@
+//! `td5::kafka::Consumer` and `td5 consume --kafka` against a fake broker
+//! speaking the same subset of the protocol.
+
+#![cfg(feature = "kafka")]
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process::Command;
+use td5::kafka::Consumer;
+
+const TOPIC: &str = "logs";
+
+fn i16s(value: i16) -> [u8; 2] {
+    value.to_be_bytes()
+}
+
+fn i32s(value: i32) -> [u8; 4] {
+    value.to_be_bytes()
+}
+
+fn i64s(value: i64) -> [u8; 8] {
+    value.to_be_bytes()
+}
+
+fn string(value: &str) -> Vec<u8> {
+    [&i16s(value.len() as i16)[..], value.as_bytes()].concat()
+}
+
+fn varint(value: i64) -> Vec<u8> {
+    let mut zigzag = ((value << 1) ^ (value >> 63)) as u64;
+    let mut out = Vec::new();
+    loop {
+        let byte = (zigzag & 0x7F) as u8;
+        zigzag >>= 7;
+        if zigzag == 0 {
+            out.push(byte);
+            return out;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+/// A record at `delta` from its batch's base offset; `None` is a null value.
+fn record(delta: i64, value: Option<&str>) -> Vec<u8> {
+    let mut body = vec![0];
+    body.extend(varint(0));
+    body.extend(varint(delta));
+    body.extend(varint(-1));
+    match value {
+        Some(value) => {
+            body.extend(varint(value.len() as i64));
+            body.extend_from_slice(value.as_bytes());
+        }
+        None => body.extend(varint(-1)),
+    }
+    body.extend(varint(0));
+    [varint(body.len() as i64), body].concat()
+}
+
+/// A v2 record batch; `records` already compressed as `codec` says.
+fn batch(base: i64, count: i32, codec: i16, records: &[u8]) -> Vec<u8> {
+    let mut after_length = Vec::new();
+    after_length.extend(i32s(0));
+    after_length.push(2);
+    // The CRC-32C, which the consumer does not check.
+    after_length.extend(i32s(0));
+    after_length.extend(i16s(codec));
+    after_length.extend(i32s(count - 1));
+    after_length.extend(i64s(0));
+    after_length.extend(i64s(0));
+    after_length.extend(i64s(-1));
+    after_length.extend(i16s(-1));
+    after_length.extend(i32s(-1));
+    after_length.extend(i32s(count));
+    after_length.extend_from_slice(records);
+    [
+        &i64s(base)[..],
+        &i32s(after_length.len() as i32),
+        &after_length,
+    ]
+    .concat()
+}
+
+/// `data` as a gzip member of one stored DEFLATE block.
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                0xEDB8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    let length = data.len() as u16;
+    let mut out = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 3, 1];
+    out.extend(length.to_le_bytes());
+    out.extend((!length).to_le_bytes());
+    out.extend_from_slice(data);
+    out.extend((crc ^ !0).to_le_bytes());
+    out.extend((data.len() as u32).to_le_bytes());
+    out
+}
+
+/// Offsets 0 to 3: a plain batch with a null value at 1, then a gzip one.
+fn records() -> Vec<u8> {
+    let plain = [
+        record(0, Some("the cat sat")),
+        record(1, None),
+        record(2, Some("on the mat")),
+    ]
+    .concat();
+    let packed = gzip(&record(0, Some("the end")));
+    let mut records = [batch(0, 3, 0, &plain), batch(3, 1, 1, &packed)].concat();
+    // The start of a batch cut short by the fetch size, left for later.
+    records.extend(&batch(4, 1, 0, &record(0, Some("later")))[..20]);
+    records
+}
+
+/// Answers one connection's requests until the consumer fetches past
+/// `records()`, then closes it; the fetch offsets asked for.
+fn serve(mut stream: TcpStream, port: u16, topic_error: i16) -> Vec<i64> {
+    let mut fetched = Vec::new();
+    loop {
+        let mut length = [0u8; 4];
+        if stream.read_exact(&mut length).is_err() {
+            return fetched;
+        }
+        let mut request = vec![0; u32::from_be_bytes(length) as usize];
+        stream.read_exact(&mut request).unwrap();
+        let api = i16::from_be_bytes([request[0], request[1]]);
+        let client = i16::from_be_bytes([request[8], request[9]]) as usize;
+        let body = &request[10 + client..];
+        let mut response = request[4..8].to_vec();
+        match api {
+            // Metadata: this broker leads the topic's only partition.
+            3 => {
+                response.extend(i32s(1));
+                response.extend(i32s(7));
+                response.extend(string("127.0.0.1"));
+                response.extend(i32s(i32::from(port)));
+                response.extend(i32s(1));
+                response.extend(i16s(topic_error));
+                response.extend(string(TOPIC));
+                response.extend(i32s(1));
+                response.extend(i16s(0));
+                response.extend(i32s(0));
+                response.extend(i32s(7));
+                response.extend(i32s(1));
+                response.extend(i32s(7));
+                response.extend(i32s(1));
+                response.extend(i32s(7));
+            }
+            // ListOffsets: 0 is the first record, 4 the end.
+            2 => {
+                let time = i64::from_be_bytes(body[body.len() - 8..].try_into().unwrap());
+                response.extend(i32s(1));
+                response.extend(string(TOPIC));
+                response.extend(i32s(1));
+                response.extend(i32s(0));
+                response.extend(i16s(0));
+                response.extend(i64s(-1));
+                response.extend(i64s(if time == -2 { 0 } else { 4 }));
+            }
+            1 => {
+                let at = body.len() - 12;
+                let offset = i64::from_be_bytes(body[at..at + 8].try_into().unwrap());
+                fetched.push(offset);
+                if offset >= 4 {
+                    return fetched;
+                }
+                let records = records();
+                response.extend(i32s(0));
+                response.extend(i32s(1));
+                response.extend(string(TOPIC));
+                response.extend(i32s(1));
+                response.extend(i32s(0));
+                response.extend(i16s(0));
+                response.extend(i64s(4));
+                response.extend(i64s(4));
+                response.extend(i32s(-1));
+                response.extend(i32s(records.len() as i32));
+                response.extend(records);
+            }
+            api => panic!("unexpected request {api}"),
+        }
+        stream.write_all(&i32s(response.len() as i32)).unwrap();
+        stream.write_all(&response).unwrap();
+    }
+}
+
+/// A broker on a free port, serving one connection.
+fn broker(topic_error: i16) -> (String, std::thread::JoinHandle<Vec<i64>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        serve(stream, port, topic_error)
+    });
+    (format!("127.0.0.1:{port}"), server)
+}
+
+#[test]
+fn records_are_read_from_the_beginning_until_the_broker_closes() {
+    let (address, server) = broker(0);
+    let mut consumer = Consumer::connect(&address, TOPIC, 0, true).unwrap();
+    let mut values = Vec::new();
+    while let Some(value) = consumer.next_message().unwrap() {
+        values.push(String::from_utf8(value).unwrap());
+    }
+    assert_eq!(values, ["the cat sat", "on the mat", "the end"]);
+    assert_eq!(server.join().unwrap(), [0, 4]);
+}
+
+#[test]
+fn an_unknown_topic_is_an_error() {
+    let (address, _server) = broker(3);
+    let Err(error) = Consumer::connect(&address, TOPIC, 0, false) else {
+        panic!("connected to an unknown topic");
+    };
+    assert!(error.to_string().contains("no topic `logs`"), "{error}");
+}
+
+#[test]
+fn consume_reports_the_records() {
+    let (address, _server) = broker(0);
+    let output = Command::new(env!("CARGO_BIN_EXE_td5"))
+        .args(["consume", "--kafka", &address, "--topic", TOPIC])
+        .arg("--from-beginning")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let last = td5::json::parse(stdout.lines().last().unwrap()).unwrap();
+    assert_eq!(last.get("messages").and_then(|m| m.as_u64()), Some(3));
+    assert_eq!(last.get("total_words").and_then(|m| m.as_u64()), Some(8));
+}
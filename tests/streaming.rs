@@ -0,0 +1,70 @@
+//! `StreamingAnalyzer` fed in chunks, against the whole text analyzed at once.
+
+use td5::streaming::{StreamingAnalyzer, WordLimit};
+
+#[test]
+fn a_word_fed_a_byte_at_a_time_is_counted_once() {
+    let mut analyzer = StreamingAnalyzer::default();
+    // Quadratic in the word's length if each push searched the whole carry.
+    for _ in 0..200_000 {
+        analyzer.push("a");
+    }
+    analyzer.push(" b\n");
+    let stats = analyzer.finish().stats;
+    assert_eq!(stats.total_words, 2);
+    assert_eq!(stats.longest_words[0].len(), 200_000);
+}
+
+#[test]
+fn chunk_boundaries_do_not_change_the_counts() {
+    let text = "The cat sat.\nOn the  mat, the\tcat slept — naïve café\r\nend";
+    let whole = td5::analyze_text_fast(text);
+    for size in 1..text.len() {
+        let mut analyzer = StreamingAnalyzer::default();
+        let mut rest = text;
+        while !rest.is_empty() {
+            let mut cut = size.min(rest.len());
+            while !rest.is_char_boundary(cut) {
+                cut += 1;
+            }
+            analyzer.push(&rest[..cut]);
+            rest = &rest[cut..];
+        }
+        let stats = analyzer.finish().stats;
+        assert_eq!(stats.total_words, whole.total_words, "chunks of {size}");
+        assert_eq!(stats.top_words, whole.top_words, "chunks of {size}");
+        assert_eq!(stats.longest_words, whole.longest_words, "chunks of {size}");
+    }
+}
+
+/// Hands out one byte per read, cutting every character.
+struct ByteReader<'a>(&'a [u8]);
+
+impl std::io::Read for ByteReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match (self.0.split_first(), buf.first_mut()) {
+            (Some((&b, rest)), Some(out)) => {
+                *out = b;
+                self.0 = rest;
+                Ok(1)
+            }
+            _ => Ok(0),
+        }
+    }
+}
+
+#[test]
+fn word_limits_skip_tokens_without_letters() {
+    let text = "« un » — deux, trois 42 quatre « cinq » —";
+    let first = WordLimit::First(3)
+        .read(ByteReader(text.as_bytes()))
+        .unwrap();
+    assert_eq!(String::from_utf8(first).unwrap(), "« un » — deux, trois");
+    let last = WordLimit::Last(2).read(text.as_bytes()).unwrap();
+    assert_eq!(String::from_utf8(last).unwrap(), "quatre « cinq » —");
+    for (limit, words) in [(WordLimit::First(3), 3), (WordLimit::Last(2), 2)] {
+        let kept = limit.read(text.as_bytes()).unwrap();
+        let stats = td5::analyze(std::str::from_utf8(&kept).unwrap());
+        assert_eq!(stats.total_words, words, "{limit:?}");
+    }
+}
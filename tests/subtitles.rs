@@ -0,0 +1,53 @@
+//! Parsing SubRip and WebVTT cues.
+
+use td5::subtitles::Subtitles;
+
+fn srt(timing: &str) -> String {
+    format!("1\n{timing}\nHello there.\n")
+}
+
+#[test]
+fn cues_keep_their_timings_and_text() {
+    let source = "WEBVTT\n\n00:01.000 --> 00:02.500 align:start\n<v Bob>Hi</v>\n\n\
+                  1:00:00.000 --> 1:00:01.000\nLater\n";
+    let subtitles = Subtitles::parse(source).unwrap();
+    let cues: Vec<_> = subtitles
+        .cues
+        .iter()
+        .map(|c| (c.start_ms, c.end_ms, c.text.as_str()))
+        .collect();
+    assert_eq!(
+        cues,
+        [(1_000, 2_500, "Hi"), (3_600_000, 3_601_000, "Later")]
+    );
+}
+
+#[test]
+fn impossible_times_are_refused() {
+    for timing in [
+        "99:99:99,999 --> 99:99:99,999",
+        "00:60:00,000 --> 00:61:00,000",
+        "00:00:60,000 --> 00:00:61,000",
+        "00:00:1,000 --> 00:00:02,000",
+        "1:2:3:04,000 --> 1:2:3:05,000",
+        "00:00:01,00 --> 00:00:02,000",
+    ] {
+        let error = Subtitles::parse(&srt(timing)).unwrap_err();
+        assert!(
+            error.to_string().contains("invalid cue timing"),
+            "{timing}: {error}"
+        );
+    }
+}
+
+#[test]
+fn a_cue_ending_before_it_starts_is_refused() {
+    let error = Subtitles::parse(&srt("00:00:05,000 --> 00:00:04,000")).unwrap_err();
+    assert!(
+        error.to_string().contains("ends before it starts"),
+        "{error}"
+    );
+    // An empty cue is allowed.
+    let subtitles = Subtitles::parse(&srt("00:00:05,000 --> 00:00:05,000")).unwrap();
+    assert_eq!(subtitles.speech_rate().span_ms, 0);
+}
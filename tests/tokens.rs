@@ -0,0 +1,38 @@
+//! `tokenize` against the analyzers it is meant to agree with.
+
+use td5::{AnalyzerConfig, ControlChars};
+
+fn tokens(text: &str, config: &AnalyzerConfig) -> Vec<(String, std::ops::Range<usize>)> {
+    let mut tokens = Vec::new();
+    td5::tokenize(text, config, |word, span| {
+        tokens.push((word.to_string(), span))
+    });
+    tokens
+}
+
+#[test]
+fn control_characters_count_as_the_analyzers_count_them() {
+    let texts = [
+        "a\0b",
+        "\u{7}start and end\u{1b}",
+        "mid\u{85}word \u{9f}\u{9f} naïve\u{1}café",
+        "l'\u{0}été, d\u{7f}'accord",
+    ];
+    for policy in [ControlChars::Strip, ControlChars::Separate] {
+        let config = AnalyzerConfig::default().control_chars(policy);
+        for text in texts {
+            let tokens = tokens(text, &config);
+            let stats = td5::analyze_text_fast_with(text, &config);
+            assert_eq!(tokens.len(), stats.total_words, "{policy:?} {text:?}");
+            for (word, span) in &tokens {
+                let original: String = text[span.clone()]
+                    .chars()
+                    .filter(|ch| ch.is_alphanumeric())
+                    .collect();
+                assert_eq!(original.to_lowercase(), *word, "{policy:?} {text:?}");
+            }
+        }
+    }
+    let stripped = tokens("a\0b", &AnalyzerConfig::default());
+    assert_eq!(stripped, [("ab".to_string(), 0..3)]);
+}